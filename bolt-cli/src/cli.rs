@@ -13,6 +13,11 @@ use crate::{common::keystore::DEFAULT_KEYSTORE_PASSWORD, contracts::EigenLayerSt
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, styles = cli_styles(), about, arg_required_else_help(true))]
 pub struct Opts {
+    /// The profile of the configuration file to use for the default option values. Explicit
+    /// flags and environment variables take precedence over the profile values.
+    #[clap(long, env = "BOLT_PROFILE")]
+    pub profile: Option<String>,
+
     /// The subcommand to run.
     #[clap(subcommand)]
     pub command: Cmd,
@@ -34,6 +39,18 @@ pub enum Cmd {
 
     /// Handle operators in the bolt network.
     Operators(OperatorsCommand),
+
+    /// Inspect and verify the signature audit logs produced by the bolt sidecar.
+    Audit(AuditCommand),
+
+    /// Inspect the wallets and accounts of a remote DIRK keystore.
+    Dirk(DirkCommand),
+
+    /// Generate BLS and ECDSA keys for testing and devnets.
+    Generate(GenerateCommand),
+
+    /// Manage the configuration file and its profiles.
+    Config(ConfigCommand),
 }
 
 impl Cmd {
@@ -45,6 +62,10 @@ impl Cmd {
             Cmd::Send(cmd) => cmd.run().await,
             Cmd::Validators(cmd) => cmd.run().await,
             Cmd::Operators(cmd) => cmd.run().await,
+            Cmd::Audit(cmd) => cmd.run().await,
+            Cmd::Dirk(cmd) => cmd.run().await,
+            Cmd::Generate(cmd) => cmd.run(),
+            Cmd::Config(cmd) => cmd.run(),
         }
     }
 }
@@ -53,8 +74,9 @@ impl Cmd {
 #[derive(Debug, Clone, Parser)]
 pub struct DelegateCommand {
     /// The BLS public key to which the delegation message should be signed.
+    /// Required by every subcommand except `verify`.
     #[clap(long, env = "DELEGATEE_PUBKEY")]
-    pub delegatee_pubkey: String,
+    pub delegatee_pubkey: Option<String>,
 
     /// The output file for the delegations.
     #[clap(long, env = "OUTPUT_FILE_PATH", default_value = "delegations.json")]
@@ -69,6 +91,16 @@ pub struct DelegateCommand {
     #[clap(long, env = "ACTION", default_value = "delegate")]
     pub action: Action,
 
+    /// Revoke the delegations of an existing delegations file, instead of delegating to
+    /// `--delegatee-pubkey`. The keys of the source are matched by validator pubkey.
+    /// Requires `--action revoke`.
+    #[clap(long, env = "FROM_DELEGATIONS")]
+    pub from_delegations: Option<PathBuf>,
+
+    /// Skip the delegating validators whose key isn't found in the source, instead of failing.
+    #[clap(long, requires = "from_delegations")]
+    pub skip_missing: bool,
+
     /// The source of the private key.
     #[clap(subcommand)]
     pub source: SecretsSource,
@@ -81,9 +113,64 @@ pub struct PubkeysCommand {
     #[clap(long, env = "OUTPUT_FILE_PATH", default_value = "pubkeys.json")]
     pub out: String,
 
-    /// The source of the private keys from which to extract the pubkeys.
+    /// The format of the output file.
+    #[clap(long, env = "PUBKEYS_FORMAT", default_value = "json")]
+    pub format: PubkeysFormat,
+
+    /// Remove the duplicated pubkeys found across the sources.
+    #[clap(long)]
+    pub dedup: bool,
+
+    /// Additional public keys in hex format, combined with the other sources.
+    /// Multiple public keys must be seperated by commas.
+    #[clap(long, value_delimiter = ',')]
+    pub public_keys: Vec<String>,
+
+    /// Additional secret keys in hex format, combined with the other sources.
+    /// Multiple secret keys must be seperated by commas.
+    #[clap(long, value_delimiter = ',')]
+    pub secret_keys: Vec<String>,
+
+    /// Additional EIP-2335 keystore directory, combined with the other sources.
+    #[clap(long)]
+    pub keystore_path: Option<String>,
+
+    /// Check the registration of the pubkeys in the bolt validators registry.
+    #[clap(long, requires = "rpc_url")]
+    pub verify: bool,
+
+    /// The URL of the RPC to read the bolt contracts from, used with `--verify`.
+    #[clap(long, env = "RPC_URL")]
+    pub rpc_url: Option<Url>,
+
+    /// The chain of the bolt deployments to query. Defaults to the chain of the RPC.
+    #[clap(long, env = "CHAIN")]
+    pub chain: Option<Chain>,
+
+    /// Print the registration report as JSON.
+    #[clap(long, requires = "verify")]
+    pub json: bool,
+
+    /// Don't fail if some pubkeys are not registered in bolt.
+    #[clap(long, requires = "verify")]
+    pub allow_missing: bool,
+
+    /// The source of the private keys from which to extract the pubkeys. Can be omitted if
+    /// the keys are given with the options above.
     #[clap(subcommand)]
-    pub source: KeysSource,
+    pub source: Option<KeysSource>,
+}
+
+/// The output format of the pubkeys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+pub enum PubkeysFormat {
+    /// A JSON array of hex-encoded pubkeys.
+    Json,
+    /// A CSV file with a `pubkey` column.
+    Csv,
+    /// One hex-encoded pubkey per line.
+    Txt,
 }
 
 /// Command for sending a preconfirmation request to a bolt proposer.
@@ -104,14 +191,73 @@ pub struct SendCommand {
     #[clap(long, env = "OVERRIDE_BOLT_SIDECAR_URL")]
     pub override_bolt_sidecar_url: Option<Url>,
 
-    /// How many transactions to send.
-    #[clap(long, env = "TRANSACTION_COUNT", default_value = "1")]
-    pub count: u32,
+    /// The beacon node URL, used to time the target slot and to discover it with `--auto-slot`.
+    #[clap(long, env = "BEACON_URL")]
+    pub beacon_url: Option<Url>,
+
+    /// If set, the target slot is the nearest future slot in the proposer duties of the beacon
+    /// node whose proposer is active in the bolt lookahead.
+    #[clap(long, env = "AUTO_SLOT", requires = "beacon_url", default_value = "false")]
+    pub auto_slot: bool,
+
+    /// The slot to target, overriding the slot discovery.
+    #[clap(long, env = "TARGET_SLOT", conflicts_with = "auto_slot")]
+    pub target_slot: Option<u64>,
+
+    /// If set, wait for the blocks of the target slots and report whether the committed
+    /// transactions were included. Requires `--beacon-url`, or `--devnet.beacon_url` on devnets.
+    #[clap(long, env = "AWAIT_INCLUSION", default_value = "false")]
+    pub await_inclusion: bool,
+
+    /// If set, request the transactions at the very top of the block of the target slot, in
+    /// the order of the request. With `--await-inclusion`, their position is checked as well.
+    #[clap(long, env = "TOP_OF_BLOCK", default_value = "false")]
+    pub top: bool,
+
+    /// If set, the request expires this many milliseconds after being sent: the sidecar
+    /// rejects it instead of committing to it past that time.
+    #[clap(long, env = "EXPIRES_IN_MS")]
+    pub expires_in_ms: Option<u64>,
+
+    /// The RPC URL of a sidecar to send the request to, instead of the bolt RPC. Can be
+    /// repeated: the request is sent to the sidecar with the earliest upcoming slot in the
+    /// lookahead, or to all of them with `--broadcast`.
+    #[clap(long, env = "SIDECAR_RPC_URLS", value_delimiter = ',')]
+    pub sidecar_rpc_url: Vec<Url>,
+
+    /// If set, send the request to all the sidecars given with `--sidecar-rpc-url`, each
+    /// for its own upcoming slot.
+    #[clap(long, env = "BROADCAST", requires = "sidecar_rpc_url", default_value = "false")]
+    pub broadcast: bool,
+
+    /// If set, print the status of the target sidecars before sending the request: their
+    /// chain, commitment signer, validators and their delegatees, and limits.
+    #[clap(long, env = "PRINT_STATUS", default_value = "false")]
+    pub print_status: bool,
+
+    /// How many self-transfers to generate, with sequential nonces. All the transactions are
+    /// sent in a single inclusion request. Defaults to 1 without `--raw-tx`, 0 otherwise.
+    #[clap(long, env = "TRANSACTION_COUNT")]
+    pub count: Option<u32>,
+
+    /// A signed raw transaction to include in the request, as a hex string. Can be repeated.
+    #[clap(long = "raw-tx", env = "RAW_TXS", value_delimiter = ',')]
+    pub raw_tx: Vec<String>,
+
+    /// The execution client URL, used to fetch the nonce of the wallet and fill the generated
+    /// transactions. Defaults to the bolt RPC URL.
+    #[clap(long, env = "EXECUTION_URL")]
+    pub execution_url: Option<Url>,
 
     /// If set, the transaction will be blob-carrying (type 3)
     #[clap(long, env = "BLOB", default_value = "false")]
     pub blob: bool,
 
+    /// The data to carry in the blobs of the transaction, either as a path to a file
+    /// or as a hex string. Implies `--blob`.
+    #[clap(long, env = "BLOB_DATA")]
+    pub blob_data: Option<String>,
+
     /// The max fee per gas in gwei.
     #[clap(long, env = "MAX_FEE")]
     pub max_fee: Option<u128>,
@@ -136,6 +282,11 @@ pub struct SendCommand {
     /// The URL of the devnet sidecar for sending transactions
     #[clap(long = "devnet.sidecar_url", hide = true)]
     pub devnet_sidecar_url: Option<Url>,
+
+    /// If set, send a suite of deliberately invalid requests to the devnet sidecar, and
+    /// fail if any of them isn't rejected with the expected error.
+    #[clap(long = "negative-suite", hide = true, requires = "devnet", default_value = "false")]
+    pub negative_suite: bool,
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -161,12 +312,47 @@ pub enum ValidatorsSubcommand {
         authorized_operator: Address,
 
         /// The path to the JSON pubkeys file, containing an array of BLS public keys.
-        #[clap(long, env = "PUBKEYS_PATH", default_value = "pubkeys.json")]
+        #[clap(
+            long,
+            visible_alias = "pubkeys-file",
+            env = "PUBKEYS_PATH",
+            default_value = "pubkeys.json"
+        )]
         pubkeys_path: PathBuf,
 
-        /// The private key to sign the transactions with.
-        #[clap(long, env = "ADMIN_PRIVATE_KEY")]
-        admin_private_key: B256,
+        /// The private key to sign the transactions with. If not provided, the calldata of
+        /// the registration transactions is printed instead of being sent.
+        #[clap(long, visible_alias = "private-key", env = "ADMIN_PRIVATE_KEY")]
+        admin_private_key: Option<B256>,
+
+        /// The maximum number of validators to register in a single transaction.
+        /// Defaults to as many as fit in half of the block gas limit.
+        #[clap(long, env = "BATCH_SIZE")]
+        batch_size: Option<usize>,
+    },
+
+    /// Check the registration status of a batch of validators.
+    Status {
+        /// The URL of the RPC to read the bolt contracts from.
+        #[clap(long, env = "RPC_URL")]
+        rpc_url: Url,
+
+        /// The chain of the bolt deployments to query. Defaults to the chain of the RPC.
+        #[clap(long, env = "CHAIN")]
+        chain: Option<Chain>,
+
+        /// The path to the JSON pubkeys file, containing an array of BLS public keys.
+        #[clap(
+            long,
+            visible_alias = "pubkeys-file",
+            env = "PUBKEYS_PATH",
+            default_value = "pubkeys.json"
+        )]
+        pubkeys_path: PathBuf,
+
+        /// Print the statuses as JSON instead of a table.
+        #[clap(long)]
+        json: bool,
     },
 }
 
@@ -264,6 +450,102 @@ pub enum SymbioticSubcommand {
     },
 }
 
+#[derive(Debug, Clone, Parser)]
+pub struct AuditCommand {
+    #[clap(subcommand)]
+    pub subcommand: AuditSubcommand,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub enum AuditSubcommand {
+    /// Re-verify every signature recorded in a sidecar audit log file.
+    Verify {
+        /// The path to the NDJSON audit log file.
+        path: PathBuf,
+
+        /// The chain on which the signatures were produced.
+        #[clap(long, env = "CHAIN", default_value = "mainnet")]
+        chain: Chain,
+    },
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct DirkCommand {
+    #[clap(subcommand)]
+    pub subcommand: DirkSubcommand,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub enum DirkSubcommand {
+    /// List the accounts of the given wallets, including distributed accounts.
+    List {
+        /// The URL of the DIRK keystore.
+        #[clap(long, env = "DIRK_URL")]
+        url: String,
+
+        /// The paths of the wallets to list, e.g. `wallet1` or `wallet1/validator.*`.
+        /// Multiple paths must be separated by commas.
+        #[clap(long, env = "DIRK_WALLET_PATH", value_delimiter = ',', required = true)]
+        wallet_path: Vec<String>,
+
+        /// The TLS credentials for connecting to the DIRK keystore.
+        #[clap(flatten)]
+        tls_credentials: TlsCredentials,
+
+        /// Print the listing as JSON instead of a table.
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+/// Command for generating test keys.
+#[derive(Debug, Clone, Parser)]
+pub struct GenerateCommand {
+    /// The number of BLS keypairs to generate.
+    #[clap(long, default_value_t = 0)]
+    pub bls_keys: usize,
+
+    /// The number of ECDSA private keys to generate.
+    #[clap(long, default_value_t = 0)]
+    pub ecdsa_keys: usize,
+
+    /// The directory to write the keys to. It is created if it doesn't exist.
+    #[clap(long, env = "OUTPUT_DIR", default_value = "keys")]
+    pub out: PathBuf,
+
+    /// If provided, the BLS keys are also written as EIP-2335 keystores encrypted with this
+    /// password, along with their password files.
+    #[clap(long, env = "KEYSTORE_PASSWORD", hide_env_values = true)]
+    pub keystore_password: Option<String>,
+
+    /// Print the generated secret keys to stdout. Don't use this for keys holding real funds.
+    #[clap(long)]
+    pub insecure_print: bool,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ConfigCommand {
+    #[clap(subcommand)]
+    pub subcommand: ConfigSubcommand,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub enum ConfigSubcommand {
+    /// Create a configuration file with example profiles.
+    Init {
+        /// Overwrite the configuration file if it already exists.
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Print the effective values of a profile, with secrets redacted.
+    Show {
+        /// The profile to print.
+        #[clap(long, env = "BOLT_PROFILE")]
+        profile: String,
+    },
+}
+
 /// The action to perform.
 #[derive(Debug, Clone, ValueEnum)]
 #[clap(rename_all = "kebab_case")]
@@ -320,6 +602,14 @@ pub enum SecretsSource {
         /// The options for reading the keystore directory.
         #[clap(flatten)]
         opts: LocalKeystoreOpts,
+
+        /// The number of keystores to decrypt and sign with in parallel.
+        #[clap(long, env = "CONCURRENCY", default_value_t = default_concurrency())]
+        concurrency: usize,
+
+        /// Abort on the first keystore that fails, instead of reporting the failures at the end.
+        #[clap(long, env = "STRICT")]
+        strict: bool,
     },
 
     /// Use a remote DIRK keystore to generate the signed messages.
@@ -328,6 +618,52 @@ pub enum SecretsSource {
         #[clap(flatten)]
         opts: DirkOpts,
     },
+
+    /// Fetch the validator pubkeys from a beacon node, and generate unsigned messages to be
+    /// signed later by the key holders, or signed ones for the matching secret keys.
+    BeaconNode {
+        /// The options for fetching the validators from the beacon node.
+        #[clap(flatten)]
+        opts: BeaconNodeOpts,
+    },
+
+    /// Verify the signed messages of a delegations file instead of generating new ones.
+    Verify {
+        /// The path to the delegations file, as produced by `bolt delegate`.
+        #[clap(long)]
+        file: PathBuf,
+
+        /// The chain for which the messages were signed.
+        #[clap(long, env = "CHAIN", default_value = "mainnet")]
+        chain: Chain,
+
+        /// The BLS public key that every message is expected to delegate to, if any.
+        #[clap(long, env = "DELEGATEE_PUBKEY")]
+        delegatee_pubkey: Option<String>,
+    },
+}
+
+/// Options for fetching validators from a beacon node.
+#[derive(Debug, Clone, Parser)]
+pub struct BeaconNodeOpts {
+    /// The URL of the beacon node.
+    #[clap(long, env = "BEACON_URL")]
+    pub beacon_url: Url,
+
+    /// The indexes of the validators, either single indexes or inclusive ranges like
+    /// `100..=199`, separated by commas. If not provided, all the active validators are
+    /// fetched, which requires a withdrawal credentials filter.
+    #[clap(long, env = "VALIDATOR_INDEXES", value_delimiter = ',')]
+    pub validator_indexes: Vec<String>,
+
+    /// Only keep the validators with these withdrawal credentials.
+    #[clap(long, env = "WITHDRAWAL_CREDENTIALS", required_unless_present = "validator_indexes")]
+    pub withdrawal_credentials: Option<B256>,
+
+    /// The secret keys to sign the messages of the matching validators with, in hex format.
+    /// Multiple secret keys must be separated by commas.
+    #[clap(long, env = "SECRET_KEYS", value_delimiter = ',', hide_env_values = true)]
+    pub secret_keys: Vec<String>,
 }
 
 /// Options for reading a keystore folder.
@@ -392,6 +728,11 @@ pub struct TlsCredentials {
     pub ca_cert_path: Option<String>,
 }
 
+/// The default number of keystores processed in parallel: the available parallelism.
+fn default_concurrency() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
 /// Supported chains for the CLI
 #[derive(Debug, Clone, Copy, ValueEnum, Hash, PartialEq, Eq)]
 #[clap(rename_all = "kebab_case")]
@@ -0,0 +1,186 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use alloy::primitives::{FixedBytes, B256};
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use eyre::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    cli::{AuditCommand, AuditSubcommand, Chain},
+    common::signing::verify_commit_boost_root,
+};
+
+impl AuditCommand {
+    /// Run the `audit` command.
+    pub async fn run(self) -> Result<()> {
+        match self.subcommand {
+            AuditSubcommand::Verify { path, chain } => {
+                let report = verify_audit_log(&path, chain)?;
+
+                for failure in &report.failures {
+                    warn!(line = failure.line, "{}", failure.reason);
+                }
+
+                info!(
+                    total = report.total,
+                    valid = report.valid,
+                    invalid = report.failures.len(),
+                    "Audit log verification completed"
+                );
+
+                if !report.failures.is_empty() {
+                    bail!("{} invalid entries found in {}", report.failures.len(), path.display());
+                }
+
+                println!("All {} signatures in {} are valid", report.total, path.display());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A single entry of the sidecar signature audit log.
+///
+/// Must be kept in sync with the `AuditEntry` type in the bolt sidecar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub slot: u64,
+    pub pubkey: BlsPublicKey,
+    pub digest: B256,
+    pub signature: FixedBytes<96>,
+    pub request_id: B256,
+    pub signer: String,
+}
+
+/// The result of verifying an audit log file.
+#[derive(Debug, Default)]
+pub struct AuditReport {
+    /// The total number of entries found.
+    pub total: usize,
+    /// The number of entries with a valid signature.
+    pub valid: usize,
+    /// The entries that couldn't be parsed or verified.
+    pub failures: Vec<AuditFailure>,
+}
+
+/// An audit log entry that failed verification.
+#[derive(Debug)]
+pub struct AuditFailure {
+    /// The 1-indexed line number of the entry in the file.
+    pub line: usize,
+    /// The reason for the failure.
+    pub reason: String,
+}
+
+/// Re-verify every signature of the audit log at the given path against
+/// the recorded digest and public key, using the Commit-Boost domain of `chain`.
+pub fn verify_audit_log(path: &Path, chain: Chain) -> Result<AuditReport> {
+    let file = File::open(path).wrap_err(format!("Failed to open {}", path.display()))?;
+    let mut report = AuditReport::default();
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        report.total += 1;
+        let fail = |reason: String| AuditFailure { line: i + 1, reason };
+
+        let entry = match serde_json::from_str::<AuditEntry>(&line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                report.failures.push(fail(format!("malformed entry: {err}")));
+                continue;
+            }
+        };
+
+        let signature = match blst::min_pk::Signature::from_bytes(entry.signature.as_ref()) {
+            Ok(signature) => signature,
+            Err(err) => {
+                report.failures.push(fail(format!("malformed signature: {err:?}")));
+                continue;
+            }
+        };
+
+        match verify_commit_boost_root(entry.pubkey.clone(), entry.digest.0, &signature, &chain) {
+            Ok(()) => report.valid += 1,
+            Err(err) => report.failures.push(fail(format!(
+                "invalid signature for slot {} by {}: {err}",
+                entry.slot, entry.pubkey
+            ))),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use alloy::primitives::{FixedBytes, B256};
+    use blst::min_pk::SecretKey;
+    use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+
+    use crate::{
+        cli::Chain,
+        common::signing::{compute_commit_boost_signing_root, BLS_DST_PREFIX},
+    };
+
+    use super::{verify_audit_log, AuditEntry};
+
+    fn signed_entry(sk: &SecretKey, slot: u64, digest: B256, chain: Chain) -> AuditEntry {
+        let signing_root = compute_commit_boost_signing_root(digest.0, &chain).unwrap();
+        let signature = sk.sign(signing_root.as_ref(), BLS_DST_PREFIX, &[]);
+
+        AuditEntry {
+            timestamp: 1_700_000_000_000,
+            slot,
+            pubkey: BlsPublicKey::try_from(sk.sk_to_pk().to_bytes().as_ref()).unwrap(),
+            digest,
+            signature: FixedBytes::from(signature.to_bytes()),
+            request_id: B256::repeat_byte(0xaa),
+            signer: "local".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_verify_audit_log() -> eyre::Result<()> {
+        let chain = Chain::Holesky;
+        let sk = SecretKey::key_gen(&[42; 32], &[]).unwrap();
+
+        let mut fixture = tempfile::NamedTempFile::new()?;
+        for slot in 0..3 {
+            let entry = signed_entry(&sk, slot, B256::repeat_byte(slot as u8), chain);
+            writeln!(fixture, "{}", serde_json::to_string(&entry)?)?;
+        }
+
+        // An entry whose digest has been tampered with after signing
+        let mut tampered = signed_entry(&sk, 3, B256::repeat_byte(3), chain);
+        tampered.digest = B256::repeat_byte(4);
+        writeln!(fixture, "{}", serde_json::to_string(&tampered)?)?;
+
+        // A line that isn't a valid entry at all
+        writeln!(fixture, "{{\"slot\": 5}}")?;
+        fixture.flush()?;
+
+        let report = verify_audit_log(fixture.path(), chain)?;
+        assert_eq!(report.total, 5);
+        assert_eq!(report.valid, 3);
+        assert_eq!(report.failures.iter().map(|f| f.line).collect::<Vec<_>>(), vec![4, 5]);
+
+        // The same signatures are not valid on a different chain
+        let report = verify_audit_log(fixture.path(), Chain::Mainnet)?;
+        assert_eq!(report.valid, 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs;
+
+use eyre::{bail, Result};
+
+use crate::{
+    cli::{ConfigCommand, ConfigSubcommand},
+    common::config::{config_path, effective_values, Config, ValueSource},
+};
+
+/// The configuration file created by `bolt config init`.
+const CONFIG_TEMPLATE: &str = r#"# bolt CLI configuration.
+#
+# Select a profile with `bolt --profile <name> <command>` or with the BOLT_PROFILE environment
+# variable. Any option of the CLI can be set in a profile using its long flag name, e.g.
+# `rpc-url` for `--rpc-url`. Explicit flags and environment variables take precedence over
+# the profile values.
+
+[profiles.holesky]
+chain = "holesky"
+bolt-rpc-url = "https://rpc-holesky.bolt.chainbound.io"
+# rpc-url = "http://localhost:8545"
+# beacon-url = "http://localhost:5052"
+# path = "/path/to/keystores/validators"
+# password-path = "/path/to/keystores/secrets"
+# client-cert-path = "/path/to/tls/client.crt"
+# client-key-path = "/path/to/tls/client.key"
+# ca-cert-path = "/path/to/tls/ca.crt"
+
+[profiles.mainnet]
+chain = "mainnet"
+# rpc-url = "http://localhost:8545"
+# beacon-url = "http://localhost:5052"
+"#;
+
+impl ConfigCommand {
+    /// Run the `config` command.
+    pub fn run(self) -> Result<()> {
+        let path = config_path()?;
+
+        match self.subcommand {
+            ConfigSubcommand::Init { force } => {
+                if path.exists() && !force {
+                    bail!(
+                        "Config file {} already exists, use --force to overwrite",
+                        path.display()
+                    );
+                }
+
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(&path, CONFIG_TEMPLATE)?;
+                println!("Config file created at {}", path.display());
+            }
+            ConfigSubcommand::Show { profile } => {
+                let config = Config::load(&path)?;
+                let values = effective_values(config.profile(&profile)?)?;
+
+                println!("Profile {} from {}", profile, path.display());
+                println!("{:<28}  {:<64}  SOURCE", "OPTION", "VALUE");
+                for value in values {
+                    let source = match value.source {
+                        ValueSource::Profile => "profile".to_string(),
+                        ValueSource::Env(env) => format!("env ({env})"),
+                    };
+                    println!("{:<28}  {:<64}  {}", value.key, value.value, source);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::{cli::Opts, common::config::configurable_options};
+
+    #[test]
+    fn test_config_template() {
+        let config: Config = toml::from_str(CONFIG_TEMPLATE).expect("valid template");
+        let options = configurable_options(&Opts::command());
+
+        for profile in config.profiles.values() {
+            assert!(profile.keys().all(|key| options.contains_key(key)), "unknown option");
+        }
+
+        // The commented out options must exist as well
+        for line in CONFIG_TEMPLATE.lines().filter_map(|line| line.strip_prefix("# ")) {
+            if let Some((key, _)) = line.split_once(" = ") {
+                assert!(options.contains_key(key), "unknown option {key}");
+            }
+        }
+    }
+}
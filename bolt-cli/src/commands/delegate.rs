@@ -1,3 +1,15 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    slice,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
 use alloy::{
     primitives::B256,
     signers::k256::sha2::{Digest, Sha256},
@@ -5,15 +17,16 @@ use alloy::{
 use ethereum_consensus::crypto::{
     PublicKey as BlsPublicKey, SecretKey as BlsSecretKey, Signature as BlsSignature,
 };
-use eyre::{bail, Result};
+use eyre::{bail, ContextCompat, Result};
 use lighthouse_eth2_keystore::Keystore;
 use serde::Serialize;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 use crate::{
     cli::{Action, Chain, DelegateCommand, SecretsSource},
     common::{
-        dirk::Dirk,
+        beacon::{fetch_validators, parse_validator_indexes},
+        dirk::{Dirk, WalletAccounts},
         keystore::{keystore_paths, KeystoreError, KeystoreSecret},
         parse_bls_public_key,
         signing::{
@@ -23,19 +36,42 @@ use crate::{
     },
 };
 
+/// Verification of existing delegations files.
+mod verify;
+
 impl DelegateCommand {
     /// Run the `delegate` command.
     pub async fn run(self) -> Result<()> {
+        if let Some(path) = &self.from_delegations {
+            if !matches!(self.action, Action::Revoke) {
+                bail!("--from-delegations requires --action revoke");
+            }
+
+            let revocations =
+                revoke_from_delegations(&self.source, path, self.chain, self.skip_missing).await?;
+
+            // Verify signatures
+            for message in &revocations {
+                verify_message_signature(message, self.chain)?;
+            }
+
+            write_to_file(&self.out, &revocations)?;
+            println!(
+                "{} revocations of {} generated and saved to {}",
+                revocations.len(),
+                path.display(),
+                self.out
+            );
+            return Ok(());
+        }
+
         match self.source {
-            SecretsSource::SecretKeys { secret_keys } => {
-                let delegatee_pubkey = parse_bls_public_key(&self.delegatee_pubkey)?;
-                let signed_messages = generate_from_local_keys(
-                    &secret_keys,
-                    delegatee_pubkey,
-                    self.chain,
-                    self.action,
-                )?;
-                debug!("Signed {} messages with local keys", signed_messages.len());
+            source @ (SecretsSource::SecretKeys { .. } |
+            SecretsSource::LocalKeystore { .. } |
+            SecretsSource::Dirk { .. }) => {
+                let delegatee_pubkey = required_delegatee(self.delegatee_pubkey.as_deref())?;
+                let signed_messages =
+                    sign_with_source(&source, delegatee_pubkey, self.chain, self.action).await?;
 
                 // Verify signatures
                 for message in &signed_messages {
@@ -45,48 +81,53 @@ impl DelegateCommand {
                 write_to_file(&self.out, &signed_messages)?;
                 println!("Signed delegation messages generated and saved to {}", self.out);
             }
-            SecretsSource::LocalKeystore { opts } => {
-                let keystore_secret = KeystoreSecret::from_keystore_options(&opts)?;
-                let delegatee_pubkey = parse_bls_public_key(&self.delegatee_pubkey)?;
-                let signed_messages = generate_from_keystore(
-                    &opts.path,
-                    keystore_secret,
+            SecretsSource::BeaconNode { opts } => {
+                let indexes = parse_validator_indexes(&opts.validator_indexes)?;
+                let mut validators = fetch_validators(&opts.beacon_url, &indexes).await?;
+                if let Some(credentials) = opts.withdrawal_credentials {
+                    let credentials = credentials.to_string();
+                    validators.retain(|v| v.withdrawal_credentials == credentials);
+                }
+                debug!("Fetched {} validators from the beacon node", validators.len());
+
+                let delegatee_pubkey = required_delegatee(self.delegatee_pubkey.as_deref())?;
+                let pubkeys = validators.into_iter().map(|v| v.pubkey).collect::<Vec<_>>();
+                let entries = generate_for_pubkeys(
+                    &pubkeys,
+                    &opts.secret_keys,
                     delegatee_pubkey,
                     self.chain,
                     self.action,
                 )?;
-                debug!("Signed {} messages with keystore", signed_messages.len());
 
                 // Verify signatures
-                for message in &signed_messages {
-                    verify_message_signature(message, self.chain)?;
+                for entry in &entries {
+                    if let DelegationEntry::Signed(message) = entry {
+                        verify_message_signature(message, self.chain)?;
+                    }
                 }
 
-                write_to_file(&self.out, &signed_messages)?;
-                println!("Signed delegation messages generated and saved to {}", self.out);
+                let unsigned = entries.iter().filter(|e| e.is_unsigned()).count();
+                write_to_file(&self.out, &entries)?;
+                println!(
+                    "{} messages generated ({} unsigned) and saved to {}",
+                    entries.len(),
+                    unsigned,
+                    self.out
+                );
             }
-            SecretsSource::Dirk { opts } => {
-                let mut dirk = Dirk::connect(opts.url, opts.tls_credentials).await?;
+            SecretsSource::Verify { file, chain, delegatee_pubkey } => {
+                let delegatee_pubkey =
+                    delegatee_pubkey.as_deref().map(parse_bls_public_key).transpose()?;
+                let checks = verify::verify_delegations_file(&file, chain, delegatee_pubkey)?;
+                verify::print_table(&checks);
 
-                let delegatee_pubkey = parse_bls_public_key(&self.delegatee_pubkey)?;
-                let signed_messages = generate_from_dirk(
-                    &mut dirk,
-                    delegatee_pubkey,
-                    opts.wallet_path,
-                    opts.passphrases,
-                    self.chain,
-                    self.action,
-                )
-                .await?;
-                debug!("Signed {} messages with Dirk", signed_messages.len());
-
-                // Verify signatures
-                for message in &signed_messages {
-                    verify_message_signature(message, self.chain)?;
+                let failed = checks.iter().filter(|check| !check.passed()).count();
+                if failed > 0 {
+                    bail!("{failed} of {} entries failed verification", checks.len());
                 }
 
-                write_to_file(&self.out, &signed_messages)?;
-                println!("Signed delegation messages generated and saved to {}", self.out);
+                println!("All {} entries in {} are valid", checks.len(), file.display());
             }
         }
 
@@ -94,6 +135,171 @@ impl DelegateCommand {
     }
 }
 
+/// Sign delegations/revocations to the given delegatee with every key of the given source.
+///
+/// Only the sources holding secret keys are supported: local keys, keystores and Dirk.
+pub async fn sign_with_source(
+    source: &SecretsSource,
+    delegatee_pubkey: BlsPublicKey,
+    chain: Chain,
+    action: Action,
+) -> Result<Vec<SignedMessage>> {
+    match source {
+        SecretsSource::SecretKeys { secret_keys } => {
+            let signed_messages =
+                generate_from_local_keys(secret_keys, delegatee_pubkey, chain, action)?;
+            debug!("Signed {} messages with local keys", signed_messages.len());
+            Ok(signed_messages)
+        }
+        SecretsSource::LocalKeystore { opts, concurrency, strict } => {
+            let keystore_secret = KeystoreSecret::from_keystore_options(opts)?;
+            let KeystoreSigningOutput { messages, errors } = generate_from_keystore(
+                &opts.path,
+                keystore_secret,
+                delegatee_pubkey,
+                chain,
+                action,
+                *concurrency,
+                *strict,
+            )?;
+            debug!("Signed {} messages with keystore", messages.len());
+
+            if !errors.is_empty() {
+                for (path, err) in &errors {
+                    warn!("Failed to sign with keystore {}: {:?}", path.display(), err);
+                }
+                println!("{} keystores failed and were skipped", errors.len());
+            }
+
+            Ok(messages)
+        }
+        SecretsSource::Dirk { opts } => {
+            let mut dirk = Dirk::connect(opts.url.clone(), opts.tls_credentials.clone()).await?;
+            let signed_messages = generate_from_dirk(
+                &mut dirk,
+                delegatee_pubkey,
+                opts.wallet_path.clone(),
+                opts.passphrases.clone(),
+                chain,
+                action,
+            )
+            .await?;
+            debug!("Signed {} messages with Dirk", signed_messages.len());
+            Ok(signed_messages)
+        }
+        SecretsSource::BeaconNode { .. } | SecretsSource::Verify { .. } => {
+            bail!("Signing requires the secret-keys, local-keystore or dirk source")
+        }
+    }
+}
+
+/// Sign the revocations of the delegations of the given delegations file, with the keys of the
+/// given source matched by validator pubkey. The revocations follow the order of the file.
+///
+/// Fails if the key of a delegating validator isn't found in the source, unless `skip_missing`
+/// is set, in which case its delegation is left unrevoked.
+pub async fn revoke_from_delegations(
+    source: &SecretsSource,
+    path: &Path,
+    chain: Chain,
+    skip_missing: bool,
+) -> Result<Vec<SignedMessage>> {
+    let pairs = verify::read_delegation_pairs(path)?;
+    if pairs.is_empty() {
+        bail!("No signed delegations found in {}", path.display());
+    }
+
+    let mut delegatees = Vec::<&BlsPublicKey>::new();
+    for (_, delegatee) in &pairs {
+        if !delegatees.contains(&delegatee) {
+            delegatees.push(delegatee);
+        }
+    }
+
+    // Sign with the whole source once per delegatee, keeping the delegated validators only
+    let mut revocations = HashMap::new();
+    for delegatee in delegatees {
+        let messages = sign_with_source(source, delegatee.clone(), chain, Action::Revoke).await?;
+        for message in messages {
+            let key = (message.validator_pubkey().to_vec(), delegatee.to_vec());
+            revocations.insert(key, message);
+        }
+    }
+
+    let mut signed_messages = Vec::with_capacity(pairs.len());
+    let mut missing = Vec::new();
+    for (validator, delegatee) in &pairs {
+        match revocations.remove(&(validator.to_vec(), delegatee.to_vec())) {
+            Some(message) => signed_messages.push(message),
+            None => missing.push(validator),
+        }
+    }
+
+    if !missing.is_empty() {
+        for validator in &missing {
+            warn!("No key found for validator {:?} in the key source", validator);
+        }
+        if !skip_missing {
+            bail!(
+                "{} of {} delegating validators have no key in the key source, use --skip-missing to ignore them",
+                missing.len(),
+                pairs.len()
+            );
+        }
+    }
+
+    Ok(signed_messages)
+}
+
+/// Parse the delegatee public key, required to generate messages.
+fn required_delegatee(delegatee_pubkey: Option<&str>) -> Result<BlsPublicKey> {
+    parse_bls_public_key(delegatee_pubkey.wrap_err("--delegatee-pubkey is required")?)
+}
+
+/// Generate delegations/revocations for the given validator pubkeys
+///
+/// - Sign the messages of the validators whose secret key is provided
+/// - Leave the other messages unsigned, to be signed later by the key holders
+pub fn generate_for_pubkeys(
+    pubkeys: &[BlsPublicKey],
+    secret_keys: &[String],
+    delegatee_pubkey: BlsPublicKey,
+    chain: Chain,
+    action: Action,
+) -> Result<Vec<DelegationEntry>> {
+    let mut entries = Vec::with_capacity(pubkeys.len());
+    for pubkey in pubkeys {
+        let secret_key = secret_keys.iter().find(|sk| {
+            BlsSecretKey::try_from(sk.trim().to_string()).is_ok_and(|sk| sk.public_key() == *pubkey)
+        });
+
+        if let Some(sk) = secret_key {
+            let signed = generate_from_local_keys(
+                slice::from_ref(sk),
+                delegatee_pubkey.clone(),
+                chain,
+                action,
+            )?;
+            entries.extend(signed.into_iter().map(DelegationEntry::Signed));
+            continue;
+        }
+
+        let message = match action {
+            Action::Delegate => UnsignedMessage::Delegation(DelegationMessage::new(
+                pubkey.clone(),
+                delegatee_pubkey.clone(),
+            )),
+            Action::Revoke => UnsignedMessage::Revocation(RevocationMessage::new(
+                pubkey.clone(),
+                delegatee_pubkey.clone(),
+            )),
+        };
+        entries.push(DelegationEntry::Unsigned { message, unsigned: true });
+    }
+
+    Ok(entries)
+}
+
 /// Generate signed delegations/revocations using local BLS private keys
 ///
 /// - Use the provided private keys from either CLI or env variable
@@ -132,52 +338,151 @@ pub fn generate_from_local_keys(
     Ok(signed_messages)
 }
 
-/// Generate signed delegations/revocations using a keystore file
+/// Signed messages generated from a keystore directory, with the keystores that failed.
+#[derive(Debug)]
+pub struct KeystoreSigningOutput {
+    /// The signed messages, sorted by validator pubkey.
+    pub messages: Vec<SignedMessage>,
+    /// The keystores that couldn't be decrypted or signed with.
+    pub errors: Vec<(PathBuf, eyre::Report)>,
+}
+
+/// Generate signed delegations/revocations using a keystore directory
 ///
-/// - Read the keystore file
-/// - Decrypt the keypairs using the password
-/// - Create messages
-/// - Compute the signing roots and sign the message
-/// - Return the signed message
+/// - Read the keystore files
+/// - Decrypt the keypairs using the password and sign the messages, with up to `concurrency`
+///   keystores processed in parallel
+/// - Return the signed messages sorted by validator pubkey, so that the output is stable regardless
+///   of the completion order
+///
+/// Keystores that fail are collected in the output, unless `strict` is set, in which case the
+/// first failure aborts the generation.
 pub fn generate_from_keystore(
     keys_path: &str,
     keystore_secret: KeystoreSecret,
     delegatee_pubkey: BlsPublicKey,
     chain: Chain,
     action: Action,
-) -> Result<Vec<SignedMessage>> {
+    concurrency: usize,
+    strict: bool,
+) -> Result<KeystoreSigningOutput> {
     let keystores_paths = keystore_paths(keys_path)?;
-    let mut signed_messages = Vec::with_capacity(keystores_paths.len());
-    debug!("Found {} keys in the keystore", keystores_paths.len());
+    let total = keystores_paths.len();
+    debug!("Found {} keys in the keystore", total);
 
-    for path in keystores_paths {
-        let ks = Keystore::from_json_file(path).map_err(KeystoreError::Eth2Keystore)?;
-        let password = keystore_secret.get(ks.pubkey()).ok_or(KeystoreError::MissingPassword)?;
-        let kp = ks.decrypt_keypair(password.as_bytes()).map_err(KeystoreError::Eth2Keystore)?;
-        let validator_pubkey = BlsPublicKey::try_from(kp.pk.serialize().to_vec().as_ref())?;
-        let validator_private_key = kp.sk;
+    let next = AtomicUsize::new(0);
+    let abort = AtomicBool::new(false);
+    let mut messages = Vec::with_capacity(total);
+    let mut errors = Vec::new();
 
-        match action {
-            Action::Delegate => {
-                let message = DelegationMessage::new(validator_pubkey, delegatee_pubkey.clone());
-                let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
-                let signature = validator_private_key.sign(signing_root.0.into());
-                let signature = BlsSignature::try_from(signature.serialize().as_ref())?;
-                let signed = SignedDelegation { message, signature };
-                signed_messages.push(SignedMessage::Delegation(signed));
-            }
-            Action::Revoke => {
-                let message = RevocationMessage::new(validator_pubkey, delegatee_pubkey.clone());
-                let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
-                let signature = validator_private_key.sign(signing_root.0.into());
-                let signature = BlsSignature::try_from(signature.serialize().as_ref())?;
-                let signed = SignedRevocation { message, signature };
-                signed_messages.push(SignedMessage::Revocation(signed));
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..concurrency.clamp(1, total.max(1)) {
+            let tx = tx.clone();
+            let (next, abort, paths) = (&next, &abort, &keystores_paths);
+            let (secret, delegatee, action) = (&keystore_secret, &delegatee_pubkey, &action);
+
+            scope.spawn(move || {
+                while !abort.load(Ordering::Relaxed) {
+                    let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+
+                    let result = sign_with_keystore(path, secret, delegatee, chain, action);
+                    if tx.send((path, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let mut progress = Progress::new(total);
+        for (path, result) in rx {
+            match result {
+                Ok(message) => messages.push(message),
+                Err(err) => {
+                    if strict {
+                        abort.store(true, Ordering::Relaxed);
+                    }
+                    errors.push((path.clone(), err));
+                }
             }
+            progress.tick();
+        }
+    });
+
+    if strict {
+        if let Some((path, err)) = errors.into_iter().next() {
+            return Err(err.wrap_err(format!("failed to sign with keystore {}", path.display())));
         }
     }
 
-    Ok(signed_messages)
+    messages.sort_by_cached_key(|message| message.validator_pubkey().to_vec());
+    Ok(KeystoreSigningOutput { messages, errors })
+}
+
+/// Decrypt the keystore at the given path and sign the message of the given action with it.
+fn sign_with_keystore(
+    path: &Path,
+    keystore_secret: &KeystoreSecret,
+    delegatee_pubkey: &BlsPublicKey,
+    chain: Chain,
+    action: &Action,
+) -> Result<SignedMessage> {
+    let ks = Keystore::from_json_file(path).map_err(KeystoreError::Eth2Keystore)?;
+    let password = keystore_secret.get(ks.pubkey()).ok_or(KeystoreError::MissingPassword)?;
+    let kp = ks.decrypt_keypair(password.as_bytes()).map_err(KeystoreError::Eth2Keystore)?;
+    let validator_pubkey = BlsPublicKey::try_from(kp.pk.serialize().to_vec().as_ref())?;
+    let validator_private_key = kp.sk;
+
+    match action {
+        Action::Delegate => {
+            let message = DelegationMessage::new(validator_pubkey, delegatee_pubkey.clone());
+            let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
+            let signature = validator_private_key.sign(signing_root.0.into());
+            let signature = BlsSignature::try_from(signature.serialize().as_ref())?;
+            Ok(SignedMessage::Delegation(SignedDelegation { message, signature }))
+        }
+        Action::Revoke => {
+            let message = RevocationMessage::new(validator_pubkey, delegatee_pubkey.clone());
+            let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
+            let signature = validator_private_key.sign(signing_root.0.into());
+            let signature = BlsSignature::try_from(signature.serialize().as_ref())?;
+            Ok(SignedMessage::Revocation(SignedRevocation { message, signature }))
+        }
+    }
+}
+
+/// Progress of a long-running operation, logged at most once per second.
+struct Progress {
+    total: usize,
+    done: usize,
+    start: Instant,
+    last_log: Instant,
+}
+
+impl Progress {
+    /// Interval between two progress logs.
+    const LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+    fn new(total: usize) -> Self {
+        let now = Instant::now();
+        Self { total, done: 0, start: now, last_log: now }
+    }
+
+    /// Record a processed item, logging the progress if due or if every item is processed.
+    fn tick(&mut self) {
+        self.done += 1;
+        if self.done < self.total && self.last_log.elapsed() < Self::LOG_INTERVAL {
+            return;
+        }
+
+        self.last_log = Instant::now();
+        let elapsed = self.start.elapsed();
+        let eta = elapsed.mul_f64((self.total - self.done) as f64 / self.done as f64);
+        info!("Processed {}/{} keystores, ETA {}s", self.done, self.total, eta.as_secs());
+    }
 }
 
 /// Generate signed delegations/revocations using a remote Dirk signer
@@ -190,10 +495,15 @@ pub async fn generate_from_dirk(
     action: Action,
 ) -> Result<Vec<SignedMessage>> {
     // first read the accounts from the remote keystore
-    let accounts = dirk.list_accounts(account_path).await?;
-    debug!("Found {} remote accounts to sign with", accounts.len());
+    let WalletAccounts { accounts, distributed_accounts } =
+        dirk.list_wallet_accounts(account_path).await?;
+    debug!(
+        "Found {} remote accounts and {} distributed accounts to sign with",
+        accounts.len(),
+        distributed_accounts.len()
+    );
 
-    let mut signed_messages = Vec::with_capacity(accounts.len());
+    let mut signed_messages = Vec::with_capacity(accounts.len() + distributed_accounts.len());
 
     // specify the signing domain (needs to be included in the signing request)
     let domain = B256::from(compute_domain_from_mask(chain.fork_version()));
@@ -236,6 +546,44 @@ pub async fn generate_from_dirk(
         }
     }
 
+    // Distributed accounts are signed by a threshold of their participants, each of them
+    // unlocking the account with the given passphrases
+    for account in distributed_accounts {
+        let Some(ref passphrases) = passphrases else {
+            bail!("A passphrase is required in order to sign messages remotely with Dirk");
+        };
+
+        let pubkey = BlsPublicKey::try_from(account.composite_public_key.as_slice())?;
+        match action {
+            Action::Delegate => {
+                let message = DelegationMessage::new(pubkey, delegatee_pubkey.clone());
+                let signature = dirk
+                    .request_threshold_signature(
+                        &account,
+                        passphrases,
+                        message.digest().into(),
+                        domain,
+                    )
+                    .await?;
+                let signed = SignedDelegation { message, signature };
+                signed_messages.push(SignedMessage::Delegation(signed));
+            }
+            Action::Revoke => {
+                let message = RevocationMessage::new(pubkey, delegatee_pubkey.clone());
+                let signature = dirk
+                    .request_threshold_signature(
+                        &account,
+                        passphrases,
+                        message.digest().into(),
+                        domain,
+                    )
+                    .await?;
+                let signed = SignedRevocation { message, signature };
+                signed_messages.push(SignedMessage::Revocation(signed));
+            }
+        }
+    }
+
     Ok(signed_messages)
 }
 
@@ -271,6 +619,52 @@ pub enum SignedMessage {
     Revocation(SignedRevocation),
 }
 
+impl SignedMessage {
+    /// Returns the validator pubkey of the message.
+    pub fn validator_pubkey(&self) -> &BlsPublicKey {
+        match self {
+            Self::Delegation(signed) => &signed.message.validator_pubkey,
+            Self::Revocation(signed) => &signed.message.validator_pubkey,
+        }
+    }
+}
+
+/// An entry of a delegations file, either a signed message or an unsigned one to be signed
+/// later by the key holder.
+///
+/// Unsigned entries carry an `unsigned` marker, so that they can't be mistaken for signed ones:
+/// ```
+/// {
+///    "message": {
+///       "action": 0,
+///       "validator_pubkey": "0x...",
+///       "delegatee_pubkey": "0x..."
+///    },
+///   "unsigned": true
+/// },
+/// ```
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum DelegationEntry {
+    Signed(SignedMessage),
+    Unsigned { message: UnsignedMessage, unsigned: bool },
+}
+
+impl DelegationEntry {
+    /// Returns true if the entry is unsigned.
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, Self::Unsigned { .. })
+    }
+}
+
+/// Transparent serialization of unsigned messages.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum UnsignedMessage {
+    Delegation(DelegationMessage),
+    Revocation(RevocationMessage),
+}
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 pub struct SignedDelegation {
     pub message: DelegationMessage,
@@ -366,7 +760,10 @@ mod tests {
         common::{dirk, keystore, parse_bls_public_key},
     };
 
-    use super::{generate_from_dirk, generate_from_keystore, verify_message_signature};
+    use super::{
+        generate_for_pubkeys, generate_from_dirk, generate_from_keystore, generate_from_local_keys,
+        revoke_from_delegations, verify_message_signature, SignedMessage,
+    };
 
     #[test]
     fn test_delegation_keystore_signer_lighthouse() -> eyre::Result<()> {
@@ -380,21 +777,113 @@ mod tests {
         let delegatee_pubkey = parse_bls_public_key(delegatee_pubkey)?;
         let chain = Chain::Mainnet;
 
-        let signed_delegations = generate_from_keystore(
+        let output = generate_from_keystore(
             &keys_path,
             keystore_secret,
             delegatee_pubkey.clone(),
             chain,
             Action::Delegate,
+            2,
+            true,
         )?;
 
-        let signed_message = signed_delegations.first().expect("to get signed delegation");
+        let signed_message = output.messages.first().expect("to get signed delegation");
 
         verify_message_signature(signed_message, chain)?;
 
         Ok(())
     }
 
+    /// Write `count` keystores with distinct keys to a temporary directory, with the same
+    /// password. A few PBKDF2 rounds are used so that decryption dominates the signing time.
+    fn write_fixture_keystores(count: u8, password: &str) -> eyre::Result<tempfile::TempDir> {
+        use lighthouse_eth2_keystore::{
+            json_keystore::{Kdf, Pbkdf2, Prf},
+            keypair_from_secret, KeystoreBuilder, DKLEN,
+        };
+
+        let dir = tempfile::tempdir()?;
+        for i in 1..=count {
+            let keypair = keypair_from_secret(&[i; 32]).map_err(|e| eyre::eyre!("{e:?}"))?;
+            let kdf = Kdf::Pbkdf2(Pbkdf2 {
+                dklen: DKLEN,
+                c: 1 << 16,
+                prf: Prf::HmacSha256,
+                salt: vec![i; 32].into(),
+            });
+            let keystore = KeystoreBuilder::new(&keypair, password.as_bytes(), String::new())
+                .and_then(|builder| builder.kdf(kdf).build())
+                .map_err(|e| eyre::eyre!("{e:?}"))?;
+
+            let keystore_dir = dir.path().join(format!("0x{}", keystore.pubkey()));
+            std::fs::create_dir(&keystore_dir)?;
+            let file = std::fs::File::create(keystore_dir.join("voting-keystore.json"))?;
+            keystore.to_json_writer(file).map_err(|e| eyre::eyre!("{e:?}"))?;
+        }
+
+        Ok(dir)
+    }
+
+    /// Test that signing with several workers is faster than sequential signing, with the same
+    /// output ordering, and that a bad keystore doesn't abort the generation unless strict.
+    #[test]
+    fn test_delegation_keystore_parallel() -> eyre::Result<()> {
+        use std::time::Instant;
+
+        let dir = write_fixture_keystores(16, "password")?;
+        let keys_path = dir.path().to_str().unwrap();
+        let delegatee_pubkey = parse_bls_public_key("0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93")?;
+
+        let run = |concurrency, strict| {
+            let start = Instant::now();
+            let secret = keystore::KeystoreSecret::from_unique_password("password".to_string());
+            let output = generate_from_keystore(
+                keys_path,
+                secret,
+                delegatee_pubkey.clone(),
+                Chain::Holesky,
+                Action::Delegate,
+                concurrency,
+                strict,
+            );
+            (output, start.elapsed())
+        };
+
+        let (sequential, sequential_time) = run(1, true);
+        let (parallel, parallel_time) = run(4, true);
+        let (sequential, parallel) = (sequential?, parallel?);
+
+        assert_eq!(sequential.messages.len(), 16);
+        assert_eq!(sequential.messages, parallel.messages);
+
+        let pubkeys = parallel.messages.iter().map(|m| m.validator_pubkey().to_vec());
+        let pubkeys = pubkeys.collect::<Vec<_>>();
+        assert!(pubkeys.windows(2).all(|w| w[0] < w[1]), "messages must be sorted by pubkey");
+
+        if std::thread::available_parallelism()?.get() > 1 {
+            assert!(
+                parallel_time < sequential_time,
+                "parallel signing took {parallel_time:?}, sequential {sequential_time:?}"
+            );
+        }
+
+        // A corrupted keystore is reported at the end, unless strict
+        let bad_dir = dir.path().join("0xbad");
+        std::fs::create_dir(&bad_dir)?;
+        std::fs::write(bad_dir.join("voting-keystore.json"), "{}")?;
+
+        let (output, _) = run(4, false);
+        let output = output?;
+        assert_eq!(output.messages.len(), 16);
+        assert_eq!(output.errors.len(), 1);
+        assert!(output.errors[0].0.starts_with(&bad_dir));
+
+        let (output, _) = run(4, true);
+        assert!(output.unwrap_err().to_string().contains("0xbad"));
+
+        Ok(())
+    }
+
     /// Test generating signed delegations using a remote Dirk signer.
     ///
     /// ```shell
@@ -429,4 +918,138 @@ mod tests {
 
         Ok(())
     }
+
+    /// Test generating delegations for validators fetched from a mocked beacon node, where
+    /// only one of the validators has its secret key provided.
+    #[tokio::test]
+    async fn test_delegation_beacon_node() -> eyre::Result<()> {
+        use ethereum_consensus::crypto::SecretKey as BlsSecretKey;
+        use reqwest::Url;
+        use serde_json::{json, Value};
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        use crate::common::{beacon::fetch_validators, write_to_file};
+
+        let secret_keys = (1..=2).map(|i| format!("0x{}", format!("{i:02x}").repeat(32)));
+        let secret_keys = secret_keys.collect::<Vec<_>>();
+        let pubkeys = secret_keys
+            .iter()
+            .map(|sk| BlsSecretKey::try_from(sk.clone()).unwrap().public_key())
+            .collect::<Vec<_>>();
+
+        let server = MockServer::start().await;
+        Mock::given(matchers::path("/eth/v1/beacon/states/head/validators"))
+            .and(matchers::query_param("id", "10,11"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "execution_optimistic": false,
+                "data": pubkeys.iter().enumerate().map(|(i, pubkey)| json!({
+                    "index": (10 + i).to_string(),
+                    "balance": "32000000000",
+                    "status": "active_ongoing",
+                    "validator": {
+                        "pubkey": format!("{pubkey:?}"),
+                        "withdrawal_credentials": format!("0x01{}", "00".repeat(31)),
+                    }
+                })).collect::<Vec<_>>()
+            })))
+            .mount(&server)
+            .await;
+
+        let validators = fetch_validators(&Url::parse(&server.uri())?, &[10, 11]).await?;
+        assert_eq!(validators.iter().map(|v| v.index).collect::<Vec<_>>(), vec![10, 11]);
+
+        let delegatee_pubkey = parse_bls_public_key("0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93")?;
+        let pubkeys = validators.into_iter().map(|v| v.pubkey).collect::<Vec<_>>();
+        let entries = generate_for_pubkeys(
+            &pubkeys,
+            &secret_keys[1..],
+            delegatee_pubkey,
+            Chain::Mainnet,
+            Action::Delegate,
+        )?;
+
+        let out = tempfile::NamedTempFile::new()?;
+        write_to_file(out.path().to_str().unwrap(), &entries)?;
+        let output = serde_json::from_reader::<_, Vec<Value>>(std::fs::File::open(out.path())?)?;
+
+        assert_eq!(output.len(), 2);
+
+        // The first validator has no secret key: its entry is unsigned
+        assert_eq!(output[0]["unsigned"], json!(true));
+        assert!(output[0].get("signature").is_none());
+        assert_eq!(output[0]["message"]["action"], json!(0));
+        assert_eq!(output[0]["message"]["validator_pubkey"], json!(format!("{:?}", pubkeys[0])));
+
+        // The second one is signed with its secret key
+        assert!(output[1].get("unsigned").is_none());
+        assert!(output[1]["signature"].is_string());
+
+        Ok(())
+    }
+
+    /// Test that the delegations of a file are mirrored into matching revocations, signed with
+    /// the keys of the source.
+    #[tokio::test]
+    async fn test_revoke_from_delegations() -> eyre::Result<()> {
+        use crate::{cli::SecretsSource, common::write_to_file};
+
+        let secret_keys = (1..=3).map(|i| format!("0x{}", format!("{i:02x}").repeat(32)));
+        let secret_keys = secret_keys.collect::<Vec<_>>();
+        let delegatees = [
+            parse_bls_public_key("0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93")?,
+            parse_bls_public_key("0x8d0edf4fe9c80cd640220ca7a68a48efcbc56a13536d6b274bf3719befaffa13688ebee9f37414b3dddc8c7e77233ce8")?,
+        ];
+        let chain = Chain::Holesky;
+
+        // The first two validators delegate to the first delegatee, the last one to the second
+        let mut delegations = generate_from_local_keys(
+            &secret_keys[..2],
+            delegatees[0].clone(),
+            chain,
+            Action::Delegate,
+        )?;
+        delegations.extend(generate_from_local_keys(
+            &secret_keys[2..],
+            delegatees[1].clone(),
+            chain,
+            Action::Delegate,
+        )?);
+
+        let file = tempfile::NamedTempFile::new()?;
+        write_to_file(file.path().to_str().unwrap(), &delegations)?;
+
+        let source = SecretsSource::SecretKeys { secret_keys: secret_keys.clone() };
+        let revocations = revoke_from_delegations(&source, file.path(), chain, false).await?;
+        assert_eq!(revocations.len(), delegations.len());
+
+        for (revocation, delegation) in revocations.iter().zip(&delegations) {
+            let (SignedMessage::Revocation(revocation), SignedMessage::Delegation(delegation)) =
+                (revocation, delegation)
+            else {
+                panic!("expected a revocation for each delegation");
+            };
+            assert_eq!(revocation.message.validator_pubkey, delegation.message.validator_pubkey);
+            assert_eq!(revocation.message.delegatee_pubkey, delegation.message.delegatee_pubkey);
+        }
+        for revocation in &revocations {
+            verify_message_signature(revocation, chain)?;
+        }
+
+        // The revocations file has the same format as the delegations file
+        let out = tempfile::NamedTempFile::new()?;
+        write_to_file(out.path().to_str().unwrap(), &revocations)?;
+        let checks = super::verify::verify_delegations_file(out.path(), chain, None)?;
+        assert!(checks.iter().all(|check| check.passed()));
+
+        // A validator missing from the source fails, unless skipped
+        let source = SecretsSource::SecretKeys { secret_keys: secret_keys[1..].to_vec() };
+        let err = revoke_from_delegations(&source, file.path(), chain, false).await.unwrap_err();
+        assert!(err.to_string().contains("1 of 3 delegating validators have no key"));
+
+        let revocations = revoke_from_delegations(&source, file.path(), chain, true).await?;
+        assert_eq!(revocations.len(), 2);
+        assert_eq!(revocations[0].validator_pubkey(), delegations[1].validator_pubkey());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,343 @@
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+use ethereum_consensus::crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature};
+use eyre::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    verify_message_signature, DelegationMessage, RevocationMessage, SignedDelegation,
+    SignedMessage, SignedMessageAction, SignedRevocation,
+};
+use crate::cli::Chain;
+
+/// An entry of a delegations file, as written by `bolt delegate`.
+#[derive(Debug, Deserialize)]
+struct FileEntry {
+    message: FileMessage,
+    signature: Option<BlsSignature>,
+    #[serde(default)]
+    unsigned: bool,
+}
+
+/// The message of an entry, either a delegation or a revocation depending on its action.
+#[derive(Debug, Deserialize)]
+struct FileMessage {
+    action: u8,
+    validator_pubkey: BlsPublicKey,
+    delegatee_pubkey: BlsPublicKey,
+}
+
+/// The result of verifying an entry of a delegations file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryCheck {
+    /// The 1-indexed position of the entry in the file.
+    pub index: usize,
+    /// The kind of message, if it could be parsed.
+    pub kind: Option<MessageKind>,
+    /// The validator public key of the message, if it could be parsed.
+    pub validator_pubkey: Option<BlsPublicKey>,
+    /// The reasons why the entry failed verification, empty if it passed.
+    pub failures: Vec<String>,
+}
+
+impl EntryCheck {
+    /// Returns true if the entry passed every check.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The kind of message of an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    Delegation,
+    Revocation,
+}
+
+impl fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delegation => write!(f, "delegate"),
+            Self::Revocation => write!(f, "revoke"),
+        }
+    }
+}
+
+/// Verify every entry of the given delegations file.
+///
+/// Each entry must be signed with a valid signature for the given chain, and delegate to the
+/// given delegatee if any. Validators must appear at most once per action, and can't both
+/// delegate and revoke the same delegatee in the same file.
+pub fn verify_delegations_file(
+    path: &Path,
+    chain: Chain,
+    delegatee_pubkey: Option<BlsPublicKey>,
+) -> Result<Vec<EntryCheck>> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read delegations file {}", path.display()))?;
+    let entries = serde_json::from_str::<Vec<Value>>(&contents)
+        .wrap_err("delegations file must contain a JSON array")?;
+
+    Ok(verify_entries(entries, chain, delegatee_pubkey.as_ref()))
+}
+
+/// Read the (validator, delegatee) pairs of the delegations of the given delegations file, in
+/// file order and without duplicates. Revocations and unsigned entries are ignored.
+pub fn read_delegation_pairs(path: &Path) -> Result<Vec<(BlsPublicKey, BlsPublicKey)>> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read delegations file {}", path.display()))?;
+    let entries = serde_json::from_str::<Vec<FileEntry>>(&contents)
+        .wrap_err("delegations file must contain a JSON array of delegations")?;
+
+    let mut pairs = Vec::<(BlsPublicKey, BlsPublicKey)>::with_capacity(entries.len());
+    for entry in entries {
+        let is_delegation = entry.message.action == SignedMessageAction::Delegation as u8;
+        if !is_delegation || entry.unsigned || entry.signature.is_none() {
+            continue;
+        }
+
+        let pair = (entry.message.validator_pubkey, entry.message.delegatee_pubkey);
+        if !pairs.contains(&pair) {
+            pairs.push(pair);
+        }
+    }
+
+    Ok(pairs)
+}
+
+/// Verify the given entries of a delegations file, see [verify_delegations_file].
+fn verify_entries(
+    entries: Vec<Value>,
+    chain: Chain,
+    delegatee_pubkey: Option<&BlsPublicKey>,
+) -> Vec<EntryCheck> {
+    let mut checks = Vec::with_capacity(entries.len());
+    let mut messages = Vec::with_capacity(entries.len());
+
+    for (i, value) in entries.into_iter().enumerate() {
+        let mut check =
+            EntryCheck { index: i + 1, kind: None, validator_pubkey: None, failures: Vec::new() };
+
+        match serde_json::from_value::<FileEntry>(value) {
+            Ok(entry) => {
+                check.validator_pubkey = Some(entry.message.validator_pubkey.clone());
+                check_entry(&mut check, entry, chain, delegatee_pubkey, &mut messages);
+            }
+            Err(err) => check.failures.push(format!("invalid entry: {err}")),
+        }
+
+        checks.push(check);
+    }
+
+    check_conflicts(&mut checks, &messages);
+    checks
+}
+
+/// Check a single parsed entry, recording its message for the cross-entry checks.
+fn check_entry(
+    check: &mut EntryCheck,
+    entry: FileEntry,
+    chain: Chain,
+    delegatee_pubkey: Option<&BlsPublicKey>,
+    messages: &mut Vec<(usize, MessageKind, FileMessage)>,
+) {
+    let kind = match entry.message.action {
+        action if action == SignedMessageAction::Delegation as u8 => MessageKind::Delegation,
+        action if action == SignedMessageAction::Revocation as u8 => MessageKind::Revocation,
+        action => {
+            check.failures.push(format!("unknown action {action}"));
+            return;
+        }
+    };
+    check.kind = Some(kind);
+
+    if delegatee_pubkey.is_some_and(|pubkey| *pubkey != entry.message.delegatee_pubkey) {
+        check.failures.push("unexpected delegatee pubkey".to_string());
+    }
+
+    match entry.signature {
+        Some(signature) if !entry.unsigned => {
+            let (validator_pubkey, delegatee_pubkey) =
+                (entry.message.validator_pubkey.clone(), entry.message.delegatee_pubkey.clone());
+            let message = match kind {
+                MessageKind::Delegation => SignedMessage::Delegation(SignedDelegation {
+                    message: DelegationMessage::new(validator_pubkey, delegatee_pubkey),
+                    signature,
+                }),
+                MessageKind::Revocation => SignedMessage::Revocation(SignedRevocation {
+                    message: RevocationMessage::new(validator_pubkey, delegatee_pubkey),
+                    signature,
+                }),
+            };
+
+            if verify_message_signature(&message, chain).is_err() {
+                check.failures.push("invalid signature".to_string());
+            }
+        }
+        _ => check.failures.push("unsigned message".to_string()),
+    }
+
+    messages.push((check.index, kind, entry.message));
+}
+
+/// Flag the validators appearing more than once for the same action, and the validators that
+/// both delegate and revoke the same delegatee.
+fn check_conflicts(checks: &mut [EntryCheck], messages: &[(usize, MessageKind, FileMessage)]) {
+    let mut occurrences = HashMap::<_, Vec<usize>>::new();
+    for (index, kind, message) in messages {
+        occurrences.entry((message.validator_pubkey.to_vec(), *kind)).or_default().push(*index);
+    }
+
+    for (index, kind, message) in messages {
+        let check = &mut checks[index - 1];
+
+        let duplicates = &occurrences[&(message.validator_pubkey.to_vec(), *kind)];
+        if duplicates.len() > 1 {
+            check.failures.push(format!("duplicate validator pubkey (entries {duplicates:?})"));
+        }
+
+        let opposite = match kind {
+            MessageKind::Delegation => MessageKind::Revocation,
+            MessageKind::Revocation => MessageKind::Delegation,
+        };
+        let conflicts = messages
+            .iter()
+            .filter(|(_, other_kind, other)| {
+                *other_kind == opposite &&
+                    other.validator_pubkey == message.validator_pubkey &&
+                    other.delegatee_pubkey == message.delegatee_pubkey
+            })
+            .map(|(other_index, _, _)| *other_index)
+            .collect::<Vec<_>>();
+        if !conflicts.is_empty() {
+            check.failures.push(format!("conflicting delegate/revoke (entries {conflicts:?})"));
+        }
+    }
+}
+
+/// Print a PASS/FAIL table of the given checks.
+pub fn print_table(checks: &[EntryCheck]) {
+    println!("{:>5}  {:<8}  {:<98}  RESULT", "ENTRY", "ACTION", "VALIDATOR");
+    for check in checks {
+        let kind = check.kind.map(|kind| kind.to_string()).unwrap_or_else(|| "-".to_string());
+        let validator = check
+            .validator_pubkey
+            .as_ref()
+            .map(|pubkey| format!("{pubkey:?}"))
+            .unwrap_or_else(|| "-".to_string());
+        let result = if check.passed() {
+            "PASS".to_string()
+        } else {
+            format!("FAIL ({})", check.failures.join(", "))
+        };
+
+        println!("{:>5}  {:<8}  {:<98}  {}", check.index, kind, validator, result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        cli::Action,
+        commands::delegate::generate_from_local_keys,
+        common::{parse_bls_public_key, write_to_file},
+    };
+
+    const DELEGATEE: &str = "0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93";
+
+    /// Returns `count` distinct local secret keys.
+    fn secret_keys(count: u8) -> Vec<String> {
+        (1..=count).map(|i| format!("0x{}", format!("{i:02x}").repeat(32))).collect()
+    }
+
+    /// Generate a mixed delegations file: delegations for the first two keys, and a
+    /// revocation for the third one.
+    fn mixed_entries() -> Vec<Value> {
+        let keys = secret_keys(3);
+        let delegatee = parse_bls_public_key(DELEGATEE).unwrap();
+
+        let mut messages = generate_from_local_keys(
+            &keys[..2],
+            delegatee.clone(),
+            Chain::Holesky,
+            Action::Delegate,
+        )
+        .unwrap();
+        messages.extend(
+            generate_from_local_keys(&keys[2..], delegatee, Chain::Holesky, Action::Revoke)
+                .unwrap(),
+        );
+
+        serde_json::from_value(serde_json::to_value(messages).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_verify_mixed_file() -> Result<()> {
+        let out = tempfile::NamedTempFile::new()?;
+        write_to_file(out.path().to_str().unwrap(), &mixed_entries())?;
+
+        let delegatee = parse_bls_public_key(DELEGATEE)?;
+        let checks = verify_delegations_file(out.path(), Chain::Holesky, Some(delegatee))?;
+
+        assert_eq!(checks.len(), 3);
+        assert!(checks.iter().all(EntryCheck::passed));
+        assert_eq!(checks[2].kind, Some(MessageKind::Revocation));
+
+        // Signatures are bound to the chain
+        let checks = verify_delegations_file(out.path(), Chain::Mainnet, None)?;
+        assert!(checks.iter().all(|check| check.failures == ["invalid signature"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_tampered_signature() {
+        let mut entries = mixed_entries();
+        entries[0]["signature"] = entries[1]["signature"].clone();
+
+        let checks = verify_entries(entries, Chain::Holesky, None);
+
+        assert_eq!(checks[0].failures, vec!["invalid signature".to_string()]);
+        assert!(checks[1].passed());
+        assert!(checks[2].passed());
+    }
+
+    #[test]
+    fn test_verify_duplicated_validator() {
+        let mut entries = mixed_entries();
+        entries.push(entries[0].clone());
+
+        let checks = verify_entries(entries, Chain::Holesky, None);
+
+        let duplicate = "duplicate validator pubkey (entries [1, 4])".to_string();
+        assert_eq!(checks[0].failures, vec![duplicate.clone()]);
+        assert_eq!(checks[3].failures, vec![duplicate]);
+        assert!(checks[1].passed());
+    }
+
+    #[test]
+    fn test_verify_conflicting_and_unsigned_entries() {
+        let keys = secret_keys(3);
+        let delegatee = parse_bls_public_key(DELEGATEE).unwrap();
+        let revocation =
+            generate_from_local_keys(&keys[..1], delegatee, Chain::Holesky, Action::Revoke)
+                .unwrap();
+
+        let mut entries = mixed_entries();
+        entries.push(serde_json::to_value(&revocation[0]).unwrap());
+        entries.push(json!({ "message": entries[1]["message"].clone(), "unsigned": true }));
+        entries.push(json!({ "foo": "bar" }));
+
+        let checks = verify_entries(entries, Chain::Holesky, None);
+
+        assert_eq!(checks[0].failures, vec!["conflicting delegate/revoke (entries [4])"]);
+        assert_eq!(checks[3].failures, vec!["conflicting delegate/revoke (entries [1])"]);
+        assert!(checks[4].failures.contains(&"unsigned message".to_string()));
+        assert!(checks[5].failures[0].starts_with("invalid entry"));
+        assert_eq!(checks[5].kind, None);
+    }
+}
@@ -0,0 +1,257 @@
+use alloy::hex;
+use eyre::{bail, Result};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{
+    cli::{DirkCommand, DirkSubcommand},
+    common::dirk::{Dirk, WalletAccounts},
+};
+
+impl DirkCommand {
+    /// Run the `dirk` command.
+    pub async fn run(self) -> Result<()> {
+        match self.subcommand {
+            DirkSubcommand::List { url, wallet_path, tls_credentials, json } => {
+                let mut dirk = Dirk::connect(url, tls_credentials).await?;
+                let listings = list_wallets(&mut dirk, wallet_path).await;
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&listings)?);
+                } else {
+                    print_table(&listings);
+                }
+
+                if listings.iter().all(|listing| listing.error.is_some()) {
+                    bail!("Failed to list any of the {} wallet paths", listings.len());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The accounts listed in a wallet path, or why they couldn't be listed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WalletListing {
+    /// The wallet path, as requested.
+    pub path: String,
+    /// The accounts in the wallet path.
+    pub accounts: Vec<AccountListing>,
+    /// The error returned when listing the wallet path, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An account of a wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccountListing {
+    /// A standard account, whose key is held by a single DIRK instance.
+    Standard { name: String, public_key: String },
+    /// A distributed account, whose key is split across several DIRK instances.
+    Distributed {
+        name: String,
+        /// The share of the key held by this DIRK instance.
+        public_key: String,
+        /// The public key of the account, recovered from the shares.
+        composite_public_key: String,
+        /// The number of participants needed to produce a signature.
+        signing_threshold: u32,
+        participants: Vec<Participant>,
+    },
+}
+
+/// A DIRK instance holding a share of a distributed account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Participant {
+    pub id: u64,
+    pub name: String,
+    pub port: u32,
+}
+
+/// List the accounts of every given wallet path. A failure to list a wallet path is recorded in
+/// its listing, and doesn't prevent listing the others.
+pub async fn list_wallets(dirk: &mut Dirk, wallet_paths: Vec<String>) -> Vec<WalletListing> {
+    let mut listings = Vec::with_capacity(wallet_paths.len());
+    for path in wallet_paths {
+        let listing = match dirk.list_wallet_accounts(path.clone()).await {
+            Ok(accounts) => {
+                WalletListing { path, accounts: account_listings(accounts), error: None }
+            }
+            Err(err) => {
+                warn!("Failed to list accounts in {}: {:?}", path, err);
+                WalletListing { path, accounts: Vec::new(), error: Some(err.to_string()) }
+            }
+        };
+
+        listings.push(listing);
+    }
+
+    listings
+}
+
+/// Convert the accounts returned by DIRK to their listing.
+fn account_listings(wallet: WalletAccounts) -> Vec<AccountListing> {
+    let standard = wallet.accounts.into_iter().map(|account| AccountListing::Standard {
+        name: account.name,
+        public_key: hex::encode_prefixed(account.public_key),
+    });
+
+    let distributed =
+        wallet.distributed_accounts.into_iter().map(|account| AccountListing::Distributed {
+            name: account.name,
+            public_key: hex::encode_prefixed(account.public_key),
+            composite_public_key: hex::encode_prefixed(account.composite_public_key),
+            signing_threshold: account.signing_threshold,
+            participants: account
+                .participants
+                .into_iter()
+                .map(|p| Participant { id: p.id, name: p.name, port: p.port })
+                .collect(),
+        });
+
+    standard.chain(distributed).collect()
+}
+
+/// Print the listings as a human-readable table.
+fn print_table(listings: &[WalletListing]) {
+    println!("{:<40}  {:<24}  PUBLIC KEY", "ACCOUNT", "TYPE");
+    for listing in listings {
+        if let Some(error) = &listing.error {
+            println!("{:<40}  {:<24}  {}", listing.path, "error", error);
+            continue;
+        }
+
+        for account in &listing.accounts {
+            match account {
+                AccountListing::Standard { name, public_key } => {
+                    println!("{:<40}  {:<24}  {}", name, "standard", public_key);
+                }
+                AccountListing::Distributed {
+                    name,
+                    composite_public_key,
+                    signing_threshold,
+                    participants,
+                    ..
+                } => {
+                    let kind =
+                        format!("distributed ({signing_threshold} of {})", participants.len());
+                    println!("{:<40}  {:<24}  {}", name, kind, composite_public_key);
+                    for p in participants {
+                        println!("  participant {}: {}:{}", p.id, p.name, p.port);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tonic::{
+        transport::{server::TcpIncoming, Channel, Server},
+        Request, Response, Status,
+    };
+
+    use super::*;
+    use crate::pb::eth2_signer_api::{
+        Account, DistributedAccount, Endpoint, ListAccountsRequest, ListAccountsResponse, Lister,
+        ListerServer, ResponseState,
+    };
+
+    /// A mock DIRK lister with one standard and one distributed account in `wallet1`, and
+    /// denying the access to any other wallet.
+    struct MockLister;
+
+    #[tonic::async_trait]
+    impl Lister for MockLister {
+        async fn list_accounts(
+            &self,
+            request: Request<ListAccountsRequest>,
+        ) -> Result<Response<ListAccountsResponse>, Status> {
+            if request.into_inner().paths != ["wallet1"] {
+                return Ok(Response::new(ListAccountsResponse {
+                    state: ResponseState::Denied as i32,
+                    ..Default::default()
+                }));
+            }
+
+            let participants =
+                (1..=3).map(|id| Endpoint { id, name: format!("dirk-{id}"), port: 9091 }).collect();
+
+            Ok(Response::new(ListAccountsResponse {
+                state: ResponseState::Succeeded as i32,
+                accounts: vec![Account {
+                    name: "wallet1/standard".to_string(),
+                    public_key: vec![0xaa; 48],
+                    uuid: Vec::new(),
+                }],
+                distributed_accounts: vec![DistributedAccount {
+                    name: "wallet1/distributed".to_string(),
+                    public_key: vec![0xbb; 48],
+                    participants,
+                    signing_threshold: 2,
+                    uuid: Vec::new(),
+                    composite_public_key: vec![0xcc; 48],
+                }],
+            }))
+        }
+    }
+
+    /// Start the mock lister on a random local port, returning a connected DIRK client.
+    async fn start_mock_dirk() -> Result<Dirk> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let incoming = TcpIncoming::from_listener(listener, true, None)
+            .map_err(|err| eyre::eyre!("{err:?}"))?;
+
+        tokio::spawn(
+            Server::builder()
+                .add_service(ListerServer::new(MockLister))
+                .serve_with_incoming(incoming),
+        );
+
+        let channel = Channel::from_shared(format!("http://{addr}"))?.connect().await?;
+        Ok(Dirk::from_channel(channel))
+    }
+
+    #[tokio::test]
+    async fn test_list_wallets() -> Result<()> {
+        let mut dirk = start_mock_dirk().await?;
+
+        let paths = vec!["wallet1".to_string(), "wallet2".to_string()];
+        let listings = list_wallets(&mut dirk, paths).await;
+
+        assert_eq!(listings.len(), 2);
+        assert_eq!(listings[0].error, None);
+        assert_eq!(
+            listings[0].accounts[0],
+            AccountListing::Standard {
+                name: "wallet1/standard".to_string(),
+                public_key: format!("0x{}", "aa".repeat(48)),
+            }
+        );
+
+        let AccountListing::Distributed { signing_threshold, participants, .. } =
+            &listings[0].accounts[1]
+        else {
+            panic!("expected a distributed account");
+        };
+        assert_eq!(*signing_threshold, 2);
+        assert_eq!(participants.len(), 3);
+        assert_eq!(participants[2].name, "dirk-3");
+
+        // The denied wallet doesn't abort the listing
+        assert!(listings[1].error.as_ref().unwrap().contains("Failed to list accounts"));
+        assert!(listings[1].accounts.is_empty());
+
+        let json = serde_json::to_value(&listings)?;
+        assert_eq!(json[0]["accounts"][1]["type"], "distributed");
+        assert!(json[0].get("error").is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use alloy::{
+    primitives::{Address, B256},
+    signers::local::PrivateKeySigner,
+};
+use blst::min_pk::SecretKey as BlstSecretKey;
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use eyre::{bail, eyre, Result};
+use lighthouse_eth2_keystore::{json_keystore::Kdf, keypair_from_secret, KeystoreBuilder};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::{GenerateCommand, PubkeysFormat},
+    commands::pubkeys::write_pubkeys,
+    common::keystore::KeystoreError,
+};
+
+/// The file holding the generated BLS keypairs.
+const BLS_KEYS_FILE: &str = "bls_keys.json";
+
+/// The file holding the public keys of the generated BLS keypairs.
+const PUBKEYS_FILE: &str = "pubkeys.json";
+
+/// The file holding the generated ECDSA keys.
+const ECDSA_KEYS_FILE: &str = "ecdsa_keys.json";
+
+/// The directory holding the EIP-2335 keystores, one `0x{pubkey}/voting-keystore.json` per key.
+const KEYSTORES_DIR: &str = "validators";
+
+/// The directory holding the keystore passwords, one `0x{pubkey}` file per key.
+const SECRETS_DIR: &str = "secrets";
+
+impl GenerateCommand {
+    /// Run the `generate` command.
+    pub fn run(self) -> Result<()> {
+        if self.bls_keys == 0 && self.ecdsa_keys == 0 {
+            bail!("Nothing to generate, use --bls-keys and/or --ecdsa-keys");
+        }
+
+        let bls_keys = generate_bls_keys(&mut OsRng, self.bls_keys)?;
+        let ecdsa_keys = generate_ecdsa_keys(&mut OsRng, self.ecdsa_keys);
+
+        fs::create_dir_all(&self.out)?;
+
+        if !bls_keys.is_empty() {
+            write_bls_keys(&self.out, &bls_keys)?;
+            println!(
+                "{} BLS keys saved to {} and {}",
+                bls_keys.len(),
+                self.out.join(BLS_KEYS_FILE).display(),
+                self.out.join(PUBKEYS_FILE).display()
+            );
+
+            if let Some(password) = &self.keystore_password {
+                write_keystores(&self.out, &bls_keys, password, None)?;
+                println!(
+                    "{} keystores saved to {} with their passwords in {}",
+                    bls_keys.len(),
+                    self.out.join(KEYSTORES_DIR).display(),
+                    self.out.join(SECRETS_DIR).display()
+                );
+            }
+        }
+
+        if !ecdsa_keys.is_empty() {
+            let out = self.out.join(ECDSA_KEYS_FILE);
+            write_private_file(&out, serde_json::to_string_pretty(&ecdsa_keys)?.as_bytes())?;
+            println!("{} ECDSA keys saved to {}", ecdsa_keys.len(), out.display());
+        }
+
+        if self.insecure_print {
+            for key in &bls_keys {
+                println!("BLS pubkey: {:?}, secret key: {}", key.pubkey, key.secret_key);
+            }
+            for key in &ecdsa_keys {
+                println!("ECDSA address: {}, private key: {}", key.address, key.private_key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A generated BLS keypair.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlsKeypair {
+    pub pubkey: BlsPublicKey,
+    pub secret_key: B256,
+}
+
+/// A generated ECDSA private key, along with its address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EcdsaKey {
+    pub address: Address,
+    pub private_key: B256,
+}
+
+/// Generate `count` BLS keypairs from the given source of randomness.
+pub fn generate_bls_keys<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    count: usize,
+) -> Result<Vec<BlsKeypair>> {
+    (0..count)
+        .map(|_| {
+            let mut ikm = [0u8; 32];
+            rng.fill_bytes(&mut ikm);
+
+            let sk = BlstSecretKey::key_gen(&ikm, &[])
+                .map_err(|e| eyre!("Failed to generate BLS secret key: {:?}", e))?;
+            let pubkey = BlsPublicKey::try_from(sk.sk_to_pk().to_bytes().as_slice())?;
+
+            Ok(BlsKeypair { pubkey, secret_key: B256::from(sk.to_bytes()) })
+        })
+        .collect()
+}
+
+/// Generate `count` ECDSA private keys from the given source of randomness.
+pub fn generate_ecdsa_keys<R: RngCore + CryptoRng>(rng: &mut R, count: usize) -> Vec<EcdsaKey> {
+    (0..count)
+        .map(|_| {
+            let signer = PrivateKeySigner::random_with(rng);
+            EcdsaKey { address: signer.address(), private_key: signer.to_bytes() }
+        })
+        .collect()
+}
+
+/// Write the BLS keypairs and their public keys to the output directory.
+fn write_bls_keys(out: &Path, keys: &[BlsKeypair]) -> Result<()> {
+    write_private_file(&out.join(BLS_KEYS_FILE), serde_json::to_string_pretty(keys)?.as_bytes())?;
+
+    let pubkeys = keys.iter().map(|key| key.pubkey.clone()).collect::<Vec<_>>();
+    let pubkeys_path = out.join(PUBKEYS_FILE);
+    write_pubkeys(&pubkeys_path.to_string_lossy(), &pubkeys, PubkeysFormat::Json)
+}
+
+/// Write the BLS keypairs as EIP-2335 keystores encrypted with the given password, in the
+/// directory layout expected by the sidecar keystore signer.
+///
+/// The keystores use the default scrypt KDF unless another one is given.
+fn write_keystores(
+    out: &Path,
+    keys: &[BlsKeypair],
+    password: &str,
+    kdf: Option<Kdf>,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::with_capacity(keys.len());
+    for key in keys {
+        let keypair =
+            keypair_from_secret(key.secret_key.as_slice()).map_err(KeystoreError::Eth2Keystore)?;
+
+        let mut builder = KeystoreBuilder::new(&keypair, password.as_bytes(), String::new())
+            .map_err(KeystoreError::Eth2Keystore)?;
+        if let Some(kdf) = kdf.clone() {
+            builder = builder.kdf(kdf);
+        }
+        let keystore = builder.build().map_err(KeystoreError::Eth2Keystore)?;
+
+        let name = format!("0x{}", keystore.pubkey());
+        let keystore_dir = out.join(KEYSTORES_DIR).join(&name);
+        fs::create_dir_all(&keystore_dir)?;
+        fs::create_dir_all(out.join(SECRETS_DIR))?;
+
+        let path = keystore_dir.join("voting-keystore.json");
+        let file = create_private_file(&path)?;
+        keystore.to_json_writer(file).map_err(KeystoreError::Eth2Keystore)?;
+        write_private_file(&out.join(SECRETS_DIR).join(&name), password.as_bytes())?;
+
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+/// Write the contents to a new file only readable and writable by its owner.
+fn write_private_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = create_private_file(path)?;
+    file.write_all(contents)?;
+    Ok(())
+}
+
+/// Create a new file only readable and writable by its owner, failing if it already exists so
+/// that existing keys are never overwritten.
+fn create_private_file(path: &Path) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path).map_err(|e| eyre!("Failed to create {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use lighthouse_eth2_keystore::{
+        json_keystore::{Pbkdf2, Prf},
+        Keystore, DKLEN,
+    };
+
+    use super::*;
+    use crate::commands::pubkeys::{list_from_keystore, list_from_local_keys};
+
+    #[test]
+    fn test_generate_keys() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let bls_keys = generate_bls_keys(&mut OsRng, 3)?;
+        write_bls_keys(dir.path(), &bls_keys)?;
+
+        // The pubkeys derive from the secret keys, and match the `pubkeys` output
+        let secret_keys = bls_keys.iter().map(|key| key.secret_key.to_string()).collect::<Vec<_>>();
+        let pubkeys = bls_keys.iter().map(|key| key.pubkey.clone()).collect::<Vec<_>>();
+        assert_eq!(list_from_local_keys(&secret_keys)?, pubkeys);
+
+        let written = fs::read_to_string(dir.path().join(PUBKEYS_FILE))?;
+        assert_eq!(serde_json::from_str::<Vec<BlsPublicKey>>(&written)?, pubkeys);
+        let written = fs::read_to_string(dir.path().join(BLS_KEYS_FILE))?;
+        assert_eq!(serde_json::from_str::<Vec<BlsKeypair>>(&written)?, bls_keys);
+
+        // Keys are never reused, nor existing files overwritten
+        assert_ne!(generate_bls_keys(&mut OsRng, 1)?[0], bls_keys[0]);
+        assert!(write_bls_keys(dir.path(), &bls_keys).is_err());
+
+        let ecdsa_keys = generate_ecdsa_keys(&mut OsRng, 2);
+        for key in &ecdsa_keys {
+            let signer = PrivateKeySigner::from_bytes(&key.private_key)?;
+            assert_eq!(signer.address(), key.address);
+        }
+        assert_ne!(ecdsa_keys[0], ecdsa_keys[1]);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.path().join(BLS_KEYS_FILE))?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keystores_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let bls_keys = generate_bls_keys(&mut OsRng, 2)?;
+
+        // A cheap KDF to keep the test fast
+        let kdf = Kdf::Pbkdf2(Pbkdf2 {
+            dklen: DKLEN,
+            c: 1 << 10,
+            prf: Prf::HmacSha256,
+            salt: vec![1; 32].into(),
+        });
+        let paths = write_keystores(dir.path(), &bls_keys, "password", Some(kdf))?;
+
+        for (path, key) in paths.iter().zip(&bls_keys) {
+            let keystore = Keystore::from_json_file(path).map_err(KeystoreError::Eth2Keystore)?;
+            let keypair =
+                keystore.decrypt_keypair(b"password").map_err(KeystoreError::Eth2Keystore)?;
+            assert_eq!(keypair.sk.serialize().as_bytes(), key.secret_key.as_slice());
+            assert!(keystore.decrypt_keypair(b"wrong").is_err());
+
+            let name = format!("0x{}", keystore.pubkey());
+            assert_eq!(fs::read_to_string(dir.path().join(SECRETS_DIR).join(name))?, "password");
+        }
+
+        let keystores_dir = dir.path().join(KEYSTORES_DIR);
+        let mut pubkeys = list_from_keystore(keystores_dir.to_str().unwrap())?;
+        pubkeys.sort_by_cached_key(|pubkey| pubkey.to_vec());
+        let mut expected = bls_keys.into_iter().map(|key| key.pubkey).collect::<Vec<_>>();
+        expected.sort_by_cached_key(|pubkey| pubkey.to_vec());
+        assert_eq!(pubkeys, expected);
+
+        Ok(())
+    }
+}
@@ -15,3 +15,19 @@ pub mod validators;
 
 /// Module for the operators-related commands to interact with the bolt network.
 pub mod operators;
+
+/// Module for the bolt `audit` command to verify the
+/// signature audit logs produced by the sidecar.
+pub mod audit;
+
+/// Module for the bolt `dirk` command to inspect the
+/// wallets and accounts of a remote DIRK keystore.
+pub mod dirk;
+
+/// Module for the bolt `generate` command to create
+/// BLS and ECDSA keys for testing and devnets.
+pub mod generate;
+
+/// Module for the bolt `config` command to manage
+/// the configuration file and its profiles.
+pub mod config;
@@ -1,52 +1,222 @@
+use std::fs;
+
+use alloy::{
+    primitives::Address,
+    providers::{Provider, ProviderBuilder},
+};
 use ethereum_consensus::crypto::bls::{PublicKey as BlsPublicKey, SecretKey as BlsSecretKey};
-use eyre::Result;
+use eyre::{bail, Result};
 use lighthouse_eth2_keystore::Keystore;
+use reqwest::Url;
+use serde::Serialize;
 
 use crate::{
-    cli::{KeysSource, PubkeysCommand},
+    cli::{Chain, KeysSource, PubkeysCommand, PubkeysFormat},
+    commands::validators::fetch_registrations,
     common::{
         dirk::Dirk,
+        hash::{compress_bls_pubkey, CompressedHash},
         keystore::{keystore_paths, KeystoreError},
-        write_to_file,
+        parse_bls_public_key, write_to_file,
     },
+    contracts::try_deployments_for_chain,
     pb::eth2_signer_api::Account,
 };
 
+/// The registration of a pubkey in the bolt validators registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PubkeyRegistration {
+    /// The BLS public key of the validator.
+    pub pubkey: BlsPublicKey,
+    /// The hash of the public key, as stored in the registry.
+    pub pubkey_hash: CompressedHash,
+    /// Whether the validator is registered in the bolt validators registry.
+    pub registered: bool,
+    /// The operator authorized to make commitments on behalf of the validator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_operator: Option<Address>,
+}
+
+/// The registration report of a set of pubkeys, printed by `bolt pubkeys --verify`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PubkeysReport {
+    /// The number of registered pubkeys.
+    pub registered: usize,
+    /// The number of pubkeys missing from the registry.
+    pub unregistered: usize,
+    /// The registration of every pubkey.
+    pub keys: Vec<PubkeyRegistration>,
+}
+
 impl PubkeysCommand {
     pub async fn run(self) -> Result<()> {
+        let mut sources = Vec::new();
         match self.source {
-            KeysSource::PublicKeys { public_keys } => {
-                write_to_file(&self.out, &public_keys)?;
-                println!("Pubkeys saved to {}", self.out);
+            Some(KeysSource::PublicKeys { public_keys }) => {
+                sources.push(("public keys", list_from_public_keys(&public_keys)?));
             }
-            KeysSource::SecretKeys { secret_keys } => {
-                let pubkeys = list_from_local_keys(&secret_keys)?;
-
-                write_to_file(&self.out, &pubkeys)?;
-                println!("Pubkeys generated and saved to {}", self.out);
+            Some(KeysSource::SecretKeys { secret_keys }) => {
+                sources.push(("secret keys", list_from_local_keys(&secret_keys)?));
             }
-            KeysSource::LocalKeystore { path } => {
-                let pubkeys = list_from_keystore(&path)?;
-
-                write_to_file(&self.out, &pubkeys)?;
-                println!("Pubkeys generated from local keystore and saved to {}", self.out);
+            Some(KeysSource::LocalKeystore { path }) => {
+                sources.push(("local keystore", list_from_keystore(&path)?));
             }
-            KeysSource::Dirk { opts } => {
+            Some(KeysSource::Dirk { opts }) => {
                 // Note: we don't need to unlock wallets to list pubkeys
                 let mut dirk = Dirk::connect(opts.url, opts.tls_credentials).await?;
 
                 let accounts = dirk.list_accounts(opts.wallet_path).await?;
-                let pubkeys = list_from_dirk_accounts(&accounts)?;
-
-                write_to_file(&self.out, &pubkeys)?;
-                println!("Pubkeys generated from Dirk and saved to {}", self.out);
+                sources.push(("dirk", list_from_dirk_accounts(&accounts)?));
             }
+            None => {}
+        }
+
+        if !self.public_keys.is_empty() {
+            sources.push(("public keys", list_from_public_keys(&self.public_keys)?));
+        }
+        if !self.secret_keys.is_empty() {
+            sources.push(("secret keys", list_from_local_keys(&self.secret_keys)?));
+        }
+        if let Some(path) = &self.keystore_path {
+            sources.push(("local keystore", list_from_keystore(path)?));
+        }
+
+        if sources.is_empty() {
+            bail!("No pubkeys source given, use a subcommand or the source options");
+        }
+
+        // Keep stdout for the JSON report when it is requested
+        let log = |msg: String| if self.json { eprintln!("{msg}") } else { println!("{msg}") };
+
+        for (name, pubkeys) in &sources {
+            log(format!("{} keys from {}", pubkeys.len(), name));
+        }
+
+        let (pubkeys, duplicates) = combine_pubkeys(sources, self.dedup);
+        if self.dedup {
+            log(format!("{} duplicates removed", duplicates));
+        }
+
+        write_pubkeys(&self.out, &pubkeys, self.format)?;
+        log(format!("{} pubkeys saved to {}", pubkeys.len(), self.out));
+
+        if !self.verify {
+            return Ok(());
+        }
+
+        let rpc_url = self.rpc_url.expect("--verify requires --rpc-url");
+        let report = verify_pubkeys(&rpc_url, self.chain, &pubkeys).await?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+
+        if report.unregistered > 0 && !self.allow_missing {
+            bail!(
+                "{} of {} pubkeys are not registered in bolt, use --allow-missing to ignore them",
+                report.unregistered,
+                report.keys.len()
+            );
         }
 
         Ok(())
     }
 }
 
+/// Check the registration of the pubkeys in the bolt validators registry of the given chain,
+/// or of the chain of the RPC if not given.
+pub async fn verify_pubkeys(
+    rpc_url: &Url,
+    chain: Option<Chain>,
+    pubkeys: &[BlsPublicKey],
+) -> Result<PubkeysReport> {
+    let chain = match chain {
+        Some(chain) => chain,
+        None => {
+            let chain_id = ProviderBuilder::new().on_http(rpc_url.clone()).get_chain_id().await?;
+            let Some(chain) = Chain::from_id(chain_id) else {
+                bail!("chain id {} not supported", chain_id);
+            };
+            chain
+        }
+    };
+
+    let Some(deployments) = try_deployments_for_chain(chain) else {
+        bail!("bolt is not deployed on {:?}", chain);
+    };
+
+    let registrations = fetch_registrations(rpc_url, deployments.bolt.validators, pubkeys).await?;
+    let keys = pubkeys
+        .iter()
+        .zip(registrations)
+        .map(|(pubkey, info)| PubkeyRegistration {
+            pubkey: pubkey.clone(),
+            pubkey_hash: compress_bls_pubkey(pubkey),
+            registered: info.is_some(),
+            authorized_operator: info.map(|info| info.authorizedOperator),
+        })
+        .collect::<Vec<_>>();
+
+    let registered = keys.iter().filter(|key| key.registered).count();
+    Ok(PubkeysReport { registered, unregistered: keys.len() - registered, keys })
+}
+
+/// Print the registration report as a human-readable table.
+fn print_report(report: &PubkeysReport) {
+    println!("{:<98}  {:<10}  OPERATOR", "VALIDATOR", "REGISTERED");
+    for key in &report.keys {
+        println!(
+            "{:<98}  {:<10}  {}",
+            format!("{:?}", key.pubkey),
+            key.registered,
+            key.authorized_operator.map(|operator| operator.to_string()).unwrap_or("-".into()),
+        );
+    }
+    println!("{} registered, {} not registered", report.registered, report.unregistered);
+}
+
+/// Combine the pubkeys of every source, sorted by pubkey bytes.
+///
+/// If `dedup` is set, the duplicated pubkeys are removed. Returns the pubkeys with the number
+/// of removed duplicates.
+pub fn combine_pubkeys(
+    sources: Vec<(&str, Vec<BlsPublicKey>)>,
+    dedup: bool,
+) -> (Vec<BlsPublicKey>, usize) {
+    let mut pubkeys = sources.into_iter().flat_map(|(_, pubkeys)| pubkeys).collect::<Vec<_>>();
+    pubkeys.sort_by_cached_key(|pubkey| pubkey.to_vec());
+
+    let total = pubkeys.len();
+    if dedup {
+        pubkeys.dedup();
+    }
+
+    let duplicates = total - pubkeys.len();
+    (pubkeys, duplicates)
+}
+
+/// Write the pubkeys to the given file in the given format.
+pub fn write_pubkeys(out: &str, pubkeys: &[BlsPublicKey], format: PubkeysFormat) -> Result<()> {
+    let lines = pubkeys.iter().map(|pubkey| format!("{pubkey:?}"));
+    match format {
+        PubkeysFormat::Json => write_to_file(out, &pubkeys),
+        PubkeysFormat::Csv => {
+            let rows = std::iter::once("pubkey".to_string()).chain(lines);
+            Ok(fs::write(out, rows.map(|row| row + "\n").collect::<String>())?)
+        }
+        PubkeysFormat::Txt => {
+            Ok(fs::write(out, lines.map(|line| line + "\n").collect::<String>())?)
+        }
+    }
+}
+
+/// Parse the provided hex-encoded public keys.
+pub fn list_from_public_keys(public_keys: &[String]) -> Result<Vec<BlsPublicKey>> {
+    public_keys.iter().map(|pubkey| parse_bls_public_key(pubkey.trim())).collect()
+}
+
 /// Derive public keys from the provided secret keys.
 pub fn list_from_local_keys(secret_keys: &[String]) -> Result<Vec<BlsPublicKey>> {
     let mut pubkeys = Vec::with_capacity(secret_keys.len());
@@ -90,3 +260,151 @@ pub fn list_from_dirk_accounts(accounts: &[Account]) -> Result<Vec<BlsPublicKey>
 
     Ok(pubkeys)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use alloy::{
+        primitives::{B256, U256},
+        providers::ext::AnvilApi,
+        signers::k256::ecdsa::SigningKey,
+    };
+
+    use crate::cli::{ValidatorsCommand, ValidatorsSubcommand};
+
+    use super::*;
+
+    const KEYSTORE_PATH: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/test_data/lighthouse/validators");
+
+    /// The pubkeys of the test keystores, as listed in the directory names.
+    fn keystore_pubkeys() -> Vec<String> {
+        let mut pubkeys = fs::read_dir(KEYSTORE_PATH)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        pubkeys.sort();
+        pubkeys
+    }
+
+    #[test]
+    fn test_dedup_overlapping_sources() -> Result<()> {
+        let expected = keystore_pubkeys();
+        let secret_key = "0x".to_string() + &"01".repeat(32);
+        let secret_pubkey = list_from_local_keys(&[secret_key])?;
+
+        // The first keystore pubkey is also given as a public key
+        let sources = vec![
+            ("local keystore", list_from_keystore(KEYSTORE_PATH)?),
+            ("public keys", list_from_public_keys(&expected[..1])?),
+            ("secret keys", secret_pubkey.clone()),
+        ];
+
+        let (pubkeys, duplicates) = combine_pubkeys(sources.clone(), true);
+        assert_eq!(duplicates, 1);
+        assert_eq!(pubkeys.len(), 3);
+        assert!(pubkeys.windows(2).all(|w| w[0].to_vec() < w[1].to_vec()));
+        assert!(pubkeys.contains(&secret_pubkey[0]));
+
+        let (pubkeys, duplicates) = combine_pubkeys(sources, false);
+        assert_eq!(duplicates, 0);
+        assert_eq!(pubkeys.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_pubkeys_formats() -> Result<()> {
+        let expected = keystore_pubkeys();
+        let (pubkeys, _) = combine_pubkeys(vec![("", list_from_keystore(KEYSTORE_PATH)?)], true);
+        let dir = tempfile::tempdir()?;
+
+        let out = dir.path().join("pubkeys.json");
+        write_pubkeys(out.to_str().unwrap(), &pubkeys, PubkeysFormat::Json)?;
+        let json = serde_json::from_str::<Vec<String>>(&fs::read_to_string(&out)?)?;
+        assert_eq!(json, expected);
+
+        let out = dir.path().join("pubkeys.csv");
+        write_pubkeys(out.to_str().unwrap(), &pubkeys, PubkeysFormat::Csv)?;
+        let csv = fs::read_to_string(&out)?;
+        assert_eq!(csv, format!("pubkey\n{}\n", expected.join("\n")));
+
+        let out = dir.path().join("pubkeys.txt");
+        write_pubkeys(out.to_str().unwrap(), &pubkeys, PubkeysFormat::Txt)?;
+        let txt = fs::read_to_string(&out)?;
+        assert_eq!(txt, format!("{}\n", expected.join("\n")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_pubkeys() -> Result<()> {
+        let provider = ProviderBuilder::new()
+            .on_anvil_with_config(|anvil| anvil.fork("https://holesky.drpc.org"));
+        let anvil_url: Url = provider.client().transport().url().parse()?;
+
+        let secret_key = SigningKey::random(&mut rand::thread_rng());
+        let account = Address::from_private_key(&secret_key);
+        provider.anvil_set_balance(account, U256::from(u64::MAX)).await?;
+
+        // Register the pubkeys of the test file only
+        let pubkeys_path = "./test_data/pubkeys.json";
+        let command = ValidatorsCommand {
+            subcommand: ValidatorsSubcommand::Register {
+                max_committed_gas_limit: 30_000_000,
+                admin_private_key: Some(B256::try_from(secret_key.to_bytes().as_slice())?),
+                authorized_operator: account,
+                pubkeys_path: pubkeys_path.parse()?,
+                rpc_url: anvil_url.clone(),
+                batch_size: Some(1),
+            },
+        };
+        command.run().await?;
+
+        let registered: Vec<String> = serde_json::from_str(&fs::read_to_string(pubkeys_path)?)?;
+        let unregistered = "0x".to_string() + &"01".repeat(32);
+        let (pubkeys, _) = combine_pubkeys(
+            vec![
+                ("public keys", list_from_public_keys(&registered)?),
+                ("secret keys", list_from_local_keys(&[unregistered])?),
+            ],
+            true,
+        );
+
+        let report = verify_pubkeys(&anvil_url, Some(Chain::Holesky), &pubkeys).await?;
+        assert_eq!(report.registered, registered.len());
+        assert_eq!(report.unregistered, 1);
+        for key in &report.keys {
+            let expected = registered.contains(&format!("{:?}", key.pubkey));
+            assert_eq!(key.registered, expected);
+            assert_eq!(key.authorized_operator, expected.then_some(account));
+        }
+
+        // The chain is resolved from the RPC when not given
+        assert_eq!(verify_pubkeys(&anvil_url, None, &pubkeys).await?, report);
+
+        let dir = tempfile::tempdir()?;
+        let mut command = PubkeysCommand {
+            out: dir.path().join("pubkeys.json").to_string_lossy().to_string(),
+            format: PubkeysFormat::Json,
+            dedup: false,
+            public_keys: registered,
+            secret_keys: vec!["0x".to_string() + &"01".repeat(32)],
+            keystore_path: None,
+            verify: true,
+            rpc_url: Some(anvil_url),
+            chain: Some(Chain::Holesky),
+            json: true,
+            allow_missing: false,
+            source: None,
+        };
+        let err = command.clone().run().await.unwrap_err();
+        assert!(err.to_string().contains("1 of 2 pubkeys are not registered"));
+
+        command.allow_missing = true;
+        command.run().await?;
+
+        Ok(())
+    }
+}
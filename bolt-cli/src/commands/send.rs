@@ -1,14 +1,22 @@
-use std::time::Duration;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    slice,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use alloy::{
     consensus::{
         constants::GWEI_TO_WEI, BlobTransactionSidecar, SidecarBuilder, SimpleCoder, Transaction,
+        TxEnvelope,
     },
-    eips::eip2718::Encodable2718,
+    eips::eip2718::{Decodable2718, Encodable2718},
     hex,
     network::{EthereumWallet, TransactionBuilder, TransactionBuilder4844},
-    primitives::{keccak256, Address, B256, U256},
-    providers::{ProviderBuilder, SendableTx},
+    primitives::{keccak256, Address, FixedBytes, Signature, B256, U256},
+    providers::{Provider, ProviderBuilder, SendableTx},
     rpc::types::TransactionRequest,
     signers::{local::PrivateKeySigner, Signer},
 };
@@ -17,97 +25,346 @@ use rand::Rng;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::cli::SendCommand;
 
+/// The negative testing suite of the devnet mode.
+mod negative;
+
+/// Discovery of the target slot from the proposer duties.
+mod slot;
+use slot::{discover_bolt_slots, SlotClock};
+
+/// Redundant submission of the request to multiple sidecars.
+mod broadcast;
+use broadcast::{broadcast, log_summary, sidecar_targets};
+
+/// Verification of the inclusion of committed transactions.
+mod inclusion;
+use inclusion::{await_inclusion, Guarantee, InclusionOutcome, DEFAULT_INCLUSION_TIMEOUT};
+
 /// Path to the lookahead endpoint on the Bolt RPC server.
 const BOLT_LOOKAHEAD_PATH: &str = "/api/v1/proposers/lookahead";
 
+/// The data carried by blob transactions when no `--blob-data` is provided.
+const DEFAULT_BLOB_DATA: &[u8] = b"Blobs are fun!";
+
+/// Error message of the sidecar when the blob budget of the target slot is full.
+const BLOB_BUDGET_FULL_ERROR: &str = "Too many EIP-4844 transactions";
+
+/// Error message of the sidecar when the commitment deadline of the target slot has passed.
+const DEADLINE_EXCEEDED_ERROR: &str = "Inclusion deadline exceeded";
+
+/// Error message of the sidecar when the request expired before it was committed.
+const REQUEST_EXPIRED_ERROR: &str = "Request expired";
+
+/// The version of the commitment format that includes the constraints signed for every
+/// transaction. Commitments without a version are legacy (v1) commitments.
+const COMMITMENT_FORMAT_V2: u64 = 2;
+
 impl SendCommand {
     /// Run the `send` command.
     pub async fn run(self) -> Result<()> {
         let wallet: PrivateKeySigner = self.private_key.parse().wrap_err("invalid private key")?;
 
-        if self.devnet {
+        if self.print_status {
+            self.print_sidecar_status().await?;
+        }
+
+        if self.devnet && self.negative_suite {
+            self.run_negative_suite(wallet).await
+        } else if self.devnet {
             self.send_devnet_transaction(wallet).await
         } else {
             self.send_transaction(wallet).await
         }
     }
 
+    /// Print the status of the sidecars targeted by the request. A sidecar failing to
+    /// answer is only reported, as the request may still go through.
+    async fn print_sidecar_status(&self) -> Result<()> {
+        let targets = if let Some(sidecar_url) = self.devnet_sidecar_url.clone() {
+            vec![sidecar_url]
+        } else if !self.sidecar_rpc_url.is_empty() {
+            self.sidecar_rpc_url.clone()
+        } else if let Some(sidecar_url) = self.override_bolt_sidecar_url.clone() {
+            vec![sidecar_url]
+        } else {
+            vec![self.bolt_rpc_url.join("/rpc")?]
+        };
+
+        for target_url in targets {
+            match fetch_sidecar_status(target_url.clone()).await {
+                Ok(status) => {
+                    println!("Status of {target_url}:\n{}", serde_json::to_string_pretty(&status)?)
+                }
+                Err(err) => warn!(%target_url, ?err, "Failed to fetch the status of the sidecar"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the blob sidecar to attach to the transactions, if blob transactions are requested.
+    fn blob_sidecar(&self) -> Result<Option<BlobTransactionSidecar>> {
+        let data = match &self.blob_data {
+            Some(data) => read_blob_data(data)?,
+            None if self.blob => DEFAULT_BLOB_DATA.to_vec(),
+            None => return Ok(None),
+        };
+
+        let sidecar = SidecarBuilder::<SimpleCoder>::from_slice(&data)
+            .build()
+            .wrap_err("failed to build blob sidecar")?;
+
+        let commitments = sidecar.commitments.iter().map(hex::encode_prefixed).collect::<Vec<_>>();
+        info!(blobs = sidecar.blobs.len(), ?commitments, "Built blob sidecar");
+
+        Ok(Some(sidecar))
+    }
+
     /// Send a transaction.
     async fn send_transaction(self, wallet: PrivateKeySigner) -> Result<()> {
-        let transaction_signer = EthereumWallet::from(wallet.clone());
-        let provider = ProviderBuilder::new()
-            .with_recommended_fillers()
-            .wallet(transaction_signer)
-            .on_http(self.bolt_rpc_url.join("/rpc")?);
-
-        // Fetch the lookahead info from the Bolt RPC server
-        let mut lookahead_url = self.bolt_rpc_url.join(BOLT_LOOKAHEAD_PATH)?;
+        if !self.sidecar_rpc_url.is_empty() {
+            return self.send_to_sidecars(wallet).await;
+        }
 
         // Note: it's possible for users to override the target sidecar URL
         // for testing and development purposes. In most cases, the sidecar will
         // reject a request for a slot that it is not responsible for.
-        let target_url = if let Some(sidecar_url) = self.override_bolt_sidecar_url {
-            // If using the override URL, we don't need to fetch the active proposers only.
-            // we will set the next slot as the target slot.
-            sidecar_url
-        } else {
-            // Filter out slots that are not active or in the past, to fetch the next
-            // active proposer slot.
-            lookahead_url.set_query(Some("activeOnly=true&futureOnly=true"));
-            self.bolt_rpc_url.join("/rpc")?
+        let target_url = match self.override_bolt_sidecar_url.clone() {
+            Some(sidecar_url) => sidecar_url,
+            None => self.bolt_rpc_url.join("/rpc")?,
         };
 
-        let lookahead_res = reqwest::get(lookahead_url).await?.json::<Vec<LookaheadSlot>>().await?;
-        if lookahead_res.is_empty() {
+        // The candidate target slots, in order of preference
+        let mut target_slots = self.target_slots().await?.into_iter();
+        let Some(mut target_slot) = target_slots.next() else {
             println!("no bolt proposer found in the lookahead, try again later 🥲");
             return Ok(());
+        };
+
+        let slot_clock = match &self.beacon_url {
+            Some(beacon_url) => Some(SlotClock::fetch(beacon_url).await?),
+            None => None,
+        };
+
+        match slot_clock {
+            Some(clock) => clock.log_target_slot(target_slot),
+            None => info!("Target slot: {}", target_slot),
         }
 
-        // Extract the next preconfirmer slot from the lookahead info
-        let target_slot = lookahead_res[0].slot;
-        info!("Target slot: {}", target_slot);
+        let (raw_txs, tx_hashes) = self.build_batch(&wallet).await?;
+        for (i, tx_hash) in tx_hashes.iter().enumerate() {
+            info!(index = i, %tx_hash, "Transaction in the batch");
+        }
 
-        // Send the transactions to the Bolt sidecar
-        let mut next_nonce = None;
-        for _ in 0..self.count {
+        // Send all the transactions to the Bolt sidecar in a single request
+        loop {
+            let expires_at = self.expires_at();
+            let response = send_rpc_request(
+                raw_txs.clone(),
+                tx_hashes.clone(),
+                target_slot,
+                self.top,
+                expires_at,
+                target_url.clone(),
+                &wallet,
+            )
+            .await?;
+
+            // Retry with the next bolt slot if the deadline of the target slot has passed
+            if is_deadline_exceeded(&response) {
+                warn!(target_slot, "Commitment deadline exceeded, retrying with the next slot");
+                target_slot = target_slots.next().wrap_err("no later bolt slot available")?;
+                match slot_clock {
+                    Some(clock) => clock.log_target_slot(target_slot),
+                    None => info!("Target slot: {}", target_slot),
+                }
+                continue;
+            }
+
+            let signature = commitment_signature(&response, target_slot)?;
+            let signer =
+                verify_commitment(&response, &tx_hashes, target_slot, self.top, expires_at)?;
+            info!(signature, %signer, "Received commitment");
+            break;
+        }
+
+        let committed = tx_hashes.into_iter().map(|tx_hash| (target_slot, tx_hash)).collect();
+        if self.await_inclusion {
+            let beacon_url = self.beacon_url.as_ref().wrap_err("missing beacon URL")?;
+            report_inclusion(beacon_url, committed, Guarantee::requested(self.top)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the inclusion request to the sidecars given with `--sidecar-rpc-url`.
+    ///
+    /// The request is sent to the sidecar with the earliest upcoming slot in the lookahead,
+    /// or to all of them with `--broadcast`, each for its own upcoming slot.
+    async fn send_to_sidecars(self, wallet: PrivateKeySigner) -> Result<()> {
+        let lookahead = if self.target_slot.is_some() {
+            Vec::new()
+        } else {
+            let mut lookahead_url = self.bolt_rpc_url.join(BOLT_LOOKAHEAD_PATH)?;
+            lookahead_url.set_query(Some("activeOnly=true&futureOnly=true"));
+            reqwest::get(lookahead_url).await?.json::<Vec<LookaheadSlot>>().await?
+        };
+
+        let targets = sidecar_targets(&self.sidecar_rpc_url, &lookahead, self.target_slot);
+        let (raw_txs, tx_hashes) = self.build_batch(&wallet).await?;
+
+        let results = if self.broadcast {
+            if self.generated_count() > 0 {
+                warn!(
+                    "Broadcasting generated transactions to several sidecars risks double \
+                     inclusion, use `--raw-tx` with fixed nonces to avoid it"
+                );
+            }
+
+            broadcast(&targets, &raw_txs, &tx_hashes, self.top, self.expires_at(), &wallet).await
+        } else {
+            let earliest = targets
+                .first()
+                .filter(|target| target.slot.is_some())
+                .wrap_err("no upcoming slot for any of the sidecars")?;
+
+            let expires_at = self.expires_at();
+            broadcast(
+                slice::from_ref(earliest),
+                &raw_txs,
+                &tx_hashes,
+                self.top,
+                expires_at,
+                &wallet,
+            )
+            .await
+        };
+
+        log_summary(&results);
+
+        let mut committed = Vec::new();
+        for result in results.iter().filter(|result| result.outcome.is_ok()) {
+            let slot = result.slot.wrap_err("missing target slot")?;
+            committed.extend(tx_hashes.iter().map(|tx_hash| (slot, *tx_hash)));
+        }
+
+        if committed.is_empty() {
+            bail!("none of the sidecars committed to the request");
+        }
+
+        if self.await_inclusion {
+            let beacon_url = self.beacon_url.as_ref().wrap_err("missing beacon URL")?;
+            report_inclusion(beacon_url, committed, Guarantee::requested(self.top)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the expiry of a request sent now with `--expires-in-ms`, as a unix timestamp in
+    /// milliseconds.
+    fn expires_at(&self) -> Option<u64> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        self.expires_in_ms.map(|expires_in| now as u64 + expires_in)
+    }
+
+    /// Returns the number of self-transfers to generate. Without raw transactions, a single
+    /// transfer is generated by default.
+    fn generated_count(&self) -> u32 {
+        self.count.unwrap_or(if self.raw_tx.is_empty() { 1 } else { 0 })
+    }
+
+    /// Build the transactions of the inclusion request, returning their hex-encoded raw bytes
+    /// and their hashes, in order.
+    ///
+    /// The raw transactions provided with `--raw-tx` come first, followed by the generated
+    /// self-transfers, whose nonces are assigned sequentially from the pending nonce of the
+    /// wallet fetched from the execution client.
+    async fn build_batch(&self, wallet: &PrivateKeySigner) -> Result<(Vec<String>, Vec<B256>)> {
+        let rpc_url = match &self.execution_url {
+            Some(execution_url) => execution_url.clone(),
+            None => self.bolt_rpc_url.join("/rpc")?,
+        };
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(wallet.clone()))
+            .on_http(rpc_url);
+
+        let mut raw_txs = Vec::new();
+        let mut tx_hashes = Vec::new();
+        let mut raw_nonces = Vec::new();
+        for raw_tx in &self.raw_tx {
+            let raw = hex::decode(raw_tx).wrap_err("invalid raw transaction hex")?;
+            let tx = TxEnvelope::decode_2718(&mut raw.as_slice())
+                .wrap_err("invalid raw transaction encoding")?;
+
+            raw_nonces.push((tx.recover_signer()?, tx.nonce()));
+            raw_txs.push(hex::encode_prefixed(&raw));
+            tx_hashes.push(*tx.tx_hash());
+        }
+
+        let count = self.generated_count();
+        if count == 0 {
+            return Ok((raw_txs, tx_hashes));
+        }
+
+        let start_nonce = provider.get_transaction_count(wallet.address()).pending().await?;
+        let nonces = generated_nonces(wallet.address(), start_nonce, count, &raw_nonces)?;
+
+        let blob_sidecar = self.blob_sidecar()?;
+        for nonce in nonces {
             // generate a simple self-transfer of ETH
-            let mut req = create_tx_request(wallet.address(), self.blob);
+            let mut req = create_tx_request(wallet.address(), blob_sidecar.clone());
             if let Some(max_fee) = self.max_fee {
                 req.set_max_fee_per_gas(max_fee * GWEI_TO_WEI as u128);
             }
 
             req.set_max_priority_fee_per_gas(self.priority_fee * GWEI_TO_WEI as u128);
+            req.set_nonce(nonce);
 
-            if let Some(next_nonce) = next_nonce {
-                req.set_nonce(next_nonce);
-            }
-
-            let (raw_tx, tx_hash) = match provider.fill(req).await.wrap_err("failed to fill")? {
+            match provider.fill(req).await.wrap_err("failed to fill")? {
                 SendableTx::Builder(_) => bail!("expected a raw transaction"),
                 SendableTx::Envelope(raw) => {
-                    next_nonce = Some(raw.nonce() + 1);
-                    (raw.encoded_2718(), *raw.tx_hash())
+                    raw_txs.push(hex::encode_prefixed(raw.encoded_2718()));
+                    tx_hashes.push(*raw.tx_hash());
                 }
-            };
+            }
+        }
 
-            send_rpc_request(
-                vec![hex::encode(&raw_tx)],
-                vec![tx_hash],
-                target_slot,
-                target_url.clone(),
-                &wallet,
-            )
-            .await?;
+        Ok((raw_txs, tx_hashes))
+    }
 
-            // Sleep for a bit to avoid spamming
-            tokio::time::sleep(Duration::from_millis(200)).await;
+    /// Returns the candidate target slots, in order of preference.
+    ///
+    /// An explicit `--target-slot` takes precedence over the slot discovery. Otherwise,
+    /// the future slots of bolt proposers are fetched from the bolt RPC lookahead, and
+    /// cross-referenced with the proposer duties of the beacon node if `--auto-slot` is set.
+    async fn target_slots(&self) -> Result<Vec<u64>> {
+        if let Some(target_slot) = self.target_slot {
+            return Ok(vec![target_slot]);
         }
 
-        Ok(())
+        // Fetch the lookahead info from the Bolt RPC server
+        let mut lookahead_url = self.bolt_rpc_url.join(BOLT_LOOKAHEAD_PATH)?;
+
+        // If using the override URL, we don't need to fetch the active proposers only.
+        // Otherwise, filter out slots that are not active or in the past, to fetch the
+        // next active proposer slots.
+        if self.override_bolt_sidecar_url.is_none() {
+            lookahead_url.set_query(Some("activeOnly=true&futureOnly=true"));
+        }
+
+        if self.auto_slot {
+            let beacon_url = self.beacon_url.as_ref().wrap_err("missing beacon URL")?;
+            return discover_bolt_slots(beacon_url, lookahead_url).await;
+        }
+
+        let lookahead = reqwest::get(lookahead_url).await?.json::<Vec<LookaheadSlot>>().await?;
+        Ok(lookahead.into_iter().map(|slot| slot.slot).collect())
     }
 
     /// Send a transaction on the Kurtosis devnet.
@@ -125,10 +382,13 @@ impl SendCommand {
         // Fetch the current slot from the devnet beacon node
         let slot = request_current_slot_number(&cl_url).await?;
 
+        let blob_sidecar = self.blob_sidecar()?;
+
         // Send the transactions to the devnet sidecar
         let mut next_nonce = None;
-        for _ in 0..self.count {
-            let mut req = create_tx_request(wallet.address(), self.blob);
+        let mut committed = Vec::new();
+        for _ in 0..self.count.unwrap_or(1) {
+            let mut req = create_tx_request(wallet.address(), blob_sidecar.clone());
             if let Some(next_nonce) = next_nonce {
                 req.set_nonce(next_nonce);
             }
@@ -141,23 +401,69 @@ impl SendCommand {
                 }
             };
 
-            send_rpc_request(
+            let expires_at = self.expires_at();
+            let response = send_rpc_request(
                 vec![hex::encode(&raw_tx)],
                 vec![tx_hash],
                 slot + 2,
+                self.top,
+                expires_at,
                 sidecar_url.clone(),
                 &wallet,
             )
             .await?;
 
+            let signature = commitment_signature(&response, slot + 2)?;
+            let signer = verify_commitment(&response, &[tx_hash], slot + 2, self.top, expires_at)?;
+            info!(signature, %signer, "Received commitment");
+            committed.push((slot + 2, tx_hash));
+
             // Sleep for a bit to avoid spamming
             tokio::time::sleep(Duration::from_millis(200)).await;
         }
 
+        if self.await_inclusion {
+            report_inclusion(&cl_url, committed, Guarantee::requested(self.top)).await?;
+        }
+
         Ok(())
     }
 }
 
+/// Wait for the target slots of the committed transactions, and report their inclusion
+/// against the guarantee of the commitments.
+///
+/// Fails if any commitment wasn't honored, i.e. a block was proposed in its target slot
+/// without the committed transactions, or with them at another position than promised.
+async fn report_inclusion(
+    beacon_url: &Url,
+    committed: Vec<(u64, B256)>,
+    guarantee: Guarantee,
+) -> Result<()> {
+    let mut by_slot = BTreeMap::<u64, Vec<B256>>::new();
+    for (slot, tx_hash) in committed {
+        by_slot.entry(slot).or_default().push(tx_hash);
+    }
+
+    let mut broken = 0;
+    for (slot, tx_hashes) in by_slot {
+        let report =
+            await_inclusion(beacon_url, slot, tx_hashes, guarantee, DEFAULT_INCLUSION_TIMEOUT)
+                .await?;
+        report.log();
+
+        if matches!(report.outcome(), InclusionOutcome::Broken | InclusionOutcome::Misplaced) {
+            broken += 1;
+        }
+    }
+
+    if broken > 0 {
+        bail!("{broken} commitment(s) were not honored");
+    }
+
+    Ok(())
+}
+
 async fn request_current_slot_number(beacon_url: &Url) -> Result<u64> {
     let res = reqwest::get(beacon_url.join("eth/v1/beacon/headers/head")?).await?;
     let res = res.json::<Value>().await?;
@@ -165,14 +471,44 @@ async fn request_current_slot_number(beacon_url: &Url) -> Result<u64> {
     Ok(slot.as_u64().unwrap_or(slot.as_str().wrap_err("invalid slot type")?.parse()?))
 }
 
-fn create_tx_request(to: Address, with_blob: bool) -> TransactionRequest {
+/// Returns the sequential nonces of `count` transactions generated by the sender, starting
+/// from the given nonce.
+///
+/// Fails if a raw transaction of the sender in the batch already uses one of these nonces.
+fn generated_nonces(
+    sender: Address,
+    start_nonce: u64,
+    count: u32,
+    raw_nonces: &[(Address, u64)],
+) -> Result<Vec<u64>> {
+    let nonces = (start_nonce..start_nonce + count as u64).collect::<Vec<_>>();
+    if let Some((_, nonce)) =
+        raw_nonces.iter().find(|(signer, nonce)| *signer == sender && nonces.contains(nonce))
+    {
+        bail!("raw transaction with nonce {nonce} conflicts with the generated transactions");
+    }
+
+    Ok(nonces)
+}
+
+/// Read the blob data from the given file path, or decode it as a hex string otherwise.
+fn read_blob_data(data: &str) -> Result<Vec<u8>> {
+    if Path::new(data).is_file() {
+        return fs::read(data).wrap_err_with(|| format!("failed to read blob data file {data}"));
+    }
+
+    hex::decode(data).wrap_err("blob data is neither a file nor a valid hex string")
+}
+
+fn create_tx_request(
+    to: Address,
+    blob_sidecar: Option<BlobTransactionSidecar>,
+) -> TransactionRequest {
     let mut req = TransactionRequest::default();
     req = req.with_to(to).with_value(U256::from(100_000));
     req = req.with_input(rand::thread_rng().gen::<[u8; 32]>());
 
-    if with_blob {
-        let sidecar = SidecarBuilder::<SimpleCoder>::from_slice(b"Blobs are fun!");
-        let sidecar: BlobTransactionSidecar = sidecar.build().unwrap();
+    if let Some(sidecar) = blob_sidecar {
         req = req.with_blob_sidecar(sidecar);
         req = req.with_max_fee_per_blob_gas(3_000_000);
     }
@@ -180,23 +516,32 @@ fn create_tx_request(to: Address, with_blob: bool) -> TransactionRequest {
     req
 }
 
+/// Send a signed inclusion request to the sidecar, returning the JSON-RPC response. With
+/// `top`, the transactions are requested at the very top of the block. With `expires_at`,
+/// the sidecar mustn't commit to the request past that unix timestamp in milliseconds.
 async fn send_rpc_request(
     txs_rlp: Vec<String>,
     tx_hashes: Vec<B256>,
     target_slot: u64,
+    top: bool,
+    expires_at: Option<u64>,
     target_sidecar_url: Url,
     wallet: &PrivateKeySigner,
-) -> Result<()> {
-    let request = prepare_rpc_request(
-        "bolt_requestInclusion",
-        serde_json::json!({
-            "slot": target_slot,
-            "txs": txs_rlp,
-        }),
-    );
+) -> Result<Value> {
+    let mut params = serde_json::json!({
+        "slot": target_slot,
+        "txs": txs_rlp,
+    });
+    if top {
+        params["top"] = Value::Bool(true);
+    }
+    if let Some(expires_at) = expires_at {
+        params["expires_at"] = Value::from(expires_at);
+    }
+    let request = prepare_rpc_request("bolt_requestInclusion", params);
 
-    info!(?tx_hashes, target_slot, %target_sidecar_url);
-    let signature = sign_request(tx_hashes, target_slot, wallet).await?;
+    info!(?tx_hashes, target_slot, top, ?expires_at, %target_sidecar_url);
+    let signature = sign_request(tx_hashes, target_slot, top, expires_at, wallet).await?;
 
     let response = reqwest::Client::new()
         .post(target_sidecar_url)
@@ -207,26 +552,180 @@ async fn send_rpc_request(
         .await
         .wrap_err("failed to send POST request")?;
 
-    let response = response.text().await?;
+    Ok(response.json::<Value>().await?)
+}
 
-    // strip out long series of zeros in the response (to avoid spamming blob contents)
-    let response = response.replace(&"0".repeat(32), ".").replace(&".".repeat(4), "");
-    info!("Response: {:?}", response);
-    Ok(())
+/// Fetch the public status of a sidecar with the `bolt_status` method.
+async fn fetch_sidecar_status(target_sidecar_url: Url) -> Result<Value> {
+    let request = serde_json::json!({
+        "id": "1",
+        "jsonrpc": "2.0",
+        "method": "bolt_status",
+        "params": [],
+    });
+
+    let response = reqwest::Client::new()
+        .post(target_sidecar_url)
+        .json(&request)
+        .send()
+        .await
+        .wrap_err("failed to send POST request")?
+        .json::<Value>()
+        .await?;
+
+    if let Some(error) = response.get("error") {
+        bail!("the sidecar rejected the status request: {error}");
+    }
+
+    response.get("result").cloned().wrap_err("missing result in the status response")
+}
+
+/// Returns true if the JSON-RPC response rejects the request because the commitment
+/// deadline of the target slot has passed.
+fn is_deadline_exceeded(response: &Value) -> bool {
+    response
+        .pointer("/error/message")
+        .and_then(Value::as_str)
+        .is_some_and(|message| message.contains(DEADLINE_EXCEEDED_ERROR))
+}
+
+/// Returns the commitment signature from the JSON-RPC response of an inclusion request,
+/// or an error describing why the sidecar rejected it.
+fn commitment_signature(response: &Value, target_slot: u64) -> Result<&str> {
+    if let Some(error) = response.get("error") {
+        let message = error.get("message").and_then(Value::as_str).unwrap_or_default();
+        if message.contains(BLOB_BUDGET_FULL_ERROR) {
+            bail!("the blob budget of slot {target_slot} is full, try again for a later slot");
+        }
+        if message.contains(REQUEST_EXPIRED_ERROR) {
+            bail!("the request expired before the sidecar committed to it: {message}");
+        }
+
+        bail!("the sidecar rejected the request: {error}");
+    }
+
+    response
+        .pointer("/result/signature")
+        .and_then(Value::as_str)
+        .wrap_err("missing commitment signature in the response")
+}
+
+/// The constraint signed for a committed transaction, included in v2 commitments.
+#[derive(Debug, Clone, Deserialize)]
+struct CommittedConstraint {
+    slot: u64,
+    pubkey: FixedBytes<48>,
+    digest: B256,
+}
+
+/// Verify the commitment in the JSON-RPC response of an inclusion request, logging the
+/// constraints signed for every transaction. Returns the address of the commitment signer.
+///
+/// The signature of legacy commitments covers the request only, while v2 commitments also
+/// cover the digest, BLS public key and slot of the constraint of every transaction, and
+/// the guarantee of top-of-block commitments:
+/// keccak256(version | request_digest | constraint_1 | ... | constraint_n [| 0x01])
+/// where constraint_i = bytes(digest) | bytes(pubkey) | le_bytes(slot)
+///
+/// Fails if the guarantee of the commitment isn't the one requested with `top`.
+fn verify_commitment(
+    response: &Value,
+    tx_hashes: &[B256],
+    target_slot: u64,
+    top: bool,
+    expires_at: Option<u64>,
+) -> Result<Address> {
+    let commitment = response.get("result").wrap_err("missing commitment in the response")?;
+    let signature = commitment
+        .get("signature")
+        .and_then(Value::as_str)
+        .wrap_err("missing commitment signature in the response")?;
+    let signature = Signature::from_str(signature).wrap_err("invalid commitment signature")?;
+
+    let guarantee = match commitment.get("guarantee") {
+        Some(guarantee) => serde_json::from_value::<Guarantee>(guarantee.clone())
+            .wrap_err("invalid commitment guarantee")?,
+        None => Guarantee::Inclusion,
+    };
+    if guarantee != Guarantee::requested(top) {
+        bail!("the commitment guarantees {guarantee}, not {}", Guarantee::requested(top));
+    }
+    info!(%guarantee, "Commitment guarantee");
+
+    let request_digest = request_digest(tx_hashes, target_slot, top, expires_at);
+    let digest = match commitment.get("version").and_then(Value::as_u64) {
+        None => request_digest,
+        Some(COMMITMENT_FORMAT_V2) => {
+            let constraints = commitment.get("constraints").cloned().unwrap_or_default();
+            let constraints = serde_json::from_value::<Vec<CommittedConstraint>>(constraints)
+                .wrap_err("invalid constraints in the commitment")?;
+            if constraints.len() != tx_hashes.len() {
+                bail!("expected {} constraints, got {}", tx_hashes.len(), constraints.len());
+            }
+
+            let mut data = vec![COMMITMENT_FORMAT_V2 as u8];
+            data.extend_from_slice(request_digest.as_slice());
+            for (tx_hash, constraint) in tx_hashes.iter().zip(&constraints) {
+                if constraint.slot != target_slot {
+                    bail!("constraint of {tx_hash} targets slot {}", constraint.slot);
+                }
+
+                info!(
+                    %tx_hash,
+                    slot = constraint.slot,
+                    pubkey = %constraint.pubkey,
+                    digest = %constraint.digest,
+                    "Signed constraint"
+                );
+
+                data.extend_from_slice(constraint.digest.as_slice());
+                data.extend_from_slice(constraint.pubkey.as_slice());
+                data.extend_from_slice(&constraint.slot.to_le_bytes());
+            }
+
+            if guarantee == Guarantee::TopOfBlock {
+                data.push(1);
+            }
+
+            keccak256(data)
+        }
+        Some(version) => bail!("unsupported commitment version {version}"),
+    };
+
+    signature.recover_address_from_prehash(&digest).wrap_err("invalid commitment signature")
+}
+
+/// Returns the digest of an inclusion request:
+/// keccak256(bytes(tx_hash1) | ... | le_bytes(target_slot) [| 0x01] [| le_bytes(expires_at)])
+/// where the `0x01` byte is only appended to top-of-block requests, and the expiry only to
+/// the requests that have one.
+fn request_digest(
+    tx_hashes: &[B256],
+    target_slot: u64,
+    top: bool,
+    expires_at: Option<u64>,
+) -> B256 {
+    let mut data = Vec::new();
+    let hashes = tx_hashes.iter().map(|hash| hash.as_slice()).collect::<Vec<_>>().concat();
+    data.extend_from_slice(&hashes);
+    data.extend_from_slice(target_slot.to_le_bytes().as_slice());
+    if top {
+        data.push(1);
+    }
+    if let Some(expires_at) = expires_at {
+        data.extend_from_slice(&expires_at.to_le_bytes());
+    }
+    keccak256(data)
 }
 
 async fn sign_request(
     tx_hashes: Vec<B256>,
     target_slot: u64,
+    top: bool,
+    expires_at: Option<u64>,
     wallet: &PrivateKeySigner,
 ) -> eyre::Result<String> {
-    let digest = {
-        let mut data = Vec::new();
-        let hashes = tx_hashes.iter().map(|hash| hash.as_slice()).collect::<Vec<_>>().concat();
-        data.extend_from_slice(&hashes);
-        data.extend_from_slice(target_slot.to_le_bytes().as_slice());
-        keccak256(data)
-    };
+    let digest = request_digest(&tx_hashes, target_slot, top, expires_at);
 
     let signature = hex::encode_prefixed(wallet.sign_hash(&digest).await?.as_bytes());
 
@@ -255,3 +754,281 @@ pub struct LookaheadSlot {
     /// Optional URL of the Bolt sidecar associated with the proposer
     pub sidecar_url: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::{consensus::TxEip4844Variant, primitives::b256};
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_blob_request_body() -> Result<()> {
+        let wallet = PrivateKeySigner::random();
+        let signer = EthereumWallet::from(wallet.clone());
+
+        let data = read_blob_data("0xdeadbeef")?;
+        let sidecar = SidecarBuilder::<SimpleCoder>::from_slice(&data).build()?;
+
+        let envelope = create_tx_request(wallet.address(), Some(sidecar.clone()))
+            .with_chain_id(17000)
+            .with_nonce(0)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(10 * GWEI_TO_WEI as u128)
+            .with_max_priority_fee_per_gas(GWEI_TO_WEI as u128)
+            .build(&signer)
+            .await?;
+
+        let body = prepare_rpc_request(
+            "bolt_requestInclusion",
+            serde_json::json!({ "slot": 42, "txs": [hex::encode(envelope.encoded_2718())] }),
+        );
+
+        let raw = body.pointer("/params/0/txs/0").and_then(Value::as_str).unwrap();
+        let decoded = TxEnvelope::decode_2718(&mut hex::decode(raw)?.as_slice())?;
+
+        let TxEnvelope::Eip4844(signed) = decoded else { bail!("expected a blob transaction") };
+        let TxEip4844Variant::TxEip4844WithSidecar(tx) = signed.tx() else {
+            bail!("expected a blob transaction with sidecar")
+        };
+
+        assert_eq!(tx.sidecar, sidecar);
+        assert_eq!(body.pointer("/params/0/slot"), Some(&serde_json::json!(42)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_batch_request() -> Result<()> {
+        let wallet = PrivateKeySigner::random();
+        let signer = EthereumWallet::from(wallet.clone());
+
+        let nonces = generated_nonces(wallet.address(), 5, 3, &[])?;
+        assert_eq!(nonces, vec![5, 6, 7]);
+
+        let mut raw_txs = Vec::new();
+        let mut tx_hashes = Vec::new();
+        for nonce in nonces {
+            let envelope = create_tx_request(wallet.address(), None)
+                .with_chain_id(17000)
+                .with_nonce(nonce)
+                .with_gas_limit(21_000)
+                .with_max_fee_per_gas(10 * GWEI_TO_WEI as u128)
+                .with_max_priority_fee_per_gas(GWEI_TO_WEI as u128)
+                .build(&signer)
+                .await?;
+
+            raw_txs.push(hex::encode_prefixed(envelope.encoded_2718()));
+            tx_hashes.push(*envelope.tx_hash());
+        }
+
+        let sidecar = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "1",
+                "result": { "slot": 42, "txs": [], "signature": "0xabcd" }
+            })))
+            .expect(1)
+            .mount(&sidecar)
+            .await;
+
+        let url = Url::parse(&sidecar.uri())?;
+        let expires_at = Some(1_700_000_000_000);
+        let response =
+            send_rpc_request(raw_txs, tx_hashes.clone(), 42, false, expires_at, url, &wallet)
+                .await?;
+        assert_eq!(commitment_signature(&response, 42)?, "0xabcd");
+
+        // All the transactions are sent in a single JSON-RPC call
+        let requests = sidecar.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+
+        // The expiry is sent along with the request, and covered by its signature
+        let body = serde_json::from_slice::<Value>(&requests[0].body)?;
+        assert_eq!(body.pointer("/params/0/expires_at"), Some(&Value::from(1_700_000_000_000u64)));
+        let header = requests[0].headers.get("x-bolt-signature").unwrap().to_str()?;
+        let (_, signature) = header.split_once(':').unwrap();
+        let digest = request_digest(&tx_hashes, 42, false, expires_at);
+        assert_ne!(digest, request_digest(&tx_hashes, 42, false, None));
+        let signer = Signature::from_str(signature)?.recover_address_from_prehash(&digest)?;
+        assert_eq!(signer, wallet.address());
+
+        let txs = body.pointer("/params/0/txs").and_then(Value::as_array).unwrap();
+        let nonces = txs
+            .iter()
+            .map(|tx| {
+                let raw = hex::decode(tx.as_str().unwrap()).unwrap();
+                TxEnvelope::decode_2718(&mut raw.as_slice()).unwrap().nonce()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, vec![5, 6, 7]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sidecar_status() -> Result<()> {
+        let status = serde_json::json!({ "chain": "holesky", "chain_id": 17000, "validators": [] });
+
+        let sidecar = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "1",
+                "result": status
+            })))
+            .expect(1)
+            .mount(&sidecar)
+            .await;
+
+        let url = Url::parse(&sidecar.uri())?;
+        assert_eq!(fetch_sidecar_status(url).await?, status);
+
+        let requests = sidecar.received_requests().await.unwrap();
+        let body = serde_json::from_slice::<Value>(&requests[0].body)?;
+        assert_eq!(body["method"], "bolt_status");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generated_nonces_conflict() {
+        let sender = Address::repeat_byte(1);
+        let other = Address::repeat_byte(2);
+
+        assert!(generated_nonces(sender, 5, 3, &[(sender, 7)]).is_err());
+        assert!(generated_nonces(sender, 5, 3, &[(sender, 4), (other, 6)]).is_ok());
+    }
+
+    #[test]
+    fn test_commitment_signature() {
+        let accepted = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "result": { "slot": 42, "txs": [], "signature": "0xabcd" }
+        });
+        assert_eq!(commitment_signature(&accepted, 42).unwrap(), "0xabcd");
+
+        let blob_budget_full = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "error": { "code": -32006, "message": "Too many EIP-4844 transactions in target block" }
+        });
+        let err = commitment_signature(&blob_budget_full, 42).unwrap_err();
+        assert!(err.to_string().contains("blob budget of slot 42 is full"));
+        assert!(!is_deadline_exceeded(&blob_budget_full));
+
+        let deadline_exceeded = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "error": { "code": -32006, "message": "Inclusion deadline exceeded" }
+        });
+        assert!(is_deadline_exceeded(&deadline_exceeded));
+        assert!(!is_deadline_exceeded(&accepted));
+    }
+
+    #[tokio::test]
+    async fn test_verify_commitment() -> Result<()> {
+        let wallet = PrivateKeySigner::random();
+        let tx_hashes =
+            vec![b256!("ea49f475608be9311fd787f4b30425464a48508b0890b27802d5501f050c6c1b")];
+        let constraint = serde_json::json!({
+            "slot": 633067,
+            "pubkey": hex::encode_prefixed([0xaa; 48]),
+            "digest": B256::repeat_byte(0x11),
+        });
+
+        // The signed digest of the same fixture in the sidecar
+        let digest = b256!("a4c655ae475f2a8077fbb50c3600825214e1f9cfe96a2fe29e52c07b1dd74a9d");
+        let signature = hex::encode_prefixed(wallet.sign_hash(&digest).await?.as_bytes());
+        let mut response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "result": {
+                "version": 2,
+                "slot": 633067,
+                "txs": [],
+                "signature": signature,
+                "constraints": [constraint],
+            }
+        });
+        assert_eq!(
+            verify_commitment(&response, &tx_hashes, 633067, false, None)?,
+            wallet.address()
+        );
+
+        // Constraints for another slot are rejected
+        response["result"]["constraints"][0]["slot"] = serde_json::json!(633068);
+        assert!(verify_commitment(&response, &tx_hashes, 633067, false, None).is_err());
+
+        // And so are missing constraints
+        response["result"]["constraints"] = serde_json::json!([]);
+        assert!(verify_commitment(&response, &tx_hashes, 633067, false, None).is_err());
+
+        // Legacy commitments only sign the request digest
+        let digest = b256!("52ecc7832625c3d107aaba5b55d4509b48cd9f4f7ce375d6696d09bbf3310525");
+        let signature = hex::encode_prefixed(wallet.sign_hash(&digest).await?.as_bytes());
+        let legacy = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "result": { "slot": 633067, "txs": [], "signature": signature }
+        });
+        assert_eq!(verify_commitment(&legacy, &tx_hashes, 633067, false, None)?, wallet.address());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_top_of_block_commitment() -> Result<()> {
+        let wallet = PrivateKeySigner::random();
+        let tx_hashes =
+            vec![b256!("ea49f475608be9311fd787f4b30425464a48508b0890b27802d5501f050c6c1b")];
+        let constraint = CommittedConstraint {
+            slot: 633067,
+            pubkey: FixedBytes::repeat_byte(0xaa),
+            digest: B256::repeat_byte(0x11),
+        };
+
+        // The top-of-block flag is appended to both the request digest and the v2 payload
+        let digest = request_digest(&tx_hashes, 633067, true, None);
+        let data = [tx_hashes[0].as_slice(), &633067u64.to_le_bytes(), &[1]].concat();
+        assert_eq!(digest, keccak256(data));
+
+        let data = [
+            &[COMMITMENT_FORMAT_V2 as u8][..],
+            digest.as_slice(),
+            constraint.digest.as_slice(),
+            constraint.pubkey.as_slice(),
+            &constraint.slot.to_le_bytes(),
+            &[1],
+        ]
+        .concat();
+        let signature = wallet.sign_hash(&keccak256(data)).await?;
+        let mut response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "result": {
+                "version": 2,
+                "slot": 633067,
+                "top": true,
+                "txs": [],
+                "signature": hex::encode_prefixed(signature.as_bytes()),
+                "guarantee": "top_of_block",
+                "constraints": [{
+                    "slot": constraint.slot,
+                    "pubkey": constraint.pubkey,
+                    "digest": constraint.digest,
+                }],
+            }
+        });
+        assert_eq!(verify_commitment(&response, &tx_hashes, 633067, true, None)?, wallet.address());
+
+        // A commitment that doesn't guarantee the requested placement is rejected
+        assert!(verify_commitment(&response, &tx_hashes, 633067, false, None).is_err());
+        response["result"]["guarantee"] = serde_json::json!("inclusion");
+        assert!(verify_commitment(&response, &tx_hashes, 633067, true, None).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use alloy::{primitives::B256, signers::local::PrivateKeySigner};
+use reqwest::Url;
+use tracing::{info, warn};
+
+use super::{commitment_signature, send_rpc_request, LookaheadSlot};
+
+/// A sidecar to send the inclusion request to, with its target slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidecarTarget {
+    /// The RPC URL of the sidecar.
+    pub url: Url,
+    /// The earliest upcoming slot of the sidecar, if any.
+    pub slot: Option<u64>,
+}
+
+/// The result of sending the inclusion request to a sidecar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidecarResult {
+    /// The RPC URL of the sidecar.
+    pub url: Url,
+    /// The target slot of the request, if any.
+    pub slot: Option<u64>,
+    /// The commitment signature, or why the request failed.
+    pub outcome: Result<String, String>,
+}
+
+/// Returns the earliest upcoming slot of each sidecar in the lookahead, sorted by slot.
+/// Sidecars without upcoming slots come last.
+///
+/// If a target slot is given, it is used for every sidecar instead.
+pub fn sidecar_targets(
+    sidecars: &[Url],
+    lookahead: &[LookaheadSlot],
+    target_slot: Option<u64>,
+) -> Vec<SidecarTarget> {
+    let mut targets = sidecars
+        .iter()
+        .map(|url| {
+            let slot = target_slot.or_else(|| {
+                lookahead
+                    .iter()
+                    .filter(|slot| {
+                        slot.sidecar_url.as_deref().and_then(|s| Url::parse(s).ok()).as_ref() ==
+                            Some(url)
+                    })
+                    .map(|slot| slot.slot)
+                    .min()
+            });
+
+            SidecarTarget { url: url.clone(), slot }
+        })
+        .collect::<Vec<_>>();
+
+    // `None` sorts before `Some`, so sort on the missing slots first
+    targets.sort_by_key(|target| (target.slot.is_none(), target.slot));
+    targets
+}
+
+/// Send the inclusion request to every sidecar with an upcoming slot, each for its own
+/// target slot. A failure of a sidecar doesn't prevent sending to the others.
+pub async fn broadcast(
+    targets: &[SidecarTarget],
+    raw_txs: &[String],
+    tx_hashes: &[B256],
+    top: bool,
+    expires_at: Option<u64>,
+    wallet: &PrivateKeySigner,
+) -> Vec<SidecarResult> {
+    let mut results = Vec::with_capacity(targets.len());
+    for target in targets {
+        let outcome = match target.slot {
+            Some(slot) => {
+                send_to_sidecar(&target.url, slot, raw_txs, tx_hashes, top, expires_at, wallet)
+                    .await
+            }
+            None => Err("no upcoming slot in the lookahead".to_string()),
+        };
+
+        results.push(SidecarResult { url: target.url.clone(), slot: target.slot, outcome });
+    }
+
+    results
+}
+
+/// Send the inclusion request to the sidecar, returning the commitment signature.
+pub async fn send_to_sidecar(
+    url: &Url,
+    slot: u64,
+    raw_txs: &[String],
+    tx_hashes: &[B256],
+    top: bool,
+    expires_at: Option<u64>,
+    wallet: &PrivateKeySigner,
+) -> Result<String, String> {
+    let (raw_txs, tx_hashes) = (raw_txs.to_vec(), tx_hashes.to_vec());
+    let response = send_rpc_request(raw_txs, tx_hashes, slot, top, expires_at, url.clone(), wallet)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    commitment_signature(&response, slot).map(str::to_string).map_err(|err| err.to_string())
+}
+
+/// Log the results of every sidecar.
+pub fn log_summary(results: &[SidecarResult]) {
+    for result in results {
+        let (url, slot) = (result.url.as_str(), result.slot);
+        match &result.outcome {
+            Ok(signature) => info!(url, ?slot, signature, "Received commitment"),
+            Err(error) => warn!(url, ?slot, error, "Request failed"),
+        }
+    }
+
+    let committed = results.iter().filter(|result| result.outcome.is_ok()).count();
+    info!("Received {committed} commitment(s) from {} sidecar(s)", results.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::constants::GWEI_TO_WEI,
+        eips::eip2718::Encodable2718,
+        network::{EthereumWallet, TransactionBuilder},
+        primitives::U256,
+        rpc::types::TransactionRequest,
+    };
+    use serde_json::json;
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    /// Start a mock sidecar accepting every request, or rejecting them if `reject` is set.
+    async fn mock_sidecar(reject: bool) -> MockServer {
+        let body = if reject {
+            json!({
+                "jsonrpc": "2.0",
+                "id": "1",
+                "error": { "code": -32006, "message": "Validator not found in the slot" }
+            })
+        } else {
+            json!({
+                "jsonrpc": "2.0",
+                "id": "1",
+                "result": { "slot": 42, "txs": [], "signature": "0xabcd" }
+            })
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(if reject { 400 } else { 200 }).set_body_json(body))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    fn lookahead_slot(slot: u64, sidecar_url: &str) -> LookaheadSlot {
+        LookaheadSlot { slot, validator_index: slot, sidecar_url: Some(sidecar_url.to_string()) }
+    }
+
+    #[test]
+    fn test_sidecar_targets() {
+        let a = Url::parse("http://sidecar-a:8017").unwrap();
+        let b = Url::parse("http://sidecar-b:8017").unwrap();
+        let c = Url::parse("http://sidecar-c:8017").unwrap();
+
+        let lookahead = vec![
+            lookahead_slot(50, "http://sidecar-a:8017"),
+            lookahead_slot(45, "http://sidecar-b:8017"),
+            lookahead_slot(44, "http://sidecar-a:8017"),
+        ];
+
+        let targets = sidecar_targets(&[a.clone(), b.clone(), c.clone()], &lookahead, None);
+        assert_eq!(
+            targets,
+            vec![
+                SidecarTarget { url: a, slot: Some(44) },
+                SidecarTarget { url: b, slot: Some(45) },
+                SidecarTarget { url: c.clone(), slot: None },
+            ]
+        );
+
+        // An explicit target slot is used for every sidecar
+        let targets = sidecar_targets(&[c.clone()], &lookahead, Some(60));
+        assert_eq!(targets, vec![SidecarTarget { url: c, slot: Some(60) }]);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_with_rejection() {
+        let accepting = mock_sidecar(false).await;
+        let rejecting = mock_sidecar(true).await;
+
+        let wallet = PrivateKeySigner::random();
+        let envelope = TransactionRequest::default()
+            .with_to(wallet.address())
+            .with_value(U256::from(100_000))
+            .with_chain_id(17000)
+            .with_nonce(0)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(10 * GWEI_TO_WEI as u128)
+            .with_max_priority_fee_per_gas(GWEI_TO_WEI as u128)
+            .build(&EthereumWallet::from(wallet.clone()))
+            .await
+            .unwrap();
+
+        let raw_txs = vec![alloy::hex::encode_prefixed(envelope.encoded_2718())];
+        let tx_hashes = vec![*envelope.tx_hash()];
+
+        let targets = vec![
+            SidecarTarget { url: Url::parse(&rejecting.uri()).unwrap(), slot: Some(41) },
+            SidecarTarget { url: Url::parse(&accepting.uri()).unwrap(), slot: Some(42) },
+        ];
+
+        let results = broadcast(&targets, &raw_txs, &tx_hashes, false, None, &wallet).await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].outcome.as_ref().unwrap_err().contains("Validator not found"));
+        assert_eq!(results[1].outcome, Ok("0xabcd".to_string()));
+    }
+}
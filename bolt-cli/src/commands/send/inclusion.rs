@@ -0,0 +1,363 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    consensus::{Transaction, TxEnvelope},
+    eips::eip2718::Decodable2718,
+    hex,
+    primitives::{keccak256, B256},
+};
+use eyre::{ContextCompat, Result};
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use super::{request_current_slot_number, slot::parse_number};
+
+/// Interval between two polls of the beacon node while waiting for a block.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum time to wait for the block of the target slot.
+pub const DEFAULT_INCLUSION_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// What a commitment promises about the placement of the committed transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Guarantee {
+    /// The transactions are included in the block, at any position and in any order.
+    #[default]
+    Inclusion,
+    /// The transactions are the very first ones of the block, in the order of the request.
+    TopOfBlock,
+}
+
+impl Guarantee {
+    /// Returns the guarantee of a commitment to a request, given whether the request asked
+    /// for the top of the block.
+    pub fn requested(top: bool) -> Self {
+        if top {
+            Self::TopOfBlock
+        } else {
+            Self::Inclusion
+        }
+    }
+}
+
+impl fmt::Display for Guarantee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inclusion => write!(f, "inclusion"),
+            Self::TopOfBlock => write!(f, "top of block"),
+        }
+    }
+}
+
+/// The outcome of a commitment, once its target slot has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InclusionOutcome {
+    /// Every committed transaction was included in the block of the target slot, at the
+    /// promised position.
+    Honored,
+    /// Every committed transaction was included in the block of the target slot, but not at
+    /// the promised position.
+    Misplaced,
+    /// The block of the target slot doesn't include every committed transaction.
+    Broken,
+    /// No block was proposed in the target slot, or it wasn't seen in time.
+    SlotMissed,
+}
+
+impl fmt::Display for InclusionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Honored => write!(f, "honored"),
+            Self::Misplaced => write!(f, "included at the wrong position"),
+            Self::Broken => write!(f, "broken"),
+            Self::SlotMissed => write!(f, "slot missed"),
+        }
+    }
+}
+
+/// The inclusion of a committed transaction in the block of the target slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxInclusion {
+    /// The hash of the transaction.
+    pub tx_hash: B256,
+    /// The position of the transaction in the block, if included.
+    pub position: Option<usize>,
+    /// The effective priority fee per gas paid by the transaction, if included.
+    pub priority_fee: Option<u128>,
+}
+
+/// A report of the inclusion of committed transactions in the block of their target slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionReport {
+    /// The target slot.
+    pub slot: u64,
+    /// What the commitment promises about the placement of the transactions.
+    pub guarantee: Guarantee,
+    /// The hash of the execution block of the target slot, if any.
+    pub block_hash: Option<B256>,
+    /// The inclusion of every committed transaction, in the order of the request.
+    pub txs: Vec<TxInclusion>,
+}
+
+impl InclusionReport {
+    /// Returns the outcome of the commitment.
+    pub fn outcome(&self) -> InclusionOutcome {
+        if self.block_hash.is_none() {
+            InclusionOutcome::SlotMissed
+        } else if self.txs.iter().any(|tx| tx.position.is_none()) {
+            InclusionOutcome::Broken
+        } else if !self.is_placement_honored() {
+            InclusionOutcome::Misplaced
+        } else {
+            InclusionOutcome::Honored
+        }
+    }
+
+    /// Returns true if the included transactions are placed as promised by the guarantee.
+    fn is_placement_honored(&self) -> bool {
+        match self.guarantee {
+            Guarantee::Inclusion => true,
+            Guarantee::TopOfBlock => {
+                self.txs.iter().enumerate().all(|(index, tx)| tx.position == Some(index))
+            }
+        }
+    }
+
+    /// Log the report.
+    pub fn log(&self) {
+        for tx in &self.txs {
+            let (slot, tx_hash, priority_fee) = (self.slot, tx.tx_hash, tx.priority_fee);
+            match tx.position {
+                Some(position) => {
+                    info!(slot, %tx_hash, position, ?priority_fee, "Transaction included")
+                }
+                None => warn!(slot, %tx_hash, "Transaction not included"),
+            }
+        }
+
+        let block_hash = self.block_hash.map(|hash| hash.to_string()).unwrap_or_default();
+        let (slot, guarantee, outcome) = (self.slot, self.guarantee, self.outcome());
+        info!(slot, block_hash, %guarantee, %outcome, "Inclusion report");
+    }
+}
+
+/// Wait for the block of the given slot, and report the inclusion of the given transactions
+/// against the guarantee of their commitment.
+///
+/// The slot is reported as missed if the beacon node has no block for it once its head has
+/// passed it, or if no block is seen before the timeout.
+pub async fn await_inclusion(
+    beacon_url: &Url,
+    slot: u64,
+    tx_hashes: Vec<B256>,
+    guarantee: Guarantee,
+    timeout: Duration,
+) -> Result<InclusionReport> {
+    info!(slot, %guarantee, "Waiting for the block of the target slot");
+
+    let start = Instant::now();
+    loop {
+        if let Some(block) = fetch_block(beacon_url, slot).await? {
+            return check_inclusion(&block, slot, &tx_hashes, guarantee);
+        }
+
+        let head = request_current_slot_number(beacon_url).await?;
+        if head > slot || start.elapsed() > timeout {
+            return Ok(InclusionReport {
+                slot,
+                guarantee,
+                block_hash: None,
+                txs: tx_hashes.into_iter().map(TxInclusion::missing).collect(),
+            });
+        }
+
+        debug!(slot, head, "Block not available yet");
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Check the inclusion of the given transactions in the given beacon block.
+pub fn check_inclusion(
+    block: &Value,
+    slot: u64,
+    tx_hashes: &[B256],
+    guarantee: Guarantee,
+) -> Result<InclusionReport> {
+    let payload = block
+        .pointer("/data/message/body/execution_payload")
+        .wrap_err("missing execution payload")?;
+
+    let block_hash = payload.get("block_hash").and_then(Value::as_str).wrap_err("missing hash")?;
+    let base_fee = parse_number(payload.get("base_fee_per_gas").wrap_err("missing base fee")?)?;
+    let transactions =
+        payload.get("transactions").and_then(Value::as_array).wrap_err("missing transactions")?;
+
+    // The raw transactions of the block, with their hash
+    let raw_txs = transactions
+        .iter()
+        .map(|tx| {
+            let raw = hex::decode(tx.as_str().wrap_err("invalid transaction")?)?;
+            Ok((keccak256(&raw), raw))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut txs = Vec::with_capacity(tx_hashes.len());
+    for tx_hash in tx_hashes {
+        let Some(position) = raw_txs.iter().position(|(hash, _)| hash == tx_hash) else {
+            txs.push(TxInclusion::missing(*tx_hash));
+            continue;
+        };
+
+        let tx = TxEnvelope::decode_2718(&mut raw_txs[position].1.as_slice())?;
+        let priority_fee = tx.effective_tip_per_gas(base_fee);
+        txs.push(TxInclusion { tx_hash: *tx_hash, position: Some(position), priority_fee });
+    }
+
+    Ok(InclusionReport { slot, guarantee, block_hash: Some(block_hash.parse()?), txs })
+}
+
+impl TxInclusion {
+    /// A transaction missing from the block.
+    fn missing(tx_hash: B256) -> Self {
+        Self { tx_hash, position: None, priority_fee: None }
+    }
+}
+
+/// Fetch the beacon block of the given slot, or `None` if there is none yet.
+async fn fetch_block(beacon_url: &Url, slot: u64) -> Result<Option<Value>> {
+    let res = reqwest::get(beacon_url.join(&format!("eth/v2/beacon/blocks/{slot}"))?).await?;
+    if res.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    Ok(Some(res.error_for_status()?.json().await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::constants::GWEI_TO_WEI,
+        eips::eip2718::Encodable2718,
+        network::{EthereumWallet, TransactionBuilder},
+        primitives::U256,
+        rpc::types::TransactionRequest,
+        signers::local::PrivateKeySigner,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    /// Sign a transfer paying the given priority fee, returning its raw bytes and its hash.
+    async fn signed_transfer(nonce: u64, priority_fee: u128) -> (String, B256) {
+        let wallet = PrivateKeySigner::random();
+        let envelope = TransactionRequest::default()
+            .with_to(wallet.address())
+            .with_value(U256::from(100_000))
+            .with_chain_id(17000)
+            .with_nonce(nonce)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(10 * GWEI_TO_WEI as u128)
+            .with_max_priority_fee_per_gas(priority_fee)
+            .build(&EthereumWallet::from(wallet))
+            .await
+            .unwrap();
+
+        (hex::encode_prefixed(envelope.encoded_2718()), *envelope.tx_hash())
+    }
+
+    /// A canned beacon block response including the given raw transactions.
+    fn beacon_block(transactions: &[String]) -> Value {
+        json!({
+            "version": "deneb",
+            "data": {
+                "message": {
+                    "slot": "42",
+                    "body": {
+                        "execution_payload": {
+                            "block_hash": format!("0x{}", "ab".repeat(32)),
+                            "base_fee_per_gas": (5 * GWEI_TO_WEI).to_string(),
+                            "transactions": transactions,
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_check_inclusion() -> Result<()> {
+        let (other_raw, _) = signed_transfer(0, GWEI_TO_WEI as u128).await;
+        let (raw, tx_hash) = signed_transfer(0, 2 * GWEI_TO_WEI as u128).await;
+
+        // The committed transaction is the second one of the block
+        let block = beacon_block(&[other_raw.clone(), raw]);
+        let report = check_inclusion(&block, 42, &[tx_hash], Guarantee::Inclusion)?;
+
+        assert_eq!(report.outcome(), InclusionOutcome::Honored);
+        assert_eq!(report.block_hash, Some(B256::repeat_byte(0xab)));
+        assert_eq!(report.txs[0].position, Some(1));
+        assert_eq!(report.txs[0].priority_fee, Some(2 * GWEI_TO_WEI as u128));
+
+        // The committed transaction is missing from the block
+        let block = beacon_block(&[other_raw]);
+        let report = check_inclusion(&block, 42, &[tx_hash], Guarantee::Inclusion)?;
+
+        assert_eq!(report.outcome(), InclusionOutcome::Broken);
+        assert_eq!(report.txs, vec![TxInclusion::missing(tx_hash)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_top_of_block_inclusion() -> Result<()> {
+        let (other_raw, _) = signed_transfer(0, GWEI_TO_WEI as u128).await;
+        let (first_raw, first_hash) = signed_transfer(0, 2 * GWEI_TO_WEI as u128).await;
+        let (second_raw, second_hash) = signed_transfer(0, 2 * GWEI_TO_WEI as u128).await;
+        let committed = [first_hash, second_hash];
+
+        // The committed transactions open the block, in the order of the request
+        let block = beacon_block(&[first_raw.clone(), second_raw.clone(), other_raw.clone()]);
+        let report = check_inclusion(&block, 42, &committed, Guarantee::TopOfBlock)?;
+        assert_eq!(report.outcome(), InclusionOutcome::Honored);
+
+        // The committed transactions are swapped
+        let block = beacon_block(&[second_raw.clone(), first_raw.clone(), other_raw.clone()]);
+        let report = check_inclusion(&block, 42, &committed, Guarantee::TopOfBlock)?;
+        assert_eq!(report.outcome(), InclusionOutcome::Misplaced);
+        assert_eq!(report.txs[0].position, Some(1));
+
+        // Another transaction is placed before the committed ones
+        let block = beacon_block(&[other_raw.clone(), first_raw.clone(), second_raw.clone()]);
+        let report = check_inclusion(&block, 42, &committed, Guarantee::TopOfBlock)?;
+        assert_eq!(report.outcome(), InclusionOutcome::Misplaced);
+
+        // The same block honors a plain inclusion commitment
+        let report = check_inclusion(&block, 42, &committed, Guarantee::Inclusion)?;
+        assert_eq!(report.outcome(), InclusionOutcome::Honored);
+
+        // A missing transaction breaks the commitment, whatever the position of the others
+        let block = beacon_block(&[first_raw, other_raw]);
+        let report = check_inclusion(&block, 42, &committed, Guarantee::TopOfBlock)?;
+        assert_eq!(report.outcome(), InclusionOutcome::Broken);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missed_slot_outcome() {
+        let report = InclusionReport {
+            slot: 42,
+            guarantee: Guarantee::Inclusion,
+            block_hash: None,
+            txs: vec![TxInclusion::missing(B256::ZERO)],
+        };
+
+        assert_eq!(report.outcome(), InclusionOutcome::SlotMissed);
+    }
+}
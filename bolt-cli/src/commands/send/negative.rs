@@ -0,0 +1,457 @@
+use std::fmt;
+
+use alloy::{
+    consensus::constants::{ETH_TO_WEI, GWEI_TO_WEI},
+    eips::eip2718::Encodable2718,
+    hex,
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use eyre::{bail, ContextCompat, Result};
+use reqwest::Url;
+use serde_json::Value;
+use tracing::{error, info};
+
+use crate::cli::SendCommand;
+
+use super::{prepare_rpc_request, request_current_slot_number, sign_request};
+
+/// Size of the calldata of the oversized transaction, above the 128 KiB accepted by the sidecar.
+const OVERSIZED_CALLDATA_BYTES: usize = 256 * 1024;
+
+/// A deliberately invalid request sent to the sidecar by the negative suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeCase {
+    /// A transaction transferring more than the balance of the sender.
+    InsufficientBalance,
+    /// A different transaction reusing the nonce of an already committed one.
+    NonceReuse,
+    /// A request targeting a past slot.
+    PastSlot,
+    /// A transaction signed for another chain.
+    ForeignChainId,
+    /// A request whose transaction isn't valid hex.
+    MalformedHex,
+    /// A transaction whose calldata makes it exceed the maximum transaction size.
+    OversizedCalldata,
+    /// A request identical to an already committed one.
+    DuplicateTx,
+    /// A request signed by another account than the one in the signature header.
+    BadSignature,
+}
+
+impl fmt::Display for NegativeCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The rejection expected from the sidecar for a negative case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedRejection {
+    /// The JSON-RPC error code.
+    pub code: i64,
+    /// Fragments of the error message identifying the rejection category.
+    /// The message must contain at least one of them.
+    pub messages: &'static [&'static str],
+}
+
+/// The error contract of `bolt_requestInclusion` for every negative case.
+///
+/// The error codes returned by the sidecar are:
+/// - `-32000`: the request can't be parsed.
+/// - `-32004`: the request signature doesn't match the signer in the signature header.
+/// - `-32006`: the request failed the consensus or execution validation.
+pub const EXPECTED_REJECTIONS: [(NegativeCase, ExpectedRejection); 8] = [
+    (
+        NegativeCase::InsufficientBalance,
+        ExpectedRejection { code: -32006, messages: &["Not enough balance"] },
+    ),
+    (NegativeCase::NonceReuse, ExpectedRejection { code: -32006, messages: &["nonce too low"] }),
+    (
+        NegativeCase::PastSlot,
+        // Depending on the current epoch, past slots fail either the consensus or
+        // the execution validation.
+        ExpectedRejection { code: -32006, messages: &["Invalid slot", "Slot must be >="] },
+    ),
+    (
+        NegativeCase::ForeignChainId,
+        ExpectedRejection { code: -32006, messages: &["Chain ID mismatch"] },
+    ),
+    (
+        NegativeCase::MalformedHex,
+        ExpectedRejection { code: -32000, messages: &["Validation failed"] },
+    ),
+    (
+        NegativeCase::OversizedCalldata,
+        ExpectedRejection { code: -32006, messages: &["exceeds the maximum"] },
+    ),
+    (
+        NegativeCase::DuplicateTx,
+        ExpectedRejection { code: -32006, messages: &["already committed"] },
+    ),
+    (
+        NegativeCase::BadSignature,
+        ExpectedRejection { code: -32004, messages: &["Invalid signature"] },
+    ),
+];
+
+/// A negative case that didn't behave as expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseFailure {
+    /// The negative case.
+    pub case: NegativeCase,
+    /// Why the sidecar response didn't match the expected rejection.
+    pub reason: String,
+}
+
+impl SendCommand {
+    /// Send a deliberately invalid request to the devnet sidecar for every [NegativeCase],
+    /// failing if any of them isn't rejected with the expected error.
+    pub(super) async fn run_negative_suite(self, wallet: PrivateKeySigner) -> Result<()> {
+        let el_url = self.devnet_execution_url.wrap_err("missing devnet execution URL")?;
+        let cl_url = self.devnet_beacon_url.wrap_err("missing devnet beacon URL")?;
+        let sidecar_url = self.devnet_sidecar_url.wrap_err("missing devnet sidecar URL")?;
+
+        let failures = run_suite(&wallet, el_url, cl_url, sidecar_url).await?;
+        if !failures.is_empty() {
+            for failure in &failures {
+                error!(case = %failure.case, reason = %failure.reason, "Negative case failed");
+            }
+            bail!("{} out of {} negative cases failed", failures.len(), EXPECTED_REJECTIONS.len());
+        }
+
+        info!("All {} negative cases were rejected as expected", EXPECTED_REJECTIONS.len());
+        Ok(())
+    }
+}
+
+/// Run the negative suite, returning the cases that didn't behave as expected.
+///
+/// A valid baseline request is sent first, so that the nonce reuse and duplicate
+/// cases can conflict with it.
+async fn run_suite(
+    wallet: &PrivateKeySigner,
+    el_url: Url,
+    cl_url: Url,
+    sidecar_url: Url,
+) -> Result<Vec<CaseFailure>> {
+    let provider = ProviderBuilder::new().on_http(el_url);
+    let signer = EthereumWallet::from(wallet.clone());
+    let sender = wallet.address();
+
+    let chain_id = provider.get_chain_id().await?;
+    let nonce = provider.get_transaction_count(sender).await?;
+    let balance = provider.get_balance(sender).await?;
+    let gas_price = provider.get_gas_price().await?;
+
+    let slot = request_current_slot_number(&cl_url).await?;
+    let target_slot = slot + 2;
+
+    // A simple self-transfer valid on the devnet, the cases below tweak it to make it invalid
+    let transfer = |nonce: u64| {
+        TransactionRequest::default()
+            .with_from(sender)
+            .with_to(sender)
+            .with_value(U256::from(100_000))
+            .with_chain_id(chain_id)
+            .with_nonce(nonce)
+            .with_gas_limit(21_000)
+            .with_max_priority_fee_per_gas(2 * GWEI_TO_WEI as u128)
+            .with_max_fee_per_gas(2 * gas_price + 2 * GWEI_TO_WEI as u128)
+    };
+
+    let (baseline_raw, baseline_hash) = sign_tx(transfer(nonce), &signer).await?;
+    let signature = sign_request(vec![baseline_hash], target_slot, false, None, wallet).await?;
+    let response =
+        send_request(vec![baseline_raw.clone()], target_slot, &sidecar_url, signature).await?;
+    if let Some(error) = response.get("error") {
+        bail!("baseline request was rejected: {error}");
+    }
+
+    let mut failures = Vec::new();
+    for (case, expected) in EXPECTED_REJECTIONS {
+        let (txs, hashes, slot) = match case {
+            NegativeCase::InsufficientBalance => {
+                let tx = transfer(nonce + 1).with_value(balance + U256::from(ETH_TO_WEI));
+                let (raw, hash) = sign_tx(tx, &signer).await?;
+                (vec![raw], vec![hash], target_slot)
+            }
+            NegativeCase::NonceReuse => {
+                let tx = transfer(nonce).with_value(U256::from(200_000));
+                let (raw, hash) = sign_tx(tx, &signer).await?;
+                (vec![raw], vec![hash], target_slot)
+            }
+            NegativeCase::PastSlot => {
+                let (raw, hash) = sign_tx(transfer(nonce + 1), &signer).await?;
+                (vec![raw], vec![hash], slot.saturating_sub(1))
+            }
+            NegativeCase::ForeignChainId => {
+                let tx = transfer(nonce + 1).with_chain_id(chain_id + 1);
+                let (raw, hash) = sign_tx(tx, &signer).await?;
+                (vec![raw], vec![hash], target_slot)
+            }
+            NegativeCase::MalformedHex => {
+                (vec!["0xnothex".to_string()], vec![B256::ZERO], target_slot)
+            }
+            NegativeCase::OversizedCalldata => {
+                let tx = transfer(nonce + 1)
+                    .with_input(Bytes::from(vec![0xff; OVERSIZED_CALLDATA_BYTES]))
+                    .with_gas_limit(5_000_000);
+                let (raw, hash) = sign_tx(tx, &signer).await?;
+                (vec![raw], vec![hash], target_slot)
+            }
+            NegativeCase::DuplicateTx => {
+                (vec![baseline_raw.clone()], vec![baseline_hash], target_slot)
+            }
+            NegativeCase::BadSignature => {
+                let (raw, hash) = sign_tx(transfer(nonce + 1), &signer).await?;
+                (vec![raw], vec![hash], target_slot)
+            }
+        };
+
+        let signature = if case == NegativeCase::BadSignature {
+            // Sign with another account, but claim the request comes from the wallet
+            let other = PrivateKeySigner::random();
+            let signature = sign_request(hashes, slot, false, None, &other).await?;
+            let (_, signature) = signature.split_once(':').wrap_err("invalid signature")?;
+            format!("{sender}:{signature}")
+        } else {
+            sign_request(hashes, slot, false, None, wallet).await?
+        };
+
+        let response = send_request(txs, slot, &sidecar_url, signature).await?;
+        match check_rejection(&response, &expected) {
+            Ok(()) => info!(%case, "Request rejected as expected"),
+            Err(reason) => failures.push(CaseFailure { case, reason }),
+        }
+    }
+
+    Ok(failures)
+}
+
+/// Check that the JSON-RPC response is the expected rejection.
+fn check_rejection(response: &Value, expected: &ExpectedRejection) -> Result<(), String> {
+    let Some(error) = response.get("error") else {
+        return Err("request was accepted".to_string());
+    };
+
+    let code = error.get("code").and_then(Value::as_i64);
+    let message = error.get("message").and_then(Value::as_str).unwrap_or_default();
+
+    if code != Some(expected.code) {
+        return Err(format!("expected code {}, got {code:?}: {message}", expected.code));
+    }
+
+    if !expected.messages.iter().any(|fragment| message.contains(fragment)) {
+        return Err(format!("expected one of {:?}, got: {message}", expected.messages));
+    }
+
+    Ok(())
+}
+
+/// Sign the given transaction, returning its hex-encoded raw bytes and its hash.
+async fn sign_tx(tx: TransactionRequest, signer: &EthereumWallet) -> Result<(String, B256)> {
+    let envelope = tx.build(signer).await?;
+    Ok((hex::encode_prefixed(envelope.encoded_2718()), *envelope.tx_hash()))
+}
+
+/// Send an inclusion request with the given signature header, returning the JSON-RPC response.
+async fn send_request(
+    txs_rlp: Vec<String>,
+    target_slot: u64,
+    sidecar_url: &Url,
+    signature: String,
+) -> Result<Value> {
+    let request = prepare_rpc_request(
+        "bolt_requestInclusion",
+        serde_json::json!({
+            "slot": target_slot,
+            "txs": txs_rlp,
+        }),
+    );
+
+    let response = reqwest::Client::new()
+        .post(sidecar_url.clone())
+        .header("content-type", "application/json")
+        .header("x-bolt-signature", signature)
+        .json(&request)
+        .send()
+        .await?;
+
+    Ok(response.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::{Arc, Mutex},
+    };
+
+    use alloy::{
+        consensus::{Transaction, TxEnvelope},
+        eips::eip2718::Decodable2718,
+        primitives::{keccak256, Signature},
+    };
+    use alloy_node_bindings::Anvil;
+    use serde_json::json;
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    use super::*;
+
+    /// The current slot of the mock beacon node.
+    const CURRENT_SLOT: u64 = 100;
+
+    /// Start a mock beacon node whose head is at [CURRENT_SLOT].
+    async fn mock_beacon() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::path("/eth/v1/beacon/headers/head"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "header": { "message": { "slot": CURRENT_SLOT.to_string() } } }
+            })))
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    /// Start a mock sidecar implementing the error codes of the sidecar for accounts funded
+    /// with 10 000 ETH on the given chain. If `check_chain_id` is false, transactions for
+    /// other chains are accepted.
+    async fn mock_sidecar(chain_id: u64, check_chain_id: bool) -> MockServer {
+        let nonces = Arc::new(Mutex::new(HashMap::<Address, u64>::new()));
+        let committed = Arc::new(Mutex::new(HashMap::<B256, u64>::new()));
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(move |req: &Request| {
+                let body = serde_json::from_slice::<Value>(&req.body).unwrap();
+                let reject = |code: i64, message: String| {
+                    ResponseTemplate::new(400).set_body_json(json!({
+                        "jsonrpc": "2.0",
+                        "error": { "code": code, "message": message }
+                    }))
+                };
+
+                let params = &body["params"][0];
+                let slot = params["slot"].as_u64().unwrap();
+                let mut txs = Vec::new();
+                for raw in params["txs"].as_array().unwrap() {
+                    let Ok(raw) = hex::decode(raw.as_str().unwrap()) else {
+                        return reject(-32000, "Validation failed: invalid hex".to_string());
+                    };
+                    txs.push(TxEnvelope::decode_2718(&mut raw.as_slice()).unwrap());
+                }
+
+                let header = req.headers.get("x-bolt-signature").unwrap().to_str().unwrap();
+                let (signer, signature) = header.split_once(':').unwrap();
+                let signer = Address::from_str(signer).unwrap();
+                let digest = {
+                    let mut data = txs.iter().map(|tx| tx.tx_hash().to_vec()).collect::<Vec<_>>();
+                    data.push(slot.to_le_bytes().to_vec());
+                    keccak256(data.concat())
+                };
+                let signature = Signature::from_str(signature).unwrap();
+                if signature.recover_address_from_prehash(&digest).unwrap() != signer {
+                    return reject(-32004, "Invalid signature".to_string());
+                }
+
+                if slot < CURRENT_SLOT {
+                    return reject(
+                        -32006,
+                        format!("Consensus validation error: Invalid slot: {slot}"),
+                    );
+                }
+
+                let mut nonces = nonces.lock().unwrap();
+                let mut committed = committed.lock().unwrap();
+                for tx in &txs {
+                    let expected_nonce = nonces.get(&signer).copied().unwrap_or_default();
+                    let error = if check_chain_id && tx.chain_id() != Some(chain_id) {
+                        "Chain ID mismatch".to_string()
+                    } else if tx.input().len() > 4 * 32 * 1024 {
+                        format!(
+                            "Transaction size {} bytes exceeds the maximum of 131072 bytes",
+                            tx.input().len()
+                        )
+                    } else if tx.value() > U256::from(10_000u64) * U256::from(ETH_TO_WEI) {
+                        "Not enough balance to pay for value + maximum fee".to_string()
+                    } else if let Some(committed_slot) = committed.get(tx.tx_hash()) {
+                        format!(
+                            "Transaction {} is already committed for slot {committed_slot}",
+                            tx.tx_hash()
+                        )
+                    } else if tx.nonce() < expected_nonce {
+                        format!(
+                            "Transaction nonce too low. Expected {expected_nonce}, got {}",
+                            tx.nonce()
+                        )
+                    } else {
+                        nonces.insert(signer, tx.nonce() + 1);
+                        committed.insert(*tx.tx_hash(), slot);
+                        continue;
+                    };
+
+                    return reject(-32006, format!("Validation failed: {error}"));
+                }
+
+                ResponseTemplate::new(200).set_body_json(json!({
+                    "jsonrpc": "2.0",
+                    "id": body["id"],
+                    "result": { "signature": "0x" }
+                }))
+            })
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    #[tokio::test]
+    async fn test_negative_suite() -> Result<()> {
+        let anvil = Anvil::new().spawn();
+        let wallet = PrivateKeySigner::from_slice(&anvil.keys()[0].to_bytes())?;
+
+        let beacon = mock_beacon().await;
+        let sidecar = mock_sidecar(anvil.chain_id(), true).await;
+
+        let failures = run_suite(
+            &wallet,
+            anvil.endpoint_url(),
+            Url::parse(&beacon.uri())?,
+            Url::parse(&sidecar.uri())?,
+        )
+        .await?;
+        assert_eq!(failures, vec![]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negative_suite_reports_accepted_requests() -> Result<()> {
+        let anvil = Anvil::new().spawn();
+        let wallet = PrivateKeySigner::from_slice(&anvil.keys()[0].to_bytes())?;
+
+        let beacon = mock_beacon().await;
+        let sidecar = mock_sidecar(anvil.chain_id(), false).await;
+
+        let failures = run_suite(
+            &wallet,
+            anvil.endpoint_url(),
+            Url::parse(&beacon.uri())?,
+            Url::parse(&sidecar.uri())?,
+        )
+        .await?;
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].case, NegativeCase::ForeignChainId);
+        assert_eq!(failures[0].reason, "request was accepted");
+
+        Ok(())
+    }
+}
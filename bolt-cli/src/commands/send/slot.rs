@@ -0,0 +1,242 @@
+use std::{
+    collections::HashSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::DateTime;
+use eyre::{ContextCompat, Result};
+use reqwest::Url;
+use serde::{de, Deserialize, Deserializer};
+use serde_json::Value;
+use tracing::info;
+
+use super::{request_current_slot_number, LookaheadSlot};
+
+/// Number of slots in an epoch.
+const SLOTS_PER_EPOCH: u64 = 32;
+
+/// Default commitment deadline of the sidecar, in milliseconds into the slot preceding the
+/// target slot.
+pub const DEFAULT_COMMITMENT_DEADLINE_MS: u64 = 8_000;
+
+/// A proposer duty as returned by the beacon node.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProposerDuty {
+    /// The BLS public key of the proposer.
+    pub pubkey: String,
+    /// The index of the proposer.
+    #[serde(deserialize_with = "deserialize_number")]
+    pub validator_index: u64,
+    /// The slot to propose.
+    #[serde(deserialize_with = "deserialize_number")]
+    pub slot: u64,
+}
+
+/// The slot clock of the beacon chain, used to time the target slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotClock {
+    /// The genesis time, in seconds since the UNIX epoch.
+    pub genesis_time: u64,
+    /// The duration of a slot, in seconds.
+    pub seconds_per_slot: u64,
+}
+
+impl SlotClock {
+    /// Fetch the slot clock parameters from the beacon node.
+    pub async fn fetch(beacon_url: &Url) -> Result<Self> {
+        let genesis = beacon_get(beacon_url, "eth/v1/beacon/genesis").await?;
+        let genesis_time =
+            genesis.pointer("/data/genesis_time").wrap_err("missing genesis time")?;
+
+        let spec = beacon_get(beacon_url, "eth/v1/config/spec").await?;
+        let seconds_per_slot =
+            spec.pointer("/data/SECONDS_PER_SLOT").wrap_err("missing seconds per slot")?;
+
+        Ok(Self {
+            genesis_time: parse_number(genesis_time).wrap_err("invalid genesis time")?,
+            seconds_per_slot: parse_number(seconds_per_slot)
+                .wrap_err("invalid seconds per slot")?,
+        })
+    }
+
+    /// Returns the start time of the given slot, in seconds since the UNIX epoch.
+    pub fn slot_start(&self, slot: u64) -> u64 {
+        self.genesis_time + slot * self.seconds_per_slot
+    }
+
+    /// Returns the commitment deadline of the given slot, in milliseconds since the UNIX epoch.
+    ///
+    /// Requests for a slot must be received before the deadline in the preceding slot.
+    pub fn commitment_deadline_ms(&self, slot: u64) -> u64 {
+        self.slot_start(slot.saturating_sub(1)) * 1000 + DEFAULT_COMMITMENT_DEADLINE_MS
+    }
+
+    /// Log the start time of the given slot and the time left before its commitment deadline.
+    pub fn log_target_slot(&self, slot: u64) {
+        let start = DateTime::from_timestamp(self.slot_start(slot) as i64, 0)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default();
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        let remaining_secs =
+            (self.commitment_deadline_ms(slot) as i128 - now as i128) as f64 / 1000.0;
+
+        info!(slot, %start, remaining_secs, "Target slot");
+    }
+}
+
+/// Discover the future slots proposed by bolt proposers, in ascending order.
+///
+/// The proposer duties of the current and next epoch are fetched from the beacon node and
+/// cross-referenced with the active proposers of the bolt lookahead.
+pub async fn discover_bolt_slots(beacon_url: &Url, lookahead_url: Url) -> Result<Vec<u64>> {
+    let current_slot = request_current_slot_number(beacon_url).await?;
+    let epoch = current_slot / SLOTS_PER_EPOCH;
+
+    let mut duties = fetch_proposer_duties(beacon_url, epoch).await?;
+    duties.extend(fetch_proposer_duties(beacon_url, epoch + 1).await?);
+
+    let lookahead = reqwest::get(lookahead_url).await?.json::<Vec<LookaheadSlot>>().await?;
+    let bolt_slots = lookahead.into_iter().map(|slot| slot.slot).collect::<HashSet<_>>();
+
+    Ok(nearest_bolt_slots(&duties, current_slot, &bolt_slots))
+}
+
+/// Returns the future slots of the duties proposed by bolt proposers, in ascending order.
+fn nearest_bolt_slots(
+    duties: &[ProposerDuty],
+    current_slot: u64,
+    bolt_slots: &HashSet<u64>,
+) -> Vec<u64> {
+    let mut slots = duties
+        .iter()
+        .map(|duty| duty.slot)
+        .filter(|slot| *slot > current_slot && bolt_slots.contains(slot))
+        .collect::<Vec<_>>();
+
+    slots.sort_unstable();
+    slots.dedup();
+    slots
+}
+
+/// Fetch the proposer duties of the given epoch from the beacon node.
+async fn fetch_proposer_duties(beacon_url: &Url, epoch: u64) -> Result<Vec<ProposerDuty>> {
+    let res = beacon_get(beacon_url, &format!("eth/v1/validator/duties/proposer/{epoch}")).await?;
+    let duties = res.get("data").wrap_err("missing proposer duties")?;
+    Ok(serde_json::from_value(duties.clone())?)
+}
+
+/// Send a GET request to the given path of the beacon node.
+async fn beacon_get(beacon_url: &Url, path: &str) -> Result<Value> {
+    let res = reqwest::get(beacon_url.join(path)?).await?.error_for_status()?;
+    Ok(res.json::<Value>().await?)
+}
+
+/// Parse a number returned by the beacon node, either as a JSON number or a decimal string.
+pub(super) fn parse_number(value: &Value) -> Result<u64> {
+    match value.as_u64() {
+        Some(number) => Ok(number),
+        None => Ok(value.as_str().wrap_err("invalid number type")?.parse()?),
+    }
+}
+
+/// Deserialize a number returned by the beacon node, see [parse_number].
+fn deserialize_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    parse_number(&Value::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    /// The current slot of the mock beacon node, in epoch 3.
+    const CURRENT_SLOT: u64 = 100;
+
+    /// Start a mock beacon node whose head is at [CURRENT_SLOT], where every slot of the
+    /// current and next epochs is proposed by the validator with the same index.
+    async fn mock_beacon() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(path("/eth/v1/beacon/headers/head"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "header": { "message": { "slot": CURRENT_SLOT.to_string() } } }
+            })))
+            .mount(&server)
+            .await;
+
+        for epoch in [3, 4] {
+            let duties = (epoch * SLOTS_PER_EPOCH..(epoch + 1) * SLOTS_PER_EPOCH)
+                .map(|slot| {
+                    json!({
+                        "pubkey": format!("0x{:096x}", slot),
+                        "validator_index": slot.to_string(),
+                        "slot": slot.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            Mock::given(path(format!("/eth/v1/validator/duties/proposer/{epoch}")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": duties })))
+                .mount(&server)
+                .await;
+        }
+
+        server
+    }
+
+    /// Start a mock bolt RPC whose lookahead contains the given slots.
+    async fn mock_bolt_rpc(slots: &[u64]) -> MockServer {
+        let lookahead = slots
+            .iter()
+            .map(|slot| json!({ "slot": slot, "validator_index": slot, "sidecar_url": null }))
+            .collect::<Vec<_>>();
+
+        let server = MockServer::start().await;
+        Mock::given(path("/api/v1/proposers/lookahead"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(lookahead))
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    #[tokio::test]
+    async fn test_discover_bolt_slots() -> Result<()> {
+        let beacon = mock_beacon().await;
+
+        // Past slots and slots beyond the next epoch are skipped
+        let bolt_rpc = mock_bolt_rpc(&[90, 140, 110, 200]).await;
+        let lookahead_url = Url::parse(&bolt_rpc.uri())?.join("/api/v1/proposers/lookahead")?;
+
+        let slots = discover_bolt_slots(&Url::parse(&beacon.uri())?, lookahead_url).await?;
+        assert_eq!(slots, vec![110, 140]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slot_clock() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(path("/eth/v1/beacon/genesis"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "genesis_time": "1695902400" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(path("/eth/v1/config/spec"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "SECONDS_PER_SLOT": "12" }
+            })))
+            .mount(&server)
+            .await;
+
+        let clock = SlotClock::fetch(&Url::parse(&server.uri())?).await?;
+        assert_eq!(clock, SlotClock { genesis_time: 1695902400, seconds_per_slot: 12 });
+        assert_eq!(clock.slot_start(10), 1695902520);
+        assert_eq!(clock.commitment_deadline_ms(10), 1695902508 * 1000 + 8_000);
+
+        Ok(())
+    }
+}
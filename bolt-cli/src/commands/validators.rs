@@ -1,18 +1,45 @@
+use std::{path::Path, str::FromStr};
+
 use alloy::{
+    contract::Error as ContractError,
+    eips::BlockNumberOrTag,
     network::EthereumWallet,
+    primitives::{Address, Bytes},
     providers::{Provider, ProviderBuilder},
+    rpc::types::BlockTransactionsKind,
     signers::local::PrivateKeySigner,
+    sol_types::{SolCall, SolInterface},
+    transports::TransportError,
 };
 use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
-use eyre::Context;
+use eyre::{bail, Context, Result};
+use reqwest::Url;
+use serde::Serialize;
 use tracing::info;
 
 use crate::{
     cli::{Chain, ValidatorsCommand, ValidatorsSubcommand},
-    common::{hash::compress_bls_pubkey, request_confirmation},
-    contracts::{bolt::BoltValidators, deployments_for_chain},
+    common::{
+        bolt_manager::BoltManager,
+        hash::{compress_bls_pubkey, CompressedHash},
+        request_confirmation,
+    },
+    contracts::{
+        bolt::BoltValidators::{
+            self, batchRegisterValidatorsUnsafeCall, getValidatorByPubkeyHashCall,
+            BoltValidatorsErrors, ValidatorInfo,
+        },
+        deployments_for_chain,
+        multicall::{Multicall3, MULTICALL3_ADDRESS},
+    },
 };
 
+/// Conservative estimate of the gas used to register a single validator in a batch.
+const REGISTRATION_GAS_PER_VALIDATOR: u64 = 100_000;
+
+/// Maximum number of validators looked up in the registry in a single multicall.
+const MULTICALL_BATCH_SIZE: usize = 500;
+
 impl ValidatorsCommand {
     pub async fn run(self) -> eyre::Result<()> {
         match self.subcommand {
@@ -22,14 +49,9 @@ impl ValidatorsCommand {
                 admin_private_key,
                 authorized_operator,
                 rpc_url,
+                batch_size,
             } => {
-                let signer = PrivateKeySigner::from_bytes(&admin_private_key)
-                    .wrap_err("valid private key")?;
-
-                let provider = ProviderBuilder::new()
-                    .with_recommended_fillers()
-                    .wallet(EthereumWallet::from(signer))
-                    .on_http(rpc_url.clone());
+                let provider = ProviderBuilder::new().on_http(rpc_url.clone());
 
                 let chain_id = provider.get_chain_id().await?;
                 let chain = Chain::from_id(chain_id)
@@ -37,49 +59,323 @@ impl ValidatorsCommand {
 
                 let bolt_validators_address = deployments_for_chain(chain).bolt.validators;
 
-                let pubkeys_file = std::fs::File::open(&pubkeys_path)?;
-                let keys: Vec<BlsPublicKey> = serde_json::from_reader(pubkeys_file)?;
+                let keys = read_pubkeys(&pubkeys_path)?;
                 let pubkey_hashes: Vec<_> = keys.iter().map(compress_bls_pubkey).collect();
 
+                let batch_size = match batch_size {
+                    Some(size) => size,
+                    None => {
+                        let block = provider
+                            .get_block_by_number(
+                                BlockNumberOrTag::Latest,
+                                BlockTransactionsKind::Hashes,
+                            )
+                            .await?
+                            .ok_or_else(|| eyre::eyre!("latest block not found"))?;
+                        registration_batch_size(block.header.gas_limit)
+                    }
+                };
+                if batch_size == 0 {
+                    bail!("batch size must be greater than zero");
+                }
+
+                let batches = pubkey_hashes
+                    .chunks(batch_size)
+                    .map(|batch| batchRegisterValidatorsUnsafeCall {
+                        pubkeyHashes: batch.to_vec(),
+                        maxCommittedGasLimit: max_committed_gas_limit,
+                        authorizedOperator: authorized_operator,
+                    })
+                    .collect::<Vec<_>>();
+
                 info!(
                     validators = ?keys.len(),
+                    transactions = ?batches.len(),
                     ?max_committed_gas_limit,
                     ?authorized_operator,
                     ?chain,
                     "Registering validators into bolt",
                 );
 
-                let bolt_validators =
-                    BoltValidators::new(bolt_validators_address, provider.clone());
+                let Some(admin_private_key) = admin_private_key else {
+                    let calldata = registration_calldata(bolt_validators_address, &batches);
+                    println!("{}", serde_json::to_string_pretty(&calldata)?);
+                    info!("No private key provided, printed the unsigned registration calldata");
+                    return Ok(());
+                };
+
+                let signer = PrivateKeySigner::from_bytes(&admin_private_key)
+                    .wrap_err("valid private key")?;
+
+                let provider = ProviderBuilder::new()
+                    .with_recommended_fillers()
+                    .wallet(EthereumWallet::from(signer))
+                    .on_http(rpc_url);
+
+                let bolt_validators = BoltValidators::new(bolt_validators_address, provider);
 
                 request_confirmation();
 
-                let pending = bolt_validators
-                    .batchRegisterValidatorsUnsafe(
-                        pubkey_hashes,
-                        max_committed_gas_limit,
-                        authorized_operator,
-                    )
-                    .send()
-                    .await?;
+                for (i, batch) in batches.into_iter().enumerate() {
+                    let validators = batch.pubkeyHashes.len();
+                    let pending = bolt_validators
+                        .batchRegisterValidatorsUnsafe(
+                            batch.pubkeyHashes,
+                            batch.maxCommittedGasLimit,
+                            batch.authorizedOperator,
+                        )
+                        .send()
+                        .await?;
 
-                info!(
-                    hash = ?pending.tx_hash(),
-                    "batchRegisterValidatorsUnsafe transaction sent, awaiting receipt..."
-                );
-                let receipt = pending.get_receipt().await?;
-                if !receipt.status() {
-                    eyre::bail!("Transaction failed: {:?}", receipt)
+                    info!(
+                        hash = ?pending.tx_hash(),
+                        batch = i + 1,
+                        validators,
+                        "batchRegisterValidatorsUnsafe transaction sent, awaiting receipt..."
+                    );
+                    let receipt = pending.get_receipt().await?;
+                    if !receipt.status() {
+                        eyre::bail!("Transaction failed: {:?}", receipt)
+                    }
                 }
 
                 info!("Successfully registered validators into bolt");
 
                 Ok(())
             }
+
+            ValidatorsSubcommand::Status { rpc_url, chain, pubkeys_path, json } => {
+                let chain = match chain {
+                    Some(chain) => chain,
+                    None => {
+                        let chain_id =
+                            ProviderBuilder::new().on_http(rpc_url.clone()).get_chain_id().await?;
+                        Chain::from_id(chain_id)
+                            .unwrap_or_else(|| panic!("chain id {} not supported", chain_id))
+                    }
+                };
+
+                let keys = read_pubkeys(&pubkeys_path)?;
+                let statuses = fetch_validator_statuses(&rpc_url, chain, &keys).await?;
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&statuses)?);
+                } else {
+                    print_table(&statuses);
+                }
+
+                Ok(())
+            }
         }
     }
 }
 
+/// The registration status of a validator in bolt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidatorStatus {
+    /// The BLS public key of the validator.
+    pub pubkey: BlsPublicKey,
+    /// The hash of the public key, as stored in the registry.
+    pub pubkey_hash: CompressedHash,
+    /// Whether the validator is registered in the bolt validators registry.
+    pub registered: bool,
+    /// The operator authorized to make commitments on behalf of the validator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_operator: Option<Address>,
+    /// The maximum gas the validator is willing to reserve to commitments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_committed_gas_limit: Option<u32>,
+    /// Whether the authorized operator is registered in the bolt manager.
+    pub operator_registered: bool,
+    /// Whether the manager considers the validator active.
+    pub active: bool,
+    /// The RPC of the authorized operator, if it is registered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_rpc: Option<String>,
+}
+
+/// The unsigned calldata of a registration transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegistrationCalldata {
+    /// The address of the bolt validators registry.
+    pub to: Address,
+    /// The ABI-encoded call to `batchRegisterValidatorsUnsafe`.
+    pub data: Bytes,
+    /// The number of validators registered by the transaction.
+    pub validators: usize,
+}
+
+/// Read a JSON file containing an array of BLS public keys.
+fn read_pubkeys(path: &Path) -> Result<Vec<BlsPublicKey>> {
+    let file = std::fs::File::open(path)
+        .wrap_err_with(|| format!("failed to open pubkeys file {}", path.display()))?;
+    serde_json::from_reader(file).wrap_err("pubkeys file must contain an array of BLS public keys")
+}
+
+/// Returns the number of validators that can be registered in a single transaction, keeping
+/// each transaction under half of the block gas limit.
+fn registration_batch_size(block_gas_limit: u64) -> usize {
+    (block_gas_limit / 2 / REGISTRATION_GAS_PER_VALIDATOR).max(1) as usize
+}
+
+/// Returns the unsigned calldata of the given registration batches.
+fn registration_calldata(
+    registry: Address,
+    batches: &[batchRegisterValidatorsUnsafeCall],
+) -> Vec<RegistrationCalldata> {
+    batches
+        .iter()
+        .map(|batch| RegistrationCalldata {
+            to: registry,
+            data: batch.abi_encode().into(),
+            validators: batch.pubkeyHashes.len(),
+        })
+        .collect()
+}
+
+/// Fetch the registration status of the given validators from the bolt contracts of the chain.
+pub async fn fetch_validator_statuses(
+    rpc_url: &Url,
+    chain: Chain,
+    keys: &[BlsPublicKey],
+) -> Result<Vec<ValidatorStatus>> {
+    let deployments = deployments_for_chain(chain);
+    let provider = ProviderBuilder::new().on_http(rpc_url.clone());
+    let bolt_validators = BoltValidators::new(deployments.bolt.validators, provider);
+    let bolt_manager = BoltManager::new(rpc_url.clone(), deployments.bolt.manager);
+
+    let mut statuses = Vec::with_capacity(keys.len());
+    for pubkey in keys {
+        let pubkey_hash = compress_bls_pubkey(pubkey);
+        let mut status = ValidatorStatus {
+            pubkey: pubkey.clone(),
+            pubkey_hash,
+            registered: false,
+            authorized_operator: None,
+            max_committed_gas_limit: None,
+            operator_registered: false,
+            active: false,
+            operator_rpc: None,
+        };
+
+        let info = match bolt_validators.getValidatorByPubkeyHash(pubkey_hash).call().await {
+            Ok(info) => info._0,
+            Err(err) => match try_decode_validators_error(&err) {
+                Some(BoltValidatorsErrors::ValidatorDoesNotExist(_)) => {
+                    statuses.push(status);
+                    continue;
+                }
+                _ => return Err(err).wrap_err("failed to fetch validator from the registry"),
+            },
+        };
+
+        let ValidatorInfo { authorizedOperator, maxCommittedGasLimit, .. } = info;
+        status.registered = true;
+        status.authorized_operator = Some(authorizedOperator);
+        status.max_committed_gas_limit = Some(maxCommittedGasLimit);
+        status.operator_registered = bolt_manager.is_operator(authorizedOperator).await?;
+
+        if status.operator_registered {
+            if let Some(proposer) = bolt_manager.get_proposer_status(pubkey_hash).await? {
+                status.active = proposer.active;
+                status.operator_rpc = Some(proposer.operatorRPC);
+            }
+        }
+
+        statuses.push(status);
+    }
+
+    Ok(statuses)
+}
+
+/// Fetch the registration of the given validators in the bolt validators registry, batching
+/// the lookups with Multicall3. Returns `None` for the validators that aren't registered.
+pub async fn fetch_registrations(
+    rpc_url: &Url,
+    registry: Address,
+    keys: &[BlsPublicKey],
+) -> Result<Vec<Option<ValidatorInfo>>> {
+    let provider = ProviderBuilder::new().on_http(rpc_url.clone());
+    let multicall = Multicall3::new(MULTICALL3_ADDRESS, provider);
+
+    let mut registrations = Vec::with_capacity(keys.len());
+    for batch in keys.chunks(MULTICALL_BATCH_SIZE) {
+        let calls = batch
+            .iter()
+            .map(|pubkey| Multicall3::Call3 {
+                target: registry,
+                allowFailure: true,
+                callData: getValidatorByPubkeyHashCall { pubkeyHash: compress_bls_pubkey(pubkey) }
+                    .abi_encode()
+                    .into(),
+            })
+            .collect();
+
+        let results = multicall
+            .aggregate3(calls)
+            .call()
+            .await
+            .wrap_err("failed to fetch validators from the registry")?
+            .returnData;
+
+        for (pubkey, result) in batch.iter().zip(results) {
+            if result.success {
+                let info =
+                    getValidatorByPubkeyHashCall::abi_decode_returns(&result.returnData, true)
+                        .wrap_err("invalid validator returned by the registry")?;
+                registrations.push(Some(info._0));
+                continue;
+            }
+
+            match BoltValidatorsErrors::abi_decode(&result.returnData, true) {
+                Ok(BoltValidatorsErrors::ValidatorDoesNotExist(_)) => registrations.push(None),
+                _ => bail!(
+                    "failed to fetch validator {pubkey:?} from the registry: {}",
+                    result.returnData
+                ),
+            }
+        }
+    }
+
+    Ok(registrations)
+}
+
+/// Try to decode the revert reason of a call to the bolt validators registry.
+fn try_decode_validators_error(error: &ContractError) -> Option<BoltValidatorsErrors> {
+    // TODO: clean this after https://github.com/alloy-rs/alloy/issues/787 is merged
+    let ContractError::TransportError(TransportError::ErrorResp(err)) = error else {
+        return None;
+    };
+
+    let data = err.data.as_ref()?;
+    let data = Bytes::from_str(data.get().trim_matches('"')).ok()?;
+    BoltValidatorsErrors::abi_decode(&data, true).ok()
+}
+
+/// Print the statuses as a human-readable table.
+fn print_table(statuses: &[ValidatorStatus]) {
+    println!(
+        "{:<98}  {:<10}  {:<42}  {:<6}  OPERATOR RPC",
+        "VALIDATOR", "REGISTERED", "OPERATOR", "ACTIVE"
+    );
+    for status in statuses {
+        let operator = match status.authorized_operator {
+            Some(operator) if status.operator_registered => operator.to_string(),
+            Some(operator) => format!("{operator} (unregistered)"),
+            None => "-".to_string(),
+        };
+
+        println!(
+            "{:<98}  {:<10}  {:<42}  {:<6}  {}",
+            format!("{:?}", status.pubkey),
+            status.registered,
+            operator,
+            status.active,
+            status.operator_rpc.as_deref().unwrap_or("-"),
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloy::{
@@ -88,7 +384,32 @@ mod tests {
         signers::k256::ecdsa::SigningKey,
     };
 
-    use crate::cli::{ValidatorsCommand, ValidatorsSubcommand};
+    use super::*;
+
+    #[test]
+    fn test_registration_batches() {
+        assert_eq!(registration_batch_size(30_000_000), 150);
+        assert_eq!(registration_batch_size(36_000_000), 180);
+        assert_eq!(registration_batch_size(0), 1);
+
+        let keys = read_pubkeys(Path::new("./test_data/pubkeys.json")).unwrap();
+        let batches = keys
+            .chunks(2)
+            .map(|batch| batchRegisterValidatorsUnsafeCall {
+                pubkeyHashes: batch.iter().map(compress_bls_pubkey).collect(),
+                maxCommittedGasLimit: 10_000_000,
+                authorizedOperator: Address::ZERO,
+            })
+            .collect::<Vec<_>>();
+
+        let calldata = registration_calldata(Address::repeat_byte(1), &batches);
+        assert_eq!(calldata.len(), keys.len().div_ceil(2));
+        assert_eq!(calldata.iter().map(|c| c.validators).sum::<usize>(), keys.len());
+
+        let decoded = batchRegisterValidatorsUnsafeCall::abi_decode(&calldata[0].data, true)
+            .expect("valid calldata");
+        assert_eq!(decoded.pubkeyHashes, batches[0].pubkeyHashes);
+    }
 
     #[tokio::test]
     async fn test_register_validators() {
@@ -102,16 +423,34 @@ mod tests {
 
         provider.anvil_set_balance(account, U256::from(u64::MAX)).await.expect("set balance");
 
+        let pubkeys_path = "./test_data/pubkeys.json";
+        let keys = read_pubkeys(Path::new(pubkeys_path)).unwrap();
+        let anvil_url: Url = anvil_url.parse().unwrap();
+
+        let statuses = fetch_validator_statuses(&anvil_url, Chain::Holesky, &keys).await.unwrap();
+        assert!(statuses.iter().all(|status| !status.registered));
+
         let command = ValidatorsCommand {
             subcommand: ValidatorsSubcommand::Register {
                 max_committed_gas_limit: 30_000_000,
-                admin_private_key: B256::try_from(secret_key.to_bytes().as_slice()).unwrap(),
+                admin_private_key: Some(B256::try_from(secret_key.to_bytes().as_slice()).unwrap()),
                 authorized_operator: account,
-                pubkeys_path: "./test_data/pubkeys.json".parse().unwrap(),
-                rpc_url: anvil_url.parse().unwrap(),
+                pubkeys_path: pubkeys_path.parse().unwrap(),
+                rpc_url: anvil_url.clone(),
+                batch_size: Some(1),
             },
         };
 
         command.run().await.expect("run command");
+
+        let statuses = fetch_validator_statuses(&anvil_url, Chain::Holesky, &keys).await.unwrap();
+        assert_eq!(statuses.len(), keys.len());
+        for status in statuses {
+            assert!(status.registered);
+            assert_eq!(status.authorized_operator, Some(account));
+            assert_eq!(status.max_committed_gas_limit, Some(30_000_000));
+            assert!(!status.operator_registered);
+            assert!(!status.active);
+        }
     }
 }
@@ -0,0 +1,127 @@
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use eyre::{bail, Context, Result};
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::parse_bls_public_key;
+
+/// Maximum number of validator IDs queried in a single request to the beacon node.
+const VALIDATORS_CHUNK_SIZE: usize = 500;
+
+/// A validator as returned by the beacon node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeaconValidator {
+    /// The index of the validator.
+    pub index: u64,
+    /// The BLS public key of the validator.
+    pub pubkey: BlsPublicKey,
+    /// The withdrawal credentials of the validator, hex-encoded.
+    pub withdrawal_credentials: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidatorsResponse {
+    data: Vec<ValidatorResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidatorResponse {
+    index: String,
+    validator: ValidatorData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidatorData {
+    pubkey: String,
+    withdrawal_credentials: String,
+}
+
+/// Parse a list of validator indexes, where every entry is either a single index or an
+/// inclusive range like `100..=199`.
+pub fn parse_validator_indexes(entries: &[String]) -> Result<Vec<u64>> {
+    let mut indexes = Vec::new();
+    for entry in entries.iter().map(|entry| entry.trim()) {
+        if let Some((start, end)) = entry.split_once("..=") {
+            let start = start.parse::<u64>().wrap_err_with(|| format!("invalid range {entry}"))?;
+            let end = end.parse::<u64>().wrap_err_with(|| format!("invalid range {entry}"))?;
+            if start > end {
+                bail!("invalid range {entry}: start is greater than end");
+            }
+
+            indexes.extend(start..=end);
+        } else {
+            indexes.push(entry.parse().wrap_err_with(|| format!("invalid index {entry}"))?);
+        }
+    }
+
+    indexes.sort_unstable();
+    indexes.dedup();
+    Ok(indexes)
+}
+
+/// Fetch the given validators from the beacon node at the head state, or all the active
+/// validators if no indexes are given.
+///
+/// Fails if any of the given indexes is unknown to the beacon node.
+pub async fn fetch_validators(beacon_url: &Url, indexes: &[u64]) -> Result<Vec<BeaconValidator>> {
+    let url = beacon_url.join("eth/v1/beacon/states/head/validators")?;
+
+    let mut validators = Vec::new();
+    if indexes.is_empty() {
+        validators.extend(query_validators(url, &[("status", "active".to_string())]).await?);
+    } else {
+        for chunk in indexes.chunks(VALIDATORS_CHUNK_SIZE) {
+            let ids = chunk.iter().map(u64::to_string).collect::<Vec<_>>().join(",");
+            validators.extend(query_validators(url.clone(), &[("id", ids)]).await?);
+        }
+
+        let missing = indexes
+            .iter()
+            .filter(|index| !validators.iter().any(|v| v.index == **index))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            bail!("validators not found on the beacon node: {missing:?}");
+        }
+    }
+
+    Ok(validators)
+}
+
+/// Query the validators endpoint of the beacon node with the given query parameters.
+async fn query_validators(url: Url, query: &[(&str, String)]) -> Result<Vec<BeaconValidator>> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .query(query)
+        .send()
+        .await?
+        .error_for_status()
+        .wrap_err("failed to fetch validators from the beacon node")?
+        .json::<ValidatorsResponse>()
+        .await?;
+
+    response
+        .data
+        .into_iter()
+        .map(|v| {
+            Ok(BeaconValidator {
+                index: v.index.parse()?,
+                pubkey: parse_bls_public_key(&v.validator.pubkey)?,
+                withdrawal_credentials: v.validator.withdrawal_credentials.to_lowercase(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_validator_indexes;
+
+    #[test]
+    fn test_parse_validator_indexes() {
+        let entries = ["3", "1..=2", " 5..=7 ", "2"].map(String::from);
+        assert_eq!(parse_validator_indexes(&entries).unwrap(), vec![1, 2, 3, 5, 6, 7]);
+
+        assert!(parse_validator_indexes(&["7..=5".to_string()]).is_err());
+        assert!(parse_validator_indexes(&["0x01".to_string()]).is_err());
+    }
+}
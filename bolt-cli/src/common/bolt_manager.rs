@@ -4,7 +4,7 @@ use std::str::FromStr;
 
 use alloy::{
     contract::{Error as ContractError, Result as ContractResult},
-    primitives::{Address, Bytes, B256},
+    primitives::{Address, Bytes},
     providers::{ProviderBuilder, RootProvider},
     sol,
     sol_types::{Error as SolError, SolInterface},
@@ -13,6 +13,7 @@ use alloy::{
 use reqwest::{Client, Url};
 use serde::Serialize;
 
+use super::hash::CompressedHash;
 use BoltManagerContract::{BoltManagerContractErrors, BoltManagerContractInstance, ProposerStatus};
 
 /// Bolt Manager contract bindings.
@@ -33,7 +34,7 @@ impl BoltManager {
     /// Returns Ok(None) if the operator is not found in the registry.
     pub async fn get_sidecar_rpc_url_for_validator(
         &self,
-        pubkey_hash: B256,
+        pubkey_hash: CompressedHash,
     ) -> ContractResult<Option<String>> {
         let registrant = self.get_proposer_status(pubkey_hash).await?;
         Ok(registrant.and_then(|r| if r.active { Some(r.operatorRPC) } else { None }))
@@ -44,7 +45,7 @@ impl BoltManager {
     /// Returns Ok(None) if the proposer is not found in the registry.
     pub async fn get_proposer_status(
         &self,
-        pubkey_hash: B256,
+        pubkey_hash: CompressedHash,
     ) -> ContractResult<Option<ProposerStatus>> {
         let returndata = self.0.getProposerStatus(pubkey_hash).call().await;
 
@@ -73,6 +74,11 @@ impl BoltManager {
             .into())
         }
     }
+
+    /// Returns true if the given address is a registered operator.
+    pub async fn is_operator(&self, operator: Address) -> ContractResult<bool> {
+        Ok(self.0.isOperator(operator).call().await?._0)
+    }
 }
 
 sol! {
@@ -80,7 +86,7 @@ sol! {
     interface BoltManagerContract {
         #[derive(Debug, Default, Serialize)]
         struct ProposerStatus {
-            bytes32 pubkeyHash;
+            bytes20 pubkeyHash;
             bool active;
             address operator;
             string operatorRPC;
@@ -88,11 +94,13 @@ sol! {
             uint256[] amounts;
         }
 
-        function getProposerStatus(bytes32 pubkeyHash) external view returns (ProposerStatus memory);
+        function getProposerStatus(bytes20 pubkeyHash) external view returns (ProposerStatus memory);
 
         function isOperator(address operator) public view returns (bool);
 
+        error KeyNotFound();
         error InvalidQuery();
-        error ValidatorDoesNotExist();
+        #[derive(Debug)]
+        error ValidatorDoesNotExist(bytes20 pubkeyHash);
     }
 }
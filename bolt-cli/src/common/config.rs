@@ -0,0 +1,373 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{ArgAction, Command, CommandFactory, FromArgMatches};
+use eyre::{bail, eyre, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::Opts;
+
+/// Environment variable selecting the profile when `--profile` isn't given.
+pub const PROFILE_ENV: &str = "BOLT_PROFILE";
+
+/// Environment variable overriding the path of the configuration file.
+pub const CONFIG_PATH_ENV: &str = "BOLT_CONFIG";
+
+/// Options that can't be set in a profile.
+const RESERVED_OPTIONS: [&str; 3] = ["profile", "help", "version"];
+
+/// Option names hinting at a secret value, redacted when printing a profile.
+const SECRET_HINTS: [&str; 4] = ["private-key", "password", "passphrase", "secret"];
+
+/// The CLI configuration file, holding named profiles of default option values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A profile, mapping option names (as their long flag, e.g. `rpc-url`) to their default value.
+pub type Profile = BTreeMap<String, toml::Value>;
+
+impl Config {
+    /// Load the configuration file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).wrap_err_with(|| {
+            format!("failed to read config file {}, run `bolt config init`", path.display())
+        })?;
+        toml::from_str(&contents)
+            .wrap_err_with(|| format!("invalid config file {}", path.display()))
+    }
+
+    /// Returns the profile with the given name.
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            let available = self.profiles.keys().cloned().collect::<Vec<_>>();
+            eyre!("unknown profile {name:?}, available profiles: {available:?}")
+        })
+    }
+}
+
+/// Returns the path of the configuration file: `$BOLT_CONFIG` if set, or `bolt/config.toml` in
+/// the user configuration directory.
+pub fn config_path() -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = std::env::var_os("HOME").ok_or_else(|| eyre!("HOME is not set"))?;
+            PathBuf::from(home).join(".config")
+        }
+    };
+
+    Ok(config_dir.join("bolt").join("config.toml"))
+}
+
+/// Parse the CLI options from the process arguments, using the values of the selected profile
+/// as defaults. Exits on invalid arguments, like [`clap::Parser::parse`].
+pub fn parse_opts() -> Result<Opts> {
+    let args = std::env::args_os().collect::<Vec<_>>();
+    let command = command_with_profile(&args, config_path)?;
+
+    let matches = command.get_matches_from(args);
+    Ok(Opts::from_arg_matches(&matches).unwrap_or_else(|err| err.exit()))
+}
+
+/// Returns the CLI command, with the values of the profile selected by the arguments (or by
+/// `$BOLT_PROFILE`) as defaults.
+///
+/// Explicit flags and environment variables still take precedence over the profile values.
+/// The path of the configuration file is only resolved when a profile is selected, so that
+/// the CLI keeps working in environments without a home directory.
+pub fn command_with_profile(
+    args: &[OsString],
+    config_path: impl FnOnce() -> Result<PathBuf>,
+) -> Result<Command> {
+    let command = Opts::command();
+
+    let Some(name) = selected_profile(args) else {
+        return Ok(command);
+    };
+
+    let config = Config::load(&config_path()?)?;
+    apply_profile(command, &name, config.profile(&name)?)
+}
+
+/// Returns the name of the profile selected with `--profile`, or with `$BOLT_PROFILE`.
+fn selected_profile(args: &[OsString]) -> Option<String> {
+    let mut args = args.iter().skip(1).map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--profile" {
+            return args.next().map(|name| name.to_string());
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+    }
+
+    std::env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty())
+}
+
+/// Set the values of the profile as the defaults of the matching options, in every subcommand.
+fn apply_profile(command: Command, name: &str, profile: &Profile) -> Result<Command> {
+    let options = configurable_options(&command);
+
+    let mut defaults = BTreeMap::new();
+    for (key, value) in profile {
+        let key = normalize_key(key);
+        if !options.contains_key(&key) {
+            bail!("unknown option {key:?} in profile {name:?}");
+        }
+
+        let values = profile_values(value)
+            .ok_or_else(|| eyre!("unsupported value for {key:?} in profile {name:?}"))?;
+        defaults.insert(key, values);
+    }
+
+    Ok(set_defaults(command, &defaults))
+}
+
+/// Recursively set the defaults of the options of the command and its subcommands.
+fn set_defaults(mut command: Command, defaults: &BTreeMap<String, Vec<String>>) -> Command {
+    let args = command
+        .get_arguments()
+        .filter_map(|arg| Some((arg.get_id().clone(), arg.get_long()?.to_string())))
+        .collect::<Vec<_>>();
+
+    for (id, long) in args {
+        if let Some(values) = defaults.get(&long) {
+            // Required options are satisfied by the profile value
+            command = command.mut_arg(id, |arg| arg.default_values(values.clone()).required(false));
+        }
+    }
+
+    let subcommands =
+        command.get_subcommands().map(|sub| sub.get_name().to_string()).collect::<Vec<_>>();
+    for name in subcommands {
+        command = command.mut_subcommand(name, |sub| set_defaults(sub, defaults));
+    }
+
+    command
+}
+
+/// An option that can be set in a profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigurableOption {
+    /// The environment variable of the option, if any.
+    pub env: Option<String>,
+    /// Whether the option holds a secret, whose value must not be printed.
+    pub secret: bool,
+}
+
+/// Returns the options of the command and its subcommands that can be set in a profile, keyed
+/// by their long flag name.
+pub fn configurable_options(command: &Command) -> BTreeMap<String, ConfigurableOption> {
+    let mut options = BTreeMap::<String, ConfigurableOption>::new();
+    collect_options(command, &mut options);
+    options
+}
+
+fn collect_options(command: &Command, options: &mut BTreeMap<String, ConfigurableOption>) {
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else { continue };
+        if RESERVED_OPTIONS.contains(&long) || matches!(arg.get_action(), ArgAction::Help) {
+            continue;
+        }
+
+        let option = options.entry(long.to_string()).or_default();
+        if option.env.is_none() {
+            option.env = arg.get_env().map(|env| env.to_string_lossy().to_string());
+        }
+        option.secret |=
+            arg.is_hide_env_values_set() || SECRET_HINTS.iter().any(|hint| long.contains(hint));
+    }
+
+    for sub in command.get_subcommands() {
+        collect_options(sub, options);
+    }
+}
+
+/// Normalize a profile key to the long flag name of the option, so that both `rpc_url` and
+/// `rpc-url` are accepted.
+fn normalize_key(key: &str) -> String {
+    key.trim_start_matches("--").replace('_', "-")
+}
+
+/// Convert a profile value to the values of the option. Arrays set multiple values.
+fn profile_values(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::String(value) => Some(vec![value.clone()]),
+        toml::Value::Integer(value) => Some(vec![value.to_string()]),
+        toml::Value::Float(value) => Some(vec![value.to_string()]),
+        toml::Value::Boolean(value) => Some(vec![value.to_string()]),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(|value| profile_values(value).filter(|v| v.len() == 1).map(|mut v| v.remove(0)))
+            .collect(),
+        toml::Value::Datetime(_) | toml::Value::Table(_) => None,
+    }
+}
+
+/// Where the effective value of a profile option comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueSource {
+    Profile,
+    Env(String),
+}
+
+/// The effective value of an option of a profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveValue {
+    pub key: String,
+    /// The value of the option, redacted if it is a secret.
+    pub value: String,
+    pub source: ValueSource,
+}
+
+/// Returns the effective values of the options set in the profile, taking the environment
+/// variables into account. Secret values are redacted.
+pub fn effective_values(profile: &Profile) -> Result<Vec<EffectiveValue>> {
+    let options = configurable_options(&Opts::command());
+
+    let mut values = Vec::with_capacity(profile.len());
+    for (key, value) in profile {
+        let key = normalize_key(key);
+        let option = options.get(&key).ok_or_else(|| eyre!("unknown option {key:?}"))?;
+
+        let env = option.env.as_ref().and_then(|env| Some((env, std::env::var(env).ok()?)));
+        let (value, source) = match env {
+            Some((env, value)) => (value, ValueSource::Env(env.clone())),
+            None => {
+                let values =
+                    profile_values(value).ok_or_else(|| eyre!("unsupported value for {key:?}"))?;
+                (values.join(","), ValueSource::Profile)
+            }
+        };
+
+        let value = if option.secret { "<redacted>".to_string() } else { value };
+        values.push(EffectiveValue { key, value, source });
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Chain, Cmd, ValidatorsSubcommand};
+
+    const CONFIG: &str = r#"
+        [profiles.holesky]
+        chain = "holesky"
+        rpc_url = "http://localhost:8545"
+        admin-private-key = "0x0000000000000000000000000000000000000000000000000000000000000001"
+
+        [profiles.typo]
+        rpc-ulr = "http://localhost:8545"
+    "#;
+
+    fn write_config() -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), CONFIG).unwrap();
+        file
+    }
+
+    fn parse(args: &[&str], config_path: &Path) -> Result<Opts> {
+        let args = args.iter().map(OsString::from).collect::<Vec<_>>();
+        let matches = command_with_profile(&args, || Ok(config_path.to_path_buf()))?
+            .try_get_matches_from(args)?;
+        Ok(Opts::from_arg_matches(&matches)?)
+    }
+
+    fn status_chain(opts: Opts) -> Option<Chain> {
+        match opts.command {
+            Cmd::Validators(cmd) => match cmd.subcommand {
+                ValidatorsSubcommand::Status { chain, .. } => chain,
+                _ => panic!("expected the status subcommand"),
+            },
+            _ => panic!("expected the validators command"),
+        }
+    }
+
+    #[test]
+    fn test_profile_precedence() -> Result<()> {
+        let config = write_config();
+        let path = config.path();
+
+        // Required options are satisfied by the profile
+        let opts = parse(&["bolt", "--profile", "holesky", "validators", "status"], path)?;
+        assert_eq!(status_chain(opts), Some(Chain::Holesky));
+
+        // Flags take precedence over the profile
+        let args = ["bolt", "--profile=holesky", "validators", "status", "--chain", "mainnet"];
+        assert_eq!(status_chain(parse(&args, path)?), Some(Chain::Mainnet));
+
+        // And so do environment variables, but not over flags
+        std::env::set_var("CHAIN", "kurtosis");
+        let env = parse(&["bolt", "--profile", "holesky", "validators", "status"], path);
+        let flag = parse(&args, path);
+        std::env::remove_var("CHAIN");
+        assert_eq!(status_chain(env?), Some(Chain::Kurtosis));
+        assert_eq!(status_chain(flag?), Some(Chain::Mainnet));
+
+        // Without a profile, the option is required again
+        assert!(parse(&["bolt", "validators", "status"], path).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_errors() {
+        let config = write_config();
+        let path = config.path();
+
+        let err = parse(&["bolt", "--profile", "mainnet", "validators", "status"], path);
+        let err = err.unwrap_err().to_string();
+        assert!(err.contains("unknown profile \"mainnet\""), "{err}");
+        assert!(err.contains("[\"holesky\", \"typo\"]"), "{err}");
+
+        let err = parse(&["bolt", "--profile", "typo", "validators", "status"], path);
+        assert!(err.unwrap_err().to_string().contains("unknown option \"rpc-ulr\""));
+
+        let missing = path.with_extension("missing");
+        assert!(parse(&["bolt", "--profile", "holesky", "pubkeys"], &missing).is_err());
+    }
+
+    #[test]
+    fn test_config_path_only_resolved_with_profile() {
+        let args = ["bolt", "validators", "status", "--chain", "holesky"];
+        let args = args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        // Without a profile, the configuration file is never looked up
+        let command = command_with_profile(&args, || bail!("HOME is not set"));
+        assert!(command.is_ok());
+
+        let mut args = args;
+        args.insert(1, "--profile=holesky".into());
+        let err = command_with_profile(&args, || bail!("HOME is not set")).unwrap_err();
+        assert_eq!(err.to_string(), "HOME is not set");
+    }
+
+    #[test]
+    fn test_effective_values_redacted() -> Result<()> {
+        let config: Config = toml::from_str(CONFIG)?;
+        let values = effective_values(config.profile("holesky")?)?;
+
+        let value = |key: &str| values.iter().find(|v| v.key == key).unwrap().clone();
+        assert_eq!(value("admin-private-key").value, "<redacted>");
+        assert_ne!(value("rpc-url").value, "<redacted>");
+
+        Ok(())
+    }
+}
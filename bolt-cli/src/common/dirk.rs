@@ -1,16 +1,19 @@
 use std::fs;
 
 use alloy::primitives::B256;
-use ethereum_consensus::crypto::bls::Signature as BlsSignature;
+use ethereum_consensus::crypto::bls::{PublicKey as BlsPublicKey, Signature as BlsSignature};
 use eyre::{bail, Context, Result};
+use tokio::task::JoinSet;
 use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::{
     cli::TlsCredentials,
+    common::{signing::verify_root, threshold::recover_signature},
     pb::eth2_signer_api::{
-        Account, AccountManagerClient, ListAccountsRequest, ListerClient, LockAccountRequest,
-        ResponseState, SignRequest, SignRequestId, SignerClient, UnlockAccountRequest,
+        Account, AccountManagerClient, DistributedAccount, Endpoint, ListAccountsRequest,
+        ListerClient, LockAccountRequest, ResponseState, SignRequest, SignRequestId, SignerClient,
+        UnlockAccountRequest,
     },
 };
 
@@ -27,24 +30,52 @@ pub struct Dirk {
     lister: ListerClient<Channel>,
     signer: SignerClient<Channel>,
     account_mng: AccountManagerClient<Channel>,
+    /// The TLS credentials used to connect, reused for the participants of distributed accounts.
+    /// Without credentials, participants are connected to in plaintext.
+    credentials: Option<TlsCredentials>,
 }
 
 impl Dirk {
     /// Connect to the DIRK server with the given address and TLS credentials.
     pub async fn connect(addr: String, credentials: TlsCredentials) -> Result<Self> {
         let addr = addr.parse()?;
-        let tls_config = compose_credentials(credentials)?;
+        let tls_config = compose_credentials(credentials.clone())?;
         let conn = Channel::builder(addr).tls_config(tls_config)?.connect().await?;
 
+        Ok(Self { credentials: Some(credentials), ..Self::from_channel(conn) })
+    }
+
+    /// Create a DIRK client from an established channel.
+    pub fn from_channel(conn: Channel) -> Self {
         let lister = ListerClient::new(conn.clone());
         let signer = SignerClient::new(conn.clone());
         let account_mng = AccountManagerClient::new(conn);
 
-        Ok(Self { lister, signer, account_mng })
+        Self { lister, signer, account_mng, credentials: None }
+    }
+
+    /// Connect to a participant of a distributed account, with the same credentials as this
+    /// client.
+    pub async fn connect_participant(&self, endpoint: &Endpoint) -> Result<Self> {
+        match &self.credentials {
+            Some(credentials) => {
+                let addr = format!("https://{}:{}", endpoint.name, endpoint.port);
+                Self::connect(addr, credentials.clone()).await
+            }
+            None => {
+                let addr = format!("http://{}:{}", endpoint.name, endpoint.port);
+                Ok(Self::from_channel(Channel::from_shared(addr)?.connect().await?))
+            }
+        }
     }
 
     /// List all accounts in the keystore.
     pub async fn list_accounts(&mut self, wallet_path: String) -> Result<Vec<Account>> {
+        Ok(self.list_wallet_accounts(wallet_path).await?.accounts)
+    }
+
+    /// List all standard and distributed accounts in the given wallet path.
+    pub async fn list_wallet_accounts(&mut self, wallet_path: String) -> Result<WalletAccounts> {
         // Request all accounts in the given path. Only one path at a time
         // as done in https://github.com/wealdtech/go-eth2-wallet-dirk/blob/182f99b22b64d01e0d4ae67bf47bb055763465d7/grpc.go#L121
         let req = ListAccountsRequest { paths: vec![wallet_path] };
@@ -54,8 +85,15 @@ impl Dirk {
             bail!("Failed to list accounts: {:?}", res);
         }
 
-        debug!("{} Accounts listed successfully", res.accounts.len());
-        Ok(res.accounts)
+        debug!(
+            "{} Accounts and {} distributed accounts listed successfully",
+            res.accounts.len(),
+            res.distributed_accounts.len()
+        );
+        Ok(WalletAccounts {
+            accounts: res.accounts,
+            distributed_accounts: res.distributed_accounts,
+        })
     }
 
     /// Unlock an account in the keystore with the given passphrase.
@@ -129,6 +167,115 @@ impl Dirk {
         debug!("Signature request succeeded for account {}", account.name);
         Ok(sig)
     }
+
+    /// Request a signature for a distributed account.
+    ///
+    /// The request is sent to every participant of the account, and the signature is recovered
+    /// from the first `signing_threshold` partial signatures, by participant ID. The recovered
+    /// signature is verified against the composite public key of the account.
+    pub async fn request_threshold_signature(
+        &self,
+        account: &DistributedAccount,
+        passphrases: &[String],
+        hash: B256,
+        domain: B256,
+    ) -> Result<BlsSignature> {
+        let mut requests = JoinSet::new();
+        for endpoint in account.participants.clone() {
+            let (dirk, name, passphrases) =
+                (self.clone(), account.name.clone(), passphrases.to_vec());
+
+            requests.spawn(async move {
+                let res =
+                    dirk.request_partial_signature(&endpoint, name, passphrases, hash, domain);
+                let res = res.await;
+                (endpoint, res)
+            });
+        }
+
+        let mut partials = Vec::new();
+        let mut failures = Vec::new();
+        while let Some(res) = requests.join_next().await {
+            let (endpoint, res) = res?;
+            match res {
+                Ok(signature) => partials.push((endpoint.id, signature)),
+                Err(err) => {
+                    debug!("Participant {} failed to sign: {:?}", endpoint.name, err);
+                    failures.push(format!("{}:{} ({err})", endpoint.name, endpoint.port));
+                }
+            }
+        }
+
+        let threshold = account.signing_threshold as usize;
+        if partials.len() < threshold {
+            bail!(
+                "Only {} of {} required partial signatures for {}, failed participants: {}",
+                partials.len(),
+                threshold,
+                account.name,
+                failures.join(", ")
+            );
+        }
+
+        partials.sort_by_key(|(id, _)| *id);
+        partials.truncate(threshold);
+        let signature = recover_signature(&partials)?;
+
+        let composite = BlsPublicKey::try_from(account.composite_public_key.as_slice())?;
+        let blst_sig = blst::min_pk::Signature::from_bytes(signature.as_ref())
+            .map_err(|e| eyre::eyre!("Failed to parse signature: {:?}", e))?;
+        verify_root(composite, hash.0, &blst_sig, domain.0).wrap_err_with(|| {
+            format!("Recovered signature doesn't match the composite key of {}", account.name)
+        })?;
+
+        debug!("Threshold signature request succeeded for account {}", account.name);
+        Ok(signature)
+    }
+
+    /// Request a partial signature of a distributed account from one of its participants,
+    /// unlocking the account on the participant first.
+    async fn request_partial_signature(
+        &self,
+        endpoint: &Endpoint,
+        account_name: String,
+        passphrases: Vec<String>,
+        hash: B256,
+        domain: B256,
+    ) -> Result<BlsSignature> {
+        let mut participant = self.connect_participant(endpoint).await?;
+
+        let mut unlocked = false;
+        for passphrase in passphrases {
+            if participant.unlock_account(account_name.clone(), passphrase).await? {
+                unlocked = true;
+                break;
+            }
+        }
+        if !unlocked {
+            bail!("Failed to unlock account {} with the given passphrases", account_name);
+        }
+
+        let account = Account { name: account_name.clone(), ..Default::default() };
+        let signature = participant.request_signature(&account, hash, domain).await;
+
+        if let Err(err) = participant.lock_account(account_name.clone()).await {
+            warn!("Failed to lock account after signing {}: {:?}", account_name, err);
+        }
+
+        signature
+    }
+}
+
+/// The accounts listed in a wallet path.
+///
+/// Distributed accounts are split across several DIRK instances, and need a threshold of
+/// them to produce a signature.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WalletAccounts {
+    /// The standard accounts.
+    pub accounts: Vec<Account>,
+    /// The distributed (threshold) accounts.
+    pub distributed_accounts: Vec<DistributedAccount>,
 }
 
 /// Compose the TLS credentials from the given paths.
@@ -222,7 +369,27 @@ pub mod test_util {
 
 #[cfg(test)]
 mod tests {
+    use blst::min_pk::SecretKey;
+    use ethereum_consensus::deneb::compute_signing_root;
+    use tokio::net::TcpListener;
+    use tonic::{
+        transport::{server::TcpIncoming, Server},
+        Request, Response, Status,
+    };
+
     use super::*;
+    use crate::{
+        common::{
+            signing::{compute_domain_from_mask, BLS_DST_PREFIX},
+            threshold::test_util::split_secret,
+        },
+        pb::eth2_signer_api::{
+            AccountManager, AccountManagerServer, GenerateRequest, GenerateResponse,
+            LockAccountResponse, MultisignRequest, MultisignResponse, SignBeaconAttestationRequest,
+            SignBeaconAttestationsRequest, SignBeaconProposalRequest, SignResponse, Signer,
+            SignerServer, UnlockAccountResponse,
+        },
+    };
 
     /// Test connecting to a DIRK server and listing available accounts.
     ///
@@ -243,4 +410,164 @@ mod tests {
 
         Ok(())
     }
+
+    /// A mock DIRK participant holding a share of a distributed account.
+    #[derive(Clone)]
+    struct MockParticipant {
+        share: SecretKey,
+    }
+
+    #[tonic::async_trait]
+    impl Signer for MockParticipant {
+        async fn sign(
+            &self,
+            request: Request<SignRequest>,
+        ) -> Result<Response<SignResponse>, Status> {
+            let req = request.into_inner();
+            let root: [u8; 32] =
+                req.data.try_into().map_err(|_| Status::invalid_argument("invalid data"))?;
+            let domain: [u8; 32] =
+                req.domain.try_into().map_err(|_| Status::invalid_argument("invalid domain"))?;
+
+            let signing_root = compute_signing_root(&root, domain)
+                .map_err(|err| Status::internal(err.to_string()))?;
+            let signature = self.share.sign(signing_root.as_ref(), BLS_DST_PREFIX, &[]);
+
+            Ok(Response::new(SignResponse {
+                state: ResponseState::Succeeded as i32,
+                signature: signature.to_bytes().to_vec(),
+            }))
+        }
+
+        async fn multisign(
+            &self,
+            _: Request<MultisignRequest>,
+        ) -> Result<Response<MultisignResponse>, Status> {
+            Err(Status::unimplemented("multisign"))
+        }
+
+        async fn sign_beacon_attestation(
+            &self,
+            _: Request<SignBeaconAttestationRequest>,
+        ) -> Result<Response<SignResponse>, Status> {
+            Err(Status::unimplemented("sign_beacon_attestation"))
+        }
+
+        async fn sign_beacon_attestations(
+            &self,
+            _: Request<SignBeaconAttestationsRequest>,
+        ) -> Result<Response<MultisignResponse>, Status> {
+            Err(Status::unimplemented("sign_beacon_attestations"))
+        }
+
+        async fn sign_beacon_proposal(
+            &self,
+            _: Request<SignBeaconProposalRequest>,
+        ) -> Result<Response<SignResponse>, Status> {
+            Err(Status::unimplemented("sign_beacon_proposal"))
+        }
+    }
+
+    #[tonic::async_trait]
+    impl AccountManager for MockParticipant {
+        async fn unlock(
+            &self,
+            _: Request<UnlockAccountRequest>,
+        ) -> Result<Response<UnlockAccountResponse>, Status> {
+            let state = ResponseState::Succeeded as i32;
+            Ok(Response::new(UnlockAccountResponse { state }))
+        }
+
+        async fn lock(
+            &self,
+            _: Request<LockAccountRequest>,
+        ) -> Result<Response<LockAccountResponse>, Status> {
+            Ok(Response::new(LockAccountResponse { state: ResponseState::Succeeded as i32 }))
+        }
+
+        async fn generate(
+            &self,
+            _: Request<GenerateRequest>,
+        ) -> Result<Response<GenerateResponse>, Status> {
+            Err(Status::unimplemented("generate"))
+        }
+    }
+
+    /// Start a mock participant on a random local port, returning the port.
+    async fn start_participant(share: SecretKey) -> eyre::Result<u32> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let incoming = TcpIncoming::from_listener(listener, true, None)
+            .map_err(|err| eyre::eyre!("{err:?}"))?;
+
+        let participant = MockParticipant { share };
+        tokio::spawn(
+            Server::builder()
+                .add_service(SignerServer::new(participant.clone()))
+                .add_service(AccountManagerServer::new(participant))
+                .serve_with_incoming(incoming),
+        );
+
+        Ok(port as u32)
+    }
+
+    /// Returns a local port with nothing listening on it.
+    async fn unreachable_port() -> eyre::Result<u32> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        Ok(listener.local_addr()?.port() as u32)
+    }
+
+    /// Test signing with a distributed account of three participants with a threshold of two,
+    /// one of them being unreachable.
+    #[tokio::test]
+    async fn test_threshold_signature() -> eyre::Result<()> {
+        let secret = SecretKey::from_bytes(&[1; 32]).unwrap();
+        let shares = split_secret(&secret, &[1, 2, 3]);
+
+        let ports = [
+            start_participant(shares[0].clone()).await?,
+            unreachable_port().await?,
+            start_participant(shares[2].clone()).await?,
+        ];
+
+        let mut account = DistributedAccount {
+            name: "wallet1/distributed".to_string(),
+            public_key: Vec::new(),
+            participants: (1..=3)
+                .zip(ports)
+                .map(|(id, port)| Endpoint { id, name: "127.0.0.1".to_string(), port })
+                .collect(),
+            signing_threshold: 2,
+            uuid: Vec::new(),
+            composite_public_key: secret.sk_to_pk().to_bytes().to_vec(),
+        };
+
+        let dirk = Dirk::from_channel(Channel::from_static("http://127.0.0.1:1").connect_lazy());
+        let (hash, domain) =
+            (B256::repeat_byte(0x11), B256::from(compute_domain_from_mask([0; 4])));
+        let passphrases = ["secret".to_string()];
+
+        let signature =
+            dirk.request_threshold_signature(&account, &passphrases, hash, domain).await?;
+
+        // The recovered signature is the one of the composite key
+        let signing_root = compute_signing_root(&hash.0, domain.0)?;
+        let expected = secret.sign(signing_root.as_ref(), BLS_DST_PREFIX, &[]).to_bytes();
+        let signature: &[u8] = signature.as_ref();
+        assert_eq!(signature, expected.as_slice());
+
+        // Below the threshold, the failed participants are named
+        account.participants[2].port = unreachable_port().await?;
+        let err = dirk
+            .request_threshold_signature(&account, &passphrases, hash, domain)
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("Only 1 of 2 required partial signatures"), "{err}");
+        assert!(err.contains(&format!("127.0.0.1:{}", ports[1])), "{err}");
+        assert!(err.contains(&format!("127.0.0.1:{}", account.participants[2].port)), "{err}");
+
+        Ok(())
+    }
 }
@@ -5,9 +5,15 @@ use eyre::{Context, Result};
 use serde::Serialize;
 use tracing::info;
 
+/// Utilities for fetching validators from a beacon node.
+pub mod beacon;
+
 /// BoltManager contract bindings.
 pub mod bolt_manager;
 
+/// Configuration file and profiles of default option values.
+pub mod config;
+
 /// Utilities for working with DIRK remote keystores.
 pub mod dirk;
 
@@ -20,6 +26,9 @@ pub mod signing;
 /// Utilities for hashing messages and custom types.
 pub mod hash;
 
+/// Utilities for recovering threshold BLS signatures.
+pub mod threshold;
+
 /// Parse a BLS public key from a string
 pub fn parse_bls_public_key(delegatee_pubkey: &str) -> Result<BlsPublicKey> {
     let hex_pk = delegatee_pubkey.strip_prefix("0x").unwrap_or(delegatee_pubkey);
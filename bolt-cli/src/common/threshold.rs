@@ -0,0 +1,194 @@
+use blst::{
+    blst_fr, blst_fr_from_uint64, blst_fr_inverse, blst_fr_mul, blst_fr_sub, blst_p2,
+    blst_p2_add_or_double, blst_p2_affine, blst_p2_compress, blst_p2_from_affine, blst_p2_mult,
+    blst_p2_uncompress, blst_scalar, blst_scalar_from_fr, BLST_ERROR,
+};
+use ethereum_consensus::crypto::Signature as BlsSignature;
+use eyre::{bail, eyre, Result};
+
+/// Number of bits of the scalars of the BLS12-381 curve.
+const SCALAR_BITS: usize = 255;
+
+/// Recover the signature of a distributed account from the partial signatures of its
+/// participants, keyed by participant ID.
+///
+/// Participants hold Shamir shares of the account secret key, evaluated at their ID. The
+/// signature is recovered by Lagrange interpolation at zero, so exactly `threshold` partial
+/// signatures must be given.
+pub fn recover_signature(partials: &[(u64, BlsSignature)]) -> Result<BlsSignature> {
+    let ids = partials.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    if ids.is_empty() {
+        bail!("No partial signatures to recover the signature from");
+    }
+    if ids.contains(&0) {
+        bail!("Participant IDs must be non-zero");
+    }
+    if (1..ids.len()).any(|i| ids[i..].contains(&ids[i - 1])) {
+        bail!("Duplicate participant IDs in the partial signatures: {ids:?}");
+    }
+
+    let mut signature = blst_p2::default();
+    for (id, partial) in partials {
+        let point = decompress_signature(partial)?;
+        let coefficient = lagrange_coefficient(*id, &ids);
+
+        let mut term = blst_p2::default();
+        let acc: *mut blst_p2 = &mut signature;
+        // SAFETY: all pointers are valid for the duration of the calls, and `blst` supports
+        // the output aliasing an input.
+        unsafe {
+            blst_p2_mult(&mut term, &point, coefficient.b.as_ptr(), SCALAR_BITS);
+            blst_p2_add_or_double(acc, acc, &term);
+        }
+    }
+
+    let mut bytes = [0u8; 96];
+    // SAFETY: the output buffer has the size of a compressed G2 point.
+    unsafe { blst_p2_compress(bytes.as_mut_ptr(), &signature) };
+
+    BlsSignature::try_from(bytes.as_slice()).map_err(|e| eyre!("Invalid signature: {:?}", e))
+}
+
+/// Returns the Lagrange coefficient of the given participant, evaluated at zero.
+fn lagrange_coefficient(id: u64, ids: &[u64]) -> blst_scalar {
+    let x_i = fr_from_u64(id);
+    let mut numerator = fr_from_u64(1);
+    let mut denominator = fr_from_u64(1);
+
+    for x_j in ids.iter().filter(|other| **other != id).map(|other| fr_from_u64(*other)) {
+        let mut diff = blst_fr::default();
+        let (num, den): (*mut blst_fr, *mut blst_fr) = (&mut numerator, &mut denominator);
+        // SAFETY: all pointers are valid, and `blst` supports the output aliasing an input.
+        unsafe {
+            blst_fr_mul(num, num, &x_j);
+            blst_fr_sub(&mut diff, &x_j, &x_i);
+            blst_fr_mul(den, den, &diff);
+        }
+    }
+
+    let mut inverse = blst_fr::default();
+    let mut coefficient = blst_fr::default();
+    let mut scalar = blst_scalar::default();
+    // SAFETY: all pointers are valid for the duration of the calls.
+    unsafe {
+        blst_fr_inverse(&mut inverse, &denominator);
+        blst_fr_mul(&mut coefficient, &numerator, &inverse);
+        blst_scalar_from_fr(&mut scalar, &coefficient);
+    }
+
+    scalar
+}
+
+/// Returns the given integer as an element of the scalar field.
+fn fr_from_u64(value: u64) -> blst_fr {
+    let mut fr = blst_fr::default();
+    // SAFETY: the input points to the 4 limbs expected by `blst`.
+    unsafe { blst_fr_from_uint64(&mut fr, [value, 0, 0, 0].as_ptr()) };
+    fr
+}
+
+/// Decompress a signature to a G2 point.
+fn decompress_signature(signature: &BlsSignature) -> Result<blst_p2> {
+    let bytes: &[u8] = signature.as_ref();
+    if bytes.len() != 96 {
+        bail!("Invalid partial signature length: {}", bytes.len());
+    }
+
+    let mut affine = blst_p2_affine::default();
+    let mut point = blst_p2::default();
+    // SAFETY: the input has the size of a compressed G2 point.
+    let res = unsafe { blst_p2_uncompress(&mut affine, bytes.as_ptr()) };
+    if res != BLST_ERROR::BLST_SUCCESS {
+        bail!("Invalid partial signature: {:?}", res);
+    }
+
+    // SAFETY: all pointers are valid for the duration of the call.
+    unsafe { blst_p2_from_affine(&mut point, &affine) };
+    Ok(point)
+}
+
+#[cfg(test)]
+pub mod test_util {
+    use blst::{
+        blst_bendian_from_scalar, blst_fr, blst_fr_add, blst_fr_from_scalar, blst_fr_mul,
+        blst_scalar, blst_scalar_from_bendian, blst_scalar_from_fr, min_pk::SecretKey,
+    };
+
+    use super::fr_from_u64;
+
+    /// Split a secret key into Shamir shares for the given participant IDs, such that any
+    /// two of them can recover signatures of the secret key.
+    pub fn split_secret(secret: &SecretKey, ids: &[u64]) -> Vec<SecretKey> {
+        let a0 = fr_from_bytes(&secret.to_bytes());
+        let a1 = fr_from_bytes(&[7; 32]);
+
+        ids.iter()
+            .map(|id| {
+                // share = a0 + a1 * id
+                let mut share = blst_fr::default();
+                // SAFETY: all pointers are valid, and `blst` supports output aliasing.
+                unsafe {
+                    blst_fr_mul(&mut share, &a1, &fr_from_u64(*id));
+                    let share: *mut blst_fr = &mut share;
+                    blst_fr_add(share, share, &a0);
+                }
+
+                let mut scalar = blst_scalar::default();
+                let mut bytes = [0u8; 32];
+                // SAFETY: all pointers are valid for the duration of the calls.
+                unsafe {
+                    blst_scalar_from_fr(&mut scalar, &share);
+                    blst_bendian_from_scalar(bytes.as_mut_ptr(), &scalar);
+                }
+
+                SecretKey::from_bytes(&bytes).expect("valid share")
+            })
+            .collect()
+    }
+
+    fn fr_from_bytes(bytes: &[u8; 32]) -> blst_fr {
+        let mut scalar = blst_scalar::default();
+        let mut fr = blst_fr::default();
+        // SAFETY: all pointers are valid for the duration of the calls.
+        unsafe {
+            blst_scalar_from_bendian(&mut scalar, bytes.as_ptr());
+            blst_fr_from_scalar(&mut fr, &scalar);
+        }
+        fr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blst::min_pk::SecretKey;
+    use ethereum_consensus::crypto::Signature as BlsSignature;
+
+    use super::{recover_signature, test_util::split_secret};
+    use crate::common::signing::BLS_DST_PREFIX;
+
+    #[test]
+    fn test_recover_signature() {
+        let secret = SecretKey::from_bytes(&[1; 32]).unwrap();
+        let shares = split_secret(&secret, &[1, 2, 3]);
+        let message = b"threshold";
+
+        let sign = |sk: &SecretKey| {
+            BlsSignature::try_from(sk.sign(message, BLS_DST_PREFIX, &[]).to_bytes().as_slice())
+                .unwrap()
+        };
+        let expected = sign(&secret);
+
+        // Any two partial signatures recover the signature of the secret key
+        for (a, b) in [(0, 1), (0, 2), (2, 1)] {
+            let partials = [(a as u64 + 1, sign(&shares[a])), (b as u64 + 1, sign(&shares[b]))];
+            assert_eq!(recover_signature(&partials).unwrap(), expected);
+        }
+
+        // A single partial signature doesn't
+        assert_ne!(recover_signature(&[(1, sign(&shares[0]))]).unwrap(), expected);
+
+        // Participant IDs must be distinct
+        let partials = [(1, sign(&shares[0])), (1, sign(&shares[0]))];
+        assert!(recover_signature(&partials).is_err());
+    }
+}
@@ -4,6 +4,19 @@ sol! {
     #[allow(missing_docs)]
     #[sol(rpc)]
     interface BoltValidators {
+        #[derive(Debug)]
+        struct ValidatorInfo {
+            bytes20 pubkeyHash;
+            uint32 maxCommittedGasLimit;
+            address authorizedOperator;
+            address controller;
+        }
+
+        /// @notice Get a validator by its BLS public key hash
+        /// @param pubkeyHash BLS public key hash of the validator
+        /// @return ValidatorInfo struct
+        function getValidatorByPubkeyHash(bytes20 pubkeyHash) public view returns (ValidatorInfo memory);
+
         /// @notice Register a batch of Validators and authorize a Collateral Provider and Operator for them
         /// @dev This function allows anyone to register a list of Validators.
         /// @param pubkeyHashes List of BLS public key hashes for the Validators to be registered
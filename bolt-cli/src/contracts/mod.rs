@@ -9,6 +9,7 @@ use crate::cli::Chain;
 pub mod bolt;
 pub mod eigenlayer;
 pub mod erc20;
+pub mod multicall;
 pub mod symbiotic;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -102,6 +103,11 @@ pub fn deployments() -> HashMap<Chain, Contracts> {
     deployments
 }
 
+/// Returns the deployments of the given chain, if bolt is deployed on it.
+pub fn try_deployments_for_chain(chain: Chain) -> Option<Contracts> {
+    deployments().remove(&chain)
+}
+
 pub fn deployments_for_chain(chain: Chain) -> Contracts {
     deployments().get(&chain).cloned().expect("no deployments for chain")
 }
@@ -110,6 +116,7 @@ const HOLESKY_DEPLOYMENTS: Contracts = Contracts {
     bolt: Bolt {
         validators: address!("47D2DC1DE1eFEFA5e6944402f2eda3981D36a9c8"),
         parameters: address!("20d1cf3A5BD5928dB3118b2CfEF54FDF9fda5c12"),
+        // Mirrors `MANAGER_ADDRESS_HOLESKY` in the chain config of the sidecar
         manager: address!("440202829b493F9FF43E730EB5e8379EEa3678CF"),
         eigenlayer_middleware: address!("a632a3e652110Bb2901D5cE390685E6a9838Ca04"),
         symbiotic_middleware: address!("04f40d9CaE475E5BaA462acE53E5c58A0DD8D8e8"),
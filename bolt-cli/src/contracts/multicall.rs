@@ -0,0 +1,32 @@
+use alloy::{
+    primitives::{address, Address},
+    sol,
+};
+
+/// The address of the Multicall3 contract, deployed at the same address on every chain.
+///
+/// Reference: https://github.com/mds1/multicall3#deployments
+pub const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface Multicall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        #[derive(Debug)]
+        struct Call3Result {
+            bool success;
+            bytes returnData;
+        }
+
+        /// @notice Aggregate calls, ensuring each returns success if required
+        /// @param calls An array of Call3 structs
+        /// @return returnData An array of Call3Result structs
+        function aggregate3(Call3[] calldata calls) external payable returns (Call3Result[] memory returnData);
+    }
+}
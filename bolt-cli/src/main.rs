@@ -1,4 +1,3 @@
-use clap::Parser;
 use tracing::error;
 
 /// CLI command definitions and options.
@@ -25,5 +24,5 @@ async fn main() -> eyre::Result<()> {
         error!("Failed to install default TLS provider: {:?}", err);
     }
 
-    cli::Opts::parse().command.run().await
+    common::config::parse_opts()?.command.run().await
 }
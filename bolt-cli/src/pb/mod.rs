@@ -5,11 +5,18 @@ pub mod eth2_signer_api {
 
     #[allow(unused_imports)]
     pub use super::v1::{
-        account_manager_client::AccountManagerClient, lister_client::ListerClient,
-        sign_request::Id as SignRequestId, signer_client::SignerClient,
-        wallet_manager_client::WalletManagerClient, Account, DistributedAccount,
+        account_manager_client::AccountManagerClient,
+        account_manager_server::{AccountManager, AccountManagerServer},
+        lister_client::ListerClient,
+        lister_server::{Lister, ListerServer},
+        sign_request::Id as SignRequestId,
+        signer_client::SignerClient,
+        signer_server::{Signer, SignerServer},
+        wallet_manager_client::WalletManagerClient,
+        Account, DistributedAccount, Endpoint, GenerateRequest, GenerateResponse,
         ListAccountsRequest, ListAccountsResponse, LockAccountRequest, LockAccountResponse,
         LockWalletRequest, LockWalletResponse, MultisignRequest, MultisignResponse, ResponseState,
+        SignBeaconAttestationRequest, SignBeaconAttestationsRequest, SignBeaconProposalRequest,
         SignRequest, SignResponse, UnlockAccountRequest, UnlockAccountResponse,
         UnlockWalletRequest, UnlockWalletResponse,
     };
@@ -0,0 +1,12 @@
+use clap::Parser;
+
+use bolt_sidecar::api::mock_relay::{start_mock_relay, MockRelayOpts};
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let opts = MockRelayOpts::parse();
+
+    start_mock_relay(opts).await
+}
@@ -17,7 +17,7 @@ async fn main() -> eyre::Result<()> {
 
     let opts = Opts::try_parse()?;
 
-    init_telemetry_stack(opts.telemetry.metrics_port())?;
+    init_telemetry_stack(opts.telemetry.metrics_port(), opts.telemetry.otlp_endpoint.as_ref())?;
 
     info!(chain = opts.chain.name(), "Starting Bolt sidecar");
 
@@ -1,41 +1,99 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
-    body::{self, Body},
-    extract::{Path, Request, State},
+    body::{self, Body, Bytes},
+    extract::{Path, Query, Request, State},
     http::StatusCode,
-    response::Html,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
     routing::{get, post},
     Json, Router,
 };
-use beacon_api_client::VersionedValue;
 use ethereum_consensus::{
     builder::SignedValidatorRegistration,
     deneb::mainnet::SignedBlindedBeaconBlock,
-    primitives::{BlsPublicKey, Hash32},
-    Fork,
+    primitives::{BlsPublicKey, ExecutionAddress, Hash32},
+    serde::as_str,
 };
+use futures::{future::join_all, stream, Stream, StreamExt};
 use parking_lot::Mutex;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::net::TcpListener;
+use tokio::{
+    net::TcpListener,
+    time::{timeout, timeout_at},
+};
 use tracing::{debug, error, info, warn};
 
 use super::spec::{
-    BuilderApiError, ConstraintsApi, GET_HEADER_PATH, GET_PAYLOAD_PATH, REGISTER_VALIDATORS_PATH,
-    STATUS_PATH,
+    BuilderApiError, ConstraintsApi, CONSTRAINTS_STREAM_PATH, GET_HEADER_PATH, GET_PAYLOAD_PATH,
+    REGISTER_VALIDATORS_PATH, STATUS_PATH, SUBMIT_CONSTRAINTS_PATH,
 };
 use crate::{
     builder::PayloadFetcher,
     client::ConstraintsClient,
-    primitives::{GetPayloadResponse, SignedBuilderBid},
+    common::retry_with_backoff,
+    config::{
+        DEFAULT_BUILDER_GET_HEADER_TIMEOUT_MS, DEFAULT_BUILDER_GET_PAYLOAD_TIMEOUT_MS,
+        DEFAULT_BUILDER_REGISTER_VALIDATOR_TIMEOUT_MS,
+    },
+    primitives::{BatchedSignedConstraints, GetPayloadResponse},
+    state::{ConstraintsStream, RegisteredGasLimits, TemplateReader},
     telemetry::ApiMetrics,
 };
 
 const MAX_BLINDED_BLOCK_LENGTH: usize = 1024 * 1024;
 
-/// TODO: determine value
-const GET_HEADER_WITH_PROOFS_TIMEOUT: Duration = Duration::from_millis(500);
+/// The path to the debug endpoint listing the latest registration of each validator.
+pub const REGISTRATIONS_DEBUG_PATH: &str = "/debug/registrations";
+
+/// Maximum number of retries when forwarding validator registrations to a relay.
+const REGISTRATION_RETRIES: usize = 3;
+
+/// Interval after which unchanged validator registrations are forwarded to the relays again.
+const REGISTRATION_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Part of the `getHeader` deadline kept to fetch the locally built payload, in case the relay
+/// doesn't answer in time.
+const LOCAL_PAYLOAD_FETCH_BUDGET: Duration = Duration::from_millis(300);
+
+/// Interval between the keep-alive comments sent to the subscribers of the constraints stream.
+const CONSTRAINTS_STREAM_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// The route labels of the builder proxy timeouts metric.
+const GET_HEADER_ROUTE: &str = "get_header";
+const GET_PAYLOAD_ROUTE: &str = "get_payload";
+const REGISTER_VALIDATORS_ROUTE: &str = "register_validators";
+
+/// The deadlines of the builder proxy requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuilderProxyTimeouts {
+    /// Deadline for answering `getHeader` requests, including the local payload fallback.
+    pub get_header: Duration,
+    /// Deadline for fetching the payload from the relay.
+    pub get_payload: Duration,
+    /// Deadline for forwarding validator registrations to each relay.
+    pub register_validator: Duration,
+}
+
+impl Default for BuilderProxyTimeouts {
+    fn default() -> Self {
+        Self {
+            get_header: Duration::from_millis(DEFAULT_BUILDER_GET_HEADER_TIMEOUT_MS),
+            get_payload: Duration::from_millis(DEFAULT_BUILDER_GET_PAYLOAD_TIMEOUT_MS),
+            register_validator: Duration::from_millis(
+                DEFAULT_BUILDER_REGISTER_VALIDATOR_TIMEOUT_MS,
+            ),
+        }
+    }
+}
 
 /// A proxy server for the builder API.
 /// Forwards all requests to the target after interception.
@@ -46,6 +104,41 @@ pub struct BuilderProxyServer<T, P> {
     local_payload: Mutex<Option<GetPayloadResponse>>,
     /// The payload fetcher to get locally built payloads.
     payload_fetcher: P,
+    /// The named relays to forward validator registrations to. If empty, they are only
+    /// forwarded to the proxy target.
+    registration_relays: Vec<(String, T)>,
+    /// The latest registration of each validator, by pubkey.
+    registrations: Mutex<HashMap<BlsPublicKey, CachedRegistration>>,
+    /// The deadlines of the requests.
+    timeouts: BuilderProxyTimeouts,
+    /// The stream of the accepted constraints, served to builders.
+    constraints_stream: ConstraintsStream,
+    /// The block templates whose constraints are served to builders, if any.
+    templates: Option<Arc<dyn TemplateReader>>,
+    /// The gas limits of the cached registrations, targeted by the local payloads.
+    registered_gas_limits: RegisteredGasLimits,
+}
+
+/// A validator registration, along with the last time it was forwarded to the relays.
+#[derive(Debug)]
+struct CachedRegistration {
+    registration: SignedValidatorRegistration,
+    forwarded_at: Option<Instant>,
+}
+
+/// A cached validator registration, as served by the debug endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistrationInfo {
+    /// The validator public key.
+    pub pubkey: BlsPublicKey,
+    /// The fee recipient of the validator.
+    pub fee_recipient: ExecutionAddress,
+    /// The preferred gas limit of the validator.
+    #[serde(with = "as_str")]
+    pub gas_limit: u64,
+    /// The timestamp of the registration.
+    #[serde(with = "as_str")]
+    pub timestamp: u64,
 }
 
 /// Parameters for the get_header request.
@@ -58,6 +151,14 @@ pub struct GetHeaderParams {
     pub public_key: BlsPublicKey,
 }
 
+/// Parameters for the get_constraints request.
+#[derive(Debug, Deserialize)]
+pub struct GetConstraintsParams {
+    /// The slot to get the constraints of: either a slot number, or relative to the head
+    /// as in `head+1`.
+    pub slot: String,
+}
+
 impl<T, P> BuilderProxyServer<T, P>
 where
     T: ConstraintsApi,
@@ -65,7 +166,50 @@ where
 {
     /// Create a new builder proxy server.
     pub fn new(proxy_target: T, payload_fetcher: P) -> Self {
-        Self { proxy_target, local_payload: Mutex::new(None), payload_fetcher }
+        Self {
+            proxy_target,
+            local_payload: Mutex::new(None),
+            payload_fetcher,
+            registration_relays: Vec::new(),
+            registrations: Mutex::new(HashMap::new()),
+            timeouts: BuilderProxyTimeouts::default(),
+            constraints_stream: ConstraintsStream::default(),
+            templates: None,
+            registered_gas_limits: RegisteredGasLimits::default(),
+        }
+    }
+
+    /// Set the block templates whose constraints are served to builders.
+    pub fn with_templates(mut self, templates: Arc<dyn TemplateReader>) -> Self {
+        self.templates = Some(templates);
+        self
+    }
+
+    /// Set the stream of the accepted constraints to serve to builders.
+    pub fn with_constraints_stream(mut self, constraints_stream: ConstraintsStream) -> Self {
+        self.constraints_stream = constraints_stream;
+        self
+    }
+
+    /// Set the handle to record the gas limits of the cached registrations in.
+    pub fn with_registered_gas_limits(
+        mut self,
+        registered_gas_limits: RegisteredGasLimits,
+    ) -> Self {
+        self.registered_gas_limits = registered_gas_limits;
+        self
+    }
+
+    /// Set the deadlines of the requests.
+    pub fn with_timeouts(mut self, timeouts: BuilderProxyTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Set the named relays to forward validator registrations to, instead of the proxy target.
+    pub fn with_registration_relays(mut self, relays: Vec<(String, T)>) -> Self {
+        self.registration_relays = relays;
+        self
     }
 
     /// Gets the status. Just forwards the request to constraints client and returns the status.
@@ -87,68 +231,249 @@ where
         status
     }
 
-    /// Registers the validators. Just forwards the request to constraints client
-    /// and returns the status.
+    /// Registers the validators, forwarding the registrations to all the relays with retries.
+    ///
+    /// Registrations that didn't change since they were last forwarded are skipped, unless they
+    /// are older than [REGISTRATION_REFRESH_INTERVAL]. The request succeeds if at least one
+    /// relay accepted them within the registration deadline.
     ///
     /// TODO: intercept this to register Bolt validators on-chain as well.
     pub async fn register_validators(
         State(server): State<Arc<BuilderProxyServer<T, P>>>,
-        Json(registrations): Json<Vec<SignedValidatorRegistration>>,
+        body: Bytes,
     ) -> Result<StatusCode, BuilderApiError> {
-        debug!("Received register validators request");
-        let response = server.proxy_target.register_validators(registrations).await;
-        response.map(|_| StatusCode::OK)
+        let registrations = serde_json::from_slice::<Vec<SignedValidatorRegistration>>(&body)
+            .map_err(|e| {
+                warn!(error = %e, "Received malformed validator registrations");
+                e
+            })?;
+        debug!(count = registrations.len(), "Received register validators request");
+
+        let to_forward = server.cache_registrations(registrations);
+        if to_forward.is_empty() {
+            debug!("All validator registrations are up to date, skipping forwarding");
+            return Ok(StatusCode::OK);
+        }
+
+        let relays = if server.registration_relays.is_empty() {
+            vec![("constraints-api", &server.proxy_target)]
+        } else {
+            server.registration_relays.iter().map(|(name, relay)| (name.as_str(), relay)).collect()
+        };
+
+        let deadline = server.timeouts.register_validator;
+        let results = join_all(relays.into_iter().map(|(name, relay)| {
+            let registrations = &to_forward;
+            async move {
+                let forward = retry_with_backoff(REGISTRATION_RETRIES, || {
+                    relay.register_validators(registrations.clone())
+                });
+                let result = timeout(deadline, forward).await.unwrap_or_else(|elapsed| {
+                    ApiMetrics::increment_builder_proxy_timeouts(REGISTER_VALIDATORS_ROUTE);
+                    Err(elapsed.into())
+                });
+                (name, result)
+            }
+        }))
+        .await;
+
+        let mut succeeded = 0;
+        let mut last_error = None;
+        for (relay, result) in results {
+            match result {
+                Ok(()) => {
+                    debug!(relay, count = to_forward.len(), "Forwarded validator registrations");
+                    succeeded += 1;
+                }
+                Err(err) => {
+                    warn!(relay, ?err, "Failed to forward validator registrations");
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        // Only fail the request if none of the relays accepted the registrations
+        match last_error {
+            Some(err) if succeeded == 0 => Err(err),
+            _ => {
+                server.mark_forwarded(&to_forward);
+                Ok(StatusCode::OK)
+            }
+        }
+    }
+
+    /// Streams the accepted constraints to a builder as server-sent events, starting with the
+    /// constraints of the upcoming slots. Each event holds a list of signed constraints.
+    pub async fn constraints_stream(
+        State(server): State<Arc<BuilderProxyServer<T, P>>>,
+    ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+        let mut subscription = server.constraints_stream.subscribe();
+        let replay = std::mem::take(&mut subscription.replay);
+        info!(replayed = replay.len(), "Builder subscribed to the constraints stream");
+
+        // The subscription is dropped along with the stream when the builder disconnects
+        let live = stream::unfold(subscription, |mut subscription| async move {
+            subscription.recv().await.map(|signed_constraints| (signed_constraints, subscription))
+        });
+        let events = stream::iter(replay).chain(live).map(|signed_constraints| {
+            let data = serde_json::to_string(&[signed_constraints]).unwrap_or_default();
+            Ok(Event::default().data(data))
+        });
+
+        Sse::new(events).keep_alive(KeepAlive::new().interval(CONSTRAINTS_STREAM_KEEP_ALIVE))
+    }
+
+    /// Gets the signed constraints of the block template for the requested slot, or an empty
+    /// list if there is none. Slots up to the head have been pruned and are not found.
+    pub async fn get_constraints(
+        State(server): State<Arc<BuilderProxyServer<T, P>>>,
+        Query(params): Query<GetConstraintsParams>,
+    ) -> Result<Json<BatchedSignedConstraints>, BuilderApiError> {
+        let Some(templates) = &server.templates else {
+            return Err(BuilderApiError::UnsupportedEndpoint(SUBMIT_CONSTRAINTS_PATH));
+        };
+
+        let head_slot = templates.head_slot().await;
+        let slot = resolve_slot(&params.slot, head_slot)?;
+        if slot <= head_slot {
+            return Err(BuilderApiError::SlotPruned(slot));
+        }
+
+        let signed_constraints = templates.signed_constraints(slot).await;
+        debug!(slot, count = signed_constraints.len(), "Returning constraints of slot");
+
+        Ok(Json(signed_constraints))
+    }
+
+    /// Lists the latest registration of each validator.
+    pub async fn list_registrations(
+        State(server): State<Arc<BuilderProxyServer<T, P>>>,
+    ) -> Json<Vec<RegistrationInfo>> {
+        let registrations = server.registrations.lock();
+        let mut list = registrations
+            .values()
+            .map(|cached| {
+                let message = &cached.registration.message;
+                RegistrationInfo {
+                    pubkey: message.public_key.clone(),
+                    fee_recipient: message.fee_recipient.clone(),
+                    gas_limit: message.gas_limit,
+                    timestamp: message.timestamp,
+                }
+            })
+            .collect::<Vec<_>>();
+        list.sort_by(|a, b| a.pubkey.as_ref().cmp(b.pubkey.as_ref()));
+
+        Json(list)
+    }
+
+    /// Cache the given registrations, returning the ones that have to be forwarded because
+    /// they are new, changed, or haven't been forwarded recently.
+    fn cache_registrations(
+        &self,
+        registrations: Vec<SignedValidatorRegistration>,
+    ) -> Vec<SignedValidatorRegistration> {
+        let mut cache = self.registrations.lock();
+
+        registrations
+            .into_iter()
+            .filter(|registration| {
+                let pubkey = &registration.message.public_key;
+                let up_to_date = cache.get(pubkey).is_some_and(|cached| {
+                    cached.registration == *registration &&
+                        cached
+                            .forwarded_at
+                            .is_some_and(|at| at.elapsed() < REGISTRATION_REFRESH_INTERVAL)
+                });
+
+                if !up_to_date {
+                    self.registered_gas_limits.update(pubkey, registration.message.gas_limit);
+                    let cached = CachedRegistration {
+                        registration: registration.clone(),
+                        forwarded_at: None,
+                    };
+                    cache.insert(pubkey.clone(), cached);
+                }
+
+                !up_to_date
+            })
+            .collect()
+    }
+
+    /// Mark the given registrations as forwarded to the relays.
+    fn mark_forwarded(&self, registrations: &[SignedValidatorRegistration]) {
+        let now = Instant::now();
+        let mut cache = self.registrations.lock();
+        for registration in registrations {
+            if let Some(cached) = cache.get_mut(&registration.message.public_key) {
+                if cached.registration == *registration {
+                    cached.forwarded_at = Some(now);
+                }
+            }
+        }
     }
 
     /// Gets the header. NOTE: converts this request to a get_header_with_proofs
     /// request to the modified constraints client.
     ///
     /// In case of a builder or relay failure, we return the locally built block header
-    /// and store the actual payload so we can return it later.
+    /// and store the actual payload so we can return it later. The local bid is tagged
+    /// with the fork of its payload.
+    ///
+    /// The whole request is bounded by the `getHeader` deadline, of which the relay gets all but
+    /// [LOCAL_PAYLOAD_FETCH_BUDGET] so that the local block can still be returned in time.
     pub async fn get_header(
         State(server): State<Arc<BuilderProxyServer<T, P>>>,
         Path(params): Path<GetHeaderParams>,
-    ) -> Result<Json<VersionedValue<SignedBuilderBid>>, BuilderApiError> {
+    ) -> Result<Response, BuilderApiError> {
         let start = std::time::Instant::now();
+        let deadline = tokio::time::Instant::from_std(start) + server.timeouts.get_header;
+        let relay_budget = server.timeouts.get_header.saturating_sub(LOCAL_PAYLOAD_FETCH_BUDGET);
 
         debug!("Received get_header request");
         let slot = params.slot;
 
-        let err = match tokio::time::timeout(
-            GET_HEADER_WITH_PROOFS_TIMEOUT,
-            server.proxy_target.get_header_with_proofs(params),
-        )
-        .await
-        {
-            Ok(res) => match res {
-                Err(builder_err) => builder_err,
-                Ok(header) => {
-                    // Clear the local payload cache if we have a successful response
-                    // By definition of `server.local_payload`, this will be `Some` IFF we have
-                    // signed a local header
-                    let mut local_payload = server.local_payload.lock();
-                    *local_payload = None;
-
-                    debug!(elapsed = ?start.elapsed(), "Returning signed builder bid");
-                    return Ok(Json(header));
-                }
-            },
-            Err(err) => BuilderApiError::Timeout(err),
+        let header = timeout(relay_budget, server.proxy_target.get_header_with_proofs(params));
+        let err = match header.await {
+            Err(elapsed) => {
+                ApiMetrics::increment_builder_proxy_timeouts(GET_HEADER_ROUTE);
+                BuilderApiError::from(elapsed)
+            }
+            Ok(Err(builder_err)) => builder_err,
+            Ok(Ok(header)) => {
+                // Clear the local payload cache if we have a successful response
+                // By definition of `server.local_payload`, this will be `Some` IFF we have
+                // signed a local header
+                let mut local_payload = server.local_payload.lock();
+                *local_payload = None;
+
+                debug!(elapsed = ?start.elapsed(), "Returning signed builder bid");
+                return Ok(Json(header).into_response());
+            }
         };
 
         // On ANY error, we fall back to locally built block
         warn!(slot, elapsed = ?start.elapsed(), err = ?err, "Proxy error, fetching local payload instead");
 
-        let Some(payload_and_bid) = server.payload_fetcher.fetch_payload(slot).await else {
-            // TODO: handle failure? In this case, we don't have a fallback block
-            // which means we haven't made any commitments. This means the EL should
-            // fallback to local block building.
-            debug!("No local payload with commitments produced for slot {slot}");
-            return Err(BuilderApiError::FailedToFetchLocalPayload(slot));
-        };
+        let payload_and_bid =
+            match timeout_at(deadline, server.payload_fetcher.fetch_payload(slot)).await {
+                Ok(Some(payload_and_bid)) => payload_and_bid,
+                Ok(None) => {
+                    // TODO: handle failure? In this case, we don't have a fallback block
+                    // which means we haven't made any commitments. This means the EL should
+                    // fallback to local block building.
+                    debug!("No local payload with commitments produced for slot {slot}");
+                    return Err(BuilderApiError::FailedToFetchLocalPayload(slot));
+                }
+                Err(elapsed) => {
+                    ApiMetrics::increment_builder_proxy_timeouts(GET_HEADER_ROUTE);
+                    warn!(slot, elapsed = ?start.elapsed(), "Timed out fetching local payload");
+                    return Err(elapsed.into());
+                }
+            };
 
-        let hash = payload_and_bid.bid.message.header.block_hash.clone();
-        let number = payload_and_bid.bid.message.header.block_number;
+        let hash = payload_and_bid.bid.block_hash().clone();
+        let number = payload_and_bid.bid.block_number();
         info!(elapsed = ?start.elapsed(), %hash, "Fetched local payload for slot {slot}");
 
         {
@@ -158,14 +483,9 @@ where
             *local_payload = Some(payload_and_bid.payload);
         }
 
-        let versioned_bid = VersionedValue::<SignedBuilderBid> {
-            version: Fork::Deneb,
-            data: payload_and_bid.bid,
-            meta: Default::default(),
-        };
-
+        let versioned_bid = payload_and_bid.bid;
         info!(elapsed = ?start.elapsed(), %hash, number, ?versioned_bid, "Returning locally built header");
-        Ok(Json(versioned_bid))
+        Ok(Json(versioned_bid).into_response())
     }
 
     /// Gets the payload. If we have a locally built payload, we return it.
@@ -204,12 +524,17 @@ where
         // TODO: how do we deal with failures here? What if we submit the signed blinded block but
         // don't get a response? should we ignore the error or proceed with a local block
         // (highly risky -> equivocation risk)
-        let payload = server
-            .proxy_target
-            .get_payload(signed_blinded_block)
-            .await
-            .map(Json)
-            .map_err(|e| {
+        let payload = timeout(
+            server.timeouts.get_payload,
+            server.proxy_target.get_payload(signed_blinded_block),
+        )
+        .await
+        .unwrap_or_else(|elapsed| {
+            ApiMetrics::increment_builder_proxy_timeouts(GET_PAYLOAD_ROUTE);
+            Err(elapsed.into())
+        })
+        .map(Json)
+        .map_err(|e| {
                 error!(elapsed = ?start.elapsed(), error = %e, "Failed to get payload from constraints client");
                 e
             })?;
@@ -226,8 +551,22 @@ where
 pub struct BuilderProxyConfig {
     /// The target constraints client server.
     pub constraints_client: ConstraintsClient,
+    /// Additional relays to forward the validator registrations to.
+    pub registration_relays: Vec<ConstraintsClient>,
     /// The port on which the builder proxy should listen.
     pub server_port: u16,
+    /// Deadline for answering `getHeader` requests.
+    pub get_header_timeout: Duration,
+    /// Deadline for answering `getPayload` requests.
+    pub get_payload_timeout: Duration,
+    /// Deadline for forwarding validator registrations to each relay.
+    pub register_validator_timeout: Duration,
+    /// The stream of the accepted constraints, served to builders.
+    pub constraints_stream: ConstraintsStream,
+    /// The block templates whose constraints are served to builders, if any.
+    pub templates: Option<Arc<dyn TemplateReader>>,
+    /// The gas limits of the cached registrations, targeted by the local payloads.
+    pub registered_gas_limits: RegisteredGasLimits,
 }
 
 /// Start the builder proxy with the given payload fetcher and configuration.
@@ -244,27 +583,76 @@ where
         "Starting builder proxy..."
     );
 
-    let server = Arc::new(BuilderProxyServer::new(config.constraints_client, payload_fetcher));
+    let registration_relays = std::iter::once(config.constraints_client.clone())
+        .chain(config.registration_relays)
+        .map(|relay| (relay.target().to_string(), relay))
+        .collect();
 
-    let router = Router::new()
-        .route("/", get(index))
-        .route(STATUS_PATH, get(BuilderProxyServer::status))
-        .route(REGISTER_VALIDATORS_PATH, post(BuilderProxyServer::register_validators))
-        .route(GET_HEADER_PATH, get(BuilderProxyServer::get_header))
-        .route(GET_PAYLOAD_PATH, post(BuilderProxyServer::get_payload))
-        .with_state(server);
+    let timeouts = BuilderProxyTimeouts {
+        get_header: config.get_header_timeout,
+        get_payload: config.get_payload_timeout,
+        register_validator: config.register_validator_timeout,
+    };
+
+    let mut server = BuilderProxyServer::new(config.constraints_client, payload_fetcher)
+        .with_registration_relays(registration_relays)
+        .with_timeouts(timeouts)
+        .with_constraints_stream(config.constraints_stream)
+        .with_registered_gas_limits(config.registered_gas_limits);
+    if let Some(templates) = config.templates {
+        server = server.with_templates(templates);
+    }
 
     let addr = format!("0.0.0.0:{}", config.server_port);
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, router).await?;
+    axum::serve(listener, builder_proxy_router(Arc::new(server))).await?;
 
     Ok(())
 }
 
+/// Returns the router of the builder proxy server.
+pub(crate) fn builder_proxy_router<P>(
+    server: Arc<BuilderProxyServer<ConstraintsClient, P>>,
+) -> Router
+where
+    P: PayloadFetcher + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(index))
+        .route(STATUS_PATH, get(BuilderProxyServer::status))
+        .route(REGISTER_VALIDATORS_PATH, post(BuilderProxyServer::register_validators))
+        .route(GET_HEADER_PATH, get(BuilderProxyServer::get_header))
+        .route(GET_PAYLOAD_PATH, post(BuilderProxyServer::get_payload))
+        .route(REGISTRATIONS_DEBUG_PATH, get(BuilderProxyServer::list_registrations))
+        .route(CONSTRAINTS_STREAM_PATH, get(BuilderProxyServer::constraints_stream))
+        .route(SUBMIT_CONSTRAINTS_PATH, get(BuilderProxyServer::get_constraints))
+        .with_state(server)
+}
+
 async fn index() -> Html<&'static str> {
     Html("Hello")
 }
 
+/// Resolves the slot of a get_constraints request, given as a slot number or relative to the
+/// head slot as in `head+1`.
+///
+/// NOTE: an unencoded `+` in a query string is decoded as a space, so `head 1` is accepted too.
+fn resolve_slot(query: &str, head_slot: u64) -> Result<u64, BuilderApiError> {
+    let invalid = || BuilderApiError::InvalidSlot(query.to_string());
+
+    let Some(offset) = query.trim().strip_prefix("head") else {
+        return query.trim().parse().map_err(|_| invalid());
+    };
+
+    let offset = offset.trim_start_matches(['+', ' ']);
+    if offset.is_empty() {
+        return Ok(head_slot);
+    }
+
+    let offset = offset.parse::<u64>().map_err(|_| invalid())?;
+    head_slot.checked_add(offset).ok_or_else(invalid)
+}
+
 /// Errors that can occur when checking the integrity of a locally built payload.
 #[derive(Error, Debug, Clone)]
 #[allow(missing_docs)]
@@ -404,3 +792,244 @@ fn check_locally_built_payload_integrity(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use alloy::primitives::bytes;
+
+    use super::*;
+    use crate::{
+        api::mock_relay::{MockRelayBehavior, ADMIN_REGISTRATIONS_PATH},
+        builder::payload_fetcher::NoopPayloadFetcher,
+        config::RelayOpts,
+        primitives::{
+            read_signed_delegations_from_file, ConstraintsMessage, FullTransaction, PayloadAndBid,
+            PayloadAndBlobs, SignedBuilderBid, SignedConstraints, VersionedSignedBuilderBid,
+        },
+        test_util::launch_mock_relay,
+    };
+
+    /// A payload fetcher returning a local payload and bid after the given delay.
+    #[derive(Debug)]
+    struct DelayedPayloadFetcher(Duration);
+
+    #[async_trait::async_trait]
+    impl PayloadFetcher for DelayedPayloadFetcher {
+        async fn fetch_payload(&self, _slot: u64) -> Option<PayloadAndBid> {
+            tokio::time::sleep(self.0).await;
+
+            let mut bid = SignedBuilderBid::default();
+            bid.message.header.block_number = 42;
+            let payload = GetPayloadResponse::Deneb(PayloadAndBlobs::default());
+            Some(PayloadAndBid { bid: bid.into(), payload })
+        }
+    }
+
+    /// Block templates holding the given constraints, by slot.
+    #[derive(Debug)]
+    struct TestTemplates {
+        head_slot: u64,
+        constraints: HashMap<u64, BatchedSignedConstraints>,
+    }
+
+    #[async_trait::async_trait]
+    impl TemplateReader for TestTemplates {
+        async fn head_slot(&self) -> u64 {
+            self.head_slot
+        }
+
+        async fn signed_constraints(&self, slot: u64) -> BatchedSignedConstraints {
+            self.constraints.get(&slot).cloned().unwrap_or_default()
+        }
+    }
+
+    fn test_registrations() -> Vec<SignedValidatorRegistration> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/delegations.json");
+        let delegation = read_signed_delegations_from_file(&path).unwrap().remove(0);
+
+        [delegation.message.validator_pubkey, delegation.message.delegatee_pubkey]
+            .into_iter()
+            .map(|pubkey| {
+                let mut registration = SignedValidatorRegistration::default();
+                registration.message.public_key = pubkey;
+                registration.message.gas_limit = 30_000_000;
+                registration.message.timestamp = 1_700_000_000;
+                registration
+            })
+            .collect()
+    }
+
+    async fn relay_registrations(url: &reqwest::Url) -> Vec<SignedValidatorRegistration> {
+        let url = url.join(ADMIN_REGISTRATIONS_PATH).unwrap();
+        reqwest::get(url).await.unwrap().json().await.unwrap()
+    }
+
+    fn body(registrations: &[SignedValidatorRegistration]) -> Bytes {
+        serde_json::to_vec(registrations).unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn test_forward_registrations_to_relays() -> eyre::Result<()> {
+        let (healthy, _handle) = launch_mock_relay(MockRelayBehavior::default()).await?;
+        let behavior = MockRelayBehavior { failure_rate: 1.0, ..Default::default() };
+        let (failing, _handle) = launch_mock_relay(behavior).await?;
+
+        let relays = [&healthy, &failing]
+            .into_iter()
+            .map(|url| (url.to_string(), ConstraintsClient::new(url.clone())))
+            .collect();
+        let client = ConstraintsClient::new(healthy.clone());
+        let gas_limits = RegisteredGasLimits::default();
+        let server = Arc::new(
+            BuilderProxyServer::new(client, NoopPayloadFetcher)
+                .with_registration_relays(relays)
+                .with_registered_gas_limits(gas_limits.clone()),
+        );
+
+        // The failing relay doesn't fail the request
+        let mut registrations = test_registrations();
+        let status =
+            BuilderProxyServer::register_validators(State(server.clone()), body(&registrations))
+                .await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(relay_registrations(&healthy).await, registrations);
+
+        // Unchanged registrations are not forwarded again, changed ones are
+        BuilderProxyServer::register_validators(State(server.clone()), body(&registrations))
+            .await?;
+        assert_eq!(relay_registrations(&healthy).await.len(), 2);
+
+        registrations[1].message.gas_limit = 36_000_000;
+        BuilderProxyServer::register_validators(State(server.clone()), body(&registrations))
+            .await?;
+        assert_eq!(relay_registrations(&healthy).await.len(), 3);
+        assert_eq!(relay_registrations(&healthy).await[2], registrations[1]);
+
+        // The gas limits of the cached registrations are shared with the local builder
+        assert_eq!(gas_limits.get(&registrations[0].message.public_key), Some(30_000_000));
+        assert_eq!(gas_limits.get(&registrations[1].message.public_key), Some(36_000_000));
+
+        let Json(cached) = BuilderProxyServer::list_registrations(State(server)).await;
+        assert_eq!(cached.len(), 2);
+        assert!(cached.iter().any(|info| info.pubkey == registrations[1].message.public_key &&
+            info.gas_limit == 36_000_000));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reject_registrations() -> eyre::Result<()> {
+        let behavior = MockRelayBehavior { failure_rate: 1.0, ..Default::default() };
+        let (failing, _handle) = launch_mock_relay(behavior).await?;
+        let server =
+            Arc::new(BuilderProxyServer::new(ConstraintsClient::new(failing), NoopPayloadFetcher));
+
+        // Malformed registrations are rejected before reaching the relays
+        let malformed = Bytes::from_static(br#"[{"message": {"fee_recipient": "0x01"}}]"#);
+        let res = BuilderProxyServer::register_validators(State(server.clone()), malformed).await;
+        assert!(matches!(res, Err(BuilderApiError::JsonError(_))));
+
+        // The request fails if no relay accepted the registrations, which are then
+        // forwarded again on the next request
+        let registrations = test_registrations();
+        let res =
+            BuilderProxyServer::register_validators(State(server.clone()), body(&registrations))
+                .await;
+        assert!(matches!(res, Err(BuilderApiError::FailedRegisteringValidators(_))));
+        assert_eq!(server.cache_registrations(registrations).len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_header_deadline_with_slow_relay() -> eyre::Result<()> {
+        let behavior = MockRelayBehavior { latency_ms: 2_000, ..Default::default() };
+        let (url, _handle) = launch_mock_relay(behavior).await?;
+
+        // The proxy deadline is tighter than the relay budget of the client
+        let opts = RelayOpts { get_header_timeout_ms: 5_000, ..Default::default() };
+        let timeouts =
+            BuilderProxyTimeouts { get_header: Duration::from_millis(500), ..Default::default() };
+        let params = || GetHeaderParams {
+            slot: 1,
+            parent_hash: Hash32::default(),
+            public_key: BlsPublicKey::default(),
+        };
+
+        // The local bid is returned within the deadline instead of an error
+        let client = ConstraintsClient::new(url.clone()).with_relay_opts(opts);
+        let fetcher = DelayedPayloadFetcher(Duration::from_millis(50));
+        let server = Arc::new(BuilderProxyServer::new(client, fetcher).with_timeouts(timeouts));
+
+        let start = Instant::now();
+        let res = BuilderProxyServer::get_header(State(server.clone()), Path(params())).await?;
+        assert!(start.elapsed() < timeouts.get_header);
+        let body = body::to_bytes(res.into_body(), usize::MAX).await?;
+        let bid = serde_json::from_slice::<VersionedSignedBuilderBid>(&body)?;
+        assert_eq!(bid.block_number(), 42);
+        assert!(server.local_payload.lock().is_some());
+
+        // Without a local payload in time, the request still fails within the deadline
+        let client = ConstraintsClient::new(url).with_relay_opts(opts);
+        let fetcher = DelayedPayloadFetcher(Duration::from_secs(5));
+        let server = Arc::new(BuilderProxyServer::new(client, fetcher).with_timeouts(timeouts));
+
+        let start = Instant::now();
+        let res = BuilderProxyServer::get_header(State(server), Path(params())).await;
+        assert!(matches!(res, Err(BuilderApiError::Timeout(_))));
+        assert!(start.elapsed() < timeouts.get_header + Duration::from_millis(100));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_constraints() -> eyre::Result<()> {
+        let tx_bytes = bytes!("f8678085019dc6838082520894deaddeaddeaddeaddeaddeaddeaddeaddeaddead38808360306ca06664c078fa60bd3ece050903dd295949908dd9686ec8871fa558f868e031cd39a00ed4f0b122b32b73f19230fabe6a726e2d07f84eda5beaa42a1ae1271bdee39f").to_vec();
+        let tx = FullTransaction::decode_enveloped(tx_bytes.as_slice())?;
+        let message = ConstraintsMessage::from_tx(Default::default(), 11, tx);
+        let constraints = vec![SignedConstraints { message, signature: Default::default() }];
+
+        let templates = TestTemplates {
+            head_slot: 10,
+            constraints: HashMap::from([(11, constraints.clone())]),
+        };
+        let client = ConstraintsClient::new("http://localhost:1".parse()?);
+        let server =
+            BuilderProxyServer::new(client, NoopPayloadFetcher).with_templates(Arc::new(templates));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}{SUBMIT_CONSTRAINTS_PATH}", listener.local_addr()?);
+        tokio::spawn(
+            async move { axum::serve(listener, builder_proxy_router(Arc::new(server))).await },
+        );
+
+        let get = |slot: &'static str| {
+            let url = url.clone();
+            async move { reqwest::get(format!("{url}?slot={slot}")).await }
+        };
+
+        // The constraints of the template are served with the submission format
+        for slot in ["11", "head%2B1", "head+1"] {
+            let res = get(slot).await?;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.json::<BatchedSignedConstraints>().await?, constraints);
+        }
+
+        // Slots without a template have no constraints
+        let res = get("head+2").await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.json::<BatchedSignedConstraints>().await?.is_empty());
+
+        // Slots up to the head have been pruned
+        for slot in ["10", "9", "head"] {
+            assert_eq!(get(slot).await?.status(), StatusCode::NOT_FOUND);
+        }
+
+        assert_eq!(get("next").await?.status(), StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+}
@@ -1,48 +1,177 @@
 use std::sync::Arc;
 
+use alloy::primitives::{Address, Signature};
 use axum::{
-    body::Body,
-    extract::State,
-    http::{HeaderMap, Request},
-    response::Html,
-    Json,
+    body::{Body, Bytes},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{HeaderMap, Request, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Extension, Json,
 };
-use axum_extra::extract::WithRejection;
-use serde_json::Value;
-use tracing::{debug, error, info, instrument};
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, instrument, warn, Span};
 
 use crate::{
-    api::commitments::headers::auth_from_headers,
+    api::commitments::headers::{auth_from_headers, batch_auth_from_headers},
+    client::SubmissionReport,
     common::CARGO_PKG_VERSION,
-    primitives::{commitment::SignatureError, InclusionRequest},
+    config::limits::LimitsOpts,
+    primitives::{
+        commitment::{RequestId, SignatureError},
+        InclusionRequest,
+    },
+    state::{ArchivedCommitment, LimitsUpdate, SealedSlot},
+    telemetry::health::HealthStatus,
 };
 
 use super::{
-    jsonrpc::{JsonPayload, JsonResponse},
+    jsonrpc::{parse_body, BatchEntry, JsonPayload, JsonResponse, JsonRpcBody},
+    proxy::ClientInfo,
     server::CommitmentsApiInner,
     spec::{
-        CommitmentError, CommitmentsApi, RejectionError, GET_METADATA_METHOD, GET_VERSION_METHOD,
-        REQUEST_INCLUSION_METHOD,
+        CommitmentError, CommitmentsApi, RejectionError, GET_METADATA_METHOD, GET_STATUS_METHOD,
+        GET_VERSION_METHOD, REQUEST_INCLUSION_METHOD, SEALED_SLOT_POLL_TIMEOUT,
+        VALIDATE_INCLUSION_METHOD,
     },
 };
 
-/// Handler function for the root JSON-RPC path.
-#[instrument(skip_all, name = "POST /rpc", fields(method = %payload.method))]
+/// Handler function for the root JSON-RPC path, serving single and batch requests.
+#[instrument(
+    skip_all,
+    name = "POST /rpc",
+    fields(method = tracing::field::Empty, client = %client.ip)
+)]
 pub async fn rpc_entrypoint(
     headers: HeaderMap,
+    Extension(client): Extension<ClientInfo>,
     State(api): State<Arc<CommitmentsApiInner>>,
-    WithRejection(Json(payload), _): WithRejection<Json<JsonPayload>, CommitmentError>,
-) -> Result<Json<JsonResponse>, CommitmentError> {
+    body: Bytes,
+) -> Response {
     debug!("Received new request");
 
+    let body = match parse_body(&body, api.max_batch_size()) {
+        Ok(body) => body,
+        Err(err) => return CommitmentError::InvalidJson(err).into_response(),
+    };
+
+    match body {
+        JsonRpcBody::Single(payload) => {
+            Span::current().record("method", payload.method.as_str());
+            match handle_request(&api, payload, auth_from_headers(&headers)).await {
+                Ok(response) => Json(response).into_response(),
+                Err(err) => err.into_response(),
+            }
+        }
+        JsonRpcBody::Batch(entries) => {
+            Span::current().record("method", "batch");
+            handle_batch(&api, &headers, entries).await
+        }
+    }
+}
+
+/// Handle the entries of a batch request concurrently, responding with an array of the
+/// responses in the same order. Notifications are processed, but not answered.
+///
+/// The inclusion requests of the batch are authenticated by the `signer:signature` pairs of
+/// the signature header, in the same order.
+async fn handle_batch(
+    api: &CommitmentsApiInner,
+    headers: &HeaderMap,
+    entries: Vec<BatchEntry>,
+) -> Response {
+    let is_inclusion_request = |entry: &BatchEntry| {
+        entry.payload.as_ref().is_ok_and(|payload| {
+            payload.method == REQUEST_INCLUSION_METHOD ||
+                payload.method == VALIDATE_INCLUSION_METHOD
+        })
+    };
+
+    let count = entries.iter().filter(|entry| is_inclusion_request(entry)).count();
+    let mut auths = batch_auth_from_headers(headers, count).into_iter();
+
+    debug!(len = entries.len(), inclusion_requests = count, "Received batch request");
+
+    let responses = entries.into_iter().map(|entry| {
+        let auth = match is_inclusion_request(&entry) {
+            true => auths.next().expect("one signature per inclusion request"),
+            false => Err(CommitmentError::NoSignature),
+        };
+
+        async move {
+            let BatchEntry { id, notification, payload } = entry;
+            let response = match payload {
+                Ok(payload) => handle_request(api, payload, auth).await,
+                Err(err) => Err(CommitmentError::InvalidJson(err).into()),
+            };
+
+            let response = response.unwrap_or_else(|err| err.to_json_response());
+            (!notification).then_some(JsonResponse { id, ..response })
+        }
+    });
+
+    let responses = join_all(responses).await.into_iter().flatten().collect::<Vec<_>>();
+    if responses.is_empty() {
+        // Only notifications: nothing to answer
+        return StatusCode::NO_CONTENT.into_response();
+    }
+
+    Json(responses).into_response()
+}
+
+/// An error handling a JSON-RPC request, along with the ID assigned to the request in case of
+/// an inclusion request.
+#[derive(Debug)]
+struct RequestError {
+    error: CommitmentError,
+    request_id: Option<RequestId>,
+}
+
+impl RequestError {
+    /// Returns the JSON-RPC error response of the error, without an ID, including the ID
+    /// assigned to the request if any.
+    fn to_json_response(&self) -> JsonResponse {
+        let response = self.error.to_json_response();
+        match self.request_id {
+            Some(request_id) => response.with_request_id(request_id),
+            None => response,
+        }
+    }
+}
+
+impl From<CommitmentError> for RequestError {
+    fn from(error: CommitmentError) -> Self {
+        Self { error, request_id: None }
+    }
+}
+
+impl IntoResponse for RequestError {
+    fn into_response(self) -> Response {
+        (self.error.status_code(), Json(self.to_json_response())).into_response()
+    }
+}
+
+/// Handle a single JSON-RPC request, authenticated by the given signer and signature in case
+/// of an inclusion request. Inclusion requests are assigned a new [RequestId], returned in
+/// their response.
+async fn handle_request(
+    api: &CommitmentsApiInner,
+    payload: JsonPayload,
+    auth: Result<(Address, Signature), CommitmentError>,
+) -> Result<JsonResponse, RequestError> {
     match payload.method.as_str() {
         GET_VERSION_METHOD => {
             let version_string = format!("bolt-sidecar-v{CARGO_PKG_VERSION}");
-            Ok(Json(JsonResponse {
+            Ok(JsonResponse {
                 id: payload.id,
                 result: Value::String(version_string),
                 ..Default::default()
-            }))
+            })
         }
 
         GET_METADATA_METHOD => {
@@ -51,64 +180,113 @@ pub async fn rpc_entrypoint(
                 result: serde_json::to_value(api.limits()).expect("infallible"),
                 ..Default::default()
             };
-            Ok(Json(response))
+            Ok(response)
         }
 
-        REQUEST_INCLUSION_METHOD => {
-            // Validate the authentication header and extract the signer and signature
-            let (signer, signature) = auth_from_headers(&headers).inspect_err(|e| {
-                error!("Failed to extract signature from headers: {:?}", e);
-            })?;
-
-            let Some(request_json) = payload.params.first().cloned() else {
-                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+        GET_STATUS_METHOD => {
+            let status = api.status().status(api.limits());
+            let response = JsonResponse {
+                id: payload.id,
+                result: serde_json::to_value(status).expect("infallible"),
+                ..Default::default()
             };
+            Ok(response)
+        }
 
-            // Parse the inclusion request from the parameters
-            let mut inclusion_request: InclusionRequest = serde_json::from_value(request_json)
-                .map_err(|e| RejectionError::ValidationFailed(e.to_string()))
-                .inspect_err(|e| error!("Failed to parse inclusion request: {:?}", e))?;
-
-            debug!(?inclusion_request, "New inclusion request");
-
-            // Set the signature here for later processing
-            inclusion_request.set_signature(signature);
+        REQUEST_INCLUSION_METHOD => {
+            let request_id = RequestId::random();
+            let with_id = |error| RequestError { error, request_id: Some(request_id) };
 
-            let digest = inclusion_request.digest();
-            let recovered_signer = signature.recover_address_from_prehash(&digest)?;
+            let inclusion_request = parse_inclusion_request(&payload, auth).map_err(with_id)?;
+            let inclusion_commitment =
+                api.request_inclusion(request_id, inclusion_request).await.map_err(with_id)?;
 
-            if recovered_signer != signer {
-                error!(
-                    %recovered_signer,
-                    %signer,
-                    "Recovered signer does not match the provided signer"
-                );
+            // Create the JSON-RPC response
+            let response = JsonResponse {
+                id: payload.id,
+                result: serde_json::to_value(inclusion_commitment).expect("infallible"),
+                ..Default::default()
+            };
 
-                return Err(CommitmentError::InvalidSignature(SignatureError));
-            }
+            Ok(response.with_request_id(request_id))
+        }
 
-            // Set the request signer
-            inclusion_request.set_signer(recovered_signer);
+        VALIDATE_INCLUSION_METHOD => {
+            let request_id = RequestId::random();
+            let with_id = |error| RequestError { error, request_id: Some(request_id) };
 
-            info!(signer = ?recovered_signer, %digest, "New valid inclusion request received");
-            let inclusion_commitment = api.request_inclusion(inclusion_request).await?;
+            let inclusion_request = parse_inclusion_request(&payload, auth).map_err(with_id)?;
+            let verdict = api
+                .validate_inclusion_request(request_id, inclusion_request)
+                .await
+                .map_err(with_id)?;
 
-            // Create the JSON-RPC response
             let response = JsonResponse {
                 id: payload.id,
-                result: serde_json::to_value(inclusion_commitment).expect("infallible"),
+                result: serde_json::to_value(verdict).expect("infallible"),
                 ..Default::default()
             };
 
-            Ok(Json(response))
+            Ok(response.with_request_id(request_id))
         }
         other => {
             error!("Unknown method: {}", other);
-            Err(CommitmentError::UnknownMethod)
+            Err(CommitmentError::UnknownMethod.into())
         }
     }
 }
 
+/// Parse the inclusion request in the parameters of the payload, authenticated by the given
+/// signer and signature.
+fn parse_inclusion_request(
+    payload: &JsonPayload,
+    auth: Result<(Address, Signature), CommitmentError>,
+) -> Result<InclusionRequest, CommitmentError> {
+    // Validate the authentication header and extract the signer and signature
+    let (signer, signature) = auth.inspect_err(|e| {
+        error!("Failed to extract signature from headers: {:?}", e);
+    })?;
+
+    let Some(request_json) = payload.params.first().cloned() else {
+        return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+    };
+
+    // Parse the inclusion request from the parameters
+    let mut inclusion_request: InclusionRequest = serde_json::from_value(request_json)
+        .map_err(|e| RejectionError::ValidationFailed(e.to_string()))
+        .inspect_err(|e| error!("Failed to parse inclusion request: {:?}", e))?;
+
+    if inclusion_request.is_by_hash() && !inclusion_request.txs.is_empty() {
+        let reason = "Request must contain either txs or tx_hashes, not both";
+        return Err(RejectionError::ValidationFailed(reason.to_string()).into());
+    }
+
+    debug!(?inclusion_request, "New inclusion request");
+
+    // Set the signature here for later processing
+    inclusion_request.set_signature(signature);
+
+    let digest = inclusion_request.digest();
+    let recovered_signer = signature.recover_address_from_prehash(&digest)?;
+
+    if recovered_signer != signer {
+        error!(
+            %recovered_signer,
+            %signer,
+            "Recovered signer does not match the provided signer"
+        );
+
+        return Err(CommitmentError::InvalidSignature(SignatureError));
+    }
+
+    // Set the request signer
+    inclusion_request.set_signer(recovered_signer);
+
+    info!(signer = ?recovered_signer, %digest, "New valid inclusion request received");
+
+    Ok(inclusion_request)
+}
+
 /// Not found fallback handler for all non-matched routes.
 ///
 /// This handler returns a simple 404 page.
@@ -123,3 +301,116 @@ pub async fn not_found(req: Request<Body>) -> Html<&'static str> {
 pub async fn status() -> Html<&'static str> {
     Html("OK")
 }
+
+/// Health handler, returning 200 if all the upstream endpoints of the sidecar are
+/// reachable and the operator is active and collateralized in Bolt, and 503 otherwise.
+/// Suitable for liveness probes.
+#[instrument(skip_all, name = "GET /health")]
+pub async fn health(
+    State(api): State<Arc<CommitmentsApiInner>>,
+) -> (StatusCode, Json<HealthStatus>) {
+    let status = api.health().status();
+    let code = if status.healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(status))
+}
+
+/// Long-poll handler resolving with the canonical constraint list hash of the given slot
+/// as soon as it's sealed at the commitment deadline, or immediately if it already is.
+/// Returns 204 if the slot isn't sealed before the poll timeout, in which case clients
+/// should poll again.
+#[instrument(skip_all, name = "GET /constraints/:slot/sealed", fields(slot))]
+pub async fn sealed_slot(
+    Path(slot): Path<u64>,
+    State(api): State<Arc<CommitmentsApiInner>>,
+) -> Response {
+    match api.sealed_slots().wait_sealed(slot, SEALED_SLOT_POLL_TIMEOUT).await {
+        Some(sealed) => Json(sealed).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+/// Admin handler returning the current operating limits.
+#[instrument(skip_all, name = "GET /admin/limits")]
+pub async fn get_limits(State(api): State<Arc<CommitmentsApiInner>>) -> Json<LimitsOpts> {
+    Json(api.limits())
+}
+
+/// Admin handler updating the operating limits present in the body, e.g.
+/// `{ "max_committed_gas_per_slot": 5000000 }`, and returning the new limits.
+///
+/// The new limits only apply to future requests: the constraints already accepted for
+/// upcoming slots are kept even if they exceed them.
+#[instrument(skip_all, name = "POST /admin/limits")]
+pub async fn update_limits(
+    State(api): State<Arc<CommitmentsApiInner>>,
+    Json(update): Json<LimitsUpdate>,
+) -> Result<Json<LimitsOpts>, (StatusCode, String)> {
+    info!(?update, "Updating limits");
+    api.shared_limits()
+        .update(update)
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Admin handler returning the reports of the latest constraints submissions, the most
+/// recent first, with the response of the relay to every attempt.
+#[instrument(skip_all, name = "GET /admin/submissions")]
+pub async fn submission_reports(
+    State(api): State<Arc<CommitmentsApiInner>>,
+) -> Json<Vec<SubmissionReport>> {
+    Json(api.submission_reports().latest())
+}
+
+/// Query parameters of the archived commitments endpoint.
+#[derive(Debug, Deserialize)]
+pub struct CommitmentsQuery {
+    /// The target slot of the commitments.
+    slot: u64,
+}
+
+/// Admin handler returning the signed commitments archived for the given slot, e.g.
+/// `GET /commitments?slot=123`, along with their signed constraints to settle disputes.
+#[instrument(skip_all, name = "GET /commitments", fields(slot = query.slot))]
+pub async fn archived_commitments(
+    State(api): State<Arc<CommitmentsApiInner>>,
+    Query(query): Query<CommitmentsQuery>,
+) -> Result<Json<Vec<ArchivedCommitment>>, (StatusCode, String)> {
+    let Some(archive) = api.commitment_archive() else {
+        return Err((StatusCode::NOT_FOUND, "Commitment archive is disabled".to_string()));
+    };
+
+    archive.query(query.slot).await.map(Json).map_err(|err| {
+        error!(?err, "Failed to query the commitment archive");
+        (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+    })
+}
+
+/// Websocket handler streaming the sidecar events to external builders.
+///
+/// Every event is a JSON object with an `event` name and its `data`. Currently, only
+/// `slot_sealed` events are emitted, with a [SealedSlot] as data.
+#[instrument(skip_all, name = "GET /events")]
+pub async fn events(ws: WebSocketUpgrade, State(api): State<Arc<CommitmentsApiInner>>) -> Response {
+    let sealed_slots = api.sealed_slots().subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, sealed_slots))
+}
+
+/// Forward the sealed slots to the websocket until either side is closed.
+async fn stream_events(mut socket: WebSocket, mut sealed_slots: broadcast::Receiver<SealedSlot>) {
+    loop {
+        let sealed = match sealed_slots.recv().await {
+            Ok(sealed) => sealed,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(skipped, "Websocket client lagging behind, skipped sealed slot events");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        let event = json!({ "event": "slot_sealed", "data": sealed });
+        if socket.send(Message::Text(event.to_string())).await.is_err() {
+            debug!("Websocket client disconnected");
+            return;
+        }
+    }
+}
@@ -11,10 +11,33 @@ use super::spec::{CommitmentError, SIGNATURE_HEADER};
 #[inline]
 pub fn auth_from_headers(headers: &HeaderMap) -> Result<(Address, Signature), CommitmentError> {
     let auth = headers.get(SIGNATURE_HEADER).ok_or(CommitmentError::NoSignature)?;
-
-    // Remove the "0x" prefix
     let auth = auth.to_str().map_err(|_| CommitmentError::MalformedHeader)?;
 
+    parse_auth(auth)
+}
+
+/// Extracts the signatures ([SIGNATURE_HEADER]) of the `count` inclusion requests of a batch
+/// from the HTTP headers: one `signer:signature` pair per request, comma-separated, in the
+/// order of the requests in the batch.
+///
+/// Every request gets its own result, so that a missing or malformed pair only fails the
+/// request it belongs to.
+pub fn batch_auth_from_headers(
+    headers: &HeaderMap,
+    count: usize,
+) -> Vec<Result<(Address, Signature), CommitmentError>> {
+    let auth = match headers.get(SIGNATURE_HEADER).map(|auth| auth.to_str()) {
+        Some(Ok(auth)) => auth,
+        Some(Err(_)) => return (0..count).map(|_| Err(CommitmentError::MalformedHeader)).collect(),
+        None => return (0..count).map(|_| Err(CommitmentError::NoSignature)).collect(),
+    };
+
+    let mut pairs = auth.split(',').map(|pair| parse_auth(pair.trim()));
+    (0..count).map(|_| pairs.next().unwrap_or(Err(CommitmentError::NoSignature))).collect()
+}
+
+/// Parses a `signer:signature` authentication pair.
+fn parse_auth(auth: &str) -> Result<(Address, Signature), CommitmentError> {
     let mut split = auth.split(':');
 
     let address = split.next().ok_or(CommitmentError::MalformedHeader)?;
@@ -54,4 +77,23 @@ mod test {
         assert_eq!(signature, Signature::try_from(expected_sig.as_bytes().as_ref()).unwrap());
         assert_eq!(address, addr);
     }
+
+    #[tokio::test]
+    async fn test_batch_signatures_from_headers() {
+        let hash = TxHash::random();
+        let signer = PrivateKeySigner::random();
+        let addr = signer.address();
+        let sig = signer.sign_hash(&hash).await.unwrap().as_bytes().encode_hex();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(SIGNATURE_HEADER, format!("{addr}:{sig}, {addr}:0x1234").parse().unwrap());
+
+        let auths = batch_auth_from_headers(&headers, 3);
+        assert_eq!(auths[0].as_ref().unwrap().0, addr);
+        assert!(matches!(auths[1], Err(CommitmentError::InvalidSignature(_))));
+        assert!(matches!(auths[2], Err(CommitmentError::NoSignature)));
+
+        let auths = batch_auth_from_headers(&HeaderMap::new(), 2);
+        assert!(auths.iter().all(|auth| matches!(auth, Err(CommitmentError::NoSignature))));
+    }
 }
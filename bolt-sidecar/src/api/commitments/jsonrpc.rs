@@ -1,5 +1,19 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use serde::{
+    de::{self, IgnoredAny, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 use serde_json::Value;
+use thiserror::Error;
+
+use crate::primitives::commitment::RequestId;
+
+/// The maximum nesting depth of a JSON-RPC request body.
+pub(super) const MAX_JSON_DEPTH: usize = 16;
+
+/// The maximum number of params of a JSON-RPC request.
+pub(super) const MAX_PARAMS: usize = 16;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonPayload {
@@ -36,13 +50,544 @@ impl JsonResponse {
             jsonrpc: "2.0".to_string(),
             id: None,
             result: Value::Null,
-            error: Some(JsonError { code, message }),
+            error: Some(JsonError { code, message, data: None }),
         }
     }
+
+    /// Attach structured data to the error of the response, if any.
+    pub fn with_error_data(mut self, data: Value) -> Self {
+        if let Some(error) = self.error.as_mut() {
+            error.data = Some(data);
+        }
+        self
+    }
+
+    /// Attach the ID of the trace of the request to the data of the error of the response,
+    /// if any, so that users can quote it in support requests.
+    pub fn with_trace_id(mut self, trace_id: String) -> Self {
+        if let Some(error) = self.error.as_mut() {
+            let data = error.data.get_or_insert_with(|| Value::Object(Default::default()));
+            if let Value::Object(data) = data {
+                data.insert("trace_id".to_string(), Value::String(trace_id));
+            }
+        }
+        self
+    }
+
+    /// Attach the ID assigned to the request to the data of the error of the response, or to
+    /// its result object on success, so that users can correlate it with the sidecar logs.
+    pub fn with_request_id(mut self, request_id: RequestId) -> Self {
+        let target = match self.error.as_mut() {
+            Some(error) => error.data.get_or_insert_with(|| Value::Object(Default::default())),
+            None => &mut self.result,
+        };
+        if let Value::Object(target) = target {
+            target.insert("request_id".to_string(), Value::String(request_id.to_string()));
+        }
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonError {
     pub code: i32,
     pub message: String,
+    /// Additional structured information about the error.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub data: Option<Value>,
+}
+
+/// Error parsing a JSON-RPC request, following the error codes of the JSON-RPC spec.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum JsonRpcParseError {
+    /// The body is not valid JSON.
+    #[error("Parse error: {0}")]
+    Parse(String),
+    /// The body is valid JSON, but not a valid request object.
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    /// The request params are invalid.
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+    /// The batch request has more entries than allowed.
+    #[error("Invalid request: batch of {len} requests exceeds the maximum of {max}")]
+    BatchTooLarge {
+        /// The number of requests in the batch.
+        len: usize,
+        /// The maximum number of requests in a batch.
+        max: usize,
+    },
+}
+
+impl JsonRpcParseError {
+    /// Returns the JSON-RPC error code of the error.
+    pub const fn code(&self) -> i32 {
+        match self {
+            Self::Parse(_) => -32700,
+            Self::InvalidRequest(_) | Self::BatchTooLarge { .. } => -32600,
+            Self::InvalidParams(_) => -32602,
+        }
+    }
+}
+
+/// A JSON-RPC request body: either a single request, or a batch of requests.
+#[derive(Debug)]
+pub enum JsonRpcBody {
+    /// A single request object.
+    Single(JsonPayload),
+    /// A batch of request objects, in the order they were received.
+    Batch(Vec<BatchEntry>),
+}
+
+/// An entry of a batch request. Entries are parsed independently: an invalid entry doesn't
+/// fail the others.
+#[derive(Debug)]
+pub struct BatchEntry {
+    /// The ID of the entry, if it could be read.
+    pub id: Option<Value>,
+    /// Whether the entry is a notification, i.e. it has no ID, and must not be answered.
+    pub notification: bool,
+    /// The request, or the reason why it's invalid.
+    pub payload: Result<JsonPayload, JsonRpcParseError>,
+}
+
+/// The request object as received, with its params bounded to [MAX_PARAMS].
+#[derive(Deserialize)]
+struct RawPayload {
+    jsonrpc: String,
+    method: String,
+    id: Option<Value>,
+    params: BoundedParams,
+}
+
+/// Request params, keeping at most [MAX_PARAMS] values. The elements past the cap are
+/// skipped without being deserialized, but counted.
+struct BoundedParams {
+    values: Vec<Value>,
+    len: usize,
+}
+
+impl<'de> Deserialize<'de> for BoundedParams {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BoundedParamsVisitor;
+
+        impl<'de> Visitor<'de> for BoundedParamsVisitor {
+            type Value = BoundedParams;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an array of params")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut values = Vec::new();
+                let mut len = 0;
+
+                while len < MAX_PARAMS {
+                    match seq.next_element::<Value>()? {
+                        Some(value) => values.push(value),
+                        None => return Ok(BoundedParams { values, len }),
+                    }
+                    len += 1;
+                }
+
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    len += 1;
+                }
+
+                Ok(BoundedParams { values, len })
+            }
+        }
+
+        deserializer.deserialize_seq(BoundedParamsVisitor)
+    }
+}
+
+/// Parse a JSON-RPC request or a batch of at most `max_batch_size` requests from the given
+/// body.
+///
+/// The nesting depth of the body is checked before any deserialization, and the params
+/// past [MAX_PARAMS] are never deserialized.
+pub fn parse_body(body: &[u8], max_batch_size: usize) -> Result<JsonRpcBody, JsonRpcParseError> {
+    if exceeds_depth(body, MAX_JSON_DEPTH) {
+        return Err(JsonRpcParseError::InvalidRequest(format!(
+            "nesting depth exceeds {MAX_JSON_DEPTH}"
+        )));
+    }
+
+    // Validate the syntax without allocating, to tell parse errors from invalid requests
+    serde_json::from_slice::<IgnoredAny>(body)
+        .map_err(|e| JsonRpcParseError::Parse(e.to_string()))?;
+
+    if body.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'[') {
+        return parse_batch(body, max_batch_size).map(JsonRpcBody::Batch);
+    }
+
+    let raw = serde_json::from_slice::<RawPayload>(body)
+        .map_err(|e| JsonRpcParseError::InvalidRequest(e.to_string()))?;
+
+    validate_payload(raw).map(JsonRpcBody::Single)
+}
+
+/// Parse the entries of a batch request. The batch itself is invalid if it's empty or has
+/// more than `max_batch_size` entries.
+fn parse_batch(body: &[u8], max_batch_size: usize) -> Result<Vec<BatchEntry>, JsonRpcParseError> {
+    let values = serde_json::from_slice::<Vec<Value>>(body)
+        .map_err(|e| JsonRpcParseError::InvalidRequest(e.to_string()))?;
+
+    if values.is_empty() {
+        return Err(JsonRpcParseError::InvalidRequest("empty batch".to_string()));
+    }
+
+    if values.len() > max_batch_size {
+        return Err(JsonRpcParseError::BatchTooLarge { len: values.len(), max: max_batch_size });
+    }
+
+    let entries = values
+        .into_iter()
+        .map(|value| {
+            let id = value.get("id").cloned();
+            let notification = value.as_object().is_some_and(|object| !object.contains_key("id"));
+            let payload = serde_json::from_value::<RawPayload>(value)
+                .map_err(|e| JsonRpcParseError::InvalidRequest(e.to_string()))
+                .and_then(validate_payload);
+
+            BatchEntry { id, notification, payload }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Validate the version and the number of params of a request object.
+fn validate_payload(raw: RawPayload) -> Result<JsonPayload, JsonRpcParseError> {
+    if raw.jsonrpc != "2.0" {
+        return Err(JsonRpcParseError::InvalidRequest(format!(
+            "unsupported jsonrpc version: {}",
+            raw.jsonrpc
+        )));
+    }
+
+    if raw.params.len > MAX_PARAMS {
+        return Err(JsonRpcParseError::InvalidParams(format!(
+            "got {} params, the maximum is {MAX_PARAMS}",
+            raw.params.len
+        )));
+    }
+
+    Ok(JsonPayload {
+        jsonrpc: raw.jsonrpc,
+        method: raw.method,
+        id: raw.id,
+        params: raw.params.values,
+    })
+}
+
+/// Returns true if the nesting depth of the given JSON exceeds `max`, by counting the
+/// opening and closing brackets outside of strings. Doesn't validate the JSON.
+fn exceeds_depth(body: &[u8], max: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use serde_json::json;
+
+    use super::*;
+
+    /// Parse a single request, failing on batches.
+    fn parse_request(body: &[u8]) -> Result<JsonPayload, JsonRpcParseError> {
+        match parse_body(body, usize::MAX)? {
+            JsonRpcBody::Single(payload) => Ok(payload),
+            JsonRpcBody::Batch(_) => panic!("unexpected batch"),
+        }
+    }
+
+    fn request(params: Value) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": params
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_valid_request() {
+        let payload = parse_request(&request(json!([{ "slot": 10 }]))).unwrap();
+        assert_eq!(payload.method, "bolt_requestInclusion");
+        assert_eq!(payload.id, Some(json!(1)));
+        assert_eq!(payload.params, vec![json!({ "slot": 10 })]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_request(b"{\"jsonrpc\": \"2.0\",").unwrap_err();
+        assert_eq!(err.code(), -32700);
+
+        let err = parse_request(b"").unwrap_err();
+        assert_eq!(err.code(), -32700);
+
+        // Valid JSON, but not a request object
+        let err = parse_request(b"{\"method\": 1}").unwrap_err();
+        assert_eq!(err.code(), -32600);
+
+        let err = parse_request(b"\"bolt_requestInclusion\"").unwrap_err();
+        assert_eq!(err.code(), -32600);
+
+        let body = String::from_utf8(request(json!([]))).unwrap().replace("2.0", "1.0");
+        assert_eq!(parse_request(body.as_bytes()).unwrap_err().code(), -32600);
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let single = serde_json::from_slice::<Value>(&request(json!([]))).unwrap();
+        let notification = json!({ "jsonrpc": "2.0", "method": "bolt_getVersion", "params": [] });
+        let body = json!([single, { "jsonrpc": "2.0", "id": "a" }, notification, 42]);
+
+        let JsonRpcBody::Batch(entries) = parse_body(body.to_string().as_bytes(), 4).unwrap()
+        else {
+            panic!("expected a batch");
+        };
+
+        // Every entry is parsed on its own
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].id, Some(json!(1)));
+        assert_eq!(entries[0].payload.as_ref().unwrap().method, "bolt_requestInclusion");
+        assert_eq!(entries[1].id, Some(json!("a")));
+        assert_eq!(entries[1].payload.as_ref().unwrap_err().code(), -32600);
+        assert!(entries[2].notification && entries[2].payload.is_ok());
+        assert!(!entries[3].notification && entries[3].id.is_none());
+        assert_eq!(entries[3].payload.as_ref().unwrap_err().code(), -32600);
+
+        // Oversized batches are rejected as a whole
+        let err = parse_body(body.to_string().as_bytes(), 3).unwrap_err();
+        assert_eq!(err, JsonRpcParseError::BatchTooLarge { len: 4, max: 3 });
+        assert_eq!(err.code(), -32600);
+
+        // Empty batches too, even with leading whitespace
+        assert_eq!(parse_body(b"  \n[]", 4).unwrap_err().code(), -32600);
+    }
+
+    #[test]
+    fn test_nesting_depth_cap() {
+        let nested = |depth: usize| {
+            let mut value = json!(1);
+            for _ in 0..depth {
+                value = json!([value]);
+            }
+            value
+        };
+
+        // The request object accounts for 1 level, on top of the params array
+        assert!(parse_request(&request(nested(MAX_JSON_DEPTH - 1))).is_ok());
+
+        let err = parse_request(&request(nested(MAX_JSON_DEPTH))).unwrap_err();
+        assert_eq!(err.code(), -32600);
+
+        // Way deeper than the serde_json recursion limit, and not even valid JSON
+        let body = "[".repeat(100_000);
+        assert_eq!(parse_request(body.as_bytes()).unwrap_err().code(), -32600);
+
+        // Brackets in strings don't count
+        let params = json!(["[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[\\\"{{{{{{{{{{{{{{{{{{{{{{{{{{{{"]);
+        assert!(parse_request(&request(params)).is_ok());
+    }
+
+    #[test]
+    fn test_params_cap() {
+        let params = Value::Array(vec![json!(0); MAX_PARAMS]);
+        assert_eq!(parse_request(&request(params)).unwrap().params.len(), MAX_PARAMS);
+
+        let params = Value::Array(vec![json!({ "slot": 1 }); 10_000]);
+        let err = parse_request(&request(params)).unwrap_err();
+        assert_eq!(
+            err,
+            JsonRpcParseError::InvalidParams(format!(
+                "got 10000 params, the maximum is {MAX_PARAMS}"
+            ))
+        );
+        assert_eq!(err.code(), -32602);
+    }
+
+    /// Regression corpus of malformed requests, plus random bytes and random mutations of a
+    /// valid request from a fixed seed. The parser must never panic.
+    #[test]
+    fn test_parse_request_fuzz_corpus() {
+        let valid = request(json!([{ "slot": 10, "txs": ["0x02f86c"] }]));
+
+        let mut corpus: Vec<Vec<u8>> = [
+            "",
+            " ",
+            "null",
+            "[]",
+            "{}",
+            "[[[[",
+            "]]]]",
+            "}{",
+            "\"",
+            "\"\\",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"bolt_requestInclusion\",\"params\":{}}",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"bolt_requestInclusion\",\"params\":null}",
+            "{\"jsonrpc\":\"2.0\",\"method\":null,\"params\":[]}",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"m\",\"params\":[1e999999]}",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"m\",\"params\":[\"\\ud800\"]}",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"m\",\"params\":[],\"params\":[]}",
+        ]
+        .iter()
+        .map(|s| s.as_bytes().to_vec())
+        .collect();
+
+        corpus.push(vec![0xff; 64]);
+        corpus.extend((0..valid.len()).map(|i| valid[..i].to_vec()));
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..2_000 {
+            let len = rng.gen_range(0..256);
+            corpus.push((0..len).map(|_| rng.gen()).collect());
+
+            let mut mutated = valid.clone();
+            for _ in 0..rng.gen_range(1..8) {
+                let index = rng.gen_range(0..mutated.len());
+                mutated[index] = rng.gen();
+            }
+            corpus.push(mutated);
+        }
+
+        for input in corpus {
+            let _ = parse_body(&input, 4);
+        }
+    }
+
+    /// Returns the nesting depth of the given JSON value, scalars being at depth 0.
+    fn depth(value: &Value) -> usize {
+        match value {
+            Value::Array(values) => 1 + values.iter().map(depth).max().unwrap_or(0),
+            Value::Object(map) => 1 + map.values().map(depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Arbitrary JSON values, nested deep enough to cross [MAX_JSON_DEPTH], with brackets
+    /// and escapes in strings.
+    fn json_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            "[\\[\\]{}\"\\\\a-z0-9]{0,8}".prop_map(Value::from),
+        ];
+
+        leaf.prop_recursive(2 * MAX_JSON_DEPTH as u32, 256, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::btree_map("[a-z]{1,4}", inner, 0..4)
+                    .prop_map(|map| Value::Object(map.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// Checks that whatever the parser accepts is within the depth and params limits.
+    fn check_limits(body: &[u8]) -> Result<(), TestCaseError> {
+        let Ok(parsed) = parse_body(body, 4) else {
+            return Ok(());
+        };
+
+        let value = serde_json::from_slice::<Value>(body).expect("accepted bodies are valid JSON");
+        prop_assert!(depth(&value) <= MAX_JSON_DEPTH);
+
+        // Pair the accepted requests with their params as received
+        let accepted = match parsed {
+            JsonRpcBody::Single(payload) => vec![(payload, &value["params"])],
+            JsonRpcBody::Batch(entries) => entries
+                .into_iter()
+                .zip(value.as_array().expect("batches are arrays"))
+                .filter_map(|(entry, request)| Some((entry.payload.ok()?, &request["params"])))
+                .collect(),
+        };
+        for (payload, params) in accepted {
+            prop_assert!(payload.params.len() <= MAX_PARAMS);
+            prop_assert_eq!(Some(&payload.params), params.as_array());
+        }
+
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_arbitrary_bytes(body in prop::collection::vec(any::<u8>(), 0..512)) {
+            check_limits(&body)?;
+        }
+
+        #[test]
+        fn test_parse_mutated_request(
+            mutations in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..8)
+        ) {
+            let mut body = request(json!([{ "slot": 10, "txs": ["0x02f86c"] }]));
+            for (index, byte) in mutations {
+                let index = index.index(body.len());
+                body[index] = byte;
+            }
+
+            check_limits(&body)?;
+        }
+
+        #[test]
+        fn test_parse_request_limits(
+            params in prop::collection::vec(json_value(), 0..2 * MAX_PARAMS)
+        ) {
+            let len = params.len();
+            let params = Value::Array(params);
+            // The request object accounts for 1 level, on top of the params array
+            let too_deep = 1 + depth(&params) > MAX_JSON_DEPTH;
+
+            match parse_body(&request(params.clone()), 4) {
+                Ok(JsonRpcBody::Single(payload)) => {
+                    prop_assert!(!too_deep && len <= MAX_PARAMS);
+                    prop_assert_eq!(Value::Array(payload.params), params);
+                }
+                Ok(JsonRpcBody::Batch(_)) => prop_assert!(false, "unexpected batch"),
+                Err(err) if too_deep => prop_assert_eq!(err.code(), -32600),
+                Err(err) => {
+                    prop_assert!(len > MAX_PARAMS);
+                    prop_assert_eq!(
+                        err,
+                        JsonRpcParseError::InvalidParams(format!(
+                            "got {len} params, the maximum is {MAX_PARAMS}"
+                        ))
+                    );
+                }
+            }
+        }
+    }
 }
@@ -1,6 +1,18 @@
-use crate::telemetry::ApiMetrics;
-use axum::{extract::Request, middleware::Next, response::IntoResponse};
-use std::time::Instant;
+use crate::{config::api::ApiOpts, telemetry::ApiMetrics};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Instant,
+};
+use tracing::{debug, warn};
+
+use super::proxy::ClientInfo;
 
 /// Middleware to track server metrics for each request.
 pub async fn track_server_metrics(req: Request, next: Next) -> impl IntoResponse {
@@ -16,3 +28,54 @@ pub async fn track_server_metrics(req: Request, next: Next) -> impl IntoResponse
 
     response
 }
+
+/// Middleware to resolve the [ClientInfo] of each request from the connection and the
+/// forwarding headers set by trusted proxies, making it available to the handlers.
+pub async fn resolve_client_info(
+    State(opts): State<Arc<ApiOpts>>,
+    mut req: Request,
+    next: Next,
+) -> impl IntoResponse {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(Ipv4Addr::UNSPECIFIED.into());
+
+    let client = ClientInfo::resolve(peer, req.headers(), &opts);
+    debug!(client = %client.ip, proto = %client.proto, path = %req.uri().path(), "New request");
+
+    req.extensions_mut().insert(client);
+    next.run(req).await
+}
+
+/// Middleware to authenticate admin requests with the bearer token of the [ApiOpts].
+/// Requests are rejected if no token is configured.
+pub async fn require_admin_token(
+    State(opts): State<Arc<ApiOpts>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    let authorized = match (token, opts.admin_token.as_deref()) {
+        (Some(token), Some(expected)) => constant_time_eq(token.as_bytes(), expected.as_bytes()),
+        _ => false,
+    };
+
+    if !authorized {
+        warn!(path = %req.uri().path(), "Unauthorized admin request");
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    }
+
+    next.run(req).await
+}
+
+/// Compare two byte strings in constant time for equal lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -6,7 +6,11 @@ mod headers;
 mod jsonrpc;
 /// The commitments-API middleware.
 mod middleware;
+/// Reverse proxy support: forwarding headers and PROXY protocol.
+mod proxy;
 /// The commitments-API JSON-RPC server implementation.
 pub mod server;
 /// The commitments-API specification and errors.
 pub mod spec;
+/// The public status of the sidecar, served by the `bolt_status` method.
+pub mod status;
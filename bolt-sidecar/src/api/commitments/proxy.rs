@@ -0,0 +1,329 @@
+use std::{
+    future::Future,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::HeaderMap,
+    Router,
+};
+use hyper::{body::Incoming, service::service_fn};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto::Builder,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite},
+    net::TcpListener,
+};
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+use tracing::{debug, error, warn};
+
+use crate::config::api::ApiOpts;
+
+/// The header carrying the chain of client addresses, as set by reverse proxies.
+pub const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// The header carrying the protocol used by the client to connect to the reverse proxy.
+pub const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// The fixed signature that starts every PROXY protocol v2 header.
+pub const PROXY_V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// The maximum time to wait for a trusted proxy to send the PROXY protocol header.
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum time to wait for a client to complete the TLS handshake.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Information about the client that originated a request, resolved from the
+/// connection and, when coming from a trusted proxy, the forwarding headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    /// The IP address of the client.
+    pub ip: IpAddr,
+    /// The protocol used by the client, e.g. "http" or "https".
+    pub proto: String,
+}
+
+impl ClientInfo {
+    /// Resolve the client information of a request received from `peer`.
+    ///
+    /// The `X-Forwarded-For` and `X-Forwarded-Proto` headers are only honored if `peer`
+    /// is a trusted proxy, otherwise they are ignored as they could be spoofed.
+    /// The `X-Forwarded-For` chain is walked from right to left, skipping any trusted
+    /// proxy, so that the first untrusted hop is considered to be the client.
+    pub fn resolve(peer: IpAddr, headers: &HeaderMap, opts: &ApiOpts) -> Self {
+        let mut info = Self { ip: peer, proto: "http".to_string() };
+
+        if !opts.is_trusted_proxy(peer) {
+            return info;
+        }
+
+        let hops = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|hop| hop.trim().parse::<IpAddr>())
+            .collect::<Result<Vec<_>, _>>()
+            // A malformed chain can't be trusted at all: fall back to the peer address
+            .unwrap_or_default();
+
+        for hop in hops.iter().rev() {
+            info.ip = *hop;
+            if !opts.is_trusted_proxy(*hop) {
+                break;
+            }
+        }
+
+        if let Some(proto) = headers
+            .get(X_FORWARDED_PROTO)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(|proto| proto.trim().to_ascii_lowercase())
+            .filter(|proto| proto == "http" || proto == "https")
+        {
+            info.proto = proto;
+        }
+
+        info
+    }
+}
+
+/// Read a PROXY protocol v2 header from the given stream.
+///
+/// Returns the source address of the proxied connection, or `None` if the header doesn't
+/// carry one (e.g. `LOCAL` health-check connections from the proxy or unix sockets).
+///
+/// Reference: <https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt>
+pub async fn read_proxy_v2_header<R>(reader: &mut R) -> io::Result<Option<SocketAddr>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 16];
+    reader.read_exact(&mut header).await?;
+
+    if header[..12] != PROXY_V2_SIGNATURE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "missing PROXY v2 signature"));
+    }
+
+    let version = header[12] >> 4;
+    let command = header[12] & 0x0f;
+    if version != 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported PROXY protocol version {version}"),
+        ));
+    }
+
+    let family = header[13] >> 4;
+    let len = u16::from_be_bytes([header[14], header[15]]) as usize;
+
+    // Always consume the whole address block (including any TLVs) so that
+    // the stream is positioned at the start of the proxied payload.
+    let mut addresses = vec![0u8; len];
+    reader.read_exact(&mut addresses).await?;
+
+    match command {
+        // LOCAL: the connection was established by the proxy itself
+        0x0 => return Ok(None),
+        // PROXY: the connection was relayed on behalf of a client
+        0x1 => {}
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported PROXY protocol command {command}"),
+            ))
+        }
+    }
+
+    let too_short =
+        || io::Error::new(io::ErrorKind::InvalidData, "PROXY v2 address block too short");
+
+    match family {
+        // AF_INET: src addr (4), dst addr (4), src port (2), dst port (2)
+        0x1 => {
+            let block = addresses.get(..12).ok_or_else(too_short)?;
+            let ip = Ipv4Addr::new(block[0], block[1], block[2], block[3]);
+            let port = u16::from_be_bytes([block[8], block[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        // AF_INET6: src addr (16), dst addr (16), src port (2), dst port (2)
+        0x2 => {
+            let block = addresses.get(..36).ok_or_else(too_short)?;
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&block[..16]);
+            let port = u16::from_be_bytes([block[32], block[33]]);
+            Ok(Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port)))
+        }
+        // AF_UNSPEC or AF_UNIX: no usable source address
+        _ => Ok(None),
+    }
+}
+
+/// Serve the router on the given listener until the shutdown signal resolves, reading a
+/// PROXY protocol v2 header on every connection coming from a trusted proxy if enabled,
+/// and terminating TLS if an acceptor is given.
+///
+/// The source address carried by the PROXY header replaces the peer address in the request
+/// [ConnectInfo]. Connections from untrusted peers are served as-is: if they try to send
+/// a PROXY header themselves it won't be parsed, and the request will be rejected as
+/// malformed HTTP.
+pub async fn serve_connections<S>(
+    listener: TcpListener,
+    router: Router,
+    opts: Arc<ApiOpts>,
+    tls: Option<TlsAcceptor>,
+    signal: S,
+) where
+    S: Future<Output = ()> + Send + Unpin,
+{
+    let mut signal = signal;
+
+    loop {
+        let (mut stream, peer) = tokio::select! {
+            res = listener.accept() => match res {
+                Ok(conn) => conn,
+                Err(err) => {
+                    error!(?err, "Failed to accept connection");
+                    continue;
+                }
+            },
+            _ = &mut signal => break,
+        };
+
+        let router = router.clone();
+        let tls = tls.clone();
+        let trusted = opts.proxy_protocol && opts.is_trusted_proxy(peer.ip());
+
+        tokio::spawn(async move {
+            let client_addr = if trusted {
+                match tokio::time::timeout(PROXY_HEADER_TIMEOUT, read_proxy_v2_header(&mut stream))
+                    .await
+                {
+                    Ok(Ok(source)) => source.unwrap_or(peer),
+                    Ok(Err(err)) => {
+                        warn!(%peer, ?err, "Invalid PROXY protocol header, dropping connection");
+                        return;
+                    }
+                    Err(_) => {
+                        warn!(%peer, "Timed out waiting for PROXY protocol header");
+                        return;
+                    }
+                }
+            } else {
+                peer
+            };
+
+            let Some(tls) = tls else {
+                return serve_connection(stream, router, client_addr).await;
+            };
+
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, tls.accept(stream)).await {
+                Ok(Ok(stream)) => serve_connection(stream, router, client_addr).await,
+                Ok(Err(err)) => debug!(%client_addr, ?err, "TLS handshake failed"),
+                Err(_) => debug!(%client_addr, "Timed out waiting for TLS handshake"),
+            }
+        });
+    }
+}
+
+/// Serve the router on a single connection, with the given client address as [ConnectInfo].
+async fn serve_connection<IO>(io: IO, router: Router, client_addr: SocketAddr)
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |mut req: Request<Incoming>| {
+        req.extensions_mut().insert(ConnectInfo(client_addr));
+        router.clone().call(req)
+    });
+
+    if let Err(err) =
+        Builder::new(TokioExecutor::new()).serve_connection(TokioIo::new(io), service).await
+    {
+        debug!(?err, %client_addr, "Error serving connection");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn opts_with_trusted(cidrs: &[&str]) -> ApiOpts {
+        ApiOpts {
+            trusted_proxies: cidrs.iter().map(|c| c.parse().unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn forwarded_headers(xff: &str, proto: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(X_FORWARDED_FOR, HeaderValue::from_str(xff).unwrap());
+        headers.insert(X_FORWARDED_PROTO, HeaderValue::from_str(proto).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_forwarded_headers_from_untrusted_peer_are_ignored() {
+        let opts = opts_with_trusted(&["10.0.0.0/8"]);
+        let headers = forwarded_headers("1.2.3.4", "https");
+        let peer: IpAddr = "203.0.113.7".parse().unwrap();
+
+        let info = ClientInfo::resolve(peer, &headers, &opts);
+        assert_eq!(info, ClientInfo { ip: peer, proto: "http".to_string() });
+    }
+
+    #[test]
+    fn test_forwarded_headers_from_trusted_peer_are_honored() {
+        let opts = opts_with_trusted(&["10.0.0.0/8"]);
+        let peer: IpAddr = "10.0.0.2".parse().unwrap();
+
+        // The client tries to spoof its address by prepending a fake hop: the right-most
+        // untrusted address is the one seen by our trusted proxies, so that's the client.
+        let headers = forwarded_headers("6.6.6.6, 1.2.3.4, 10.0.0.5", "https");
+        let info = ClientInfo::resolve(peer, &headers, &opts);
+        assert_eq!(info.ip, "1.2.3.4".parse::<IpAddr>().unwrap());
+        assert_eq!(info.proto, "https");
+
+        // A malformed chain is discarded altogether
+        let headers = forwarded_headers("1.2.3.4, not-an-ip", "https");
+        let info = ClientInfo::resolve(peer, &headers, &opts);
+        assert_eq!(info.ip, peer);
+    }
+
+    #[tokio::test]
+    async fn test_read_proxy_v2_header() {
+        let payload = b"POST / HTTP/1.1\r\n";
+
+        let mut bytes = PROXY_V2_SIGNATURE.to_vec();
+        // version 2, PROXY command; TCP over IPv4; 12 bytes of addresses
+        bytes.extend_from_slice(&[0x21, 0x11, 0x00, 0x0c]);
+        bytes.extend_from_slice(&[192, 0, 2, 1, 10, 0, 0, 1]);
+        bytes.extend_from_slice(&4242u16.to_be_bytes());
+        bytes.extend_from_slice(&8017u16.to_be_bytes());
+        bytes.extend_from_slice(payload);
+
+        let mut reader = bytes.as_slice();
+        let source = read_proxy_v2_header(&mut reader).await.unwrap();
+        assert_eq!(source, Some("192.0.2.1:4242".parse().unwrap()));
+        // The stream is left at the start of the proxied payload
+        assert_eq!(reader, &payload[..]);
+
+        // LOCAL command with no addresses
+        let mut bytes = PROXY_V2_SIGNATURE.to_vec();
+        bytes.extend_from_slice(&[0x20, 0x00, 0x00, 0x00]);
+        assert_eq!(read_proxy_v2_header(&mut bytes.as_slice()).await.unwrap(), None);
+
+        // Plain HTTP without a PROXY header
+        let mut reader = &b"GET /status HTTP/1.1\r\n\r\n"[..];
+        assert!(read_proxy_v2_header(&mut reader).await.is_err());
+    }
+}
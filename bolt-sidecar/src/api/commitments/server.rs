@@ -1,45 +1,69 @@
-use std::{
-    fmt,
-    future::Future,
-    net::{SocketAddr, ToSocketAddrs},
-    pin::Pin,
-    sync::Arc,
-};
+use std::{fmt, future::Future, net::SocketAddr, pin::Pin, sync::Arc, time::Instant};
 
 use axum::{
     middleware,
     routing::{get, post},
     Router,
 };
+use rustls::ServerConfig;
 use tokio::{
     net::TcpListener,
-    sync::{mpsc, oneshot},
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot,
+    },
 };
+use tokio_rustls::TlsAcceptor;
 use tower_http::timeout::TimeoutLayer;
-use tracing::{error, info};
+use tracing::{error, info, instrument, warn, Span};
 
 use crate::{
     api::commitments::handlers,
-    config::limits::LimitsOpts,
+    client::SubmissionReports,
+    config::{
+        api::{ApiOpts, DEFAULT_MAX_BATCH_SIZE},
+        limits::LimitsOpts,
+    },
     primitives::{
-        commitment::{InclusionCommitment, SignedCommitment},
+        commitment::{InclusionCommitment, RequestId, SignedCommitment, ValidationVerdict},
         CommitmentRequest, InclusionRequest,
     },
+    state::{CommitmentArchive, SealedSlots, SharedLimits},
+    telemetry::{ApiMetrics, HealthHandle},
 };
 
 use super::{
-    middleware::track_server_metrics,
+    middleware::{require_admin_token, resolve_client_info, track_server_metrics},
+    proxy::serve_connections,
     spec,
     spec::{CommitmentError, CommitmentsApi},
+    status::StatusHandle,
 };
 
 /// Event type emitted by the commitments API.
 #[derive(Debug)]
 pub struct CommitmentEvent {
+    /// The unique ID assigned to the request.
+    pub request_id: RequestId,
     /// The request to process.
     pub request: CommitmentRequest,
     /// The response channel.
     pub response: oneshot::Sender<Result<SignedCommitment, CommitmentError>>,
+    /// The span of the request, under which it's processed.
+    pub span: Span,
+}
+
+/// Event type emitted by the commitments API for the dry-run validations of inclusion requests.
+#[derive(Debug)]
+pub struct ValidationEvent {
+    /// The unique ID assigned to the request.
+    pub request_id: RequestId,
+    /// The request to validate.
+    pub request: InclusionRequest,
+    /// The response channel.
+    pub response: oneshot::Sender<Result<ValidationVerdict, CommitmentError>>,
+    /// The span of the request, under which it's processed.
+    pub span: Span,
 }
 
 /// The inner commitments-API handler that implements the [CommitmentsApi] spec.
@@ -48,38 +72,222 @@ pub struct CommitmentEvent {
 pub struct CommitmentsApiInner {
     /// Event notification channel
     events: mpsc::Sender<CommitmentEvent>,
-    /// The sidecar's operating limits that should be exposed in a metadata endpoint
-    limits: LimitsOpts,
+    /// Channel for the dry-run validations, which are only served if set
+    validation_events: Option<mpsc::Sender<ValidationEvent>>,
+    /// The sidecar's operating limits that should be exposed in a metadata endpoint,
+    /// and can be updated through the admin endpoint
+    limits: SharedLimits,
+    /// The health of the upstream endpoints, exposed in the health endpoint
+    health: HealthHandle,
+    /// The slots sealed at the commitment deadline, exposed to external builders
+    sealed_slots: SealedSlots,
+    /// The latest reports of the constraints submissions, exposed in the admin endpoints
+    submission_reports: SubmissionReports,
+    /// The archive of the signed commitments, exposed in the admin endpoints if enabled
+    commitment_archive: Option<CommitmentArchive>,
+    /// The public identity of the sidecar and its validators, exposed in the status method
+    status: StatusHandle,
+    /// The maximum number of requests in a JSON-RPC batch
+    max_batch_size: usize,
 }
 
 impl CommitmentsApiInner {
     /// Creates a new instance of the commitments API handler.
-    pub fn new(events: mpsc::Sender<CommitmentEvent>, limits: LimitsOpts) -> Self {
-        Self { events, limits }
+    pub fn new(events: mpsc::Sender<CommitmentEvent>, limits: SharedLimits) -> Self {
+        Self {
+            events,
+            validation_events: None,
+            limits,
+            health: HealthHandle::default(),
+            sealed_slots: SealedSlots::default(),
+            submission_reports: SubmissionReports::default(),
+            commitment_archive: None,
+            status: StatusHandle::default(),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the channel to send the dry-run validations of inclusion requests to.
+    pub fn with_validation_events(mut self, events: mpsc::Sender<ValidationEvent>) -> Self {
+        self.validation_events = Some(events);
+        self
+    }
+
+    /// Sets the handle to the health status of the upstream endpoints.
+    pub fn with_health(mut self, health: HealthHandle) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Sets the registry of the slots sealed at the commitment deadline.
+    pub fn with_sealed_slots(mut self, sealed_slots: SealedSlots) -> Self {
+        self.sealed_slots = sealed_slots;
+        self
     }
 
-    /// Returns the operating limits for the sidecar.
+    /// Sets the ring buffer of the latest constraints submission reports.
+    pub fn with_submission_reports(mut self, submission_reports: SubmissionReports) -> Self {
+        self.submission_reports = submission_reports;
+        self
+    }
+
+    /// Sets the archive of the signed commitments.
+    pub fn with_commitment_archive(mut self, commitment_archive: CommitmentArchive) -> Self {
+        self.commitment_archive = Some(commitment_archive);
+        self
+    }
+
+    /// Sets the handle to the public identity of the sidecar and its validators.
+    pub fn with_status(mut self, status: StatusHandle) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Sets the maximum number of requests in a JSON-RPC batch.
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size;
+        self
+    }
+
+    /// Returns the current operating limits for the sidecar.
     pub fn limits(&self) -> LimitsOpts {
-        self.limits
+        self.limits.get()
+    }
+
+    /// Returns the shared handle to the operating limits, to update them at runtime.
+    pub fn shared_limits(&self) -> &SharedLimits {
+        &self.limits
+    }
+
+    /// Returns the handle to the health status of the upstream endpoints.
+    pub fn health(&self) -> &HealthHandle {
+        &self.health
+    }
+
+    /// Returns the registry of the slots sealed at the commitment deadline.
+    pub fn sealed_slots(&self) -> &SealedSlots {
+        &self.sealed_slots
+    }
+
+    /// Returns the latest reports of the constraints submissions.
+    pub fn submission_reports(&self) -> &SubmissionReports {
+        &self.submission_reports
+    }
+
+    /// Returns the archive of the signed commitments, if enabled.
+    pub fn commitment_archive(&self) -> Option<&CommitmentArchive> {
+        self.commitment_archive.as_ref()
+    }
+
+    /// Returns the handle to the public identity of the sidecar and its validators.
+    pub fn status(&self) -> &StatusHandle {
+        &self.status
+    }
+
+    /// Returns the maximum number of requests in a JSON-RPC batch.
+    pub fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+
+    /// Enqueue an event for the driver without waiting, failing right away if the queue
+    /// is full.
+    fn enqueue(&self, event: CommitmentEvent) -> Result<(), CommitmentError> {
+        try_enqueue(&self.events, event)?;
+        ApiMetrics::set_api_queue_depth(self.queue_depth());
+        Ok(())
+    }
+
+    /// Returns the number of events waiting in the queue.
+    fn queue_depth(&self) -> usize {
+        self.events.max_capacity() - self.events.capacity()
     }
 }
 
 #[async_trait::async_trait]
 impl CommitmentsApi for CommitmentsApiInner {
+    #[instrument(
+        skip_all,
+        name = "request_inclusion",
+        fields(%request_id, digest = %inclusion_request.digest())
+    )]
     async fn request_inclusion(
         &self,
+        request_id: RequestId,
         inclusion_request: InclusionRequest,
     ) -> Result<InclusionCommitment, CommitmentError> {
+        // The latency is recorded whatever the outcome, so that rejections are included
+        let start = Instant::now();
+        let result: Result<InclusionCommitment, CommitmentError> = async {
+            if self.health.is_waiting_for_beacon() {
+                return Err(CommitmentError::BeaconUnavailable);
+            }
+
+            let (response_tx, response_rx) = oneshot::channel();
+
+            let event = CommitmentEvent {
+                request_id,
+                request: CommitmentRequest::Inclusion(inclusion_request),
+                response: response_tx,
+                span: Span::current(),
+            };
+
+            self.enqueue(event)?;
+
+            response_rx.await.map_err(|_| CommitmentError::Internal)?.map(|c| c.into())
+        }
+        .await;
+        ApiMetrics::observe_commitment_latency(start.elapsed());
+
+        result
+    }
+
+    #[instrument(
+        skip_all,
+        name = "validate_inclusion_request",
+        fields(%request_id, digest = %inclusion_request.digest())
+    )]
+    async fn validate_inclusion_request(
+        &self,
+        request_id: RequestId,
+        inclusion_request: InclusionRequest,
+    ) -> Result<ValidationVerdict, CommitmentError> {
+        let Some(validation_events) = &self.validation_events else {
+            return Err(CommitmentError::UnknownMethod);
+        };
+
+        if self.health.is_waiting_for_beacon() {
+            return Err(CommitmentError::BeaconUnavailable);
+        }
+
         let (response_tx, response_rx) = oneshot::channel();
 
-        let event = CommitmentEvent {
-            request: CommitmentRequest::Inclusion(inclusion_request),
+        let event = ValidationEvent {
+            request_id,
+            request: inclusion_request,
             response: response_tx,
+            span: Span::current(),
         };
 
-        self.events.send(event).await.unwrap();
+        try_enqueue(validation_events, event)?;
 
-        response_rx.await.map_err(|_| CommitmentError::Internal)?.map(|c| c.into())
+        response_rx.await.map_err(|_| CommitmentError::Internal)?
+    }
+}
+
+/// Send an event to the driver without waiting, failing right away if its queue is full.
+fn try_enqueue<T>(events: &mpsc::Sender<T>, event: T) -> Result<(), CommitmentError> {
+    match events.try_send(event) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(_)) => {
+            let capacity = events.max_capacity();
+            warn!(capacity, "Commitment request queue is full, rejecting request");
+            ApiMetrics::increment_api_queue_overflows();
+            Err(CommitmentError::Overloaded { capacity })
+        }
+        Err(TrySendError::Closed(_)) => {
+            error!("Commitment request queue is closed");
+            Err(CommitmentError::Internal)
+        }
     }
 }
 
@@ -88,44 +296,126 @@ pub struct CommitmentsApiServer {
     /// The address to bind the server to. This will be updated
     /// with the actual address after the server is started.
     addr: SocketAddr,
+    /// The TLS configuration of the server, if served over HTTPS.
+    tls: Option<Arc<ServerConfig>>,
+    /// The options for exposing the API, e.g. behind a reverse proxy.
+    api_opts: ApiOpts,
+    /// The health of the upstream endpoints, exposed in the health endpoint.
+    health: HealthHandle,
+    /// The slots sealed at the commitment deadline, exposed to external builders.
+    sealed_slots: SealedSlots,
+    /// The latest reports of the constraints submissions, served in the admin endpoints.
+    submission_reports: SubmissionReports,
+    /// The archive of the signed commitments, exposed in the admin endpoints if enabled.
+    commitment_archive: Option<CommitmentArchive>,
+    /// The public identity of the sidecar and its validators, exposed in the status method.
+    status: StatusHandle,
+    /// The channel for the dry-run validations of inclusion requests, if served.
+    validation_events: Option<mpsc::Sender<ValidationEvent>>,
     /// The shutdown signal.
     signal: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
 }
 
 impl fmt::Debug for CommitmentsApiServer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("CommitmentsApiServer").field("addr", &self.addr).finish()
+        f.debug_struct("CommitmentsApiServer")
+            .field("addr", &self.addr)
+            .field("tls", &self.tls.is_some())
+            .field("api_opts", &self.api_opts)
+            .finish()
     }
 }
 
 impl CommitmentsApiServer {
-    /// Creates the server with the given address and default shutdown signal (CTRL+C).
-    pub fn new<A: ToSocketAddrs>(addr: A) -> Self {
+    /// Creates the server with the given address, optional TLS configuration and default
+    /// shutdown signal (CTRL+C).
+    pub fn new(addr: SocketAddr, tls: Option<Arc<ServerConfig>>) -> Self {
         Self {
-            addr: addr.to_socket_addrs().unwrap().next().unwrap(),
+            addr,
+            tls,
+            api_opts: ApiOpts::default(),
+            health: HealthHandle::default(),
+            sealed_slots: SealedSlots::default(),
+            submission_reports: SubmissionReports::default(),
+            commitment_archive: None,
+            status: StatusHandle::default(),
+            validation_events: None,
             signal: Some(Box::pin(async {
                 let _ = tokio::signal::ctrl_c().await;
             })),
         }
     }
 
+    /// Sets the options for exposing the API, such as the base path and trusted proxies.
+    pub fn with_api_opts(mut self, api_opts: ApiOpts) -> Self {
+        self.api_opts = api_opts;
+        self
+    }
+
+    /// Sets the handle to the health status reported by the health endpoint.
+    pub fn with_health(mut self, health: HealthHandle) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Sets the registry of the slots sealed at the commitment deadline.
+    pub fn with_sealed_slots(mut self, sealed_slots: SealedSlots) -> Self {
+        self.sealed_slots = sealed_slots;
+        self
+    }
+
+    /// Sets the ring buffer of the latest constraints submission reports, served in the
+    /// admin endpoints.
+    pub fn with_submission_reports(mut self, submission_reports: SubmissionReports) -> Self {
+        self.submission_reports = submission_reports;
+        self
+    }
+
+    /// Sets the archive of the signed commitments, served in the admin endpoints.
+    pub fn with_commitment_archive(mut self, commitment_archive: CommitmentArchive) -> Self {
+        self.commitment_archive = Some(commitment_archive);
+        self
+    }
+
+    /// Sets the handle to the public identity of the sidecar, served in the status method.
+    pub fn with_status(mut self, status: StatusHandle) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Sets the channel to send the dry-run validations of inclusion requests to, served in
+    /// the `bolt_validateInclusionRequest` method.
+    pub fn with_validation_events(mut self, events: mpsc::Sender<ValidationEvent>) -> Self {
+        self.validation_events = Some(events);
+        self
+    }
+
     /// Creates the server with the given address and shutdown signal.
-    pub fn with_shutdown<A, S>(self, addr: A, signal: S) -> Self
+    pub fn with_shutdown<S>(self, addr: SocketAddr, signal: S) -> Self
     where
-        A: ToSocketAddrs,
         S: Future<Output = ()> + Send + 'static,
     {
-        Self {
-            addr: addr.to_socket_addrs().unwrap().next().unwrap(),
-            signal: Some(Box::pin(signal)),
-        }
+        Self { addr, signal: Some(Box::pin(signal)), ..self }
     }
 
     /// Runs the JSON-RPC server, sending events to the provided channel.
-    pub async fn run(&mut self, events_tx: mpsc::Sender<CommitmentEvent>, limits: LimitsOpts) {
-        let api = Arc::new(CommitmentsApiInner::new(events_tx, limits));
+    pub async fn run(&mut self, events_tx: mpsc::Sender<CommitmentEvent>, limits: SharedLimits) {
+        let mut api = CommitmentsApiInner::new(events_tx, limits)
+            .with_health(self.health.clone())
+            .with_sealed_slots(self.sealed_slots.clone())
+            .with_submission_reports(self.submission_reports.clone())
+            .with_status(self.status.clone())
+            .with_max_batch_size(self.api_opts.max_batch_size.get());
+        if let Some(commitment_archive) = &self.commitment_archive {
+            api = api.with_commitment_archive(commitment_archive.clone());
+        }
+        if let Some(validation_events) = &self.validation_events {
+            api = api.with_validation_events(validation_events.clone());
+        }
+        let api = Arc::new(api);
 
-        let router = make_router(api);
+        let api_opts = Arc::new(self.api_opts.clone());
+        let router = make_router(api, api_opts.clone());
 
         let listener = match TcpListener::bind(self.addr).await {
             Ok(listener) => listener,
@@ -138,12 +428,24 @@ impl CommitmentsApiServer {
         let addr = listener.local_addr().expect("Failed to get local address");
         self.addr = addr;
 
-        info!("Commitments RPC server bound to {addr}");
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        info!(
+            base_path = api_opts.normalized_base_path().as_deref().unwrap_or("/"),
+            proxy_protocol = api_opts.proxy_protocol,
+            "Commitments RPC server listening on {scheme}://{addr}"
+        );
 
         let signal = self.signal.take().expect("Signal not set");
 
+        if api_opts.proxy_protocol || self.tls.is_some() {
+            let tls = self.tls.clone().map(TlsAcceptor::from);
+            tokio::spawn(serve_connections(listener, router, api_opts, tls, signal));
+            return;
+        }
+
         tokio::spawn(async move {
-            if let Err(err) = axum::serve(listener, router).with_graceful_shutdown(signal).await {
+            let service = router.into_make_service_with_connect_info::<SocketAddr>();
+            if let Err(err) = axum::serve(listener, service).with_graceful_shutdown(signal).await {
                 error!(?err, "Commitments API Server error");
             }
         });
@@ -155,26 +457,64 @@ impl CommitmentsApiServer {
     }
 }
 
-/// Creates a new [Router]
+/// Creates a new [Router], with all routes nested under the configured base path.
 ///
 /// NOTE: Keeping the router separate from the server start method allows
 /// for easier integration testing through the [`tower::Service`] interface.
 #[inline]
-fn make_router(state: Arc<CommitmentsApiInner>) -> Router {
-    Router::new()
+fn make_router(state: Arc<CommitmentsApiInner>, api_opts: Arc<ApiOpts>) -> Router {
+    let mut router = Router::new()
         .route("/", post(handlers::rpc_entrypoint))
         .route("/status", get(handlers::status))
+        .route("/health", get(handlers::health))
+        .route(spec::SEALED_SLOT_PATH, get(handlers::sealed_slot))
+        .route(spec::EVENTS_PATH, get(handlers::events));
+
+    // The admin endpoints are only served if a token is configured
+    if api_opts.admin_token.is_some() {
+        router = router.route(
+            spec::ADMIN_LIMITS_PATH,
+            get(handlers::get_limits)
+                .post(handlers::update_limits)
+                .route_layer(middleware::from_fn_with_state(api_opts.clone(), require_admin_token)),
+        );
+        router = router.route(
+            spec::ADMIN_SUBMISSIONS_PATH,
+            get(handlers::submission_reports)
+                .route_layer(middleware::from_fn_with_state(api_opts.clone(), require_admin_token)),
+        );
+
+        if state.commitment_archive().is_some() {
+            router = router.route(
+                spec::ADMIN_COMMITMENTS_PATH,
+                get(handlers::archived_commitments).route_layer(middleware::from_fn_with_state(
+                    api_opts.clone(),
+                    require_admin_token,
+                )),
+            );
+        }
+    }
+
+    let router = router
         .fallback(handlers::not_found)
         .layer(TimeoutLayer::new(spec::MAX_REQUEST_TIMEOUT))
         .route_layer(middleware::from_fn(track_server_metrics))
-        .with_state(state)
+        .layer(middleware::from_fn_with_state(api_opts.clone(), resolve_client_info))
+        .with_state(state);
+
+    match api_opts.normalized_base_path() {
+        Some(base_path) => Router::new().nest(&base_path, router),
+        None => router,
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::api::commitments::{jsonrpc::JsonResponse, spec::SIGNATURE_HEADER};
+    use crate::api::commitments::{
+        jsonrpc::JsonResponse, spec::SIGNATURE_HEADER, status::SidecarStatus,
+    };
     use alloy::signers::{k256::SecretKey, local::PrivateKeySigner};
-    use serde_json::json;
+    use serde_json::{json, Value};
 
     use crate::{
         primitives::commitment::ECDSASignatureExt,
@@ -183,15 +523,23 @@ mod test {
 
     use super::*;
 
+    fn any_addr() -> SocketAddr {
+        SocketAddr::from(([0, 0, 0, 0], 0))
+    }
+
+    fn loopback_addr() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 0))
+    }
+
     #[tokio::test]
     async fn test_request_unauthorized() {
         let _ = tracing_subscriber::fmt::try_init();
 
-        let mut server = CommitmentsApiServer::new("0.0.0.0:0");
+        let mut server = CommitmentsApiServer::new(any_addr(), None);
 
         let (events_tx, _) = mpsc::channel(1);
 
-        server.run(events_tx, LimitsOpts::default()).await;
+        server.run(events_tx, SharedLimits::default()).await;
         let addr = server.local_addr();
 
         let sk = SecretKey::random(&mut rand::thread_rng());
@@ -225,15 +573,73 @@ mod test {
         assert_eq!(response.error.unwrap().code, -32003);
     }
 
+    #[tokio::test]
+    async fn test_malformed_requests() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new(any_addr(), None);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let request =
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "bolt_getVersion", "params": [] });
+        let too_many_params = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": vec![json!({}); 1000]
+        });
+
+        let cases = [
+            ("{\"jsonrpc\": ".to_string(), -32700),
+            ("[".repeat(10_000), -32600),
+            (json!([]).to_string(), -32600),
+            (json!({ "jsonrpc": "2.0", "id": 1 }).to_string(), -32600),
+            (too_many_params.to_string(), -32602),
+        ];
+
+        let client = reqwest::Client::new();
+        for (body, code) in cases {
+            let response = client
+                .post(format!("http://{addr}"))
+                .header("content-type", "application/json")
+                .body(body)
+                .send()
+                .await
+                .unwrap()
+                .json::<JsonResponse>()
+                .await
+                .unwrap();
+
+            assert_eq!(response.error.unwrap().code, code);
+        }
+
+        // The server is still up and well
+        let response = client
+            .post(format!("http://{addr}"))
+            .json(&request)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+
+        assert!(response.error.is_none());
+    }
+
     #[tokio::test]
     async fn test_request_success() {
         let _ = tracing_subscriber::fmt::try_init();
 
-        let mut server = CommitmentsApiServer::new("0.0.0.0:0");
+        let mut server = CommitmentsApiServer::new(any_addr(), None);
 
         let (events_tx, mut events) = mpsc::channel(1);
 
-        server.run(events_tx, LimitsOpts::default()).await;
+        server.run(events_tx, SharedLimits::default()).await;
         let addr = server.local_addr();
 
         let sk = SecretKey::random(&mut rand::thread_rng());
@@ -274,7 +680,7 @@ mod test {
             let _ = tx.send(());
         });
 
-        let CommitmentEvent { request, response } = events.recv().await.unwrap();
+        let CommitmentEvent { request, response, .. } = events.recv().await.unwrap();
 
         let commitment_signer = PrivateKeySigner::random();
 
@@ -285,15 +691,241 @@ mod test {
         rx.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_validate_inclusion_request() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let (validation_tx, mut validations) = mpsc::channel(1);
+        let mut server =
+            CommitmentsApiServer::new(any_addr(), None).with_validation_events(validation_tx);
+
+        let (events_tx, mut events) = mpsc::channel(1);
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+        let sig = req.signature.unwrap().to_hex();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_validateInclusionRequest",
+            "params": [req]
+        });
+        let response = tokio::spawn(
+            reqwest::Client::new()
+                .post(format!("http://{addr}"))
+                .header(SIGNATURE_HEADER, format!("{}:{}", signer.address(), sig))
+                .json(&payload)
+                .send(),
+        );
+
+        // The request is sent for a dry run, not for a commitment
+        let ValidationEvent { request, response: verdict_tx, .. } =
+            validations.recv().await.unwrap();
+        assert_eq!(request.digest(), req.digest());
+        assert!(events.try_recv().is_err());
+
+        let verdict = ValidationVerdict {
+            ok: true,
+            slot: 12,
+            remaining_gas: 9_979_000,
+            remaining_blobs: 6,
+            remaining_constraints: 127,
+            projected_basefee: 1_000_000_000,
+            min_priority_fee: 1_000_000_000,
+        };
+        verdict_tx.send(Ok(verdict.clone())).unwrap();
+
+        let json = response.await.unwrap().unwrap().json::<JsonResponse>().await.unwrap();
+        assert!(json.error.is_none());
+        let request_id = json.result["request_id"].as_str().unwrap().to_string();
+        assert_eq!(serde_json::from_value::<ValidationVerdict>(json.result).unwrap(), verdict);
+
+        // Unsigned requests are rejected like commitment requests
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+        let data = response.error.unwrap().data.unwrap();
+
+        // Every request is assigned a new ID, returned on errors as well
+        let other_request_id = data["request_id"].as_str().unwrap();
+        assert!(other_request_id.parse::<RequestId>().is_ok());
+        assert_ne!(other_request_id, request_id);
+    }
+
+    #[tokio::test]
+    async fn test_batch_requests() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new(any_addr(), None).with_api_opts(ApiOpts {
+            max_batch_size: std::num::NonZero::new(8).unwrap(),
+            ..Default::default()
+        });
+
+        let (events_tx, mut events) = mpsc::channel(8);
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        // The driver commits to every request it receives
+        tokio::spawn(async move {
+            let commitment_signer = PrivateKeySigner::random();
+            while let Some(CommitmentEvent { request, response, .. }) = events.recv().await {
+                let commitment = request.commit_and_sign(&commitment_signer).await.unwrap();
+                let _ = response.send(Ok(commitment));
+            }
+        });
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx.clone()], &sk, 12).await.unwrap();
+        let sig = req.signature.unwrap().to_hex();
+
+        // The second inclusion request is signed by someone else than the advertised signer
+        let other_sk = SecretKey::random(&mut rand::thread_rng());
+        let other_req = create_signed_inclusion_request(&[tx], &other_sk, 12).await.unwrap();
+        let other_sig = other_req.signature.unwrap().to_hex();
+
+        let call = |id: u64, method: &str, params: Value| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params
+            })
+        };
+        let batch = json!([
+            call(1, "bolt_getVersion", json!([])),
+            call(2, "bolt_requestInclusion", json!([req])),
+            { "jsonrpc": "2.0", "id": 3 },
+            call(4, "bolt_unknownMethod", json!([])),
+            call(5, "bolt_requestInclusion", json!([other_req])),
+            { "jsonrpc": "2.0", "method": "bolt_getVersion", "params": [] },
+            42,
+        ]);
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("http://{addr}"))
+            .header(SIGNATURE_HEADER, format!("{0}:{sig},{0}:{other_sig}", signer.address()))
+            .json(&batch)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        // One response per entry but the notification, in order, with errors isolated
+        let responses = response.json::<Vec<JsonResponse>>().await.unwrap();
+        let ids = responses.iter().map(|r| r.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids, [json!(1), json!(2), json!(3), json!(4), json!(5), Value::Null].map(Some));
+
+        let codes = responses.iter().map(|r| r.error.as_ref().map(|e| e.code)).collect::<Vec<_>>();
+        assert_eq!(codes, [None, None, Some(-32600), Some(-32601), Some(-32004), Some(-32600)]);
+        assert!(responses[1].result.get("signature").is_some());
+
+        // Batches of notifications only get no response
+        let notifications =
+            json!([{ "jsonrpc": "2.0", "method": "bolt_getVersion", "params": [] }]);
+        let response = client.post(format!("http://{addr}")).json(&notifications).send().await;
+        assert_eq!(response.unwrap().status(), 204);
+
+        // Oversized batches are rejected as a whole
+        let oversized = Value::Array(vec![call(1, "bolt_getVersion", json!([])); 9]);
+        let response = client.post(format!("http://{addr}")).json(&oversized).send().await;
+        let error = response.unwrap().json::<JsonResponse>().await.unwrap().error.unwrap();
+        assert_eq!(error.code, -32600);
+        assert!(error.message.contains("exceeds the maximum of 8"));
+    }
+
+    #[tokio::test]
+    async fn test_request_queue_overflow() {
+        use std::time::Duration;
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new(any_addr(), None);
+
+        // Nobody processes the events until the queue is full
+        let (events_tx, mut events) = mpsc::channel(2);
+        let queue = events_tx.clone();
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+        let sig = req.signature.unwrap().to_hex();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": [req]
+        });
+
+        let client = reqwest::Client::new();
+        let send = move || {
+            client
+                .post(format!("http://{addr}"))
+                .header(SIGNATURE_HEADER, format!("{}:{}", signer.address(), sig))
+                .json(&payload)
+                .send()
+        };
+
+        // The first requests fill up the queue and wait for a response
+        let pending = (0..2).map(|_| tokio::spawn(send())).collect::<Vec<_>>();
+        while queue.capacity() > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The next ones are rejected right away with a structured error
+        for _ in 0..3 {
+            let response = send().await.unwrap();
+            assert_eq!(response.status(), 503);
+
+            let error = response.json::<JsonResponse>().await.unwrap().error.unwrap();
+            assert_eq!(error.code, -32008);
+            assert_eq!(error.data, Some(json!({ "reason": "queue_full", "queue_capacity": 2 })));
+        }
+
+        // Once the queue drains, requests are accepted again
+        let commitment_signer = PrivateKeySigner::random();
+        for _ in 0..2 {
+            let CommitmentEvent { request, response, .. } = events.recv().await.unwrap();
+            let commitment = request.commit_and_sign(&commitment_signer).await.unwrap();
+            response.send(Ok(commitment)).unwrap();
+        }
+
+        for pending in pending {
+            let response = pending.await.unwrap().unwrap();
+            assert!(response.json::<JsonResponse>().await.unwrap().error.is_none());
+        }
+        assert_eq!(queue.capacity(), 2);
+    }
+
     #[tokio::test]
     async fn test_request_metadata() {
         let _ = tracing_subscriber::fmt::try_init();
 
-        let mut server = CommitmentsApiServer::new("0.0.0.0:0");
+        let mut server = CommitmentsApiServer::new(any_addr(), None);
 
         let (events_tx, _) = mpsc::channel(1);
 
-        server.run(events_tx, LimitsOpts::default()).await;
+        server.run(events_tx, SharedLimits::default()).await;
         let addr = server.local_addr();
 
         let payload = json!({
@@ -321,4 +953,500 @@ mod test {
 
         assert_eq!(limits, LimitsOpts::default());
     }
+
+    #[tokio::test]
+    async fn test_request_status() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let commitment_signer = alloy::primitives::Address::repeat_byte(0x42);
+        let status = StatusHandle::new(Default::default(), commitment_signer, "local", vec![]);
+        let mut server = CommitmentsApiServer::new(any_addr(), None).with_status(status);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let payload = json!({ "jsonrpc": "2.0", "id": 1, "method": "bolt_status", "params": [] });
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+
+        let status: SidecarStatus = serde_json::from_value(response.result).unwrap();
+        assert_eq!(status.chain_id, 1);
+        assert_eq!(status.commitment_signer, commitment_signer);
+        assert_eq!(status.signer_backend, "local");
+        assert!(status.validators.is_empty());
+        assert_eq!(status.limits, LimitsOpts::default());
+    }
+
+    #[tokio::test]
+    async fn test_request_with_base_path() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let api_opts = ApiOpts { base_path: "/bolt/".to_string(), ..Default::default() };
+        let mut server = CommitmentsApiServer::new(any_addr(), None).with_api_opts(api_opts);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_metadata",
+            "params": []
+        });
+
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("http://{addr}/bolt"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+
+        let limits: LimitsOpts = serde_json::from_value(response.result).unwrap();
+        assert_eq!(limits, LimitsOpts::default());
+
+        let status = client.get(format!("http://{addr}/bolt/status")).send().await.unwrap();
+        assert!(status.status().is_success());
+
+        // Routes are not served at the root anymore
+        let response = client.post(format!("http://{addr}/")).json(&payload).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_limits() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let api_opts = ApiOpts { admin_token: Some("secret".to_string()), ..Default::default() };
+        let mut server = CommitmentsApiServer::new(any_addr(), None).with_api_opts(api_opts);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        let limits = SharedLimits::default();
+        server.run(events_tx, limits.clone()).await;
+        let addr = server.local_addr();
+
+        let url = format!("http://{addr}{}", spec::ADMIN_LIMITS_PATH);
+        let update = json!({ "max_committed_gas_per_slot": 1_000_000 });
+        let client = reqwest::Client::new();
+
+        // Unauthenticated requests are rejected
+        let response = client.post(&url).json(&update).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let response = client.post(&url).bearer_auth("wrong").json(&update).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert_eq!(limits.get(), LimitsOpts::default());
+
+        // Invalid updates are rejected
+        let response = client
+            .post(&url)
+            .bearer_auth("secret")
+            .json(&json!({ "max_tx_size": 1_000_000 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let response = client.post(&url).bearer_auth("secret").json(&update).send().await.unwrap();
+        assert!(response.status().is_success());
+
+        let new = response.json::<LimitsOpts>().await.unwrap();
+        assert_eq!(new.max_committed_gas_per_slot.get(), 1_000_000);
+        assert_eq!(limits.get(), new);
+
+        // The metadata endpoint reflects the new limits
+        let payload = json!({ "jsonrpc": "2.0", "id": 1, "method": "bolt_metadata", "params": [] });
+        let response = client
+            .post(format!("http://{addr}"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+
+        let metadata: LimitsOpts = serde_json::from_value(response.result).unwrap();
+        assert_eq!(metadata, new);
+    }
+
+    #[tokio::test]
+    async fn test_admin_submission_reports() {
+        use std::time::Duration;
+
+        use crate::{
+            api::spec::BuilderApiError,
+            client::{SubmissionAttempt, SubmissionReport},
+        };
+
+        let api_opts = ApiOpts { admin_token: Some("secret".to_string()), ..Default::default() };
+        let reports = SubmissionReports::default();
+        let mut server = CommitmentsApiServer::new(any_addr(), None)
+            .with_api_opts(api_opts)
+            .with_submission_reports(reports.clone());
+
+        let (events_tx, _) = mpsc::channel(1);
+        server.run(events_tx, SharedLimits::default()).await;
+        let url = format!("http://{}{}", server.local_addr(), spec::ADMIN_SUBMISSIONS_PATH);
+        let client = reqwest::Client::new();
+
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let body = r#"{"code":400,"message":"slot too old"}"#;
+        let result = Err(BuilderApiError::RelayRejected { status: 400, body: body.to_string() });
+        let attempt = SubmissionAttempt::new(1, "http://relay", Duration::from_millis(5), &result);
+        reports.record(SubmissionReport { slot: 10, success: false, attempts: vec![attempt] });
+
+        let response: Value =
+            client.get(&url).bearer_auth("secret").send().await.unwrap().json().await.unwrap();
+        assert_eq!(response[0]["slot"], 10);
+        assert_eq!(response[0]["attempts"][0]["status"], 400);
+        assert_eq!(response[0]["attempts"][0]["body"], body);
+        assert_eq!(response[0]["attempts"][0]["reason"], "relay_rejected");
+    }
+
+    #[tokio::test]
+    async fn test_admin_disabled_without_token() {
+        let mut server = CommitmentsApiServer::new(any_addr(), None);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}{}", spec::ADMIN_LIMITS_PATH))
+            .json(&json!({ "max_committed_gas_per_slot": 1_000_000 }))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_request_with_proxy_protocol() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use crate::api::commitments::proxy::PROXY_V2_SIGNATURE;
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let api_opts = ApiOpts {
+            trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+            proxy_protocol: true,
+            ..Default::default()
+        };
+        let mut server = CommitmentsApiServer::new(loopback_addr(), None).with_api_opts(api_opts);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        // A trusted proxy relaying a client connection from 192.0.2.1:4242
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut header = PROXY_V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x21, 0x11, 0x00, 0x0c, 192, 0, 2, 1, 127, 0, 0, 1]);
+        header.extend_from_slice(&4242u16.to_be_bytes());
+        header.extend_from_slice(&addr.port().to_be_bytes());
+        stream.write_all(&header).await.unwrap();
+        stream
+            .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "unexpected response: {response}");
+
+        // The same proxy omitting the header gets its connection dropped
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response).await;
+        assert!(response.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_plain_http_on_loopback() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new(loopback_addr(), None);
+        let (events_tx, _) = mpsc::channel(1);
+        server.run(events_tx, SharedLimits::default()).await;
+
+        let addr = server.local_addr();
+        assert!(addr.ip().is_loopback());
+
+        let response = reqwest::get(format!("http://{addr}/status")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_tls_handshake() -> eyre::Result<()> {
+        use crate::test_util::generate_test_tls;
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let dir = tempfile::tempdir()?;
+        let tls = generate_test_tls(dir.path())?;
+        let api_opts = ApiOpts {
+            tls_cert: Some(tls.cert_path.clone()),
+            tls_key: Some(tls.key_path.clone()),
+            ..Default::default()
+        };
+
+        let mut server = CommitmentsApiServer::new(loopback_addr(), api_opts.tls_config()?);
+        let (events_tx, _) = mpsc::channel(1);
+        server.run(events_tx, SharedLimits::default()).await;
+        let port = server.local_addr().port();
+
+        // A client trusting the test CA completes the handshake
+        let client = reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(tls.ca_pem.as_bytes())?)
+            .build()?;
+        let response = client.get(format!("https://localhost:{port}/status")).send().await?;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        // Plain HTTP requests are not served
+        assert!(reqwest::get(format!("http://localhost:{port}/status")).await.is_err());
+
+        // Neither are clients that don't trust the CA
+        assert!(reqwest::get(format!("https://localhost:{port}/status")).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoint() {
+        use std::time::Duration;
+
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        use crate::telemetry::health::{HealthProber, HealthTarget, TargetKind};
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let beacon = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&beacon)
+            .await;
+
+        let target = HealthTarget::new("beacon", TargetKind::Beacon, beacon.uri().parse().unwrap());
+        let prober = HealthProber::new(vec![target], Duration::from_secs(12));
+
+        let mut server = CommitmentsApiServer::new(any_addr(), None).with_health(prober.handle());
+        let (events_tx, _) = mpsc::channel(1);
+        server.run(events_tx, SharedLimits::default()).await;
+        let url = format!("http://{}/health", server.local_addr());
+
+        // No checks have been performed yet
+        let response = reqwest::get(&url).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        prober.probe_all().await;
+
+        let response = reqwest::get(&url).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let body = response.json::<serde_json::Value>().await.unwrap();
+        assert_eq!(body["healthy"], false);
+        assert_eq!(body["targets"][0]["name"], "beacon");
+    }
+
+    #[tokio::test]
+    async fn test_requests_while_waiting_for_beacon() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let health = HealthHandle::default();
+        health.set_waiting_for_beacon(true);
+
+        let mut server = CommitmentsApiServer::new(any_addr(), None).with_health(health.clone());
+        let (events_tx, mut events) = mpsc::channel(1);
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+        let sig = req.signature.unwrap().to_hex();
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": [req]
+        });
+
+        let client = reqwest::Client::new();
+        let send = || {
+            client
+                .post(format!("http://{addr}"))
+                .header(SIGNATURE_HEADER, format!("{}:{}", signer.address(), sig))
+                .json(&payload)
+                .send()
+        };
+
+        // Requests are rejected right away, and the health endpoint reports degraded
+        let response = send().await.unwrap();
+        assert_eq!(response.status(), 503);
+        let error = response.json::<JsonResponse>().await.unwrap().error.unwrap();
+        assert_eq!(error.code, -32010);
+        assert_eq!(error.data, Some(json!({ "reason": "beacon_unavailable" })));
+        assert!(events.try_recv().is_err());
+
+        let response = reqwest::get(format!("http://{addr}/health")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let body = response.json::<Value>().await.unwrap();
+        assert_eq!(body["waiting_for_beacon"], true);
+
+        // Once the beacon node is reachable, requests reach the driver again
+        health.set_waiting_for_beacon(false);
+        let pending = tokio::spawn(send());
+        let CommitmentEvent { request, response, .. } = events.recv().await.unwrap();
+        let commitment = request.commit_and_sign(&PrivateKeySigner::random()).await.unwrap();
+        response.send(Ok(commitment)).unwrap();
+        let response = pending.await.unwrap().unwrap();
+        assert!(response.json::<JsonResponse>().await.unwrap().error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_commitment_latency_metrics() {
+        use crate::telemetry::prometheus_builder;
+
+        // The server runs on the thread of the test, which records to a local recorder
+        let recorder = prometheus_builder().unwrap().build_recorder();
+        let handle = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+
+        let health = HealthHandle::default();
+        let mut server = CommitmentsApiServer::new(any_addr(), None).with_health(health.clone());
+        let (events_tx, mut events) = mpsc::channel(1);
+        server.run(events_tx, SharedLimits::default()).await;
+        let addr = server.local_addr();
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+        let sig = req.signature.unwrap().to_hex();
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": [req]
+        });
+
+        let client = reqwest::Client::new();
+        let send = || {
+            client
+                .post(format!("http://{addr}"))
+                .header(SIGNATURE_HEADER, format!("{}:{}", signer.address(), sig))
+                .json(&payload)
+                .send()
+        };
+        let latency_count = || {
+            let scrape = handle.render();
+            scrape
+                .lines()
+                .find(|line| line.starts_with("bolt_sidecar_commitment_latency_seconds_count"))
+                .and_then(|line| line.split_whitespace().last())
+                .map(|count| count.parse::<u64>().unwrap())
+                .unwrap_or_default()
+        };
+
+        // Requests rejected by the server, by the driver or committed are all recorded
+        health.set_waiting_for_beacon(true);
+        let response = send().await.unwrap().json::<JsonResponse>().await.unwrap();
+        assert!(response.error.is_some());
+        assert_eq!(latency_count(), 1);
+        health.set_waiting_for_beacon(false);
+
+        let pending = tokio::spawn(send());
+        let CommitmentEvent { response, .. } = events.recv().await.unwrap();
+        response.send(Err(CommitmentError::Duplicate)).unwrap();
+        let response = pending.await.unwrap().unwrap().json::<JsonResponse>().await.unwrap();
+        assert!(response.error.is_some());
+        assert_eq!(latency_count(), 2);
+
+        let pending = tokio::spawn(send());
+        let CommitmentEvent { request, response, .. } = events.recv().await.unwrap();
+        let commitment = request.commit_and_sign(&PrivateKeySigner::random()).await.unwrap();
+        response.send(Ok(commitment)).unwrap();
+        let response = pending.await.unwrap().unwrap().json::<JsonResponse>().await.unwrap();
+        assert!(response.error.is_none());
+        assert_eq!(latency_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sealed_slot_long_poll() {
+        use std::time::{Duration, Instant};
+
+        use crate::{
+            builder::BlockTemplate,
+            primitives::{ConstraintsMessage, SignedConstraints},
+            state::{SealedSlot, SealedSlots},
+        };
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let sealed_slots = SealedSlots::default();
+        let mut server =
+            CommitmentsApiServer::new(any_addr(), None).with_sealed_slots(sealed_slots.clone());
+        let (events_tx, _) = mpsc::channel(1);
+        server.run(events_tx, SharedLimits::default()).await;
+        let url = format!("http://{}/constraints/12/sealed", server.local_addr());
+
+        // A request is accepted for slot 12 and its constraints are added to the template
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let mut req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+        req.recover_signers().unwrap();
+
+        let message = ConstraintsMessage::build(Default::default(), req);
+        let mut template = BlockTemplate::default();
+        template.add_constraints(SignedConstraints { message, signature: Default::default() });
+
+        let poll = tokio::spawn(reqwest::get(url.clone()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!poll.is_finished());
+
+        // The commitment deadline is reached and the slot is sealed
+        sealed_slots.seal(12, &template.signed_constraints_list);
+
+        let response = poll.await.unwrap().unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let sealed = response.json::<SealedSlot>().await.unwrap();
+        assert_eq!(sealed, SealedSlot::new(12, &template.signed_constraints_list));
+        assert_eq!(sealed.constraints_count, 1);
+
+        // A second poll returns immediately
+        let start = Instant::now();
+        let response = reqwest::get(&url).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(response.json::<SealedSlot>().await.unwrap(), sealed);
+    }
 }
@@ -1,13 +1,18 @@
 use alloy::primitives::SignatureError;
-use axum::{extract::rejection::JsonRejection, http::StatusCode, response::IntoResponse, Json};
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
 use thiserror::Error;
 
 use crate::{
-    primitives::{commitment::InclusionCommitment, InclusionRequest},
+    primitives::{
+        commitment::{InclusionCommitment, RequestId, ValidationVerdict},
+        InclusionRequest,
+    },
     state::{consensus::ConsensusError, ValidationError},
+    telemetry::otel::current_trace_id,
 };
 
-use super::jsonrpc::JsonResponse;
+use super::jsonrpc::{JsonResponse, JsonRpcParseError};
 
 pub(super) const SIGNATURE_HEADER: &str = "x-bolt-signature";
 
@@ -15,10 +20,33 @@ pub(super) const GET_VERSION_METHOD: &str = "bolt_getVersion";
 
 pub(super) const REQUEST_INCLUSION_METHOD: &str = "bolt_requestInclusion";
 
+pub(super) const VALIDATE_INCLUSION_METHOD: &str = "bolt_validateInclusionRequest";
+
 pub(super) const GET_METADATA_METHOD: &str = "bolt_metadata";
 
+pub(super) const GET_STATUS_METHOD: &str = "bolt_status";
+
 pub(super) const MAX_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(6);
 
+/// The path of the long-poll endpoint resolving when the constraints of a slot are sealed.
+pub(super) const SEALED_SLOT_PATH: &str = "/constraints/:slot/sealed";
+
+/// The path of the websocket endpoint streaming the sidecar events, e.g. `slot_sealed`.
+pub(super) const EVENTS_PATH: &str = "/events";
+
+/// The path of the admin endpoint to read and update the operating limits at runtime.
+pub(super) const ADMIN_LIMITS_PATH: &str = "/admin/limits";
+
+/// The path of the admin endpoint returning the latest reports of the constraints submissions.
+pub(super) const ADMIN_SUBMISSIONS_PATH: &str = "/admin/submissions";
+
+/// The path of the admin endpoint returning the archived commitments of a slot.
+pub(super) const ADMIN_COMMITMENTS_PATH: &str = "/commitments";
+
+/// How long a sealed slot long-poll waits before returning empty-handed.
+/// NOTE: must be lower than [MAX_REQUEST_TIMEOUT].
+pub(super) const SEALED_SLOT_POLL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// Error type for the commitments API.
 #[derive(Debug, Error)]
 pub enum CommitmentError {
@@ -37,6 +65,29 @@ pub enum CommitmentError {
     /// Internal server error.
     #[error("Internal server error")]
     Internal,
+    /// The queue of requests waiting to be processed is full.
+    #[error("Server overloaded, try again later")]
+    Overloaded {
+        /// The capacity of the request queue.
+        capacity: usize,
+    },
+    /// The sidecar is waiting for the beacon node to become reachable.
+    #[error("Beacon node unavailable, try again later")]
+    BeaconUnavailable,
+    /// The commitment deadline of the target slot passed while the request was processed.
+    #[error("Commitment deadline exceeded before {stage}")]
+    DeadlineExceeded {
+        /// The processing stage the request was aborted at.
+        stage: &'static str,
+    },
+    /// The expiry set by the user passed before the request was committed.
+    #[error("Request expired at {expires_at} before {stage}")]
+    RequestExpired {
+        /// The unix timestamp in milliseconds the request expired at.
+        expires_at: u64,
+        /// The processing stage the request was rejected at.
+        stage: &'static str,
+    },
     /// Missing signature.
     #[error("Missing '{SIGNATURE_HEADER}' header")]
     NoSignature,
@@ -52,64 +103,94 @@ pub enum CommitmentError {
     /// Unknown method.
     #[error("Unknown method")]
     UnknownMethod,
-    /// Invalid JSON.
+    /// Invalid JSON-RPC request.
     #[error(transparent)]
-    InvalidJson(#[from] JsonRejection),
+    InvalidJson(#[from] JsonRpcParseError),
 }
 
-impl IntoResponse for CommitmentError {
-    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+impl CommitmentError {
+    /// Returns the HTTP status code of the error.
+    pub fn status_code(&self) -> StatusCode {
         match self {
-            CommitmentError::Rejected(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32000, err.to_string())))
-                    .into_response()
+            CommitmentError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            CommitmentError::Overloaded { .. } | CommitmentError::BeaconUnavailable => {
+                StatusCode::SERVICE_UNAVAILABLE
             }
-            CommitmentError::Duplicate => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32001, self.to_string())))
-                    .into_response()
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Returns the JSON-RPC error response of the error, without an ID. The ID of the trace of
+    /// the current request is included in the error data if traces are exported.
+    ///
+    /// Validation errors about the account state or the fees carry their context in the error
+    /// data, e.g. the expected nonce, so that clients can correct their requests. So do the
+    /// requests received too close to the commitment deadline, with the earliest slot they
+    /// can be retargeted to.
+    pub fn to_json_response(&self) -> JsonResponse {
+        let response = match self {
+            CommitmentError::Rejected(err) => JsonResponse::from_error(-32000, err.to_string()),
+            CommitmentError::Duplicate => JsonResponse::from_error(-32001, self.to_string()),
+            CommitmentError::Internal => JsonResponse::from_error(-32002, self.to_string()),
+            CommitmentError::Overloaded { capacity } => {
+                let data = json!({ "reason": "queue_full", "queue_capacity": capacity });
+                JsonResponse::from_error(-32008, self.to_string()).with_error_data(data)
             }
-            CommitmentError::Internal => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(JsonResponse::from_error(-32002, self.to_string())),
-            )
-                .into_response(),
-            CommitmentError::NoSignature => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32003, self.to_string())))
-                    .into_response()
+            CommitmentError::BeaconUnavailable => {
+                let data = json!({ "reason": "beacon_unavailable" });
+                JsonResponse::from_error(-32010, self.to_string()).with_error_data(data)
             }
-            CommitmentError::InvalidSignature(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32004, err.to_string())))
-                    .into_response()
+            CommitmentError::DeadlineExceeded { stage } => {
+                let data = json!({ "reason": "deadline_exceeded", "stage": stage });
+                JsonResponse::from_error(-32009, self.to_string()).with_error_data(data)
             }
-            CommitmentError::Signature(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32005, err.to_string())))
-                    .into_response()
+            CommitmentError::RequestExpired { expires_at, stage } => {
+                let data = json!({
+                    "reason": "request_expired",
+                    "expires_at": expires_at,
+                    "stage": stage,
+                });
+                JsonResponse::from_error(-32011, self.to_string()).with_error_data(data)
             }
+            CommitmentError::NoSignature => JsonResponse::from_error(-32003, self.to_string()),
+            CommitmentError::InvalidSignature(err) => {
+                JsonResponse::from_error(-32004, err.to_string())
+            }
+            CommitmentError::Signature(err) => JsonResponse::from_error(-32005, err.to_string()),
             CommitmentError::Consensus(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32006, err.to_string())))
-                    .into_response()
+                let response = JsonResponse::from_error(-32006, err.to_string());
+                match err.data() {
+                    Some(data) => response.with_error_data(data),
+                    None => response,
+                }
             }
             CommitmentError::Validation(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32006, err.to_string())))
-                    .into_response()
-            }
-            CommitmentError::MalformedHeader => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32007, self.to_string())))
-                    .into_response()
+                let response = JsonResponse::from_error(-32006, err.to_string());
+                match err.data() {
+                    Some(data) => response.with_error_data(data),
+                    None => response,
+                }
             }
-            CommitmentError::UnknownMethod => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32601, self.to_string())))
-                    .into_response()
+            CommitmentError::MalformedHeader => JsonResponse::from_error(-32007, self.to_string()),
+            CommitmentError::UnknownMethod => JsonResponse::from_error(-32601, self.to_string()),
+            CommitmentError::InvalidJson(err) => {
+                JsonResponse::from_error(err.code(), err.to_string())
             }
-            CommitmentError::InvalidJson(err) => (
-                StatusCode::BAD_REQUEST,
-                Json(JsonResponse::from_error(-32600, format!("Invalid request: {err}"))),
-            )
-                .into_response(),
+        };
+
+        match current_trace_id() {
+            Some(trace_id) => response.with_trace_id(trace_id),
+            None => response,
         }
     }
 }
 
+impl IntoResponse for CommitmentError {
+    fn into_response(self) -> axum::http::Response<axum::body::Body> {
+        (self.status_code(), Json(self.to_json_response())).into_response()
+    }
+}
+
 /// Error indicating the rejection of a commitment request. This should
 /// be returned to the user.
 #[derive(Debug, Error)]
@@ -123,8 +204,85 @@ pub enum RejectionError {
 #[async_trait::async_trait]
 pub trait CommitmentsApi {
     /// Implements: <https://chainbound.github.io/bolt-docs/api/rpc#bolt_requestinclusion>
+    ///
+    /// The request is handled under the given ID, which is included in all its logs.
     async fn request_inclusion(
         &self,
+        request_id: RequestId,
         inclusion_request: InclusionRequest,
     ) -> Result<InclusionCommitment, CommitmentError>;
+
+    /// Validates an inclusion request like [CommitmentsApi::request_inclusion], without
+    /// signing it nor reserving anything for it.
+    async fn validate_inclusion_request(
+        &self,
+        request_id: RequestId,
+        inclusion_request: InclusionRequest,
+    ) -> Result<ValidationVerdict, CommitmentError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U256;
+    use serde_json::Value;
+
+    use super::*;
+
+    fn error_data(err: ValidationError) -> Option<Value> {
+        CommitmentError::Validation(err).to_json_response().error.and_then(|error| error.data)
+    }
+
+    #[test]
+    fn test_validation_error_data() {
+        let err = ValidationError::NonceTooLow { expected: 5, got: 3 };
+        assert_eq!(
+            error_data(err),
+            Some(json!({ "reason": "nonce_too_low", "expected": 5, "got": 3 }))
+        );
+
+        let err = ValidationError::NonceTooHigh { expected: 5, got: 7 };
+        assert_eq!(
+            error_data(err),
+            Some(json!({ "reason": "nonce_too_high", "expected": 5, "got": 7 }))
+        );
+
+        let err = ValidationError::InsufficientBalance {
+            required: U256::from(21_000_000_000_000u64),
+            available: U256::from(1_000),
+        };
+        assert_eq!(
+            error_data(err),
+            Some(json!({
+                "reason": "insufficient_balance",
+                "required": "21000000000000",
+                "available": "1000",
+            }))
+        );
+
+        let err = ValidationError::BaseFeeTooLow { projected_basefee: 12_500, max_fee: 10_000 };
+        assert_eq!(
+            error_data(err),
+            Some(json!({
+                "reason": "base_fee_too_low",
+                "projected_basefee": "12500",
+                "max_fee": "10000",
+            }))
+        );
+
+        let err = ValidationError::BlobFeeTooLow {
+            projected_blob_basefee: u128::MAX,
+            max_fee_per_blob_gas: 1,
+        };
+        assert_eq!(
+            error_data(err),
+            Some(json!({
+                "reason": "blob_base_fee_too_low",
+                "projected_blob_basefee": u128::MAX.to_string(),
+                "max_fee_per_blob_gas": "1",
+            }))
+        );
+
+        // Errors without context have no data
+        assert_eq!(error_data(ValidationError::AccountHasCode), None);
+    }
 }
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use alloy::primitives::Address;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{chain::ChainConfig, limits::LimitsOpts},
+    primitives::BlsPublicKey,
+    state::VerifiedValidators,
+};
+
+/// The public identity of the sidecar and the validators it commits for, served by the
+/// `bolt_status` method. It never holds any secret.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarStatus {
+    /// The name of the chain.
+    pub chain: String,
+    /// The ID of the chain.
+    pub chain_id: u64,
+    /// The address of the commitment signer.
+    pub commitment_signer: Address,
+    /// The backend of the constraint signer: `local`, `commit-boost` or `keystore`.
+    pub signer_backend: String,
+    /// The validators whose proposer duties are accepted for commitments.
+    pub validators: Vec<ValidatorStatus>,
+    /// The current operating limits.
+    pub limits: LimitsOpts,
+}
+
+/// A validator the sidecar commits for, along with the delegatees signing constraints on its
+/// behalf. Without delegatees, the constraints are signed with the validator key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorStatus {
+    /// The public key of the validator.
+    pub pubkey: BlsPublicKey,
+    /// The public keys of its delegatees, sorted.
+    pub delegatees: Vec<BlsPublicKey>,
+}
+
+impl ValidatorStatus {
+    /// Create a new validator status, sorting its delegatees.
+    pub fn new(pubkey: BlsPublicKey, delegatees: impl IntoIterator<Item = BlsPublicKey>) -> Self {
+        let mut delegatees = delegatees.into_iter().collect::<Vec<_>>();
+        delegatees.sort_by_key(ToString::to_string);
+        Self { pubkey, delegatees }
+    }
+}
+
+/// A cheaply cloneable handle to build the [SidecarStatus], from the identity of the sidecar
+/// known at startup, its current validators and the ones verified in the BoltManager contract.
+#[derive(Debug, Clone, Default)]
+pub struct StatusHandle {
+    chain: ChainConfig,
+    commitment_signer: Address,
+    signer_backend: &'static str,
+    validators: Arc<RwLock<Vec<ValidatorStatus>>>,
+    verified_validators: VerifiedValidators,
+}
+
+impl StatusHandle {
+    /// Create a new status handle for the given chain, signers and validators.
+    pub fn new(
+        chain: ChainConfig,
+        commitment_signer: Address,
+        signer_backend: &'static str,
+        validators: Vec<ValidatorStatus>,
+    ) -> Self {
+        let handle = Self {
+            chain,
+            commitment_signer,
+            signer_backend,
+            validators: Arc::default(),
+            verified_validators: VerifiedValidators::default(),
+        };
+        handle.set_validators(validators);
+        handle
+    }
+
+    /// Replace the validators of the sidecar, e.g. when a remote signer lists new keys.
+    pub fn set_validators(&self, mut validators: Vec<ValidatorStatus>) {
+        validators.sort_by_key(|validator| validator.pubkey.to_string());
+        validators.dedup_by(|a, b| a.pubkey == b.pubkey);
+        *self.validators.write() = validators;
+    }
+
+    /// Only report the validators in the given set of verified validators.
+    pub fn with_verified_validators(mut self, verified_validators: VerifiedValidators) -> Self {
+        self.verified_validators = verified_validators;
+        self
+    }
+
+    /// Returns the current status of the sidecar, with the given operating limits.
+    pub fn status(&self, limits: LimitsOpts) -> SidecarStatus {
+        let validators = self
+            .validators
+            .read()
+            .iter()
+            .filter(|validator| self.verified_validators.contains(&validator.pubkey))
+            .cloned()
+            .collect();
+
+        SidecarStatus {
+            chain: self.chain.name().to_string(),
+            chain_id: self.chain.chain_id(),
+            commitment_signer: self.commitment_signer,
+            signer_backend: self.signer_backend.to_string(),
+            validators,
+            limits,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        chain_io::manager::VerificationReport, config::chain::DEFAULT_CHAIN_CONFIG,
+        primitives::read_signed_delegations_from_file,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_status_golden_json() -> eyre::Result<()> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/delegations.json");
+        let delegation = read_signed_delegations_from_file(&path)?.remove(0);
+        let validator = delegation.message.validator_pubkey;
+        let delegatee = delegation.message.delegatee_pubkey;
+
+        let validators = vec![
+            ValidatorStatus::new(validator.clone(), [delegatee.clone()]),
+            ValidatorStatus::new(delegatee.clone(), []),
+        ];
+        let handle = StatusHandle::new(
+            DEFAULT_CHAIN_CONFIG,
+            Address::repeat_byte(0x42),
+            "keystore",
+            validators,
+        );
+
+        let mut golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        golden_path.push("test_data/sidecar_status.json");
+        let golden =
+            serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(golden_path)?)?;
+
+        let status = handle.status(LimitsOpts::default());
+        assert_eq!(serde_json::to_value(&status)?, golden);
+        assert_eq!(serde_json::from_value::<SidecarStatus>(golden)?, status);
+
+        // Only the verified validators are reported
+        let verified_validators = VerifiedValidators::default();
+        let handle = handle.with_verified_validators(verified_validators.clone());
+        verified_validators.update(&VerificationReport {
+            verified: vec![validator.clone()],
+            ..Default::default()
+        });
+        let status = handle.status(LimitsOpts::default());
+        assert_eq!(status.validators.len(), 1);
+        assert_eq!(status.validators[0].pubkey, validator);
+        assert_eq!(status.validators[0].delegatees, vec![delegatee]);
+
+        // The validators are shared by every clone of the handle when updated
+        handle.clone().set_validators(vec![ValidatorStatus::new(validator.clone(), [])]);
+        let status = handle.status(LimitsOpts::default());
+        assert_eq!(status.validators, vec![ValidatorStatus::new(validator, [])]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,627 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    eips::eip2718::Encodable2718,
+    primitives::{B256, U256},
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use beacon_api_client::VersionedValue;
+use clap::Parser;
+use ethereum_consensus::{
+    bellatrix::mainnet::Transaction,
+    builder::SignedValidatorRegistration,
+    crypto::PublicKey as BlsPublicKey,
+    deneb::{
+        mainnet::{ExecutionPayload, ExecutionPayloadHeader, SignedBlindedBeaconBlock},
+        Hash32,
+    },
+    ssz::prelude::{HashTreeRoot, List, MerkleizationError},
+    types::mainnet::ExecutionPayload as VersionedExecutionPayload,
+    Fork,
+};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tracing::{debug, info, warn};
+
+use super::{
+    builder::GetHeaderParams,
+    spec::{
+        BuilderApiError, ErrorResponse, DELEGATE_PATH, GET_HEADER_WITH_PROOFS_PATH,
+        GET_PAYLOAD_PATH, OCTET_STREAM, REGISTER_VALIDATORS_PATH, REVOKE_PATH, STATUS_PATH,
+        SUBMIT_CONSTRAINTS_PATH,
+    },
+};
+use crate::{
+    builder::signature::sign_builder_message,
+    common::BlsSecretKeyWrapper,
+    config::ChainConfig,
+    primitives::{
+        decode_constraints_ssz, BatchedSignedConstraints, BuilderBid, GetPayloadResponse,
+        PayloadAndBlobs, SignedBuilderBid, SignedConstraints, SignedDelegation, SignedRevocation,
+    },
+};
+
+/// Default port of the mock relay, matching the default constraints API URL of the sidecar.
+pub const DEFAULT_MOCK_RELAY_PORT: u16 = 18551;
+
+/// The path to the admin endpoint to read and update the behavior of the mock relay.
+pub const ADMIN_BEHAVIOR_PATH: &str = "/admin/behavior";
+/// The path to the admin endpoint to read the constraints submitted for a slot.
+pub const ADMIN_CONSTRAINTS_PATH: &str = "/admin/constraints/:slot";
+/// The path to the admin endpoint to read the active delegations.
+pub const ADMIN_DELEGATIONS_PATH: &str = "/admin/delegations";
+/// The path to the admin endpoint to read the received validator registrations.
+pub const ADMIN_REGISTRATIONS_PATH: &str = "/admin/registrations";
+
+/// The gas limit of the fabricated payloads.
+const MOCK_GAS_LIMIT: u64 = 30_000_000;
+
+/// Command-line options for the mock relay.
+#[derive(Debug, Parser)]
+#[clap(name = "bolt-mock-relay")]
+pub struct MockRelayOpts {
+    /// Port to listen on for Builder and Constraints API requests.
+    #[clap(long, env = "BOLT_MOCK_RELAY_PORT", default_value_t = DEFAULT_MOCK_RELAY_PORT)]
+    pub port: u16,
+    /// Secret BLS key to sign the fabricated bids with. A random one is used if not provided.
+    #[clap(long, env = "BOLT_MOCK_RELAY_BUILDER_PRIVATE_KEY")]
+    pub builder_private_key: Option<BlsSecretKeyWrapper>,
+    /// The initial behavior of the relay. It can be updated at runtime through the admin API.
+    #[clap(flatten)]
+    pub behavior: MockRelayBehavior,
+    /// Chain config used to sign the fabricated bids
+    #[clap(flatten)]
+    pub chain: ChainConfig,
+}
+
+/// Configurable behavior of the mock relay, to exercise the sidecar against slow,
+/// unreliable or competitive relays.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockRelayBehavior {
+    /// Latency injected before responding to any Builder or Constraints API request,
+    /// in milliseconds.
+    #[clap(long, env = "BOLT_MOCK_RELAY_LATENCY_MS", default_value_t = 0)]
+    pub latency_ms: u64,
+    /// Probability in `[0, 1]` that a Builder or Constraints API request fails with an
+    /// internal server error.
+    #[clap(long, env = "BOLT_MOCK_RELAY_FAILURE_RATE", default_value_t = 0.0)]
+    pub failure_rate: f64,
+    /// Value of the fabricated bids, in wei.
+    #[clap(long, env = "BOLT_MOCK_RELAY_BID_VALUE", default_value = "1000000000000000000")]
+    pub bid_value: U256,
+    /// Reject SSZ-encoded constraints with 415 Unsupported Media Type, like relays that only
+    /// accept JSON.
+    #[clap(long, env = "BOLT_MOCK_RELAY_REJECT_SSZ", default_value_t = false)]
+    pub reject_ssz: bool,
+}
+
+impl Default for MockRelayBehavior {
+    fn default() -> Self {
+        Self {
+            latency_ms: 0,
+            failure_rate: 0.0,
+            bid_value: U256::from(10u128.pow(18)),
+            reject_ssz: false,
+        }
+    }
+}
+
+/// The data received and produced by the mock relay.
+#[derive(Debug, Default)]
+struct RelayState {
+    registrations: Vec<SignedValidatorRegistration>,
+    constraints: HashMap<u64, Vec<SignedConstraints>>,
+    delegations: Vec<SignedDelegation>,
+    /// The fabricated payloads, keyed by block hash.
+    payloads: HashMap<B256, ExecutionPayload>,
+}
+
+/// A minimal in-memory relay implementing the subset of the Builder and Constraints APIs
+/// used by the sidecar, for local development and integration tests.
+///
+/// Headers are fabricated on demand with the constrained transactions of the requested slot,
+/// and signed with the relay's own BLS key. The payload of a fabricated header is served
+/// once when the matching signed blinded block is submitted.
+#[derive(Debug)]
+pub struct MockRelay {
+    chain: ChainConfig,
+    secret_key: BlsSecretKeyWrapper,
+    behavior: RwLock<MockRelayBehavior>,
+    state: Mutex<RelayState>,
+}
+
+impl MockRelay {
+    /// Create a new mock relay with the given chain config, signing key and behavior.
+    pub fn new(
+        chain: ChainConfig,
+        secret_key: BlsSecretKeyWrapper,
+        behavior: MockRelayBehavior,
+    ) -> Self {
+        Self {
+            chain,
+            secret_key,
+            behavior: RwLock::new(behavior),
+            state: Mutex::new(RelayState::default()),
+        }
+    }
+
+    /// Create a new mock relay from the command-line options.
+    pub fn from_opts(opts: MockRelayOpts) -> Self {
+        let secret_key = opts.builder_private_key.unwrap_or_else(BlsSecretKeyWrapper::random);
+        Self::new(opts.chain, secret_key, opts.behavior)
+    }
+
+    /// Returns the router of the mock relay, including its admin API.
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route(STATUS_PATH, get(Self::status))
+            .route(REGISTER_VALIDATORS_PATH, post(Self::register_validators))
+            .route(GET_HEADER_WITH_PROOFS_PATH, get(Self::get_header_with_proofs))
+            .route(GET_PAYLOAD_PATH, post(Self::get_payload))
+            .route(SUBMIT_CONSTRAINTS_PATH, post(Self::submit_constraints))
+            .route(DELEGATE_PATH, post(Self::delegate))
+            .route(REVOKE_PATH, post(Self::revoke))
+            .route(ADMIN_BEHAVIOR_PATH, get(Self::get_behavior).post(Self::set_behavior))
+            .route(ADMIN_CONSTRAINTS_PATH, get(Self::get_constraints))
+            .route(ADMIN_DELEGATIONS_PATH, get(Self::get_delegations))
+            .route(ADMIN_REGISTRATIONS_PATH, get(Self::get_registrations))
+            .with_state(self)
+    }
+
+    /// Serve the mock relay on the given listener in the background.
+    pub fn spawn(self, listener: TcpListener) -> JoinHandle<()> {
+        let router = Arc::new(self).router();
+
+        tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, router).await {
+                warn!(?err, "Mock relay server stopped");
+            }
+        })
+    }
+
+    /// Apply the configured latency, and return an error with the given message
+    /// if the request should fail according to the configured failure rate.
+    async fn inject_faults(&self, message: &str) -> Result<(), ErrorResponse> {
+        let (latency, failure_rate) = {
+            let behavior = self.behavior.read();
+            (Duration::from_millis(behavior.latency_ms), behavior.failure_rate)
+        };
+
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+
+        if failure_rate > 0.0 && rand::random::<f64>() < failure_rate {
+            debug!(message, "Injecting mock relay failure");
+            return Err(ErrorResponse::new(500, message));
+        }
+
+        Ok(())
+    }
+
+    async fn status(State(relay): State<Arc<Self>>) -> StatusCode {
+        match relay.inject_faults("injected status failure").await {
+            Ok(()) => StatusCode::OK,
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    async fn register_validators(
+        State(relay): State<Arc<Self>>,
+        Json(registrations): Json<Vec<SignedValidatorRegistration>>,
+    ) -> Result<StatusCode, BuilderApiError> {
+        relay
+            .inject_faults("injected registration failure")
+            .await
+            .map_err(BuilderApiError::FailedRegisteringValidators)?;
+
+        debug!(count = registrations.len(), "Received validator registrations");
+        relay.state.lock().registrations.extend(registrations);
+        Ok(StatusCode::OK)
+    }
+
+    async fn submit_constraints(
+        State(relay): State<Arc<Self>>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<StatusCode, BuilderApiError> {
+        relay
+            .inject_faults("injected constraints submission failure")
+            .await
+            .map_err(BuilderApiError::FailedSubmittingConstraints)?;
+
+        let is_ssz = headers.get(CONTENT_TYPE).is_some_and(|value| value == OCTET_STREAM);
+        let constraints: BatchedSignedConstraints = if is_ssz {
+            if relay.behavior.read().reject_ssz {
+                return Err(BuilderApiError::FailedSubmittingConstraints(ErrorResponse::new(
+                    415,
+                    "SSZ constraints are not supported",
+                )));
+            }
+
+            decode_constraints_ssz(&body).map_err(|e| {
+                BuilderApiError::FailedSubmittingConstraints(ErrorResponse::new(400, e.to_string()))
+            })?
+        } else {
+            serde_json::from_slice(&body)?
+        };
+
+        let mut state = relay.state.lock();
+        for signed_constraints in constraints {
+            let slot = signed_constraints.message.slot;
+            info!(
+                slot,
+                txs = signed_constraints.message.transactions.len(),
+                "Received constraints"
+            );
+            state.constraints.entry(slot).or_default().push(signed_constraints);
+        }
+
+        Ok(StatusCode::OK)
+    }
+
+    async fn delegate(
+        State(relay): State<Arc<Self>>,
+        Json(delegations): Json<Vec<SignedDelegation>>,
+    ) -> Result<StatusCode, BuilderApiError> {
+        relay
+            .inject_faults("injected delegation failure")
+            .await
+            .map_err(BuilderApiError::FailedDelegating)?;
+
+        debug!(count = delegations.len(), "Received delegations");
+        relay.state.lock().delegations.extend(delegations);
+        Ok(StatusCode::OK)
+    }
+
+    async fn revoke(
+        State(relay): State<Arc<Self>>,
+        Json(revocations): Json<Vec<SignedRevocation>>,
+    ) -> Result<StatusCode, BuilderApiError> {
+        relay
+            .inject_faults("injected revocation failure")
+            .await
+            .map_err(BuilderApiError::FailedRevoking)?;
+
+        debug!(count = revocations.len(), "Received revocations");
+        relay.state.lock().delegations.retain(|delegation| {
+            !revocations.iter().any(|revocation| {
+                revocation.message.validator_pubkey == delegation.message.validator_pubkey &&
+                    revocation.message.delegatee_pubkey == delegation.message.delegatee_pubkey
+            })
+        });
+        Ok(StatusCode::OK)
+    }
+
+    async fn get_header_with_proofs(
+        State(relay): State<Arc<Self>>,
+        Path(params): Path<GetHeaderParams>,
+    ) -> Result<Json<VersionedValue<SignedBuilderBid>>, BuilderApiError> {
+        relay
+            .inject_faults("injected get header failure")
+            .await
+            .map_err(BuilderApiError::FailedGettingHeader)?;
+
+        let bid = relay.fabricate_bid(&params).map_err(|e| {
+            BuilderApiError::FailedGettingHeader(ErrorResponse::new(500, e.to_string()))
+        })?;
+
+        info!(slot = params.slot, hash = %bid.message.header.block_hash, "Returning fabricated bid");
+        Ok(Json(VersionedValue { version: Fork::Deneb, data: bid, meta: Default::default() }))
+    }
+
+    async fn get_payload(
+        State(relay): State<Arc<Self>>,
+        Json(signed_block): Json<SignedBlindedBeaconBlock>,
+    ) -> Result<Json<GetPayloadResponse>, BuilderApiError> {
+        relay
+            .inject_faults("injected get payload failure")
+            .await
+            .map_err(BuilderApiError::FailedGettingPayload)?;
+
+        let block_hash = &signed_block.message.body.execution_payload_header.block_hash;
+        let block_hash = B256::from_slice(block_hash.as_ref());
+
+        let Some(payload) = relay.state.lock().payloads.remove(&block_hash) else {
+            return Err(BuilderApiError::FailedGettingPayload(ErrorResponse::new(
+                400,
+                format!("unknown block hash {block_hash}"),
+            )));
+        };
+
+        info!(%block_hash, "Returning fabricated payload");
+        Ok(Json(GetPayloadResponse::Deneb(PayloadAndBlobs {
+            execution_payload: VersionedExecutionPayload::Deneb(payload),
+            blobs_bundle: Default::default(),
+        })))
+    }
+
+    async fn get_behavior(State(relay): State<Arc<Self>>) -> Json<MockRelayBehavior> {
+        Json(relay.behavior.read().clone())
+    }
+
+    async fn set_behavior(
+        State(relay): State<Arc<Self>>,
+        Json(behavior): Json<MockRelayBehavior>,
+    ) -> Json<MockRelayBehavior> {
+        info!(?behavior, "Updating mock relay behavior");
+        *relay.behavior.write() = behavior.clone();
+        Json(behavior)
+    }
+
+    async fn get_constraints(
+        State(relay): State<Arc<Self>>,
+        Path(slot): Path<u64>,
+    ) -> Json<Vec<SignedConstraints>> {
+        Json(relay.state.lock().constraints.get(&slot).cloned().unwrap_or_default())
+    }
+
+    async fn get_delegations(State(relay): State<Arc<Self>>) -> Json<Vec<SignedDelegation>> {
+        Json(relay.state.lock().delegations.clone())
+    }
+
+    async fn get_registrations(
+        State(relay): State<Arc<Self>>,
+    ) -> Json<Vec<SignedValidatorRegistration>> {
+        Json(relay.state.lock().registrations.clone())
+    }
+
+    /// Fabricate a signed bid on top of the requested parent, including the constrained
+    /// transactions of the slot, and keep the matching payload around for `get_payload`.
+    fn fabricate_bid(
+        &self,
+        params: &GetHeaderParams,
+    ) -> Result<SignedBuilderBid, MerkleizationError> {
+        let mut transactions = List::default();
+        if let Some(constraints) = self.state.lock().constraints.get(&params.slot) {
+            for tx in constraints.iter().flat_map(|sc| sc.message.transactions.iter()) {
+                let encoded = tx.clone().into_inner().into_transaction().encoded_2718();
+                transactions.push(Transaction::try_from(encoded.as_ref()).expect("valid tx bytes"));
+            }
+        }
+
+        let block_hash = B256::random();
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+
+        let payload = ExecutionPayload {
+            parent_hash: params.parent_hash.clone(),
+            block_number: params.slot,
+            gas_limit: MOCK_GAS_LIMIT,
+            timestamp,
+            block_hash: Hash32::try_from(block_hash.as_slice()).expect("valid hash"),
+            transactions,
+            ..Default::default()
+        };
+
+        let header = ExecutionPayloadHeader {
+            parent_hash: payload.parent_hash.clone(),
+            fee_recipient: payload.fee_recipient.clone(),
+            state_root: payload.state_root.clone(),
+            receipts_root: payload.receipts_root.clone(),
+            logs_bloom: payload.logs_bloom.clone(),
+            prev_randao: payload.prev_randao.clone(),
+            block_number: payload.block_number,
+            gas_limit: payload.gas_limit,
+            gas_used: payload.gas_used,
+            timestamp: payload.timestamp,
+            extra_data: payload.extra_data.clone(),
+            base_fee_per_gas: payload.base_fee_per_gas,
+            block_hash: payload.block_hash.clone(),
+            transactions_root: payload.transactions.hash_tree_root()?,
+            withdrawals_root: payload.withdrawals.hash_tree_root()?,
+            blob_gas_used: payload.blob_gas_used,
+            excess_blob_gas: payload.excess_blob_gas,
+        };
+
+        let pubkey = self.secret_key.sk_to_pk().to_bytes();
+        let message = BuilderBid {
+            header,
+            blob_kzg_commitments: List::default(),
+            value: self.behavior.read().bid_value,
+            public_key: BlsPublicKey::try_from(pubkey.as_slice()).expect("valid pubkey bytes"),
+        };
+        let signature = sign_builder_message(&self.chain, &self.secret_key, &message)?;
+
+        self.state.lock().payloads.insert(block_hash, payload);
+
+        Ok(SignedBuilderBid { message, signature })
+    }
+}
+
+/// Start the mock relay with the given options, serving requests until the process exits.
+pub async fn start_mock_relay(opts: MockRelayOpts) -> eyre::Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], opts.port));
+    let listener = TcpListener::bind(addr).await?;
+
+    info!(%addr, behavior = ?opts.behavior, "Starting mock relay...");
+    MockRelay::from_opts(opts).spawn(listener).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use alloy::primitives::bytes;
+
+    use crate::{
+        api::spec::{BuilderApi, ConstraintsApi},
+        builder::signature::verify_signed_builder_message,
+        client::{circuit_breaker::CircuitState, ConstraintsClient},
+        config::RelayOpts,
+        primitives::{ConstraintsMessage, FullTransaction},
+        test_util::launch_mock_relay,
+    };
+
+    use super::*;
+
+    fn test_constraints(slot: u64) -> SignedConstraints {
+        let tx_bytes = bytes!("f8678085019dc6838082520894deaddeaddeaddeaddeaddeaddeaddeaddeaddead38808360306ca06664c078fa60bd3ece050903dd295949908dd9686ec8871fa558f868e031cd39a00ed4f0b122b32b73f19230fabe6a726e2d07f84eda5beaa42a1ae1271bdee39f").to_vec();
+        let tx = FullTransaction::decode_enveloped(tx_bytes.as_slice()).unwrap();
+
+        let message = ConstraintsMessage::from_tx(BlsPublicKey::default(), slot, tx);
+        SignedConstraints { message, signature: Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_constraints_header_payload_cycle() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let (url, _handle) = launch_mock_relay(MockRelayBehavior::default()).await?;
+        let client = ConstraintsClient::new(url);
+        let slot = 42;
+
+        assert_eq!(client.status().await?, StatusCode::OK);
+
+        let constraints = test_constraints(slot);
+        client.submit_constraints(&vec![constraints.clone()]).await?;
+
+        let params = GetHeaderParams {
+            slot,
+            parent_hash: Hash32::try_from([1u8; 32].as_slice()).unwrap(),
+            public_key: BlsPublicKey::default(),
+        };
+        let bid = client.get_header_with_proofs(params).await?.data;
+        assert_eq!(bid.message.value, MockRelayBehavior::default().bid_value);
+
+        // The bid is signed by the relay with the builder domain of the chain
+        let pubkey = blst::min_pk::PublicKey::from_bytes(bid.message.public_key.as_ref()).unwrap();
+        let signature = alloy::rpc::types::beacon::BlsSignature::from_slice(bid.signature.as_ref());
+        assert!(verify_signed_builder_message(
+            &ChainConfig::default(),
+            &pubkey,
+            &bid.message,
+            &signature
+        )
+        .is_ok());
+
+        let mut signed_block = SignedBlindedBeaconBlock::default();
+        signed_block.message.slot = slot;
+        signed_block.message.body.execution_payload_header = bid.message.header.clone();
+
+        let GetPayloadResponse::Deneb(PayloadAndBlobs {
+            execution_payload: VersionedExecutionPayload::Deneb(payload),
+            ..
+        }) = client.get_payload(signed_block.clone()).await?
+        else {
+            panic!("expected a deneb payload");
+        };
+
+        // The payload includes the constrained transaction and matches the signed header
+        assert_eq!(payload.block_hash, bid.message.header.block_hash);
+        assert_eq!(payload.transactions.len(), 1);
+        assert_eq!(payload.transactions.hash_tree_root()?, bid.message.header.transactions_root);
+
+        // Payloads are only served once
+        assert!(client.get_payload(signed_block).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_api_controls_failures() -> eyre::Result<()> {
+        let (url, _handle) = launch_mock_relay(MockRelayBehavior::default()).await?;
+        let client = ConstraintsClient::new(url.clone());
+        let http = reqwest::Client::new();
+
+        let behavior = MockRelayBehavior { failure_rate: 1.0, ..Default::default() };
+        http.post(url.join(ADMIN_BEHAVIOR_PATH)?).json(&behavior).send().await?;
+
+        assert!(matches!(
+            client.submit_constraints(&vec![test_constraints(1)]).await,
+            Err(BuilderApiError::RelayRejected { .. })
+        ));
+
+        let behavior = MockRelayBehavior { failure_rate: 0.0, ..Default::default() };
+        http.post(url.join(ADMIN_BEHAVIOR_PATH)?).json(&behavior).send().await?;
+
+        client.submit_constraints(&vec![test_constraints(1)]).await?;
+
+        let constraints: Vec<SignedConstraints> =
+            http.get(url.join("/admin/constraints/1")?).send().await?.json().await?;
+        assert_eq!(constraints, vec![test_constraints(1)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ssz_constraints_fallback_to_json() -> eyre::Result<()> {
+        let behavior = MockRelayBehavior { reject_ssz: true, ..Default::default() };
+        let (url, _handle) = launch_mock_relay(behavior).await?;
+        let client = ConstraintsClient::new(url.clone()).with_ssz_constraints(true);
+        let http = reqwest::Client::new();
+
+        // The 415 is not an error, the constraints are resubmitted as JSON
+        client.submit_constraints(&vec![test_constraints(1)]).await?;
+        assert!(!client.ssz_constraints());
+
+        client.submit_constraints(&vec![test_constraints(1)]).await?;
+
+        let constraints: Vec<SignedConstraints> =
+            http.get(url.join("/admin/constraints/1")?).send().await?.json().await?;
+        assert_eq!(constraints, vec![test_constraints(1), test_constraints(1)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ssz_constraints_accepted() -> eyre::Result<()> {
+        let (url, _handle) = launch_mock_relay(MockRelayBehavior::default()).await?;
+        let client = ConstraintsClient::new(url.clone()).with_ssz_constraints(true);
+        let http = reqwest::Client::new();
+
+        client.submit_constraints(&vec![test_constraints(2)]).await?;
+        assert!(client.ssz_constraints());
+
+        let constraints: Vec<SignedConstraints> =
+            http.get(url.join("/admin/constraints/2")?).send().await?.json().await?;
+        assert_eq!(constraints, vec![test_constraints(2)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slow_relay_opens_circuit_breaker() -> eyre::Result<()> {
+        let behavior = MockRelayBehavior { latency_ms: 500, ..Default::default() };
+        let (url, _handle) = launch_mock_relay(behavior).await?;
+
+        let opts = RelayOpts {
+            submit_timeout_ms: 50,
+            breaker_threshold: 2,
+            breaker_cooldown_ms: 60_000,
+            ..Default::default()
+        };
+        let client = ConstraintsClient::new(url).with_relay_opts(opts);
+
+        // Submissions time out within their budget instead of waiting for the relay
+        for _ in 0..2 {
+            let start = Instant::now();
+            let res = client.submit_constraints(&vec![test_constraints(1)]).await;
+            assert!(matches!(res, Err(BuilderApiError::Timeout(_))));
+            assert!(start.elapsed() < Duration::from_millis(400));
+        }
+        assert_eq!(client.circuit_breaker().state(), CircuitState::Open);
+
+        // Requests to the open relay are skipped right away, on all the clones of the client
+        let start = Instant::now();
+        let res = client.clone().submit_constraints(&vec![test_constraints(1)]).await;
+        assert!(matches!(res, Err(BuilderApiError::CircuitOpen(_))));
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        Ok(())
+    }
+}
@@ -7,3 +7,6 @@ pub mod spec;
 
 /// Commitments-API spec and errors.
 pub mod commitments;
+
+/// A minimal relay implementing the Builder and Constraints APIs, for local development.
+pub mod mock_relay;
@@ -22,15 +22,23 @@ pub const STATUS_PATH: &str = "/eth/v1/builder/status";
 pub const REGISTER_VALIDATORS_PATH: &str = "/eth/v1/builder/validators";
 /// The path to the builder API get header endpoint.
 pub const GET_HEADER_PATH: &str = "/eth/v1/builder/header/:slot/:parent_hash/:pubkey";
+/// The path to the constraints API get header with proofs endpoint.
+pub const GET_HEADER_WITH_PROOFS_PATH: &str =
+    "/eth/v1/builder/header_with_proofs/:slot/:parent_hash/:pubkey";
 /// The path to the builder API get payload endpoint.
 pub const GET_PAYLOAD_PATH: &str = "/eth/v1/builder/blinded_blocks";
 /// The path to the constraints API submit constraints endpoint.
 pub const SUBMIT_CONSTRAINTS_PATH: &str = "/constraints/v1/builder/constraints";
+/// The path to the constraints API constraints stream endpoint, for builders.
+pub const CONSTRAINTS_STREAM_PATH: &str = "/constraints/v1/builder/constraints_stream";
 /// The path to the constraints API delegate endpoint.
 pub const DELEGATE_PATH: &str = "/constraints/v1/builder/delegate";
 /// The path to the constraints API revoke endpoint.
 pub const REVOKE_PATH: &str = "/constraints/v1/builder/revoke";
 
+/// The content type of SSZ-encoded request and response bodies.
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
 /// A response object for errors.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponse {
@@ -39,6 +47,13 @@ pub struct ErrorResponse {
     message: String,
 }
 
+impl ErrorResponse {
+    /// Create a new error response with the given status code and message.
+    pub fn new(code: u16, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
 /// Helper to serialize a status code as a string using the provided serializer.
 pub fn serialize_status_code<S>(value: &u16, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -59,6 +74,10 @@ pub enum BuilderApiError {
     FailedGettingPayload(ErrorResponse),
     #[error("Failed submitting constraints: {0:?}")]
     FailedSubmittingConstraints(ErrorResponse),
+    /// The relay refused the request. Its response is kept as-is, as it may not be a
+    /// well-formed [ErrorResponse].
+    #[error("Relay answered with status {status}: {body}")]
+    RelayRejected { status: u16, body: String },
     #[error("Failed to delegate constraint submission rights: {0:?}")]
     FailedDelegating(ErrorResponse),
     #[error("Failed to revoke constraint submission rights: {0:?}")]
@@ -75,12 +94,39 @@ pub enum BuilderApiError {
     Timeout(#[from] tokio::time::error::Elapsed),
     #[error("Invalid fork: {0}")]
     InvalidFork(String),
+    #[error("Endpoint not supported by the relay: {0}")]
+    UnsupportedEndpoint(&'static str),
+    #[error("Circuit breaker of relay {0} is open, skipping request")]
+    CircuitOpen(String),
+    #[error("Invalid slot: {0}")]
+    InvalidSlot(String),
+    #[error("The templates of slot {0} have been pruned")]
+    SlotPruned(u64),
     #[error("Locally-built payload does not match expected signed header")]
     LocalPayloadIntegrity(#[from] super::builder::LocalPayloadIntegrityError),
     #[error("Generic error: {0}")]
     Generic(String),
 }
 
+impl BuilderApiError {
+    /// Returns true if the error is a failure of the relay itself, i.e. it timed out,
+    /// couldn't be reached or answered with a server error, as opposed to rejecting
+    /// the request.
+    pub fn is_relay_failure(&self) -> bool {
+        match self {
+            Self::FailedRegisteringValidators(error) |
+            Self::FailedGettingHeader(error) |
+            Self::FailedGettingPayload(error) |
+            Self::FailedSubmittingConstraints(error) |
+            Self::FailedDelegating(error) |
+            Self::FailedRevoking(error) => error.code >= 500,
+            Self::RelayRejected { status, .. } => *status >= 500,
+            Self::ReqwestError(_) | Self::Timeout(_) => true,
+            _ => false,
+        }
+    }
+}
+
 impl IntoResponse for BuilderApiError {
     fn into_response(self) -> Response {
         match self {
@@ -96,6 +142,10 @@ impl IntoResponse for BuilderApiError {
             BuilderApiError::FailedSubmittingConstraints(error) => {
                 (StatusCode::from_u16(error.code).unwrap(), Json(error)).into_response()
             }
+            BuilderApiError::RelayRejected { status, body } => {
+                (StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY), body)
+                    .into_response()
+            }
             BuilderApiError::FailedDelegating(error) => {
                 (StatusCode::from_u16(error.code).unwrap(), Json(error)).into_response()
             }
@@ -124,6 +174,18 @@ impl IntoResponse for BuilderApiError {
             BuilderApiError::InvalidFork(err) => {
                 (StatusCode::BAD_REQUEST, Json(err)).into_response()
             }
+            BuilderApiError::UnsupportedEndpoint(_) => {
+                (StatusCode::NOT_FOUND, self.to_string()).into_response()
+            }
+            BuilderApiError::CircuitOpen(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, self.to_string()).into_response()
+            }
+            BuilderApiError::InvalidSlot(_) => {
+                (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+            }
+            BuilderApiError::SlotPruned(_) => {
+                (StatusCode::NOT_FOUND, self.to_string()).into_response()
+            }
             BuilderApiError::LocalPayloadIntegrity(err) => {
                 (StatusCode::BAD_REQUEST, err.to_string()).into_response()
             }
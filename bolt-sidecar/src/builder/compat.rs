@@ -20,8 +20,13 @@ use ethereum_consensus::{
         },
         ExecutionAddress, ExecutionPayload as DenebExecutionPayload,
     },
+    electra::mainnet::{
+        ExecutionPayload as ElectraExecutionPayload,
+        ExecutionPayloadHeader as ElectraExecutionPayloadHeader,
+    },
     ssz::prelude::{ssz_rs, ByteList, ByteVector, HashTreeRoot, List},
     types::mainnet::ExecutionPayload as ConsensusExecutionPayload,
+    Fork,
 };
 use reth_primitives::{SealedBlock, TransactionSigned};
 
@@ -76,6 +81,45 @@ pub(crate) fn to_execution_payload_header(
     }
 }
 
+/// Compatibility: convert a Deneb execution payload header into the Electra header of the
+/// given payload. The execution requests roots are computed from the payload.
+pub(crate) fn to_electra_execution_payload_header(
+    header: ConsensusExecutionPayloadHeader,
+    payload: &ElectraExecutionPayload,
+) -> ElectraExecutionPayloadHeader {
+    ElectraExecutionPayloadHeader {
+        parent_hash: header.parent_hash,
+        fee_recipient: header.fee_recipient,
+        state_root: header.state_root,
+        receipts_root: header.receipts_root,
+        logs_bloom: header.logs_bloom,
+        prev_randao: header.prev_randao,
+        block_number: header.block_number,
+        gas_limit: header.gas_limit,
+        gas_used: header.gas_used,
+        timestamp: header.timestamp,
+        extra_data: header.extra_data,
+        base_fee_per_gas: header.base_fee_per_gas,
+        block_hash: header.block_hash,
+        transactions_root: header.transactions_root,
+        withdrawals_root: header.withdrawals_root,
+        blob_gas_used: header.blob_gas_used,
+        excess_blob_gas: header.excess_blob_gas,
+        deposit_requests_root: payload
+            .deposit_requests
+            .hash_tree_root()
+            .expect("valid deposit requests root"),
+        withdrawal_requests_root: payload
+            .withdrawal_requests
+            .hash_tree_root()
+            .expect("valid withdrawal requests root"),
+        consolidation_requests_root: payload
+            .consolidation_requests
+            .hash_tree_root()
+            .expect("valid consolidation requests root"),
+    }
+}
+
 /// Compatibility: convert a sealed block into an Alloy execution payload
 pub(crate) fn to_alloy_execution_payload(
     block: &SealedBlock,
@@ -123,8 +167,45 @@ pub(crate) fn to_alloy_execution_payload(
     })
 }
 
-/// Compatibility: convert a sealed block into an ethereum-consensus execution payload
-pub(crate) fn to_consensus_execution_payload(value: &SealedBlock) -> ConsensusExecutionPayload {
+/// Compatibility: convert a sealed block into an ethereum-consensus execution payload of the
+/// given fork. Electra payloads have empty execution requests, as they can't be built locally.
+pub(crate) fn to_consensus_execution_payload(
+    value: &SealedBlock,
+    fork: Fork,
+) -> ConsensusExecutionPayload {
+    let payload = to_deneb_execution_payload(value);
+
+    match fork {
+        Fork::Electra => ConsensusExecutionPayload::Electra(ElectraExecutionPayload {
+            parent_hash: payload.parent_hash,
+            fee_recipient: payload.fee_recipient,
+            state_root: payload.state_root,
+            receipts_root: payload.receipts_root,
+            logs_bloom: payload.logs_bloom,
+            prev_randao: payload.prev_randao,
+            block_number: payload.block_number,
+            gas_limit: payload.gas_limit,
+            gas_used: payload.gas_used,
+            timestamp: payload.timestamp,
+            extra_data: payload.extra_data,
+            base_fee_per_gas: payload.base_fee_per_gas,
+            block_hash: payload.block_hash,
+            transactions: payload.transactions,
+            withdrawals: payload.withdrawals,
+            blob_gas_used: payload.blob_gas_used,
+            excess_blob_gas: payload.excess_blob_gas,
+            deposit_requests: List::default(),
+            withdrawal_requests: List::default(),
+            consolidation_requests: List::default(),
+        }),
+        _ => ConsensusExecutionPayload::Deneb(payload),
+    }
+}
+
+/// Compatibility: convert a sealed block into a Deneb ethereum-consensus execution payload
+fn to_deneb_execution_payload(
+    value: &SealedBlock,
+) -> ethereum_consensus::deneb::mainnet::ExecutionPayload {
     let hash = value.hash();
     let header = &value.header;
     let transactions = &value.body.transactions;
@@ -145,7 +226,7 @@ pub(crate) fn to_consensus_execution_payload(value: &SealedBlock) -> ConsensusEx
         })
         .collect::<Vec<_>>();
 
-    let payload = DenebExecutionPayload {
+    DenebExecutionPayload {
         parent_hash: to_bytes32(header.parent_hash),
         fee_recipient: to_bytes20(header.beneficiary),
         state_root: to_bytes32(header.state_root),
@@ -163,8 +244,7 @@ pub(crate) fn to_consensus_execution_payload(value: &SealedBlock) -> ConsensusEx
         withdrawals: TryFrom::try_from(withdrawals).unwrap(),
         blob_gas_used: value.blob_gas_used(),
         excess_blob_gas: value.excess_blob_gas.unwrap_or_default(),
-    };
-    ConsensusExecutionPayload::Deneb(payload)
+    }
 }
 
 /// Compatibility: convert a Withdrawal from ethereum-consensus to alloy::primitives
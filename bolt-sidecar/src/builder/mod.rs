@@ -1,16 +1,19 @@
 use alloy::primitives::U256;
 use beacon_api_client::mainnet::Client as BeaconClient;
 use ethereum_consensus::{
-    crypto::{KzgCommitment, PublicKey},
-    deneb::mainnet::ExecutionPayloadHeader,
+    crypto::PublicKey,
+    deneb::mainnet::SignedBlindedBeaconBlock,
     ssz::prelude::{List, MerkleizationError},
+    types::mainnet::ExecutionPayload,
+    Fork,
 };
 
 use crate::{
     common::BlsSecretKeyWrapper,
     config::{ChainConfig, Opts},
     primitives::{
-        BuilderBid, GetPayloadResponse, PayloadAndBid, PayloadAndBlobs, SignedBuilderBid,
+        BuilderBid, ElectraBuilderBid, GetPayloadResponse, PayloadAndBid, PayloadAndBlobs,
+        SignedBuilderBid, SignedElectraBuilderBid, VersionedSignedBuilderBid,
     },
 };
 
@@ -27,7 +30,7 @@ pub mod signature;
 use signature::sign_builder_message;
 
 /// Fallback Payload builder agent that leverages the engine API's
-/// `engine_newPayloadV3` response error to produce a valid payload.
+/// `engine_newPayloadV3` (`V4` from Electra) response error to produce a valid payload.
 pub mod payload_builder;
 use payload_builder::FallbackPayloadBuilder;
 
@@ -35,6 +38,10 @@ use payload_builder::FallbackPayloadBuilder;
 pub mod payload_fetcher;
 pub use payload_fetcher::{LocalPayloadFetcher, PayloadFetcher};
 
+/// Cache of the local payload, checked against the requests before being served.
+pub mod payload_cache;
+pub use payload_cache::{LocalPayloadCache, PayloadMismatch};
+
 /// Compatibility types and utilities between Alloy, Reth,
 /// Ethereum-consensus and other crates.
 #[doc(hidden)]
@@ -79,15 +86,15 @@ pub struct LocalBuilder {
     /// Async fallback payload builder to generate valid payloads with
     /// the engine API's `engine_newPayloadV3` response error.
     fallback_builder: FallbackPayloadBuilder,
-    /// The last payload and bid that was built by the local builder.
-    payload_and_bid: Option<PayloadAndBid>,
+    /// The last payload and bid built by the local builder, along with the bids served.
+    payload_cache: LocalPayloadCache,
 }
 
 impl LocalBuilder {
     /// Create a new local builder with the given secret key.
     pub fn new(opts: &Opts, beacon_api_client: BeaconClient, genesis_time: u64) -> Self {
         Self {
-            payload_and_bid: None,
+            payload_cache: LocalPayloadCache::default(),
             fallback_builder: FallbackPayloadBuilder::new(opts, beacon_api_client, genesis_time),
             secret_key: opts.builder_private_key.clone(),
             chain: opts.chain,
@@ -95,19 +102,27 @@ impl LocalBuilder {
     }
 
     /// Build a new payload with the given transactions. This method will
-    /// cache the payload in the local builder instance, and make it available
+    /// cache the payload in the local builder instance, and make it available.
+    ///
+    /// The payload and bid are built for the fork active at the given slot. If the proposer
+    /// registered a gas limit, the gas limit of the payload moves toward it.
     pub async fn build_new_local_payload(
         &mut self,
         slot: u64,
         template: &BlockTemplate,
+        registered_gas_limit: Option<u64>,
     ) -> Result<(), BuilderError> {
         let transactions = template.as_signed_transactions();
         let blobs_bundle = template.as_blobs_bundle();
         let kzg_commitments = blobs_bundle.commitments.clone();
+        let fork = self.chain.fork_at_slot(slot);
 
         // 1. build a fallback payload with the given transactions, on top of
         // the current head of the chain
-        let block = self.fallback_builder.build_fallback_payload(slot, &transactions).await?;
+        let block = self
+            .fallback_builder
+            .build_fallback_payload(slot, fork, &transactions, registered_gas_limit)
+            .await?;
 
         // NOTE: we use a big value for the bid to ensure it gets chosen by constraints client.
         // the client has no way to actually verify this, and we don't need to trust
@@ -117,48 +132,64 @@ impl LocalBuilder {
         // to ALWAYS prefer PBS blocks. This is a safety measure that doesn't hurt to keep.
         let value = U256::from(100_000_000_000_000_000_000u128);
 
-        let eth_payload = compat::to_consensus_execution_payload(&block);
-        let payload_and_blobs = PayloadAndBlobs { execution_payload: eth_payload, blobs_bundle };
+        let eth_payload = compat::to_consensus_execution_payload(&block, fork);
 
         // 2. create a signed builder bid with the sealed block header we just created
         let eth_header = compat::to_execution_payload_header(&block, transactions);
+        let blob_kzg_commitments = List::try_from(kzg_commitments).expect("valid list");
 
         // 3. sign the bid with the local builder's BLS key
-        let signed_bid = self.create_signed_builder_bid(value, eth_header, kzg_commitments)?;
+        let signed_bid = match &eth_payload {
+            ExecutionPayload::Electra(payload) => {
+                let header = compat::to_electra_execution_payload_header(eth_header, payload);
+                let message = ElectraBuilderBid {
+                    header,
+                    blob_kzg_commitments,
+                    public_key: self.public_key(),
+                    value,
+                };
+                let signature = sign_builder_message(&self.chain, &self.secret_key, &message)?;
+                VersionedSignedBuilderBid::Electra(SignedElectraBuilderBid { message, signature })
+            }
+            _ => {
+                let message = BuilderBid {
+                    header: eth_header,
+                    blob_kzg_commitments,
+                    public_key: self.public_key(),
+                    value,
+                };
+                let signature = sign_builder_message(&self.chain, &self.secret_key, &message)?;
+                VersionedSignedBuilderBid::Deneb(SignedBuilderBid { message, signature })
+            }
+        };
+
+        let payload_and_blobs = PayloadAndBlobs { execution_payload: eth_payload, blobs_bundle };
 
         // 4. prepare a get_payload response for when the beacon node will ask for it
         let get_payload_response = GetPayloadResponse::from(payload_and_blobs);
 
-        self.payload_and_bid =
-            Some(PayloadAndBid { bid: signed_bid, payload: get_payload_response });
+        self.payload_cache
+            .insert(slot, PayloadAndBid { bid: signed_bid, payload: get_payload_response });
 
         Ok(())
     }
 
-    /// Get the cached payload and bid from the local builder, consuming the value.
+    /// Get the cached payload and bid for the given slot from the local builder, checking
+    /// that its block hash matches the bid already served for the slot and the signed
+    /// blinded block, if any.
     #[inline]
-    pub fn get_cached_payload(&mut self) -> Option<PayloadAndBid> {
-        self.payload_and_bid.take()
+    pub fn get_cached_payload(
+        &mut self,
+        slot: u64,
+        signed_blinded_block: Option<&SignedBlindedBeaconBlock>,
+    ) -> Result<Option<PayloadAndBid>, PayloadMismatch> {
+        self.payload_cache.get(slot, signed_blinded_block)
     }
 
-    /// transform a sealed header into a signed builder bid using
-    /// the local builder's BLS key.
-    fn create_signed_builder_bid(
-        &self,
-        value: U256,
-        header: ExecutionPayloadHeader,
-        blob_kzg_commitments: Vec<KzgCommitment>,
-    ) -> Result<SignedBuilderBid, BuilderError> {
+    /// The public key of the local builder.
+    fn public_key(&self) -> PublicKey {
         // compat: convert from blst to ethereum consensus types
         let pubkey = self.secret_key.sk_to_pk().to_bytes();
-        let consensus_pubkey = PublicKey::try_from(pubkey.as_slice()).expect("valid pubkey bytes");
-        let blob_kzg_commitments = List::try_from(blob_kzg_commitments).expect("valid list");
-
-        let message =
-            BuilderBid { header, blob_kzg_commitments, public_key: consensus_pubkey, value };
-
-        let signature = sign_builder_message(&self.chain, &self.secret_key, &message)?;
-
-        Ok(SignedBuilderBid { message, signature })
+        PublicKey::try_from(pubkey.as_slice()).expect("valid pubkey bytes")
     }
 }
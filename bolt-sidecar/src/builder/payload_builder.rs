@@ -5,19 +5,23 @@ use std::{
 
 use alloy::{
     consensus::{Header, EMPTY_OMMER_ROOT_HASH},
-    eips::{calc_excess_blob_gas, calc_next_block_base_fee, eip1559::BaseFeeParams},
+    eips::{
+        calc_excess_blob_gas, calc_next_block_base_fee, eip1559::BaseFeeParams,
+        eip7685::EMPTY_REQUESTS_HASH,
+    },
     primitives::{Address, Bloom, Bytes, B256, B64, U256},
     rpc::types::{Block, Withdrawal, Withdrawals},
 };
 use alloy_rpc_types_engine::{Claims, ExecutionPayload, JwtSecret};
 use axum::http::HeaderValue;
 use beacon_api_client::{BlockId, StateId};
+use ethereum_consensus::Fork;
 use hex::FromHex;
 use regex::Regex;
 use reqwest::Url;
 use reth_primitives::{proofs, BlockBody, SealedBlock, SealedHeader, TransactionSigned};
 use serde_json::Value;
-use tracing::trace;
+use tracing::{debug, trace};
 
 use super::{
     compat::{to_alloy_execution_payload, to_alloy_withdrawal},
@@ -37,6 +41,13 @@ const DEFAULT_EXTRA_DATA: [u8; 20] = [
     0x42, 0x6f, 0x6c, 0x74,
 ];
 
+/// The bound divisor of the gas limit: a block can move the gas limit of its parent by less
+/// than `parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR`.
+const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+
+/// The minimum gas limit of a block.
+const MIN_GAS_LIMIT: u64 = 5000;
+
 /// The fallback payload builder is responsible for assembling a valid
 /// sealed block from a set of transactions. It (ab)uses the engine API
 /// to fetch "hints" for missing header values, such as the block hash,
@@ -95,6 +106,8 @@ struct Context {
     withdrawals_root: B256,
     parent_beacon_block_root: B256,
     block_timestamp: u64,
+    gas_limit: u64,
+    requests_hash: Option<B256>,
 }
 
 #[derive(Debug, Default)]
@@ -109,10 +122,18 @@ struct Hints {
 impl FallbackPayloadBuilder {
     /// Build a minimal payload to be used as a fallback in case PBS relays fail
     /// to provide a valid payload that fulfills the commitments made by Bolt.
+    ///
+    /// Electra payloads carry no execution requests, so their header commits to
+    /// the hash of an empty requests list.
+    ///
+    /// If a target gas limit is given, e.g. the one registered by the proposer, the gas limit
+    /// of the payload moves toward it from the parent one. Otherwise, it's kept unchanged.
     pub async fn build_fallback_payload(
         &self,
         target_slot: u64,
+        fork: Fork,
         transactions: &[TransactionSigned],
+        target_gas_limit: Option<u64>,
     ) -> Result<SealedBlock, BuilderError> {
         // We fetch the latest block to get the necessary parent values for the new block.
         // For the timestamp, we must use the one expected by the beacon chain instead, to
@@ -160,6 +181,15 @@ impl FallbackPayloadBuilder {
         // been missed by the proposers immediately before us.
         let block_timestamp = self.genesis_time + (target_slot * self.slot_time);
 
+        let execution_requests = (fork == Fork::Electra).then(Vec::new);
+
+        let parent_gas_limit = latest_block.header.gas_limit;
+        let gas_limit = target_gas_limit
+            .map_or(parent_gas_limit, |target| next_block_gas_limit(parent_gas_limit, target));
+        if gas_limit != parent_gas_limit {
+            debug!(parent_gas_limit, gas_limit, ?target_gas_limit, "Adjusting the gas limit");
+        }
+
         let ctx = Context {
             base_fee,
             blob_gas_used,
@@ -171,6 +201,8 @@ impl FallbackPayloadBuilder {
             transactions_root: proofs::calculate_transaction_root(transactions),
             withdrawals_root: proofs::calculate_withdrawals_root(&withdrawals),
             block_timestamp,
+            gas_limit,
+            requests_hash: execution_requests.as_ref().map(|_| EMPTY_REQUESTS_HASH),
         };
 
         let body = BlockBody {
@@ -195,7 +227,12 @@ impl FallbackPayloadBuilder {
 
             let engine_hint = self
                 .engine_hinter
-                .fetch_next_payload_hint(&exec_payload, &versioned_hashes, parent_beacon_block_root)
+                .fetch_next_payload_hint(
+                    &exec_payload,
+                    &versioned_hashes,
+                    parent_beacon_block_root,
+                    execution_requests.as_deref(),
+                )
                 .await?;
 
             trace!("engine_hint: {:?}", engine_hint);
@@ -298,20 +335,33 @@ pub(crate) struct EngineHinter {
 
 impl EngineHinter {
     /// Fetch the next payload hint from the engine API to complete the sealed block.
+    ///
+    /// Payloads with execution requests are sent with `engine_newPayloadV4`, and the others
+    /// with `engine_newPayloadV3`.
     pub async fn fetch_next_payload_hint(
         &self,
         exec_payload: &ExecutionPayload,
         versioned_hashes: &[B256],
         parent_beacon_root: B256,
+        execution_requests: Option<&[Bytes]>,
     ) -> Result<EngineApiHint, BuilderError> {
         let auth_jwt = secret_to_bearer_header(&JwtSecret::from_hex(&self.jwt_hex)?);
 
-        let body = format!(
-            r#"{{"id":1,"jsonrpc":"2.0","method":"engine_newPayloadV3","params":[{}, {}, "{:?}"]}}"#,
-            serde_json::to_string(&exec_payload)?,
-            serde_json::to_string(&versioned_hashes)?,
-            parent_beacon_root
-        );
+        let body = match execution_requests {
+            Some(requests) => format!(
+                r#"{{"id":1,"jsonrpc":"2.0","method":"engine_newPayloadV4","params":[{}, {}, "{:?}", {}]}}"#,
+                serde_json::to_string(&exec_payload)?,
+                serde_json::to_string(&versioned_hashes)?,
+                parent_beacon_root,
+                serde_json::to_string(&requests)?
+            ),
+            None => format!(
+                r#"{{"id":1,"jsonrpc":"2.0","method":"engine_newPayloadV3","params":[{}, {}, "{:?}"]}}"#,
+                serde_json::to_string(&exec_payload)?,
+                serde_json::to_string(&versioned_hashes)?,
+                parent_beacon_root
+            ),
+        };
 
         let raw_hint = self
             .client
@@ -372,6 +422,19 @@ pub(crate) fn parse_geth_response(error: &str) -> Option<String> {
         .and_then(|capture| capture.get(1).map(|matched| matched.as_str().to_string()))
 }
 
+/// Returns the gas limit of a block on top of a parent with the given gas limit, moved toward
+/// the desired one by at most the bound allowed by consensus, as the execution clients do.
+fn next_block_gas_limit(parent_gas_limit: u64, desired_gas_limit: u64) -> u64 {
+    let desired_gas_limit = desired_gas_limit.max(MIN_GAS_LIMIT);
+    let delta = (parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR).saturating_sub(1);
+
+    if parent_gas_limit < desired_gas_limit {
+        parent_gas_limit.saturating_add(delta).min(desired_gas_limit)
+    } else {
+        parent_gas_limit.saturating_sub(delta).max(desired_gas_limit)
+    }
+}
+
 /// Build a header with the given hints and context values.
 fn build_header_with_hints_and_context(
     latest_block: &Block,
@@ -395,7 +458,7 @@ fn build_header_with_hints_and_context(
         logs_bloom,
         difficulty: U256::ZERO,
         number: latest_block.header.number + 1,
-        gas_limit: latest_block.header.gas_limit,
+        gas_limit: context.gas_limit,
         gas_used,
         timestamp: context.block_timestamp,
         mix_hash: context.prev_randao,
@@ -404,7 +467,7 @@ fn build_header_with_hints_and_context(
         blob_gas_used: Some(context.blob_gas_used),
         excess_blob_gas: Some(context.excess_blob_gas),
         parent_beacon_block_root: Some(context.parent_beacon_block_root),
-        requests_hash: None,
+        requests_hash: context.requests_hash,
         extra_data: context.extra_data.clone(),
     }
 }
@@ -453,7 +516,7 @@ mod tests {
     use tracing::warn;
 
     use crate::{
-        builder::payload_builder::FallbackPayloadBuilder,
+        builder::payload_builder::{next_block_gas_limit, FallbackPayloadBuilder, MIN_GAS_LIMIT},
         test_util::{default_test_transaction, get_test_config},
     };
 
@@ -486,12 +549,26 @@ mod tests {
             (SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() / cfg.chain.slot_time()) +
             1;
 
-        let block = builder.build_fallback_payload(slot, &[tx_signed_reth]).await?;
+        let fork = cfg.chain.fork_at_slot(slot);
+        let block = builder.build_fallback_payload(slot, fork, &[tx_signed_reth], None).await?;
         assert_eq!(block.body.transactions.len(), 1);
 
         Ok(())
     }
 
+    #[test]
+    fn test_next_block_gas_limit() {
+        // The gas limit moves toward the desired one by less than 1/1024 of the parent's
+        assert_eq!(next_block_gas_limit(30_000_000, 36_000_000), 30_029_295);
+        assert_eq!(next_block_gas_limit(30_000_000, 10_000_000), 29_970_705);
+        assert_eq!(next_block_gas_limit(30_000_000, 30_000_000), 30_000_000);
+
+        // And stops once reached
+        assert_eq!(next_block_gas_limit(30_000_000, 30_010_000), 30_010_000);
+        assert_eq!(next_block_gas_limit(30_000_000, 29_990_000), 29_990_000);
+        assert_eq!(next_block_gas_limit(MIN_GAS_LIMIT, 0), MIN_GAS_LIMIT);
+    }
+
     #[test]
     fn test_empty_el_withdrawals_root() {
         // Withdrawal root in the execution layer header is MPT.
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use ethereum_consensus::{deneb::mainnet::SignedBlindedBeaconBlock, primitives::Hash32};
+use thiserror::Error;
+
+use crate::primitives::{PayloadAndBid, Slot};
+
+/// The number of slots for which the block hashes of the served bids are kept.
+const SERVED_BIDS_RETENTION_SLOTS: u64 = 64;
+
+/// A mismatch between a request for a local payload and the cached one.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PayloadMismatch {
+    /// The cached payload was built for another slot.
+    #[error("requested slot {requested}, but the local payload is for slot {cached}")]
+    Slot {
+        /// The requested slot.
+        requested: Slot,
+        /// The slot of the cached payload.
+        cached: Slot,
+    },
+    /// The block hash of the cached payload doesn't match the one expected by the request.
+    #[error("expected block hash {expected}, but the local payload has block hash {payload}")]
+    BlockHash {
+        /// The block hash of the signed header or of the bid previously served for the slot.
+        expected: Hash32,
+        /// The block hash of the cached payload.
+        payload: Hash32,
+    },
+}
+
+/// The latest local payload and bid, along with the block hashes of the bids already served
+/// for the recent slots.
+///
+/// The payload is only handed out for the slot it was built for, and only if its block hash
+/// matches the bid previously served for that slot and the signed header of the request, if
+/// any. Otherwise the proposer would sign a header that doesn't match the payload.
+#[derive(Debug, Default)]
+pub struct LocalPayloadCache {
+    latest: Option<(Slot, PayloadAndBid)>,
+    served_bids: BTreeMap<Slot, Hash32>,
+}
+
+impl LocalPayloadCache {
+    /// Cache the payload and bid built for the given slot, replacing the previous one.
+    pub fn insert(&mut self, slot: Slot, payload_and_bid: PayloadAndBid) {
+        self.latest = Some((slot, payload_and_bid));
+    }
+
+    /// Returns the cached payload and bid for the given slot, if any, after checking it
+    /// against the bid previously served for the slot and the signed blinded block.
+    ///
+    /// The block hash of the returned bid is recorded as served for the slot.
+    pub fn get(
+        &mut self,
+        slot: Slot,
+        signed_blinded_block: Option<&SignedBlindedBeaconBlock>,
+    ) -> Result<Option<PayloadAndBid>, PayloadMismatch> {
+        let Some((cached_slot, payload_and_bid)) = &self.latest else {
+            return Ok(None);
+        };
+
+        if *cached_slot != slot {
+            return Err(PayloadMismatch::Slot { requested: slot, cached: *cached_slot });
+        }
+
+        let payload_hash = payload_and_bid.payload.block_hash();
+        let header_hash = signed_blinded_block
+            .map(|block| &block.message.body.execution_payload_header.block_hash);
+
+        for expected in [self.served_bids.get(&slot), header_hash].into_iter().flatten() {
+            if expected != payload_hash {
+                return Err(PayloadMismatch::BlockHash {
+                    expected: expected.clone(),
+                    payload: payload_hash.clone(),
+                });
+            }
+        }
+
+        let bid_hash = payload_and_bid.bid.block_hash().clone();
+        self.served_bids.insert(slot, bid_hash);
+        self.served_bids.retain(|served, _| served + SERVED_BIDS_RETENTION_SLOTS > slot);
+
+        Ok(Some(payload_and_bid.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitives::{GetPayloadResponse, PayloadAndBlobs, SignedBuilderBid};
+
+    use super::*;
+
+    fn test_payload(block_hash: Hash32) -> PayloadAndBid {
+        let mut bid = SignedBuilderBid::default();
+        bid.message.header.block_hash = block_hash.clone();
+
+        let mut payload = PayloadAndBlobs::default();
+        if let Some(deneb) = payload.execution_payload.deneb_mut() {
+            deneb.block_hash = block_hash;
+        }
+
+        PayloadAndBid { bid: bid.into(), payload: GetPayloadResponse::Deneb(payload) }
+    }
+
+    fn signed_block(block_hash: Hash32) -> SignedBlindedBeaconBlock {
+        let mut block = SignedBlindedBeaconBlock::default();
+        block.message.body.execution_payload_header.block_hash = block_hash;
+        block
+    }
+
+    fn hash(byte: u8) -> Hash32 {
+        Hash32::try_from([byte; 32].as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_matching_payload() {
+        let mut cache = LocalPayloadCache::default();
+        assert!(matches!(cache.get(10, None), Ok(None)));
+
+        cache.insert(10, test_payload(hash(1)));
+        let served = cache.get(10, None).unwrap().expect("cached payload");
+        assert_eq!(served.payload.block_hash(), &hash(1));
+
+        // The payload can be served again, with the signed header of the served bid
+        let served = cache.get(10, Some(&signed_block(hash(1)))).unwrap();
+        assert!(served.is_some());
+    }
+
+    #[test]
+    fn test_slot_mismatch() {
+        let mut cache = LocalPayloadCache::default();
+        cache.insert(9, test_payload(hash(1)));
+
+        assert_eq!(
+            cache.get(10, None).unwrap_err(),
+            PayloadMismatch::Slot { requested: 10, cached: 9 }
+        );
+
+        // The payload is still available for its own slot
+        assert!(cache.get(9, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_block_hash_mismatch() {
+        let mut cache = LocalPayloadCache::default();
+        cache.insert(10, test_payload(hash(1)));
+
+        // The signed header doesn't match the payload
+        assert_eq!(
+            cache.get(10, Some(&signed_block(hash(2)))).unwrap_err(),
+            PayloadMismatch::BlockHash { expected: hash(2), payload: hash(1) }
+        );
+
+        // The payload was rebuilt after its bid was served for the slot
+        assert!(cache.get(10, None).unwrap().is_some());
+        cache.insert(10, test_payload(hash(3)));
+        assert_eq!(
+            cache.get(10, None).unwrap_err(),
+            PayloadMismatch::BlockHash { expected: hash(1), payload: hash(3) }
+        );
+    }
+}
@@ -22,7 +22,7 @@ impl PayloadFetcher for LocalPayloadFetcher {
     async fn fetch_payload(&self, slot: u64) -> Option<PayloadAndBid> {
         let (response_tx, response_rx) = oneshot::channel();
 
-        let fetch_params = FetchPayloadRequest { response_tx, slot };
+        let fetch_params = FetchPayloadRequest { response_tx, slot, signed_blinded_block: None };
         self.tx.send(fetch_params).await.ok()?;
 
         match response_rx.await {
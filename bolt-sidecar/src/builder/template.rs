@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use alloy::primitives::{Address, TxHash, U256};
 use ethereum_consensus::{
@@ -22,7 +22,7 @@ use crate::{
 /// - Simulate new commitment requests.
 /// - Update state every block, to invalidate old commitments.
 /// - Make sure we DO NOT accept invalid commitments in any circumstances.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct BlockTemplate {
     /// The state diffs per address given the list of commitments.
     pub(crate) state_diff: StateDiff,
@@ -42,12 +42,24 @@ impl BlockTemplate {
         self.signed_constraints_list.iter().flat_map(|sc| sc.message.transactions.clone()).collect()
     }
 
-    /// Converts the list of signed constraints into a list of signed transactions. Use this when
-    /// building a local execution payload.
+    /// Returns the signed constraints in the order of their transactions in a locally built
+    /// block: the top-of-block constraints first, then the others, both in acceptance order.
+    fn constraints_in_block_order(&self) -> impl Iterator<Item = &SignedConstraints> {
+        let (top, rest): (Vec<_>, Vec<_>) =
+            self.signed_constraints_list.iter().partition(|sc| sc.message.top);
+        top.into_iter().chain(rest)
+    }
+
+    /// Get all the transactions in the signed constraints list, in block order.
+    pub fn transactions_in_block_order(&self) -> Vec<FullTransaction> {
+        self.constraints_in_block_order().flat_map(|sc| sc.message.transactions.clone()).collect()
+    }
+
+    /// Converts the list of signed constraints into a list of signed transactions, in block
+    /// order. Use this when building a local execution payload.
     #[inline]
     pub fn as_signed_transactions(&self) -> Vec<TransactionSigned> {
-        self.signed_constraints_list
-            .iter()
+        self.constraints_in_block_order()
             .flat_map(|sc| {
                 sc.message.transactions.iter().map(|c| c.clone().into_inner().into_transaction())
             })
@@ -64,12 +76,11 @@ impl BlockTemplate {
     }
 
     /// Converts the list of signed constraints into a list of all blobs in all transactions
-    /// in the constraints. Use this when building a local execution payload.
+    /// in the constraints, in block order. Use this when building a local execution payload.
     #[inline]
     pub fn as_blobs_bundle(&self) -> BlobsBundle {
         let (commitments, proofs, blobs) =
-            self.signed_constraints_list
-                .iter()
+            self.constraints_in_block_order()
                 .flat_map(|sc| sc.message.transactions.iter())
                 .filter_map(|c| c.blob_sidecar())
                 .fold(
@@ -107,6 +118,36 @@ impl BlockTemplate {
         })
     }
 
+    /// Returns the number of top-of-block transactions in the block template.
+    #[inline]
+    pub fn top_transactions_len(&self) -> usize {
+        self.signed_constraints_list
+            .iter()
+            .filter(|sc| sc.message.top)
+            .fold(0, |acc, sc| acc + sc.message.transactions.len())
+    }
+
+    /// Returns the gas committed at the top of the block in the block template.
+    #[inline]
+    pub fn top_committed_gas(&self) -> u64 {
+        self.signed_constraints_list
+            .iter()
+            .filter(|sc| sc.message.top)
+            .flat_map(|sc| sc.message.transactions.iter())
+            .fold(0, |acc, c| acc + c.gas_limit())
+    }
+
+    /// Returns the senders of the transactions below the top of the block in the block
+    /// template.
+    pub fn senders_below_top(&self) -> HashSet<Address> {
+        self.signed_constraints_list
+            .iter()
+            .filter(|sc| !sc.message.top)
+            .flat_map(|sc| sc.message.transactions.iter())
+            .map(|c| *c.sender().expect("recovered sender"))
+            .collect()
+    }
+
     /// Returns the blob count of the block template.
     #[inline]
     pub fn blob_count(&self) -> usize {
@@ -119,6 +160,71 @@ impl BlockTemplate {
         })
     }
 
+    /// Returns the sum of the maximum priority fees of the transactions in the block template,
+    /// in wei. Legacy transactions pay their whole gas price as priority fee at most.
+    #[inline]
+    pub fn max_priority_fees(&self) -> u128 {
+        self.signed_constraints_list
+            .iter()
+            .flat_map(|sc| sc.message.transactions.iter())
+            .map(|c| {
+                let fee = c.max_priority_fee_per_gas().unwrap_or_else(|| c.max_fee_per_gas());
+                fee * c.gas_limit() as u128
+            })
+            .sum()
+    }
+
+    /// Returns the senders of the transactions in the block template.
+    pub fn senders(&self) -> HashSet<Address> {
+        self.signed_constraints_list
+            .iter()
+            .flat_map(|sc| sc.message.transactions.iter())
+            .map(|c| *c.sender().expect("recovered sender"))
+            .collect()
+    }
+
+    /// Returns the lowest nonce of the transactions of the given sender in the block template.
+    pub fn min_nonce(&self, address: &Address) -> Option<u64> {
+        self.signed_constraints_list
+            .iter()
+            .flat_map(|sc| sc.message.transactions.iter())
+            .filter(|c| c.sender().expect("recovered sender") == address)
+            .map(|c| c.nonce())
+            .min()
+    }
+
+    /// Returns the number of signed constraints with transactions of any of the given senders.
+    pub fn signed_constraints_from(&self, senders: &HashSet<Address>) -> usize {
+        self.indexes_from(senders).len()
+    }
+
+    /// Removes the signed constraints with transactions of any of the given senders and updates
+    /// the state diff. Returns the number of removed signed constraints.
+    pub fn remove_signed_constraints_from(&mut self, senders: &HashSet<Address>) -> usize {
+        let indexes = self.indexes_from(senders);
+        for index in indexes.iter().rev() {
+            self.remove_constraints_at_index(*index);
+        }
+
+        indexes.len()
+    }
+
+    /// Returns the indexes of the signed constraints with transactions of any of the given
+    /// senders, in ascending order.
+    fn indexes_from(&self, senders: &HashSet<Address>) -> Vec<usize> {
+        self.signed_constraints_list
+            .iter()
+            .enumerate()
+            .filter(|(_, sc)| {
+                sc.message
+                    .transactions
+                    .iter()
+                    .any(|c| senders.contains(c.sender().expect("recovered sender")))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
     /// Adds a list of constraints to the block template and updates the state diff.
     pub fn add_constraints(&mut self, constraints: SignedConstraints) {
         for constraint in constraints.message.transactions.iter() {
@@ -152,7 +258,8 @@ impl BlockTemplate {
     }
 
     /// Retain removes any transactions that conflict with the given account state.
-    pub fn retain(&mut self, address: Address, state: AccountState) {
+    /// Returns the number of removed signed constraints.
+    pub fn retain(&mut self, address: Address, state: AccountState) -> usize {
         let mut indexes: Vec<usize> = Vec::new();
 
         // The preconfirmations made by such address, and the indexes of the signed constraints
@@ -191,14 +298,17 @@ impl BlockTemplate {
             indexes = constraints_with_address.iter().map(|(i, _)| *i).collect();
         }
 
+        let removed = indexes.len();
         for index in indexes.into_iter().rev() {
             self.remove_constraints_at_index(index);
         }
+
+        removed
     }
 }
 
 /// StateDiff tracks the intermediate changes to the state according to the block template.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct StateDiff {
     /// Map of diffs per address. Each diff is a tuple of the nonce and balance diff
     /// that should be applied to the current state.
@@ -1,18 +1,20 @@
 use alloy::{
-    primitives::Address,
+    primitives::{Address, U256},
     providers::{ProviderBuilder, RootProvider},
     sol,
     transports::http::Http,
 };
 use ethereum_consensus::primitives::BlsPublicKey;
 use eyre::{bail, Context};
+use futures::{stream, StreamExt, TryStreamExt};
 use reqwest::{Client, Url};
 use serde::Serialize;
 
-use tracing::debug;
+use tracing::{debug, warn};
 use BoltManagerContract::{
     BoltManagerContractErrors, BoltManagerContractInstance, ProposerStatus, ValidatorDoesNotExist,
 };
+use BoltParametersContract::BoltParametersContractInstance;
 
 use crate::config::chain::Chain;
 
@@ -21,6 +23,9 @@ use super::utils::{self, CompressedHash};
 /// Maximum number of keys to fetch from the EL node in a single query.
 const MAX_CHUNK_SIZE: usize = 100;
 
+/// Maximum number of queries in flight to the EL node when fetching the statuses of many keys.
+const MAX_CONCURRENT_CHUNKS: usize = 4;
+
 /// A wrapper over a BoltManagerContract that exposes various utility methods.
 #[derive(Debug, Clone)]
 pub struct BoltManager(BoltManagerContractInstance<Http<Client>, RootProvider<Http<Client>>>);
@@ -46,75 +51,307 @@ impl BoltManager {
     /// Verify the provided validator public keys are registered in Bolt and are active
     /// and their authorized operator is the given commitment signer public key.
     ///
+    /// Fails if any of the keys can't be verified. Use [BoltManager::verify_validators] to get
+    /// the verification result of every key instead.
+    ///
     /// NOTE: it also checks the operator associated to the `commitment_signer_pubkey` exists.
     pub async fn verify_validator_pubkeys(
         &self,
         keys: Vec<BlsPublicKey>,
         commitment_signer_pubkey: Address,
-    ) -> eyre::Result<Vec<ProposerStatus>> {
-        let hashes_with_preimages = utils::pubkey_hashes(keys);
-        let mut hashes = hashes_with_preimages.keys().cloned().collect::<Vec<_>>();
+    ) -> eyre::Result<VerificationReport> {
+        let report = self.verify_validators(keys, commitment_signer_pubkey).await?;
+        report.ensure_all_verified(commitment_signer_pubkey)?;
+        Ok(report)
+    }
 
-        let total_keys = hashes.len();
-        let chunk_count = total_keys.div_ceil(MAX_CHUNK_SIZE);
+    /// Verify the provided validator public keys are registered in Bolt and are active
+    /// and their authorized operator is the given commitment signer public key, returning
+    /// the result for every key.
+    ///
+    /// NOTE: it fails if the operator associated to the `commitment_signer_pubkey` doesn't exist.
+    pub async fn verify_validators(
+        &self,
+        keys: Vec<BlsPublicKey>,
+        commitment_signer_pubkey: Address,
+    ) -> eyre::Result<VerificationReport> {
+        let mut hashes_with_preimages = utils::pubkey_hashes(keys);
+        let hashes = hashes_with_preimages.keys().cloned().collect::<Vec<_>>();
+
+        let (statuses, missing) =
+            self.get_proposer_statuses(hashes, commitment_signer_pubkey).await?;
+
+        let mut report = VerificationReport {
+            unregistered: missing
+                .iter()
+                .filter_map(|hash| hashes_with_preimages.remove(hash))
+                .collect(),
+            ..Default::default()
+        };
+
+        for status in statuses {
+            let Some(pubkey) = hashes_with_preimages.remove(&status.pubkeyHash) else {
+                bail!("BoltManager returned an unexpected public key hash: {}", status.pubkeyHash);
+            };
 
-        let mut proposers_statuses = Vec::with_capacity(total_keys);
+            if !status.active {
+                report.inactive.push(pubkey);
+            } else if status.operator != commitment_signer_pubkey {
+                report.operator_mismatch.push((pubkey, status.operator));
+            } else {
+                report.verified.push(pubkey);
+            }
+        }
 
-        let mut i = 0;
-        while !hashes.is_empty() {
-            i += 1;
+        Ok(report)
+    }
 
-            // No more than MAX_CHUNK_SIZE at a time to avoid EL config limits
-            let chunk_size = MAX_CHUNK_SIZE.min(hashes.len());
-            let hashes_chunk = hashes.drain(..chunk_size).collect::<Vec<_>>();
+    /// Fetch the proposer statuses of the given public key hashes, in chunks of at most
+    /// [MAX_CHUNK_SIZE] keys with up to [MAX_CONCURRENT_CHUNKS] queries in flight.
+    ///
+    /// Returns the statuses, along with the hashes of the validators not registered in Bolt.
+    async fn get_proposer_statuses(
+        &self,
+        hashes: Vec<CompressedHash>,
+        commitment_signer_pubkey: Address,
+    ) -> eyre::Result<(Vec<ProposerStatus>, Vec<CompressedHash>)> {
+        let chunk_count = hashes.len().div_ceil(MAX_CHUNK_SIZE);
+
+        let chunks = hashes.chunks(MAX_CHUNK_SIZE).map(<[_]>::to_vec).enumerate();
+        let results = stream::iter(chunks)
+            .map(|(i, chunk)| {
+                debug!("fetching proposer statuses for chunk {} of {}", i + 1, chunk_count);
+                self.get_proposer_statuses_chunk(chunk, commitment_signer_pubkey)
+            })
+            .buffered(MAX_CONCURRENT_CHUNKS)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut statuses = Vec::with_capacity(hashes.len());
+        let mut missing = Vec::new();
+        for (chunk_statuses, chunk_missing) in results {
+            statuses.extend(chunk_statuses);
+            missing.extend(chunk_missing);
+        }
 
-            debug!("fetching proposer statuses for chunk {} of {}", i, chunk_count);
+        Ok((statuses, missing))
+    }
 
-            let returndata = match self.0.getProposerStatuses(hashes_chunk).call().await {
-                Ok(returndata) => returndata,
-                Err(error) => {
-                    let decoded_error = utils::try_parse_contract_error(error)
-                        .wrap_err("Failed to fetch proposer statuses from EL client")?;
+    /// Fetch the proposer statuses of the given public key hashes in a single query.
+    ///
+    /// The query reverts if any of the validators is not registered in Bolt: in that case it
+    /// is removed from the chunk, which is queried again.
+    async fn get_proposer_statuses_chunk(
+        &self,
+        mut hashes: Vec<CompressedHash>,
+        commitment_signer_pubkey: Address,
+    ) -> eyre::Result<(Vec<ProposerStatus>, Vec<CompressedHash>)> {
+        let mut missing = Vec::new();
 
-                    bail!(generate_bolt_manager_error(decoded_error, commitment_signer_pubkey));
-                }
+        while !hashes.is_empty() {
+            let error = match self.0.getProposerStatuses(hashes.clone()).call().await {
+                Ok(returndata) => return Ok((returndata.statuses, missing)),
+                Err(error) => utils::try_parse_contract_error(error)
+                    .wrap_err("Failed to fetch proposer statuses from EL client")?,
             };
 
-            // Check that all validators are active and have the correct operator
-            for status in &returndata.statuses {
-                if !status.active {
-                    if let Some(pubkey) = hashes_with_preimages.get(&status.pubkeyHash) {
-                        bail!(
-                            "validator with public key {} and public key hash {} is not active in Bolt",
-                            pubkey,
-                            status.pubkeyHash
-                        );
-                    } else {
-                        bail!(
-                            "BoltManager returned an unexpected public key hash: {}",
-                            status.pubkeyHash
-                        );
-                    }
-                }
-
-                if status.operator != commitment_signer_pubkey {
-                    bail!(generate_operator_keys_mismatch_error(
-                        status.pubkeyHash,
-                        commitment_signer_pubkey,
-                        status.operator
-                    ));
+            match error {
+                BoltManagerContractErrors::ValidatorDoesNotExist(ValidatorDoesNotExist {
+                    pubkeyHash,
+                }) if hashes.contains(&pubkeyHash) => {
+                    hashes.retain(|hash| *hash != pubkeyHash);
+                    missing.push(pubkeyHash);
                 }
+                error => bail!(generate_bolt_manager_error(error, commitment_signer_pubkey)),
             }
+        }
+
+        Ok((Vec::new(), missing))
+    }
+
+    /// Returns the total collateral of the given operator, as registered in the BoltManager
+    /// contract, through the proposer statuses of the given validator public keys.
+    ///
+    /// The collateral is tracked per operator, so the statuses of all its keys report the same
+    /// amounts: every chunk of keys is queried, and the largest total among the keys
+    /// authorized to the operator is returned. Keys not registered in Bolt are skipped.
+    pub async fn get_operator_collateral(
+        &self,
+        operator: Address,
+        keys: &[BlsPublicKey],
+    ) -> eyre::Result<U256> {
+        let hashes = utils::pubkey_hashes(keys.to_vec()).into_keys().collect();
+        let (statuses, _) = self.get_proposer_statuses(hashes, operator).await?;
 
-            proposers_statuses.extend(returndata.statuses);
+        Ok(operator_collateral(&statuses, operator))
+    }
+
+    /// Returns the registration status and the collateral of the given operator, along with the
+    /// minimum collateral required by the protocol.
+    ///
+    /// The collateral is fetched through the proposer statuses of the given validator public
+    /// keys, which should be authorized to the operator: see
+    /// [BoltManager::get_operator_collateral].
+    pub async fn get_operator_status(
+        &self,
+        operator: Address,
+        keys: &[BlsPublicKey],
+    ) -> eyre::Result<OperatorStatus> {
+        let registered = self
+            .0
+            .isOperator(operator)
+            .call()
+            .await
+            .wrap_err("Failed to fetch operator registration from EL client")?
+            .isOperator;
 
-            continue;
+        let minimum_collateral = self.get_minimum_collateral().await?;
+
+        // The operator must be registered to query its status, and its collateral isn't counted
+        // by the BoltManager otherwise.
+        if !registered {
+            return Ok(OperatorStatus { minimum_collateral, ..Default::default() });
         }
 
-        Ok(proposers_statuses)
+        let enabled = self
+            .0
+            .isOperatorEnabled(operator)
+            .call()
+            .await
+            .wrap_err("Failed to fetch operator status from EL client")?
+            .enabled;
+
+        let collateral = if keys.is_empty() {
+            U256::ZERO
+        } else {
+            self.get_operator_collateral(operator, keys).await?
+        };
+
+        Ok(OperatorStatus { registered, enabled, collateral, minimum_collateral })
+    }
+
+    /// Returns the minimum collateral an operator must have to be active in Bolt, as set in the
+    /// BoltParameters contract.
+    async fn get_minimum_collateral(&self) -> eyre::Result<U256> {
+        let parameters = self
+            .0
+            .parameters()
+            .call()
+            .await
+            .wrap_err("Failed to fetch the BoltParameters address from EL client")?
+            .parameters;
+
+        let parameters = BoltParametersContractInstance::new(parameters, self.0.provider().clone());
+        let minimum = parameters
+            .MINIMUM_OPERATOR_STAKE()
+            .call()
+            .await
+            .wrap_err("Failed to fetch the minimum operator stake from EL client")?
+            .minimum;
+
+        Ok(minimum)
+    }
+}
+
+/// The status of an operator in the BoltManager contract.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct OperatorStatus {
+    /// Whether the operator is registered in Bolt.
+    pub registered: bool,
+    /// Whether the operator is registered and hasn't paused its activity.
+    pub enabled: bool,
+    /// The total collateral of the operator, in wei.
+    pub collateral: U256,
+    /// The minimum collateral required by the protocol for the operator to be active, in wei.
+    pub minimum_collateral: U256,
+}
+
+impl OperatorStatus {
+    /// Returns true if the operator is registered and enabled.
+    pub fn is_active(&self) -> bool {
+        self.registered && self.enabled
+    }
+
+    /// Returns true if the collateral of the operator covers the protocol minimum.
+    pub fn is_collateralized(&self) -> bool {
+        self.collateral >= self.minimum_collateral
+    }
+}
+
+/// The result of the verification of a set of validator public keys in the BoltManager contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// The validators registered in Bolt, active, and authorized to the operator.
+    pub verified: Vec<BlsPublicKey>,
+    /// The validators not registered in Bolt.
+    pub unregistered: Vec<BlsPublicKey>,
+    /// The validators registered in Bolt, but not active.
+    pub inactive: Vec<BlsPublicKey>,
+    /// The validators authorized to another operator, along with its address.
+    pub operator_mismatch: Vec<(BlsPublicKey, Address)>,
+}
+
+impl VerificationReport {
+    /// Returns the number of validators that couldn't be verified.
+    pub fn unverified_count(&self) -> usize {
+        self.unregistered.len() + self.inactive.len() + self.operator_mismatch.len()
+    }
+
+    /// Returns an error describing the first validator that couldn't be verified, if any.
+    pub fn ensure_all_verified(&self, commitment_signer_pubkey: Address) -> eyre::Result<()> {
+        if let Some(pubkey) = self.unregistered.first() {
+            let error = BoltManagerContractErrors::ValidatorDoesNotExist(ValidatorDoesNotExist {
+                pubkeyHash: utils::pubkey_hash(pubkey),
+            });
+            bail!(generate_bolt_manager_error(error, commitment_signer_pubkey));
+        }
+
+        if let Some(pubkey) = self.inactive.first() {
+            bail!(
+                "validator with public key {} and public key hash {} is not active in Bolt",
+                pubkey,
+                utils::pubkey_hash(pubkey)
+            );
+        }
+
+        if let Some((pubkey, operator)) = self.operator_mismatch.first() {
+            bail!(generate_operator_keys_mismatch_error(
+                utils::pubkey_hash(pubkey),
+                commitment_signer_pubkey,
+                *operator
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Log every validator that couldn't be verified.
+    pub fn log_unverified(&self) {
+        for pubkey in &self.unregistered {
+            warn!(%pubkey, "Validator is not registered in Bolt");
+        }
+        for pubkey in &self.inactive {
+            warn!(%pubkey, "Validator is not active in Bolt");
+        }
+        for (pubkey, operator) in &self.operator_mismatch {
+            warn!(%pubkey, %operator, "Validator is authorized to another operator");
+        }
     }
 }
 
+/// Returns the largest total collateral among the given proposer statuses of the keys
+/// authorized to the given operator, or zero if there's none.
+fn operator_collateral(statuses: &[ProposerStatus], operator: Address) -> U256 {
+    statuses
+        .iter()
+        .filter(|status| status.operator == operator)
+        .map(|status| {
+            status.amounts.iter().fold(U256::ZERO, |acc, amount| acc.saturating_add(*amount))
+        })
+        .max()
+        .unwrap_or_default()
+}
+
 fn generate_bolt_manager_error(
     error: BoltManagerContractErrors,
     commitment_signer_pubkey: Address,
@@ -171,17 +408,30 @@ sol! {
 
         function isOperator(address operator) external view returns (bool isOperator);
 
+        function isOperatorEnabled(address operator) public view returns (bool enabled);
+
+        function parameters() external view returns (address parameters);
+
         error KeyNotFound();
         error InvalidQuery();
         #[derive(Debug)]
         error ValidatorDoesNotExist(bytes20 pubkeyHash);
     }
+
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface BoltParametersContract {
+        function MINIMUM_OPERATOR_STAKE() external view returns (uint256 minimum);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use ::hex::FromHex;
-    use alloy::{hex, primitives::Address};
+    use alloy::{
+        hex,
+        primitives::{Address, U256},
+    };
     use ethereum_consensus::primitives::BlsPublicKey;
     use reqwest::Url;
 
@@ -190,7 +440,29 @@ mod tests {
         config::chain::Chain,
     };
 
-    use super::BoltManager;
+    use super::{operator_collateral, BoltManager, ProposerStatus, MAX_CHUNK_SIZE};
+
+    #[test]
+    fn test_operator_collateral_across_chunks() {
+        let operator = Address::repeat_byte(0x11);
+        let status = |operator: Address, amounts: &[u64]| ProposerStatus {
+            pubkeyHash: Default::default(),
+            active: true,
+            operator,
+            operatorRPC: String::new(),
+            collaterals: vec![Address::ZERO; amounts.len()],
+            amounts: amounts.iter().map(|amount| U256::from(*amount)).collect(),
+        };
+
+        // The keys of the first chunk are authorized to another operator, whose collateral is
+        // ignored: the one of the operator is only reported by a later chunk
+        let mut statuses = vec![status(Address::repeat_byte(0x22), &[1_000]); MAX_CHUNK_SIZE];
+        statuses.push(status(operator, &[10, 5, 20]));
+
+        assert_eq!(operator_collateral(&statuses, operator), U256::from(35));
+        assert_eq!(operator_collateral(&statuses[..MAX_CHUNK_SIZE], operator), U256::ZERO);
+        assert_eq!(operator_collateral(&[], operator), U256::ZERO);
+    }
 
     #[tokio::test]
     async fn test_verify_validator_pubkeys() {
@@ -230,9 +502,9 @@ mod tests {
 
         let commitment_signer_pubkey = operator;
         let res = manager
-            .verify_validator_pubkeys(keys, commitment_signer_pubkey)
+            .verify_validator_pubkeys(keys.clone(), commitment_signer_pubkey)
             .await
             .expect("active validator and correct operator");
-        assert!(res[0].active);
+        assert_eq!(res.verified, keys);
     }
 }
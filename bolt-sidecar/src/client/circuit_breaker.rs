@@ -0,0 +1,173 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tracing::{info, warn};
+
+use crate::telemetry::ApiMetrics;
+
+/// The state of a [CircuitBreaker].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through.
+    Closed = 0,
+    /// The cooldown has elapsed: requests go through, and the first result closes or reopens
+    /// the circuit.
+    HalfOpen = 1,
+    /// Requests are skipped until the cooldown elapses.
+    Open = 2,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => write!(f, "closed"),
+            Self::HalfOpen => write!(f, "half_open"),
+            Self::Open => write!(f, "open"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+}
+
+/// A circuit breaker for the requests to a single relay.
+///
+/// It opens after `threshold` consecutive failures, and half-opens once `cooldown` has
+/// elapsed since. State changes are logged and exported as metrics.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Create a new closed circuit breaker for the relay with the given name.
+    pub fn new(name: impl Into<String>, threshold: u32, cooldown: Duration) -> Self {
+        let name = name.into();
+        ApiMetrics::set_relay_circuit_state(&name, CircuitState::Closed);
+
+        Self {
+            name,
+            threshold: threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Returns the current state of the circuit breaker.
+    pub fn state(&self) -> CircuitState {
+        self.state.lock().state
+    }
+
+    /// Returns true if a request can be sent to the relay, half-opening the circuit if
+    /// it is open and the cooldown has elapsed.
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock();
+        if state.state != CircuitState::Open {
+            return true;
+        }
+
+        if state.opened_at.elapsed() < self.cooldown {
+            return false;
+        }
+
+        self.transition(&mut state, CircuitState::HalfOpen);
+        true
+    }
+
+    /// Record a successful request, closing the circuit.
+    pub fn record_success(&self) {
+        let mut state = self.state.lock();
+        state.consecutive_failures = 0;
+        self.transition(&mut state, CircuitState::Closed);
+    }
+
+    /// Record a failed request, opening the circuit if it was half-open or if the threshold
+    /// of consecutive failures is reached.
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+
+        let should_open = match state.state {
+            CircuitState::Closed => state.consecutive_failures >= self.threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+
+        if should_open {
+            state.opened_at = Instant::now();
+            self.transition(&mut state, CircuitState::Open);
+        }
+    }
+
+    fn transition(&self, state: &mut BreakerState, new_state: CircuitState) {
+        if state.state == new_state {
+            return;
+        }
+
+        if new_state == CircuitState::Open {
+            warn!(
+                relay = %self.name,
+                failures = state.consecutive_failures,
+                cooldown = ?self.cooldown,
+                "Relay circuit breaker opened, skipping requests"
+            );
+        } else {
+            let from = state.state;
+            info!(relay = %self.name, %from, to = %new_state, "Relay circuit breaker state changed");
+        }
+
+        state.state = new_state;
+        ApiMetrics::set_relay_circuit_state(&self.name, new_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker_transitions() {
+        let breaker = CircuitBreaker::new("relay", 3, Duration::from_millis(50));
+
+        // Successes reset the consecutive failures
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+
+        // A failure while half-open reopens the circuit right away
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+
+        // And a success closes it
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
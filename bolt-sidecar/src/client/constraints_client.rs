@@ -1,81 +1,161 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use alloy::hex;
-use axum::http::StatusCode;
+use axum::http::{header::CONTENT_TYPE, StatusCode};
 use beacon_api_client::VersionedValue;
 use ethereum_consensus::{
     builder::SignedValidatorRegistration, crypto::PublicKey as BlsPublicKey,
     deneb::mainnet::SignedBlindedBeaconBlock, Fork,
 };
 use reqwest::Url;
-use tracing::error;
+use tracing::{error, warn};
 
+use super::CircuitBreaker;
 use crate::{
     api::{
         builder::GetHeaderParams,
         spec::{
             BuilderApi, BuilderApiError, ConstraintsApi, ErrorResponse, DELEGATE_PATH,
-            GET_PAYLOAD_PATH, REGISTER_VALIDATORS_PATH, REVOKE_PATH, STATUS_PATH,
+            GET_PAYLOAD_PATH, OCTET_STREAM, REGISTER_VALIDATORS_PATH, REVOKE_PATH, STATUS_PATH,
             SUBMIT_CONSTRAINTS_PATH,
         },
     },
+    config::RelayOpts,
     primitives::{
-        BatchedSignedConstraints, GetPayloadResponse, SignedBuilderBid, SignedDelegation,
-        SignedRevocation,
+        encode_constraints_ssz, BatchedSignedConstraints, ConstraintsDigestVersion,
+        GetPayloadResponse, SignedBuilderBid, SignedDelegation, SignedRevocation,
     },
 };
 
+/// How long idle connections to the relay are kept in the pool.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Interval of the TCP keep-alive probes on the connections to the relay.
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+
 /// A client for interacting with the Constraints client API.
 #[derive(Debug, Clone)]
 pub struct ConstraintsClient {
     url: Url,
     client: reqwest::Client,
     delegations: Vec<SignedDelegation>,
+    /// Whether the relay supports the delegation and revocation endpoints. It is disabled
+    /// the first time the relay answers with a 404.
+    delegations_supported: Arc<AtomicBool>,
+    /// Whether constraints are submitted as SSZ. It is disabled the first time the relay
+    /// answers with a 415.
+    ssz_constraints: Arc<AtomicBool>,
+    /// The version of the constraints digest verified by the relay.
+    constraints_digest_version: ConstraintsDigestVersion,
+    /// The request timeouts and circuit breaker options.
+    opts: RelayOpts,
+    /// The circuit breaker of the relay, shared by all the clones of the client.
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl ConstraintsClient {
     /// Creates a new constraint client with the given URL.
+    ///
+    /// The connections to the relay are pooled and kept alive, and shared by all the clones
+    /// of the client.
     pub fn new<U: Into<Url>>(url: U) -> Self {
+        let url = url.into();
+        let opts = RelayOpts::default();
+        let breaker =
+            CircuitBreaker::new(url.as_str(), opts.breaker_threshold, opts.breaker_cooldown());
+
+        let client = reqwest::ClientBuilder::new()
+            .user_agent("bolt-sidecar")
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .build()
+            .unwrap();
+
         Self {
-            url: url.into(),
-            client: reqwest::ClientBuilder::new().user_agent("bolt-sidecar").build().unwrap(),
+            url,
+            client,
             delegations: Vec::new(),
+            delegations_supported: Arc::new(AtomicBool::new(true)),
+            ssz_constraints: Arc::new(AtomicBool::new(false)),
+            constraints_digest_version: ConstraintsDigestVersion::default(),
+            opts,
+            breaker: Arc::new(breaker),
         }
     }
 
+    /// Set the version of the constraints digest verified by the relay.
+    pub fn with_constraints_digest_version(mut self, version: ConstraintsDigestVersion) -> Self {
+        self.constraints_digest_version = version;
+        self
+    }
+
+    /// Returns the version of the constraints digest to sign for the relay.
+    pub fn constraints_digest_version(&self) -> ConstraintsDigestVersion {
+        self.constraints_digest_version
+    }
+
+    /// Set the request timeouts and circuit breaker options of the client.
+    pub fn with_relay_opts(mut self, opts: RelayOpts) -> Self {
+        let breaker =
+            CircuitBreaker::new(self.url.as_str(), opts.breaker_threshold, opts.breaker_cooldown());
+        self.breaker = Arc::new(breaker);
+        self.opts = opts;
+        self
+    }
+
+    /// Returns the circuit breaker of the relay.
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// Submit constraints as SSZ instead of JSON if enabled. The client falls back to JSON
+    /// if the relay doesn't support it.
+    pub fn with_ssz_constraints(self, enabled: bool) -> Self {
+        self.ssz_constraints.store(enabled, Ordering::Relaxed);
+        self
+    }
+
+    /// Returns true if the constraints are submitted as SSZ.
+    pub fn ssz_constraints(&self) -> bool {
+        self.ssz_constraints.load(Ordering::Relaxed)
+    }
+
     /// Adds a list of delegations to the client.
     pub fn add_delegations(&mut self, delegations: Vec<SignedDelegation>) {
         self.delegations.extend(delegations);
     }
 
-    /// Return a public key that can be used to sign constraints with for the given
-    /// validator public key.
+    /// Return the public keys that can be used to sign constraints with for the given
+    /// validator public key, in order of preference.
     ///
     /// Rationale:
-    /// - If there are no delegatee keys, try to use the validator key directly if available.
-    /// - If there are delegatee keys, try to use the first one that is available in the list.
-    pub fn find_signing_key(
+    /// - The available delegatee keys come first, sorted so that the selection is stable.
+    /// - The validator key comes last if available, as a fallback if the delegatees fail.
+    pub fn find_signing_keys(
         &self,
-        validator_pubkey: BlsPublicKey,
-        available_pubkeys: HashSet<BlsPublicKey>,
-    ) -> Option<BlsPublicKey> {
-        let delegatees = self.find_delegatees(&validator_pubkey);
-
-        if delegatees.is_empty() {
-            if available_pubkeys.contains(&validator_pubkey) {
-                return Some(validator_pubkey);
-            } else {
-                return None;
-            }
-        } else {
-            for delegatee in delegatees {
-                if available_pubkeys.contains(&delegatee) {
-                    return Some(delegatee);
-                }
-            }
+        validator_pubkey: &BlsPublicKey,
+        available_pubkeys: &HashSet<BlsPublicKey>,
+    ) -> Vec<BlsPublicKey> {
+        let mut signing_keys = self
+            .find_delegatees(validator_pubkey)
+            .into_iter()
+            .filter(|delegatee| available_pubkeys.contains(delegatee))
+            .collect::<Vec<_>>();
+        signing_keys.sort();
+
+        if available_pubkeys.contains(validator_pubkey) {
+            signing_keys.push(validator_pubkey.clone());
         }
 
-        None
+        signing_keys
     }
 
     /// Finds all delegations for the given validator public key.
@@ -87,6 +167,80 @@ impl ConstraintsClient {
             .collect::<HashSet<_>>()
     }
 
+    /// Submit the given delegations to the relay.
+    ///
+    /// If the relay doesn't support the delegation endpoints, this is logged once and no
+    /// delegations nor revocations are submitted to it anymore.
+    pub async fn submit_delegations(
+        &self,
+        delegations: &[SignedDelegation],
+    ) -> Result<(), BuilderApiError> {
+        if delegations.is_empty() || !self.delegations_supported() {
+            return Ok(());
+        }
+
+        self.disable_if_unsupported(self.delegate(delegations).await)
+    }
+
+    /// Submit the given revocations to the relay. See [Self::submit_delegations].
+    pub async fn submit_revocations(
+        &self,
+        revocations: &[SignedRevocation],
+    ) -> Result<(), BuilderApiError> {
+        if revocations.is_empty() || !self.delegations_supported() {
+            return Ok(());
+        }
+
+        self.disable_if_unsupported(self.revoke(revocations).await)
+    }
+
+    /// Returns true unless the relay answered that it doesn't support delegations.
+    pub fn delegations_supported(&self) -> bool {
+        self.delegations_supported.load(Ordering::Relaxed)
+    }
+
+    /// Disable the delegation submissions if the relay doesn't support their endpoints.
+    fn disable_if_unsupported(
+        &self,
+        result: Result<(), BuilderApiError>,
+    ) -> Result<(), BuilderApiError> {
+        match result {
+            Err(BuilderApiError::UnsupportedEndpoint(path)) => {
+                if self.delegations_supported.swap(false, Ordering::Relaxed) {
+                    warn!(
+                        relay = %self.url,
+                        path,
+                        "Relay doesn't support delegations, they won't be submitted to it"
+                    );
+                }
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Send a request to the relay within the given time budget, unless its circuit breaker
+    /// is open. Timeouts, transport and server errors count as failures of the relay.
+    async fn guarded<T, F>(&self, timeout: Duration, request: F) -> Result<T, BuilderApiError>
+    where
+        F: Future<Output = Result<T, BuilderApiError>>,
+    {
+        if !self.breaker.allow() {
+            return Err(BuilderApiError::CircuitOpen(self.url.to_string()));
+        }
+
+        let result = tokio::time::timeout(timeout, request)
+            .await
+            .unwrap_or_else(|elapsed| Err(BuilderApiError::Timeout(elapsed)));
+
+        match &result {
+            Err(err) if err.is_relay_failure() => self.breaker.record_failure(),
+            _ => self.breaker.record_success(),
+        }
+
+        result
+    }
+
     /// Returns the URL of the target client.
     pub fn target(&self) -> &str {
         self.url.as_str()
@@ -148,7 +302,7 @@ impl BuilderApi for ConstraintsClient {
                 .cloned()
                 .collect::<Vec<_>>();
 
-            if let Err(err) = self.delegate(&filtered_delegations).await {
+            if let Err(err) = self.submit_delegations(&filtered_delegations).await {
                 error!(?err, "Failed to propagate delegations during validator registration");
             }
         }
@@ -161,27 +315,30 @@ impl BuilderApi for ConstraintsClient {
         &self,
         params: GetHeaderParams,
     ) -> Result<SignedBuilderBid, BuilderApiError> {
-        let parent_hash = hex::encode_prefixed(params.parent_hash.as_ref());
-        let public_key = hex::encode_prefixed(params.public_key.as_ref());
-
-        let response = self
-            .client
-            .get(self.endpoint(&format!(
-                "/eth/v1/builder/header/{}/{}/{}",
-                params.slot, parent_hash, public_key
-            )))
-            .header("content-type", "application/json")
-            .send()
-            .await?;
-
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedGettingHeader(error));
-        }
+        self.guarded(self.opts.get_header_timeout(), async {
+            let parent_hash = hex::encode_prefixed(params.parent_hash.as_ref());
+            let public_key = hex::encode_prefixed(params.public_key.as_ref());
+
+            let response = self
+                .client
+                .get(self.endpoint(&format!(
+                    "/eth/v1/builder/header/{}/{}/{}",
+                    params.slot, parent_hash, public_key
+                )))
+                .header("content-type", "application/json")
+                .send()
+                .await?;
+
+            if response.status() != StatusCode::OK {
+                let error = response.json::<ErrorResponse>().await?;
+                return Err(BuilderApiError::FailedGettingHeader(error));
+            }
 
-        let header = response.json::<SignedBuilderBid>().await?;
+            let header = response.json::<SignedBuilderBid>().await?;
 
-        Ok(header)
+            Ok(header)
+        })
+        .await
     }
 
     /// Implements: <https://ethereum.github.io/builder-specs/#/Builder/submitBlindedBlock>
@@ -189,22 +346,25 @@ impl BuilderApi for ConstraintsClient {
         &self,
         signed_block: SignedBlindedBeaconBlock,
     ) -> Result<GetPayloadResponse, BuilderApiError> {
-        let response = self
-            .client
-            .post(self.endpoint(GET_PAYLOAD_PATH))
-            .header("content-type", "application/json")
-            .body(serde_json::to_vec(&signed_block)?)
-            .send()
-            .await?;
-
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedGettingPayload(error));
-        }
+        self.guarded(self.opts.get_payload_timeout(), async {
+            let response = self
+                .client
+                .post(self.endpoint(GET_PAYLOAD_PATH))
+                .header("content-type", "application/json")
+                .body(serde_json::to_vec(&signed_block)?)
+                .send()
+                .await?;
+
+            if response.status() != StatusCode::OK {
+                let error = response.json::<ErrorResponse>().await?;
+                return Err(BuilderApiError::FailedGettingPayload(error));
+            }
 
-        let payload = response.json().await?;
+            let payload = response.json().await?;
 
-        Ok(payload)
+            Ok(payload)
+        })
+        .await
     }
 }
 
@@ -214,51 +374,85 @@ impl ConstraintsApi for ConstraintsClient {
         &self,
         constraints: &BatchedSignedConstraints,
     ) -> Result<(), BuilderApiError> {
-        let response = self
-            .client
-            .post(self.endpoint(SUBMIT_CONSTRAINTS_PATH))
-            .header("content-type", "application/json")
-            .body(serde_json::to_vec(&constraints)?)
-            .send()
-            .await?;
+        self.guarded(self.opts.submit_timeout(), async {
+            let mut response = None;
+
+            if self.ssz_constraints() {
+                let body = encode_constraints_ssz(constraints)
+                    .map_err(|e| BuilderApiError::Generic(e.to_string()))?;
+                let ssz_response = self
+                    .client
+                    .post(self.endpoint(SUBMIT_CONSTRAINTS_PATH))
+                    .header(CONTENT_TYPE, OCTET_STREAM)
+                    .body(body)
+                    .send()
+                    .await?;
+
+                if ssz_response.status() == StatusCode::UNSUPPORTED_MEDIA_TYPE {
+                    if self.ssz_constraints.swap(false, Ordering::Relaxed) {
+                        warn!(relay = %self.url, "Relay doesn't accept SSZ constraints, using JSON");
+                    }
+                } else {
+                    response = Some(ssz_response);
+                }
+            }
 
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedSubmittingConstraints(error));
-        }
+            let response = match response {
+                Some(response) => response,
+                None => {
+                    self.client
+                        .post(self.endpoint(SUBMIT_CONSTRAINTS_PATH))
+                        .header(CONTENT_TYPE, "application/json")
+                        .body(serde_json::to_vec(&constraints)?)
+                        .send()
+                        .await?
+                }
+            };
 
-        Ok(())
+            // Keep the response of the relay verbatim, to tell why it rejected the constraints
+            let status = response.status();
+            if status != StatusCode::OK {
+                let body = response.text().await?;
+                return Err(BuilderApiError::RelayRejected { status: status.as_u16(), body });
+            }
+
+            Ok(())
+        })
+        .await
     }
 
     async fn get_header_with_proofs(
         &self,
         params: GetHeaderParams,
     ) -> Result<VersionedValue<SignedBuilderBid>, BuilderApiError> {
-        let parent_hash = hex::encode_prefixed(params.parent_hash.as_ref());
-        let public_key = hex::encode_prefixed(params.public_key.as_ref());
-
-        let response = self
-            .client
-            .get(self.endpoint(&format!(
-                "/eth/v1/builder/header_with_proofs/{}/{}/{}",
-                params.slot, parent_hash, public_key,
-            )))
-            .header("content-type", "application/json")
-            .send()
-            .await?;
-
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedGettingHeader(error));
-        }
+        self.guarded(self.opts.get_header_timeout(), async {
+            let parent_hash = hex::encode_prefixed(params.parent_hash.as_ref());
+            let public_key = hex::encode_prefixed(params.public_key.as_ref());
+
+            let response = self
+                .client
+                .get(self.endpoint(&format!(
+                    "/eth/v1/builder/header_with_proofs/{}/{}/{}",
+                    params.slot, parent_hash, public_key,
+                )))
+                .header("content-type", "application/json")
+                .send()
+                .await?;
+
+            if response.status() != StatusCode::OK {
+                let error = response.json::<ErrorResponse>().await?;
+                return Err(BuilderApiError::FailedGettingHeader(error));
+            }
 
-        let header = response.json::<VersionedValue<SignedBuilderBid>>().await?;
+            let header = response.json::<VersionedValue<SignedBuilderBid>>().await?;
 
-        if !matches!(header.version, Fork::Deneb) {
-            return Err(BuilderApiError::InvalidFork(header.version.to_string()));
-        };
+            if !matches!(header.version, Fork::Deneb) {
+                return Err(BuilderApiError::InvalidFork(header.version.to_string()));
+            };
 
-        Ok(header)
+            Ok(header)
+        })
+        .await
     }
 
     async fn delegate(&self, signed_data: &[SignedDelegation]) -> Result<(), BuilderApiError> {
@@ -270,6 +464,10 @@ impl ConstraintsApi for ConstraintsClient {
             .send()
             .await?;
 
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(BuilderApiError::UnsupportedEndpoint(DELEGATE_PATH));
+        }
+
         if response.status() != StatusCode::OK {
             let error = response.json::<ErrorResponse>().await?;
             return Err(BuilderApiError::FailedDelegating(error));
@@ -287,6 +485,10 @@ impl ConstraintsApi for ConstraintsClient {
             .send()
             .await?;
 
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(BuilderApiError::UnsupportedEndpoint(REVOKE_PATH));
+        }
+
         if response.status() != StatusCode::OK {
             let error = response.json::<ErrorResponse>().await?;
             return Err(BuilderApiError::FailedRevoking(error));
@@ -298,9 +500,73 @@ impl ConstraintsApi for ConstraintsClient {
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{extract::State, routing::post, Json, Router};
     use reqwest::Url;
+    use serde_json::Value;
+    use tokio::net::TcpListener;
 
     use super::ConstraintsClient;
+    use crate::{
+        api::spec::{DELEGATE_PATH, REVOKE_PATH},
+        primitives::{
+            read_signed_messages_from_file, RevocationMessage, SignedMessages, SignedRevocation,
+        },
+    };
+
+    type Received = Arc<Mutex<Vec<(&'static str, Value)>>>;
+
+    /// Serve the given router on a random port, returning its URL.
+    async fn serve(router: Router) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap()).parse().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        url
+    }
+
+    /// The test delegations file, with a revocation for the same validator appended.
+    fn test_messages() -> SignedMessages {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/delegations.json");
+
+        let mut messages = read_signed_messages_from_file(&path).unwrap();
+        let delegation = messages.delegations[0].clone();
+        messages.revocations.push(SignedRevocation {
+            message: RevocationMessage::new(
+                delegation.message.validator_pubkey,
+                delegation.message.delegatee_pubkey,
+            ),
+            signature: delegation.signature,
+        });
+        messages
+    }
+
+    /// Assert the body is a list of `{ message: { action, validator_pubkey, delegatee_pubkey },
+    /// signature }` objects as specified by the constraints API.
+    fn assert_spec_shape(body: &Value, action: u64) {
+        let sorted_keys = |value: &Value| {
+            let mut keys = value.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+
+        let entries = body.as_array().expect("array of signed messages");
+        assert_eq!(entries.len(), 1);
+
+        let entry = &entries[0];
+        assert_eq!(sorted_keys(entry), ["message", "signature"]);
+        assert!(entry["signature"].as_str().unwrap().starts_with("0x"));
+
+        let message = &entry["message"];
+        assert_eq!(sorted_keys(message), ["action", "delegatee_pubkey", "validator_pubkey"]);
+        assert_eq!(message["action"].as_u64(), Some(action));
+        assert!(message["validator_pubkey"].as_str().unwrap().starts_with("0x"));
+        assert!(message["delegatee_pubkey"].as_str().unwrap().starts_with("0x"));
+    }
 
     #[test]
     fn test_join_endpoints() {
@@ -315,4 +581,59 @@ mod tests {
             Url::parse("http://localhost:8080/eth/v1/builder/validators").unwrap()
         );
     }
+
+    #[tokio::test]
+    async fn test_submit_delegations_and_revocations() {
+        async fn record(path: &'static str, received: Received, body: Value) {
+            received.lock().unwrap().push((path, body));
+        }
+
+        let received = Received::default();
+        let router = Router::new()
+            .route(
+                DELEGATE_PATH,
+                post(|State(r): State<Received>, Json(b): Json<Value>| record(DELEGATE_PATH, r, b)),
+            )
+            .route(
+                REVOKE_PATH,
+                post(|State(r): State<Received>, Json(b): Json<Value>| record(REVOKE_PATH, r, b)),
+            )
+            .with_state(received.clone());
+
+        let client = ConstraintsClient::new(serve(router).await);
+        let messages = test_messages();
+        client.submit_delegations(&messages.delegations).await.unwrap();
+        client.submit_revocations(&messages.revocations).await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].0, DELEGATE_PATH);
+        assert_spec_shape(&received[0].1, 0);
+        assert_eq!(received[1].0, REVOKE_PATH);
+        assert_spec_shape(&received[1].1, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_delegations_endpoint() {
+        let hits = Arc::new(Mutex::new(0));
+        let counter = hits.clone();
+        let router = Router::new().fallback(move || async move {
+            *counter.lock().unwrap() += 1;
+            axum::http::StatusCode::NOT_FOUND
+        });
+
+        let client = ConstraintsClient::new(serve(router).await);
+        let messages = test_messages();
+        assert!(client.delegations_supported());
+
+        // The 404 is not an error, and disables the submissions to this relay
+        client.submit_delegations(&messages.delegations).await.unwrap();
+        assert!(!client.delegations_supported());
+        client.submit_delegations(&messages.delegations).await.unwrap();
+        client.submit_revocations(&messages.revocations).await.unwrap();
+
+        // Clones share the state of the relay
+        assert!(!client.clone().delegations_supported());
+        assert_eq!(*hits.lock().unwrap(), 1);
+    }
 }
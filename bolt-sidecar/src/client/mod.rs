@@ -4,10 +4,18 @@
 pub mod constraints_client;
 pub use constraints_client::ConstraintsClient;
 
+/// Reports of the submissions of constraints to the relay, with the responses of every attempt.
+pub mod submissions;
+pub use submissions::{SubmissionAttempt, SubmissionReport, SubmissionReports};
+
+/// A circuit breaker skipping requests to a failing relay until it recovers.
+pub mod circuit_breaker;
+pub use circuit_breaker::CircuitBreaker;
+
 /// Module defining an RpcClient wrapper around the [`alloy::rpc::client::RpcClient`].
 /// It provides a simple interface to interact with the Execution layer JSON-RPC API.
 pub mod rpc;
-pub use rpc::RpcClient;
+pub use rpc::{RpcClient, SimulatedCall};
 
 // Re-export the beacon_api_client
 pub use beacon_api_client::mainnet::Client as BeaconClient;
@@ -1,20 +1,61 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
 
 use alloy::{
     eips::BlockNumberOrTag,
+    network::TransactionResponse,
     primitives::{Address, Bytes, TxHash, B256, U256, U64},
     rpc::{
         client::{self as alloyClient, ClientBuilder},
-        types::{Block, FeeHistory, TransactionReceipt},
+        json_rpc::ErrorPayload,
+        types::{Block, FeeHistory, Transaction, TransactionReceipt, TransactionRequest},
     },
     transports::{http::Http, TransportErrorKind, TransportResult},
 };
 
 use futures::{stream::FuturesUnordered, StreamExt};
 use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::json;
 
 use crate::primitives::AccountState;
 
+/// The outcome of a call simulated with [RpcClient::simulate].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedCall {
+    /// `1` if the call succeeded, `0` if it reverted or ran out of gas.
+    pub status: U64,
+    /// The output of the call, or its revert data.
+    pub return_data: Bytes,
+    /// The gas used by the call.
+    pub gas_used: U64,
+    /// The error of the call if it failed, with the revert data if any.
+    #[serde(default)]
+    pub error: Option<ErrorPayload>,
+}
+
+impl SimulatedCall {
+    /// Returns whether the call succeeded.
+    pub fn is_success(&self) -> bool {
+        self.status == U64::from(1)
+    }
+
+    /// Returns the reason of the failure of the call, with its revert data if any.
+    pub fn failure_reason(&self) -> String {
+        match &self.error {
+            Some(ErrorPayload { message, data: Some(data), .. }) => {
+                format!("{message} ({})", data.get())
+            }
+            Some(ErrorPayload { message, .. }) => message.to_string(),
+            None if self.return_data.is_empty() => "execution reverted".to_string(),
+            None => format!("execution reverted ({})", self.return_data),
+        }
+    }
+}
+
 /// An HTTP-based JSON-RPC client that supports batching.
 /// Implements all methods that are relevant to Bolt state.
 #[derive(Clone, Debug)]
@@ -106,6 +147,75 @@ impl RpcClient {
         Ok(AccountState { balance, transaction_count: tx_count.to(), has_code: !code.is_empty() })
     }
 
+    /// Get the account states of multiple addresses in a single JSON-RPC batch request.
+    ///
+    /// If `with_code` is false, `eth_getCode` isn't called and `has_code` is always `false`.
+    pub async fn get_account_states(
+        &self,
+        addresses: &[Address],
+        block_number: Option<u64>,
+        with_code: bool,
+    ) -> TransportResult<HashMap<Address, AccountState>> {
+        let tag = block_number.map_or(BlockNumberOrTag::Latest, BlockNumberOrTag::Number);
+        self.get_account_states_at(addresses, tag, with_code).await
+    }
+
+    /// Get the account states of multiple addresses at the given block tag in a single
+    /// JSON-RPC batch request, e.g. at the `pending` block to include the mempool transactions.
+    pub async fn get_account_states_at(
+        &self,
+        addresses: &[Address],
+        tag: BlockNumberOrTag,
+        with_code: bool,
+    ) -> TransportResult<HashMap<Address, AccountState>> {
+        let mut batch = self.0.new_batch();
+
+        let mut calls = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let balance =
+                batch.add_call("eth_getBalance", &(address, tag)).expect("Correct parameters");
+            let tx_count = batch
+                .add_call("eth_getTransactionCount", &(address, tag))
+                .expect("Correct parameters");
+            let code = with_code.then(|| {
+                batch.add_call("eth_getCode", &(address, tag)).expect("Correct parameters")
+            });
+
+            calls.push((*address, balance, tx_count, code));
+        }
+
+        // After the batch is complete, we can get the results.
+        // Note that requests may error separately!
+        batch.send().await?;
+
+        let mut states = HashMap::with_capacity(addresses.len());
+        for (address, balance, tx_count, code) in calls {
+            let balance: U256 = balance.await?;
+            let tx_count: U64 = tx_count.await?;
+            let has_code = match code {
+                Some(code) => {
+                    let code: Bytes = code.await?;
+                    !code.is_empty()
+                }
+                None => false,
+            };
+
+            states.insert(
+                address,
+                AccountState { balance, transaction_count: tx_count.to(), has_code },
+            );
+        }
+
+        Ok(states)
+    }
+
+    /// Get the number of the block with the given tag, e.g. the finalized or safe block.
+    pub async fn get_block_number(&self, tag: BlockNumberOrTag) -> TransportResult<u64> {
+        let block: Block = self.0.request("eth_getBlockByNumber", (tag, false)).await?;
+
+        Ok(block.header.number)
+    }
+
     /// Get the block with the given number. If `None`, the latest block is returned.
     pub async fn get_block(&self, block_number: Option<u64>, full: bool) -> TransportResult<Block> {
         let tag = block_number.map_or(BlockNumberOrTag::Latest, BlockNumberOrTag::Number);
@@ -113,12 +223,84 @@ impl RpcClient {
         self.0.request("eth_getBlockByNumber", (tag, full)).await
     }
 
+    /// Simulate the given calls in order, in a single block on top of the latest one.
+    /// Returns the outcome of every call, in the same order.
+    ///
+    /// The calls aren't validated (nonces and fees), so that signed transactions can be
+    /// replayed as is ahead of the ones to simulate.
+    pub async fn simulate(
+        &self,
+        calls: &[TransactionRequest],
+    ) -> TransportResult<Vec<SimulatedCall>> {
+        #[derive(Deserialize)]
+        struct SimulatedBlock {
+            calls: Vec<SimulatedCall>,
+        }
+
+        let payload = json!({ "blockStateCalls": [{ "calls": calls }], "validation": false });
+        let blocks: Vec<SimulatedBlock> =
+            self.0.request("eth_simulateV1", (payload, BlockNumberOrTag::Latest)).await?;
+
+        let block = blocks
+            .into_iter()
+            .next()
+            .ok_or(TransportErrorKind::Custom("empty simulation result".into()))?;
+        if block.calls.len() != calls.len() {
+            return Err(
+                TransportErrorKind::Custom("missing calls in simulation result".into()).into()
+            );
+        }
+
+        Ok(block.calls)
+    }
+
     /// Send a raw transaction to the network.
     #[allow(unused)]
     pub async fn send_raw_transaction(&self, raw: Bytes) -> TransportResult<B256> {
         self.0.request("eth_sendRawTransaction", [raw]).await
     }
 
+    /// Get the transactions with the given hashes in a single JSON-RPC batch request, in the
+    /// same order. Transactions unknown to the execution client are `None`.
+    pub async fn get_transactions_by_hash(
+        &self,
+        hashes: &[TxHash],
+    ) -> TransportResult<Vec<Option<Transaction>>> {
+        let mut batch = self.0.new_batch();
+
+        let calls = hashes
+            .iter()
+            .map(|hash| {
+                batch.add_call("eth_getTransactionByHash", &(&[hash])).expect("Correct parameters")
+            })
+            .collect::<Vec<_>>();
+
+        batch.send().await?;
+
+        let mut txs = Vec::with_capacity(calls.len());
+        for call in calls {
+            let tx: Option<Transaction> = call.await?;
+            txs.push(tx);
+        }
+
+        Ok(txs)
+    }
+
+    /// Get the pending and queued transactions in the mempool of the execution client with
+    /// `txpool_content`, keyed by hash.
+    pub async fn get_txpool_content(&self) -> TransportResult<HashMap<TxHash, Transaction>> {
+        // { "pending": { sender: { nonce: tx } }, "queued": { sender: { nonce: tx } } }
+        let content: HashMap<String, HashMap<Address, HashMap<String, Transaction>>> =
+            self.0.request("txpool_content", ()).await?;
+
+        Ok(content
+            .into_values()
+            .flat_map(HashMap::into_values)
+            .flat_map(HashMap::into_values)
+            .map(|tx| (tx.tx_hash(), tx))
+            .collect())
+    }
+
     /// Get the receipts for a list of transaction hashes.
     pub async fn get_receipts(
         &self,
@@ -167,9 +349,12 @@ mod tests {
 
     use alloy::{
         consensus::constants::ETH_TO_WEI,
-        primitives::{uint, Uint},
+        network::TransactionBuilder,
+        primitives::{address, uint, Uint},
     };
     use dotenvy::dotenv;
+    use serde_json::Value;
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
 
     use crate::test_util::launch_anvil;
 
@@ -191,6 +376,62 @@ mod tests {
         assert_eq!(account_state.transaction_count, 0);
     }
 
+    #[tokio::test]
+    async fn test_simulate() -> eyre::Result<()> {
+        let sender = address!("1111111111111111111111111111111111111111");
+        let reverting = address!("2222222222222222222222222222222222222222");
+
+        // Mock EL node reverting calls to `reverting`, and succeeding otherwise
+        let simulate_call = move |call: &Value| {
+            let to = call["to"].as_str().unwrap().parse::<Address>().unwrap();
+            if to == reverting {
+                json!({
+                    "status": "0x0",
+                    "returnData": "0xdead",
+                    "gasUsed": "0x5208",
+                    "logs": [],
+                    "error": { "code": 3, "message": "execution reverted", "data": "0xdead" }
+                })
+            } else {
+                json!({ "status": "0x1", "returnData": "0x01", "gasUsed": "0x5208", "logs": [] })
+            }
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(move |req: &Request| {
+                let body = serde_json::from_slice::<Value>(&req.body).unwrap();
+                assert_eq!(body["method"], "eth_simulateV1");
+                assert_eq!(body["params"][0]["validation"], json!(false));
+
+                let calls = body["params"][0]["blockStateCalls"][0]["calls"].as_array().unwrap();
+                let results = calls.iter().map(simulate_call).collect::<Vec<_>>();
+
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": body["id"],
+                    "result": [{ "number": "0x1", "calls": results }]
+                });
+                ResponseTemplate::new(200).set_body_json(response)
+            })
+            .mount(&server)
+            .await;
+
+        let client = RpcClient::new(Url::parse(&server.uri())?);
+
+        let success = TransactionRequest::default().with_from(sender).with_to(Address::ZERO);
+        let revert = TransactionRequest::default().with_from(sender).with_to(reverting);
+        let results = client.simulate(&[success, revert]).await?;
+
+        assert!(results[0].is_success());
+        assert_eq!(results[0].return_data, Bytes::from_static(&[1]));
+
+        assert!(!results[1].is_success());
+        assert_eq!(results[1].failure_reason(), "execution reverted (\"0xdead\")");
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_get_receipts() {
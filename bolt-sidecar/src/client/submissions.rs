@@ -0,0 +1,152 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use crate::api::spec::BuilderApiError;
+
+/// The maximum length of the relay response bodies kept in the reports, in bytes.
+const MAX_REPORTED_BODY_LEN: usize = 1024;
+
+/// The maximum number of submission reports kept in memory. Older reports are dropped first.
+const MAX_SUBMISSION_REPORTS: usize = 64;
+
+/// A single attempt to submit constraints to a relay.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubmissionAttempt {
+    /// The number of the attempt, starting from 1.
+    pub attempt: usize,
+    /// The URL of the relay.
+    pub relay: String,
+    /// The HTTP status of the response of the relay, if it answered.
+    pub status: Option<u16>,
+    /// The body of the response of the relay if it refused the constraints, truncated to
+    /// [MAX_REPORTED_BODY_LEN] bytes.
+    pub body: Option<String>,
+    /// The time the attempt took, in milliseconds.
+    pub latency_ms: u64,
+    /// The error of the attempt if it failed.
+    pub error: Option<String>,
+    /// The reason of the failure of the attempt, as a metric label.
+    pub reason: Option<&'static str>,
+}
+
+impl SubmissionAttempt {
+    /// Create the record of an attempt from its result.
+    pub fn new(
+        attempt: usize,
+        relay: impl Into<String>,
+        latency: Duration,
+        result: &Result<(), BuilderApiError>,
+    ) -> Self {
+        let (status, body) = match result {
+            Ok(()) => (Some(200), None),
+            Err(BuilderApiError::RelayRejected { status, body }) => {
+                (Some(*status), Some(truncate(body, MAX_REPORTED_BODY_LEN)))
+            }
+            Err(_) => (None, None),
+        };
+
+        Self {
+            attempt,
+            relay: relay.into(),
+            status,
+            body,
+            latency_ms: latency.as_millis() as u64,
+            error: result.as_ref().err().map(ToString::to_string),
+            reason: result.as_ref().err().map(failure_reason),
+        }
+    }
+}
+
+/// The report of the submission of the constraints of a slot, with all its attempts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubmissionReport {
+    /// The slot of the constraints.
+    pub slot: u64,
+    /// Whether the constraints were eventually accepted by the relay.
+    pub success: bool,
+    /// The attempts, in order.
+    pub attempts: Vec<SubmissionAttempt>,
+}
+
+/// A cheaply cloneable ring buffer of the latest submission reports.
+#[derive(Debug, Clone, Default)]
+pub struct SubmissionReports(Arc<RwLock<VecDeque<SubmissionReport>>>);
+
+impl SubmissionReports {
+    /// Record a new report, dropping the oldest one if the buffer is full.
+    pub fn record(&self, report: SubmissionReport) {
+        let mut reports = self.0.write();
+        if reports.len() == MAX_SUBMISSION_REPORTS {
+            reports.pop_front();
+        }
+        reports.push_back(report);
+    }
+
+    /// Returns the latest reports, the most recent first.
+    pub fn latest(&self) -> Vec<SubmissionReport> {
+        self.0.read().iter().rev().cloned().collect()
+    }
+}
+
+/// Returns the reason of the failure of a submission attempt with the given error, as a
+/// metric label.
+pub fn failure_reason(err: &BuilderApiError) -> &'static str {
+    match err {
+        BuilderApiError::RelayRejected { status, .. } if *status >= 500 => "relay_error",
+        BuilderApiError::RelayRejected { .. } => "relay_rejected",
+        BuilderApiError::Timeout(_) => "timeout",
+        BuilderApiError::CircuitOpen(_) => "circuit_open",
+        BuilderApiError::ReqwestError(_) => "unreachable",
+        _ => "other",
+    }
+}
+
+/// Truncate the given string to at most `max_len` bytes, on a character boundary.
+fn truncate(s: &str, max_len: usize) -> String {
+    let mut end = s.len().min(max_len);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    s[..end].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_submission_attempt_from_result() {
+        let body = r#"{"code":400,"message":"invalid signature"}"#.to_string();
+        let err = Err(BuilderApiError::RelayRejected { status: 400, body: body.clone() });
+        let attempt = SubmissionAttempt::new(2, "http://relay", Duration::from_millis(15), &err);
+        assert_eq!(attempt.status, Some(400));
+        assert_eq!(attempt.body, Some(body));
+        assert_eq!(attempt.latency_ms, 15);
+        assert_eq!(attempt.reason, Some("relay_rejected"));
+
+        // Long bodies are truncated
+        let err = Err(BuilderApiError::RelayRejected { status: 502, body: "é".repeat(1000) });
+        let attempt = SubmissionAttempt::new(1, "http://relay", Duration::ZERO, &err);
+        assert_eq!(attempt.body.unwrap().len(), MAX_REPORTED_BODY_LEN);
+        assert_eq!(attempt.reason, Some("relay_error"));
+
+        let attempt = SubmissionAttempt::new(1, "http://relay", Duration::ZERO, &Ok(()));
+        assert_eq!((attempt.status, attempt.error, attempt.reason), (Some(200), None, None));
+    }
+
+    #[test]
+    fn test_submission_reports_ring_buffer() {
+        let reports = SubmissionReports::default();
+        for slot in 0..(MAX_SUBMISSION_REPORTS as u64 + 2) {
+            reports.record(SubmissionReport { slot, success: true, attempts: Vec::new() });
+        }
+
+        let latest = reports.latest();
+        assert_eq!(latest.len(), MAX_SUBMISSION_REPORTS);
+        assert_eq!(latest.first().unwrap().slot, MAX_SUBMISSION_REPORTS as u64 + 1);
+        assert_eq!(latest.last().unwrap().slot, 2);
+    }
+}
@@ -4,7 +4,7 @@ use std::{
     future::Future,
     ops::Deref,
     path::Path,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use alloy::{hex, primitives::U256, signers::k256::ecdsa::SigningKey};
@@ -79,22 +79,26 @@ pub fn validate_transaction(
 ) -> Result<(), ValidationError> {
     // Check if the nonce is correct (should be the same as the transaction count)
     if transaction.nonce() < account_state.transaction_count {
-        return Err(ValidationError::NonceTooLow(
-            account_state.transaction_count,
-            transaction.nonce(),
-        ));
+        return Err(ValidationError::NonceTooLow {
+            expected: account_state.transaction_count,
+            got: transaction.nonce(),
+        });
     }
 
     if transaction.nonce() > account_state.transaction_count {
-        return Err(ValidationError::NonceTooHigh(
-            account_state.transaction_count,
-            transaction.nonce(),
-        ));
+        return Err(ValidationError::NonceTooHigh {
+            expected: account_state.transaction_count,
+            got: transaction.nonce(),
+        });
     }
 
     // Check if the balance is enough
-    if max_transaction_cost(transaction) > account_state.balance {
-        return Err(ValidationError::InsufficientBalance);
+    let required = max_transaction_cost(transaction);
+    if required > account_state.balance {
+        return Err(ValidationError::InsufficientBalance {
+            required,
+            available: account_state.balance,
+        });
     }
 
     // Check if the account has code (i.e. is a smart contract)
@@ -259,6 +263,22 @@ where
     Retry::spawn(backoff, fut).await
 }
 
+/// Retry a future with exponential backoff and jitter, up to 5 seconds apart, until it succeeds
+/// or the next attempt would start after the given deadline.
+pub async fn retry_until_deadline<F, T, E>(deadline: Duration, fut: impl Fn() -> F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let give_up_at = Instant::now() + deadline;
+    let backoff = ExponentialBackoff::from_millis(2)
+        .factor(100)
+        .max_delay(Duration::from_secs(5))
+        .map(jitter)
+        .take_while(move |delay| Instant::now() + *delay < give_up_at);
+
+    Retry::spawn(backoff, fut).await
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -374,4 +394,39 @@ mod tests {
             "Total backoff duration should be at least 700ms"
         );
     }
+
+    #[tokio::test]
+    async fn test_retry_until_deadline() {
+        // Succeeds within the deadline
+        let counter = Arc::new(Mutex::new(Counter::new(2)));
+        let result = retry_until_deadline(Duration::from_secs(5), || {
+            let counter = Arc::clone(&counter);
+            async move { counter.lock().await.retryable_fn().await }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(counter.lock().await.count, 3);
+
+        // Gives up once the deadline passed
+        let counter = Arc::new(Mutex::new(Counter::new(usize::MAX)));
+        let start_time = Instant::now();
+        let result = retry_until_deadline(Duration::from_secs(1), || {
+            let counter = Arc::clone(&counter);
+            async move { counter.lock().await.retryable_fn().await }
+        })
+        .await;
+        assert!(result.is_err());
+        assert!(start_time.elapsed() <= Duration::from_millis(1200));
+        assert!(counter.lock().await.count > 1);
+
+        // A zero deadline fails right away
+        let counter = Arc::new(Mutex::new(Counter::new(usize::MAX)));
+        let result = retry_until_deadline(Duration::ZERO, || {
+            let counter = Arc::clone(&counter);
+            async move { counter.lock().await.retryable_fn().await }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(counter.lock().await.count, 1);
+    }
 }
@@ -0,0 +1,306 @@
+use std::{
+    fmt, fs,
+    net::{IpAddr, Ipv4Addr},
+    num::NonZero,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use clap::Parser;
+use eyre::{bail, eyre, Context};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    ServerConfig,
+};
+use serde::{Deserialize, Deserializer};
+
+/// Default address on which the commitments API listens: all interfaces.
+pub const DEFAULT_API_HOST: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+/// Default base path under which the commitments API routes are served.
+pub const DEFAULT_BASE_PATH: &str = "/";
+
+/// Default capacity of the queue of commitment requests waiting to be processed.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// Default maximum number of requests in a JSON-RPC batch.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 32;
+
+/// Options for exposing the commitments API, including deployments behind a reverse proxy.
+#[derive(Debug, Clone, Parser, Deserialize)]
+pub struct ApiOpts {
+    /// Address on which the commitments API listens, e.g. `127.0.0.1` to only accept
+    /// connections from a reverse proxy on the same host.
+    #[clap(long = "api-host", env = "BOLT_SIDECAR_API_HOST", default_value_t = DEFAULT_API_HOST)]
+    pub host: IpAddr,
+    /// Path to the PEM-encoded TLS certificate chain of the commitments API. If provided
+    /// along with the key, the API is served over HTTPS.
+    #[clap(long = "api-tls-cert", env = "BOLT_SIDECAR_API_TLS_CERT")]
+    #[serde(default)]
+    pub tls_cert: Option<PathBuf>,
+    /// Path to the PEM-encoded private key of the TLS certificate of the commitments API.
+    #[clap(long = "api-tls-key", env = "BOLT_SIDECAR_API_TLS_KEY")]
+    #[serde(default)]
+    pub tls_key: Option<PathBuf>,
+    /// Base path under which all the commitments API routes are served, e.g. `/bolt`.
+    /// Useful when the sidecar sits behind a reverse proxy that routes on a path prefix.
+    #[clap(long, env = "BOLT_SIDECAR_API_BASE_PATH", default_value = DEFAULT_BASE_PATH)]
+    pub base_path: String,
+    /// Comma-separated list of CIDR ranges of trusted reverse proxies, e.g. `10.0.0.0/8`.
+    ///
+    /// The `X-Forwarded-For` and `X-Forwarded-Proto` headers and PROXY protocol headers
+    /// are only honored when the connection comes from one of these ranges.
+    #[clap(long, env = "BOLT_SIDECAR_API_TRUSTED_PROXIES", value_delimiter = ',')]
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpCidr>,
+    /// Expect a PROXY protocol v2 header on incoming connections from trusted proxies.
+    #[clap(long, env = "BOLT_SIDECAR_API_PROXY_PROTOCOL", default_value_t = false)]
+    #[serde(default)]
+    pub proxy_protocol: bool,
+    /// Bearer token authenticating the admin endpoints, e.g. to update the limits at
+    /// runtime. The admin endpoints are disabled if not provided.
+    #[clap(long, env = "BOLT_SIDECAR_ADMIN_TOKEN", hide_env_values = true)]
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Capacity of the queue of commitment requests waiting to be processed. Requests
+    /// received while it is full are rejected right away with a "server overloaded" error.
+    #[clap(
+        long = "api-queue-capacity",
+        env = "BOLT_SIDECAR_API_QUEUE_CAPACITY",
+        default_value_t = ApiOpts::default().queue_capacity
+    )]
+    pub queue_capacity: NonZero<usize>,
+    /// Maximum number of requests in a JSON-RPC batch. Larger batches are rejected as a whole.
+    #[clap(
+        long = "api-max-batch-size",
+        env = "BOLT_SIDECAR_API_MAX_BATCH_SIZE",
+        default_value_t = ApiOpts::default().max_batch_size
+    )]
+    pub max_batch_size: NonZero<usize>,
+}
+
+impl Default for ApiOpts {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_API_HOST,
+            tls_cert: None,
+            tls_key: None,
+            base_path: DEFAULT_BASE_PATH.to_string(),
+            trusted_proxies: Vec::new(),
+            proxy_protocol: false,
+            admin_token: None,
+            queue_capacity: NonZero::new(DEFAULT_QUEUE_CAPACITY).expect("Valid non-zero"),
+            max_batch_size: NonZero::new(DEFAULT_MAX_BATCH_SIZE).expect("Valid non-zero"),
+        }
+    }
+}
+
+impl ApiOpts {
+    /// Returns the base path normalized with a leading slash and without a trailing one,
+    /// or `None` if routes should be served at the root.
+    pub fn normalized_base_path(&self) -> Option<String> {
+        let trimmed = self.base_path.trim().trim_matches('/');
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(format!("/{trimmed}"))
+        }
+    }
+
+    /// Returns true if the given address belongs to one of the trusted proxy ranges.
+    pub fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// Load the TLS configuration of the API from the certificate and key files, if any.
+    ///
+    /// Fails if only one of them is provided, or if the key doesn't match the certificate.
+    pub fn tls_config(&self) -> eyre::Result<Option<Arc<ServerConfig>>> {
+        let (cert_path, key_path) = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => (cert, key),
+            (None, None) => return Ok(None),
+            (Some(_), None) => bail!("--api-tls-cert is set without --api-tls-key, provide both"),
+            (None, Some(_)) => bail!("--api-tls-key is set without --api-tls-cert, provide both"),
+        };
+
+        let certs = read_certs(cert_path)?;
+        let key = read_private_key(key_path)?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .wrap_err_with(|| {
+                format!(
+                    "TLS key {} doesn't match the certificate {}, check --api-tls-key",
+                    key_path.display(),
+                    cert_path.display()
+                )
+            })?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(Some(Arc::new(config)))
+    }
+}
+
+/// Read the PEM-encoded certificate chain at the given path.
+fn read_certs(path: &Path) -> eyre::Result<Vec<CertificateDer<'static>>> {
+    let pem = fs::read(path)
+        .wrap_err_with(|| format!("Failed to read TLS certificate {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err_with(|| format!("Invalid TLS certificate {}", path.display()))?;
+
+    if certs.is_empty() {
+        bail!("No certificate found in {}, check --api-tls-cert", path.display());
+    }
+    Ok(certs)
+}
+
+/// Read the PEM-encoded private key at the given path.
+fn read_private_key(path: &Path) -> eyre::Result<PrivateKeyDer<'static>> {
+    let pem =
+        fs::read(path).wrap_err_with(|| format!("Failed to read TLS key {}", path.display()))?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .wrap_err_with(|| format!("Invalid TLS key {}", path.display()))?
+        .ok_or_else(|| eyre!("No private key found in {}, check --api-tls-key", path.display()))
+}
+
+/// An IP address range in CIDR notation, e.g. `192.168.0.0/16` or `fd00::/8`.
+///
+/// A plain IP address without a prefix length is treated as a single host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Returns true if the given address is part of this range.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // Compare IPv4-mapped IPv6 addresses (e.g. `::ffff:10.0.0.1`) as plain IPv4
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            ip => ip,
+        };
+
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s, None),
+        };
+
+        let addr = addr.parse::<IpAddr>().map_err(|e| format!("invalid address {addr}: {e}"))?;
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        let prefix_len = match prefix_len {
+            Some(len) => len.parse::<u8>().map_err(|e| format!("invalid prefix length: {e}"))?,
+            None => max_len,
+        };
+
+        if prefix_len > max_len {
+            return Err(format!("prefix length {prefix_len} is too large for {addr}"));
+        }
+
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpCidr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::generate_test_tls;
+
+    use super::*;
+
+    #[test]
+    fn test_ip_cidr_contains() {
+        let cidr: IpCidr = "10.0.0.0/8".parse().unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(cidr.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+
+        let host: IpCidr = "127.0.0.1".parse().unwrap();
+        assert!(host.contains("127.0.0.1".parse().unwrap()));
+        assert!(!host.contains("127.0.0.2".parse().unwrap()));
+
+        let v6: IpCidr = "fd00::/8".parse().unwrap();
+        assert!(v6.contains("fd12::1".parse().unwrap()));
+        assert!(!v6.contains("fe80::1".parse().unwrap()));
+
+        let any: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains("8.8.8.8".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("not-an-ip/8".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn test_tls_config_errors() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let tls = generate_test_tls(&dir.path().join("first"))?;
+        let other = generate_test_tls(&dir.path().join("second"))?;
+
+        let mut opts = ApiOpts::default();
+        assert!(opts.tls_config()?.is_none());
+
+        // Both files are required
+        opts.tls_cert = Some(tls.cert_path.clone());
+        let err = opts.tls_config().unwrap_err().to_string();
+        assert!(err.contains("--api-tls-key"), "{err}");
+
+        // The key must match the certificate
+        opts.tls_key = Some(other.key_path);
+        let err = opts.tls_config().unwrap_err().to_string();
+        assert!(err.contains("doesn't match the certificate"), "{err}");
+
+        opts.tls_key = Some(tls.key_path);
+        assert!(opts.tls_config()?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalized_base_path() {
+        let mut opts = ApiOpts::default();
+        assert_eq!(opts.normalized_base_path(), None);
+
+        opts.base_path = "bolt/".to_string();
+        assert_eq!(opts.normalized_base_path().as_deref(), Some("/bolt"));
+
+        opts.base_path = "/api/bolt".to_string();
+        assert_eq!(opts.normalized_base_path().as_deref(), Some("/api/bolt"));
+    }
+}
@@ -1,13 +1,19 @@
 use core::fmt;
 use std::{
+    collections::HashMap,
     fmt::{Display, Formatter},
+    fs,
     ops::Deref,
     time::Duration,
 };
 
-use alloy::primitives::{address, Address};
+use alloy::primitives::{address, b256, Address, FixedBytes, B256};
 use clap::{Args, ValueEnum};
-use ethereum_consensus::deneb::{compute_fork_data_root, Root};
+use ethereum_consensus::{
+    deneb::{compute_fork_data_root, DomainType, Root},
+    phase0::mainnet::SLOTS_PER_EPOCH,
+    Fork,
+};
 use serde::Deserialize;
 
 /// Default commitment deadline duration.
@@ -17,9 +23,25 @@ use serde::Deserialize;
 /// relays have enough time to build valid payloads.
 pub const DEFAULT_COMMITMENT_DEADLINE_IN_MILLIS: u64 = 8_000;
 
+/// Default safety margin before the start of the target slot at which the sidecar stops
+/// accepting commitments, with the [DeadlinePolicyKind::DutyDistance] policy.
+pub const DEFAULT_DEADLINE_SAFETY_MARGIN_IN_MILLIS: u64 = 4_000;
+
 /// Default slot time duration in seconds.
 pub const DEFAULT_SLOT_TIME_IN_SECONDS: u64 = 12;
 
+/// Default minimum delay between the start of a slot and the arrival of its head with a
+/// synchronized clock, i.e. the time to propose, propagate and import the fastest blocks.
+pub const DEFAULT_CLOCK_SKEW_PROPAGATION_BASELINE_IN_MILLIS: u64 = 1_000;
+
+/// Default offset between the arrival of the heads and the start of their slot above which
+/// the local clock is reported as skewed.
+pub const DEFAULT_CLOCK_SKEW_WARN_THRESHOLD_IN_MILLIS: u64 = 500;
+
+/// Default offset between the arrival of the heads and the start of their slot above which
+/// the commitment deadlines are compensated for it.
+pub const DEFAULT_CLOCK_SKEW_COMPENSATION_THRESHOLD_IN_MILLIS: u64 = 2_000;
+
 /// The domain mask for signing application-builder messages.
 pub const APPLICATION_BUILDER_DOMAIN_MASK: [u8; 4] = [0, 0, 0, 1];
 
@@ -30,8 +52,18 @@ pub const COMMIT_BOOST_DOMAIN_MASK: [u8; 4] = [109, 109, 111, 67];
 pub const DEFAULT_CHAIN_CONFIG: ChainConfig = ChainConfig {
     chain: Chain::Mainnet,
     commitment_deadline: DEFAULT_COMMITMENT_DEADLINE_IN_MILLIS,
+    deadline_policy: DeadlinePolicyKind::Fixed,
+    deadline_safety_margin: DEFAULT_DEADLINE_SAFETY_MARGIN_IN_MILLIS,
+    min_lead_time: 0,
+    min_lead_slots: 0,
+    clock_skew_propagation_baseline: DEFAULT_CLOCK_SKEW_PROPAGATION_BASELINE_IN_MILLIS,
+    clock_skew_warn_threshold: DEFAULT_CLOCK_SKEW_WARN_THRESHOLD_IN_MILLIS,
+    clock_skew_compensation_threshold: DEFAULT_CLOCK_SKEW_COMPENSATION_THRESHOLD_IN_MILLIS,
     slot_time: DEFAULT_SLOT_TIME_IN_SECONDS,
     enable_unsafe_lookahead: false,
+    require_replay_protection: false,
+    electra_fork_epoch: None,
+    chain_config: None,
 };
 
 /// The address of the canonical BoltManager contract for the Holesky chain.
@@ -39,6 +71,70 @@ pub const DEFAULT_CHAIN_CONFIG: ChainConfig = ChainConfig {
 /// https://holesky.etherscan.io/address/0x440202829b493F9FF43E730EB5e8379EEa3678CF
 pub const MANAGER_ADDRESS_HOLESKY: Address = address!("440202829b493F9FF43E730EB5e8379EEa3678CF");
 
+/// The epoch of the Electra fork on Mainnet.
+pub const ELECTRA_FORK_EPOCH_MAINNET: u64 = 364_032;
+
+/// The epoch of the Electra fork on Holesky.
+pub const ELECTRA_FORK_EPOCH_HOLESKY: u64 = 115_968;
+
+/// The fork schedule of Mainnet.
+pub const MAINNET_FORK_SCHEDULE: ForkSchedule = ForkSchedule {
+    chain_id: 1,
+    genesis_validators_root: b256!(
+        "4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"
+    ),
+    genesis_fork_version: [0, 0, 0, 0],
+    deneb_fork_version: [4, 0, 0, 0],
+    deneb_fork_epoch: 269_568,
+    electra_fork_version: [5, 0, 0, 0],
+    electra_fork_epoch: Some(ELECTRA_FORK_EPOCH_MAINNET),
+    seconds_per_slot: None,
+};
+
+/// The fork schedule of Holesky.
+pub const HOLESKY_FORK_SCHEDULE: ForkSchedule = ForkSchedule {
+    chain_id: 17000,
+    genesis_validators_root: b256!(
+        "9143aa7c615a7f7115e2b6aac319c03529df8242ae705fba9df39b79c59fa8b1"
+    ),
+    genesis_fork_version: [1, 1, 112, 0],
+    deneb_fork_version: [5, 1, 112, 0],
+    deneb_fork_epoch: 29_696,
+    electra_fork_version: [6, 1, 112, 0],
+    electra_fork_epoch: Some(ELECTRA_FORK_EPOCH_HOLESKY),
+    seconds_per_slot: None,
+};
+
+/// The fork schedule of the Helder devnet, see `testnets/helder/network-configs`.
+pub const HELDER_FORK_SCHEDULE: ForkSchedule = ForkSchedule {
+    chain_id: 7014190335,
+    genesis_validators_root: b256!(
+        "a55f9089402f027c67db4a43b6eb7fbb7b2eb79f194a90a2cd4f31913e47b336"
+    ),
+    genesis_fork_version: [16, 0, 0, 0],
+    deneb_fork_version: [80, 19, 39, 54],
+    deneb_fork_epoch: 0,
+    electra_fork_version: [96, 19, 39, 54],
+    electra_fork_epoch: None,
+    seconds_per_slot: None,
+};
+
+/// The fork schedule of the default Kurtosis devnets, which start at Deneb. Devnets with other
+/// parameters are configured with `--chain-config`.
+pub const KURTOSIS_FORK_SCHEDULE: ForkSchedule = ForkSchedule {
+    chain_id: 3151908,
+    genesis_validators_root: B256::ZERO,
+    genesis_fork_version: [16, 0, 0, 56],
+    deneb_fork_version: [80, 0, 0, 56],
+    deneb_fork_epoch: 0,
+    electra_fork_version: [96, 0, 0, 56],
+    electra_fork_epoch: None,
+    seconds_per_slot: None,
+};
+
+/// The epoch value used by the consensus configs for the forks that aren't scheduled.
+const FAR_FUTURE_EPOCH: u64 = u64::MAX;
+
 /// Configuration for the chain the sidecar is running on.
 #[derive(Debug, Clone, Copy, Args, Deserialize)]
 pub struct ChainConfig {
@@ -53,6 +149,67 @@ pub struct ChainConfig {
         default_value_t = DEFAULT_CHAIN_CONFIG.commitment_deadline
     )]
     pub(crate) commitment_deadline: u64,
+    /// The policy deciding when the sidecar stops accepting new commitments for a slot.
+    #[clap(long, env = "BOLT_SIDECAR_DEADLINE_POLICY", default_value = "fixed", value_enum)]
+    #[serde(default)]
+    pub(crate) deadline_policy: DeadlinePolicyKind,
+    /// The safety margin before the start of the target slot at which the sidecar stops
+    /// accepting new commitments for it, with the `duty-distance` deadline policy (parsed as
+    /// milliseconds).
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_DEADLINE_SAFETY_MARGIN",
+        default_value_t = DEFAULT_CHAIN_CONFIG.deadline_safety_margin
+    )]
+    #[serde(default = "default_deadline_safety_margin")]
+    pub(crate) deadline_safety_margin: u64,
+    /// The minimum time before the commitment deadline of the target slot at which requests
+    /// must be received (parsed as milliseconds). Disabled if zero.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_MIN_LEAD_TIME",
+        default_value_t = DEFAULT_CHAIN_CONFIG.min_lead_time
+    )]
+    #[serde(default)]
+    pub(crate) min_lead_time: u64,
+    /// The minimum number of slots before the commitment deadline of the target slot at which
+    /// requests must be received. Disabled if zero.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_MIN_LEAD_SLOTS",
+        default_value_t = DEFAULT_CHAIN_CONFIG.min_lead_slots
+    )]
+    #[serde(default)]
+    pub(crate) min_lead_slots: u64,
+    /// The minimum delay between the start of a slot and the arrival of its head with a
+    /// synchronized clock, subtracted from the measured delays to get the offset of the local
+    /// clock (parsed as milliseconds).
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CLOCK_SKEW_PROPAGATION_BASELINE",
+        default_value_t = DEFAULT_CHAIN_CONFIG.clock_skew_propagation_baseline
+    )]
+    #[serde(default = "default_clock_skew_propagation_baseline")]
+    pub(crate) clock_skew_propagation_baseline: u64,
+    /// The offset between the arrival of the heads and the start of their slot according to
+    /// the local clock above which a warning is logged (parsed as milliseconds).
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CLOCK_SKEW_WARN_THRESHOLD",
+        default_value_t = DEFAULT_CHAIN_CONFIG.clock_skew_warn_threshold
+    )]
+    #[serde(default = "default_clock_skew_warn_threshold")]
+    pub(crate) clock_skew_warn_threshold: u64,
+    /// The offset between the arrival of the heads and the start of their slot according to
+    /// the local clock above which the commitment deadlines are shifted by it, within a
+    /// bounded correction (parsed as milliseconds). Disabled if zero.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CLOCK_SKEW_COMPENSATION_THRESHOLD",
+        default_value_t = DEFAULT_CHAIN_CONFIG.clock_skew_compensation_threshold
+    )]
+    #[serde(default = "default_clock_skew_compensation_threshold")]
+    pub(crate) clock_skew_compensation_threshold: u64,
     /// The slot time duration in seconds. If provided,
     /// it overrides the default for the selected [Chain].
     #[clap(
@@ -69,6 +226,26 @@ pub struct ChainConfig {
         default_value_t = DEFAULT_CHAIN_CONFIG.enable_unsafe_lookahead
     )]
     pub(crate) enable_unsafe_lookahead: bool,
+    /// Toggle to reject legacy transactions without a chain ID, which could be replayed
+    /// on other chains.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_REQUIRE_REPLAY_PROTECTION",
+        default_value_t = DEFAULT_CHAIN_CONFIG.require_replay_protection
+    )]
+    #[serde(default)]
+    pub(crate) require_replay_protection: bool,
+    /// The epoch of the Electra fork. If provided, it overrides the fork schedule of
+    /// the selected [Chain].
+    #[clap(long, env = "BOLT_SIDECAR_ELECTRA_FORK_EPOCH")]
+    #[serde(default)]
+    pub(crate) electra_fork_epoch: Option<u64>,
+    /// Path to the config of a custom network, such as a devnet, in the YAML or JSON format of
+    /// the consensus configs. Its chain ID, genesis validators root, fork schedule and slot time
+    /// override the preset of the selected [Chain].
+    #[clap(long, env = "BOLT_SIDECAR_CHAIN_CONFIG", value_parser = parse_chain_config)]
+    #[serde(skip)]
+    pub(crate) chain_config: Option<ForkSchedule>,
 }
 
 impl Default for ChainConfig {
@@ -85,6 +262,162 @@ impl Deref for ChainConfig {
     }
 }
 
+/// The policies deciding when the sidecar stops accepting new commitments for a slot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum DeadlinePolicyKind {
+    /// Stop at the commitment deadline after the head preceding the target slot.
+    #[default]
+    Fixed,
+    /// Stop at the safety margin before the start of the target slot, so that requests for
+    /// slots further away are accepted for longer.
+    DutyDistance,
+}
+
+fn default_deadline_safety_margin() -> u64 {
+    DEFAULT_DEADLINE_SAFETY_MARGIN_IN_MILLIS
+}
+
+fn default_clock_skew_propagation_baseline() -> u64 {
+    DEFAULT_CLOCK_SKEW_PROPAGATION_BASELINE_IN_MILLIS
+}
+
+fn default_clock_skew_warn_threshold() -> u64 {
+    DEFAULT_CLOCK_SKEW_WARN_THRESHOLD_IN_MILLIS
+}
+
+fn default_clock_skew_compensation_threshold() -> u64 {
+    DEFAULT_CLOCK_SKEW_COMPENSATION_THRESHOLD_IN_MILLIS
+}
+
+/// The identity and the fork schedule of a chain, which resolve the fork version active at
+/// any epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForkSchedule {
+    /// The chain ID of the execution layer.
+    pub chain_id: u64,
+    /// The genesis validators root of the beacon chain.
+    pub genesis_validators_root: B256,
+    /// The fork version at genesis.
+    pub genesis_fork_version: [u8; 4],
+    /// The fork version of Deneb.
+    pub deneb_fork_version: [u8; 4],
+    /// The epoch of the Deneb fork.
+    pub deneb_fork_epoch: u64,
+    /// The fork version of Electra.
+    pub electra_fork_version: [u8; 4],
+    /// The epoch of the Electra fork, if scheduled.
+    pub electra_fork_epoch: Option<u64>,
+    /// The slot time in seconds, if it overrides the one of the chain.
+    pub seconds_per_slot: Option<u64>,
+}
+
+impl ForkSchedule {
+    /// Parse the fork schedule from a consensus config, in YAML or JSON. Only a flat subset of
+    /// YAML is supported, with one `KEY: value` pair per line, as in the configs of the specs.
+    ///
+    /// The genesis validators root isn't part of the consensus configs: it is read from the
+    /// `GENESIS_VALIDATORS_ROOT` key if present, and zero otherwise. The slot time is read from
+    /// the `SECONDS_PER_SLOT` key if present.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let values = if contents.trim_start().starts_with('{') {
+            let object = serde_json::from_str::<HashMap<String, serde_json::Value>>(contents)
+                .map_err(|err| format!("invalid JSON: {err}"))?;
+
+            object
+                .into_iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(value) => (key, value),
+                    value => (key, value.to_string()),
+                })
+                .collect()
+        } else {
+            parse_yaml_values(contents)?
+        };
+
+        let get = |key: &str| values.get(key).map(String::as_str);
+        let required = |key: &str| get(key).ok_or_else(|| format!("missing {key}"));
+        let number = |key: &str| {
+            required(key)?.parse::<u64>().map_err(|err| format!("invalid {key}: {err}"))
+        };
+        let version = |key: &str| {
+            required(key)?
+                .parse::<FixedBytes<4>>()
+                .map(|version| version.0)
+                .map_err(|err| format!("invalid {key}: {err}"))
+        };
+
+        let chain_id = number("DEPOSIT_CHAIN_ID")?;
+        let genesis_validators_root = match get("GENESIS_VALIDATORS_ROOT") {
+            Some(root) => root
+                .parse::<B256>()
+                .map_err(|err| format!("invalid GENESIS_VALIDATORS_ROOT: {err}"))?,
+            None => B256::ZERO,
+        };
+
+        let (electra_fork_version, electra_fork_epoch) = match get("ELECTRA_FORK_VERSION") {
+            Some(_) => {
+                let epoch = number("ELECTRA_FORK_EPOCH")?;
+                (version("ELECTRA_FORK_VERSION")?, Some(epoch).filter(|e| *e != FAR_FUTURE_EPOCH))
+            }
+            None => (version("DENEB_FORK_VERSION")?, None),
+        };
+
+        Ok(Self {
+            chain_id,
+            genesis_validators_root,
+            genesis_fork_version: version("GENESIS_FORK_VERSION")?,
+            deneb_fork_version: version("DENEB_FORK_VERSION")?,
+            deneb_fork_epoch: number("DENEB_FORK_EPOCH")?,
+            electra_fork_version,
+            electra_fork_epoch,
+            seconds_per_slot: get("SECONDS_PER_SLOT")
+                .map(|_| number("SECONDS_PER_SLOT"))
+                .transpose()?,
+        })
+    }
+
+    /// Get the fork version active at the given epoch. The forks before Deneb aren't tracked,
+    /// so the genesis fork version is returned for the epochs before it.
+    pub fn fork_version_at_epoch(&self, epoch: u64) -> [u8; 4] {
+        match self.electra_fork_epoch {
+            Some(electra) if epoch >= electra => self.electra_fork_version,
+            _ if epoch >= self.deneb_fork_epoch => self.deneb_fork_version,
+            _ => self.genesis_fork_version,
+        }
+    }
+}
+
+/// Parse the top-level `KEY: value` pairs of a YAML document, ignoring comments and quotes.
+/// Nested values, such as lists, are skipped.
+fn parse_yaml_values(contents: &str) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.starts_with(char::is_whitespace) || line.starts_with('-') {
+            continue;
+        }
+
+        let line = line.split(" #").next().unwrap_or_default().trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) =
+            line.split_once(':').ok_or_else(|| format!("line {}: expected `KEY: value`", i + 1))?;
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+
+    Ok(values)
+}
+
+/// Read the fork schedule of a custom network from the consensus config at the given path.
+fn parse_chain_config(path: &str) -> Result<ForkSchedule, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("can't read {path}: {err}"))?;
+    ForkSchedule::parse(&contents).map_err(|err| format!("invalid chain config {path}: {err}"))
+}
+
 /// Supported chains for the sidecar
 #[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
 #[clap(rename_all = "kebab_case")]
@@ -107,16 +440,26 @@ impl Chain {
         }
     }
 
-    /// Get the fork version for the given chain.
-    pub fn fork_version(&self) -> [u8; 4] {
+    /// Get the preset fork schedule of the given chain.
+    pub const fn fork_schedule(&self) -> ForkSchedule {
         match self {
-            Chain::Mainnet => [0, 0, 0, 0],
-            Chain::Holesky => [1, 1, 112, 0],
-            Chain::Helder => [16, 0, 0, 0],
-            Chain::Kurtosis => [16, 0, 0, 56],
+            Chain::Mainnet => MAINNET_FORK_SCHEDULE,
+            Chain::Holesky => HOLESKY_FORK_SCHEDULE,
+            Chain::Helder => HELDER_FORK_SCHEDULE,
+            Chain::Kurtosis => KURTOSIS_FORK_SCHEDULE,
         }
     }
 
+    /// Get the genesis fork version for the given chain.
+    pub fn fork_version(&self) -> [u8; 4] {
+        self.fork_schedule().genesis_fork_version
+    }
+
+    /// Get the epoch of the Electra fork for the given chain, if scheduled.
+    pub const fn electra_fork_epoch(&self) -> Option<u64> {
+        self.fork_schedule().electra_fork_epoch
+    }
+
     /// Returns the address of the canonical BoltManager contract for a given chain, if present
     pub const fn manager_address(&self) -> Option<Address> {
         match self {
@@ -133,19 +476,50 @@ impl Display for Chain {
 }
 
 impl ChainConfig {
+    /// Get the fork schedule of the chain: the custom chain config if provided, or the preset
+    /// of the selected [Chain], with the configured Electra fork epoch.
+    pub fn fork_schedule(&self) -> ForkSchedule {
+        let mut schedule = self.chain_config.unwrap_or(self.chain.fork_schedule());
+        if let Some(epoch) = self.electra_fork_epoch {
+            schedule.electra_fork_epoch = Some(epoch);
+        }
+
+        schedule
+    }
+
     /// Get the chain ID for the given chain.
     pub fn chain_id(&self) -> u64 {
-        match self.chain {
-            Chain::Mainnet => 1,
-            Chain::Holesky => 17000,
-            Chain::Helder => 7014190335,
-            Chain::Kurtosis => 3151908,
-        }
+        self.fork_schedule().chain_id
+    }
+
+    /// Get the genesis fork version for the given chain.
+    pub fn fork_version(&self) -> [u8; 4] {
+        self.fork_schedule().genesis_fork_version
+    }
+
+    /// Get the fork version active at the given epoch.
+    pub fn fork_version_at_epoch(&self, epoch: u64) -> [u8; 4] {
+        self.fork_schedule().fork_version_at_epoch(epoch)
     }
 
-    /// Get the slot time for the given chain in seconds.
+    /// Get the slot time for the given chain in seconds, from the custom chain config if it
+    /// sets one.
     pub fn slot_time(&self) -> u64 {
-        self.slot_time
+        self.chain_config.and_then(|config| config.seconds_per_slot).unwrap_or(self.slot_time)
+    }
+
+    /// Get the epoch of the Electra fork, either configured or from the schedule of the chain.
+    pub fn electra_fork_epoch(&self) -> Option<u64> {
+        self.fork_schedule().electra_fork_epoch
+    }
+
+    /// Get the fork of the payloads built for the given slot. Electra is active from the
+    /// first slot of its fork epoch, and Deneb before it.
+    pub fn fork_at_slot(&self, slot: u64) -> Fork {
+        match self.electra_fork_epoch() {
+            Some(epoch) if slot / SLOTS_PER_EPOCH >= epoch => Fork::Electra,
+            _ => Fork::Deneb,
+        }
     }
 
     /// Get the domain for signing application-builder messages on the given chain.
@@ -163,11 +537,67 @@ impl ChainConfig {
         Duration::from_millis(self.commitment_deadline)
     }
 
+    /// Get the safety margin before the start of the target slot of the duty distance
+    /// deadline policy.
+    pub fn deadline_safety_margin(&self) -> Duration {
+        Duration::from_millis(self.deadline_safety_margin)
+    }
+
+    /// Get the minimum time before the commitment deadline of the target slot at which
+    /// requests must be received: the longest of the minimum lead time and slots.
+    pub fn min_lead_time(&self) -> Duration {
+        let lead_slots = Duration::from_secs(self.slot_time() * self.min_lead_slots);
+        Duration::from_millis(self.min_lead_time).max(lead_slots)
+    }
+
+    /// Get the minimum arrival delay of the heads with a synchronized clock.
+    pub fn clock_skew_propagation_baseline(&self) -> Duration {
+        Duration::from_millis(self.clock_skew_propagation_baseline)
+    }
+
+    /// Get the clock offset above which the local clock is reported as skewed.
+    pub fn clock_skew_warn_threshold(&self) -> Duration {
+        Duration::from_millis(self.clock_skew_warn_threshold)
+    }
+
+    /// Get the clock offset above which the commitment deadlines are compensated for
+    /// it. Zero disables the compensation.
+    pub fn clock_skew_compensation_threshold(&self) -> Duration {
+        Duration::from_millis(self.clock_skew_compensation_threshold)
+    }
+
+    /// Get the number of slots after the head for which commitments are accepted: one epoch,
+    /// or two with the unsafe lookahead enabled.
+    pub fn lookahead_slots(&self) -> u64 {
+        if self.enable_unsafe_lookahead {
+            2 * SLOTS_PER_EPOCH
+        } else {
+            SLOTS_PER_EPOCH
+        }
+    }
+
+    /// Compute the domain of the given type for in-protocol messages of the given epoch, with
+    /// the fork version active at that epoch and the genesis validators root of the chain.
+    pub fn domain_at_epoch(&self, domain_type: DomainType, epoch: u64) -> [u8; 32] {
+        let schedule = self.fork_schedule();
+        let fork_version = schedule.fork_version_at_epoch(epoch);
+        let root = Root::from_slice(schedule.genesis_validators_root.as_slice());
+        let fork_data_root = compute_fork_data_root(fork_version, root).expect("valid fork data");
+
+        let mut domain = [0; 32];
+        domain[..4].copy_from_slice(&domain_type.as_bytes());
+        domain[4..].copy_from_slice(&fork_data_root[..28]);
+        domain
+    }
+
     /// Compute the domain for signing messages on the given chain.
+    ///
+    /// NOTE: out-of-protocol messages are signed with the genesis fork version at any epoch,
+    /// as required by the builder specs.
     fn compute_domain_from_mask(&self, mask: [u8; 4]) -> [u8; 32] {
         let mut domain = [0; 32];
 
-        let fork_version = self.chain.fork_version();
+        let fork_version = self.fork_version();
 
         // Note: the application builder domain specs require the genesis_validators_root
         // to be 0x00 for any out-of-protocol message. The commit-boost domain follows the
@@ -221,6 +651,76 @@ mod tests {
     const BUILDER_DOMAIN_KURTOSIS: [u8; 32] =
         b256!("000000010b41be4cdb34d183dddca5398337626dcdcfaf1720c1202d3b95f84e").0;
 
+    #[test]
+    fn test_fork_at_slot_boundary() {
+        use ethereum_consensus::{phase0::mainnet::SLOTS_PER_EPOCH, Fork};
+
+        use super::{ChainConfig, ELECTRA_FORK_EPOCH_HOLESKY};
+
+        let holesky = ChainConfig::holesky();
+        let fork_slot = ELECTRA_FORK_EPOCH_HOLESKY * SLOTS_PER_EPOCH;
+        assert_eq!(holesky.fork_at_slot(fork_slot - 1), Fork::Deneb);
+        assert_eq!(holesky.fork_at_slot(fork_slot), Fork::Electra);
+        assert_eq!(holesky.fork_at_slot(fork_slot + 1), Fork::Electra);
+
+        // The configured epoch overrides the schedule of the chain
+        let kurtosis = ChainConfig::kurtosis(2, 1_000);
+        assert_eq!(kurtosis.fork_at_slot(u64::MAX), Fork::Deneb);
+
+        let kurtosis = ChainConfig { electra_fork_epoch: Some(4), ..kurtosis };
+        assert_eq!(kurtosis.fork_at_slot(4 * SLOTS_PER_EPOCH - 1), Fork::Deneb);
+        assert_eq!(kurtosis.fork_at_slot(4 * SLOTS_PER_EPOCH), Fork::Electra);
+    }
+
+    #[test]
+    fn test_custom_chain_config() {
+        use ethereum_consensus::{deneb::DomainType, phase0::mainnet::SLOTS_PER_EPOCH, Fork};
+
+        use super::{parse_chain_config, ChainConfig};
+
+        let dir = env!("CARGO_MANIFEST_DIR");
+        let schedule = parse_chain_config(&format!("{dir}/test_data/chain_config.yaml")).unwrap();
+        let json = parse_chain_config(&format!("{dir}/test_data/chain_config.json")).unwrap();
+        assert_eq!(schedule, json);
+
+        let kurtosis = ChainConfig::kurtosis(2, 1_000);
+        let custom = ChainConfig { chain_config: Some(schedule), ..kurtosis };
+        assert_eq!(custom.chain_id(), 3151908);
+        assert_eq!(custom.fork_version(), [16, 0, 0, 56]);
+        assert_eq!(schedule.seconds_per_slot, Some(2));
+
+        // The slot time of the custom config overrides the one of the chain
+        let slow = ChainConfig { chain_config: Some(schedule), ..ChainConfig::kurtosis(12, 1_000) };
+        assert_eq!(slow.slot_time(), 2);
+        assert_eq!(ChainConfig::kurtosis(12, 1_000).slot_time(), 12);
+        assert_eq!(custom.electra_fork_epoch(), Some(4));
+        assert_eq!(custom.fork_at_slot(4 * SLOTS_PER_EPOCH - 1), Fork::Deneb);
+        assert_eq!(custom.fork_at_slot(4 * SLOTS_PER_EPOCH), Fork::Electra);
+
+        // The fork version, and thus the domain of in-protocol messages, changes at the
+        // Electra boundary epoch
+        assert_eq!(custom.fork_version_at_epoch(3), [80, 0, 0, 56]);
+        assert_eq!(custom.fork_version_at_epoch(4), [96, 0, 0, 56]);
+
+        let deneb = custom.domain_at_epoch(DomainType::BeaconProposer, 3);
+        let electra = custom.domain_at_epoch(DomainType::BeaconProposer, 4);
+        assert_ne!(deneb, electra);
+        assert_eq!(deneb, custom.domain_at_epoch(DomainType::BeaconProposer, 0));
+        assert_eq!(electra, custom.domain_at_epoch(DomainType::BeaconProposer, u64::MAX));
+        assert_eq!(electra[..4], DomainType::BeaconProposer.as_bytes());
+
+        // Without the custom config, the preset doesn't schedule Electra
+        assert_eq!(kurtosis.fork_version_at_epoch(4), [80, 0, 0, 56]);
+        assert_ne!(kurtosis.domain_at_epoch(DomainType::BeaconProposer, 4), electra);
+
+        // The out-of-protocol domains are computed with the genesis fork version
+        assert_eq!(custom.application_builder_domain(), kurtosis.application_builder_domain());
+
+        // Invalid configs are rejected
+        assert!(parse_chain_config(&format!("{dir}/test_data/missing.yaml")).is_err());
+        assert!(super::ForkSchedule::parse("GENESIS_FORK_VERSION: 0x10000038").is_err());
+    }
+
     #[test]
     fn test_compute_builder_domains() {
         use super::ChainConfig;
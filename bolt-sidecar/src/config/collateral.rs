@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+/// Default multiple of the operator collateral that a single request can be worth.
+pub const DEFAULT_COLLATERAL_MULTIPLE: f64 = 1.0;
+
+/// Default interval between two collateral polls from the BoltManager contract (one epoch).
+pub const DEFAULT_COLLATERAL_POLL_INTERVAL_SECS: u64 = 384;
+
+/// Default maximum age of the collateral snapshot before it's considered stale.
+pub const DEFAULT_COLLATERAL_MAX_STALENESS_SECS: u64 = 3 * DEFAULT_COLLATERAL_POLL_INTERVAL_SECS;
+
+/// What to do with requests whose value exceeds the allowed multiple of the operator collateral.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum CollateralPolicy {
+    /// Don't check requests against the operator collateral.
+    #[default]
+    Disabled,
+    /// Accept the request, but log a warning.
+    Warn,
+    /// Reject the request.
+    Reject,
+}
+
+/// Options for gating commitments on the operator collateral registered in Bolt.
+#[derive(Debug, Clone, Copy, Parser, Deserialize)]
+pub struct CollateralOpts {
+    /// What to do with requests whose total transaction value exceeds the allowed
+    /// multiple of the operator collateral.
+    #[clap(
+        long = "collateral-policy",
+        env = "BOLT_SIDECAR_COLLATERAL_POLICY",
+        default_value = "disabled"
+    )]
+    #[serde(default)]
+    pub policy: CollateralPolicy,
+    /// The maximum total transaction value of a request, as a multiple of the operator collateral.
+    #[clap(
+        long = "collateral-multiple",
+        env = "BOLT_SIDECAR_COLLATERAL_MULTIPLE",
+        default_value_t = DEFAULT_COLLATERAL_MULTIPLE
+    )]
+    pub multiple: f64,
+    /// Interval in seconds between two collateral polls from the BoltManager contract.
+    #[clap(
+        long = "collateral-poll-interval",
+        env = "BOLT_SIDECAR_COLLATERAL_POLL_INTERVAL",
+        default_value_t = DEFAULT_COLLATERAL_POLL_INTERVAL_SECS
+    )]
+    pub poll_interval_secs: u64,
+    /// Maximum age in seconds of the latest collateral poll. Past this threshold every request
+    /// is rejected, regardless of the policy, until a fresh value is fetched.
+    #[clap(
+        long = "collateral-max-staleness",
+        env = "BOLT_SIDECAR_COLLATERAL_MAX_STALENESS",
+        default_value_t = DEFAULT_COLLATERAL_MAX_STALENESS_SECS
+    )]
+    pub max_staleness_secs: u64,
+    /// Reject every request while the operator is not registered and enabled in Bolt, or while
+    /// its collateral is below the protocol minimum, as commitments made in that state can be
+    /// ignored. Requires on-chain checks.
+    #[clap(
+        long = "require-collateral",
+        env = "BOLT_SIDECAR_REQUIRE_COLLATERAL",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub require: bool,
+}
+
+impl Default for CollateralOpts {
+    fn default() -> Self {
+        Self {
+            policy: CollateralPolicy::default(),
+            multiple: DEFAULT_COLLATERAL_MULTIPLE,
+            poll_interval_secs: DEFAULT_COLLATERAL_POLL_INTERVAL_SECS,
+            max_staleness_secs: DEFAULT_COLLATERAL_MAX_STALENESS_SECS,
+            require: false,
+        }
+    }
+}
+
+impl CollateralOpts {
+    /// Returns the interval between two collateral polls.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs)
+    }
+
+    /// Returns the maximum age of a collateral snapshot.
+    pub fn max_staleness(&self) -> Duration {
+        Duration::from_secs(self.max_staleness_secs)
+    }
+}
@@ -1,9 +1,9 @@
 use std::{fmt, path::PathBuf};
 
-use clap::{ArgGroup, Args};
+use clap::{ArgGroup, Args, ValueEnum};
 use lighthouse_account_utils::ZeroizeString;
 use reqwest::Url;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::common::{BlsSecretKeyWrapper, JwtSecretConfig};
 
@@ -37,6 +37,39 @@ pub struct ConstraintSigningOpts {
     /// Path to the delegations file. If not provided, the default path is used.
     #[clap(long, env = "BOLT_SIDECAR_DELEGATIONS_PATH")]
     pub delegations_path: Option<PathBuf>,
+    /// What to do when the delegations and revocations of the delegations file can't be
+    /// submitted to the relay at startup.
+    #[clap(long, env = "BOLT_SIDECAR_DELEGATIONS_SUBMISSION_FAILURE", default_value = "warn")]
+    #[serde(default)]
+    pub delegations_submission_failure: SubmissionFailureMode,
+}
+
+/// What to do when a startup submission to the relay fails after all its retries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum SubmissionFailureMode {
+    /// Log a warning and keep starting the sidecar.
+    #[default]
+    Warn,
+    /// Abort the startup of the sidecar.
+    Abort,
+}
+
+/// The backend holding the ECDSA key that signs the commitment responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum CommitmentSignerKind {
+    /// A private key provided with `--commitment-private-key`.
+    #[default]
+    PrivateKey,
+    /// A key held by AWS KMS, with the region and credentials of the standard AWS environment.
+    Kms,
+    /// A key held by a Web3Signer instance.
+    #[clap(name = "web3signer")]
+    #[serde(rename = "web3signer")]
+    Web3signer,
 }
 
 // Implement Debug manually to hide the keystore_password field
@@ -50,6 +83,7 @@ impl fmt::Debug for ConstraintSigningOpts {
             .field("keystore_path", &self.keystore_path)
             .field("keystore_secrets_path", &self.keystore_secrets_path)
             .field("delegations_path", &self.delegations_path)
+            .field("delegations_submission_failure", &self.delegations_submission_failure)
             .finish()
     }
 }
@@ -0,0 +1,35 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::Parser;
+use serde::Deserialize;
+
+/// Options for refusing commitments on transactions interacting with denylisted addresses.
+#[derive(Debug, Clone, Default, Parser, Deserialize)]
+pub struct DenylistOpts {
+    /// Path to a file containing the denylisted addresses, one per line. Empty lines and
+    /// lines starting with `#` are ignored.
+    ///
+    /// Requests with a transaction whose sender, recipient or access list contains one of
+    /// these addresses are rejected.
+    #[clap(long = "denylist-path", env = "BOLT_SIDECAR_DENYLIST_PATH")]
+    pub path: Option<PathBuf>,
+    /// Interval in seconds between two reloads of the denylist file. If not provided,
+    /// the denylist is only loaded at startup.
+    #[clap(long = "denylist-reload-interval", env = "BOLT_SIDECAR_DENYLIST_RELOAD_INTERVAL")]
+    pub reload_interval_secs: Option<u64>,
+    /// Reject requests containing contract creation transactions.
+    #[clap(
+        long = "reject-contract-creation",
+        env = "BOLT_SIDECAR_REJECT_CONTRACT_CREATION",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub reject_contract_creation: bool,
+}
+
+impl DenylistOpts {
+    /// Returns the interval between two reloads of the denylist file, if any.
+    pub fn reload_interval(&self) -> Option<Duration> {
+        self.reload_interval_secs.map(Duration::from_secs)
+    }
+}
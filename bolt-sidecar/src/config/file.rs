@@ -0,0 +1,456 @@
+use std::{collections::HashSet, ffi::OsString, fmt::Write, fs, path::Path};
+
+use clap::{parser::ValueSource, ArgMatches, Args, Command, CommandFactory};
+use eyre::{bail, eyre, Context};
+use toml::{Table, Value};
+
+use super::{
+    api::ApiOpts, chain::ChainConfig, collateral::CollateralOpts,
+    constraint_signing::ConstraintSigningOpts, denylist::DenylistOpts, limits::LimitsOpts,
+    relay::RelayOpts, simulation::SimulationOpts, telemetry::TelemetryOpts, Opts,
+};
+
+/// The sections of the config file, named after the flattened option groups of [Opts].
+const SECTIONS: &[&str] = &[
+    "api",
+    "limits",
+    "collateral",
+    "denylist",
+    "simulation",
+    "relay",
+    "chain",
+    "constraint_signing",
+    "telemetry",
+];
+
+/// The options that can't be set from the config file.
+const FORBIDDEN_KEYS: &[&str] = &["config", "dry_run"];
+
+/// The options holding secrets, which are redacted from the effective configuration.
+const SECRET_KEYS: &[&str] = &[
+    "engine_jwt_hex",
+    "builder_private_key",
+    "commitment_private_key",
+    "constraint_private_key",
+    "commit_boost_jwt_hex",
+    "keystore_password",
+    "admin_token",
+];
+
+/// Placeholder for the redacted secrets in the effective configuration.
+const REDACTED: &str = "********";
+
+/// Returns the command with the options of the given config file section, if it exists.
+fn section_command(section: &str) -> Option<Command> {
+    let command = Command::new(section.to_string());
+    let command = match section {
+        "api" => ApiOpts::augment_args(command),
+        "limits" => LimitsOpts::augment_args(command),
+        "collateral" => CollateralOpts::augment_args(command),
+        "denylist" => DenylistOpts::augment_args(command),
+        "simulation" => SimulationOpts::augment_args(command),
+        "relay" => RelayOpts::augment_args(command),
+        "chain" => ChainConfig::augment_args(command),
+        "constraint_signing" => ConstraintSigningOpts::augment_args(command),
+        "telemetry" => TelemetryOpts::augment_args(command),
+        _ => return None,
+    };
+
+    Some(command)
+}
+
+/// An option set in the config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileOption {
+    /// The name of the option, i.e. its key in the file.
+    pub key: String,
+    /// The environment variable of the option.
+    pub env: String,
+    /// The values of the option, one per element for lists.
+    pub values: Vec<String>,
+}
+
+/// Load the TOML config file at the given path.
+pub fn load_config_file(path: &Path) -> eyre::Result<Vec<FileOption>> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read config file {}", path.display()))?;
+
+    parse_config_file(&contents).wrap_err_with(|| format!("Invalid config file {}", path.display()))
+}
+
+/// Parse the given TOML config file into the options it sets.
+///
+/// Top-level keys are the options of [Opts] itself, and sections the flattened option
+/// groups, e.g. `[limits]`. Unknown sections and keys are rejected.
+pub fn parse_config_file(contents: &str) -> eyre::Result<Vec<FileOption>> {
+    let table: Table = contents.parse()?;
+    let opts = Opts::command();
+
+    let mut options = Vec::new();
+    for (key, value) in table {
+        match value {
+            Value::Table(section) => {
+                let command = section_command(&key)
+                    .ok_or_else(|| eyre!("Unknown config file section: [{key}]"))?;
+
+                for (key, value) in section {
+                    options.push(file_option(&command, &key, &value)?);
+                }
+            }
+            value => {
+                // Options of the sections must be set in their section
+                if SECTIONS.iter().filter_map(|s| section_command(s)).any(|c| has_option(&c, &key))
+                {
+                    bail!("Option `{key}` must be set in its section");
+                }
+
+                options.push(file_option(&opts, &key, &value)?);
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+/// Returns true if the given command has an option with the given name.
+fn has_option(command: &Command, key: &str) -> bool {
+    command.get_arguments().any(|arg| arg.get_id() == key)
+}
+
+/// Returns the option of the given command with the given name and value.
+fn file_option(command: &Command, key: &str, value: &Value) -> eyre::Result<FileOption> {
+    if FORBIDDEN_KEYS.contains(&key) {
+        bail!("Option `{key}` can't be set from the config file");
+    }
+
+    let arg = command
+        .get_arguments()
+        .find(|arg| arg.get_id() == key)
+        .ok_or_else(|| eyre!("Unknown config file option: `{key}`"))?;
+
+    let env = arg
+        .get_env()
+        .and_then(|env| env.to_str())
+        .ok_or_else(|| eyre!("Option `{key}` can't be set from the config file"))?;
+
+    let values = match value {
+        Value::Array(values) => values
+            .iter()
+            .map(|value| scalar_to_string(key, value))
+            .collect::<eyre::Result<Vec<_>>>()?,
+        value => vec![scalar_to_string(key, value)?],
+    };
+
+    Ok(FileOption { key: key.to_string(), env: env.to_string(), values })
+}
+
+/// Merge the options of the config file into the given command-line arguments, and return
+/// them with the names of the options taken from the file.
+///
+/// The options are inserted as CLI flags, so that they satisfy the required options and
+/// groups of the command. Options already set by a CLI flag or an environment variable are
+/// skipped, since these take precedence over the file.
+pub fn merge_config_file(
+    command: &Command,
+    args: Vec<OsString>,
+    options: &[FileOption],
+) -> (Vec<OsString>, HashSet<String>) {
+    // Errors are reported by the actual parsing, this only resolves the sources of the
+    // values. It only fails for `--help` and `--version`, which don't need the file.
+    let Ok(matches) = command.clone().ignore_errors(true).try_get_matches_from(args.iter()) else {
+        return (args, HashSet::new());
+    };
+
+    let mut file_args = Vec::new();
+    let mut from_file = HashSet::new();
+    for option in options {
+        let Some(arg) = command.get_arguments().find(|arg| arg.get_id() == option.key.as_str())
+        else {
+            continue;
+        };
+        let Some(long) = arg.get_long() else { continue };
+
+        if matches!(
+            matches.value_source(&option.key),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+
+        if !arg.get_action().takes_values() {
+            // Boolean flags are only set when enabled
+            if option.values.iter().any(|value| value == "true") {
+                file_args.push(OsString::from(format!("--{long}")));
+                from_file.insert(option.key.clone());
+            }
+            continue;
+        }
+
+        match arg.get_value_delimiter() {
+            Some(delimiter) => {
+                let value = option.values.join(&delimiter.to_string());
+                file_args.push(OsString::from(format!("--{long}={value}")));
+            }
+            None => {
+                for value in &option.values {
+                    file_args.push(OsString::from(format!("--{long}={value}")));
+                }
+            }
+        }
+        from_file.insert(option.key.clone());
+    }
+
+    let mut args = args.into_iter();
+    let merged = args.next().into_iter().chain(file_args).chain(args).collect();
+
+    (merged, from_file)
+}
+
+/// Returns the string representation of a scalar TOML value, as it would be provided
+/// through the environment.
+fn scalar_to_string(key: &str, value: &Value) -> eyre::Result<String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Integer(value) => Ok(value.to_string()),
+        Value::Float(value) => Ok(value.to_string()),
+        Value::Boolean(value) => Ok(value.to_string()),
+        _ => bail!("Invalid value for option `{key}`: {value}"),
+    }
+}
+
+/// Render the effective configuration resolved from the CLI flags, environment variables
+/// and config file as a TOML config file, with the secrets redacted.
+///
+/// `from_file` holds the options taken from the config file, as returned by
+/// [merge_config_file]. Options that aren't set and have no default value are omitted.
+pub fn effective_config(matches: &ArgMatches, from_file: &HashSet<String>) -> String {
+    let opts = Opts::command();
+    let sections = SECTIONS.iter().filter_map(|s| section_command(s).map(|c| (*s, c)));
+
+    let mut out = String::new();
+    render_options(&mut out, &opts, matches, from_file, |key| {
+        !SECTIONS.iter().filter_map(|s| section_command(s)).any(|c| has_option(&c, key))
+    });
+
+    for (section, command) in sections {
+        let _ = writeln!(out, "\n[{section}]");
+        render_options(&mut out, &command, matches, from_file, |_| true);
+    }
+
+    out
+}
+
+/// Render the options of the given command that are set in the matches and accepted by
+/// the filter, as `key = value` lines.
+fn render_options(
+    out: &mut String,
+    command: &Command,
+    matches: &ArgMatches,
+    from_file: &HashSet<String>,
+    filter: impl Fn(&str) -> bool,
+) {
+    for arg in command.get_arguments() {
+        let key = arg.get_id().as_str();
+        if FORBIDDEN_KEYS.contains(&key) || arg.get_env().is_none() || !filter(key) {
+            continue;
+        }
+
+        let Some(raw) = matches.try_get_raw(key).ok().flatten() else { continue };
+        let values =
+            raw.map(|v| v.to_string_lossy().into_owned()).map(Value::String).collect::<Vec<_>>();
+
+        let value = if SECRET_KEYS.contains(&key) {
+            Value::String(REDACTED.to_string())
+        } else if arg.get_value_delimiter().is_some() {
+            Value::Array(values)
+        } else {
+            match values.into_iter().next() {
+                Some(value) => value,
+                None => continue,
+            }
+        };
+
+        let source = match matches.value_source(key) {
+            Some(ValueSource::CommandLine) if from_file.contains(key) => "file",
+            Some(ValueSource::CommandLine) => "cli",
+            Some(ValueSource::EnvVariable) => "env",
+            _ => "default",
+        };
+
+        let _ = writeln!(out, "{key} = {value} # {source}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig};
+
+    /// Sets the given environment variables, and restores their previous values on drop.
+    struct EnvGuard(Vec<(&'static str, Option<OsString>)>);
+
+    impl EnvGuard {
+        fn set(vars: &[(&'static str, &str)]) -> Self {
+            let previous = vars.iter().map(|(key, _)| (*key, std::env::var_os(key))).collect();
+            for (key, value) in vars {
+                std::env::set_var(key, value);
+            }
+            Self(previous)
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            for (key, value) in &self.0 {
+                match value {
+                    Some(value) => std::env::set_var(key, value),
+                    None => std::env::remove_var(key),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_config_file() {
+        let contents = r#"
+            port = 9000
+            execution_api_fallback_urls = ["http://a:8545", "http://b:8545"]
+
+            [limits]
+            max_commitments_per_slot = 64
+
+            [chain]
+            chain = "holesky"
+            require_replay_protection = true
+        "#;
+
+        let options = parse_config_file(contents).unwrap();
+        let option = |key: &str| options.iter().find(|o| o.key == key).unwrap();
+
+        assert_eq!(options.len(), 5);
+        assert_eq!(option("port").env, "BOLT_SIDECAR_PORT");
+        assert_eq!(option("port").values, ["9000"]);
+        assert_eq!(
+            option("execution_api_fallback_urls").values,
+            ["http://a:8545", "http://b:8545"]
+        );
+        assert_eq!(option("max_commitments_per_slot").env, "BOLT_SIDECAR_MAX_COMMITMENTS");
+        assert_eq!(option("chain").values, ["holesky"]);
+        assert_eq!(option("require_replay_protection").values, ["true"]);
+    }
+
+    #[test]
+    fn test_invalid_config_files() {
+        // Typos in keys and sections
+        assert!(parse_config_file("prot = 9000").is_err());
+        assert!(parse_config_file("[limits]\nmax_commitment_per_slot = 64").is_err());
+        assert!(parse_config_file("[limit]\nmax_commitments_per_slot = 64").is_err());
+
+        // Options out of their section
+        assert!(parse_config_file("max_commitments_per_slot = 64").is_err());
+        assert!(parse_config_file("[chain]\nmax_commitments_per_slot = 64").is_err());
+
+        // Invalid values and options
+        assert!(parse_config_file("port = { value = 9000 }").is_err());
+        assert!(parse_config_file("config = \"bolt.toml\"").is_err());
+        assert!(parse_config_file("port = ").is_err());
+    }
+
+    #[test]
+    fn test_config_file_precedence() {
+        use clap::Parser;
+
+        let options = parse_config_file(
+            "[telemetry]\naudit_log_max_size = 1\naudit_log_max_files = 2\nhealth_check_interval_secs = 3",
+        )
+        .unwrap();
+
+        // Environment variables take precedence over the config file
+        let _env = EnvGuard::set(&[("BOLT_SIDECAR_AUDIT_LOG_MAX_FILES", "20")]);
+
+        // And CLI flags take precedence over both
+        let args = ["telemetry", "--health-check-interval-secs", "30"].map(OsString::from);
+        let (args, from_file) =
+            merge_config_file(&TelemetryOpts::command(), args.to_vec(), &options);
+        let opts = TelemetryOpts::try_parse_from(args).unwrap();
+
+        assert_eq!(opts.audit_log_max_size, 1);
+        assert_eq!(opts.audit_log_max_files, 20);
+        assert_eq!(opts.health_check_interval_secs, 30);
+        assert_eq!(from_file, HashSet::from(["audit_log_max_size".to_string()]));
+    }
+
+    #[test]
+    fn test_config_file_satisfies_required_options() {
+        use clap::FromArgMatches;
+
+        let fee_recipient = "0x0000000000000000000000000000000000000001";
+        let options = parse_config_file(&format!(
+            "fee_recipient = \"{fee_recipient}\"\n\n[constraint_signing]\nconstraint_private_key = \"{}\"",
+            BlsSecretKeyWrapper::random()
+        ))
+        .unwrap();
+
+        let args = [
+            "bolt-sidecar".to_string(),
+            "--engine-jwt-hex".to_string(),
+            JwtSecretConfig::default().to_string(),
+            "--builder-private-key".to_string(),
+            BlsSecretKeyWrapper::random().to_string(),
+            "--commitment-private-key".to_string(),
+            EcdsaSecretKeyWrapper::random().to_string(),
+        ]
+        .map(OsString::from);
+
+        // The required fee recipient and signing group are only set in the config file
+        let (args, from_file) = merge_config_file(&Opts::command(), args.to_vec(), &options);
+        let matches = Opts::command().try_get_matches_from(args).unwrap();
+        let opts = Opts::from_arg_matches(&matches).unwrap();
+
+        assert_eq!(
+            opts.fee_recipient,
+            fee_recipient.parse::<alloy::primitives::Address>().unwrap()
+        );
+        assert!(opts.constraint_signing.constraint_private_key.is_some());
+
+        let config = effective_config(&matches, &from_file);
+        assert!(config.contains(&format!("fee_recipient = \"{fee_recipient}\" # file")));
+        assert!(config.contains("engine_jwt_hex = \"********\" # cli"));
+    }
+
+    #[test]
+    fn test_effective_config_redacts_secrets() {
+        let builder_key = BlsSecretKeyWrapper::random().to_string();
+        let constraint_key = BlsSecretKeyWrapper::random().to_string();
+        let commitment_key = EcdsaSecretKeyWrapper::random().to_string();
+        let jwt = JwtSecretConfig::default().to_string();
+
+        let matches = Opts::command()
+            .try_get_matches_from([
+                "bolt-sidecar",
+                "--port",
+                "9000",
+                "--engine-jwt-hex",
+                &jwt,
+                "--fee-recipient",
+                "0x0000000000000000000000000000000000000000",
+                "--builder-private-key",
+                &builder_key,
+                "--commitment-private-key",
+                &commitment_key,
+                "--constraint-private-key",
+                &constraint_key,
+            ])
+            .unwrap();
+
+        let config = effective_config(&matches, &HashSet::new());
+
+        assert!(config.contains("port = \"9000\" # cli"));
+        assert!(config.contains("builder_private_key = \"********\""));
+        assert!(config.contains("commitment_private_key = \"********\""));
+        assert!(config.contains("[constraint_signing]\nconstraint_private_key = \"********\""));
+        for secret in [&builder_key, &constraint_key, &commitment_key, &jwt] {
+            assert!(!config.contains(secret.trim_start_matches("0x")));
+        }
+    }
+}
@@ -2,15 +2,31 @@ use std::num::NonZero;
 
 use clap::Parser;
 
+use crate::primitives::MAX_CONSTRAINT_PROOFS;
+
 /// Default max commitments to accept per block.
 pub const DEFAULT_MAX_COMMITMENTS: usize = 128;
 
 /// Default max committed gas per block.
 pub const DEFAULT_MAX_COMMITTED_GAS: u64 = 10_000_000;
 
+/// Default max constrained transactions per block, as many as can be proven to the relay.
+pub const DEFAULT_MAX_CONSTRAINTS: usize = MAX_CONSTRAINT_PROOFS;
+
+/// Default max transactions to accept at the top of the block per slot. Relays only accept a
+/// single top-of-block bundle per slot.
+pub const DEFAULT_MAX_TOP_OF_BLOCK_TXS: usize = 1;
+
+/// Default max gas to accept at the top of the block per slot.
+pub const DEFAULT_MAX_TOP_OF_BLOCK_GAS: u64 = 1_000_000;
+
 /// Default min priority fee to accept for a commitment.
 pub const DEFAULT_MIN_PRIORITY_FEE: u128 = 1_000_000_000; // 1 Gwei
 
+/// Max size in bytes of a transaction, same as the default txpool limit of geth and reth.
+/// Operators can only lower it.
+pub const MAX_TX_SIZE: usize = 128 * 1024;
+
 /// Limits for the sidecar.
 #[cfg_attr(test, derive(PartialEq))]
 #[derive(Debug, Parser, Clone, Copy, serde::Serialize, serde::Deserialize)]
@@ -29,6 +45,28 @@ pub struct LimitsOpts {
         default_value_t = LimitsOpts::default().max_committed_gas_per_slot
     )]
     pub max_committed_gas_per_slot: NonZero<u64>,
+    /// Max number of constrained transactions to accept per block, across all commitments
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_MAX_CONSTRAINTS_PER_SLOT",
+        default_value_t = LimitsOpts::default().max_constraints_per_slot
+    )]
+    pub max_constraints_per_slot: NonZero<usize>,
+    /// Max number of transactions to accept at the top of the block per slot, across all
+    /// commitments. Zero disables top-of-block requests
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_MAX_TOP_OF_BLOCK_TXS",
+        default_value_t = LimitsOpts::default().max_top_of_block_txs_per_slot
+    )]
+    pub max_top_of_block_txs_per_slot: usize,
+    /// Max gas to accept at the top of the block per slot, across all commitments
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_MAX_TOP_OF_BLOCK_GAS",
+        default_value_t = LimitsOpts::default().max_top_of_block_gas_per_slot
+    )]
+    pub max_top_of_block_gas_per_slot: u64,
     /// Min priority fee to accept for a commitment
     #[clap(
         long,
@@ -36,6 +74,22 @@ pub struct LimitsOpts {
         default_value_t = LimitsOpts::default().min_priority_fee
     )]
     pub min_priority_fee: u128,
+    /// Max size in bytes of a single transaction. Can't be higher than the default of 128 KiB
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_MAX_TX_SIZE",
+        default_value_t = LimitsOpts::default().max_tx_size,
+        value_parser = parse_max_tx_size
+    )]
+    pub max_tx_size: NonZero<usize>,
+    /// Max gas limit of a single transaction. Unlimited by default
+    #[clap(long, env = "BOLT_SIDECAR_MAX_GAS_PER_TX")]
+    #[serde(default)]
+    pub max_gas_per_tx: Option<NonZero<u64>>,
+    /// Max size in bytes of the calldata of a single transaction. Unlimited by default
+    #[clap(long, env = "BOLT_SIDECAR_MAX_CALLDATA_BYTES")]
+    #[serde(default)]
+    pub max_calldata_bytes: Option<NonZero<usize>>,
 }
 
 impl Default for LimitsOpts {
@@ -45,7 +99,37 @@ impl Default for LimitsOpts {
                 .expect("Valid non-zero"),
             max_committed_gas_per_slot: NonZero::new(DEFAULT_MAX_COMMITTED_GAS)
                 .expect("Valid non-zero"),
+            max_constraints_per_slot: NonZero::new(DEFAULT_MAX_CONSTRAINTS)
+                .expect("Valid non-zero"),
+            max_top_of_block_txs_per_slot: DEFAULT_MAX_TOP_OF_BLOCK_TXS,
+            max_top_of_block_gas_per_slot: DEFAULT_MAX_TOP_OF_BLOCK_GAS,
             min_priority_fee: DEFAULT_MIN_PRIORITY_FEE,
+            max_tx_size: NonZero::new(MAX_TX_SIZE).expect("Valid non-zero"),
+            max_gas_per_tx: None,
+            max_calldata_bytes: None,
         }
     }
 }
+
+/// Parse the max transaction size, which can only be lower than [MAX_TX_SIZE].
+fn parse_max_tx_size(s: &str) -> Result<NonZero<usize>, String> {
+    let size = s.parse::<NonZero<usize>>().map_err(|e| e.to_string())?;
+    if size.get() > MAX_TX_SIZE {
+        return Err(format!("max tx size can't be higher than {MAX_TX_SIZE} bytes"));
+    }
+
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_max_tx_size() {
+        assert_eq!(parse_max_tx_size("1024").unwrap().get(), 1024);
+        assert_eq!(parse_max_tx_size("131072").unwrap().get(), MAX_TX_SIZE);
+        assert!(parse_max_tx_size("131073").is_err());
+        assert!(parse_max_tx_size("0").is_err());
+    }
+}
@@ -1,18 +1,38 @@
-use std::env;
+use std::{collections::HashSet, env, ffi::OsString, path::PathBuf};
 
 use alloy::primitives::Address;
-use clap::Parser;
-use eyre::bail;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use eyre::{bail, eyre};
 use reqwest::Url;
 use serde::Deserialize;
 
+/// Commitments API server options.
+pub mod api;
+use api::ApiOpts;
+
 /// Chain configuration options.
 pub mod chain;
-pub use chain::ChainConfig;
+pub use chain::{ChainConfig, DeadlinePolicyKind};
+
+/// Collateral gating options for commitments.
+pub mod collateral;
+use collateral::CollateralOpts;
+
+/// Address denylist options for commitments.
+pub mod denylist;
+use denylist::DenylistOpts;
 
 /// Commitment and constraint signing related options.
 pub mod constraint_signing;
-pub use constraint_signing::ConstraintSigningOpts;
+pub use constraint_signing::{CommitmentSignerKind, ConstraintSigningOpts, SubmissionFailureMode};
+
+/// Transaction simulation options for commitments.
+pub mod simulation;
+use simulation::SimulationOpts;
+
+/// Relay request timeouts and circuit breaker options.
+pub mod relay;
+pub use relay::RelayOpts;
 
 /// Telemetry and metrics related options.
 pub mod telemetry;
@@ -21,9 +41,16 @@ use telemetry::TelemetryOpts;
 /// Operating limits for commitments and constraints.
 pub mod limits;
 use limits::LimitsOpts;
+
+/// TOML configuration file support.
+pub mod file;
 use tracing::debug;
 
-use crate::common::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig};
+use crate::{
+    common::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig},
+    primitives::ConstraintsDigestVersion,
+    state::archive::DEFAULT_RETENTION_EPOCHS,
+};
 
 /// Default port for the JSON-RPC server exposed by the sidecar supporting the Commitments API.
 ///
@@ -33,9 +60,44 @@ pub const DEFAULT_RPC_PORT: u16 = 8017;
 /// Default port for the Constraints proxy server, binded to the default port used by MEV-Boost.
 pub const DEFAULT_CONSTRAINTS_PROXY_PORT: u16 = 18550;
 
+/// Default interval in seconds between two verifications of the validators in the BoltManager
+/// contract (one epoch).
+pub const DEFAULT_VALIDATORS_VERIFICATION_INTERVAL_SECS: u64 = 384;
+
+/// Default deadline in seconds for the beacon node to become reachable at startup.
+pub const DEFAULT_BEACON_STARTUP_TIMEOUT_SECS: u64 = 300;
+
+/// Default deadline in milliseconds for answering `getHeader` requests of the Beacon client,
+/// which usually waits about one second for them.
+pub const DEFAULT_BUILDER_GET_HEADER_TIMEOUT_MS: u64 = 950;
+
+/// Default deadline in milliseconds for answering `getPayload` requests of the Beacon client.
+pub const DEFAULT_BUILDER_GET_PAYLOAD_TIMEOUT_MS: u64 = 3_000;
+
+/// Default deadline in milliseconds for forwarding validator registrations to the relays.
+pub const DEFAULT_BUILDER_REGISTER_VALIDATOR_TIMEOUT_MS: u64 = 3_000;
+
 /// Command-line options for the Bolt sidecar
 #[derive(Debug, Parser, Deserialize)]
 pub struct Opts {
+    /// Path to a TOML config file to load the options from. Top-level keys are the options
+    /// below, and sections group the options of each category, e.g. `[limits]`.
+    ///
+    /// CLI flags and environment variables take precedence over the values in the file, which
+    /// take precedence over the `.env` file.
+    #[clap(long, env = "BOLT_SIDECAR_CONFIG")]
+    #[serde(default)]
+    pub config: Option<PathBuf>,
+    /// Parse and validate the configuration, print the effective options with the secrets
+    /// redacted, and exit.
+    #[clap(long, default_value_t = false)]
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Skip the startup checks of the upstream endpoints and of the signers, which abort the
+    /// startup listing every misconfiguration found.
+    #[clap(long, env = "BOLT_SIDECAR_SKIP_PREFLIGHT", default_value_t = false)]
+    #[serde(default)]
+    pub skip_preflight: bool,
     /// Port to listen on for incoming JSON-RPC requests of the Commitments API.
     /// This port should be open on your firewall in order to receive external requests!
     #[clap(long, env = "BOLT_SIDECAR_PORT", default_value_t = DEFAULT_RPC_PORT)]
@@ -43,6 +105,13 @@ pub struct Opts {
     /// Execution client API URL
     #[clap(long, env = "BOLT_SIDECAR_EXECUTION_API_URL", default_value = "http://localhost:8545")]
     pub execution_api_url: Url,
+    /// Comma-separated list of fallback execution client API URLs, in order of preference.
+    ///
+    /// State requests fail over to these endpoints when the primary one is unreachable
+    /// or too slow, and fail back to it as soon as it recovers.
+    #[clap(long, env = "BOLT_SIDECAR_EXECUTION_API_FALLBACK_URLS", value_delimiter = ',')]
+    #[serde(default)]
+    pub execution_api_fallback_urls: Vec<Url>,
     /// URL for the beacon client
     #[clap(long, env = "BOLT_SIDECAR_BEACON_API_URL", default_value = "http://localhost:5052")]
     pub beacon_api_url: Url,
@@ -58,6 +127,43 @@ pub struct Opts {
         default_value = "http://localhost:18551"
     )]
     pub constraints_api_url: Url,
+    /// Submit constraints to the Constraints API as SSZ instead of JSON. Relays that don't
+    /// support it yet answer with 415 Unsupported Media Type, after which JSON is used.
+    #[clap(long, env = "BOLT_SIDECAR_CONSTRAINTS_API_SSZ", default_value_t = false)]
+    #[serde(default)]
+    pub constraints_api_ssz: bool,
+    /// Drop the constraints whose senders broadcast conflicting transactions or spent their
+    /// balance after the commitment, before the commitment deadline of their slot. Otherwise
+    /// they are only reported as at risk.
+    #[clap(long, env = "BOLT_SIDECAR_DROP_AT_RISK_CONSTRAINTS", default_value_t = false)]
+    #[serde(default)]
+    pub drop_at_risk_constraints: bool,
+    /// Hold the requests rejected only because their nonce is too high for a short while,
+    /// until a request with the earlier nonces of the same sender is committed for the slot or
+    /// the head advances. They are rejected if that doesn't happen before the commitment deadline.
+    #[clap(long, env = "BOLT_SIDECAR_QUEUE_NONCE_GAPS", default_value_t = false)]
+    #[serde(default)]
+    pub queue_nonce_gaps: bool,
+    /// The version of the digest signed in the constraints, which must match the one the relay
+    /// verifies. `legacy` is only meant for relays that don't support `v1` yet.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CONSTRAINTS_DIGEST_VERSION",
+        default_value = "v1",
+        value_enum
+    )]
+    #[serde(default)]
+    pub constraints_digest_version: ConstraintsDigestVersion,
+    /// Merge the constraints of a slot sharing the same pubkey into a single message, signed
+    /// again before submitting them to the relay. The commitments keep the original constraints.
+    #[clap(long, env = "BOLT_SIDECAR_MERGE_CONSTRAINTS", default_value_t = false)]
+    #[serde(default)]
+    pub merge_constraints: bool,
+    /// Additional relays to forward the validator registrations received from the Beacon client
+    /// to, besides the Constraints API.
+    #[clap(long, env = "BOLT_SIDECAR_REGISTRATION_RELAY_URLS", value_delimiter = ',')]
+    #[serde(default)]
+    pub registration_relay_urls: Vec<Url>,
     /// The port from which the Bolt sidecar will receive Builder-API requests from the
     /// Beacon client
     #[clap(
@@ -66,6 +172,29 @@ pub struct Opts {
         default_value_t = DEFAULT_CONSTRAINTS_PROXY_PORT
     )]
     pub constraints_proxy_port: u16,
+    /// Deadline in milliseconds for answering `getHeader` requests of the Beacon client. Part of
+    /// it is kept to fetch the locally built block if the relay is too slow.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_BUILDER_GET_HEADER_TIMEOUT",
+        default_value_t = DEFAULT_BUILDER_GET_HEADER_TIMEOUT_MS
+    )]
+    pub builder_get_header_timeout: u64,
+    /// Deadline in milliseconds for answering `getPayload` requests of the Beacon client.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_BUILDER_GET_PAYLOAD_TIMEOUT",
+        default_value_t = DEFAULT_BUILDER_GET_PAYLOAD_TIMEOUT_MS
+    )]
+    pub builder_get_payload_timeout: u64,
+    /// Deadline in milliseconds for forwarding the validator registrations received from the
+    /// Beacon client to each relay.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_BUILDER_REGISTER_VALIDATOR_TIMEOUT",
+        default_value_t = DEFAULT_BUILDER_REGISTER_VALIDATOR_TIMEOUT_MS
+    )]
+    pub builder_register_validator_timeout: u64,
     /// The JWT secret token to authenticate calls to the engine API.
     ///
     /// It can either be a hex-encoded string or a file path to a file
@@ -78,10 +207,39 @@ pub struct Opts {
     /// Secret BLS key to sign fallback payloads with
     #[clap(long, env = "BOLT_SIDECAR_BUILDER_PRIVATE_KEY")]
     pub builder_private_key: BlsSecretKeyWrapper,
-    /// Secret ECDSA key to sign commitment messages with. The public key associated to it must be
-    /// then used when registering the operator in the `BoltManager` contract.
+    /// The backend of the ECDSA key signing the commitment messages. The address of the key
+    /// must be then used when registering the operator in the `BoltManager` contract.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_COMMITMENT_SIGNER",
+        default_value = "private-key",
+        value_enum
+    )]
+    #[serde(default)]
+    pub commitment_signer: CommitmentSignerKind,
+    /// Secret ECDSA key to sign commitment messages with, required by the `private-key`
+    /// commitment signer.
     #[clap(long, env = "BOLT_SIDECAR_COMMITMENT_PRIVATE_KEY")]
-    pub commitment_private_key: EcdsaSecretKeyWrapper,
+    pub commitment_private_key: Option<EcdsaSecretKeyWrapper>,
+    /// The ID or ARN of the AWS KMS key to sign commitment messages with, required by the `kms`
+    /// commitment signer.
+    #[clap(long, env = "BOLT_SIDECAR_COMMITMENT_KMS_KEY_ID")]
+    pub commitment_kms_key_id: Option<String>,
+    /// The URL of the Web3Signer instance holding the commitment key, required by the
+    /// `web3signer` commitment signer.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_COMMITMENT_WEB3SIGNER_URL",
+        requires("commitment_web3signer_public_key")
+    )]
+    pub commitment_web3signer_url: Option<Url>,
+    /// The hex-encoded secp256k1 public key of the commitment key in Web3Signer.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_COMMITMENT_WEB3SIGNER_PUBLIC_KEY",
+        requires("commitment_web3signer_url")
+    )]
+    pub commitment_web3signer_public_key: Option<String>,
     /// Unsafely disables consensus checks when validating commitments.
     ///
     /// If enabled, the sidecar will sign every commitment request with the first private key
@@ -91,9 +249,72 @@ pub struct Opts {
     /// Unsafely disables on-chain checks of validators and operator when starting the sidecar
     #[clap(long, env = "BOLT_SIDECAR_UNSAFE_DISABLE_ONCHAIN_CHECKS", default_value_t = false)]
     pub unsafe_disable_onchain_checks: bool,
+    /// Start the sidecar even if some validators can't be verified in the BoltManager contract.
+    /// They are excluded from commitments until they are verified, instead of aborting startup.
+    #[clap(long, env = "BOLT_SIDECAR_ALLOW_UNVERIFIED_KEYS", default_value_t = false)]
+    #[serde(default)]
+    pub allow_unverified_keys: bool,
+    /// Respond to inclusion requests with legacy (v1) commitments, which only sign the request,
+    /// for clients that don't support the v2 format including the signed constraints yet.
+    #[clap(long, env = "BOLT_SIDECAR_LEGACY_COMMITMENT_FORMAT", default_value_t = false)]
+    #[serde(default)]
+    pub legacy_commitment_format: bool,
+    /// Directory in which the sidecar persists its data, such as the archive of the signed
+    /// commitments and the sealed slots. If not provided, nothing is persisted.
+    #[clap(long, env = "BOLT_SIDECAR_DATA_DIR")]
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// Number of epochs the signed commitments are kept in full in the archive of the data
+    /// directory, to settle disputes about allegedly broken commitments. Older commitments are
+    /// aggregated into daily summaries and deleted.
+    #[clap(
+        long,
+        alias = "commitment-archive-retention",
+        env = "BOLT_SIDECAR_RETENTION_EPOCHS",
+        default_value_t = DEFAULT_RETENTION_EPOCHS
+    )]
+    pub retention_epochs: u64,
+    /// Interval in seconds between two verifications of the validators in the BoltManager
+    /// contract. Requests for the slots of validators that are no longer registered and active
+    /// are rejected.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_VALIDATORS_VERIFICATION_INTERVAL",
+        default_value_t = DEFAULT_VALIDATORS_VERIFICATION_INTERVAL_SECS
+    )]
+    pub validators_verification_interval: u64,
+    /// Indexes of validators to exclude from the validators the sidecar acts for, i.e. the
+    /// ones of the delegations file or of the constraint signer. Requests for their slots are
+    /// rejected. Resolved to their public keys with the beacon node at startup.
+    #[clap(long, env = "BOLT_SIDECAR_EXCLUDE_VALIDATOR_INDEXES", value_delimiter = ',')]
+    pub exclude_validator_indexes: Vec<u64>,
+    /// Deadline in seconds for the beacon node to become reachable at startup. Until then, the
+    /// sidecar waits for it with the Commitments API answering that the beacon node is
+    /// unavailable, and aborts if the deadline passes. Set to 0 to fail right away.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_BEACON_STARTUP_TIMEOUT",
+        default_value_t = DEFAULT_BEACON_STARTUP_TIMEOUT_SECS
+    )]
+    pub beacon_startup_timeout: u64,
+    /// Commitments API server options
+    #[clap(flatten)]
+    pub api: ApiOpts,
     /// Operating limits for the sidecar
     #[clap(flatten)]
     pub limits: LimitsOpts,
+    /// Collateral gating options
+    #[clap(flatten)]
+    pub collateral: CollateralOpts,
+    /// Address denylist options
+    #[clap(flatten)]
+    pub denylist: DenylistOpts,
+    /// Transaction simulation options
+    #[clap(flatten)]
+    pub simulation: SimulationOpts,
+    /// Relay request options
+    #[clap(flatten)]
+    pub relay: RelayOpts,
     /// Chain config for the chain on which the sidecar is running
     #[clap(flatten)]
     pub chain: ChainConfig,
@@ -113,26 +334,89 @@ pub struct Opts {
 }
 
 impl Opts {
-    /// Parse the command-line arguments into the `Opts` struct, using
-    /// environment variables as fallback values when not provided via CLI.
+    /// Parse the command-line arguments into the `Opts` struct, using environment variables,
+    /// the config file and then the `.env` file as fallback values when not provided via CLI.
+    ///
+    /// If `--dry-run` is set, the effective configuration is printed and the process exits.
     pub fn try_parse() -> eyre::Result<Self> {
-        read_env_file()?;
+        remove_empty_envs()?;
+        let env_file = read_env_file()?;
+
+        let args = env::args_os().collect::<Vec<_>>();
+        let options = match config_file_path(&args, &env_file) {
+            Some(path) => file::load_config_file(&path)?,
+            None => Vec::new(),
+        };
+
+        // The config file is merged before loading the `.env` file, so that it only takes
+        // precedence over the latter, not over the actual environment.
+        let (args, from_file) = file::merge_config_file(&Opts::command(), args, &options);
+        let file_envs = options.iter().map(|option| option.env.as_str()).collect::<HashSet<_>>();
+        load_env_file(env_file, &file_envs);
+
+        let matches = Opts::command().get_matches_from(args);
+        let opts = Opts::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
 
-        Ok(Opts::parse())
+        if opts.dry_run {
+            println!("{}", file::effective_config(&matches, &from_file));
+            std::process::exit(0);
+        }
+
+        Ok(opts)
     }
 }
 
-/// Reads the `.env` file and loads the environment variables into the process.
-fn read_env_file() -> eyre::Result<()> {
-    match dotenvy::dotenv() {
+/// Returns the path of the config file from the `--config` flag or its environment variable,
+/// which can also be set in the `.env` file.
+///
+/// This has to be resolved before parsing the options, since the file provides their
+/// fallback values.
+fn config_file_path(args: &[OsString], env_file: &[(String, String)]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    env::var_os("BOLT_SIDECAR_CONFIG").map(PathBuf::from).or_else(|| {
+        env_file.iter().find(|(key, _)| key == "BOLT_SIDECAR_CONFIG").map(|(_, path)| path.into())
+    })
+}
+
+/// Reads the variables of the `.env` file, without loading them into the process yet.
+fn read_env_file() -> eyre::Result<Vec<(String, String)>> {
+    match dotenvy::dotenv_iter() {
         // It means the .env file hasn't been found but it's okay since it's optional
-        Err(dotenvy::Error::Io(_)) => (),
+        Err(dotenvy::Error::Io(_)) => Ok(Vec::new()),
         Err(err) => bail!("Failed to load .env file: {:?}", err),
-        Ok(path) => println!("Loaded environment variables from path: {:?}", path),
-    };
+        Ok(vars) => {
+            let vars = vars
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| eyre!("Failed to load .env file: {:?}", err))?;
+            println!("Loaded environment variables from the .env file");
+            Ok(vars)
+        }
+    }
+}
 
-    remove_empty_envs()?;
-    Ok(())
+/// Loads the variables of the `.env` file into the process, except the ones that are already
+/// set or set by the config file, which both take precedence over it. Empty values are
+/// skipped, like the ones removed by [remove_empty_envs].
+fn load_env_file(vars: Vec<(String, String)>, file_envs: &HashSet<&str>) {
+    for (key, value) in vars {
+        if value.trim().is_empty() ||
+            file_envs.contains(key.as_str()) ||
+            env::var_os(&key).is_some()
+        {
+            continue;
+        }
+
+        env::set_var(key, value);
+    }
 }
 
 /// Removes environment variables that are set as empty strings, i.e. like `MY_VAR=`. This is
@@ -165,6 +449,28 @@ mod tests {
         println!("{:#?}", opts);
     }
 
+    #[test]
+    fn test_env_file_precedence() {
+        let (set, from_file, from_env_file) = (
+            "BOLT_SIDECAR_TEST_ENV_FILE_SET",
+            "BOLT_SIDECAR_TEST_ENV_FILE_CONFIG",
+            "BOLT_SIDECAR_TEST_ENV_FILE_LOADED",
+        );
+        env::set_var(set, "env");
+
+        let vars =
+            [set, from_file, from_env_file].map(|key| (key.to_string(), "dotenv".to_string()));
+        load_env_file(vars.to_vec(), &HashSet::from([from_file]));
+
+        // Only the variables set neither in the environment nor the config file are loaded
+        assert_eq!(env::var(set).unwrap(), "env");
+        assert!(env::var_os(from_file).is_none());
+        assert_eq!(env::var(from_env_file).unwrap(), "dotenv");
+
+        env::remove_var(set);
+        env::remove_var(from_env_file);
+    }
+
     #[test]
     fn test_validate_cli_flags() {
         use clap::CommandFactory;
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use clap::Parser;
+use serde::Deserialize;
+
+/// Default time budget in milliseconds for submitting constraints to the relay.
+pub const DEFAULT_SUBMIT_TIMEOUT_MS: u64 = 1_000;
+
+/// Default time budget in milliseconds for fetching a header from the relay.
+pub const DEFAULT_GET_HEADER_TIMEOUT_MS: u64 = 500;
+
+/// Default time budget in milliseconds for fetching a payload from the relay.
+pub const DEFAULT_GET_PAYLOAD_TIMEOUT_MS: u64 = 2_000;
+
+/// Default number of consecutive failures after which requests to the relay are skipped.
+pub const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+
+/// Default cooldown in milliseconds before requests to a failing relay are attempted again,
+/// one slot.
+pub const DEFAULT_BREAKER_COOLDOWN_MS: u64 = 12_000;
+
+/// Options for the requests sent to the relay through the Constraints API.
+#[derive(Debug, Clone, Copy, Parser, Deserialize)]
+pub struct RelayOpts {
+    /// Time budget in milliseconds for a single constraints submission to the relay.
+    #[clap(
+        long = "relay-submit-timeout",
+        env = "BOLT_SIDECAR_RELAY_SUBMIT_TIMEOUT",
+        default_value_t = DEFAULT_SUBMIT_TIMEOUT_MS
+    )]
+    pub submit_timeout_ms: u64,
+    /// Time budget in milliseconds for fetching a header from the relay. Past it, the locally
+    /// built block is proposed instead.
+    #[clap(
+        long = "relay-get-header-timeout",
+        env = "BOLT_SIDECAR_RELAY_GET_HEADER_TIMEOUT",
+        default_value_t = DEFAULT_GET_HEADER_TIMEOUT_MS
+    )]
+    pub get_header_timeout_ms: u64,
+    /// Time budget in milliseconds for fetching a payload from the relay.
+    #[clap(
+        long = "relay-get-payload-timeout",
+        env = "BOLT_SIDECAR_RELAY_GET_PAYLOAD_TIMEOUT",
+        default_value_t = DEFAULT_GET_PAYLOAD_TIMEOUT_MS
+    )]
+    pub get_payload_timeout_ms: u64,
+    /// Number of consecutive failed requests after which the circuit breaker of the relay
+    /// opens, and requests to it are skipped until the cooldown elapses.
+    #[clap(
+        long = "relay-breaker-threshold",
+        env = "BOLT_SIDECAR_RELAY_BREAKER_THRESHOLD",
+        default_value_t = DEFAULT_BREAKER_THRESHOLD
+    )]
+    pub breaker_threshold: u32,
+    /// Cooldown in milliseconds after which an open circuit breaker lets requests through
+    /// again. The first result then closes or reopens it.
+    #[clap(
+        long = "relay-breaker-cooldown",
+        env = "BOLT_SIDECAR_RELAY_BREAKER_COOLDOWN",
+        default_value_t = DEFAULT_BREAKER_COOLDOWN_MS
+    )]
+    pub breaker_cooldown_ms: u64,
+}
+
+impl Default for RelayOpts {
+    fn default() -> Self {
+        Self {
+            submit_timeout_ms: DEFAULT_SUBMIT_TIMEOUT_MS,
+            get_header_timeout_ms: DEFAULT_GET_HEADER_TIMEOUT_MS,
+            get_payload_timeout_ms: DEFAULT_GET_PAYLOAD_TIMEOUT_MS,
+            breaker_threshold: DEFAULT_BREAKER_THRESHOLD,
+            breaker_cooldown_ms: DEFAULT_BREAKER_COOLDOWN_MS,
+        }
+    }
+}
+
+impl RelayOpts {
+    /// Returns the time budget for a single constraints submission.
+    pub fn submit_timeout(&self) -> Duration {
+        Duration::from_millis(self.submit_timeout_ms)
+    }
+
+    /// Returns the time budget for fetching a header.
+    pub fn get_header_timeout(&self) -> Duration {
+        Duration::from_millis(self.get_header_timeout_ms)
+    }
+
+    /// Returns the time budget for fetching a payload.
+    pub fn get_payload_timeout(&self) -> Duration {
+        Duration::from_millis(self.get_payload_timeout_ms)
+    }
+
+    /// Returns the cooldown of an open circuit breaker.
+    pub fn breaker_cooldown(&self) -> Duration {
+        Duration::from_millis(self.breaker_cooldown_ms)
+    }
+}
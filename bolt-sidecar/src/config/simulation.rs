@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+/// Default time budget in milliseconds for simulating the transactions of a single request.
+pub const DEFAULT_SIMULATION_TIMEOUT_MS: u64 = 200;
+
+/// Options for simulating transactions against the execution state before committing to them.
+#[derive(Debug, Clone, Copy, Parser, Deserialize)]
+pub struct SimulationOpts {
+    /// Simulate every transaction of a request on top of the transactions already committed up
+    /// to its target slot, and reject requests that revert or run out of gas.
+    #[clap(
+        long = "simulate-commitments",
+        env = "BOLT_SIDECAR_SIMULATE_COMMITMENTS",
+        default_value_t = false
+    )]
+    #[serde(default)]
+    pub enabled: bool,
+    /// Time budget in milliseconds for simulating the transactions of a single request.
+    /// If the simulation doesn't complete in time, the request is handled according to
+    /// the simulation fallback.
+    #[clap(
+        long = "simulation-timeout",
+        env = "BOLT_SIDECAR_SIMULATION_TIMEOUT",
+        default_value_t = DEFAULT_SIMULATION_TIMEOUT_MS
+    )]
+    pub timeout_ms: u64,
+    /// What to do with a request whose simulation times out or can't be served by the
+    /// execution client.
+    #[clap(
+        long = "simulation-fallback",
+        env = "BOLT_SIDECAR_SIMULATION_FALLBACK",
+        default_value = "reject"
+    )]
+    #[serde(default)]
+    pub fallback: SimulationFallback,
+}
+
+impl Default for SimulationOpts {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_ms: DEFAULT_SIMULATION_TIMEOUT_MS,
+            fallback: SimulationFallback::default(),
+        }
+    }
+}
+
+/// What to do with a request whose simulation doesn't complete.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum SimulationFallback {
+    /// Reject the request.
+    #[default]
+    Reject,
+    /// Accept the request without simulating it.
+    Accept,
+}
+
+impl SimulationOpts {
+    /// Returns the time budget for simulating a single request.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
@@ -1,6 +1,14 @@
+use std::path::PathBuf;
+
 use clap::Parser;
+use reqwest::Url;
 use serde::Deserialize;
 
+use crate::telemetry::{
+    audit::{DEFAULT_AUDIT_LOG_MAX_FILES, DEFAULT_AUDIT_LOG_MAX_SIZE},
+    health::DEFAULT_HEALTH_CHECK_INTERVAL_SECS,
+};
+
 /// Telemetry and metrics related options.
 #[derive(Parser, Debug, Clone, Deserialize)]
 pub struct TelemetryOpts {
@@ -9,6 +17,37 @@ pub struct TelemetryOpts {
     metrics_port: u16,
     #[clap(long, env = "BOLT_SIDECAR_DISABLE_METRICS", default_value_t = false)]
     disable_metrics: bool,
+    /// Path to the append-only audit log of the BLS signatures of the constraints committed
+    /// by the sidecar. If not provided, the audit log is disabled.
+    #[clap(long, env = "BOLT_SIDECAR_AUDIT_LOG_PATH")]
+    pub audit_log_path: Option<PathBuf>,
+    /// Maximum size in bytes of the audit log file before it gets rotated.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_AUDIT_LOG_MAX_SIZE",
+        default_value_t = DEFAULT_AUDIT_LOG_MAX_SIZE
+    )]
+    pub audit_log_max_size: u64,
+    /// Maximum number of rotated audit log files to keep.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_AUDIT_LOG_MAX_FILES",
+        default_value_t = DEFAULT_AUDIT_LOG_MAX_FILES
+    )]
+    pub audit_log_max_files: usize,
+    /// Interval in seconds between two health checks of the beacon, execution and
+    /// constraints endpoints.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_HEALTH_CHECK_INTERVAL",
+        default_value_t = DEFAULT_HEALTH_CHECK_INTERVAL_SECS
+    )]
+    pub health_check_interval_secs: u64,
+    /// OTLP gRPC endpoint to export the traces of the commitment requests to, e.g.
+    /// `http://localhost:4317`. If not provided, traces are not exported.
+    #[clap(long, env = "BOLT_SIDECAR_OTLP_ENDPOINT")]
+    #[serde(default)]
+    pub otlp_endpoint: Option<Url>,
 }
 
 impl TelemetryOpts {
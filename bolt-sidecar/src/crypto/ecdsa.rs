@@ -1,9 +1,10 @@
 use std::fmt::Debug;
 
 use alloy::{
-    primitives::Address,
-    signers::{local::PrivateKeySigner, Signature as AlloySignature, Signer},
+    primitives::{keccak256, Address},
+    signers::{aws::AwsSigner, local::PrivateKeySigner, Signature as AlloySignature, Signer},
 };
+use eyre::Context;
 use secp256k1::{ecdsa::Signature, Message, PublicKey, SecretKey};
 
 /// Trait for any types that can be signed and verified with ECDSA.
@@ -59,11 +60,17 @@ impl ECDSASigner {
 
 /// A generic signing trait to generate ECDSA signatures.
 #[async_trait::async_trait]
-pub trait SignerECDSA: Send + Debug {
+pub trait SignerECDSA: Send + Sync + Debug {
     /// Returns the public key of the signer.
     fn public_key(&self) -> Address;
     /// Sign the given hash and return the signature.
     async fn sign_hash(&self, hash: &[u8; 32]) -> eyre::Result<AlloySignature>;
+
+    /// Sign the keccak256 hash of the given payload and return the signature. Signers hashing
+    /// the data they're given themselves should override this to send the payload instead.
+    async fn sign_payload(&self, payload: &[u8]) -> eyre::Result<AlloySignature> {
+        self.sign_hash(&keccak256(payload).0).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -80,14 +87,105 @@ impl SignerECDSA for PrivateKeySigner {
     }
 }
 
+#[async_trait::async_trait]
+impl SignerECDSA for AwsSigner {
+    fn public_key(&self) -> Address {
+        self.address()
+    }
+
+    async fn sign_hash(&self, hash: &[u8; 32]) -> eyre::Result<AlloySignature> {
+        let sig = Signer::sign_hash(self, hash.into()).await?;
+        AlloySignature::try_from(sig.as_bytes().as_ref())
+            .wrap_err("Invalid signature returned by AWS KMS")
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use alloy::signers::k256::ecdsa::{
+        signature::hazmat::PrehashSigner, Signature as K256Signature, SigningKey,
+    };
+    use aws_sdk_kms::config::{BehaviorVersion, Credentials, Region};
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use serde_json::{json, Value};
+    use wiremock::{matchers, Mock, MockServer, Request, Respond, ResponseTemplate};
+
     use crate::test_util::TestSignableData;
 
-    use super::ECDSASigner;
+    use super::*;
     use rand::Rng;
     use secp256k1::{PublicKey, SecretKey};
 
+    /// A KMS endpoint holding a single secp256k1 key, answering the `GetPublicKey` and `Sign`
+    /// calls of the AWS signer.
+    struct MockKms(SigningKey);
+
+    impl Respond for MockKms {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            let target = request.headers.get("x-amz-target").unwrap().to_str().unwrap();
+
+            let response = match target {
+                "TrentService.GetPublicKey" => {
+                    // DER-encoded SubjectPublicKeyInfo of an uncompressed secp256k1 key
+                    let mut der =
+                        hex::decode("3056301006072a8648ce3d020106052b8104000a034200").unwrap();
+                    der.extend_from_slice(
+                        self.0.verifying_key().to_encoded_point(false).as_bytes(),
+                    );
+                    json!({
+                        "KeyId": body["KeyId"],
+                        "PublicKey": BASE64.encode(der),
+                        "KeySpec": "ECC_SECG_P256K1",
+                        "KeyUsage": "SIGN_VERIFY",
+                        "SigningAlgorithms": ["ECDSA_SHA_256"],
+                    })
+                }
+                "TrentService.Sign" => {
+                    assert_eq!(body["MessageType"], "DIGEST");
+                    let digest = BASE64.decode(body["Message"].as_str().unwrap()).unwrap();
+                    let signature: K256Signature = self.0.sign_prehash(&digest).unwrap();
+                    json!({
+                        "KeyId": body["KeyId"],
+                        "Signature": BASE64.encode(signature.to_der().as_bytes()),
+                        "SigningAlgorithm": "ECDSA_SHA_256",
+                    })
+                }
+                target => panic!("unexpected KMS call {target}"),
+            };
+
+            ResponseTemplate::new(200)
+                .set_body_raw(response.to_string(), "application/x-amz-json-1.1")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kms_signer() -> eyre::Result<()> {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let expected = PrivateKeySigner::from_signing_key(key.clone());
+
+        let server = MockServer::start().await;
+        Mock::given(matchers::method("POST")).respond_with(MockKms(key)).mount(&server).await;
+
+        let config = aws_sdk_kms::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("key", "secret", None, None, "test"))
+            .endpoint_url(server.uri())
+            .build();
+        let client = aws_sdk_kms::Client::from_conf(config);
+        let signer = AwsSigner::new(client, "commitment-key".to_string(), None).await?;
+        assert_eq!(SignerECDSA::public_key(&signer), expected.address());
+
+        let payload = b"commitment payload";
+        let signature = signer.sign_payload(payload).await?;
+        let hash = keccak256(payload);
+        assert_eq!(signature.recover_address_from_prehash(&hash)?, expected.address());
+        assert_eq!(signature, SignerECDSA::sign_hash(&expected, &hash.0).await?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_ecdsa_signer() {
         let secp256k1_key = SecretKey::from_slice(&[1; 32]).unwrap();
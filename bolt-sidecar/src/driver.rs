@@ -1,46 +1,112 @@
-use std::{fmt, sync::Arc, time::Instant};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use alloy::{rpc::types::beacon::events::HeadEvent, signers::local::PrivateKeySigner};
-use beacon_api_client::mainnet::Client as BeaconClient;
+use alloy::{
+    primitives::{Address, B256},
+    rpc::types::beacon::events::{FinalizedCheckpointEvent, HeadEvent},
+};
+use beacon_api_client::{mainnet::Client as BeaconClient, BlockId, PublicKeyOrIndex, StateId};
 use ethereum_consensus::{
     clock::{self, SlotStream, SystemTimeProvider},
     phase0::mainnet::SLOTS_PER_EPOCH,
 };
 use eyre::Context;
 use futures::StreamExt;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
+use parking_lot::Mutex;
+use tokio::{
+    sync::{
+        mpsc,
+        oneshot::{self, error::TryRecvError},
+        watch, Semaphore,
+    },
+    task::JoinHandle,
+};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
 
 use crate::{
     api::{
         builder::{start_builder_proxy_server, BuilderProxyConfig},
         commitments::{
-            server::{CommitmentEvent, CommitmentsApiServer},
+            server::{CommitmentEvent, CommitmentsApiServer, ValidationEvent},
             spec::CommitmentError,
+            status::{StatusHandle, ValidatorStatus},
         },
-        spec::ConstraintsApi,
+        spec::{BuilderApiError, ConstraintsApi},
     },
     builder::payload_fetcher::LocalPayloadFetcher,
     chain_io::BoltManager,
-    client::ConstraintsClient,
-    common::retry_with_backoff,
-    config::Opts,
-    crypto::{SignableBLS, SignerECDSA},
+    client::{
+        submissions::failure_reason, ConstraintsClient, SubmissionAttempt, SubmissionReport,
+        SubmissionReports,
+    },
+    common::{retry_until_deadline, retry_with_backoff},
+    config::{DeadlinePolicyKind, Opts, SubmissionFailureMode},
+    crypto::{bls::BLSSig, SignerECDSA},
+    preflight::PreflightChecks,
     primitives::{
-        commitment::SignedCommitment, read_signed_delegations_from_file, CommitmentRequest,
-        ConstraintsMessage, FetchPayloadRequest, SignedConstraints, TransactionExt,
+        commitment::{
+            CollateralAttestation, CommittedConstraint, RequestId, SignedCommitment,
+            ValidationVerdict,
+        },
+        merge_constraints, read_signed_messages_from_file, BatchedSignedConstraints, BlsPublicKey,
+        ChainHead, CommitmentRequest, ConstraintsMessage, FetchPayloadRequest, InclusionRequest,
+        SignedConstraints, SignedMessages, TransactionExt,
+    },
+    signer::{
+        keystore::KeystoreSigner, local::LocalSigner, AvailablePubkeys, CommitBoostSigner,
+        CommitmentSigner, SignerBLS, SignerResult, REMOTE_PUBKEYS_REFRESH_INTERVAL,
+    },
+    state::{
+        archive::ARCHIVE_CLEANUP_INTERVAL, fetcher::StateFetcher, head_tracker::finalized_slot,
+        ArchivedCommitment, CachedStateFetcher, ClockSkewMonitor, CollateralTracker,
+        CommitmentArchive, ConsensusState, ConstraintsStream, Denylist, DutyDistanceDeadline,
+        EffectiveValidators, ExecutionState, FailoverStateClient, HeadTracker, NonceGapQueue,
+        RegisteredGasLimits, SealedSlots, SharedExecutionState, SlotTiming, ValidationError,
+        VerifiedValidators,
     },
-    signer::{keystore::KeystoreSigner, local::LocalSigner, CommitBoostSigner, SignerBLS},
-    state::{fetcher::StateFetcher, ConsensusState, ExecutionState, HeadTracker, StateClient},
-    telemetry::ApiMetrics,
+    telemetry::{ApiMetrics, AuditEntry, AuditLog, HealthHandle, HealthProber},
     LocalBuilder,
 };
 
+/// Maximum number of retries when submitting the delegations file to the relay at startup.
+const DELEGATIONS_SUBMISSION_RETRIES: usize = 5;
+
+/// Maximum number of retries when submitting the constraints of a slot to the relay.
+const CONSTRAINTS_SUBMISSION_RETRIES: usize = 10;
+
+/// Maximum time to wait for the execution client to import the block of a new beacon head.
+const EXECUTION_HEAD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum number of commitment requests validated and signed concurrently. Past it, new
+/// requests wait in the API events queue.
+const MAX_CONCURRENT_COMMITMENT_REQUESTS: usize = 32;
+
+/// Maximum number of candidate pubkeys tried to sign the constraints of a request, when the
+/// signer fails with the previous ones.
+const MAX_SIGNING_ATTEMPTS: usize = 3;
+
+/// The state fetcher of the sidecar: the execution API endpoints, behind a cache.
+type SidecarStateClient = CachedStateFetcher<FailoverStateClient>;
+
+/// Create the default state client, fetching state updates from the execution API URLs.
+fn state_client(opts: &Opts) -> SidecarStateClient {
+    CachedStateFetcher::new(FailoverStateClient::from_urls(
+        opts.execution_api_url.clone(),
+        opts.execution_api_fallback_urls.clone(),
+    ))
+}
+
 /// The driver for the sidecar, responsible for managing the main event loop.
 ///
 /// The reponsibilities of the driver include:
-/// - Handling incoming API events
+/// - Handling incoming API events, dispatching them to concurrent commitment workers
 /// - Updating the execution state based on new beacon chain heads
+/// - Pruning the records older than the finalized checkpoint
 /// - Submitting constraints to the constraints service at the commitment deadline
 /// - Building local payloads for the beacon chain
 /// - Responding to requests to fetch a local payload
@@ -48,33 +114,63 @@ use crate::{
 pub struct SidecarDriver<C, ECDSA> {
     /// Head tracker for monitoring the beacon chain clock
     head_tracker: HeadTracker,
-    /// Execution state for tracking the current head and block templates
-    execution: ExecutionState<C>,
+    /// Execution state for tracking the current head and block templates, shared with the
+    /// commitment workers
+    execution: SharedExecutionState<C>,
     /// Consensus state for tracking the current slot and validator indexes
     consensus: ConsensusState,
     /// Signer for creating constraints
     constraint_signer: SignerBLS,
-    /// Signer for creating commitment responses
-    commitment_signer: ECDSA,
+    /// The last known public keys available to the constraint signer
+    available_pubkeys: AvailablePubkeys,
     /// Local block builder for creating local payloads
     local_builder: LocalBuilder,
     /// Client for interacting with the constraints service
     constraints_client: ConstraintsClient,
     /// Channel for receiving incoming API events
     api_events_rx: mpsc::Receiver<CommitmentEvent>,
+    /// Channel for receiving the dry-run validations of inclusion requests
+    validation_events_rx: mpsc::Receiver<ValidationEvent>,
     /// Channel for receiving requests to fetch a local payload
     payload_requests_rx: mpsc::Receiver<FetchPayloadRequest>,
     /// Stream of slots made from the consensus clock
     slot_stream: SlotStream<SystemTimeProvider>,
     /// Whether to skip consensus checks (should only be used for testing)
     unsafe_skip_consensus_checks: bool,
+    /// Worker validating and signing the requests that passed the consensus checks
+    worker: Arc<CommitmentWorker<C, ECDSA>>,
+    /// Permits bounding the number of requests processed concurrently by the worker
+    worker_permits: Arc<Semaphore>,
+    /// Queues keeping the requests of every sender in order
+    sender_queue: SenderQueue,
+    /// Handle to the health status of the upstream endpoints
+    health: HealthHandle,
+    /// Tracker of the operator collateral, used to gate commitments on it
+    collateral: CollateralTracker,
+    /// Registry of the slots sealed at the commitment deadline
+    sealed_slots: SealedSlots,
+    /// The current chain head, updated on every slot tick and head event
+    chain_head: ChainHead,
+    /// Channel for sending the outcome of the constraints submissions, from their retry tasks
+    submission_results_tx: mpsc::UnboundedSender<SubmissionOutcome>,
+    /// Channel for receiving the outcome of the constraints submissions
+    submission_results_rx: mpsc::UnboundedReceiver<SubmissionOutcome>,
+    /// The latest reports of the constraints submissions, served in the admin endpoints
+    submission_reports: SubmissionReports,
+    /// The task updating the execution state to the latest head, which may wait for the EL
+    head_update: Option<JoinHandle<()>>,
+    /// Channel for sending the block numbers of the execution heads, from their update tasks
+    execution_heads_tx: mpsc::UnboundedSender<u64>,
+    /// Channel for receiving the block numbers of the execution heads
+    execution_heads_rx: mpsc::UnboundedReceiver<u64>,
+    /// The gas limits registered by the validators, capping the gas committed in their slots
+    registered_gas_limits: RegisteredGasLimits,
 }
 
-impl SidecarDriver<StateClient, PrivateKeySigner> {
-    /// Create a new sidecar driver with the given [Opts] and private key signer.
+impl SidecarDriver<SidecarStateClient, CommitmentSigner> {
+    /// Create a new sidecar driver with the given [Opts] and local constraint signer.
     pub async fn with_local_signer(opts: &Opts) -> eyre::Result<Self> {
-        // The default state client simply uses the execution API URL to fetch state updates.
-        let state_client = StateClient::new(opts.execution_api_url.clone());
+        let state_client = state_client(opts);
 
         // Constraints are signed with a BLS private key
         let constraint_signer = SignerBLS::Local(LocalSigner::new(
@@ -86,9 +182,9 @@ impl SidecarDriver<StateClient, PrivateKeySigner> {
             opts.chain,
         ));
 
-        // Commitment responses are signed with a regular Ethereum wallet private key.
-        let commitment_key = opts.commitment_private_key.0.clone();
-        let commitment_signer = PrivateKeySigner::from_signing_key(commitment_key);
+        // Commitment responses are signed with the ECDSA key of the selected backend
+        let commitment_signer = CommitmentSigner::from_opts(opts).await?;
+        debug!(backend = commitment_signer.backend(), "Loaded the commitment signer");
 
         Self::from_components(opts, constraint_signer, commitment_signer, state_client)
             .await
@@ -96,11 +192,10 @@ impl SidecarDriver<StateClient, PrivateKeySigner> {
     }
 }
 
-impl SidecarDriver<StateClient, PrivateKeySigner> {
+impl SidecarDriver<SidecarStateClient, CommitmentSigner> {
     /// Create a new sidecar driver with the given [Opts] and keystore signer.
     pub async fn with_keystore_signer(opts: &Opts) -> eyre::Result<Self> {
-        // The default state client simply uses the execution API URL to fetch state updates.
-        let state_client = StateClient::new(opts.execution_api_url.clone());
+        let state_client = state_client(opts);
 
         let keystore = if let Some(psw) = opts.constraint_signing.keystore_password.as_ref() {
             KeystoreSigner::from_password(
@@ -118,9 +213,9 @@ impl SidecarDriver<StateClient, PrivateKeySigner> {
 
         let keystore_signer = SignerBLS::Keystore(keystore);
 
-        // Commitment responses are signed with a regular Ethereum wallet private key.
-        let commitment_key = opts.commitment_private_key.0.clone();
-        let commitment_signer = PrivateKeySigner::from_signing_key(commitment_key);
+        // Commitment responses are signed with the ECDSA key of the selected backend
+        let commitment_signer = CommitmentSigner::from_opts(opts).await?;
+        debug!(backend = commitment_signer.backend(), "Loaded the commitment signer");
 
         Self::from_components(opts, keystore_signer, commitment_signer, state_client)
             .await
@@ -128,11 +223,10 @@ impl SidecarDriver<StateClient, PrivateKeySigner> {
     }
 }
 
-impl SidecarDriver<StateClient, CommitBoostSigner> {
+impl SidecarDriver<SidecarStateClient, CommitBoostSigner> {
     /// Create a new sidecar driver with the given [Opts] and commit-boost signer.
     pub async fn with_commit_boost_signer(opts: &Opts) -> eyre::Result<Self> {
-        // The default state client simply uses the execution API URL to fetch state updates.
-        let state_client = StateClient::new(opts.execution_api_url.clone());
+        let state_client = state_client(opts);
 
         let commit_boost_signer = CommitBoostSigner::new(
             opts.constraint_signing.commit_boost_signer_url.clone().expect("CommitBoost URL"),
@@ -147,7 +241,11 @@ impl SidecarDriver<StateClient, CommitBoostSigner> {
     }
 }
 
-impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
+impl<C, ECDSA> SidecarDriver<C, ECDSA>
+where
+    C: StateFetcher + Send + Sync + 'static,
+    ECDSA: SignerECDSA + Sync + 'static,
+{
     /// Create a new sidecar driver with the given components
     pub async fn from_components(
         opts: &Opts,
@@ -155,21 +253,69 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
         commitment_signer: ECDSA,
         fetcher: C,
     ) -> eyre::Result<Self> {
-        let mut constraints_client = ConstraintsClient::new(opts.constraints_api_url.clone());
+        // Remote signers can fail to list their keys, in which case they are listed again
+        // on the next commitment request
+        let available_pubkeys = AvailablePubkeys::default();
+        let constraint_pubkeys = available_pubkeys.refresh(&constraint_signer).await;
+        if constraint_signer.is_remote() {
+            available_pubkeys
+                .spawn_refresher(constraint_signer.clone(), REMOTE_PUBKEYS_REFRESH_INTERVAL);
+        }
+
+        // Operators fund and register this address in the BoltManager contract
+        info!(address = %commitment_signer.public_key(), "Signing commitments with the operator key");
+
+        if opts.skip_preflight {
+            warn!("Skipping the preflight checks, --skip-preflight is 'true'");
+        } else {
+            PreflightChecks::from_opts(opts)
+                .with_empty_remote_signer_allowed(constraint_signer.is_remote())
+                .run(commitment_signer.public_key(), constraint_pubkeys.len())
+                .await?;
+        }
+
+        // Fail early on invalid TLS files, before connecting to anything
+        let api_tls = opts.api.tls_config()?;
+
+        let mut constraints_client = ConstraintsClient::new(opts.constraints_api_url.clone())
+            .with_ssz_constraints(opts.constraints_api_ssz)
+            .with_constraints_digest_version(opts.constraints_digest_version)
+            .with_relay_opts(opts.relay);
 
         // read the delegations from disk if they exist and add them to the constraints client.
         let validator_pubkeys = if let Some(delegations_path) =
             &opts.constraint_signing.delegations_path
         {
-            let delegations = read_signed_delegations_from_file(delegations_path)?;
-            let keys = delegations.iter().map(|d| d.validator_pubkey.clone()).collect::<Vec<_>>();
-            constraints_client.add_delegations(delegations);
+            let messages = read_signed_messages_from_file(delegations_path)?;
+            let keys = messages.delegations.iter().map(|d| d.validator_pubkey.clone()).collect();
+
+            // Relays enforcing delegation checks need them before we submit constraints
+            submit_signed_messages(
+                &constraints_client,
+                &messages,
+                opts.constraint_signing.delegations_submission_failure,
+            )
+            .await?;
+
+            constraints_client.add_delegations(messages.delegations);
             keys
         } else {
             // If no delegations are provided, we just use the public keys from the signer.
-            Vec::from_iter(constraint_signer.available_pubkeys())
+            Vec::from_iter(constraint_pubkeys)
         };
 
+        // The validators we act for, minus the excluded ones once resolved with the beacon node
+        let validators = EffectiveValidators::from_iter(validator_pubkeys.clone());
+
+        // start probing the health of the upstream endpoints
+        let health_prober = HealthProber::from_opts(opts);
+        let health = health_prober.handle();
+        health_prober.spawn();
+
+        let collateral = CollateralTracker::new(opts.collateral).with_health(health.clone());
+        let mut collateral_polled = false;
+        let verified_validators = VerifiedValidators::default();
+
         if opts.unsafe_disable_onchain_checks {
             warn!("Skipping validators and operator public keys verification, --unsafe-disable-onchain-checks is 'true'");
         } else if let Some(manager) =
@@ -181,11 +327,31 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
                 "Verifying validators and operator keys with Bolt Manager, this may take a while..."
             );
 
-            manager
-                .verify_validator_pubkeys(validator_pubkeys, commitment_signer.public_key())
+            let report = verified_validators
+                .verify_at_startup(
+                    &manager,
+                    validator_pubkeys.clone(),
+                    commitment_signer.public_key(),
+                    opts.allow_unverified_keys,
+                )
                 .await?;
 
-            info!("Successfully verified validators and operator keys with Bolt Manager.");
+            info!(
+                verified = report.verified.len(),
+                "Successfully verified validators and operator keys with Bolt Manager."
+            );
+
+            // Validators can drop out at any time, keep verifying them in the background
+            verified_validators.spawn_verifier(
+                manager.clone(),
+                validators.clone(),
+                commitment_signer.public_key(),
+                Duration::from_secs(opts.validators_verification_interval),
+            );
+
+            // Keep track of the operator status and collateral, reported in the health endpoint
+            collateral.spawn_poller(manager, commitment_signer.public_key(), report.verified);
+            collateral_polled = true;
         } else {
             warn!(
                 "Bolt Manager is not deployed on {}, skipping validators and operator public keys verification",
@@ -193,27 +359,47 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
             );
         }
 
-        let beacon_client = BeaconClient::new(opts.beacon_api_url.clone());
-        let execution = ExecutionState::new(fetcher, opts.limits).await?;
-
-        let genesis_time = beacon_client.get_genesis_details().await?.genesis_time;
-        let slot_stream =
-            clock::from_system_time(genesis_time, opts.chain.slot_time(), SLOTS_PER_EPOCH)
-                .into_stream();
+        if collateral.is_enabled() && !collateral_polled {
+            warn!("Collateral gating is enabled but the Bolt Manager can't be queried: all commitment requests will be rejected");
+        }
 
-        let local_builder = LocalBuilder::new(opts, beacon_client.clone(), genesis_time);
-        let head_tracker = HeadTracker::start(beacon_client.clone());
+        let denylist = Denylist::new(&opts.denylist)?;
+        if let Some(interval) = opts.denylist.reload_interval() {
+            denylist.spawn_reloader(interval);
+        }
 
-        let consensus = ConsensusState::new(
-            beacon_client,
-            opts.chain.commitment_deadline(),
-            opts.chain.enable_unsafe_lookahead,
-        );
+        let beacon_client = BeaconClient::new(opts.beacon_api_url.clone());
+        let constraints_stream = ConstraintsStream::default();
+        let execution = ExecutionState::new(fetcher, opts.limits)
+            .await?
+            .with_chain(opts.chain)
+            .with_simulation(opts.simulation)
+            .with_denylist(denylist)
+            .with_drop_at_risk_constraints(opts.drop_at_risk_constraints)
+            .with_lookahead_slots(opts.chain.lookahead_slots())
+            .with_constraints_stream(constraints_stream.clone());
+        let chain_head = ChainHead::new(0, execution.block_number());
+        let limits = execution.limits();
+        let registered_gas_limits = RegisteredGasLimits::new(limits.clone());
+        let execution = SharedExecutionState::new(execution);
 
         let (payload_requests_tx, payload_requests_rx) = mpsc::channel(16);
         let builder_proxy_cfg = BuilderProxyConfig {
             constraints_client: constraints_client.clone(),
+            registration_relays: opts
+                .registration_relay_urls
+                .iter()
+                .map(|url| ConstraintsClient::new(url.clone()).with_relay_opts(opts.relay))
+                .collect(),
             server_port: opts.constraints_proxy_port,
+            get_header_timeout: Duration::from_millis(opts.builder_get_header_timeout),
+            get_payload_timeout: Duration::from_millis(opts.builder_get_payload_timeout),
+            register_validator_timeout: Duration::from_millis(
+                opts.builder_register_validator_timeout,
+            ),
+            constraints_stream,
+            templates: Some(Arc::new(execution.clone())),
+            registered_gas_limits: registered_gas_limits.clone(),
         };
 
         // start the builder api proxy server
@@ -225,27 +411,195 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
         });
 
         // start the commitments api server
-        let api_addr = format!("0.0.0.0:{}", opts.port);
-        let (api_events_tx, api_events_rx) = mpsc::channel(1024);
-        CommitmentsApiServer::new(api_addr).run(api_events_tx, opts.limits).await;
+        let api_addr = SocketAddr::new(opts.api.host, opts.port);
+        let (api_events_tx, api_events_rx) = mpsc::channel(opts.api.queue_capacity.get());
+        let (validation_events_tx, validation_events_rx) =
+            mpsc::channel(opts.api.queue_capacity.get());
+        let submission_reports = SubmissionReports::default();
+
+        // The sealed slots are restored from the data directory, so that a restart never seals
+        // a slot again with a different constraint set
+        let sealed_slots = opts
+            .data_dir
+            .as_ref()
+            .map(SealedSlots::open)
+            .transpose()
+            .wrap_err("Failed to open the sealed slots")?
+            .unwrap_or_default();
+        if let Some(slot) = sealed_slots.last_sealed() {
+            execution.lock().await.seal_slot(slot);
+        }
+
+        let archive = opts
+            .data_dir
+            .as_ref()
+            .map(|data_dir| CommitmentArchive::open(data_dir, opts.retention_epochs))
+            .transpose()
+            .wrap_err("Failed to open the commitment archive")?;
+
+        // the public identity of the sidecar, served by the status method without any secret
+        let status = StatusHandle::new(
+            opts.chain,
+            commitment_signer.public_key(),
+            constraint_signer.backend(),
+            validator_statuses(&validators, &constraints_client),
+        )
+        .with_verified_validators(verified_validators.clone());
+
+        let mut api_server = CommitmentsApiServer::new(api_addr, api_tls)
+            .with_api_opts(opts.api.clone())
+            .with_health(health.clone())
+            .with_sealed_slots(sealed_slots.clone())
+            .with_submission_reports(submission_reports.clone())
+            .with_status(status.clone())
+            .with_validation_events(validation_events_tx);
+        if let Some(archive) = &archive {
+            api_server = api_server.with_commitment_archive(archive.clone());
+        }
+        api_server.run(api_events_tx, limits).await;
+
+        // Wait for the beacon node before following the chain, answering the commitment
+        // requests that it's unavailable in the meantime
+        let beacon_startup_timeout = Duration::from_secs(opts.beacon_startup_timeout);
+        let (genesis_time, head_slot) =
+            wait_for_beacon(&beacon_client, &health, beacon_startup_timeout).await?;
+
+        // The validators we act for are the ones of the delegations file or of the signer,
+        // minus the excluded ones
+        let excluded = excluded_validators(&beacon_client, &opts.exclude_validator_indexes).await?;
+        validators.exclude(excluded);
+        status.set_validators(validator_statuses(&validators, &constraints_client));
+        info!(
+            validators = validators.len(),
+            excluded_indexes = opts.exclude_validator_indexes.len(),
+            "Resolved the effective validator set"
+        );
+
+        // Without delegations, the validators are the keys of the signer: the ones listed later
+        // by a remote signer, e.g. if it was unavailable at startup, are accepted too
+        if constraint_signer.is_remote() && opts.constraint_signing.delegations_path.is_none() {
+            spawn_validators_sync(
+                available_pubkeys.subscribe(),
+                validators.clone(),
+                status,
+                constraints_client.clone(),
+            );
+        }
+
+        let slot_stream =
+            clock::from_system_time(genesis_time, opts.chain.slot_time(), SLOTS_PER_EPOCH)
+                .into_stream();
+
+        let local_builder = LocalBuilder::new(opts, beacon_client.clone(), genesis_time);
+        let head_tracker = HeadTracker::start(beacon_client.clone());
+
+        let slot_time = Duration::from_secs(opts.chain.slot_time());
+        let mut consensus = ConsensusState::new(
+            beacon_client,
+            opts.chain.commitment_deadline(),
+            slot_time,
+            opts.chain.enable_unsafe_lookahead,
+        )
+        .with_verified_validators(verified_validators)
+        .with_validators(validators)
+        .with_min_lead_time(opts.chain.min_lead_time(), SlotTiming::new(genesis_time, slot_time))
+        .with_clock_skew_monitor(
+            ClockSkewMonitor::new(
+                SlotTiming::new(genesis_time, slot_time),
+                opts.chain.clock_skew_warn_threshold(),
+                opts.chain.clock_skew_compensation_threshold(),
+            )
+            .with_propagation_baseline(opts.chain.clock_skew_propagation_baseline()),
+        );
+        if opts.chain.deadline_policy == DeadlinePolicyKind::DutyDistance {
+            let margin = opts.chain.deadline_safety_margin();
+            consensus = consensus.with_deadline_policy(DutyDistanceDeadline::new(
+                genesis_time,
+                slot_time,
+                margin,
+            ));
+        }
+
+        // Bound the size of the archive from now on, based on the current slot
+        if let Some(archive) = &archive {
+            archive
+                .spawn_cleanup(SlotTiming::new(genesis_time, slot_time), ARCHIVE_CLEANUP_INTERVAL);
+        }
+
+        // Fetch the proposer duties of the current epoch right away. If it fails, they are
+        // fetched again on the next slot.
+        chain_head.update_slot(head_slot);
+        if let Err(err) = consensus.update_slot(head_slot).await {
+            warn!(?err, "Failed to fetch the initial proposer duties");
+        }
 
         let unsafe_skip_consensus_checks = opts.unsafe_disable_consensus_checks;
 
+        let audit_log = match &opts.telemetry.audit_log_path {
+            Some(path) => {
+                let log = AuditLog::open(
+                    path,
+                    opts.telemetry.audit_log_max_size,
+                    opts.telemetry.audit_log_max_files,
+                )
+                .wrap_err("Failed to open signature audit log")?;
+                info!(path = %path.display(), "Signature audit log enabled");
+                Some(Mutex::new(log))
+            }
+            None => None,
+        };
+
+        let worker = Arc::new(CommitmentWorker {
+            execution: execution.clone(),
+            constraint_signer: constraint_signer.clone(),
+            commitment_signer,
+            constraints_client: constraints_client.clone(),
+            audit_log,
+            archive,
+            legacy_commitment_format: opts.legacy_commitment_format,
+            merge_constraints: opts.merge_constraints,
+            nonce_gaps: opts.queue_nonce_gaps.then(|| Mutex::new(NonceGapQueue::default())),
+            committed_transactions: Default::default(),
+        });
+
+        let (submission_results_tx, submission_results_rx) = mpsc::unbounded_channel();
+        let (execution_heads_tx, execution_heads_rx) = mpsc::unbounded_channel();
+
         Ok(SidecarDriver {
             unsafe_skip_consensus_checks,
+            worker,
+            worker_permits: Arc::new(Semaphore::new(MAX_CONCURRENT_COMMITMENT_REQUESTS)),
+            sender_queue: SenderQueue::default(),
+            health,
+            collateral,
+            sealed_slots,
+            chain_head,
             head_tracker,
             execution,
             consensus,
             constraint_signer,
-            commitment_signer,
+            available_pubkeys,
             local_builder,
             constraints_client,
             api_events_rx,
+            validation_events_rx,
             payload_requests_rx,
             slot_stream,
+            submission_results_tx,
+            submission_results_rx,
+            submission_reports,
+            head_update: None,
+            execution_heads_tx,
+            execution_heads_rx,
+            registered_gas_limits,
         })
     }
 
+    /// Returns a handle to the chain head maintained by the driver.
+    pub fn chain_head(&self) -> ChainHead {
+        self.chain_head.clone()
+    }
+
     /// Run the main event loop endlessly for the sidecar driver.
     ///
     /// Any errors encountered are contained to the specific `handler` in which
@@ -253,22 +607,45 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
     pub async fn run_forever(mut self) -> ! {
         loop {
             tokio::select! {
-                Some(api_event) = self.api_events_rx.recv() => {
+                // Leave the requests in the queue while all the workers are busy
+                Some(api_event) = self.api_events_rx.recv(),
+                    if self.worker_permits.available_permits() > 0 => {
+                    ApiMetrics::set_api_queue_depth(self.api_events_rx.len());
                     self.handle_incoming_api_event(api_event).await;
                 }
+                Some(validation_event) = self.validation_events_rx.recv(),
+                    if self.worker_permits.available_permits() > 0 => {
+                    self.handle_validation_event(validation_event).await;
+                }
                 Ok(head_event) = self.head_tracker.next_head() => {
-                    self.handle_new_head_event(head_event).await;
+                    self.handle_new_head_event(head_event);
                 }
-                Some(slot) = self.consensus.wait_commitment_deadline() => {
+                Some(block_number) = self.execution_heads_rx.recv() => {
+                    self.handle_execution_head(block_number);
+                }
+                Ok(finalized_event) = self.head_tracker.next_finalized() => {
+                    self.handle_finalized_checkpoint_event(finalized_event).await;
+                }
+                slot = self.consensus.wait_commitment_deadline() => {
                     self.handle_commitment_deadline(slot).await;
                 }
                 Some(payload_request) = self.payload_requests_rx.recv() => {
                     self.handle_fetch_payload_request(payload_request);
                 }
+                Some(outcome) = self.submission_results_rx.recv() => {
+                    handle_submission_outcome(outcome, &self.submission_reports);
+                }
                 Some(slot) = self.slot_stream.next() => {
+                    self.chain_head.update_slot(slot);
                     if let Err(e) = self.consensus.update_slot(slot).await {
                         error!(err = ?e, "Failed to update consensus state slot");
                     }
+
+                    // Without any duty of ours, the next slot can't receive commitments: let
+                    // external builders know right away instead of at its deadline
+                    if !self.consensus.is_commitment_deadline_armed() {
+                        self.sealed_slots.seal(slot + 1, &[]);
+                    }
                 }
             }
         }
@@ -276,10 +653,27 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
 
     /// Handle an incoming API event, validating the request and responding with a commitment.
     async fn handle_incoming_api_event(&mut self, event: CommitmentEvent) {
-        let CommitmentEvent { request, response } = event;
+        let CommitmentEvent { request_id, request, response, span } = event;
+
+        span.in_scope(|| {
+            info!("Received new commitment request: {:?}", request);
+            ApiMetrics::increment_inclusion_commitments_received();
 
-        info!("Received new commitment request: {:?}", request);
-        ApiMetrics::increment_inclusion_commitments_received();
+            if !self.health.is_healthy() {
+                let status = self.health.status();
+                let unreachable = status
+                    .targets
+                    .into_iter()
+                    .filter(|t| !t.reachable)
+                    .map(|t| t.name)
+                    .collect::<Vec<_>>();
+                warn!(
+                    ?unreachable,
+                    operator = ?status.operator,
+                    "Processing commitment request while the sidecar is unhealthy"
+                );
+            }
+        });
 
         let start = Instant::now();
 
@@ -288,176 +682,2147 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
         let CommitmentRequest::Inclusion(mut inclusion_request) = request;
         let target_slot = inclusion_request.slot;
 
-        let available_pubkeys = self.constraint_signer.available_pubkeys();
+        let checked =
+            self.check_request(&mut inclusion_request, &span).instrument(span.clone()).await;
+        let (signing_pubkeys, collateral) = match checked {
+            Ok(checked) => checked,
+            Err(err) => {
+                let _ = response.send(Err(err));
+                return;
+            }
+        };
+
+        let senders = inclusion_request.txs.iter().filter_map(|tx| tx.sender().copied());
+        let mut turn = self.sender_queue.enqueue(senders);
+
+        let permit = Arc::clone(&self.worker_permits)
+            .try_acquire_owned()
+            .expect("API events are only received with available permits");
+
+        let job = CommitmentJob {
+            deadline: self.consensus.commitment_deadline_at(target_slot),
+            request_id,
+            span: span.clone(),
+            request: inclusion_request,
+            signing_pubkeys,
+            collateral,
+            response,
+            start,
+        };
+
+        // Validate the request against the execution state and sign it concurrently, once the
+        // previous requests of the same senders are processed
+        let worker = Arc::clone(&self.worker);
+        tokio::spawn(
+            async move {
+                turn.wait().await;
+                worker.process(job).await;
+                drop((turn, permit));
+            }
+            .instrument(span),
+        );
+    }
+
+    /// Check a request against the consensus state and the collateral of the operator, once
+    /// its transactions are fetched if referenced by hash, and recover their senders. Returns
+    /// the candidate public keys to sign its constraints with, in order of preference, and the
+    /// collateral it's checked against.
+    async fn check_request(
+        &self,
+        inclusion_request: &mut InclusionRequest,
+        span: &Span,
+    ) -> Result<(Vec<BlsPublicKey>, Option<CollateralAttestation>), CommitmentError> {
+        let target_slot = inclusion_request.slot;
+        check_expiry(inclusion_request, "receipt")?;
+
+        let available_pubkeys =
+            self.available_pubkeys.get_or_refresh(&self.constraint_signer).await;
 
-        // Determine the constraint signing public key for this request. Rationale:
+        // Determine the constraint signing public keys for this request. Rationale:
         // - If we're skipping consensus checks, we can use any available pubkey in the keystore.
         // - On regular operation, we need to validate the request against the consensus state to
         //   determine if the sidecar is the proposer for the given slot. If so, we use the
         //   validator pubkey or any of its active delegatees to sign constraints.
-        let signing_pubkey = if self.unsafe_skip_consensus_checks {
+        // The next candidates are used if signing with the first one fails.
+        let consensus_start = Instant::now();
+        let consensus_span = info_span!(parent: span, "consensus_validation", target_slot);
+        let consensus_entered = consensus_span.enter();
+        let mut registered_gas_limit = None;
+        let signing_pubkeys = if self.unsafe_skip_consensus_checks {
             // PERF: this is inefficient, but it's only used for testing purposes.
-            let mut ap = available_pubkeys.iter().collect::<Vec<_>>();
+            let mut ap = available_pubkeys.into_iter().collect::<Vec<_>>();
             ap.sort();
-            ap.first().cloned().cloned().expect("at least one available pubkey")
+            ap
         } else {
-            let validator_pubkey = match self.consensus.validate_request(&inclusion_request) {
+            let validator_pubkey = match self.consensus.validate_request(inclusion_request) {
                 Ok(pubkey) => pubkey,
                 Err(err) => {
                     warn!(?err, "Consensus: failed to validate request");
-                    let _ = response.send(Err(CommitmentError::Consensus(err)));
-                    return;
+                    return Err(CommitmentError::Consensus(err));
                 }
             };
+            registered_gas_limit = self.registered_gas_limits.get(&validator_pubkey);
 
-            // Find a public key to sign new constraints with for this slot.
-            // This can either be the validator pubkey or a delegatee (if one is available).
-            let Some(signing_key) =
-                self.constraints_client.find_signing_key(validator_pubkey, available_pubkeys)
-            else {
-                error!(%target_slot, "No available public key to sign constraints with");
-                let _ = response.send(Err(CommitmentError::Internal));
-                return;
-            };
+            // Find the public keys to sign new constraints with for this slot.
+            // These can either be the validator pubkey or its delegatees (if available).
+            self.constraints_client.find_signing_keys(&validator_pubkey, &available_pubkeys)
+        };
+        if signing_pubkeys.is_empty() {
+            error!(%target_slot, "No available public key to sign constraints with");
+            return Err(CommitmentError::Internal);
+        }
+        ApiMetrics::observe_validation_duration("consensus", consensus_start.elapsed());
+        drop(consensus_entered);
+
+        // The gas committed in the slot can't exceed the gas limit registered by its proposer
+        if let Some(gas_limit) = registered_gas_limit {
+            self.execution.lock().await.set_registered_gas_limit(target_slot, gas_limit);
+        }
+
+        // Requests referencing transactions already in the mempool need them before anything
+        // else can be checked
+        if inclusion_request.is_by_hash() {
+            let resolved = self
+                .execution
+                .resolve_transactions(inclusion_request)
+                .instrument(info_span!(parent: span, "resolve_transactions", target_slot))
+                .await;
+
+            if let Err(err) = resolved {
+                warn!(?err, "Failed to fetch the transactions of the request from the mempool");
+                ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
+                return Err(CommitmentError::Validation(err));
+            }
+        }
 
-            signing_key
+        let collateral = match self.collateral.check(inclusion_request.value()) {
+            Ok(collateral) => collateral,
+            Err(err) => {
+                warn!(?err, "Collateral: failed to validate request");
+                ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
+                return Err(CommitmentError::Validation(err));
+            }
         };
 
-        if let Err(err) = self.execution.validate_request(&mut inclusion_request).await {
-            warn!(?err, "Execution: failed to validate request");
-            ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
-            let _ = response.send(Err(CommitmentError::Validation(err)));
+        // The senders are needed to keep the requests of every sender in order
+        if let Err(err) = inclusion_request.recover_signers() {
+            warn!(?err, "Failed to recover the signers of the request");
+            return Err(CommitmentError::Validation(err.into()));
+        }
+
+        Ok((signing_pubkeys, collateral))
+    }
+
+    /// Handle a dry-run validation request, running the same checks as for a commitment
+    /// request and responding with the verdict. Nothing is signed nor reserved for it.
+    async fn handle_validation_event(&mut self, event: ValidationEvent) {
+        let ValidationEvent { request_id: _, mut request, response, span } = event;
+        info!(parent: &span, target_slot = request.slot, "Received new dry-run validation request");
+
+        let checked = self.check_request(&mut request, &span).instrument(span.clone()).await;
+        if let Err(err) = checked {
+            let _ = response.send(Err(err));
             return;
         }
 
-        info!(
-            target_slot,
-            elapsed = ?start.elapsed(),
-            "Validation against execution state passed"
+        let senders = request.txs.iter().filter_map(|tx| tx.sender().copied());
+        let mut turn = self.sender_queue.enqueue(senders);
+
+        let permit = Arc::clone(&self.worker_permits)
+            .try_acquire_owned()
+            .expect("validation events are only received with available permits");
+
+        // Validate the request after the previous requests of the same senders, so that the
+        // verdict accounts for them
+        let deadline = self.consensus.commitment_deadline_at(request.slot);
+        let worker = Arc::clone(&self.worker);
+        tokio::spawn(
+            async move {
+                turn.wait().await;
+                worker.dry_run(request, deadline, response).await;
+                drop((turn, permit));
+            }
+            .instrument(span),
         );
+    }
 
-        // NOTE: we iterate over the transactions in the request and generate a signed constraint
-        // for each one. This is because the transactions in the commitment request are not supposed
-        // to be treated as a relative-ordering bundle, but a batch with no ordering guarantees.
-        //
-        // For more information, check out the constraints API docs:
-        // https://docs.boltprotocol.xyz/technical-docs/api/builder#constraints
-        for tx in inclusion_request.txs.iter() {
-            let tx_type = tx.tx_type();
-            let message =
-                ConstraintsMessage::from_tx(signing_pubkey.clone(), target_slot, tx.clone());
-            let digest = message.digest();
-
-            let signature_result = match &self.constraint_signer {
-                SignerBLS::Local(signer) => signer.sign_commit_boost_root(digest),
-                SignerBLS::CommitBoost(signer) => signer.sign_commit_boost_root(digest).await,
-                SignerBLS::Keystore(signer) => {
-                    signer.sign_commit_boost_root(digest, &signing_pubkey)
-                }
-            };
+    /// Handle a new head event, updating the execution state.
+    fn handle_new_head_event(&mut self, head_event: HeadEvent) {
+        let slot = head_event.slot;
+        info!(slot, "Received new head event");
 
-            let signed_constraints = match signature_result {
-                Ok(signature) => SignedConstraints { message, signature },
-                Err(e) => {
-                    error!(?e, "Failed to sign constraints");
-                    let _ = response.send(Err(CommitmentError::Internal));
-                    return;
-                }
-            };
+        // The arrival of the heads reveals the offset of the local clock the slots follow
+        self.consensus.record_head(slot, Instant::now());
 
-            ApiMetrics::increment_transactions_preconfirmed(tx_type);
-            self.execution.add_constraint(target_slot, signed_constraints);
-        }
+        self.chain_head.update_slot(slot);
 
-        // Create a commitment by signing the request
-        match inclusion_request.commit_and_sign(&self.commitment_signer).await {
-            Ok(commitment) => {
-                debug!(target_slot, elapsed = ?start.elapsed(), "Commitment signed and sent");
-                response.send(Ok(SignedCommitment::Inclusion(commitment))).ok()
+        // Beacon head events don't carry the execution block number: the execution state waits
+        // for the EL to import a new block, as it may lag behind the CL. That happens in its own
+        // task so that the driver loop keeps running meanwhile, after the previous update to
+        // keep the heads in order.
+        let previous = self.head_update.take();
+        let execution = self.execution.clone();
+        let execution_heads_tx = self.execution_heads_tx.clone();
+        let update = async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
             }
-            Err(err) => {
-                error!(?err, "Failed to sign commitment");
-                response.send(Err(CommitmentError::Internal)).ok()
+
+            if let Err(e) = execution.update_head(slot, EXECUTION_HEAD_TIMEOUT).await {
+                error!(err = ?e, "Failed to update execution state head");
+                return;
             }
+
+            let block_number = execution.lock().await.block_number();
+            let _ = execution_heads_tx.send(block_number);
         };
+        self.head_update = Some(tokio::spawn(update.instrument(info_span!("update_head", slot))));
+    }
 
-        ApiMetrics::increment_inclusion_commitments_accepted();
+    /// Handle the execution state reaching a new head block, retrying the queued requests.
+    fn handle_execution_head(&mut self, block_number: u64) {
+        self.chain_head.update_block(block_number);
+        self.retry_queued_requests();
     }
 
-    /// Handle a new head event, updating the execution state.
-    async fn handle_new_head_event(&mut self, head_event: HeadEvent) {
-        let slot = head_event.slot;
-        info!(slot, "Received new head event");
+    /// Process the requests waiting in the nonce gap queue again, in order with the other
+    /// requests of their senders: their earlier nonces may have been mined in the new head.
+    fn retry_queued_requests(&mut self) {
+        for job in self.worker.take_queued() {
+            let senders = job.request.txs.iter().filter_map(|tx| tx.sender().copied());
+            let mut turn = self.sender_queue.enqueue(senders);
+
+            let worker = Arc::clone(&self.worker);
+            tokio::spawn(async move {
+                turn.wait().await;
+                worker.process(job).await;
+                drop(turn);
+            });
+        }
+    }
 
-        // We use None to signal that we want to fetch the latest EL head
-        if let Err(e) = self.execution.update_head(None, slot).await {
-            error!(err = ?e, "Failed to update execution state head");
+    /// Handle a new finalized checkpoint event, updating the finalized and safe checkpoints
+    /// of the chain head and pruning the records older than finalization.
+    async fn handle_finalized_checkpoint_event(&mut self, event: FinalizedCheckpointEvent) {
+        let slot = finalized_slot(&event);
+        info!(slot, epoch = event.epoch, "Received new finalized checkpoint");
+
+        // The event only carries the beacon block root: fetch the block numbers from the EL
+        let (finalized_block, safe_block) = match self.execution.get_checkpoint_blocks().await {
+            Ok(blocks) => blocks,
+            Err(err) => {
+                error!(?err, "Failed to fetch the finalized and safe blocks");
+                (self.chain_head.finalized_block(), self.chain_head.safe_block())
+            }
+        };
+
+        if self.chain_head.update_finalized(slot, finalized_block) {
+            ApiMetrics::set_finalized_checkpoint(slot, self.chain_head.finalized_block());
+            self.sealed_slots.prune_finalized(slot);
+        }
+
+        if self.chain_head.update_safe_block(safe_block) {
+            ApiMetrics::set_safe_block(safe_block);
         }
     }
 
     /// Handle a commitment deadline event, submitting constraints to the Constraints client service
     /// and starting to build a local payload for the given target slot.
     async fn handle_commitment_deadline(&mut self, slot: u64) {
-        let Some(template) = self.execution.get_block_template(slot) else {
+        // The requests still waiting for an earlier nonce can't be committed anymore
+        self.worker.reject_expired(slot);
+
+        let (template, registered_gas_limit) = {
+            let mut execution = self.execution.lock().await;
+            execution.seal_slot(slot);
+            (execution.get_block_template(slot).cloned(), execution.registered_gas_limit(slot))
+        };
+        let committed_transactions = self.worker.take_committed_transactions(slot);
+        let Some(template) = template else {
             // Nothing to do then. Block templates are created only when constraints are added,
             // which means we haven't issued any commitment for this slot because we are
             // (probably) not the proposer for this block.
+            self.sealed_slots.seal(slot, &[]);
+
+            // Unless commitments were issued, which are now broken
+            if committed_transactions > 0 {
+                error!(
+                    slot,
+                    transactions = committed_transactions,
+                    "No block template found for a slot with commitments, no constraints submitted"
+                );
+                let reason = SubmissionFailure::NoTemplate.as_str();
+                ApiMetrics::increment_constraints_submission_failed(reason);
+            }
             return;
         };
+        let transactions = template.transactions_len();
+
+        // No more constraints can be added to the slot: let external builders know
+        self.sealed_slots.seal(slot, &template.signed_constraints_list);
 
         info!(slot, "Commitment deadline reached, building local block");
 
-        if let Err(e) = self.local_builder.build_new_local_payload(slot, template).await {
-            error!(err = ?e, "Error while building local payload at deadline for slot {slot}");
+        let deadline_span = info_span!("commitment_deadline", slot);
+        let build_result = self
+            .local_builder
+            .build_new_local_payload(slot, &template, registered_gas_limit)
+            .instrument(info_span!(parent: &deadline_span, "build_payload", slot))
+            .await;
+        if let Err(e) = build_result {
+            error!(
+                err = ?e,
+                transactions,
+                "Error while building local payload at deadline for slot {slot}"
+            );
+            ApiMetrics::increment_local_payload_build_failed();
+            ApiMetrics::increment_constraints_submission_failed(
+                SubmissionFailure::BuildFailed.as_str(),
+            );
         };
 
         let constraints = Arc::new(template.signed_constraints_list.clone());
         let constraints_client = self.constraints_client.clone();
+        let submit_span = info_span!(
+            parent: &deadline_span,
+            "submit_constraints",
+            relay = %constraints_client.target(),
+            count = constraints.len()
+        );
 
-        // Submit constraints to the constraints service with an exponential retry mechanism.
-        tokio::spawn(retry_with_backoff(10, move || {
-            let constraints_client = constraints_client.clone();
-            let constraints = Arc::clone(&constraints);
-            async move {
-                match constraints_client.submit_constraints(constraints.as_ref()).await {
-                    Ok(_) => Ok(()),
-                    Err(e) => {
-                        error!(err = ?e, "Failed to submit constraints, retrying...");
-                        Err(e)
-                    }
-                }
-            }
-        }));
+        // Submit constraints to the constraints service with an exponential retry mechanism,
+        // and report the outcome back to the driver loop
+        let results_tx = self.submission_results_tx.clone();
+        let worker = self.worker.clone();
+        let submission = async move {
+            let constraints = worker.consolidate_constraints(constraints).await;
+            let (report, result) = submit_constraints_with_retries(
+                &constraints_client,
+                slot,
+                constraints,
+                CONSTRAINTS_SUBMISSION_RETRIES,
+            )
+            .await;
+            let _ = results_tx.send(SubmissionOutcome { transactions, report, result });
+        };
+        tokio::spawn(submission.instrument(submit_span));
     }
 
     /// Handle a fetch payload request, responding with the local payload if available.
     fn handle_fetch_payload_request(&mut self, request: FetchPayloadRequest) {
-        info!(slot = request.slot, "Received local payload request");
+        let FetchPayloadRequest { slot, signed_blinded_block, response_tx } = request;
+        info!(slot, "Received local payload request");
 
-        let Some(payload_and_bid) = self.local_builder.get_cached_payload() else {
-            warn!(slot = request.slot, "No local payload found");
-            let _ = request.response_tx.send(None);
-            return;
-        };
+        let payload_and_bid =
+            match self.local_builder.get_cached_payload(slot, signed_blinded_block.as_deref()) {
+                Ok(Some(payload_and_bid)) => payload_and_bid,
+                Ok(None) => {
+                    warn!(slot, "No local payload found");
+                    let _ = response_tx.send(None);
+                    return;
+                }
+                Err(err) => {
+                    error!(slot, %err, "Local payload doesn't match the request, not serving it");
+                    let _ = response_tx.send(None);
+                    return;
+                }
+            };
 
-        if let Err(e) = request.response_tx.send(Some(payload_and_bid)) {
+        if let Err(e) = response_tx.send(Some(payload_and_bid)) {
             error!(err = ?e, "Failed to send payload and bid in response channel");
         }
     }
 }
 
-impl fmt::Debug for SidecarDriver<StateClient, PrivateKeySigner> {
+impl fmt::Debug for SidecarDriver<SidecarStateClient, CommitmentSigner> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SidecarDriver")
             .field("head_tracker", &self.head_tracker)
             .field("execution", &self.execution)
             .field("consensus", &self.consensus)
             .field("constraint_signer", &self.constraint_signer)
-            .field("commitment_signer", &self.commitment_signer)
             .field("local_builder", &self.local_builder)
             .field("constraints_client", &self.constraints_client)
             .field("api_events_rx", &self.api_events_rx)
+            .field("validation_events_rx", &self.validation_events_rx)
             .field("payload_requests_rx", &self.payload_requests_rx)
+            .field("worker", &self.worker)
+            .field("health", &self.health)
+            .field("collateral", &self.collateral)
+            .field("sealed_slots", &self.sealed_slots)
+            .field("chain_head", &self.chain_head)
             .finish()
     }
 }
+
+/// A commitment request that passed the consensus and collateral checks, waiting to be
+/// validated against the execution state and signed.
+#[derive(Debug)]
+struct CommitmentJob {
+    /// The unique ID assigned to the request by the commitments API.
+    request_id: RequestId,
+    /// The span of the request, under which it's processed even when released from the nonce
+    /// gap queue by another request.
+    span: Span,
+    request: InclusionRequest,
+    /// The candidate pubkeys to sign the constraints with, in order of preference.
+    signing_pubkeys: Vec<BlsPublicKey>,
+    collateral: Option<CollateralAttestation>,
+    response: oneshot::Sender<Result<SignedCommitment, CommitmentError>>,
+    start: Instant,
+    /// The commitment deadline of the target slot, past which the request is aborted.
+    deadline: Instant,
+}
+
+/// Validates commitment requests against the execution state and signs them, outside of the
+/// driver event loop so that a slow request doesn't hold back the other events.
+#[derive(Debug)]
+struct CommitmentWorker<C, ECDSA> {
+    execution: SharedExecutionState<C>,
+    constraint_signer: SignerBLS,
+    commitment_signer: ECDSA,
+    constraints_client: ConstraintsClient,
+    audit_log: Option<Mutex<AuditLog>>,
+    archive: Option<CommitmentArchive>,
+    legacy_commitment_format: bool,
+    /// Whether to merge the constraints of a slot before submitting them to the relay.
+    merge_constraints: bool,
+    /// The requests waiting for an earlier nonce of their sender, along with the error they
+    /// are rejected with if it's not committed in time. `None` if disabled.
+    nonce_gaps: Option<Mutex<NonceGapQueue<(CommitmentJob, ValidationError)>>>,
+    /// The number of transactions committed for each upcoming slot, to detect the commitments
+    /// whose block template went missing at the commitment deadline.
+    committed_transactions: Mutex<HashMap<u64, usize>>,
+}
+
+impl<C: StateFetcher, ECDSA: SignerECDSA> CommitmentWorker<C, ECDSA> {
+    /// Process a commitment request, responding with a commitment or an error. The queued
+    /// requests waiting for the nonces it commits are processed next, in nonce order.
+    async fn process(&self, job: CommitmentJob) {
+        let mut jobs = VecDeque::from([job]);
+        while let Some(job) = jobs.pop_front() {
+            let span = job.span.clone();
+            jobs.extend(self.process_one(job).instrument(span).await);
+        }
+    }
+
+    /// Validate a request against the execution state like [Self::process], responding with
+    /// its verdict instead of a commitment: nothing is signed nor added to the templates.
+    /// Requests waiting for an earlier nonce of their sender are rejected right away.
+    async fn dry_run(
+        &self,
+        mut request: InclusionRequest,
+        deadline: Instant,
+        response: oneshot::Sender<Result<ValidationVerdict, CommitmentError>>,
+    ) {
+        let target_slot = request.slot;
+        let verdict = match check_deadline(deadline, "execution_validation") {
+            Ok(()) => self
+                .execution
+                .dry_run(&mut request)
+                .instrument(info_span!("execution_validation", target_slot))
+                .await
+                .map_err(CommitmentError::Validation),
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = &verdict {
+            debug!(?err, target_slot, "Dry-run validation failed");
+        }
+        let _ = response.send(verdict);
+    }
+
+    /// Process a single commitment request, responding with a commitment or an error, unless
+    /// it's queued waiting for an earlier nonce of its sender. Returns the queued requests of
+    /// the same senders and slot if it's committed.
+    async fn process_one(&self, mut job: CommitmentJob) -> Vec<CommitmentJob> {
+        // The client may have given up on a request waiting in the nonce gap queue
+        if job.response.is_closed() {
+            debug!(target_slot = job.request.slot, "Client gone, dropping request");
+            return Vec::new();
+        }
+
+        let target_slot = job.request.slot;
+
+        // The request is aborted as soon as it can't make the deadline of its slot: past it,
+        // the constraints of the slot are already submitted and would never be honored
+        if let Err(err) = check_deadline(job.deadline, "execution_validation") {
+            let _ = job.response.send(Err(err));
+            return Vec::new();
+        }
+
+        let execution_start = Instant::now();
+        let execution_result = self
+            .execution
+            .validate_request(&mut job.request)
+            .instrument(info_span!("execution_validation", target_slot))
+            .await;
+        ApiMetrics::observe_validation_duration("execution", execution_start.elapsed());
+
+        if let Err(err) = execution_result {
+            let Some((job, err)) = self.queue_nonce_gap(job, err) else {
+                return Vec::new();
+            };
+            warn!(?err, "Execution: failed to validate request");
+            ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
+            let _ = job.response.send(Err(CommitmentError::Validation(err)));
+            return Vec::new();
+        }
+
+        let CommitmentJob {
+            request_id,
+            span: _,
+            request: inclusion_request,
+            signing_pubkeys,
+            collateral,
+            response,
+            start,
+            deadline,
+        } = job;
+        let request_digest = inclusion_request.digest();
+
+        info!(
+            target_slot,
+            elapsed = ?start.elapsed(),
+            "Validation against execution state passed"
+        );
+
+        if let Err(err) = check_deadline(deadline, "signing") {
+            let _ = response.send(Err(err));
+            return Vec::new();
+        }
+
+        // Sign all the constraints before touching the execution state: if any signature fails,
+        // the request is rejected without leaving partial constraints in the block template
+        let signing = self.sign_constraints_with_fallback(&inclusion_request, &signing_pubkeys);
+        let (signing_pubkey, signed_constraints, committed_constraints) = match signing.await {
+            Ok(constraints) => constraints,
+            Err(err) => {
+                let _ = response.send(Err(err));
+                return Vec::new();
+            }
+        };
+
+        // Keep a copy of the signed constraints to archive along with the commitment
+        let archived_constraints = self.archive.as_ref().map(|_| {
+            let digests = committed_constraints.iter().map(|c| c.digest).collect::<Vec<_>>();
+            (signed_constraints.clone(), digests)
+        });
+
+        // The signatures are written to the audit log once the request is committed
+        let audited_signatures = self.audit_log.as_ref().map(|_| {
+            committed_constraints
+                .iter()
+                .zip(&signed_constraints)
+                .map(|(committed, signed)| (committed.digest, signed.signature))
+                .collect::<Vec<_>>()
+        });
+
+        // Signing the constraints may have taken long enough for the request to expire
+        if let Err(err) = check_expiry(&inclusion_request, "signing") {
+            let _ = response.send(Err(err));
+            return Vec::new();
+        }
+
+        // Create a commitment by signing the request, along with the constraints and the
+        // collateral unless the legacy format is requested, which only signs the request
+        // NOTE: the request is still needed to add its constraints to the template
+        let commitment = if self.legacy_commitment_format {
+            inclusion_request.clone().commit_and_sign(&self.commitment_signer).await.map(
+                |mut commitment| {
+                    if let Some(collateral) = collateral {
+                        commitment.set_collateral(collateral);
+                    }
+                    commitment
+                },
+            )
+        } else {
+            inclusion_request
+                .clone()
+                .commit_and_sign_with_constraints(
+                    &self.commitment_signer,
+                    committed_constraints,
+                    collateral,
+                )
+                .await
+        };
+
+        let mut commitment = match commitment {
+            Ok(commitment) => commitment,
+            Err(err) => {
+                error!(?err, "Failed to sign commitment");
+                let _ = response.send(Err(CommitmentError::Internal));
+                return Vec::new();
+            }
+        };
+
+        // The commitment is signed as well before adding the constraints, so that a failure
+        // never leaves constraints without a commitment. Other requests may have filled the
+        // slot while this one was being processed: the capacity is checked again before adding
+        // the constraints. The deadline is checked under the lock, so that the slot can't be
+        // sealed before the constraints are added, and so is the expiry of the request, which
+        // may have passed while signing it.
+        let commit_result = {
+            let mut execution = self.execution.lock().await;
+            match check_deadline(deadline, "commit")
+                .and_then(|()| check_expiry(&inclusion_request, "commit"))
+            {
+                Ok(()) => execution
+                    .commit_request(&inclusion_request, signed_constraints)
+                    .map(|()| execution.slot_capacity(target_slot).ok())
+                    .map_err(CommitmentError::Validation),
+                Err(err) => Err(err),
+            }
+        };
+        let slot_capacity = match commit_result {
+            Ok(slot_capacity) => slot_capacity,
+            Err(err) => {
+                if let CommitmentError::Validation(err) = &err {
+                    warn!(?err, "Execution: failed to commit request");
+                    ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
+                }
+                let _ = response.send(Err(err));
+                return Vec::new();
+            }
+        };
+
+        for (digest, signature) in audited_signatures.into_iter().flatten() {
+            self.audit_signature(target_slot, &signing_pubkey, digest.0, signature, request_digest);
+        }
+
+        // The capacity left in the slot, including this request, is returned alongside the
+        // signed commitment
+        if let Some(slot_capacity) = slot_capacity {
+            commitment.set_slot_capacity(slot_capacity);
+        }
+
+        for tx in inclusion_request.txs.iter() {
+            ApiMetrics::increment_transactions_preconfirmed(tx.tx_type());
+        }
+
+        if let (Some(archive), Some((signed_constraints, digests))) =
+            (&self.archive, archived_constraints)
+        {
+            let received_at = SystemTime::now().checked_sub(start.elapsed()).unwrap_or(UNIX_EPOCH);
+            archive.archive(
+                ArchivedCommitment::new(
+                    target_slot,
+                    received_at,
+                    self.commitment_signer.public_key(),
+                    signing_pubkey,
+                    digests,
+                    commitment.clone(),
+                    signed_constraints,
+                )
+                .with_request_id(request_id),
+            );
+        }
+
+        debug!(target_slot, elapsed = ?start.elapsed(), "Commitment signed and sent");
+        let _ = response.send(Ok(SignedCommitment::Inclusion(commitment)));
+
+        ApiMetrics::increment_inclusion_commitments_accepted();
+        *self.committed_transactions.lock().entry(target_slot).or_default() +=
+            inclusion_request.txs.len();
+
+        // The requests waiting for the committed nonces can go through now
+        let Some(nonce_gaps) = &self.nonce_gaps else {
+            return Vec::new();
+        };
+        let senders = inclusion_request.txs.iter().filter_map(|tx| tx.sender().copied());
+        let mut nonce_gaps = nonce_gaps.lock();
+        senders
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .flat_map(|sender| nonce_gaps.take(sender, target_slot))
+            .map(|(job, _)| job)
+            .collect()
+    }
+
+    /// Queue a request rejected with [ValidationError::NonceTooHigh], if enabled, to process
+    /// it again once the earlier nonces of its sender are committed for the same slot. The
+    /// request is keyed by the sender and nonce of its first transaction.
+    ///
+    /// Returns the request and its error if it's not queued.
+    fn queue_nonce_gap(
+        &self,
+        job: CommitmentJob,
+        err: ValidationError,
+    ) -> Option<(CommitmentJob, ValidationError)> {
+        let (Some(nonce_gaps), ValidationError::NonceTooHigh { .. }) = (&self.nonce_gaps, &err)
+        else {
+            return Some((job, err));
+        };
+        let Some((sender, nonce)) =
+            job.request.txs.first().and_then(|tx| Some((*tx.sender()?, tx.nonce())))
+        else {
+            return Some((job, err));
+        };
+
+        let slot = job.request.slot;
+        match nonce_gaps.lock().push(sender, slot, nonce, (job, err)) {
+            Ok(()) => {
+                debug!(%sender, nonce, slot, "Queued request waiting for an earlier nonce");
+                None
+            }
+            Err(rejected) => Some(rejected),
+        }
+    }
+
+    /// Take the number of transactions committed for the given slot, forgetting the ones
+    /// committed for earlier slots.
+    fn take_committed_transactions(&self, slot: u64) -> usize {
+        let mut committed_transactions = self.committed_transactions.lock();
+        let count = committed_transactions.remove(&slot).unwrap_or_default();
+        committed_transactions.retain(|committed_slot, _| *committed_slot > slot);
+        count
+    }
+
+    /// Take all the requests waiting in the nonce gap queue.
+    fn take_queued(&self) -> Vec<CommitmentJob> {
+        let Some(nonce_gaps) = &self.nonce_gaps else {
+            return Vec::new();
+        };
+        nonce_gaps.lock().take_all().into_iter().map(|(job, _)| job).collect()
+    }
+
+    /// Reject the requests waiting in the nonce gap queue for the given slot or an earlier
+    /// one, whose earlier nonces haven't been committed before the commitment deadline.
+    fn reject_expired(&self, slot: u64) {
+        let Some(nonce_gaps) = &self.nonce_gaps else {
+            return;
+        };
+
+        for (job, err) in nonce_gaps.lock().take_expired(slot) {
+            warn!(
+                parent: &job.span,
+                ?err,
+                slot = job.request.slot,
+                "Earlier nonce not committed before the deadline"
+            );
+            ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
+            let _ = job.response.send(Err(CommitmentError::Validation(err)));
+        }
+    }
+
+    /// Sign the constraints of the request like [Self::sign_constraints], with the first of the
+    /// candidate pubkeys that works. On errors specific to a key, e.g. a key missing from the
+    /// signer, the next candidate is tried, up to [MAX_SIGNING_ATTEMPTS] keys. All the
+    /// constraints of the request are signed with the same key, which is returned.
+    async fn sign_constraints_with_fallback(
+        &self,
+        inclusion_request: &InclusionRequest,
+        signing_pubkeys: &[BlsPublicKey],
+    ) -> Result<(BlsPublicKey, Vec<SignedConstraints>, Vec<CommittedConstraint>), CommitmentError>
+    {
+        let backend = self.constraint_signer.backend();
+        for (attempt, signing_pubkey) in
+            signing_pubkeys.iter().take(MAX_SIGNING_ATTEMPTS).enumerate()
+        {
+            match self.sign_constraints(inclusion_request, signing_pubkey).await {
+                Ok((signed_constraints, committed_constraints)) => {
+                    if attempt > 0 {
+                        info!(%signing_pubkey, attempt, "Signed constraints with a fallback key");
+                        ApiMetrics::increment_signing_key_fallbacks(backend);
+                    } else {
+                        debug!(%signing_pubkey, count = signed_constraints.len(), "Signed constraints");
+                    }
+                    return Ok((signing_pubkey.clone(), signed_constraints, committed_constraints));
+                }
+                Err(err) if err.is_key_specific() => {
+                    warn!(?err, %signing_pubkey, "Failed to sign constraints, trying the next key");
+                }
+                Err(err) => {
+                    error!(?err, "Failed to sign constraints, rejecting the request");
+                    return Err(CommitmentError::Internal);
+                }
+            }
+        }
+
+        error!("Failed to sign constraints with every candidate key, rejecting the request");
+        Err(CommitmentError::Internal)
+    }
+
+    /// Sign a constraint for every transaction of the request with the given pubkey, along with
+    /// their digests to include in the commitment. Fails if any of the signatures fails.
+    async fn sign_constraints(
+        &self,
+        inclusion_request: &InclusionRequest,
+        signing_pubkey: &BlsPublicKey,
+    ) -> SignerResult<(Vec<SignedConstraints>, Vec<CommittedConstraint>)> {
+        // NOTE: we iterate over the transactions in the request and generate a signed constraint
+        // for each one. This is because the transactions in the commitment request are not supposed
+        // to be treated as a relative-ordering bundle, but a batch with no ordering guarantees.
+        //
+        // For more information, check out the constraints API docs:
+        // https://docs.boltprotocol.xyz/technical-docs/api/builder#constraints
+        let target_slot = inclusion_request.slot;
+        let digest_version = self.constraints_client.constraints_digest_version();
+        let mut signed_constraints = Vec::with_capacity(inclusion_request.txs.len());
+        let mut committed_constraints = Vec::with_capacity(inclusion_request.txs.len());
+        for (index, tx) in inclusion_request.txs.iter().enumerate() {
+            let message =
+                ConstraintsMessage::from_tx(signing_pubkey.clone(), target_slot, tx.clone())
+                    .with_top(inclusion_request.top);
+            let digest = message.digest_with(digest_version);
+
+            let signing_start = Instant::now();
+            let signer = self.constraint_signer.backend();
+            let signature_result = self
+                .sign_digest(digest, signing_pubkey)
+                .instrument(info_span!("sign_constraint", signer, index))
+                .await;
+            ApiMetrics::observe_constraint_signing(signer, signing_start.elapsed());
+
+            let signature = signature_result?;
+
+            committed_constraints.push(CommittedConstraint {
+                slot: target_slot,
+                pubkey: signing_pubkey.clone(),
+                digest: digest.into(),
+            });
+            signed_constraints.push(SignedConstraints { message, signature });
+        }
+
+        Ok((signed_constraints, committed_constraints))
+    }
+
+    /// Returns the constraints to submit for a slot. When enabled, the constraints sharing the
+    /// same pubkey are merged into a single message signed again with that key, falling back
+    /// to the original constraints if any of the signatures fails.
+    async fn consolidate_constraints(
+        &self,
+        constraints: Arc<BatchedSignedConstraints>,
+    ) -> Arc<BatchedSignedConstraints> {
+        if !self.merge_constraints {
+            return constraints;
+        }
+
+        match self.sign_merged_constraints(&constraints).await {
+            Ok(merged) => {
+                debug!(original = constraints.len(), merged = merged.len(), "Merged constraints");
+                Arc::new(merged)
+            }
+            Err(err) => {
+                error!(?err, "Failed to sign the merged constraints, submitting them unmerged");
+                constraints
+            }
+        }
+    }
+
+    /// Merge the given constraints with [merge_constraints] and sign the resulting messages
+    /// with their pubkey. The audit log records these signatures without a request ID.
+    async fn sign_merged_constraints(
+        &self,
+        constraints: &[SignedConstraints],
+    ) -> SignerResult<BatchedSignedConstraints> {
+        let digest_version = self.constraints_client.constraints_digest_version();
+        let messages = merge_constraints(constraints);
+        let mut signed_constraints = Vec::with_capacity(messages.len());
+        for message in messages {
+            let digest = message.digest_with(digest_version);
+
+            let signing_start = Instant::now();
+            let signer = self.constraint_signer.backend();
+            let signature = self
+                .sign_digest(digest, &message.pubkey)
+                .instrument(info_span!("sign_merged_constraint", signer, slot = message.slot))
+                .await;
+            ApiMetrics::observe_constraint_signing(signer, signing_start.elapsed());
+            let signature = signature?;
+
+            self.audit_signature(message.slot, &message.pubkey, digest, signature, B256::ZERO);
+            signed_constraints.push(SignedConstraints { message, signature });
+        }
+
+        Ok(signed_constraints)
+    }
+
+    /// Sign the digest of a constraints message with the given pubkey of the constraint signer.
+    async fn sign_digest(
+        &self,
+        digest: [u8; 32],
+        signing_pubkey: &BlsPublicKey,
+    ) -> SignerResult<BLSSig> {
+        match &self.constraint_signer {
+            SignerBLS::Local(signer) => signer.sign_commit_boost_root(digest),
+            SignerBLS::CommitBoost(signer) => {
+                signer.sign_commit_boost_root(digest, signing_pubkey).await
+            }
+            SignerBLS::Keystore(signer) => signer.sign_commit_boost_root(digest, signing_pubkey),
+        }
+    }
+
+    /// Record a constraint signature in the audit log, if enabled.
+    fn audit_signature(
+        &self,
+        slot: u64,
+        pubkey: &BlsPublicKey,
+        digest: [u8; 32],
+        signature: BLSSig,
+        request_digest: B256,
+    ) {
+        let Some(audit_log) = &self.audit_log else {
+            return;
+        };
+
+        let entry = AuditEntry::new(
+            slot,
+            pubkey.clone(),
+            digest.into(),
+            signature,
+            request_digest,
+            self.constraint_signer.backend(),
+        );
+        if let Err(err) = audit_log.lock().append(&entry) {
+            error!(?err, "Failed to write signature to the audit log");
+        }
+    }
+}
+
+/// Returns a [CommitmentError::DeadlineExceeded] error if the given commitment deadline has
+/// passed, recording the processing stage the request is aborted at.
+fn check_deadline(deadline: Instant, stage: &'static str) -> Result<(), CommitmentError> {
+    if Instant::now() < deadline {
+        return Ok(());
+    }
+
+    warn!(stage, late_by = ?deadline.elapsed(), "Commitment deadline exceeded, aborting request");
+    ApiMetrics::increment_deadline_aborts(stage);
+    Err(CommitmentError::DeadlineExceeded { stage })
+}
+
+/// Returns a [CommitmentError::RequestExpired] error if the expiry set by the user in the
+/// request has passed, recording the processing stage the request is rejected at.
+fn check_expiry(request: &InclusionRequest, stage: &'static str) -> Result<(), CommitmentError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    let Some(expires_at) = request.expires_at.filter(|_| request.is_expired_at(now)) else {
+        return Ok(());
+    };
+
+    warn!(stage, expires_at, late_by_ms = now - expires_at, "Request expired, rejecting it");
+    ApiMetrics::increment_expired_requests(stage);
+    Err(CommitmentError::RequestExpired { expires_at, stage })
+}
+
+/// Keeps the commitment requests of every sender in order while they are processed
+/// concurrently: a request waits until the previous requests of all its senders are done.
+///
+/// Without it, two requests of the same sender could be validated against the same nonce.
+#[derive(Debug, Default)]
+struct SenderQueue(HashMap<Address, oneshot::Receiver<()>>);
+
+impl SenderQueue {
+    /// Enqueue a request of the given senders, returning its turn.
+    fn enqueue(&mut self, senders: impl IntoIterator<Item = Address>) -> SenderTurn {
+        // Forget the senders without requests in flight
+        self.0.retain(|_, done| matches!(done.try_recv(), Err(TryRecvError::Empty)));
+
+        let senders = senders.into_iter().collect::<HashSet<_>>();
+        let mut turn = SenderTurn { previous: Vec::new(), _release: Vec::new() };
+        for sender in senders {
+            let (tx, rx) = oneshot::channel();
+            if let Some(previous) = self.0.insert(sender, rx) {
+                turn.previous.push(previous);
+            }
+            turn._release.push(tx);
+        }
+
+        turn
+    }
+}
+
+/// The turn of a request in the [SenderQueue]. Dropping it lets the next requests of the same
+/// senders through.
+#[derive(Debug)]
+struct SenderTurn {
+    previous: Vec<oneshot::Receiver<()>>,
+    _release: Vec<oneshot::Sender<()>>,
+}
+
+impl SenderTurn {
+    /// Wait until the previous requests of the same senders are done.
+    async fn wait(&mut self) {
+        // The previous requests never send anything: their senders are dropped when they're done
+        while let Some(previous) = self.previous.last_mut() {
+            let _ = previous.await;
+            self.previous.pop();
+        }
+    }
+}
+
+/// Submit the delegations and revocations of the delegations file to the relay, retrying with
+/// backoff. A failure after all the retries either aborts the startup or is only logged,
+/// depending on the given mode.
+async fn submit_signed_messages(
+    client: &ConstraintsClient,
+    messages: &SignedMessages,
+    on_failure: SubmissionFailureMode,
+) -> eyre::Result<()> {
+    let result = retry_with_backoff(DELEGATIONS_SUBMISSION_RETRIES, || async {
+        client.submit_delegations(&messages.delegations).await?;
+        client.submit_revocations(&messages.revocations).await
+    })
+    .await;
+
+    match result {
+        Ok(()) if client.delegations_supported() => {
+            info!(
+                delegations = messages.delegations.len(),
+                revocations = messages.revocations.len(),
+                relay = client.target(),
+                "Submitted the delegations file to the relay"
+            );
+            Ok(())
+        }
+        Ok(()) => Ok(()),
+        Err(err) => match on_failure {
+            SubmissionFailureMode::Warn => {
+                warn!(?err, "Failed to submit the delegations file to the relay, continuing");
+                Ok(())
+            }
+            SubmissionFailureMode::Abort => {
+                Err(err).wrap_err("Failed to submit the delegations file to the relay")
+            }
+        },
+    }
+}
+
+/// Wait for the beacon node to become reachable, retrying with backoff until the given deadline.
+/// Returns its genesis time and the slot of its head.
+///
+/// Meanwhile, the sidecar runs in degraded mode: the health handle reports that it's waiting
+/// for the beacon node, and the Commitments API rejects the requests accordingly.
+async fn wait_for_beacon(
+    beacon_client: &BeaconClient,
+    health: &HealthHandle,
+    deadline: Duration,
+) -> eyre::Result<(u64, u64)> {
+    health.set_waiting_for_beacon(true);
+
+    let result = retry_until_deadline(deadline, || async {
+        let fetch = async {
+            let genesis = beacon_client.get_genesis_details().await?;
+            let head = beacon_client.get_beacon_header(BlockId::Head).await?;
+            Ok::<_, beacon_api_client::Error>((genesis.genesis_time, head.header.message.slot))
+        };
+
+        fetch.await.inspect_err(|err| warn!(?err, "Beacon node unavailable, waiting for it..."))
+    })
+    .await
+    .wrap_err("Beacon node unavailable at startup");
+
+    if result.is_ok() {
+        info!("Beacon node reachable, leaving degraded mode");
+        health.set_waiting_for_beacon(false);
+    }
+
+    result
+}
+
+/// Returns the public keys of the validators with the excluded indexes, resolved with the beacon
+/// node.
+async fn excluded_validators(
+    beacon_client: &BeaconClient,
+    excluded_indexes: &[u64],
+) -> eyre::Result<HashSet<BlsPublicKey>> {
+    if excluded_indexes.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let ids = excluded_indexes
+        .iter()
+        .map(|index| PublicKeyOrIndex::Index(*index as usize))
+        .collect::<Vec<_>>();
+    let excluded = beacon_client
+        .get_validators(StateId::Head, &ids, &[])
+        .await
+        .wrap_err("Failed to resolve the excluded validator indexes")?
+        .into_iter()
+        .map(|summary| (summary.index as u64, summary.validator.public_key))
+        .collect::<HashMap<_, _>>();
+
+    for index in excluded_indexes.iter().filter(|index| !excluded.contains_key(index)) {
+        warn!(index, "Excluded validator index not found on the beacon node, ignoring");
+    }
+
+    Ok(excluded.into_values().collect())
+}
+
+/// Returns the status of the given validators, along with their delegatees.
+fn validator_statuses(
+    validators: &EffectiveValidators,
+    constraints_client: &ConstraintsClient,
+) -> Vec<ValidatorStatus> {
+    validators
+        .keys()
+        .into_iter()
+        .map(|pubkey| {
+            let delegatees = constraints_client.find_delegatees(&pubkey);
+            ValidatorStatus::new(pubkey, delegatees)
+        })
+        .collect()
+}
+
+/// Spawn a background task keeping the validators in sync with the keys listed by the remote
+/// constraint signer, along with the status of the sidecar, so that the keys added to the
+/// signer are accepted without a restart. The exclusions are applied again on every change.
+fn spawn_validators_sync(
+    mut pubkeys: watch::Receiver<HashSet<BlsPublicKey>>,
+    validators: EffectiveValidators,
+    status: StatusHandle,
+    constraints_client: ConstraintsClient,
+) -> JoinHandle<()> {
+    // The keys listed while starting up are synced right away
+    pubkeys.mark_changed();
+
+    tokio::spawn(async move {
+        while pubkeys.changed().await.is_ok() {
+            let keys = pubkeys.borrow_and_update().clone();
+            if validators.update(keys) {
+                status.set_validators(validator_statuses(&validators, &constraints_client));
+            }
+        }
+    })
+}
+
+/// The terminal outcome of the submission of the constraints of a slot, reported back to the
+/// driver loop by its retry task.
+#[derive(Debug)]
+struct SubmissionOutcome {
+    /// The number of transactions in the block template of the slot.
+    transactions: usize,
+    /// The report of the submission, with the responses of the relay to every attempt.
+    report: SubmissionReport,
+    result: Result<(), BuilderApiError>,
+}
+
+/// The reasons for which the commitments of a slot may not be honored at its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubmissionFailure {
+    /// Commitments were issued for the slot, but its block template is missing.
+    NoTemplate,
+    /// The local payload couldn't be built as a fallback for the slot.
+    BuildFailed,
+    /// The relay refused the constraints.
+    RelayRejected,
+    /// The relay couldn't be reached or failed after all the retries.
+    RetriesExhausted,
+}
+
+impl SubmissionFailure {
+    /// Returns the reason of the failed submission of constraints with the given error.
+    fn from_error(err: &BuilderApiError) -> Self {
+        match err {
+            BuilderApiError::FailedSubmittingConstraints(_) |
+            BuilderApiError::RelayRejected { .. }
+                if !err.is_relay_failure() =>
+            {
+                Self::RelayRejected
+            }
+            _ => Self::RetriesExhausted,
+        }
+    }
+
+    /// Returns the reason as a metric label.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoTemplate => "no_template",
+            Self::BuildFailed => "build_failed",
+            Self::RelayRejected => "relay_rejected",
+            Self::RetriesExhausted => "retries_exhausted",
+        }
+    }
+}
+
+/// Submit the constraints of a slot to the relay, retrying with backoff. Returns the report of
+/// all the attempts, and the error of the last one if all of them failed.
+async fn submit_constraints_with_retries(
+    client: &ConstraintsClient,
+    slot: u64,
+    constraints: Arc<BatchedSignedConstraints>,
+    max_retries: usize,
+) -> (SubmissionReport, Result<(), BuilderApiError>) {
+    let attempts = Mutex::new(Vec::new());
+    let result = retry_with_backoff(max_retries, || async {
+        let start = Instant::now();
+        let result = client.submit_constraints(constraints.as_ref()).await;
+        let latency = start.elapsed();
+        ApiMetrics::observe_constraints_submission(result.is_ok(), latency);
+
+        let mut attempts = attempts.lock();
+        let attempt = SubmissionAttempt::new(attempts.len() + 1, client.target(), latency, &result);
+        if let Err(ref err) = result {
+            ApiMetrics::increment_constraints_submission_attempt_failed(failure_reason(err));
+            warn!(
+                attempt = attempt.attempt,
+                relay = %attempt.relay,
+                status = attempt.status,
+                body = attempt.body.as_deref(),
+                latency_ms = attempt.latency_ms,
+                reason = attempt.reason,
+                ?err,
+                "Failed to submit constraints, retrying..."
+            );
+        }
+        attempts.push(attempt);
+
+        result
+    })
+    .await;
+
+    let report =
+        SubmissionReport { slot, success: result.is_ok(), attempts: attempts.into_inner() };
+    (report, result)
+}
+
+/// Record the outcome of the submission of the constraints of a slot, and keep its report.
+fn handle_submission_outcome(outcome: SubmissionOutcome, reports: &SubmissionReports) {
+    let SubmissionOutcome { transactions, report, result } = outcome;
+    let slot = report.slot;
+    reports.record(report);
+
+    match result {
+        Ok(()) => {
+            info!(slot, transactions, "Submitted constraints to the relay");
+            ApiMetrics::set_last_successful_constraint_submission_slot(slot);
+        }
+        Err(err) => {
+            let reason = SubmissionFailure::from_error(&err);
+            error!(
+                ?err,
+                slot,
+                transactions,
+                reason = reason.as_str(),
+                "Failed to submit constraints, giving up"
+            );
+            ApiMetrics::increment_constraints_submission_failed(reason.as_str());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::Signature as AlloySignature, signers::local::PrivateKeySigner};
+    use alloy_node_bindings::AnvilInstance;
+    use futures::FutureExt;
+
+    use crate::{
+        config::{limits::LimitsOpts, ChainConfig},
+        primitives::commitment::InclusionCommitment,
+        signer::local::LocalSigner,
+        state::StateClient,
+        test_util::{create_signed_inclusion_request, default_test_transaction, launch_anvil},
+    };
+
+    use super::*;
+
+    /// A commitment signer taking its time.
+    #[derive(Debug)]
+    struct SlowSigner(PrivateKeySigner, Duration);
+
+    /// A commitment signer failing on its Nth call.
+    #[derive(Debug)]
+    struct FailingSigner {
+        inner: PrivateKeySigner,
+        fail_on: usize,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl SignerECDSA for FailingSigner {
+        fn public_key(&self) -> Address {
+            SignerECDSA::public_key(&self.inner)
+        }
+
+        async fn sign_hash(&self, hash: &[u8; 32]) -> eyre::Result<AlloySignature> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if call == self.fail_on {
+                eyre::bail!("signer unavailable");
+            }
+            SignerECDSA::sign_hash(&self.inner, hash).await
+        }
+    }
+
+    /// Create a commitment worker with the given commitment signer, against the execution
+    /// state of the given anvil node.
+    async fn test_worker<ECDSA: SignerECDSA>(
+        anvil: &AnvilInstance,
+        commitment_signer: ECDSA,
+    ) -> eyre::Result<CommitmentWorker<StateClient, ECDSA>> {
+        let client = StateClient::new(anvil.endpoint_url());
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        Ok(CommitmentWorker {
+            execution: SharedExecutionState::new(state),
+            constraint_signer: SignerBLS::Local(LocalSigner::random()),
+            commitment_signer,
+            constraints_client: ConstraintsClient::new(anvil.endpoint_url()),
+            audit_log: None,
+            archive: None,
+            legacy_commitment_format: false,
+            merge_constraints: false,
+            nonce_gaps: None,
+            committed_transactions: Default::default(),
+        })
+    }
+
+    /// Create a job for the given request, with a deadline in one minute.
+    fn test_job(
+        worker: &CommitmentWorker<StateClient, impl SignerECDSA>,
+        request: InclusionRequest,
+    ) -> (CommitmentJob, oneshot::Receiver<Result<SignedCommitment, CommitmentError>>) {
+        let (response, rx) = oneshot::channel();
+        let start = Instant::now();
+        let SignerBLS::Local(signer) = &worker.constraint_signer else {
+            panic!("test workers use a local signer");
+        };
+        let job = CommitmentJob {
+            request_id: RequestId::random(),
+            span: Span::none(),
+            request,
+            signing_pubkeys: vec![signer.pubkey()],
+            collateral: None,
+            response,
+            start,
+            deadline: start + Duration::from_secs(60),
+        };
+        (job, rx)
+    }
+
+    #[async_trait::async_trait]
+    impl SignerECDSA for SlowSigner {
+        fn public_key(&self) -> Address {
+            SignerECDSA::public_key(&self.0)
+        }
+
+        async fn sign_hash(&self, hash: &[u8; 32]) -> eyre::Result<AlloySignature> {
+            tokio::time::sleep(self.1).await;
+            SignerECDSA::sign_hash(&self.0, hash).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sender_queue_ordering() {
+        let (alice, bob, carol) =
+            (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let mut queue = SenderQueue::default();
+
+        let mut first = queue.enqueue([alice, alice]);
+        let mut second = queue.enqueue([alice, bob]);
+        let mut third = queue.enqueue([bob]);
+        let mut other = queue.enqueue([carol]);
+
+        // Requests of different senders don't wait for each other
+        assert!(first.wait().now_or_never().is_some());
+        assert!(other.wait().now_or_never().is_some());
+
+        // The next ones wait for the previous requests of all their senders
+        assert!(second.wait().now_or_never().is_none());
+        assert!(third.wait().now_or_never().is_none());
+
+        drop(first);
+        assert!(second.wait().now_or_never().is_some());
+        assert!(third.wait().now_or_never().is_none());
+
+        drop(second);
+        assert!(third.wait().now_or_never().is_some());
+
+        // Senders without requests in flight are forgotten
+        drop((third, other));
+        let _ = queue.enqueue([]);
+        assert!(queue.0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_request_aborted_after_deadline() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let signer = SlowSigner(PrivateKeySigner::random(), Duration::from_secs(2));
+        let worker = test_worker(&anvil, signer).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let tx = default_test_transaction(*sender, None);
+        let request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        // The deadline passes while the commitment is being signed
+        let (mut job, rx) = test_job(&worker, request);
+        job.deadline = job.start + Duration::from_secs(1);
+        worker.process(job).await;
+
+        match rx.await? {
+            Err(CommitmentError::DeadlineExceeded { stage }) => assert_eq!(stage, "commit"),
+            res => panic!("expected a deadline error, got {res:?}"),
+        }
+
+        // No constraint was added to the slot
+        assert!(worker.execution.lock().await.get_block_template(10).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_expired_while_signing() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let signer = SlowSigner(PrivateKeySigner::random(), Duration::from_secs(2));
+        let worker = test_worker(&anvil, signer).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+
+        // Already expired when it's about to be signed, even though it passed validation
+        request.expires_at = Some(now - 1);
+        let (job, rx) = test_job(&worker, request.clone());
+        worker.process(job).await;
+        match rx.await? {
+            Err(CommitmentError::RequestExpired { stage, .. }) => assert_eq!(stage, "signing"),
+            res => panic!("expected an expiry error, got {res:?}"),
+        }
+
+        // The expiry passes while the commitment is being signed
+        request.expires_at = Some(now + 1_000);
+        let (job, rx) = test_job(&worker, request);
+        worker.process(job).await;
+        match rx.await? {
+            Err(CommitmentError::RequestExpired { expires_at, stage }) => {
+                assert_eq!((expires_at, stage), (now + 1_000, "commit"))
+            }
+            res => panic!("expected an expiry error, got {res:?}"),
+        }
+
+        // No constraint was added to the slot
+        assert!(worker.execution.lock().await.get_block_template(10).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_signature_leaves_template_untouched() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let signer = FailingSigner {
+            inner: PrivateKeySigner::random(),
+            fail_on: 2,
+            calls: Default::default(),
+        };
+        let mut worker = test_worker(&anvil, signer).await?;
+        let dir = tempfile::tempdir()?;
+        let audit_path = dir.path().join("audit.log");
+        worker.audit_log = Some(Mutex::new(AuditLog::open(&audit_path, 1 << 20, 1)?));
+
+        // Three requests of different senders, the second one failing to be signed
+        let mut responses = Vec::new();
+        let mut request_digests = Vec::new();
+        for (sender, sender_pk) in anvil.addresses().iter().zip(anvil.keys()).take(3) {
+            let txs = [
+                default_test_transaction(*sender, None),
+                default_test_transaction(*sender, Some(1)),
+            ];
+            let request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+            request_digests.push(request.digest());
+            let (job, rx) = test_job(&worker, request);
+            worker.process(job).await;
+            responses.push(rx.await?);
+        }
+
+        assert!(responses[0].is_ok() && responses[2].is_ok());
+        assert!(matches!(responses[1], Err(CommitmentError::Internal)));
+
+        // Only the constraints and state diffs of the committed requests are in the template
+        let execution = worker.execution.lock().await;
+        let template = execution.get_block_template(10).expect("template for the slot");
+        assert_eq!(template.signed_constraints_list.len(), 4);
+        assert_eq!(template.get_diff(&anvil.addresses()[0]).map(|(nonce, _)| nonce), Some(2));
+        assert_eq!(template.get_diff(&anvil.addresses()[1]), None);
+        assert_eq!(template.get_diff(&anvil.addresses()[2]).map(|(nonce, _)| nonce), Some(2));
+
+        // And only their constraint signatures are in the audit log
+        drop(execution);
+        drop(worker.audit_log.take());
+        let audited = std::fs::read_to_string(&audit_path)?
+            .lines()
+            .map(|line| serde_json::from_str::<AuditEntry>(line).map(|entry| entry.request_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let committed = [request_digests[0], request_digests[2]];
+        assert_eq!(audited.len(), 4);
+        assert!(audited.iter().all(|request_id| committed.contains(request_id)));
+
+        Ok(())
+    }
+
+    /// Test that a request falls back to the next candidate pubkey when the signer rejects the
+    /// selected one, and that all its constraints are signed with the same key.
+    #[tokio::test]
+    async fn test_signing_key_fallback() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let mut worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+
+        // The signer holds the validator key, but rejects the one of its delegatee
+        let validator = lighthouse_bls::Keypair::random();
+        let validator_pubkey = BlsPublicKey::try_from(validator.pk.serialize().as_ref())?;
+        let delegatee_pubkey = LocalSigner::random().pubkey();
+        worker.constraint_signer = SignerBLS::Keystore(KeystoreSigner::from_keypairs(
+            vec![validator],
+            ChainConfig::mainnet(),
+        ));
+
+        let (sender, sender_pk) = (anvil.addresses()[0], &anvil.keys()[0]);
+        let txs =
+            [default_test_transaction(sender, None), default_test_transaction(sender, Some(1))];
+        let request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+        let (mut job, rx) = test_job(&worker, request);
+        job.signing_pubkeys = vec![delegatee_pubkey.clone(), validator_pubkey.clone()];
+        worker.process(job).await;
+        assert!(rx.await?.is_ok());
+
+        {
+            let execution = worker.execution.lock().await;
+            let template = execution.get_block_template(10).expect("template for the slot");
+            assert_eq!(template.signed_constraints_list.len(), 2);
+            for constraints in &template.signed_constraints_list {
+                assert_eq!(constraints.message.pubkey, validator_pubkey);
+            }
+        }
+
+        // Without any candidate the signer accepts, the request is rejected
+        let (sender, sender_pk) = (anvil.addresses()[1], &anvil.keys()[1]);
+        let request = create_signed_inclusion_request(
+            &[default_test_transaction(sender, None)],
+            sender_pk,
+            10,
+        )
+        .await?;
+        let (mut job, rx) = test_job(&worker, request);
+        job.signing_pubkeys = vec![delegatee_pubkey];
+        worker.process(job).await;
+        assert!(matches!(rx.await?, Err(CommitmentError::Internal)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_then_request() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let txs =
+            [default_test_transaction(*sender, None), default_test_transaction(*sender, Some(1))];
+        let request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+
+        // The same request, committed by a worker with and without a dry run beforehand
+        let mut templates = Vec::new();
+        for dry_run in [true, false] {
+            let worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+            if dry_run {
+                let (response, rx) = oneshot::channel();
+                let deadline = Instant::now() + Duration::from_secs(60);
+                worker.dry_run(request.clone(), deadline, response).await;
+                assert!(rx.await?.is_ok_and(|verdict| verdict.ok));
+            }
+
+            let (job, rx) = test_job(&worker, request.clone());
+            worker.process(job).await;
+            assert!(rx.await?.is_ok());
+
+            let execution = worker.execution.lock().await;
+            let template = execution.get_block_template(10).expect("template for the slot");
+            templates.push((template.transactions_len(), template.get_diff(sender)));
+        }
+
+        assert_eq!(templates[0].0, 2);
+        assert_eq!(templates[0], templates[1]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merged_constraints() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let mut worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+
+        // Two requests of different senders, committed one after the other
+        for (sender, sender_pk) in anvil.addresses().iter().zip(anvil.keys()).take(2) {
+            let txs = [
+                default_test_transaction(*sender, None),
+                default_test_transaction(*sender, Some(1)),
+            ];
+            let request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+            let (job, rx) = test_job(&worker, request);
+            worker.process(job).await;
+            assert!(rx.await?.is_ok());
+        }
+        let constraints = {
+            let execution = worker.execution.lock().await;
+            let template = execution.get_block_template(10).expect("template for the slot");
+            Arc::new(template.signed_constraints_list.clone())
+        };
+        assert_eq!(constraints.len(), 4);
+
+        // Disabled, the original constraints are submitted
+        let submitted = worker.consolidate_constraints(constraints.clone()).await;
+        assert!(Arc::ptr_eq(&submitted, &constraints));
+
+        // Enabled, a single message holds all the transactions in order
+        worker.merge_constraints = true;
+        let submitted = worker.consolidate_constraints(constraints.clone()).await;
+        assert_eq!(submitted.len(), 1);
+        let merged = &submitted[0];
+        let transactions = constraints
+            .iter()
+            .flat_map(|constraint| constraint.message.transactions.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(merged.message.transactions, transactions);
+        assert_eq!(merged.message.slot, 10);
+
+        // Signed again with the same pubkey
+        let SignerBLS::Local(signer) = &worker.constraint_signer else {
+            unreachable!("local signer in tests");
+        };
+        assert_eq!(merged.message.pubkey, constraints[0].message.pubkey);
+        assert_eq!(merged.message.pubkey, signer.pubkey());
+        let digest =
+            merged.message.digest_with(worker.constraints_client.constraints_digest_version());
+        let signature = blst::min_pk::Signature::from_bytes(merged.signature.as_ref()).unwrap();
+        assert!(signer.verify_commit_boost_root(digest, &signature).is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nonce_gap_queue() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let mut worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+        worker.nonce_gaps = Some(Mutex::new(NonceGapQueue::default()));
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let request = |nonce| async move {
+            let tx = default_test_transaction(*sender, Some(nonce));
+            create_signed_inclusion_request(&[tx], sender_pk, 10).await
+        };
+
+        // The request with the second nonce arrives first and waits for the first one
+        let (second, mut second_rx) = test_job(&worker, request(1).await?);
+        worker.process(second).await;
+        assert!(second_rx.try_recv().is_err());
+
+        let (first, first_rx) = test_job(&worker, request(0).await?);
+        worker.process(first).await;
+        assert!(first_rx.await?.is_ok());
+        assert!(second_rx.await?.is_ok());
+
+        {
+            let execution = worker.execution.lock().await;
+            let template = execution.get_block_template(10).expect("template for the slot");
+            assert_eq!(template.transactions_len(), 2);
+        }
+
+        // Requests still waiting at the commitment deadline are rejected
+        let (gap, gap_rx) = test_job(&worker, request(5).await?);
+        worker.process(gap).await;
+        worker.reject_expired(10);
+        match gap_rx.await? {
+            Err(CommitmentError::Validation(ValidationError::NonceTooHigh { .. })) => {}
+            res => panic!("expected a nonce too high error, got {res:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_constraints_submission_outcomes() -> eyre::Result<()> {
+        let recorder = crate::telemetry::prometheus_builder()?.build_recorder();
+        let handle = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+
+        // Relays accepting, rejecting and failing to process the constraints
+        let mut relays = Vec::new();
+        for status in [200, 400, 500] {
+            let server = wiremock::MockServer::start().await;
+            wiremock::Mock::given(wiremock::matchers::method("POST"))
+                .respond_with(wiremock::ResponseTemplate::new(status).set_body_json(
+                    serde_json::json!({ "code": status, "message": "constraints not accepted" }),
+                ))
+                .mount(&server)
+                .await;
+            relays.push(server);
+        }
+
+        let reports = SubmissionReports::default();
+        for (slot, relay) in (10..).zip(&relays) {
+            let client = ConstraintsClient::new(relay.uri().parse::<reqwest::Url>()?);
+            let (report, result) =
+                submit_constraints_with_retries(&client, slot, Arc::new(Vec::new()), 1).await;
+            handle_submission_outcome(
+                SubmissionOutcome { transactions: 1, report, result },
+                &reports,
+            );
+        }
+        assert_eq!(reports.latest().iter().map(|r| r.slot).collect::<Vec<_>>(), vec![12, 11, 10]);
+
+        let scrape = handle.render();
+        let value = |series: &str| {
+            let line = scrape
+                .lines()
+                .find(|l| l.starts_with(series))
+                .unwrap_or_else(|| panic!("missing {series} in:\n{scrape}"));
+            line.split_whitespace().last().unwrap().parse::<f64>().unwrap()
+        };
+
+        assert_eq!(value("bolt_sidecar_last_successful_constraint_submission_slot"), 10.0);
+        assert_eq!(
+            value("bolt_sidecar_constraints_submission_failed_total{reason=\"relay_rejected\"}"),
+            1.0
+        );
+        assert_eq!(
+            value("bolt_sidecar_constraints_submission_failed_total{reason=\"retries_exhausted\"}"),
+            1.0
+        );
+
+        // Every failed attempt is counted with its reason, including the retries
+        assert_eq!(
+            value(
+                "bolt_sidecar_constraints_submission_attempts_failed_total{reason=\"relay_rejected\"}"
+            ),
+            2.0
+        );
+        assert_eq!(
+            value(
+                "bolt_sidecar_constraints_submission_attempts_failed_total{reason=\"relay_error\"}"
+            ),
+            2.0
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validators_synced_with_remote_signer() -> eyre::Result<()> {
+        let initial = LocalSigner::random().pubkey();
+        let excluded = LocalSigner::random().pubkey();
+        let added = LocalSigner::random().pubkey();
+
+        // The signer was unavailable at startup: its keys are listed afterwards
+        let validators = EffectiveValidators::default();
+        validators.exclude(HashSet::from([excluded.clone()]));
+        let status = StatusHandle::default();
+        let (pubkeys_tx, pubkeys_rx) = watch::channel(HashSet::from([initial.clone()]));
+        let constraints_client = ConstraintsClient::new("http://127.0.0.1:1".parse()?);
+        spawn_validators_sync(pubkeys_rx, validators.clone(), status.clone(), constraints_client);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(validators.contains(&initial));
+        assert_eq!(status.status(Default::default()).validators.len(), 1);
+
+        // Keys added to the signer are accepted, the excluded ones still aren't
+        pubkeys_tx.send(HashSet::from([initial.clone(), excluded.clone(), added.clone()]))?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(validators.len(), 2);
+        assert!(validators.contains(&added) && !validators.contains(&excluded));
+        assert_eq!(status.status(Default::default()).validators.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_effective_validators() -> eyre::Result<()> {
+        let delegated = LocalSigner::random().pubkey();
+        let signer = LocalSigner::random().pubkey();
+        let excluded = LocalSigner::random().pubkey();
+
+        // A beacon node resolving the excluded validator index 42
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/eth/v1/beacon/states/head/validators"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "execution_optimistic": false,
+                "finalized": false,
+                "data": [{
+                    "index": "42",
+                    "balance": "32000000000",
+                    "status": "active_ongoing",
+                    "validator": {
+                        "pubkey": excluded,
+                        "withdrawal_credentials": format!("0x{}", "00".repeat(32)),
+                        "effective_balance": "32000000000",
+                        "slashed": false,
+                        "activation_eligibility_epoch": "0",
+                        "activation_epoch": "0",
+                        "exit_epoch": "18446744073709551615",
+                        "withdrawable_epoch": "18446744073709551615"
+                    }
+                }]
+            })))
+            .mount(&server)
+            .await;
+        let beacon_client = BeaconClient::new(server.uri().parse::<reqwest::Url>()?);
+
+        // The delegated validators and the ones of the signer are kept, the excluded one is
+        // subtracted last
+        let excluded_keys = excluded_validators(&beacon_client, &[42]).await?;
+        assert_eq!(excluded_keys, HashSet::from([excluded.clone()]));
+        let validators =
+            EffectiveValidators::from_iter([delegated.clone(), signer.clone(), excluded.clone()]);
+        validators.exclude(excluded_keys);
+        assert_eq!(validators.len(), 2);
+        assert!(validators.contains(&delegated) && validators.contains(&signer));
+
+        // Including when new keys are listed by the signer
+        let added = LocalSigner::random().pubkey();
+        assert!(validators.update([signer.clone(), excluded.clone(), added.clone()]));
+        assert!(validators.contains(&added) && !validators.contains(&excluded));
+
+        // Nothing is resolved without exclusions
+        let unreachable = BeaconClient::new("http://127.0.0.1:1".parse::<reqwest::Url>()?);
+        assert!(excluded_validators(&unreachable, &[]).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_constraints_submission_report() -> eyre::Result<()> {
+        // A relay rejecting the constraints with a JSON error body
+        let body = r#"{"code":400,"message":"unknown pubkey 0xa1b2"}"#;
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(wiremock::ResponseTemplate::new(400).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let client = ConstraintsClient::new(server.uri().parse::<reqwest::Url>()?);
+        let (report, result) =
+            submit_constraints_with_retries(&client, 10, Arc::new(Vec::new()), 1).await;
+        assert!(matches!(result, Err(BuilderApiError::RelayRejected { status: 400, .. })));
+
+        // The response of the relay is preserved verbatim for every attempt
+        assert_eq!((report.slot, report.success, report.attempts.len()), (10, false, 2));
+        for (i, attempt) in report.attempts.iter().enumerate() {
+            assert_eq!(attempt.attempt, i + 1);
+            assert_eq!(attempt.relay, client.target());
+            assert_eq!(attempt.status, Some(400));
+            assert_eq!(attempt.body.as_deref(), Some(body));
+            assert_eq!(attempt.reason, Some("relay_rejected"));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_committed_transactions_tracking() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let txs =
+            [default_test_transaction(*sender, None), default_test_transaction(*sender, Some(1))];
+        let request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+        let (job, rx) = test_job(&worker, request);
+        worker.process(job).await;
+        assert!(rx.await?.is_ok());
+
+        // The committed transactions are known at the deadline, to detect a missing template
+        assert_eq!(worker.take_committed_transactions(9), 0);
+        assert_eq!(worker.take_committed_transactions(10), 2);
+        assert_eq!(worker.take_committed_transactions(10), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_constraints_stream() -> eyre::Result<()> {
+        use crate::{
+            api::{
+                builder::{builder_proxy_router, BuilderProxyServer},
+                spec::CONSTRAINTS_STREAM_PATH,
+            },
+            builder::payload_fetcher::NoopPayloadFetcher,
+        };
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let mut worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+        let constraints_stream = ConstraintsStream::default();
+        let state = ExecutionState::new(StateClient::new(anvil.endpoint_url()), Default::default())
+            .await?
+            .with_constraints_stream(constraints_stream.clone());
+        worker.execution = SharedExecutionState::new(state);
+
+        let server = BuilderProxyServer::new(worker.constraints_client.clone(), NoopPayloadFetcher)
+            .with_constraints_stream(constraints_stream.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}{CONSTRAINTS_STREAM_PATH}", listener.local_addr()?);
+        tokio::spawn(
+            async move { axum::serve(listener, builder_proxy_router(Arc::new(server))).await },
+        );
+
+        // A builder subscribes to the stream, then a request is committed
+        let mut response = reqwest::get(url).await?;
+        assert_eq!(constraints_stream.subscribers(), 1);
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let tx = default_test_transaction(*sender, None);
+        let request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        let (job, rx) = test_job(&worker, request);
+        worker.process(job).await;
+        assert!(rx.await?.is_ok());
+
+        let receive = async {
+            let mut buffer = String::new();
+            loop {
+                let chunk = response.chunk().await?.expect("stream open");
+                buffer.push_str(std::str::from_utf8(&chunk)?);
+
+                // Skip the keep-alive comments until a full event is received
+                let Some((event, _)) = buffer.split_once("\n\n") else { continue };
+                if let Some(data) = event.lines().find_map(|line| line.strip_prefix("data: ")) {
+                    return eyre::Ok(serde_json::from_str::<Vec<SignedConstraints>>(data)?);
+                }
+                buffer = buffer.split_off(event.len() + 2);
+            }
+        };
+        let received = tokio::time::timeout(Duration::from_secs(5), receive).await??;
+
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].message.slot, 10);
+        assert_eq!(received[0].message.transactions.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slot_capacity_in_commitments() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+
+        let mut capacities = Vec::new();
+        for (sender, sender_pk) in anvil.addresses().iter().zip(anvil.keys()).take(2) {
+            let tx = default_test_transaction(*sender, None);
+            let request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+            let gas_limit = request.gas_limit();
+
+            let (job, rx) = test_job(&worker, request);
+            worker.process(job).await;
+            let commitment = InclusionCommitment::from(rx.await??);
+            let capacity = *commitment.slot_capacity().expect("slot capacity");
+            capacities.push((capacity, gas_limit));
+
+            // The capacity isn't covered by the signature
+            assert_eq!(commitment.recover_signer()?, worker.commitment_signer.address());
+        }
+
+        // The capacity includes the request just committed
+        let (first, _) = capacities[0];
+        let (second, gas_limit) = capacities[1];
+        assert_eq!(second.remaining_gas, first.remaining_gas - gas_limit);
+        assert_eq!(second.remaining_constraints, first.remaining_constraints - 1);
+        assert_eq!(second.remaining_blobs, first.remaining_blobs);
+        assert_eq!(second.projected_basefee, first.projected_basefee);
+
+        let limits = LimitsOpts::default();
+        assert_eq!(first.remaining_constraints, limits.max_constraints_per_slot.get() - 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_spans() -> eyre::Result<()> {
+        use opentelemetry::trace::TracerProvider as _;
+        use opentelemetry_sdk::{testing::trace::InMemorySpanExporter, trace::TracerProvider};
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let anvil = launch_anvil();
+        let worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let tx = default_test_transaction(*sender, None);
+        let request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        let (job, rx) = test_job(&worker, request);
+        worker.process(job).instrument(info_span!("request_inclusion")).await;
+        assert!(rx.await?.is_ok());
+
+        provider.force_flush();
+        let spans = exporter.get_finished_spans()?;
+        let find = |name: &str| {
+            spans.iter().find(|span| span.name == name).unwrap_or_else(|| panic!("{name} span"))
+        };
+
+        // The validation and signing stages are children of the request span
+        let request_span = find("request_inclusion");
+        let validation_span = find("execution_validation");
+        let signing_span = find("sign_constraint");
+        for span in [validation_span, signing_span] {
+            assert_eq!(span.parent_span_id, request_span.span_context.span_id());
+            assert_eq!(span.span_context.trace_id(), request_span.span_context.trace_id());
+        }
+        assert!(signing_span
+            .attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == "signer" && kv.value.as_str() == "local"));
+
+        // And the calls to the execution client are children of the validation
+        let fetch_span = find("get_account_states");
+        assert_eq!(fetch_span.parent_span_id, validation_span.span_context.span_id());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_id_logs() -> eyre::Result<()> {
+        /// A log writer capturing the lines in memory.
+        #[derive(Clone, Default)]
+        struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for CapturedLogs {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let anvil = launch_anvil();
+        let mut worker = test_worker(&anvil, PrivateKeySigner::random()).await?;
+        let dir = tempfile::tempdir()?;
+        let archive = CommitmentArchive::open(dir.path(), 4)?;
+        worker.archive = Some(archive.clone());
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let tx = default_test_transaction(*sender, None);
+        let request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        // The job carries the span of the request, as created by the commitments API
+        let (mut job, rx) = test_job(&worker, request);
+        let request_id = job.request_id;
+        job.span = info_span!("request_inclusion", %request_id);
+        worker.process(job).await;
+        assert!(rx.await?.is_ok());
+
+        // The ID is on the logs of the validation, signing and response stages
+        let logs = String::from_utf8(logs.0.lock().clone())?;
+        for stage in [
+            "Validation against execution state passed",
+            "Signed constraints",
+            "Commitment signed and sent",
+        ] {
+            let line = logs.lines().find(|line| line.contains(stage)).expect(stage);
+            assert!(line.contains(&request_id.to_string()), "no request ID: {line}");
+        }
+
+        // And in the archived commitment
+        let archived = archive.query(10).await?;
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].request_id, Some(request_id));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_beacon_recovery() -> eyre::Result<()> {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        // Reserve the address of the beacon node, which only starts a few seconds later
+        let beacon_addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        let beacon_client = BeaconClient::new(format!("http://{beacon_addr}").parse()?);
+
+        let health = HealthHandle::default();
+        let waiting = tokio::spawn({
+            let health = health.clone();
+            async move { wait_for_beacon(&beacon_client, &health, Duration::from_secs(30)).await }
+        });
+
+        // The sidecar is degraded while the beacon node is down
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(!waiting.is_finished());
+        assert!(health.is_waiting_for_beacon());
+        assert!(!health.is_healthy());
+
+        let listener = std::net::TcpListener::bind(beacon_addr)?;
+        let beacon = MockServer::builder().listener(listener).start().await;
+        Mock::given(method("GET"))
+            .and(path("/eth/v1/beacon/genesis"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {
+                    "genesis_time": "1606824023",
+                    "genesis_validators_root": format!("0x{}", "00".repeat(32)),
+                    "genesis_fork_version": "0x00000000",
+                }
+            })))
+            .mount(&beacon)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/eth/v1/beacon/headers/head"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {
+                    "root": format!("0x{}", "00".repeat(32)),
+                    "canonical": true,
+                    "header": {
+                        "message": {
+                            "slot": "100",
+                            "proposer_index": "1",
+                            "parent_root": format!("0x{}", "00".repeat(32)),
+                            "state_root": format!("0x{}", "00".repeat(32)),
+                            "body_root": format!("0x{}", "00".repeat(32)),
+                        },
+                        "signature": format!("0x{}", "00".repeat(96)),
+                    }
+                }
+            })))
+            .mount(&beacon)
+            .await;
+
+        // The sidecar recovers on its own once the beacon node is up
+        let (genesis_time, head_slot) = waiting.await??;
+        assert_eq!(genesis_time, 1606824023);
+        assert_eq!(head_slot, 100);
+        assert!(!health.is_waiting_for_beacon());
+        assert!(health.is_healthy());
+
+        Ok(())
+    }
+}
@@ -45,6 +45,9 @@ pub mod signer;
 /// Utilities and contracts wrappers for interacting with the Bolt registry
 pub mod chain_io;
 
+/// Startup checks of the configuration and of the upstream endpoints
+pub mod preflight;
+
 /// Utilities for testing
 #[cfg(test)]
 mod test_util;
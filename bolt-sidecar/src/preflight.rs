@@ -0,0 +1,499 @@
+use std::{fmt, time::Duration};
+
+use alloy::primitives::Address;
+use reqwest::Url;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{
+    api::spec::STATUS_PATH,
+    config::{chain::ChainConfig, Opts},
+};
+
+/// The timeout for a single preflight request.
+const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The beacon node API path used to check its genesis.
+const BEACON_GENESIS_PATH: &str = "/eth/v1/beacon/genesis";
+
+/// The beacon node API path used to check the chain ID of its deposit contract.
+const BEACON_DEPOSIT_CONTRACT_PATH: &str = "/eth/v1/config/deposit_contract";
+
+/// The beacon node API path used to check its slot time.
+const BEACON_SPEC_PATH: &str = "/eth/v1/config/spec";
+
+/// A failed preflight check, with a message telling the operator what to fix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightFailure {
+    /// The name of the failed check.
+    pub check: &'static str,
+    /// The actionable message of the failure.
+    pub message: String,
+}
+
+impl fmt::Display for PreflightFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.check, self.message)
+    }
+}
+
+/// The failures of all the preflight checks that didn't pass.
+#[derive(Debug, Error)]
+#[error("{} preflight check(s) failed:\n{}", .0.len(), format_failures(.0))]
+pub struct PreflightError(pub Vec<PreflightFailure>);
+
+fn format_failures(failures: &[PreflightFailure]) -> String {
+    failures.iter().map(|failure| format!("  - {failure}")).collect::<Vec<_>>().join("\n")
+}
+
+/// Startup checks of the upstream endpoints and of the signers of the sidecar, catching
+/// misconfigurations before they surface as cryptic errors in the middle of a slot.
+///
+/// All the checks are run, and their failures are reported at once.
+#[derive(Debug)]
+pub struct PreflightChecks {
+    client: reqwest::Client,
+    beacon_api_url: Url,
+    execution_api_url: Url,
+    constraints_api_url: Url,
+    chain: ChainConfig,
+    /// Whether an unreachable beacon node is only reported, since the sidecar waits for it.
+    allow_unreachable_beacon: bool,
+    /// Whether a constraint signer without any public key is only reported, since the keys of
+    /// remote signers are listed again in the background.
+    allow_empty_remote_signer: bool,
+}
+
+impl PreflightChecks {
+    /// Create new preflight checks for the given endpoints and chain.
+    pub fn new(
+        beacon_api_url: Url,
+        execution_api_url: Url,
+        constraints_api_url: Url,
+        chain: ChainConfig,
+    ) -> Self {
+        let client = reqwest::ClientBuilder::new()
+            .user_agent("bolt-sidecar")
+            .timeout(PREFLIGHT_TIMEOUT)
+            .build()
+            .expect("valid client");
+
+        Self {
+            client,
+            beacon_api_url,
+            execution_api_url,
+            constraints_api_url,
+            chain,
+            allow_unreachable_beacon: false,
+            allow_empty_remote_signer: false,
+        }
+    }
+
+    /// Only report an unreachable beacon node instead of failing, when the sidecar waits for
+    /// it at startup.
+    pub fn with_unreachable_beacon_allowed(mut self, allowed: bool) -> Self {
+        self.allow_unreachable_beacon = allowed;
+        self
+    }
+
+    /// Only report a constraint signer without any public key instead of failing, when it's a
+    /// remote signer whose keys are listed again until it's available.
+    pub fn with_empty_remote_signer_allowed(mut self, allowed: bool) -> Self {
+        self.allow_empty_remote_signer = allowed;
+        self
+    }
+
+    /// Create new preflight checks for the endpoints and chain in [Opts].
+    pub fn from_opts(opts: &Opts) -> Self {
+        Self::new(
+            opts.beacon_api_url.clone(),
+            opts.execution_api_url.clone(),
+            opts.constraints_api_url.clone(),
+            opts.chain,
+        )
+        .with_unreachable_beacon_allowed(opts.beacon_startup_timeout > 0)
+    }
+
+    /// Run all the checks, given the address of the commitment signer and the number of
+    /// public keys exposed by the constraint signer.
+    pub async fn run(
+        &self,
+        commitment_signer: Address,
+        constraint_pubkeys: usize,
+    ) -> Result<(), PreflightError> {
+        let (beacon, execution, constraints) =
+            tokio::join!(self.check_beacon(), self.check_execution(), self.check_constraints());
+
+        let mut failures = [beacon, execution, constraints]
+            .into_iter()
+            .filter_map(Result::err)
+            .collect::<Vec<_>>();
+
+        if commitment_signer == Address::ZERO {
+            failures.push(PreflightFailure {
+                check: "commitment_signer",
+                message: "the commitment signer address couldn't be derived, check \
+                          --commitment-private-key"
+                    .to_string(),
+            });
+        }
+
+        if constraint_pubkeys == 0 && self.allow_empty_remote_signer {
+            warn!(
+                "The remote constraint signer exposes no public key yet, commitments are rejected \
+                 until its keys are listed"
+            );
+        } else if constraint_pubkeys == 0 {
+            failures.push(PreflightFailure {
+                check: "constraint_signer",
+                message: "the constraint signer exposes no public key, check \
+                          --constraint-private-key, --commit-boost-signer-url or --keystore-path"
+                    .to_string(),
+            });
+        }
+
+        if failures.is_empty() {
+            info!("All preflight checks passed");
+            Ok(())
+        } else {
+            for failure in &failures {
+                warn!(check = failure.check, "Preflight check failed: {}", failure.message);
+            }
+            Err(PreflightError(failures))
+        }
+    }
+
+    /// Check that the beacon node is reachable and that it's on the chain: its genesis fork
+    /// version, the chain ID of its deposit contract and its slot time must match the ones
+    /// of the chain, or of the custom chain config if any.
+    async fn check_beacon(&self) -> Result<(), PreflightFailure> {
+        let failure = |message: String| PreflightFailure { check: "beacon_api", message };
+        let url = &self.beacon_api_url;
+
+        let (genesis, deposit_contract, spec) = tokio::join!(
+            self.get_json(url, BEACON_GENESIS_PATH),
+            self.get_json(url, BEACON_DEPOSIT_CONTRACT_PATH),
+            self.get_json(url, BEACON_SPEC_PATH)
+        );
+
+        let genesis = match genesis {
+            Ok(body) => body,
+            Err(err) if self.allow_unreachable_beacon => {
+                warn!(%url, %err, "Beacon node is unreachable, the sidecar will wait for it");
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(failure(format!(
+                    "beacon node at {url} is unreachable ({err}), check --beacon-api-url"
+                )))
+            }
+        };
+
+        let fork_version = format!("0x{}", alloy::hex::encode(self.chain.fork_version()));
+        let chain_id = self.chain.chain_id().to_string();
+        let slot_time = self.chain.slot_time().to_string();
+        let reported = [
+            ("genesis fork", fork_version, Ok(genesis), "/data/genesis_fork_version"),
+            ("deposit chain ID", chain_id, deposit_contract, "/data/chain_id"),
+            ("slot time", slot_time, spec, "/data/SECONDS_PER_SLOT"),
+        ];
+
+        let mut mismatches = Vec::new();
+        for (name, expected, body, pointer) in reported {
+            let value =
+                body.map(|body| body.pointer(pointer).and_then(Value::as_str).map(String::from));
+            match value {
+                Ok(Some(value)) if value.eq_ignore_ascii_case(&expected) => {}
+                Ok(Some(value)) => mismatches.push(format!(
+                    "is on {name} {value}, but {} expects {expected}",
+                    self.chain.name()
+                )),
+                Ok(None) => mismatches.push(format!("returned no {name}")),
+                Err(err) => mismatches.push(format!("didn't return its {name} ({err})")),
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(failure(format!(
+                "beacon node at {url} {}, check --chain, --chain-config or --beacon-api-url",
+                mismatches.join(" and ")
+            )))
+        }
+    }
+
+    /// Check that the execution client is reachable and that its chain ID matches the chain.
+    async fn check_execution(&self) -> Result<(), PreflightFailure> {
+        let failure = |message: String| PreflightFailure { check: "execution_api", message };
+        let url = &self.execution_api_url;
+
+        let chain_id = self.get_chain_id().await.map_err(|err| {
+            failure(format!(
+                "execution client at {url} is unreachable ({err}), check --execution-api-url"
+            ))
+        })?;
+
+        let expected = self.chain.chain_id();
+        if chain_id == expected {
+            Ok(())
+        } else {
+            Err(failure(format!(
+                "execution client at {url} is on chain ID {chain_id}, but {} expects {expected}, \
+                 check --chain, --chain-config or --execution-api-url",
+                self.chain.name()
+            )))
+        }
+    }
+
+    /// Check that the constraints relay responds to its status endpoint.
+    async fn check_constraints(&self) -> Result<(), PreflightFailure> {
+        let url = &self.constraints_api_url;
+        self.get(url, STATUS_PATH).await.map(|_| ()).map_err(|err| PreflightFailure {
+            check: "constraints_api",
+            message: format!(
+                "constraints relay at {url} is unreachable ({err}), check --constraints-api-url"
+            ),
+        })
+    }
+
+    /// Send a `GET` request to the given path, failing on unsuccessful status codes.
+    async fn get(&self, url: &Url, path: &str) -> Result<reqwest::Response, String> {
+        let url = url.join(path).map_err(|e| e.to_string())?;
+        let response = self.client.get(url).send().await.map_err(|e| e.to_string())?;
+
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(format!("unexpected status code {}", response.status()))
+        }
+    }
+
+    async fn get_json(&self, url: &Url, path: &str) -> Result<Value, String> {
+        self.get(url, path).await?.json::<Value>().await.map_err(|e| e.to_string())
+    }
+
+    /// Returns the chain ID reported by the execution client with `eth_chainId`.
+    async fn get_chain_id(&self) -> Result<u64, String> {
+        let payload = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_chainId", "params": [] });
+        let response = self
+            .client
+            .post(self.execution_api_url.clone())
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("unexpected status code {}", response.status()));
+        }
+
+        let body = response.json::<Value>().await.map_err(|e| e.to_string())?;
+        match (body["result"].as_str(), body.get("error")) {
+            (_, Some(err)) => Err(format!("JSON-RPC error: {err}")),
+            (Some(result), None) => u64::from_str_radix(result.trim_start_matches("0x"), 16)
+                .map_err(|e| format!("invalid chain ID {result}: {e}")),
+            _ => Err("missing JSON-RPC result".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{body_partial_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::config::chain::{ForkSchedule, DEFAULT_CHAIN_CONFIG};
+
+    use super::*;
+
+    const SIGNER: Address = Address::repeat_byte(0x42);
+
+    /// The chain reported by the mock beacon node and execution client.
+    struct Reported {
+        fork_version: &'static str,
+        deposit_chain_id: u64,
+        seconds_per_slot: u64,
+        chain_id: u64,
+    }
+
+    /// Mainnet, as reported by endpoints on the right chain.
+    const MAINNET: Reported = Reported {
+        fork_version: "0x00000000",
+        deposit_chain_id: 1,
+        seconds_per_slot: 12,
+        chain_id: 1,
+    };
+
+    /// Start mock beacon, execution and relay endpoints on the same server, reporting the
+    /// given chain.
+    async fn mock_endpoints(reported: Reported, relay_up: bool) -> (MockServer, PreflightChecks) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(BEACON_GENESIS_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {
+                    "genesis_time": "1606824023",
+                    "genesis_validators_root": format!("0x{}", "00".repeat(32)),
+                    "genesis_fork_version": reported.fork_version,
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(BEACON_DEPOSIT_CONTRACT_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {
+                    "chain_id": reported.deposit_chain_id.to_string(),
+                    "address": "0x00000000219ab540356cbb839cbe05303d7705fa",
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(BEACON_SPEC_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "SECONDS_PER_SLOT": reported.seconds_per_slot.to_string() }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": "eth_chainId" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0", "id": 1, "result": format!("0x{:x}", reported.chain_id)
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(STATUS_PATH))
+            .respond_with(ResponseTemplate::new(if relay_up { 200 } else { 503 }))
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri()).unwrap();
+        let checks = PreflightChecks::new(url.clone(), url.clone(), url, DEFAULT_CHAIN_CONFIG);
+        (server, checks)
+    }
+
+    fn failed_checks(res: Result<(), PreflightError>) -> Vec<&'static str> {
+        res.unwrap_err().0.into_iter().map(|failure| failure.check).collect()
+    }
+
+    #[tokio::test]
+    async fn test_preflight_checks_pass() {
+        let (_server, checks) = mock_endpoints(MAINNET, true).await;
+        checks.run(SIGNER, 1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_preflight_check_failures() {
+        // Beacon node of another chain
+        let (_server, checks) =
+            mock_endpoints(Reported { fork_version: "0x01017000", ..MAINNET }, true).await;
+        assert_eq!(failed_checks(checks.run(SIGNER, 1).await), vec!["beacon_api"]);
+
+        // Execution client of another chain
+        let (_server, checks) = mock_endpoints(Reported { chain_id: 17000, ..MAINNET }, true).await;
+        let res = checks.run(SIGNER, 1).await;
+        let message = res.as_ref().unwrap_err().0[0].message.clone();
+        assert_eq!(failed_checks(res), vec!["execution_api"]);
+        assert!(message.contains("chain ID 17000") && message.contains("--execution-api-url"));
+
+        // Beacon node with the right genesis fork but on another deposit chain
+        let (_server, checks) =
+            mock_endpoints(Reported { deposit_chain_id: 17000, ..MAINNET }, true).await;
+        assert_eq!(failed_checks(checks.run(SIGNER, 1).await), vec!["beacon_api"]);
+
+        // Relay not responding
+        let (_server, checks) = mock_endpoints(MAINNET, false).await;
+        assert_eq!(failed_checks(checks.run(SIGNER, 1).await), vec!["constraints_api"]);
+
+        // Missing signers
+        let (_server, checks) = mock_endpoints(MAINNET, true).await;
+        assert_eq!(failed_checks(checks.run(Address::ZERO, 1).await), vec!["commitment_signer"]);
+        assert_eq!(failed_checks(checks.run(SIGNER, 0).await), vec!["constraint_signer"]);
+
+        // Unless the keys of a remote signer are listed again later
+        let checks = checks.with_empty_remote_signer_allowed(true);
+        checks.run(SIGNER, 0).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_preflight_reports_all_failures() {
+        // Nothing listens on the endpoints
+        let server = MockServer::start().await;
+        let url = Url::parse(&server.uri()).unwrap();
+        drop(server);
+
+        let checks = PreflightChecks::new(url.clone(), url.clone(), url, DEFAULT_CHAIN_CONFIG);
+        let err = checks.run(Address::ZERO, 0).await.unwrap_err();
+        assert_eq!(
+            err.0.iter().map(|failure| failure.check).collect::<Vec<_>>(),
+            vec![
+                "beacon_api",
+                "execution_api",
+                "constraints_api",
+                "commitment_signer",
+                "constraint_signer"
+            ]
+        );
+
+        // Every failure is reported on its own line
+        let report = err.to_string();
+        assert!(report.starts_with("5 preflight check(s) failed"));
+        assert_eq!(report.lines().count(), 6);
+
+        // The beacon node is waited for at startup if allowed
+        let checks = PreflightChecks::new(url.clone(), url.clone(), url, DEFAULT_CHAIN_CONFIG)
+            .with_unreachable_beacon_allowed(true);
+        let err = checks.run(SIGNER, 1).await.unwrap_err();
+        assert_eq!(
+            err.0.iter().map(|failure| failure.check).collect::<Vec<_>>(),
+            vec!["execution_api", "constraints_api"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preflight_chain_mismatch_messages() {
+        // Beacon node on another chain ID and slot time, execution client on another chain
+        let reported =
+            Reported { deposit_chain_id: 17000, seconds_per_slot: 2, chain_id: 17000, ..MAINNET };
+        let (server, checks) = mock_endpoints(reported, true).await;
+        let url = Url::parse(&server.uri()).unwrap();
+        let err = checks.run(SIGNER, 1).await.unwrap_err();
+        assert_eq!(
+            err.0[0].message,
+            format!(
+                "beacon node at {url} is on deposit chain ID 17000, but mainnet expects 1 and \
+                 is on slot time 2, but mainnet expects 12, check --chain, --chain-config or \
+                 --beacon-api-url"
+            )
+        );
+        assert_eq!(
+            err.0[1].message,
+            format!(
+                "execution client at {url} is on chain ID 17000, but mainnet expects 1, check \
+                 --chain, --chain-config or --execution-api-url"
+            )
+        );
+
+        // The expected chain can be overridden with a custom chain config
+        let dir = env!("CARGO_MANIFEST_DIR");
+        let config = std::fs::read_to_string(format!("{dir}/test_data/chain_config.yaml")).unwrap();
+        let schedule = ForkSchedule::parse(&config).unwrap();
+        let chain =
+            ChainConfig { chain_config: Some(schedule), ..ChainConfig::kurtosis(12, 1_000) };
+        let reported = Reported {
+            fork_version: "0x10000038",
+            deposit_chain_id: 3151908,
+            seconds_per_slot: 2,
+            chain_id: 3151908,
+        };
+        let (server, _) = mock_endpoints(reported, true).await;
+        let url = Url::parse(&server.uri()).unwrap();
+        PreflightChecks::new(url.clone(), url.clone(), url, chain).run(SIGNER, 1).await.unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use tokio::sync::watch;
+
+use super::Slot;
+
+/// The current head of the chain: the latest beacon chain slot and the latest execution
+/// block number, along with the latest finalized and safe checkpoints.
+///
+/// All the values only move forward. The handle is cheap to clone: every clone shares the same
+/// head, and tasks can subscribe to its changes instead of polling it.
+#[derive(Debug, Clone)]
+pub struct ChainHead {
+    slot: Arc<AtomicU64>,
+    block: Arc<AtomicU64>,
+    finalized_slot: Arc<AtomicU64>,
+    finalized_block: Arc<AtomicU64>,
+    safe_block: Arc<AtomicU64>,
+    tx: Arc<watch::Sender<(Slot, u64)>>,
+}
+
+impl Default for ChainHead {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl ChainHead {
+    /// Create a new chain head at the given slot and block number.
+    pub fn new(slot: Slot, block: u64) -> Self {
+        let (tx, _) = watch::channel((slot, block));
+        Self {
+            slot: Arc::new(AtomicU64::new(slot)),
+            block: Arc::new(AtomicU64::new(block)),
+            finalized_slot: Arc::default(),
+            finalized_block: Arc::default(),
+            safe_block: Arc::default(),
+            tx: Arc::new(tx),
+        }
+    }
+
+    /// Returns the current slot.
+    pub fn slot(&self) -> Slot {
+        self.slot.load(Ordering::SeqCst)
+    }
+
+    /// Returns the current block number.
+    pub fn block(&self) -> u64 {
+        self.block.load(Ordering::SeqCst)
+    }
+
+    /// Returns the latest finalized slot, the first slot of the finalized epoch.
+    pub fn finalized_slot(&self) -> Slot {
+        self.finalized_slot.load(Ordering::SeqCst)
+    }
+
+    /// Returns the latest finalized block number.
+    pub fn finalized_block(&self) -> u64 {
+        self.finalized_block.load(Ordering::SeqCst)
+    }
+
+    /// Returns the latest safe block number.
+    pub fn safe_block(&self) -> u64 {
+        self.safe_block.load(Ordering::SeqCst)
+    }
+
+    /// Update the current slot, if the given one is greater. Returns true if it was updated.
+    pub fn update_slot(&self, slot: Slot) -> bool {
+        let updated = self.slot.fetch_max(slot, Ordering::SeqCst) < slot;
+        if updated {
+            self.notify();
+        }
+        updated
+    }
+
+    /// Update the current block number, if the given one is greater. Returns true if it was
+    /// updated.
+    pub fn update_block(&self, block: u64) -> bool {
+        let updated = self.block.fetch_max(block, Ordering::SeqCst) < block;
+        if updated {
+            self.notify();
+        }
+        updated
+    }
+
+    /// Update the finalized checkpoint, if the given slot is greater. Returns true if it was
+    /// updated.
+    pub fn update_finalized(&self, slot: Slot, block: u64) -> bool {
+        let updated = self.finalized_slot.fetch_max(slot, Ordering::SeqCst) < slot;
+        if updated {
+            self.finalized_block.fetch_max(block, Ordering::SeqCst);
+        }
+        updated
+    }
+
+    /// Update the safe block number, if the given one is greater. Returns true if it was
+    /// updated.
+    pub fn update_safe_block(&self, block: u64) -> bool {
+        self.safe_block.fetch_max(block, Ordering::SeqCst) < block
+    }
+
+    /// Subscribe to the changes of the head, as `(slot, block)` pairs.
+    pub fn subscribe(&self) -> watch::Receiver<(Slot, u64)> {
+        self.tx.subscribe()
+    }
+
+    /// Send the latest head to the subscribers, never going back in case of concurrent updates.
+    fn notify(&self) {
+        let head = (self.slot(), self.block());
+        self.tx.send_if_modified(|current| {
+            let next = (current.0.max(head.0), current.1.max(head.1));
+            let modified = next != *current;
+            *current = next;
+            modified
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_chain_head_subscribers() {
+        let head = ChainHead::new(10, 100);
+        let mut rx = head.subscribe();
+        assert_eq!(*rx.borrow_and_update(), (10, 100));
+
+        // Simulated slot ticks and head events, some of them late or duplicated
+        let events = [(11, 100), (11, 101), (10, 101), (12, 99), (12, 102), (12, 102), (13, 103)];
+
+        let watcher = tokio::spawn(async move {
+            let mut seen = vec![];
+            while rx.changed().await.is_ok() {
+                seen.push(*rx.borrow_and_update());
+                if seen.last() == Some(&(13, 103)) {
+                    break;
+                }
+            }
+            seen
+        });
+
+        let writer = head.clone();
+        for (slot, block) in events {
+            writer.update_slot(slot);
+            writer.update_block(block);
+            tokio::task::yield_now().await;
+        }
+
+        let seen = watcher.await.unwrap();
+        assert_eq!(seen.last(), Some(&(13, 103)));
+        assert!(seen.windows(2).all(|w| w[0].0 <= w[1].0 && w[0].1 <= w[1].1 && w[0] != w[1]));
+
+        // Stale updates are ignored
+        assert!(!head.update_slot(12));
+        assert!(!head.update_block(100));
+        assert_eq!((head.slot(), head.block()), (13, 103));
+    }
+
+    #[test]
+    fn test_chain_head_checkpoints() {
+        let head = ChainHead::default();
+        assert_eq!((head.finalized_slot(), head.finalized_block(), head.safe_block()), (0, 0, 0));
+
+        assert!(head.update_finalized(64, 1_000));
+        assert!(head.update_safe_block(1_020));
+        assert_eq!(
+            (head.finalized_slot(), head.finalized_block(), head.safe_block()),
+            (64, 1_000, 1_020)
+        );
+
+        // Checkpoints never go back
+        assert!(!head.update_finalized(32, 990));
+        assert!(!head.update_safe_block(1_010));
+        assert_eq!(
+            (head.finalized_slot(), head.finalized_block(), head.safe_block()),
+            (64, 1_000, 1_020)
+        );
+    }
+}
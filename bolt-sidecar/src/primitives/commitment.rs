@@ -2,14 +2,19 @@ use std::str::FromStr;
 
 use alloy::{
     hex,
-    primitives::{keccak256, Address, Signature, B256},
+    primitives::{keccak256, Address, Signature, TxHash, B256, U256},
 };
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
 use serde::{de, Deserialize, Deserializer, Serialize};
 
-use crate::crypto::SignerECDSA;
+use crate::{config::collateral::CollateralPolicy, crypto::SignerECDSA};
 
 use super::{deserialize_txs, serialize_txs, FullTransaction, TransactionExt};
 
+/// The version of the commitment format that includes the constraints signed for every
+/// transaction. Commitments without a version are legacy (v1) commitments.
+pub const COMMITMENT_FORMAT_V2: u8 = 2;
+
 /// Error type for signature errors.
 #[derive(Debug, thiserror::Error)]
 #[error("Invalid signature")]
@@ -23,6 +28,55 @@ pub enum CommitmentRequest {
     Inclusion(InclusionRequest),
 }
 
+/// A unique ID assigned to every inclusion request received by the commitments API, to
+/// correlate its logs, its response and its archived commitment. Formatted as a random (v4)
+/// UUID, so that it's unique across restarts of the sidecar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(u128);
+
+impl RequestId {
+    /// Generate a new random request ID.
+    pub fn random() -> Self {
+        let bits = rand::random::<u128>();
+        // Set the version (4) and the variant (RFC 4122) of the UUID
+        let bits = (bits & !(0xf << 76)) | (0x4 << 76);
+        let bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+        Self(bits)
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = format!("{:032x}", self.0);
+        write!(f, "{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+    }
+}
+
+impl FromStr for RequestId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.replace('-', "");
+        if hex.len() != 32 {
+            return Err(format!("invalid request ID: {s}"));
+        }
+        u128::from_str_radix(&hex, 16).map(Self).map_err(|_| format!("invalid request ID: {s}"))
+    }
+}
+
+impl Serialize for RequestId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RequestId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
 /// A signed commitment with a generic signature.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
@@ -34,10 +88,197 @@ pub enum SignedCommitment {
 /// A signed inclusion commitment with a generic signature.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct InclusionCommitment {
+    /// The version of the commitment format, absent in legacy commitments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u8>,
     #[serde(flatten)]
     request: InclusionRequest,
     #[serde(deserialize_with = "deserialize_sig", serialize_with = "serialize_sig")]
     signature: Signature,
+    /// What is promised about the placement of the transactions, derived from the request.
+    /// Only covered by the signature of v2 commitments: legacy ones sign the request digest
+    /// alone, so their guarantee is only checked against the request when recovering the signer.
+    /// Omitted for plain inclusion, to keep legacy commitments in their original format.
+    #[serde(default, skip_serializing_if = "Guarantee::is_inclusion")]
+    guarantee: Guarantee,
+    /// The constraints signed for the transactions of the request, in the same order.
+    /// Only in v2 commitments, where they are covered by the signature.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    constraints: Vec<CommittedConstraint>,
+    /// The operator collateral the request has been checked against, if any.
+    /// Only covered by the signature of v2 commitments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    collateral: Option<CollateralAttestation>,
+    /// The capacity left in the target slot once the request is committed, if known.
+    /// Informational only: it's not covered by the signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    slot_capacity: Option<SlotCapacity>,
+}
+
+impl InclusionCommitment {
+    /// Returns the version of the commitment format.
+    pub fn version(&self) -> u8 {
+        self.version.unwrap_or(1)
+    }
+
+    /// Returns the committed request.
+    pub fn request(&self) -> &InclusionRequest {
+        &self.request
+    }
+
+    /// Returns what is promised about the placement of the committed transactions.
+    pub fn guarantee(&self) -> Guarantee {
+        self.guarantee
+    }
+
+    /// Returns the constraints signed for the transactions of the request. Empty for legacy
+    /// commitments.
+    pub fn constraints(&self) -> &[CommittedConstraint] {
+        &self.constraints
+    }
+
+    /// Returns the digest signed by the commitment signer, according to the commitment version.
+    pub fn digest(&self) -> B256 {
+        match self.version {
+            Some(_) => keccak256(v2_payload(
+                &self.request,
+                self.guarantee,
+                &self.constraints,
+                self.collateral.as_ref(),
+            )),
+            None => self.request.digest(),
+        }
+    }
+
+    /// Recovers the address of the commitment signer. Fails if the guarantee doesn't match
+    /// the committed request.
+    pub fn recover_signer(&self) -> Result<Address, SignatureError> {
+        if self.guarantee != Guarantee::of(&self.request) {
+            return Err(SignatureError);
+        }
+
+        self.signature.recover_address_from_prehash(&self.digest()).map_err(|_| SignatureError)
+    }
+
+    /// Returns the collateral attestation of the commitment, if any.
+    pub fn collateral(&self) -> Option<&CollateralAttestation> {
+        self.collateral.as_ref()
+    }
+
+    /// Sets the collateral attestation of a legacy commitment, which isn't covered by its
+    /// signature. v2 commitments sign it along with the request instead: see
+    /// [InclusionRequest::commit_and_sign_with_constraints].
+    pub fn set_collateral(&mut self, collateral: CollateralAttestation) {
+        self.collateral = Some(collateral);
+    }
+
+    /// Returns the capacity left in the target slot once the request is committed, if known.
+    pub fn slot_capacity(&self) -> Option<&SlotCapacity> {
+        self.slot_capacity.as_ref()
+    }
+
+    /// Sets the capacity left in the target slot once the request is committed.
+    pub fn set_slot_capacity(&mut self, slot_capacity: SlotCapacity) {
+        self.slot_capacity = Some(slot_capacity);
+    }
+}
+
+/// What an inclusion commitment promises about the placement of its transactions in the block
+/// of the target slot, derived from the flags of the request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Guarantee {
+    /// The transactions are included in the block, at any position and in any order.
+    #[default]
+    Inclusion,
+    /// The transactions are the very first ones of the block, in the order of the request.
+    TopOfBlock,
+}
+
+impl Guarantee {
+    /// Returns the guarantee given for the given request.
+    pub fn of(request: &InclusionRequest) -> Self {
+        if request.top {
+            Self::TopOfBlock
+        } else {
+            Self::Inclusion
+        }
+    }
+
+    /// Returns true if this is the default, plain inclusion guarantee.
+    pub fn is_inclusion(&self) -> bool {
+        matches!(self, Self::Inclusion)
+    }
+}
+
+impl std::fmt::Display for Guarantee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inclusion => write!(f, "inclusion"),
+            Self::TopOfBlock => write!(f, "top_of_block"),
+        }
+    }
+}
+
+/// The constraint signed for a transaction of an inclusion request, which can be correlated
+/// with the constraints submitted to relays.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommittedConstraint {
+    /// The target slot of the constraint.
+    pub slot: u64,
+    /// The BLS public key that signed the constraint: the proposer or one of its delegatees.
+    pub pubkey: BlsPublicKey,
+    /// The digest of the constraints message signed with `pubkey`.
+    pub digest: B256,
+}
+
+/// The verdict of the dry-run validation of an inclusion request, which would be committed if
+/// it were sent as is. Nothing is signed nor reserved for the request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidationVerdict {
+    /// Whether the request would be committed. Rejected requests get an error instead.
+    pub ok: bool,
+    /// The target slot of the request.
+    pub slot: u64,
+    /// The gas left in the slot once the request is committed.
+    pub remaining_gas: u64,
+    /// The number of blobs left in the slot once the request is committed.
+    pub remaining_blobs: usize,
+    /// The number of constraints left in the slot once the request is committed.
+    pub remaining_constraints: usize,
+    /// The maximum base fee projected for the target slot, in wei.
+    pub projected_basefee: u128,
+    /// The minimum priority fee per gas on top of the projected base fee, in wei.
+    pub min_priority_fee: u128,
+}
+
+/// The capacity left in a slot with its committed requests, and the base fee projected for it,
+/// so that clients can tell whether more requests would fit without asking again.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SlotCapacity {
+    /// The gas left for new commitments in the slot.
+    pub remaining_gas: u64,
+    /// The number of blobs left in the slot.
+    pub remaining_blobs: usize,
+    /// The number of constraints left in the slot.
+    pub remaining_constraints: usize,
+    /// The maximum base fee projected for the slot, in wei.
+    pub projected_basefee: u128,
+}
+
+/// The operator collateral snapshot and the policy applied to a commitment request,
+/// returned alongside the commitment so that users can verify them on-chain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CollateralAttestation {
+    /// The operator collateral registered in the BoltManager contract, in wei.
+    pub collateral: U256,
+    /// Unix timestamp in seconds of when the collateral has been fetched.
+    pub updated_at: u64,
+    /// The policy applied to the request.
+    pub policy: CollateralPolicy,
+    /// The maximum total transaction value allowed by the collateral, in wei.
+    pub max_value: U256,
 }
 
 impl From<SignedCommitment> for InclusionCommitment {
@@ -82,8 +323,20 @@ pub struct InclusionRequest {
     /// The consensus slot number at which the transaction should be included.
     pub slot: u64,
     /// The transaction to be included.
-    #[serde(deserialize_with = "deserialize_txs", serialize_with = "serialize_txs")]
+    #[serde(default, deserialize_with = "deserialize_txs", serialize_with = "serialize_txs")]
     pub txs: Vec<FullTransaction>,
+    /// The hashes of transactions already broadcast to the public mempool, sent instead of
+    /// `txs`. They are fetched from the execution client before validating the request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tx_hashes: Vec<TxHash>,
+    /// Whether the transactions must be placed at the very top of the block, before every
+    /// other constrained transaction. Covered by the signature of the user.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub top: bool,
+    /// The unix timestamp in milliseconds past which the request mustn't be committed, if any.
+    /// Covered by the signature of the user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
     /// The signature over the "slot" and "tx" fields by the user.
     /// A valid signature is the only proof that the user actually requested
     /// this specific commitment to be included at the given slot.
@@ -95,14 +348,45 @@ pub struct InclusionRequest {
 }
 
 impl InclusionRequest {
-    /// Commits and signs the request with the provided signer. Returns an [InclusionCommitment].
+    /// Commits and signs the request with the provided signer. Returns a legacy (v1)
+    /// [InclusionCommitment], which only covers the request.
     pub async fn commit_and_sign<S: SignerECDSA>(
         self,
         signer: &S,
     ) -> eyre::Result<InclusionCommitment> {
-        let digest = self.digest();
-        let signature = signer.sign_hash(&digest).await?;
-        Ok(InclusionCommitment { request: self, signature })
+        let signature = signer.sign_payload(&self.digest_payload()).await?;
+        Ok(InclusionCommitment {
+            version: None,
+            guarantee: Guarantee::of(&self),
+            request: self,
+            signature,
+            constraints: Vec::new(),
+            collateral: None,
+            slot_capacity: None,
+        })
+    }
+
+    /// Commits and signs the request along with the constraints signed for its transactions
+    /// and the collateral it has been checked against, if any, with the provided signer.
+    /// Returns a v2 [InclusionCommitment].
+    pub async fn commit_and_sign_with_constraints<S: SignerECDSA>(
+        self,
+        signer: &S,
+        constraints: Vec<CommittedConstraint>,
+        collateral: Option<CollateralAttestation>,
+    ) -> eyre::Result<InclusionCommitment> {
+        let guarantee = Guarantee::of(&self);
+        let payload = v2_payload(&self, guarantee, &constraints, collateral.as_ref());
+        let signature = signer.sign_payload(&payload).await?;
+        Ok(InclusionCommitment {
+            version: Some(COMMITMENT_FORMAT_V2),
+            guarantee,
+            request: self,
+            signature,
+            constraints,
+            collateral,
+            slot_capacity: None,
+        })
     }
 
     /// Validates the transaction fees against a minimum basefee.
@@ -117,33 +401,6 @@ impl InclusionRequest {
         true
     }
 
-    /// Validates the transaction chain id against the provided chain id.
-    /// Returns true if the chain id matches, false otherwise. Will always return true
-    /// for pre-EIP155 transactions.
-    pub fn validate_chain_id(&self, chain_id: u64) -> bool {
-        for tx in &self.txs {
-            // Check if pre-EIP155 transaction
-            if let Some(id) = tx.chain_id() {
-                if id != chain_id {
-                    return false;
-                }
-            }
-        }
-
-        true
-    }
-
-    /// Validates the tx size limit.
-    pub fn validate_tx_size_limit(&self, limit: usize) -> bool {
-        for tx in &self.txs {
-            if tx.size() > limit {
-                return false;
-            }
-        }
-
-        true
-    }
-
     /// Validates the init code limit.
     pub fn validate_init_code_limit(&self, limit: usize) -> bool {
         for tx in &self.txs {
@@ -177,11 +434,34 @@ impl InclusionRequest {
         })
     }
 
+    /// Returns the total value transferred by all transactions in this request.
+    pub fn value(&self) -> U256 {
+        self.txs.iter().map(|tx| tx.value()).fold(U256::ZERO, |acc, v| acc.saturating_add(v))
+    }
+
     /// Returns the total gas limit of all transactions in this request.
     pub fn gas_limit(&self) -> u64 {
         self.txs.iter().map(|tx| tx.gas_limit()).sum()
     }
 
+    /// Returns true if the transactions of the request are referenced by hash, and must be
+    /// fetched from the mempool before validating the request.
+    pub fn is_by_hash(&self) -> bool {
+        !self.tx_hashes.is_empty()
+    }
+
+    /// Returns true if the request has an expiry and it's past the given unix timestamp in
+    /// milliseconds.
+    pub fn is_expired_at(&self, now_millis: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now_millis > expires_at)
+    }
+
+    /// Sets the transactions fetched for the hashes of the request, in the same order.
+    pub fn set_transactions(&mut self, txs: Vec<FullTransaction>) {
+        self.txs = txs;
+        self.tx_hashes.clear();
+    }
+
     /// Returns the transaction signer.
     pub fn signer(&self) -> Option<Address> {
         self.signer
@@ -229,18 +509,85 @@ fn serialize_sig<S: serde::Serializer>(sig: &Signature, serializer: S) -> Result
 impl InclusionRequest {
     /// Returns the digest of the request.
     /// digest = keccak256(bytes(tx_hash1) | bytes(tx_hash2) | ... | le_bytes(target_slot))
+    ///
+    /// Top-of-block requests append a `0x01` byte, and requests with an expiry append
+    /// `le_bytes(expires_at)` after it, so that the digest of the other requests is
+    /// unchanged. Requests referencing their transactions by hash have the same digest as
+    /// the ones sending the full transactions.
     pub fn digest(&self) -> B256 {
+        keccak256(self.digest_payload())
+    }
+
+    /// Returns the payload hashed into the [digest](Self::digest) of the request.
+    fn digest_payload(&self) -> Vec<u8> {
         let mut data = Vec::new();
         // First field is the concatenation of all the transaction hashes
-        data.extend_from_slice(
-            &self.txs.iter().map(|tx| tx.hash().as_slice()).collect::<Vec<_>>().concat(),
-        );
+        if self.is_by_hash() {
+            self.tx_hashes.iter().for_each(|hash| data.extend_from_slice(hash.as_slice()));
+        } else {
+            data.extend_from_slice(
+                &self.txs.iter().map(|tx| tx.hash().as_slice()).collect::<Vec<_>>().concat(),
+            );
+        }
 
         // Second field is the little endian encoding of the target slot
         data.extend_from_slice(&self.slot.to_le_bytes());
 
-        keccak256(&data)
+        // Last field is the top-of-block flag, only when set
+        if self.top {
+            data.push(1);
+        }
+
+        if let Some(expires_at) = self.expires_at {
+            data.extend_from_slice(&expires_at.to_le_bytes());
+        }
+
+        data
+    }
+}
+
+/// Returns the payload signed in a v2 commitment:
+/// version | bytes(request_digest) | constraint_1 | ... | constraint_n [| collateral]
+/// [| guarantee]
+/// where constraint_i = bytes(digest) | bytes(pubkey) | le_bytes(slot)
+/// and collateral = be_bytes(collateral) | le_bytes(updated_at) | policy | be_bytes(max_value)
+///
+/// The collateral is only appended when the request has been checked against it, and the
+/// guarantee byte for top-of-block (`0x01`) commitments, so that the payload of plain
+/// inclusion commitments is unchanged. Policies are encoded as `0x00` (disabled), `0x01`
+/// (warn) and `0x02` (reject).
+fn v2_payload(
+    request: &InclusionRequest,
+    guarantee: Guarantee,
+    constraints: &[CommittedConstraint],
+    collateral: Option<&CollateralAttestation>,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + 32 + constraints.len() * (32 + 48 + 8) + 73 + 1);
+    data.push(COMMITMENT_FORMAT_V2);
+    data.extend_from_slice(request.digest().as_slice());
+
+    for constraint in constraints {
+        data.extend_from_slice(constraint.digest.as_slice());
+        data.extend_from_slice(constraint.pubkey.as_ref());
+        data.extend_from_slice(&constraint.slot.to_le_bytes());
     }
+
+    if let Some(collateral) = collateral {
+        data.extend_from_slice(&collateral.collateral.to_be_bytes::<32>());
+        data.extend_from_slice(&collateral.updated_at.to_le_bytes());
+        data.push(match collateral.policy {
+            CollateralPolicy::Disabled => 0,
+            CollateralPolicy::Warn => 1,
+            CollateralPolicy::Reject => 2,
+        });
+        data.extend_from_slice(&collateral.max_value.to_be_bytes::<32>());
+    }
+
+    if guarantee == Guarantee::TopOfBlock {
+        data.push(1);
+    }
+
+    data
 }
 
 impl From<InclusionRequest> for CommitmentRequest {
@@ -279,10 +626,31 @@ mod tests {
 
     use alloy::{
         hex,
-        primitives::{Address, Signature},
+        primitives::{b256, keccak256, Address, Signature, B256, U256},
+        signers::local::PrivateKeySigner,
     };
+    use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
 
-    use super::{CommitmentRequest, InclusionRequest};
+    use super::{
+        v2_payload, CollateralAttestation, CommitmentRequest, CommittedConstraint, Guarantee,
+        InclusionCommitment, InclusionRequest, RequestId, SlotCapacity,
+    };
+    use crate::config::collateral::CollateralPolicy;
+
+    #[test]
+    fn test_request_id_roundtrip() {
+        let id = RequestId::random();
+        let s = id.to_string();
+        assert_eq!(s.len(), 36);
+        assert_eq!(&s[14..15], "4");
+        assert!(["8", "9", "a", "b"].contains(&&s[19..20]));
+        assert_ne!(RequestId::random(), id);
+
+        let json = serde_json::to_value(id).unwrap();
+        assert_eq!(json, serde_json::Value::String(s));
+        assert_eq!(serde_json::from_value::<RequestId>(json).unwrap(), id);
+        assert!("not-a-request-id".parse::<RequestId>().is_err());
+    }
 
     #[test]
     fn test_create_digest() {
@@ -305,6 +673,31 @@ mod tests {
             recovered,
             Address::from_str("0x27083ED52464625660f3e30Aa5B9C20A30D7E110").unwrap()
         );
+
+        // The top-of-block flag is covered by the digest
+        let mut top: InclusionRequest =
+            serde_json::from_str(&json_req.replace("\"slot\"", "\"top\": true, \"slot\"")).unwrap();
+        assert!(top.top);
+        let mut data = [req.txs[0].hash().as_slice(), &633067u64.to_le_bytes()].concat();
+        data.push(1);
+        assert_eq!(top.digest(), keccak256(&data));
+        top.top = false;
+        assert_eq!(top.digest(), digest);
+
+        // So is the expiry, after the top-of-block flag
+        let mut expiring: InclusionRequest = serde_json::from_str(
+            &json_req.replace("\"slot\"", "\"top\": true, \"expires_at\": 1700000000000, \"slot\""),
+        )
+        .unwrap();
+        assert_eq!(expiring.expires_at, Some(1_700_000_000_000));
+        data.extend_from_slice(&1_700_000_000_000u64.to_le_bytes());
+        assert_eq!(expiring.digest(), keccak256(&data));
+        assert!(!expiring.is_expired_at(1_700_000_000_000));
+        assert!(expiring.is_expired_at(1_700_000_000_001));
+        expiring.top = false;
+        expiring.expires_at = None;
+        assert_eq!(expiring.digest(), digest);
+        assert!(!expiring.is_expired_at(u64::MAX));
     }
 
     #[test]
@@ -341,4 +734,244 @@ mod tests {
             panic!("Expected Inclusion request");
         }
     }
+
+    #[test]
+    fn test_deserialize_inclusion_request_by_hash() {
+        let json_req = r#"{
+            "slot": 633067,
+            "txs": ["0xf86b82016e84042343e0830f424094deaddeaddeaddeaddeaddeaddeaddeaddeaddead0780850344281a21a0e525fc31b5574722ff064bdd127c4441b0fc66de7dc44928e163cb68e9d807e5a00b3ec02fc1e34b0209f252369ad10b745cd5a51c88384a340f7a150d0e45e471"]
+        }"#;
+        let full: InclusionRequest = serde_json::from_str(json_req).unwrap();
+        let tx = full.txs[0].clone();
+
+        let json_req = format!(r#"{{ "slot": 633067, "tx_hashes": ["{}"] }}"#, tx.hash());
+        let mut req: InclusionRequest = serde_json::from_str(&json_req).unwrap();
+        assert!(req.is_by_hash() && req.txs.is_empty());
+
+        // The signature over the hashes is valid for the full transactions too
+        assert_eq!(req.digest(), full.digest());
+
+        req.set_transactions(vec![tx]);
+        assert!(!req.is_by_hash());
+        assert_eq!(req, full);
+        assert!(serde_json::to_value(&req).unwrap().get("tx_hashes").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_commitment_v2_signed_payload() -> eyre::Result<()> {
+        let json_req = r#"{
+            "slot": 633067,
+            "txs": ["0xf86b82016e84042343e0830f424094deaddeaddeaddeaddeaddeaddeaddeaddeaddead0780850344281a21a0e525fc31b5574722ff064bdd127c4441b0fc66de7dc44928e163cb68e9d807e5a00b3ec02fc1e34b0209f252369ad10b745cd5a51c88384a340f7a150d0e45e471"]
+        }"#;
+        let req: InclusionRequest = serde_json::from_str(json_req)?;
+
+        let constraints = vec![CommittedConstraint {
+            slot: 633067,
+            pubkey: BlsPublicKey::try_from([0xaa; 48].as_ref()).expect("valid bls public key"),
+            digest: B256::repeat_byte(0x11),
+        }];
+
+        // version | request digest | constraint digest | pubkey | le_bytes(slot)
+        let payload = v2_payload(&req, Guarantee::Inclusion, &constraints, None);
+        assert_eq!(
+            hex::encode(&payload),
+            concat!(
+                "02",
+                "52ecc7832625c3d107aaba5b55d4509b48cd9f4f7ce375d6696d09bbf3310525",
+                "1111111111111111111111111111111111111111111111111111111111111111",
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "eba8090000000000",
+            )
+        );
+        assert_eq!(
+            keccak256(&payload),
+            b256!("a4c655ae475f2a8077fbb50c3600825214e1f9cfe96a2fe29e52c07b1dd74a9d")
+        );
+
+        let signer = PrivateKeySigner::random();
+        let commitment = req
+            .clone()
+            .commit_and_sign_with_constraints(&signer, constraints.clone(), None)
+            .await?;
+        assert_eq!(commitment.version(), 2);
+        assert_eq!(commitment.digest(), keccak256(&payload));
+        assert_eq!(commitment.recover_signer()?, signer.address());
+
+        let json = serde_json::to_value(&commitment)?;
+        assert_eq!(json["version"], 2);
+        assert_eq!(json["constraints"][0]["slot"], 633067);
+        assert_eq!(json["constraints"][0]["digest"], B256::repeat_byte(0x11).to_string());
+        assert_eq!(serde_json::from_value::<InclusionCommitment>(json)?, commitment);
+
+        // Legacy commitments only sign the request, and don't include the new fields
+        let legacy = req.commit_and_sign(&signer).await?;
+        assert_eq!(legacy.version(), 1);
+        assert_eq!(
+            hex::encode(legacy.digest()),
+            "52ecc7832625c3d107aaba5b55d4509b48cd9f4f7ce375d6696d09bbf3310525"
+        );
+        assert_eq!(legacy.recover_signer()?, signer.address());
+
+        let json = serde_json::to_value(&legacy)?;
+        assert!(json.get("version").is_none() && json.get("constraints").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_commitment_json() -> eyre::Result<()> {
+        // A legacy commitment as returned before versioned commitments, signed by
+        // 0x27083ED52464625660f3e30Aa5B9C20A30D7E110
+        let json = serde_json::json!({
+            "slot": 633067,
+            "txs": ["0xf86b82016e84042343e0830f424094deaddeaddeaddeaddeaddeaddeaddeaddeaddead0780850344281a21a0e525fc31b5574722ff064bdd127c4441b0fc66de7dc44928e163cb68e9d807e5a00b3ec02fc1e34b0209f252369ad10b745cd5a51c88384a340f7a150d0e45e471"],
+            "signature": "0xcdd20b2abbd8cdfb77ec2608e1227f8ce0f66133b9d0ec0ea68102c2152b82193e3be0d6967b7c20b83e1a2530daa3a07713556541dc2aa16a46d922e6145a2b01"
+        });
+
+        let commitment = serde_json::from_value::<InclusionCommitment>(json.clone())?;
+        assert_eq!(commitment.version(), 1);
+        assert_eq!(commitment.guarantee(), Guarantee::Inclusion);
+        assert_eq!(
+            commitment.recover_signer()?,
+            Address::from_str("0x27083ED52464625660f3e30Aa5B9C20A30D7E110")?
+        );
+
+        // It's serialized back exactly, without any of the fields of newer versions
+        assert_eq!(serde_json::to_value(&commitment)?, json);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_guarantee() -> eyre::Result<()> {
+        let json_req = r#"{
+            "slot": 633067,
+            "top": true,
+            "txs": ["0xf86b82016e84042343e0830f424094deaddeaddeaddeaddeaddeaddeaddeaddeaddead0780850344281a21a0e525fc31b5574722ff064bdd127c4441b0fc66de7dc44928e163cb68e9d807e5a00b3ec02fc1e34b0209f252369ad10b745cd5a51c88384a340f7a150d0e45e471"]
+        }"#;
+        let req: InclusionRequest = serde_json::from_str(json_req)?;
+        assert_eq!(Guarantee::of(&req), Guarantee::TopOfBlock);
+
+        // The guarantee byte is appended to the v2 payload of top-of-block commitments only
+        let payload = v2_payload(&req, Guarantee::TopOfBlock, &[], None);
+        assert_eq!(payload.len(), 1 + 32 + 1);
+        assert_eq!(payload.last(), Some(&1));
+        assert_eq!(v2_payload(&req, Guarantee::Inclusion, &[], None).len(), 1 + 32);
+
+        let signer = PrivateKeySigner::random();
+        let commitment =
+            req.clone().commit_and_sign_with_constraints(&signer, Vec::new(), None).await?;
+        assert_eq!(commitment.guarantee(), Guarantee::TopOfBlock);
+        assert_eq!(commitment.recover_signer()?, signer.address());
+
+        let mut json = serde_json::to_value(&commitment)?;
+        assert_eq!(json["guarantee"], "top_of_block");
+        assert_eq!(serde_json::from_value::<InclusionCommitment>(json.clone())?, commitment);
+
+        // A downgraded guarantee doesn't match the signature nor the request anymore
+        json["guarantee"] = serde_json::json!("inclusion");
+        let downgraded = serde_json::from_value::<InclusionCommitment>(json.clone())?;
+        assert!(downgraded.recover_signer().is_err());
+
+        // Neither does an upgraded guarantee of a legacy commitment
+        let mut req = req;
+        req.top = false;
+        let legacy = req.commit_and_sign(&signer).await?;
+        let mut json = serde_json::to_value(&legacy)?;
+        assert!(json.get("guarantee").is_none());
+        json["guarantee"] = serde_json::json!("top_of_block");
+        let upgraded = serde_json::from_value::<InclusionCommitment>(json)?;
+        assert!(upgraded.recover_signer().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_slot_capacity() -> eyre::Result<()> {
+        let json_req = r#"{
+            "slot": 633067,
+            "txs": ["0xf86b82016e84042343e0830f424094deaddeaddeaddeaddeaddeaddeaddeaddeaddead0780850344281a21a0e525fc31b5574722ff064bdd127c4441b0fc66de7dc44928e163cb68e9d807e5a00b3ec02fc1e34b0209f252369ad10b745cd5a51c88384a340f7a150d0e45e471"]
+        }"#;
+        let req: InclusionRequest = serde_json::from_str(json_req)?;
+        let signer = PrivateKeySigner::random();
+        let mut commitment =
+            req.commit_and_sign_with_constraints(&signer, Vec::new(), None).await?;
+        let digest = commitment.digest();
+
+        // Commitments without a known capacity don't include it
+        let json = serde_json::to_value(&commitment)?;
+        assert!(json.get("slot_capacity").is_none());
+
+        let capacity = SlotCapacity {
+            remaining_gas: 9_979_000,
+            remaining_blobs: 6,
+            remaining_constraints: 127,
+            projected_basefee: 1_000_000_000,
+        };
+        commitment.set_slot_capacity(capacity);
+
+        let json = serde_json::to_value(&commitment)?;
+        assert_eq!(
+            json["slot_capacity"],
+            serde_json::json!({
+                "remaining_gas": 9_979_000,
+                "remaining_blobs": 6,
+                "remaining_constraints": 127,
+                "projected_basefee": 1_000_000_000,
+            })
+        );
+
+        // The capacity isn't signed: the signature stays valid
+        let decoded = serde_json::from_value::<InclusionCommitment>(json)?;
+        assert_eq!(decoded.slot_capacity(), Some(&capacity));
+        assert_eq!(decoded.digest(), digest);
+        assert_eq!(decoded.recover_signer()?, signer.address());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_collateral() -> eyre::Result<()> {
+        let json_req = r#"{
+            "slot": 633067,
+            "txs": ["0xf86b82016e84042343e0830f424094deaddeaddeaddeaddeaddeaddeaddeaddeaddead0780850344281a21a0e525fc31b5574722ff064bdd127c4441b0fc66de7dc44928e163cb68e9d807e5a00b3ec02fc1e34b0209f252369ad10b745cd5a51c88384a340f7a150d0e45e471"]
+        }"#;
+        let req: InclusionRequest = serde_json::from_str(json_req)?;
+        let collateral = CollateralAttestation {
+            collateral: U256::from(10u64.pow(18)),
+            updated_at: 1_700_000_000,
+            policy: CollateralPolicy::Reject,
+            max_value: U256::from(10u64.pow(17)),
+        };
+
+        // collateral | updated_at | policy | max_value is appended after the constraints
+        let payload = v2_payload(&req, Guarantee::Inclusion, &[], Some(&collateral));
+        assert_eq!(payload.len(), 1 + 32 + 32 + 8 + 1 + 32);
+        assert_eq!(&payload[33..65], &collateral.collateral.to_be_bytes::<32>());
+        assert_eq!(&payload[65..73], &1_700_000_000u64.to_le_bytes());
+        assert_eq!(payload[73], 2);
+
+        let signer = PrivateKeySigner::random();
+        let commitment = req
+            .clone()
+            .commit_and_sign_with_constraints(&signer, Vec::new(), Some(collateral.clone()))
+            .await?;
+        assert_eq!(commitment.collateral(), Some(&collateral));
+        assert_eq!(commitment.recover_signer()?, signer.address());
+
+        let mut json = serde_json::to_value(&commitment)?;
+        assert_eq!(serde_json::from_value::<InclusionCommitment>(json.clone())?, commitment);
+
+        // A tampered collateral doesn't match the signature anymore
+        json["collateral"]["maxValue"] = serde_json::to_value(collateral.collateral)?;
+        let tampered = serde_json::from_value::<InclusionCommitment>(json.clone())?;
+        assert_ne!(tampered.recover_signer().ok(), Some(signer.address()));
+
+        // Neither does a stripped one
+        json.as_object_mut().unwrap().remove("collateral");
+        let stripped = serde_json::from_value::<InclusionCommitment>(json)?;
+        assert_ne!(stripped.recover_signer().ok(), Some(signer.address()));
+
+        Ok(())
+    }
 }
@@ -1,5 +1,16 @@
-use alloy::signers::k256::sha2::{Digest, Sha256};
-use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use std::collections::HashSet;
+
+use alloy::{
+    eips::eip2718::Encodable2718,
+    signers::k256::sha2::{Digest, Sha256},
+};
+use clap::ValueEnum;
+use ethereum_consensus::{
+    bellatrix::mainnet::Transaction,
+    crypto::PublicKey as BlsPublicKey,
+    deneb::mainnet::MAX_TRANSACTIONS_PER_PAYLOAD,
+    ssz::prelude::{ssz_rs, ByteVector, HashTreeRoot, List, SimpleSerialize},
+};
 use serde::{Deserialize, Serialize};
 
 use crate::crypto::{bls::BLSSig, SignableBLS};
@@ -10,10 +21,13 @@ use super::{deserialize_txs, serialize_txs, FullTransaction, InclusionRequest};
 /// that need to be forwarded to the PBS pipeline to inform block production.
 pub type BatchedSignedConstraints = Vec<SignedConstraints>;
 
+/// The maximum number of signed constraints in an SSZ-encoded batch.
+pub const MAX_CONSTRAINTS_PER_BATCH: usize = 256;
+
 /// A container for a list of constraints and the signature of the proposer sidecar.
 ///
 /// Reference: https://chainbound.github.io/bolt-docs/api/builder#constraints
-#[derive(Serialize, Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 pub struct SignedConstraints {
     /// The constraints that need to be signed.
     pub message: ConstraintsMessage,
@@ -43,17 +57,33 @@ impl ConstraintsMessage {
     pub fn build(pubkey: BlsPublicKey, request: InclusionRequest) -> Self {
         let transactions = request.txs;
 
-        Self { pubkey, slot: request.slot, top: false, transactions }
+        Self { pubkey, slot: request.slot, top: request.top, transactions }
     }
 
     /// Builds a constraints message from a single transaction.
     pub fn from_tx(pubkey: BlsPublicKey, slot: u64, tx: FullTransaction) -> Self {
         Self { pubkey, slot, top: false, transactions: vec![tx] }
     }
+
+    /// Sets whether the constraints are only valid on the top of the block.
+    pub fn with_top(mut self, top: bool) -> Self {
+        self.top = top;
+        self
+    }
 }
 
-impl SignableBLS for ConstraintsMessage {
-    fn digest(&self) -> [u8; 32] {
+impl ConstraintsMessage {
+    /// Returns the digest of the message with the given version of the signing scheme.
+    pub fn digest_with(&self, version: ConstraintsDigestVersion) -> [u8; 32] {
+        match version {
+            ConstraintsDigestVersion::Legacy => self.legacy_digest(),
+            ConstraintsDigestVersion::V1 => self.v1_digest(),
+        }
+    }
+
+    /// The SHA256 hash of the pubkey, the little-endian slot, the top flag and the hashes of
+    /// the transactions.
+    fn legacy_digest(&self) -> [u8; 32] {
         let mut hasher = Sha256::new();
         hasher.update(self.pubkey.to_vec());
         hasher.update(self.slot.to_le_bytes());
@@ -65,6 +95,183 @@ impl SignableBLS for ConstraintsMessage {
 
         hasher.finalize().into()
     }
+
+    /// The hash tree root of the [SszConstraintsMessage] container, which commits to the full
+    /// bytes of the transactions.
+    fn v1_digest(&self) -> [u8; 32] {
+        let message = SszConstraintsMessage::try_from(self)
+            .expect("transactions fit in an execution payload");
+        message.hash_tree_root().expect("valid constraints message root").0
+    }
+}
+
+impl SignableBLS for ConstraintsMessage {
+    fn digest(&self) -> [u8; 32] {
+        self.digest_with(ConstraintsDigestVersion::default())
+    }
+}
+
+/// The version of the scheme used to compute the digest of a [ConstraintsMessage], which is
+/// signed in [SignedConstraints]. Relays must verify signatures with the same version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConstraintsDigestVersion {
+    /// The SHA256 hash of the pubkey, slot, top flag and transaction hashes, for relays that
+    /// still verify constraints with it.
+    Legacy,
+    /// The hash tree root of the SSZ constraints message, per the constraints API spec.
+    #[default]
+    V1,
+}
+
+/// Errors that can occur when encoding or decoding constraints as SSZ.
+#[derive(Debug, thiserror::Error)]
+pub enum ConstraintsSszError {
+    /// The batch holds more constraints than an SSZ list can.
+    #[error("Too many constraints in batch: {0}, max {MAX_CONSTRAINTS_PER_BATCH}")]
+    TooManyConstraints(usize),
+    /// The SSZ encoding or decoding failed.
+    #[error("SSZ error: {0}")]
+    Ssz(String),
+    /// A constrained transaction could not be decoded.
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+}
+
+/// The SSZ representation of [`SignedConstraints`], submitted to relays that accept
+/// `application/octet-stream` bodies on the constraints endpoint.
+#[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+pub struct SszSignedConstraints {
+    message: SszConstraintsMessage,
+    signature: ByteVector<96>,
+}
+
+/// The SSZ representation of [`ConstraintsMessage`], with the transactions as opaque
+/// EIP-2718 encoded bytes like in execution payloads. Its hash tree root is the digest of
+/// [`ConstraintsDigestVersion::V1`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+pub struct SszConstraintsMessage {
+    pubkey: BlsPublicKey,
+    slot: u64,
+    top: bool,
+    transactions: List<Transaction, MAX_TRANSACTIONS_PER_PAYLOAD>,
+}
+
+impl TryFrom<&ConstraintsMessage> for SszConstraintsMessage {
+    type Error = ConstraintsSszError;
+
+    fn try_from(message: &ConstraintsMessage) -> Result<Self, Self::Error> {
+        let mut transactions = List::default();
+        for tx in &message.transactions {
+            let encoded = tx.tx.encoded_2718();
+            transactions.push(
+                Transaction::try_from(encoded.as_slice())
+                    .map_err(|e| ConstraintsSszError::Ssz(e.to_string()))?,
+            );
+        }
+
+        Ok(Self {
+            pubkey: message.pubkey.clone(),
+            slot: message.slot,
+            top: message.top,
+            transactions,
+        })
+    }
+}
+
+impl TryFrom<&SignedConstraints> for SszSignedConstraints {
+    type Error = ConstraintsSszError;
+
+    fn try_from(constraints: &SignedConstraints) -> Result<Self, Self::Error> {
+        let message = SszConstraintsMessage::try_from(&constraints.message)?;
+        let signature = ByteVector::try_from(constraints.signature.as_slice())
+            .map_err(|e| ConstraintsSszError::Ssz(e.to_string()))?;
+
+        Ok(Self { message, signature })
+    }
+}
+
+impl TryFrom<SszSignedConstraints> for SignedConstraints {
+    type Error = ConstraintsSszError;
+
+    fn try_from(constraints: SszSignedConstraints) -> Result<Self, Self::Error> {
+        let transactions = constraints
+            .message
+            .transactions
+            .iter()
+            .map(|tx| {
+                FullTransaction::decode_enveloped(tx.as_ref())
+                    .map_err(|e| ConstraintsSszError::InvalidTransaction(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let message = ConstraintsMessage {
+            pubkey: constraints.message.pubkey,
+            slot: constraints.message.slot,
+            top: constraints.message.top,
+            transactions,
+        };
+
+        Ok(Self { message, signature: BLSSig::from_slice(constraints.signature.as_ref()) })
+    }
+}
+
+/// Encode a batch of signed constraints as an SSZ list of [`SszSignedConstraints`].
+pub fn encode_constraints_ssz(
+    constraints: &BatchedSignedConstraints,
+) -> Result<Vec<u8>, ConstraintsSszError> {
+    if constraints.len() > MAX_CONSTRAINTS_PER_BATCH {
+        return Err(ConstraintsSszError::TooManyConstraints(constraints.len()));
+    }
+
+    let mut list = List::<SszSignedConstraints, MAX_CONSTRAINTS_PER_BATCH>::default();
+    for signed_constraints in constraints {
+        list.push(SszSignedConstraints::try_from(signed_constraints)?);
+    }
+
+    ssz_rs::serialize(&list).map_err(|e| ConstraintsSszError::Ssz(e.to_string()))
+}
+
+/// Decode a batch of signed constraints encoded with [`encode_constraints_ssz`].
+pub fn decode_constraints_ssz(
+    bytes: &[u8],
+) -> Result<BatchedSignedConstraints, ConstraintsSszError> {
+    let list: List<SszSignedConstraints, MAX_CONSTRAINTS_PER_BATCH> =
+        ssz_rs::deserialize(bytes).map_err(|e| ConstraintsSszError::Ssz(e.to_string()))?;
+
+    list.iter().cloned().map(SignedConstraints::try_from).collect()
+}
+
+/// Merge the messages of a batch of signed constraints sharing the same pubkey, slot and top
+/// flag into a single message each, to be signed again before submission. The merged messages
+/// follow the order in which their first constraint appears, and keep the transactions in the
+/// order they were accepted in. Duplicate transactions are only included once.
+pub fn merge_constraints(constraints: &[SignedConstraints]) -> Vec<ConstraintsMessage> {
+    let mut merged: Vec<ConstraintsMessage> = Vec::new();
+
+    for SignedConstraints { message, .. } in constraints {
+        let existing = merged.iter_mut().find(|merged| {
+            merged.pubkey == message.pubkey &&
+                merged.slot == message.slot &&
+                merged.top == message.top
+        });
+
+        let Some(existing) = existing else {
+            let mut message = message.clone();
+            let mut seen = HashSet::new();
+            message.transactions.retain(|tx| seen.insert(*tx.hash()));
+            merged.push(message);
+            continue;
+        };
+
+        for tx in &message.transactions {
+            if !existing.transactions.iter().any(|existing| existing.hash() == tx.hash()) {
+                existing.transactions.push(tx.clone());
+            }
+        }
+    }
+
+    merged
 }
 
 #[cfg(test)]
@@ -150,4 +357,121 @@ mod tests {
         let blst_sig = BlsSignature::from_bytes(signed_constraints.signature.as_ref()).unwrap();
         assert!(signer.verify_commit_boost_root(digest, &blst_sig).is_ok());
     }
+
+    #[test]
+    fn test_constraints_ssz_golden_vector() {
+        let tx_bytes = bytes!("f8678085019dc6838082520894deaddeaddeaddeaddeaddeaddeaddeaddeaddead38808360306ca06664c078fa60bd3ece050903dd295949908dd9686ec8871fa558f868e031cd39a00ed4f0b122b32b73f19230fabe6a726e2d07f84eda5beaa42a1ae1271bdee39f").to_vec();
+        let tx = FullTransaction::decode_enveloped(tx_bytes.as_slice()).unwrap();
+
+        let pubkey = bytes!("83b85769a8f2a1a6bd3a609e51b460f6fb897daff1157991479421493926faeffa6670152524403929a8a7e551d345f3");
+        let pubkey = BlsPublicKey::try_from(pubkey.as_ref()).unwrap();
+
+        let message = ConstraintsMessage::from_tx(pubkey, 165, tx);
+        let signature = BLSSig::repeat_byte(0xab);
+        let constraints = vec![SignedConstraints { message, signature }];
+
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/constraints.ssz.hex");
+        let fixture = alloy::hex::decode(std::fs::read_to_string(path).unwrap().trim()).unwrap();
+
+        let encoded = encode_constraints_ssz(&constraints).unwrap();
+        assert_eq!(encoded, fixture);
+        assert_eq!(decode_constraints_ssz(&encoded).unwrap(), constraints);
+    }
+
+    #[test]
+    fn test_constraints_ssz_roundtrip() {
+        let constraints = (0..3)
+            .map(|slot| SignedConstraints {
+                message: ConstraintsMessage {
+                    pubkey: BlsPublicKey::default(),
+                    slot,
+                    top: slot == 0,
+                    transactions: random_constraints(slot as usize),
+                },
+                signature: BLSSig::default(),
+            })
+            .collect::<Vec<_>>();
+
+        let encoded = encode_constraints_ssz(&constraints).unwrap();
+        assert_eq!(decode_constraints_ssz(&encoded).unwrap(), constraints);
+
+        let too_many = vec![SignedConstraints::default(); MAX_CONSTRAINTS_PER_BATCH + 1];
+        assert!(matches!(
+            encode_constraints_ssz(&too_many),
+            Err(ConstraintsSszError::TooManyConstraints(_))
+        ));
+    }
+
+    #[derive(Deserialize)]
+    struct DigestVector {
+        pubkey: BlsPublicKey,
+        slot: u64,
+        top: bool,
+        #[serde(deserialize_with = "deserialize_txs")]
+        transactions: Vec<FullTransaction>,
+        legacy_digest: alloy::primitives::B256,
+        v1_digest: alloy::primitives::B256,
+    }
+
+    #[test]
+    fn test_constraints_digest_vectors() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/constraints_digest_vectors.json");
+        let vectors: Vec<DigestVector> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert!(!vectors.is_empty());
+
+        let signer = LocalSigner::random();
+        for vector in vectors {
+            let message = ConstraintsMessage {
+                pubkey: vector.pubkey,
+                slot: vector.slot,
+                top: vector.top,
+                transactions: vector.transactions,
+            };
+
+            let legacy = message.digest_with(ConstraintsDigestVersion::Legacy);
+            let v1 = message.digest_with(ConstraintsDigestVersion::V1);
+            assert_eq!(legacy, vector.legacy_digest.0);
+            assert_eq!(v1, vector.v1_digest.0);
+            assert_eq!(message.digest(), v1);
+
+            // Signatures over one version don't verify with the other
+            let signature = signer.sign_commit_boost_root(v1).unwrap();
+            let signature = BlsSignature::from_bytes(signature.as_ref()).unwrap();
+            assert!(signer.verify_commit_boost_root(v1, &signature).is_ok());
+            assert!(signer.verify_commit_boost_root(legacy, &signature).is_err());
+        }
+    }
+
+    #[test]
+    fn test_merge_constraints() {
+        let txs = crate::test_util::default_inclusion_request().txs;
+        let (a, b) = (LocalSigner::random().pubkey(), LocalSigner::random().pubkey());
+
+        let constraint = |pubkey: &BlsPublicKey, slot, tx: &FullTransaction| SignedConstraints {
+            message: ConstraintsMessage::from_tx(pubkey.clone(), slot, tx.clone()),
+            signature: BLSSig::default(),
+        };
+        let constraints = vec![
+            constraint(&a, 10, &txs[0]),
+            constraint(&b, 10, &txs[1]),
+            constraint(&a, 10, &txs[1]),
+            constraint(&a, 11, &txs[2]),
+            constraint(&a, 10, &txs[2]),
+            // Duplicates are only included once
+            constraint(&a, 10, &txs[0]),
+        ];
+
+        let merged = merge_constraints(&constraints);
+        assert_eq!(
+            merged,
+            vec![
+                ConstraintsMessage { pubkey: a.clone(), slot: 10, top: false, transactions: txs },
+                ConstraintsMessage::from_tx(b, 10, constraints[1].message.transactions[0].clone()),
+                ConstraintsMessage::from_tx(a, 11, constraints[3].message.transactions[0].clone()),
+            ]
+        );
+    }
 }
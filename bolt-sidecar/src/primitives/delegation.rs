@@ -65,24 +65,63 @@ impl SignableBLS for DelegationMessage {
     }
 }
 
+/// The signed messages of a delegations file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignedMessages {
+    /// The signed delegations of the file.
+    pub delegations: Vec<SignedDelegation>,
+    /// The signed revocations of the file.
+    pub revocations: Vec<SignedRevocation>,
+}
+
 /// read the delegations from disk if they exist and add them to the constraints client
 pub fn read_signed_delegations_from_file(
     file_path: &PathBuf,
 ) -> eyre::Result<Vec<SignedDelegation>> {
-    match fs::read_to_string(file_path) {
-        Ok(contents) => match serde_json::from_str::<Vec<SignedDelegation>>(&contents) {
-            Ok(delegations) => Ok(delegations),
-            Err(err) => bail!("Failed to parse signed delegations from disk: {:?}", err),
-        },
+    read_signed_messages_from_file(file_path).map(|messages| messages.delegations)
+}
+
+/// Read the signed delegations and revocations of a delegations file, split by their action.
+pub fn read_signed_messages_from_file(file_path: &PathBuf) -> eyre::Result<SignedMessages> {
+    let contents = match fs::read_to_string(file_path) {
+        Ok(contents) => contents,
         Err(err) => bail!("Failed to read signed delegations from disk: {:?}", err),
+    };
+
+    let entries = match serde_json::from_str::<Vec<serde_json::Value>>(&contents) {
+        Ok(entries) => entries,
+        Err(err) => bail!("Failed to parse signed delegations from disk: {:?}", err),
+    };
+
+    // Refuse the entries generated without signature, to be signed later by the key holders
+    let unsigned = entries.iter().filter(|e| e.get("unsigned") == Some(&true.into())).count();
+    if unsigned > 0 {
+        bail!("Delegations file contains {unsigned} unsigned entries, sign them before loading");
+    }
+
+    let mut messages = SignedMessages::default();
+    for entry in entries {
+        let action = entry.get("message").and_then(|m| m.get("action")).and_then(|a| a.as_u64());
+        let parsed = match action {
+            Some(action) if action == SignedMessageAction::Revocation as u64 => {
+                serde_json::from_value(entry).map(|r| messages.revocations.push(r))
+            }
+            _ => serde_json::from_value(entry).map(|d| messages.delegations.push(d)),
+        };
+
+        if let Err(err) = parsed {
+            bail!("Failed to parse signed delegations from disk: {:?}", err);
+        }
     }
+
+    Ok(messages)
 }
 
 /// A signed revocation message.
 ///
 /// This is a message that is signed by a validator to revoke its
 /// constraint signing power from another key (delegatee).
-#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub struct SignedRevocation {
     /// The revocation message.
     pub message: RevocationMessage,
@@ -99,7 +138,7 @@ impl Deref for SignedRevocation {
 }
 
 /// A revocation message.
-#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub struct RevocationMessage {
     action: u8,
     /// The validator pubkey that is revoking a delegatee's power.
@@ -144,4 +183,52 @@ mod tests {
             "0x83b85769a8f2a1a6bd3a609e51b460f6fb897daff1157991479421493926faeffa6670152524403929a8a7e551d345f3"
         );
     }
+
+    #[test]
+    fn test_refuse_unsigned_delegations() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/delegations.json");
+
+        let mut entries: Vec<serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let mut unsigned = entries[0].clone();
+        unsigned.as_object_mut().unwrap().remove("signature");
+        unsigned["unsigned"] = true.into();
+        entries.push(unsigned);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("delegations.json");
+        std::fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+
+        let err = super::read_signed_delegations_from_file(&path).unwrap_err();
+
+        assert!(err.to_string().contains("1 unsigned entries"));
+    }
+
+    #[test]
+    fn test_read_signed_messages_split_by_action() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/delegations.json");
+
+        let mut entries: Vec<serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let mut revocation = entries[0].clone();
+        revocation["message"]["action"] = 1.into();
+        entries.push(revocation);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("delegations.json");
+        std::fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+
+        let messages = super::read_signed_messages_from_file(&path).unwrap();
+        let delegations = super::read_signed_delegations_from_file(&path).unwrap();
+
+        assert_eq!(messages.delegations.len(), 1);
+        assert_eq!(messages.revocations.len(), 1);
+        assert_eq!(delegations, messages.delegations);
+        assert_eq!(
+            messages.revocations[0].validator_pubkey,
+            messages.delegations[0].validator_pubkey
+        );
+    }
 }
@@ -3,10 +3,11 @@ use ethereum_consensus::{
     crypto::KzgCommitment,
     deneb::{
         self,
-        mainnet::{BlobsBundle, MAX_BLOB_COMMITMENTS_PER_BLOCK},
+        mainnet::{BlobsBundle, SignedBlindedBeaconBlock, MAX_BLOB_COMMITMENTS_PER_BLOCK},
         presets::mainnet::ExecutionPayloadHeader,
         Hash32,
     },
+    electra::presets::mainnet::ExecutionPayloadHeader as ElectraExecutionPayloadHeader,
     serde::as_str,
     ssz::prelude::*,
     types::mainnet::ExecutionPayload,
@@ -16,6 +17,10 @@ use tokio::sync::oneshot;
 
 pub use ethereum_consensus::crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature};
 
+/// The current head of the chain, shared by the sidecar components.
+pub mod chain_head;
+pub use chain_head::ChainHead;
+
 /// Commitment types, received by users wishing to receive preconfirmations.
 pub mod commitment;
 pub use commitment::{CommitmentRequest, InclusionRequest};
@@ -23,18 +28,24 @@ pub use commitment::{CommitmentRequest, InclusionRequest};
 /// Constraint types, signed by proposers and sent along the PBS pipeline
 /// for validation.
 pub mod constraint;
-pub use constraint::{BatchedSignedConstraints, ConstraintsMessage, SignedConstraints};
+pub use constraint::{
+    decode_constraints_ssz, encode_constraints_ssz, merge_constraints, BatchedSignedConstraints,
+    ConstraintsDigestVersion, ConstraintsMessage, ConstraintsSszError, SignedConstraints,
+};
 
 /// Delegation and revocation signed message types and utilities.
 pub mod delegation;
 pub use delegation::{
-    read_signed_delegations_from_file, DelegationMessage, RevocationMessage, SignedDelegation,
-    SignedRevocation,
+    read_signed_delegations_from_file, read_signed_messages_from_file, DelegationMessage,
+    RevocationMessage, SignedDelegation, SignedMessages, SignedRevocation,
 };
 
 /// Transaction types and extension utilities.
 pub mod transaction;
-pub use transaction::{deserialize_txs, serialize_txs, FullTransaction, TransactionExt};
+pub use transaction::{
+    decode_hex_tx, deserialize_txs, intrinsic_gas, serialize_txs, FullTransaction, TransactionExt,
+    TxDecodingError,
+};
 
 /// An alias for a Beacon Chain slot number
 pub type Slot = u64;
@@ -70,12 +81,79 @@ pub struct SignedBuilderBid {
     pub signature: BlsSignature,
 }
 
+/// Builder bid for an Electra payload, object that is signed by the proposer
+#[derive(Debug, Default, Clone, SimpleSerialize, serde::Serialize, serde::Deserialize)]
+#[allow(missing_docs)]
+pub struct ElectraBuilderBid {
+    pub header: ElectraExecutionPayloadHeader,
+    pub blob_kzg_commitments: List<KzgCommitment, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
+    #[serde(with = "as_str")]
+    pub value: U256,
+    #[serde(rename = "pubkey")]
+    pub public_key: BlsPublicKey,
+}
+
+/// Signed Electra builder bid with the proposer signature
+#[derive(Debug, Default, Clone, SimpleSerialize, serde::Serialize, serde::Deserialize)]
+#[allow(missing_docs)]
+pub struct SignedElectraBuilderBid {
+    pub message: ElectraBuilderBid,
+    pub signature: BlsSignature,
+}
+
+/// Signed builder bid of the fork of its payload, serialized with its `version` tag
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "version", content = "data")]
+#[allow(missing_docs)]
+pub enum VersionedSignedBuilderBid {
+    #[serde(rename = "deneb")]
+    Deneb(SignedBuilderBid),
+    #[serde(rename = "electra")]
+    Electra(SignedElectraBuilderBid),
+}
+
+impl VersionedSignedBuilderBid {
+    /// Returns the fork of the bid
+    pub fn version(&self) -> Fork {
+        match self {
+            VersionedSignedBuilderBid::Deneb(_) => Fork::Deneb,
+            VersionedSignedBuilderBid::Electra(_) => Fork::Electra,
+        }
+    }
+
+    /// Returns the block hash of the header of the bid
+    pub fn block_hash(&self) -> &Hash32 {
+        match self {
+            VersionedSignedBuilderBid::Deneb(bid) => &bid.message.header.block_hash,
+            VersionedSignedBuilderBid::Electra(bid) => &bid.message.header.block_hash,
+        }
+    }
+
+    /// Returns the block number of the header of the bid
+    pub fn block_number(&self) -> u64 {
+        match self {
+            VersionedSignedBuilderBid::Deneb(bid) => bid.message.header.block_number,
+            VersionedSignedBuilderBid::Electra(bid) => bid.message.header.block_number,
+        }
+    }
+}
+
+impl From<SignedBuilderBid> for VersionedSignedBuilderBid {
+    fn from(bid: SignedBuilderBid) -> Self {
+        VersionedSignedBuilderBid::Deneb(bid)
+    }
+}
+
+/// Max number of constraint proofs in a signed builder bid, and thus max number of
+/// constrained transactions in a block.
+pub const MAX_CONSTRAINT_PROOFS: usize = 300;
+
 /// Signed builder bid with the proposer signature and Bolt inclusion proofs
 #[derive(Debug, Default, Clone, SimpleSerialize, serde::Serialize, serde::Deserialize)]
 #[allow(missing_docs)]
 pub struct SignedBuilderBidWithProofs {
     pub bid: SignedBuilderBid,
-    pub proofs: List<ConstraintProof, 300>,
+    pub proofs: List<ConstraintProof, MAX_CONSTRAINT_PROOFS>,
 }
 
 /// A proof that a transaction is included in a block
@@ -110,15 +188,18 @@ pub struct MerkleMultiProof {
 pub struct FetchPayloadRequest {
     /// Slot number for the payload to fetch
     pub slot: u64,
+    /// The signed blinded block of the proposer, if available, whose header must match the
+    /// block hash of the payload
+    pub signed_blinded_block: Option<Box<SignedBlindedBeaconBlock>>,
     /// Channel to send the response to
     pub response_tx: oneshot::Sender<Option<PayloadAndBid>>,
 }
 
 /// Response to a fetch payload request
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(missing_docs)]
 pub struct PayloadAndBid {
-    pub bid: SignedBuilderBid,
+    pub bid: VersionedSignedBuilderBid,
     pub payload: GetPayloadResponse,
 }
 
@@ -140,7 +221,7 @@ impl Default for PayloadAndBlobs {
 }
 
 /// Response to a get payload request
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "version", content = "data")]
 #[allow(missing_docs)]
 pub enum GetPayloadResponse {
@@ -188,3 +269,43 @@ impl From<PayloadAndBlobs> for GetPayloadResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ethereum_consensus::electra;
+
+    use super::*;
+
+    #[test]
+    fn test_electra_payload_and_blobs_roundtrip() {
+        let payload_and_blobs = PayloadAndBlobs {
+            execution_payload: ExecutionPayload::Electra(
+                electra::mainnet::ExecutionPayload::default(),
+            ),
+            blobs_bundle: BlobsBundle::default(),
+        };
+
+        let response = GetPayloadResponse::from(payload_and_blobs);
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["version"], "electra");
+        assert_eq!(json["data"]["execution_payload"]["deposit_requests"], serde_json::json!([]));
+
+        let decoded = serde_json::from_value::<GetPayloadResponse>(json.clone()).unwrap();
+        assert!(matches!(decoded, GetPayloadResponse::Electra(_)));
+        assert_eq!(decoded.execution_payload().version(), Fork::Electra);
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn test_versioned_bid_roundtrip() {
+        let bid = VersionedSignedBuilderBid::Electra(SignedElectraBuilderBid::default());
+        let json = serde_json::to_value(&bid).unwrap();
+        assert_eq!(json["version"], "electra");
+
+        let decoded = serde_json::from_value::<VersionedSignedBuilderBid>(json).unwrap();
+        assert_eq!(decoded.version(), Fork::Electra);
+
+        let bid = VersionedSignedBuilderBid::from(SignedBuilderBid::default());
+        assert_eq!(serde_json::to_value(&bid).unwrap()["version"], "deneb");
+    }
+}
@@ -2,11 +2,14 @@ use std::{borrow::Cow, fmt};
 
 use alloy::{
     consensus::BlobTransactionSidecar,
-    eips::eip2718::{Decodable2718, Encodable2718},
+    eips::{
+        eip2718::{Decodable2718, Eip2718Error, Encodable2718},
+        eip2930::AccessList,
+    },
     hex,
     primitives::{Address, Bytes, TxKind, U256},
 };
-use reth_primitives::{PooledTransactionsElement, TxType};
+use reth_primitives::{revm_primitives::SpecId, PooledTransactionsElement, TxType};
 use serde::{de, ser::SerializeSeq};
 
 /// Trait that exposes additional information on transaction types that don't already do it
@@ -33,6 +36,12 @@ pub trait TransactionExt {
     /// Returns the blob sidecar of the transaction, if any.
     fn blob_sidecar(&self) -> Option<&BlobTransactionSidecar>;
 
+    /// Returns the access list of the transaction, if any.
+    fn access_list(&self) -> Option<&AccessList>;
+
+    /// Returns the number of EIP-7702 authorizations of the transaction.
+    fn authorization_count(&self) -> usize;
+
     /// Returns the size of the transaction in bytes.
     fn size(&self) -> usize;
 }
@@ -43,8 +52,8 @@ impl TransactionExt for PooledTransactionsElement {
             PooledTransactionsElement::Legacy { transaction, .. } => transaction.gas_limit,
             PooledTransactionsElement::Eip2930 { transaction, .. } => transaction.gas_limit,
             PooledTransactionsElement::Eip1559 { transaction, .. } => transaction.gas_limit,
+            PooledTransactionsElement::Eip7702 { transaction, .. } => transaction.gas_limit,
             PooledTransactionsElement::BlobTransaction(blob_tx) => blob_tx.transaction.tx.gas_limit,
-            _ => unimplemented!(),
         }
     }
 
@@ -53,8 +62,8 @@ impl TransactionExt for PooledTransactionsElement {
             PooledTransactionsElement::Legacy { transaction, .. } => transaction.value,
             PooledTransactionsElement::Eip2930 { transaction, .. } => transaction.value,
             PooledTransactionsElement::Eip1559 { transaction, .. } => transaction.value,
+            PooledTransactionsElement::Eip7702 { transaction, .. } => transaction.value,
             PooledTransactionsElement::BlobTransaction(blob_tx) => blob_tx.transaction.tx.value,
-            _ => unimplemented!(),
         }
     }
 
@@ -63,8 +72,8 @@ impl TransactionExt for PooledTransactionsElement {
             PooledTransactionsElement::Legacy { .. } => TxType::Legacy,
             PooledTransactionsElement::Eip2930 { .. } => TxType::Eip2930,
             PooledTransactionsElement::Eip1559 { .. } => TxType::Eip1559,
+            PooledTransactionsElement::Eip7702 { .. } => TxType::Eip7702,
             PooledTransactionsElement::BlobTransaction(_) => TxType::Eip4844,
-            _ => unimplemented!(),
         }
     }
 
@@ -73,10 +82,10 @@ impl TransactionExt for PooledTransactionsElement {
             PooledTransactionsElement::Legacy { transaction, .. } => transaction.to,
             PooledTransactionsElement::Eip2930 { transaction, .. } => transaction.to,
             PooledTransactionsElement::Eip1559 { transaction, .. } => transaction.to,
+            PooledTransactionsElement::Eip7702 { transaction, .. } => TxKind::Call(transaction.to),
             PooledTransactionsElement::BlobTransaction(blob_tx) => {
                 TxKind::Call(blob_tx.transaction.tx.to)
             }
-            _ => unimplemented!(),
         }
     }
 
@@ -85,8 +94,8 @@ impl TransactionExt for PooledTransactionsElement {
             PooledTransactionsElement::Legacy { transaction, .. } => &transaction.input,
             PooledTransactionsElement::Eip2930 { transaction, .. } => &transaction.input,
             PooledTransactionsElement::Eip1559 { transaction, .. } => &transaction.input,
+            PooledTransactionsElement::Eip7702 { transaction, .. } => &transaction.input,
             PooledTransactionsElement::BlobTransaction(blob_tx) => &blob_tx.transaction.tx.input,
-            _ => unimplemented!(),
         }
     }
 
@@ -95,10 +104,10 @@ impl TransactionExt for PooledTransactionsElement {
             PooledTransactionsElement::Legacy { transaction, .. } => transaction.chain_id,
             PooledTransactionsElement::Eip2930 { transaction, .. } => Some(transaction.chain_id),
             PooledTransactionsElement::Eip1559 { transaction, .. } => Some(transaction.chain_id),
+            PooledTransactionsElement::Eip7702 { transaction, .. } => Some(transaction.chain_id),
             PooledTransactionsElement::BlobTransaction(blob_tx) => {
                 Some(blob_tx.transaction.tx.chain_id)
             }
-            _ => unimplemented!(),
         }
     }
 
@@ -111,17 +120,101 @@ impl TransactionExt for PooledTransactionsElement {
         }
     }
 
+    fn access_list(&self) -> Option<&AccessList> {
+        match self {
+            PooledTransactionsElement::Legacy { .. } => None,
+            PooledTransactionsElement::Eip2930 { transaction, .. } => {
+                Some(&transaction.access_list)
+            }
+            PooledTransactionsElement::Eip1559 { transaction, .. } => {
+                Some(&transaction.access_list)
+            }
+            PooledTransactionsElement::Eip7702 { transaction, .. } => {
+                Some(&transaction.access_list)
+            }
+            PooledTransactionsElement::BlobTransaction(blob_tx) => {
+                Some(&blob_tx.transaction.tx.access_list)
+            }
+        }
+    }
+
+    fn authorization_count(&self) -> usize {
+        match self {
+            PooledTransactionsElement::Eip7702 { transaction, .. } => {
+                transaction.authorization_list.len()
+            }
+            _ => 0,
+        }
+    }
+
     fn size(&self) -> usize {
         match self {
             PooledTransactionsElement::Legacy { transaction, .. } => transaction.size(),
             PooledTransactionsElement::Eip2930 { transaction, .. } => transaction.size(),
             PooledTransactionsElement::Eip1559 { transaction, .. } => transaction.size(),
+            PooledTransactionsElement::Eip7702 { transaction, .. } => transaction.size(),
             PooledTransactionsElement::BlobTransaction(blob_tx) => blob_tx.transaction.tx.size(),
-            _ => unimplemented!(),
         }
     }
 }
 
+/// Base cost of every transaction.
+const TX_BASE_GAS: u64 = 21_000;
+/// Additional cost of contract creation transactions.
+const TX_CREATE_GAS: u64 = 32_000;
+/// Cost of every zero byte of calldata.
+const TX_DATA_ZERO_GAS: u64 = 4;
+/// Cost of every non-zero byte of calldata (EIP-2028).
+const TX_DATA_NON_ZERO_GAS: u64 = 16;
+/// Cost of every address in the access list (EIP-2930).
+const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+/// Cost of every storage key in the access list (EIP-2930).
+const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+/// Cost of every 32-byte word of initcode (EIP-3860).
+const INITCODE_WORD_GAS: u64 = 2;
+/// Cost of every authorization of set code transactions (EIP-7702).
+const PER_EMPTY_ACCOUNT_GAS: u64 = 25_000;
+/// Floor cost of every calldata token (EIP-7623).
+const TOTAL_COST_FLOOR_PER_TOKEN: u64 = 10;
+
+/// Returns the intrinsic gas of the transaction under the given fork, i.e. the gas charged
+/// before any execution. A transaction with a lower gas limit is invalid.
+///
+/// NOTE: blobs are paid with blob gas, which is separate from the gas limit.
+pub fn intrinsic_gas(tx: &impl TransactionExt, spec: SpecId) -> u64 {
+    let input = tx.input();
+    let zero_bytes = input.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zero_bytes = input.len() as u64 - zero_bytes;
+
+    let mut gas =
+        TX_BASE_GAS + zero_bytes * TX_DATA_ZERO_GAS + non_zero_bytes * TX_DATA_NON_ZERO_GAS;
+
+    if tx.tx_kind().is_create() {
+        gas += TX_CREATE_GAS;
+
+        if spec.is_enabled_in(SpecId::SHANGHAI) {
+            gas += (input.len() as u64).div_ceil(32) * INITCODE_WORD_GAS;
+        }
+    }
+
+    if let Some(access_list) = tx.access_list() {
+        for item in access_list.iter() {
+            gas += ACCESS_LIST_ADDRESS_GAS;
+            gas += item.storage_keys.len() as u64 * ACCESS_LIST_STORAGE_KEY_GAS;
+        }
+    }
+
+    if spec.is_enabled_in(SpecId::PRAGUE) {
+        gas += tx.authorization_count() as u64 * PER_EMPTY_ACCOUNT_GAS;
+
+        // The gas limit must also cover the calldata floor cost
+        let tokens = zero_bytes + non_zero_bytes * (TX_DATA_NON_ZERO_GAS / TX_DATA_ZERO_GAS);
+        gas = gas.max(TX_BASE_GAS + tokens * TOTAL_COST_FLOOR_PER_TOKEN);
+    }
+
+    gas
+}
+
 /// Returns a string representation of the transaction type.
 pub const fn tx_type_str(tx_type: TxType) -> &'static str {
     match tx_type {
@@ -232,7 +325,8 @@ impl FullTransaction {
     }
 }
 
-/// Serialize a list of transactions into a sequence of hex-encoded strings.
+/// Serialize a list of transactions into a sequence of `0x`-prefixed hex-encoded EIP-2718
+/// envelopes. Legacy transactions are encoded as plain RLP lists, without a type byte.
 pub fn serialize_txs<S: serde::Serializer>(
     txs: &[FullTransaction],
     serializer: S,
@@ -245,7 +339,8 @@ pub fn serialize_txs<S: serde::Serializer>(
     seq.end()
 }
 
-/// Deserialize a list of transactions from a sequence of hex-encoded strings.
+/// Deserialize a list of transactions from a sequence of hex-encoded strings, with or without
+/// a `0x` prefix. Errors name the index of the invalid transaction in the sequence.
 pub fn deserialize_txs<'de, D>(deserializer: D) -> Result<Vec<FullTransaction>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -253,13 +348,323 @@ where
     let hex_strings = <Vec<Cow<'_, str>> as de::Deserialize>::deserialize(deserializer)?;
     let mut txs = Vec::with_capacity(hex_strings.len());
 
-    for s in hex_strings {
-        let data = hex::decode(s.trim_start_matches("0x")).map_err(de::Error::custom)?;
-        let tx = PooledTransactionsElement::decode_2718(&mut data.as_slice())
-            .map_err(de::Error::custom)
-            .map(|tx| FullTransaction { tx, sender: None })?;
+    for (index, s) in hex_strings.iter().enumerate() {
+        let tx = decode_hex_tx(s).map_err(|err| {
+            de::Error::custom(format!("invalid transaction at index {index}: {err}"))
+        })?;
         txs.push(tx);
     }
 
     Ok(txs)
 }
+
+/// Error decoding a hex-encoded transaction.
+#[derive(Debug, thiserror::Error)]
+pub enum TxDecodingError {
+    /// The string isn't valid hex.
+    #[error("invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    /// The bytes aren't a valid EIP-2718 envelope.
+    #[error("invalid envelope: {0}")]
+    Envelope(#[from] Eip2718Error),
+    /// The envelope decodes, but doesn't re-encode to the same bytes, e.g. with trailing bytes.
+    #[error("non-canonical encoding of a {0} transaction")]
+    NonCanonical(&'static str),
+}
+
+/// Decode a transaction from its hex-encoded EIP-2718 envelope, with or without a single `0x`
+/// prefix. The transaction must re-encode to the exact same bytes, so that it's forwarded to
+/// the relays and builders as it was committed to.
+pub fn decode_hex_tx(s: &str) -> Result<FullTransaction, TxDecodingError> {
+    // NOTE: the prefix is stripped by the decoder, while a duplicated prefix is invalid hex
+    let data = hex::decode(s)?;
+    let tx = PooledTransactionsElement::decode_2718(&mut data.as_slice())?;
+
+    if tx.encoded_2718() != data {
+        return Err(TxDecodingError::NonCanonical(tx_type_str(tx.tx_type())));
+    }
+
+    Ok(FullTransaction { tx, sender: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{
+            SidecarBuilder, SignableTransaction, Signed, SimpleCoder, TxEip1559, TxEip2930,
+            TxEip4844, TxEip4844WithSidecar, TxEip7702, TxEnvelope, TxLegacy,
+        },
+        eips::eip2930::AccessListItem,
+        network::{TransactionBuilder, TxSignerSync},
+        primitives::B256,
+        rpc::types::TransactionRequest,
+        signers::{k256::SecretKey, local::PrivateKeySigner, Signature},
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        primitives::InclusionRequest,
+        test_util::{create_signed_inclusion_request, default_test_transaction},
+    };
+
+    use super::*;
+
+    /// Sign a test transaction, customized with the given function.
+    async fn signed_tx(
+        f: impl FnOnce(TransactionRequest) -> TransactionRequest,
+    ) -> FullTransaction {
+        let sk = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let sender = PrivateKeySigner::from_slice(&[0x42; 32]).unwrap().address();
+
+        let tx = f(default_test_transaction(sender, None).with_gas_limit(1_000_000));
+        let request = create_signed_inclusion_request(&[tx], &sk, 10).await.unwrap();
+        request.txs[0].clone()
+    }
+
+    #[tokio::test]
+    async fn test_intrinsic_gas_transfer() {
+        let tx = signed_tx(|tx| tx).await;
+        assert_eq!(intrinsic_gas(&tx.tx, SpecId::CANCUN), 21_000);
+        assert_eq!(intrinsic_gas(&tx.tx, SpecId::PRAGUE), 21_000);
+    }
+
+    #[tokio::test]
+    async fn test_intrinsic_gas_calldata() {
+        // 100 zero bytes and 100 non-zero bytes
+        let input = [vec![0u8; 100], vec![0xffu8; 100]].concat();
+        let tx = signed_tx(|tx| tx.with_input(input)).await;
+
+        // 21000 + 100 * 4 + 100 * 16
+        assert_eq!(intrinsic_gas(&tx.tx, SpecId::CANCUN), 23_000);
+        // EIP-7623 floor: 21000 + (100 + 100 * 4) * 10
+        assert_eq!(intrinsic_gas(&tx.tx, SpecId::PRAGUE), 26_000);
+    }
+
+    #[tokio::test]
+    async fn test_intrinsic_gas_access_list() {
+        let access_list = AccessList::from(vec![
+            AccessListItem { address: Address::repeat_byte(0x11), storage_keys: vec![B256::ZERO] },
+            AccessListItem {
+                address: Address::repeat_byte(0x22),
+                storage_keys: vec![B256::ZERO, B256::repeat_byte(0x01)],
+            },
+        ]);
+        let tx = signed_tx(|tx| tx.with_access_list(access_list)).await;
+
+        // 21000 + 2 * 2400 + 3 * 1900
+        assert_eq!(intrinsic_gas(&tx.tx, SpecId::CANCUN), 31_500);
+    }
+
+    #[tokio::test]
+    async fn test_intrinsic_gas_contract_creation() {
+        let tx = signed_tx(|tx| tx.with_kind(TxKind::Create).with_input(vec![0xffu8; 64])).await;
+
+        // 21000 + 32000 + 64 * 16, plus 2 words of initcode after Shanghai
+        assert_eq!(intrinsic_gas(&tx.tx, SpecId::LONDON), 54_024);
+        assert_eq!(intrinsic_gas(&tx.tx, SpecId::CANCUN), 54_028);
+    }
+
+    /// The number of random transactions of each type to round-trip.
+    const ROUND_TRIPS: u64 = 8;
+
+    fn random_bytes(rng: &mut StdRng, max_len: usize) -> Bytes {
+        let len = rng.gen_range(0..max_len);
+        (0..len).map(|_| rng.gen::<u8>()).collect::<Vec<_>>().into()
+    }
+
+    fn random_access_list(rng: &mut StdRng) -> AccessList {
+        let items = (0..rng.gen_range(0..3))
+            .map(|_| AccessListItem {
+                address: Address::from(rng.gen::<[u8; 20]>()),
+                storage_keys: (0..rng.gen_range(0..3))
+                    .map(|_| B256::from(rng.gen::<[u8; 32]>()))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        AccessList::from(items)
+    }
+
+    fn sign<T: SignableTransaction<Signature>>(mut tx: T, signer: &PrivateKeySigner) -> Signed<T> {
+        let signature = signer.sign_transaction_sync(&mut tx).unwrap();
+        tx.into_signed(signature)
+    }
+
+    /// Generate a random signed transaction of the given type, as an EIP-2718 envelope.
+    fn random_envelope(rng: &mut StdRng, tx_type: TxType) -> Vec<u8> {
+        let signer = PrivateKeySigner::from_slice(&rng.gen::<[u8; 32]>()).unwrap();
+        let to = Address::from(rng.gen::<[u8; 20]>());
+        let nonce = rng.gen::<u32>() as u64;
+        let gas_limit = rng.gen_range(21_000..30_000_000);
+        let value = U256::from(rng.gen::<u128>());
+        let input = random_bytes(rng, 256);
+        let max_fee_per_gas = rng.gen::<u64>() as u128;
+        let max_priority_fee_per_gas = rng.gen_range(0..=max_fee_per_gas);
+
+        let envelope = match tx_type {
+            TxType::Legacy => {
+                // Pre-EIP-155 transactions don't have a chain ID
+                let chain_id = rng.gen_bool(0.5).then_some(17000);
+                let tx = TxLegacy {
+                    chain_id,
+                    nonce,
+                    gas_price: max_fee_per_gas,
+                    gas_limit,
+                    to: TxKind::Call(to),
+                    value,
+                    input,
+                };
+                TxEnvelope::from(sign(tx, &signer))
+            }
+            TxType::Eip2930 => {
+                let tx = TxEip2930 {
+                    chain_id: 17000,
+                    nonce,
+                    gas_price: max_fee_per_gas,
+                    gas_limit,
+                    to: TxKind::Call(to),
+                    value,
+                    access_list: random_access_list(rng),
+                    input,
+                };
+                TxEnvelope::from(sign(tx, &signer))
+            }
+            TxType::Eip1559 => {
+                let tx = TxEip1559 {
+                    chain_id: 17000,
+                    nonce,
+                    gas_limit,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    to: TxKind::Call(to),
+                    value,
+                    access_list: random_access_list(rng),
+                    input,
+                };
+                TxEnvelope::from(sign(tx, &signer))
+            }
+            TxType::Eip4844 => {
+                let data = random_bytes(rng, 1024);
+                let sidecar = SidecarBuilder::<SimpleCoder>::from_slice(&data).build().unwrap();
+                let tx = TxEip4844 {
+                    chain_id: 17000,
+                    nonce,
+                    gas_limit,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    to,
+                    value,
+                    access_list: random_access_list(rng),
+                    blob_versioned_hashes: sidecar.versioned_hashes().collect(),
+                    max_fee_per_blob_gas: rng.gen::<u64>() as u128,
+                    input,
+                };
+                let tx = TxEip4844WithSidecar::from_tx_and_sidecar(tx, sidecar);
+                TxEnvelope::from(sign(tx, &signer))
+            }
+            TxType::Eip7702 => {
+                // The authorizations are only checked when executing the transaction
+                let tx = TxEip7702 {
+                    chain_id: 17000,
+                    nonce,
+                    gas_limit,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    to,
+                    value,
+                    access_list: random_access_list(rng),
+                    input,
+                    ..Default::default()
+                };
+                TxEnvelope::from(sign(tx, &signer))
+            }
+        };
+
+        envelope.encoded_2718()
+    }
+
+    #[test]
+    fn test_txs_round_trip() {
+        let tx_types =
+            [TxType::Legacy, TxType::Eip2930, TxType::Eip1559, TxType::Eip4844, TxType::Eip7702];
+
+        for seed in 0..ROUND_TRIPS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let envelopes = tx_types
+                .iter()
+                .map(|tx_type| random_envelope(&mut rng, *tx_type))
+                .collect::<Vec<_>>();
+
+            let txs = envelopes
+                .iter()
+                .map(|envelope| FullTransaction::decode_enveloped(envelope).unwrap())
+                .collect::<Vec<_>>();
+            for (tx, tx_type) in txs.iter().zip(tx_types) {
+                assert_eq!(TransactionExt::tx_type(&tx.tx), tx_type);
+            }
+
+            let request = InclusionRequest {
+                slot: 42,
+                txs,
+                tx_hashes: Vec::new(),
+                top: false,
+                expires_at: None,
+                signature: None,
+                signer: None,
+            };
+            let json = serde_json::to_value(&request).unwrap();
+            for raw in json["txs"].as_array().unwrap() {
+                let raw = raw.as_str().unwrap();
+                assert!(raw.starts_with("0x") && !raw.starts_with("0x0x"), "seed {seed}");
+            }
+
+            let parsed: InclusionRequest = serde_json::from_value(json).unwrap();
+            for (tx, envelope) in parsed.txs.iter().zip(&envelopes) {
+                assert_eq!(
+                    &tx.encoded_2718(),
+                    envelope,
+                    "seed {seed}, {}",
+                    tx_type_str(tx.tx_type())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize_txs_hex_prefix() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let envelope = random_envelope(&mut rng, TxType::Legacy);
+
+        // Both prefixed and bare hex are accepted
+        let prefixed = serde_json::json!({ "slot": 1, "txs": [hex::encode_prefixed(&envelope)] });
+        let bare = serde_json::json!({ "slot": 1, "txs": [hex::encode(&envelope)] });
+        let prefixed = serde_json::from_value::<InclusionRequest>(prefixed).unwrap();
+        let bare = serde_json::from_value::<InclusionRequest>(bare).unwrap();
+        assert_eq!(prefixed.txs, bare.txs);
+        assert_eq!(bare.txs[0].encoded_2718(), envelope);
+
+        // A duplicated prefix is rejected
+        let raw = format!("0x0x{}", hex::encode(&envelope));
+        assert!(decode_hex_tx(&raw).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_txs_non_canonical() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let valid = random_envelope(&mut rng, TxType::Eip1559);
+
+        // The envelope decodes, but the trailing byte is lost when re-encoding
+        let mut trailing = random_envelope(&mut rng, TxType::Legacy);
+        trailing.push(0x00);
+        assert!(matches!(
+            decode_hex_tx(&hex::encode(&trailing)),
+            Err(TxDecodingError::NonCanonical("legacy"))
+        ));
+
+        let request = serde_json::json!({
+            "slot": 1,
+            "txs": [hex::encode_prefixed(valid), hex::encode_prefixed(trailing)]
+        });
+        let err = serde_json::from_value::<InclusionRequest>(request).unwrap_err();
+        assert!(err.to_string().contains("invalid transaction at index 1"), "{err}");
+    }
+}
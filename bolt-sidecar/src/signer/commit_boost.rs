@@ -1,4 +1,4 @@
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashSet, str::FromStr, sync::Arc};
 
 use alloy::{primitives::Address, rpc::types::beacon::BlsSignature, signers::Signature};
 use cb_common::{
@@ -11,7 +11,7 @@ use parking_lot::RwLock;
 use reqwest::Url;
 use ssz::Decode;
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, info};
 
 use crate::{
     crypto::{bls::BLS_DST_PREFIX, ecdsa::SignerECDSA},
@@ -48,38 +48,41 @@ impl CommitBoostSigner {
         let socket_addr = parse_address_from_url(signer_url).map_err(CommitBoostError::Other)?;
         let signer_client = SignerClient::new(socket_addr, jwt).map_err(CommitBoostError::Other)?;
 
-        let client = Self {
+        // The pubkeys are fetched with `refresh_pubkeys`, when listing the available keys
+        Ok(Self {
             signer_client,
             pubkeys: Arc::new(RwLock::new(Vec::new())),
             proxy_ecdsa: Arc::new(RwLock::new(Vec::new())),
-        };
-
-        let mut this = client.clone();
-        tokio::spawn(async move {
-            match this.signer_client.get_pubkeys().await {
-                Ok(pubkeys) => {
-                    info!(
-                        consensus = pubkeys.consensus.len(),
-                        bls_proxy = pubkeys.proxy_bls.len(),
-                        ecdsa_proxy = pubkeys.proxy_ecdsa.len(),
-                        "Received pubkeys"
-                    );
-                    let mut pubkeys_lock = this.pubkeys.write();
-                    let mut proxy_ecdsa_lock = this.proxy_ecdsa.write();
-                    *pubkeys_lock = pubkeys
-                        .consensus
-                        .into_iter()
-                        .map(|k| BlsPublicKey::try_from(k.as_ref()).unwrap())
-                        .collect();
-                    *proxy_ecdsa_lock = pubkeys.proxy_ecdsa;
-                }
-                Err(e) => {
-                    error!(?e, "Failed to fetch pubkeys");
-                }
-            }
-        });
+        })
+    }
 
-        Ok(client)
+    /// Fetch the current pubkeys of the Commit-Boost signer, and replace the known ones with
+    /// them. Returns the consensus pubkeys available for signing.
+    pub async fn refresh_pubkeys(&self) -> SignerResult<HashSet<BlsPublicKey>> {
+        let mut signer_client = self.signer_client.clone();
+        let pubkeys =
+            signer_client.get_pubkeys().await.map_err(CommitBoostError::SignerClientError)?;
+
+        let consensus = pubkeys
+            .consensus
+            .iter()
+            .map(|k| BlsPublicKey::try_from(k.as_ref()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| CommitBoostError::Other(eyre::eyre!("invalid consensus pubkey: {e}")))?;
+
+        let changed = *self.pubkeys.read() != consensus;
+        if changed {
+            info!(
+                consensus = consensus.len(),
+                bls_proxy = pubkeys.proxy_bls.len(),
+                ecdsa_proxy = pubkeys.proxy_ecdsa.len(),
+                "Received pubkeys"
+            );
+        }
+
+        *self.pubkeys.write() = consensus.clone();
+        *self.proxy_ecdsa.write() = pubkeys.proxy_ecdsa;
+        Ok(consensus.into_iter().collect())
     }
 
     /// Get the consensus public key from the Commit-Boost signer.
@@ -120,11 +123,15 @@ impl CommitBoostSigner {
         self.get_consensus_pubkey()
     }
 
-    /// Sign an object root with the Commit Boost domain.
-    pub async fn sign_commit_boost_root(&self, data: [u8; 32]) -> SignerResult<BlsSignature> {
+    /// Sign an object root with the Commit Boost domain, with the given consensus pubkey.
+    pub async fn sign_commit_boost_root(
+        &self,
+        data: [u8; 32],
+        pubkey: &BlsPublicKey,
+    ) -> SignerResult<BlsSignature> {
         // convert the pubkey from ethereum_consensus to commit-boost format
         // TODO: compat: this is the only way to obtain a BlsPubkey for now unfortunately
-        let pubkey = cb_common::signer::BlsPublicKey::from_ssz_bytes(self.pubkey().as_ref())
+        let pubkey = cb_common::signer::BlsPublicKey::from_ssz_bytes(pubkey.as_ref())
             .expect("pubkey bytes conversion");
 
         let request = SignConsensusRequest { pubkey, object_root: data };
@@ -221,15 +228,16 @@ mod test {
             }
         };
         let signer = CommitBoostSigner::new(signer_server_address.parse()?, &jwt_hex).unwrap();
+        signer.refresh_pubkeys().await?;
 
         // Generate random data for the test
         let mut rng = rand::thread_rng();
         let mut data = [0u8; 32];
         rng.fill(&mut data);
 
-        let signature = signer.sign_commit_boost_root(data).await.unwrap();
-        let sig = blst::min_pk::Signature::from_bytes(signature.as_ref()).unwrap();
         let pubkey = signer.get_consensus_pubkey();
+        let signature = signer.sign_commit_boost_root(data, &pubkey).await.unwrap();
+        let sig = blst::min_pk::Signature::from_bytes(signature.as_ref()).unwrap();
         let bls_pubkey = blst::min_pk::PublicKey::from_bytes(pubkey.as_ref()).unwrap();
         assert!(signer.verify_bls(&data, &sig, &bls_pubkey));
 
@@ -251,6 +259,7 @@ mod test {
             }
         };
         let signer = CommitBoostSigner::new(signer_server_address.parse()?, &jwt_hex).unwrap();
+        signer.refresh_pubkeys().await?;
         let pubkey = signer.get_proxy_ecdsa_pubkey();
 
         // Generate random data for the test
@@ -98,6 +98,12 @@ impl KeystoreSigner {
         Ok(Self { keypairs, chain })
     }
 
+    /// Creates a new `KeystoreSigner` from the given keypairs.
+    #[cfg(test)]
+    pub fn from_keypairs(keypairs: Vec<Keypair>, chain: ChainConfig) -> Self {
+        Self { keypairs, chain }
+    }
+
     /// Returns the public keys of the keypairs in the keystore.
     pub fn pubkeys(&self) -> HashSet<BlsPublicKey> {
         self.keypairs
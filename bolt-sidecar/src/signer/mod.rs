@@ -1,6 +1,18 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
+use alloy::{
+    primitives::Address,
+    signers::{aws::AwsSigner, local::PrivateKeySigner, Signature as AlloySignature},
+};
 use ethereum_consensus::crypto::bls::PublicKey as BlsPublicKey;
+use eyre::{eyre, Context};
+use tokio::{sync::watch, task::JoinHandle, time::Instant};
+use tracing::{debug, warn};
+
+use crate::{
+    config::{CommitmentSignerKind, Opts},
+    crypto::SignerECDSA,
+};
 
 /// Commit-Boost remote signer client wrapper.
 pub mod commit_boost;
@@ -14,6 +26,10 @@ pub use keystore::KeystoreSigner;
 pub mod local;
 pub use local::LocalSigner;
 
+/// Web3Signer remote ECDSA signer client.
+pub mod web3signer;
+pub use web3signer::Web3Signer;
+
 /// Error in the signer.
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
@@ -26,9 +42,31 @@ pub enum SignerError {
     Keystore(#[from] keystore::KeystoreError),
 }
 
+impl SignerError {
+    /// Returns true if the error is specific to the signing key, e.g. a key missing from the
+    /// signer, so that signing with another key may succeed.
+    pub fn is_key_specific(&self) -> bool {
+        match self {
+            SignerError::LocalSigner(_) => false,
+            // The remote signer rejects keys it doesn't hold
+            SignerError::CommitBoost(err) => {
+                !matches!(err, commit_boost::CommitBoostError::Other(_))
+            }
+            SignerError::Keystore(err) => matches!(
+                err,
+                keystore::KeystoreError::UnknownPublicKey(_) |
+                    keystore::KeystoreError::SignatureLength(..)
+            ),
+        }
+    }
+}
+
 /// Result type for the signer.
 pub type SignerResult<T> = std::result::Result<T, SignerError>;
 
+/// Interval between two refreshes of the public keys of a remote signer.
+pub const REMOTE_PUBKEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Signer for BLS signatures.
 #[derive(Debug, Clone)]
 pub enum SignerBLS {
@@ -41,12 +79,296 @@ pub enum SignerBLS {
 }
 
 impl SignerBLS {
-    /// Returns all the public keys available for signing.
-    pub fn available_pubkeys(&self) -> HashSet<BlsPublicKey> {
+    /// Returns all the public keys available for signing. Remote signers are queried for
+    /// their current keys, which can fail.
+    pub async fn available_pubkeys(&self) -> SignerResult<HashSet<BlsPublicKey>> {
+        match self {
+            SignerBLS::Local(signer) => Ok([signer.pubkey()].into()),
+            SignerBLS::CommitBoost(signer) => signer.refresh_pubkeys().await,
+            SignerBLS::Keystore(signer) => Ok(signer.pubkeys()),
+        }
+    }
+
+    /// Returns true if the keys of the signer are held by a remote service, and can change
+    /// while the sidecar is running.
+    pub fn is_remote(&self) -> bool {
+        matches!(self, SignerBLS::CommitBoost(_))
+    }
+
+    /// Returns a short label identifying the signer backend, for logs and metrics.
+    pub fn backend(&self) -> &'static str {
+        match self {
+            SignerBLS::Local(_) => "local",
+            SignerBLS::CommitBoost(_) => "commit-boost",
+            SignerBLS::Keystore(_) => "keystore",
+        }
+    }
+}
+
+/// Signer for the ECDSA signatures of the commitment responses.
+#[derive(Debug, Clone)]
+pub enum CommitmentSigner {
+    /// Local signer with an ECDSA private key.
+    PrivateKey(PrivateKeySigner),
+    /// Signer with a key held by AWS KMS.
+    Kms(AwsSigner),
+    /// Signer with a key held by a Web3Signer instance.
+    Web3Signer(Web3Signer),
+}
+
+impl CommitmentSigner {
+    /// Create the commitment signer selected by the given [Opts]. Remote signers are reached
+    /// to resolve the address of their key.
+    pub async fn from_opts(opts: &Opts) -> eyre::Result<Self> {
+        match opts.commitment_signer {
+            CommitmentSignerKind::PrivateKey => {
+                let key = opts.commitment_private_key.as_ref().ok_or_else(|| {
+                    eyre!("--commitment-private-key is required with the private-key signer")
+                })?;
+
+                Ok(Self::PrivateKey(PrivateKeySigner::from_signing_key(key.0.clone())))
+            }
+            CommitmentSignerKind::Kms => {
+                let key_id = opts.commitment_kms_key_id.clone().ok_or_else(|| {
+                    eyre!("--commitment-kms-key-id is required with the kms signer")
+                })?;
+
+                // The region and credentials are resolved from the standard AWS environment
+                let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                let client = aws_sdk_kms::Client::new(&config);
+                let signer = AwsSigner::new(client, key_id, None)
+                    .await
+                    .wrap_err("Failed to load the commitment key from AWS KMS")?;
+
+                Ok(Self::Kms(signer))
+            }
+            CommitmentSignerKind::Web3signer => {
+                let url = opts.commitment_web3signer_url.clone().ok_or_else(|| {
+                    eyre!("--commitment-web3signer-url is required with the web3signer signer")
+                })?;
+                let public_key = opts.commitment_web3signer_public_key.as_deref().ok_or_else(|| {
+                    eyre!("--commitment-web3signer-public-key is required with the web3signer signer")
+                })?;
+
+                Ok(Self::Web3Signer(Web3Signer::new(url, public_key)?))
+            }
+        }
+    }
+
+    /// Returns a short label identifying the signer backend, for logs and metrics.
+    pub fn backend(&self) -> &'static str {
+        match self {
+            CommitmentSigner::PrivateKey(_) => "private-key",
+            CommitmentSigner::Kms(_) => "kms",
+            CommitmentSigner::Web3Signer(_) => "web3signer",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SignerECDSA for CommitmentSigner {
+    fn public_key(&self) -> Address {
+        match self {
+            CommitmentSigner::PrivateKey(signer) => signer.public_key(),
+            CommitmentSigner::Kms(signer) => signer.public_key(),
+            CommitmentSigner::Web3Signer(signer) => signer.public_key(),
+        }
+    }
+
+    async fn sign_hash(&self, hash: &[u8; 32]) -> eyre::Result<AlloySignature> {
+        match self {
+            CommitmentSigner::PrivateKey(signer) => signer.sign_hash(hash).await,
+            CommitmentSigner::Kms(signer) => signer.sign_hash(hash).await,
+            CommitmentSigner::Web3Signer(signer) => signer.sign_hash(hash).await,
+        }
+    }
+
+    async fn sign_payload(&self, payload: &[u8]) -> eyre::Result<AlloySignature> {
         match self {
-            SignerBLS::Local(signer) => [signer.pubkey()].into(),
-            SignerBLS::CommitBoost(signer) => [signer.pubkey()].into(),
-            SignerBLS::Keystore(signer) => signer.pubkeys(),
+            CommitmentSigner::PrivateKey(signer) => signer.sign_payload(payload).await,
+            CommitmentSigner::Kms(signer) => signer.sign_payload(payload).await,
+            CommitmentSigner::Web3Signer(signer) => signer.sign_payload(payload).await,
         }
     }
 }
+
+/// The last known set of public keys available for signing. Failures to list the keys of the
+/// signer keep the last known good set. The set is cheap to clone: every clone shares the same
+/// keys, and changes can be subscribed to.
+#[derive(Debug, Clone)]
+pub struct AvailablePubkeys(Arc<watch::Sender<HashSet<BlsPublicKey>>>);
+
+impl Default for AvailablePubkeys {
+    fn default() -> Self {
+        let (tx, _) = watch::channel(HashSet::new());
+        Self(Arc::new(tx))
+    }
+}
+
+impl AvailablePubkeys {
+    /// Returns the last known set of public keys.
+    pub fn get(&self) -> HashSet<BlsPublicKey> {
+        self.0.borrow().clone()
+    }
+
+    /// Subscribe to the changes of the set of public keys, e.g. when keys are added to a
+    /// remote signer.
+    pub fn subscribe(&self) -> watch::Receiver<HashSet<BlsPublicKey>> {
+        self.0.subscribe()
+    }
+
+    /// List the public keys of the given signer and record them. On failure, the last known
+    /// good set is kept and returned.
+    pub async fn refresh(&self, signer: &SignerBLS) -> HashSet<BlsPublicKey> {
+        match signer.available_pubkeys().await {
+            Ok(pubkeys) => {
+                self.0.send_if_modified(|current| {
+                    let modified = *current != pubkeys;
+                    if modified {
+                        current.clone_from(&pubkeys);
+                    }
+                    modified
+                });
+                pubkeys
+            }
+            Err(err) => {
+                let pubkeys = self.get();
+                warn!(?err, last_known = pubkeys.len(), "Failed to list the signer public keys");
+                pubkeys
+            }
+        }
+    }
+
+    /// Returns the last known set of public keys, listing the keys of the signer again if it
+    /// is empty, e.g. if the signer was unavailable at startup.
+    pub async fn get_or_refresh(&self, signer: &SignerBLS) -> HashSet<BlsPublicKey> {
+        let pubkeys = self.get();
+        if pubkeys.is_empty() {
+            return self.refresh(signer).await;
+        }
+        pubkeys
+    }
+
+    /// Spawn a background task listing the keys of the given signer every `interval`, starting
+    /// after the first interval, so that keys added to a remote signer become usable.
+    pub fn spawn_refresher(&self, signer: SignerBLS, interval: Duration) -> JoinHandle<()> {
+        let pubkeys = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(Instant::now() + interval, interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                let count = pubkeys.refresh(&signer).await.len();
+                debug!(count, backend = signer.backend(), "Refreshed the signer public keys");
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        hex,
+        primitives::B256,
+        signers::{local::PrivateKeySigner, SignerSync},
+    };
+    use wiremock::{matchers, Mock, MockServer, Request, ResponseTemplate};
+
+    use crate::test_util::default_inclusion_request;
+
+    use super::*;
+
+    /// Test that a remote signer failing to list its keys falls back to the last known good
+    /// set, and that new keys are picked up once it recovers.
+    #[tokio::test]
+    async fn test_remote_pubkeys_fallback() -> eyre::Result<()> {
+        let server = MockServer::start().await;
+        let pubkey = LocalSigner::random().pubkey();
+        let get_pubkeys =
+            || Mock::given(matchers::method("GET")).and(matchers::path("/signer/v1/get_pubkeys"));
+
+        // The signer fails once, recovers once, then fails for good
+        get_pubkeys()
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        get_pubkeys()
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "consensus": [format!("{pubkey:?}")],
+                "proxy_bls": [],
+                "proxy_ecdsa": [],
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        get_pubkeys().respond_with(ResponseTemplate::new(500)).mount(&server).await;
+
+        let signer = SignerBLS::CommitBoost(CommitBoostSigner::new(server.uri().parse()?, "jwt")?);
+        assert!(signer.is_remote());
+        let pubkeys = AvailablePubkeys::default();
+
+        let mut updates = pubkeys.subscribe();
+
+        // Nothing is known yet, the failure falls back to an empty set
+        assert!(pubkeys.refresh(&signer).await.is_empty());
+        assert!(!updates.has_changed()?);
+
+        // The signer recovered: its keys are picked up on the next listing, and subscribers
+        // are notified
+        let expected = HashSet::from([pubkey]);
+        assert_eq!(pubkeys.get_or_refresh(&signer).await, expected);
+        assert!(updates.has_changed()?);
+        assert_eq!(*updates.borrow_and_update(), expected);
+
+        // The next failure keeps the last known good set
+        assert!(signer.available_pubkeys().await.is_err());
+        assert_eq!(pubkeys.refresh(&signer).await, expected);
+        assert_eq!(pubkeys.get(), expected);
+        assert!(!updates.has_changed()?);
+
+        Ok(())
+    }
+
+    /// Test that commitments signed through a Web3Signer instance recover to the address of
+    /// its key, which is the one registered in the BoltManager contract.
+    #[tokio::test]
+    async fn test_web3signer_commitment_signer() -> eyre::Result<()> {
+        let server = MockServer::start().await;
+        let key = PrivateKeySigner::random();
+        let public_key = key.credential().verifying_key().to_encoded_point(false);
+
+        // Web3Signer signs whatever hash it is given with the key
+        let web3signer_key = key.clone();
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path(format!("/api/v1/eth1/sign/{}", hex::encode_prefixed(public_key))))
+            .respond_with(move |req: &Request| {
+                let body: serde_json::Value = req.body_json().unwrap();
+                let hash: B256 = body["data"].as_str().unwrap().parse().unwrap();
+                let signature = web3signer_key.sign_hash_sync(&hash).unwrap();
+                ResponseTemplate::new(200)
+                    .set_body_string(hex::encode_prefixed(signature.as_bytes()))
+            })
+            .mount(&server)
+            .await;
+
+        let signer = CommitmentSigner::Web3Signer(Web3Signer::new(
+            server.uri().parse()?,
+            &hex::encode(public_key),
+        )?);
+        assert_eq!(signer.public_key(), key.address());
+        assert_eq!(signer.backend(), "web3signer");
+
+        let commitment = default_inclusion_request().commit_and_sign(&signer).await?;
+        assert_eq!(commitment.recover_signer()?, key.address());
+
+        let commitment = default_inclusion_request()
+            .commit_and_sign_with_constraints(&signer, Vec::new())
+            .await?;
+        assert_eq!(commitment.recover_signer()?, key.address());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use std::time::Duration;
+
+use alloy::{
+    hex,
+    primitives::{keccak256, Address},
+    signers::{
+        k256::ecdsa::VerifyingKey, utils::public_key_to_address, Signature as AlloySignature,
+    },
+};
+use reqwest::Url;
+use serde_json::json;
+use thiserror::Error;
+use tracing::debug;
+
+use crate::crypto::ecdsa::SignerECDSA;
+
+/// The path of the eth1 signing endpoint of Web3Signer, followed by the public key to sign with.
+const ETH1_SIGN_PATH: &str = "/api/v1/eth1/sign";
+
+/// The timeout of the signing requests to Web3Signer.
+const SIGN_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Error in the Web3Signer signer.
+#[derive(Debug, Error)]
+#[allow(missing_docs)]
+pub enum Web3SignerError {
+    #[error("invalid secp256k1 public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("failed to reach web3signer: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("web3signer refused to sign with status {status}: {body}")]
+    Refused { status: u16, body: String },
+    #[error("invalid signature returned by web3signer: {0}")]
+    InvalidSignature(String),
+    #[error("web3signer signature recovers to {recovered}, expected {expected}")]
+    WrongSigner { recovered: Address, expected: Address },
+}
+
+/// A client signing commitments with a secp256k1 key held by a Web3Signer instance, through
+/// its eth1 signing endpoint.
+///
+/// Web3Signer signs the keccak256 hash of the data it's given: the signer is sent the payloads
+/// of the commitments, and can't sign arbitrary hashes.
+#[derive(Debug, Clone)]
+pub struct Web3Signer {
+    /// The URL of the Web3Signer instance.
+    url: Url,
+    /// The hex-encoded public key identifying the key in Web3Signer.
+    public_key: String,
+    /// The address derived from the public key.
+    address: Address,
+    client: reqwest::Client,
+}
+
+impl Web3Signer {
+    /// Create a new [Web3Signer] signing with the key of the given hex-encoded SEC1 public key.
+    pub fn new(url: Url, public_key: &str) -> Result<Self, Web3SignerError> {
+        let bytes = hex::decode(public_key)
+            .map_err(|e| Web3SignerError::InvalidPublicKey(e.to_string()))?;
+        let key = VerifyingKey::from_sec1_bytes(&bytes)
+            .map_err(|e| Web3SignerError::InvalidPublicKey(e.to_string()))?;
+
+        // Web3Signer identifies the keys by their uncompressed public key
+        let public_key = hex::encode_prefixed(key.to_encoded_point(false).as_bytes());
+        let client = reqwest::Client::builder().timeout(SIGN_REQUEST_TIMEOUT).build()?;
+
+        Ok(Self { url, public_key, address: public_key_to_address(&key), client })
+    }
+
+    /// Request the signature of the keccak256 hash of the given payload from Web3Signer, and
+    /// check that it was produced by the expected key.
+    pub async fn request_signature(
+        &self,
+        payload: &[u8],
+    ) -> Result<AlloySignature, Web3SignerError> {
+        let url = format!(
+            "{}{ETH1_SIGN_PATH}/{}",
+            self.url.as_str().trim_end_matches('/'),
+            self.public_key
+        );
+
+        let hash = keccak256(payload);
+        debug!(%url, %hash, "Requesting signature from web3signer");
+
+        let data = hex::encode_prefixed(payload);
+        let response = self.client.post(url).json(&json!({ "data": data })).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(Web3SignerError::Refused { status: status.as_u16(), body });
+        }
+
+        let bytes = hex::decode(body.trim().trim_matches('"'))
+            .map_err(|e| Web3SignerError::InvalidSignature(e.to_string()))?;
+        let signature = AlloySignature::try_from(bytes.as_slice())
+            .map_err(|e| Web3SignerError::InvalidSignature(e.to_string()))?;
+
+        // A signature over anything else than the hash of the payload, e.g. with a message
+        // prefix, would produce commitments that can't be verified
+        let recovered = signature
+            .recover_address_from_prehash(&hash)
+            .map_err(|e| Web3SignerError::InvalidSignature(e.to_string()))?;
+        if recovered != self.address {
+            return Err(Web3SignerError::WrongSigner { recovered, expected: self.address });
+        }
+
+        Ok(signature)
+    }
+}
+
+#[async_trait::async_trait]
+impl SignerECDSA for Web3Signer {
+    fn public_key(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_hash(&self, _hash: &[u8; 32]) -> eyre::Result<AlloySignature> {
+        eyre::bail!("web3signer hashes the data it signs, only payloads can be signed")
+    }
+
+    async fn sign_payload(&self, payload: &[u8]) -> eyre::Result<AlloySignature> {
+        Ok(self.request_signature(payload).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::B256,
+        signers::{local::PrivateKeySigner, SignerSync},
+    };
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    /// Mount the eth1 signing endpoint of the key of `key` on the server, answering requests
+    /// to sign `payload` with the given response.
+    async fn mock_sign_endpoint(
+        server: &MockServer,
+        key: &PrivateKeySigner,
+        payload: &[u8],
+        response: String,
+    ) {
+        let public_key = key.credential().verifying_key().to_encoded_point(false);
+
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path(format!("{ETH1_SIGN_PATH}/{}", hex::encode_prefixed(public_key))))
+            .and(matchers::body_json(json!({ "data": hex::encode_prefixed(payload) })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(response))
+            .mount(server)
+            .await;
+    }
+
+    /// Returns the signature of the keccak256 hash of the payload by `signer`, as returned by
+    /// Web3Signer.
+    fn signature_response(signer: &PrivateKeySigner, payload: &[u8]) -> String {
+        let signature = signer.sign_hash_sync(&keccak256(payload)).unwrap();
+        hex::encode_prefixed(signature.as_bytes())
+    }
+
+    #[tokio::test]
+    async fn test_web3signer_sign_payload() -> eyre::Result<()> {
+        let server = MockServer::start().await;
+        let key = PrivateKeySigner::random();
+        let payload = B256::random();
+        let response = signature_response(&key, payload.as_slice());
+        mock_sign_endpoint(&server, &key, payload.as_slice(), response).await;
+
+        // Compressed public keys are accepted, and sent uncompressed to Web3Signer
+        let public_key = key.credential().verifying_key().to_encoded_point(true);
+        let signer = Web3Signer::new(server.uri().parse()?, &hex::encode(public_key))?;
+        assert_eq!(signer.public_key(), key.address());
+
+        let signature = signer.sign_payload(payload.as_slice()).await?;
+        let hash = keccak256(payload);
+        assert_eq!(signature.as_bytes(), key.sign_hash_sync(&hash)?.as_bytes());
+        assert_eq!(signature.recover_address_from_prehash(&hash)?, key.address());
+
+        // Raw hashes can't be signed by Web3Signer
+        assert!(signer.sign_hash(&hash).await.is_err());
+
+        Ok(())
+    }
+
+    /// Web3Signer signs the keccak256 hash of the `data` of eth1 signing requests, and answers
+    /// with the hex-encoded `r | s | v` signature, `v` being 27 or 28. The signatures are
+    /// deterministic (RFC 6979): this is the answer for the key `0x42..42` and the payload of
+    /// a request for the transaction `0x11..11` at slot 10.
+    #[tokio::test]
+    async fn test_web3signer_response_format() -> eyre::Result<()> {
+        const RESPONSE: &str = "0x66fe0d12cffd68433b073dbcd765247f9ecb4fe270bbdab7486429407ba3f76325a196654b59b3518cd20a663245ada2fb726051e71409afa43a080b1575f4401c";
+
+        let server = MockServer::start().await;
+        let key = PrivateKeySigner::from_slice(&[0x42; 32])?;
+        let payload = [[0x11; 32].as_slice(), &10u64.to_le_bytes()].concat();
+        mock_sign_endpoint(&server, &key, &payload, RESPONSE.to_string()).await;
+
+        let public_key = key.credential().verifying_key().to_encoded_point(false);
+        let signer = Web3Signer::new(server.uri().parse()?, &hex::encode(public_key))?;
+        let signature = signer.sign_payload(&payload).await?;
+        assert_eq!(hex::encode_prefixed(signature.as_bytes()), RESPONSE);
+        assert_eq!(signature.recover_address_from_prehash(&keccak256(&payload))?, key.address());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_web3signer_rejects_foreign_signatures() -> eyre::Result<()> {
+        let server = MockServer::start().await;
+        let key = PrivateKeySigner::random();
+        let payload = B256::random();
+        let response = signature_response(&PrivateKeySigner::random(), payload.as_slice());
+        mock_sign_endpoint(&server, &key, payload.as_slice(), response).await;
+
+        let public_key = key.credential().verifying_key().to_encoded_point(false);
+        let signer = Web3Signer::new(server.uri().parse()?, &hex::encode(&public_key))?;
+
+        let err = signer.request_signature(payload.as_slice()).await.unwrap_err();
+        assert!(
+            matches!(err, Web3SignerError::WrongSigner { expected, .. } if expected == key.address())
+        );
+
+        // A signature of the payload itself instead of its hash is rejected as well
+        let server = MockServer::start().await;
+        let signature = key.sign_hash_sync(&payload)?;
+        let response = hex::encode_prefixed(signature.as_bytes());
+        mock_sign_endpoint(&server, &key, payload.as_slice(), response).await;
+        let signer = Web3Signer::new(server.uri().parse()?, &hex::encode(&public_key))?;
+        let err = signer.request_signature(payload.as_slice()).await.unwrap_err();
+        assert!(matches!(err, Web3SignerError::WrongSigner { .. }));
+
+        // Unknown payloads aren't signed by the mock
+        let err = signer.request_signature(B256::random().as_slice()).await.unwrap_err();
+        assert!(matches!(err, Web3SignerError::Refused { status: 404, .. }));
+
+        assert!(Web3Signer::new(server.uri().parse()?, "0x1234").is_err());
+
+        Ok(())
+    }
+}
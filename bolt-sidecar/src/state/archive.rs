@@ -0,0 +1,661 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use alloy::primitives::{Address, B256};
+use ethereum_consensus::{crypto::PublicKey as BlsPublicKey, phase0::mainnet::SLOTS_PER_EPOCH};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot,
+    },
+    task::JoinHandle,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    primitives::{
+        commitment::{InclusionCommitment, RequestId},
+        SignedConstraints, Slot,
+    },
+    state::SlotTiming,
+    telemetry::ApiMetrics,
+};
+
+/// Default number of epochs the signed commitments are kept in full in the archive (about
+/// a week).
+pub const DEFAULT_RETENTION_EPOCHS: u64 = 1_575;
+
+/// Interval between two cleanups of the archive, which also run at startup.
+pub const ARCHIVE_CLEANUP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// The name of the archive directory within the data directory.
+const ARCHIVE_DIR: &str = "commitments";
+
+/// The name of the file of the daily summaries within the archive directory.
+const SUMMARIES_FILE: &str = "summaries.json";
+
+/// The number of milliseconds in a day.
+const DAY_MS: u64 = 86_400_000;
+
+/// The capacity of the channel to the archive writer. Commitments archived while it is full
+/// are dropped, so that the commitment path never waits on the disk.
+const ARCHIVE_CHANNEL_CAPACITY: usize = 4_096;
+
+/// A signed commitment recorded in the archive, along with everything needed to prove that
+/// it has been honored: the signed constraints submitted to the relays and their digests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedCommitment {
+    /// Unix timestamp in milliseconds of when the request was received.
+    pub received_at: u64,
+    /// The target slot of the commitment.
+    pub slot: Slot,
+    /// The address of the commitment signer.
+    pub signer: Address,
+    /// The BLS public key that signed the constraints.
+    pub pubkey: BlsPublicKey,
+    /// The digests of the signed constraints, in the order of the transactions of the request.
+    pub constraint_digests: Vec<B256>,
+    /// The signed commitment, as returned to the user.
+    pub commitment: InclusionCommitment,
+    /// The signed constraints of the commitment, as submitted to the relays.
+    pub signed_constraints: Vec<SignedConstraints>,
+    /// The ID assigned to the request by the commitments API. Missing in the records
+    /// archived before request IDs were introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<RequestId>,
+}
+
+impl ArchivedCommitment {
+    /// Create a new archive record for a commitment whose request was received at the
+    /// given time.
+    pub fn new(
+        slot: Slot,
+        received_at: SystemTime,
+        signer: Address,
+        pubkey: BlsPublicKey,
+        constraint_digests: Vec<B256>,
+        commitment: InclusionCommitment,
+        signed_constraints: Vec<SignedConstraints>,
+    ) -> Self {
+        let received_at = received_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+
+        Self {
+            received_at,
+            slot,
+            signer,
+            pubkey,
+            constraint_digests,
+            commitment,
+            signed_constraints,
+            request_id: None,
+        }
+    }
+
+    /// Sets the ID assigned to the request by the commitments API.
+    pub fn with_request_id(mut self, request_id: RequestId) -> Self {
+        self.request_id = Some(request_id);
+        self
+    }
+}
+
+/// The aggregated commitments of a day, kept once their full records are pruned from the
+/// archive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailySummary {
+    /// The day the requests were received, in days since the UNIX epoch.
+    pub day: u64,
+    /// The number of signed commitments.
+    pub commitments: u64,
+    /// The number of committed transactions.
+    pub transactions: u64,
+    /// The total gas limit of the committed transactions.
+    pub committed_gas: u64,
+    /// The earliest target slot of the commitments.
+    pub first_slot: Slot,
+    /// The latest target slot of the commitments.
+    pub last_slot: Slot,
+}
+
+impl DailySummary {
+    /// Add an archived commitment to the summary.
+    fn add(&mut self, commitment: &ArchivedCommitment) {
+        if self.commitments == 0 {
+            self.first_slot = commitment.slot;
+            self.last_slot = commitment.slot;
+        }
+
+        self.commitments += 1;
+        self.transactions += commitment.commitment.request().txs.len() as u64;
+        self.committed_gas += commitment.commitment.request().gas_limit();
+        self.first_slot = self.first_slot.min(commitment.slot);
+        self.last_slot = self.last_slot.max(commitment.slot);
+    }
+}
+
+/// The daily summaries of the pruned epochs, persisted in a single file of the archive.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Summaries {
+    /// The latest epoch aggregated into the summaries. Its file may still be around if the
+    /// sidecar stopped before removing it, in which case it is removed without being
+    /// aggregated again.
+    last_epoch: Option<u64>,
+    /// The summaries, by day.
+    days: BTreeMap<u64, DailySummary>,
+}
+
+/// A message to the archive writer task.
+#[derive(Debug)]
+enum ArchiveMessage {
+    Append(Box<ArchivedCommitment>),
+    Query(Slot, oneshot::Sender<io::Result<Vec<ArchivedCommitment>>>),
+    Flush(oneshot::Sender<io::Result<()>>),
+    Cleanup(Slot, oneshot::Sender<io::Result<()>>),
+    Summaries(oneshot::Sender<Vec<DailySummary>>),
+}
+
+/// An on-disk archive of the signed commitments, kept for a number of epochs so that the
+/// operator can settle disputes about allegedly broken commitments. Older commitments are
+/// aggregated into daily summaries before being deleted.
+///
+/// Commitments are appended as NDJSON to one file per epoch, by a writer task running on
+/// a blocking thread: archiving a commitment only enqueues it. The handle is cheap to clone:
+/// every clone shares the same writer.
+#[derive(Debug, Clone)]
+pub struct CommitmentArchive {
+    tx: mpsc::Sender<ArchiveMessage>,
+}
+
+impl CommitmentArchive {
+    /// Open (or create) the archive in the given data directory and spawn its writer task.
+    ///
+    /// The epochs older than `retention_epochs` before the latest archived one are pruned,
+    /// see [CommitmentArchive::cleanup] to prune them based on the current slot.
+    pub fn open<P: AsRef<Path>>(data_dir: P, retention_epochs: u64) -> io::Result<Self> {
+        let writer = ArchiveWriter::open(data_dir.as_ref().join(ARCHIVE_DIR), retention_epochs)?;
+
+        let (tx, rx) = mpsc::channel(ARCHIVE_CHANNEL_CAPACITY);
+        tokio::task::spawn_blocking(move || writer.run(rx));
+
+        Ok(Self { tx })
+    }
+
+    /// Enqueue a commitment to be written to the archive, without waiting.
+    pub fn archive(&self, commitment: ArchivedCommitment) {
+        let slot = commitment.slot;
+        match self.tx.try_send(ArchiveMessage::Append(Box::new(commitment))) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                error!(slot, "Commitment archive writer is lagging behind, dropping commitment")
+            }
+            Err(TrySendError::Closed(_)) => {
+                error!(slot, "Commitment archive writer stopped, dropping commitment")
+            }
+        }
+    }
+
+    /// Returns the commitments archived for the given slot, in the order they were signed.
+    pub async fn query(&self, slot: Slot) -> io::Result<Vec<ArchivedCommitment>> {
+        let (tx, rx) = oneshot::channel();
+        self.tx.send(ArchiveMessage::Query(slot, tx)).await.map_err(|_| writer_stopped())?;
+        rx.await.map_err(|_| writer_stopped())?
+    }
+
+    /// Wait until all the commitments enqueued so far are written to disk.
+    pub async fn flush(&self) -> io::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.tx.send(ArchiveMessage::Flush(tx)).await.map_err(|_| writer_stopped())?;
+        rx.await.map_err(|_| writer_stopped())?
+    }
+
+    /// Aggregate the epochs older than the retention window before the epoch of the given
+    /// slot into the daily summaries, and delete their records.
+    pub async fn cleanup(&self, current_slot: Slot) -> io::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .send(ArchiveMessage::Cleanup(current_slot, tx))
+            .await
+            .map_err(|_| writer_stopped())?;
+        rx.await.map_err(|_| writer_stopped())?
+    }
+
+    /// Returns the daily summaries of the pruned commitments, the oldest day first.
+    pub async fn summaries(&self) -> io::Result<Vec<DailySummary>> {
+        let (tx, rx) = oneshot::channel();
+        self.tx.send(ArchiveMessage::Summaries(tx)).await.map_err(|_| writer_stopped())?;
+        rx.await.map_err(|_| writer_stopped())
+    }
+
+    /// Spawn a background task cleaning up the archive right away, then every `interval`,
+    /// based on the current slot of the given timing.
+    pub fn spawn_cleanup(&self, timing: SlotTiming, interval: Duration) -> JoinHandle<()> {
+        let archive = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                let slot = timing.slot_at(Instant::now());
+                if let Err(err) = archive.cleanup(slot).await {
+                    error!(?err, slot, "Failed to clean up the commitment archive");
+                }
+            }
+        })
+    }
+}
+
+fn writer_stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "commitment archive writer stopped")
+}
+
+/// The writer task of the [CommitmentArchive], owning the archive files.
+#[derive(Debug)]
+struct ArchiveWriter {
+    /// The archive directory, with one `<epoch>.jsonl` file per epoch.
+    dir: PathBuf,
+    /// The number of epochs to keep.
+    retention_epochs: u64,
+    /// The latest archived epoch.
+    latest_epoch: Option<u64>,
+    /// The buffered writers over the files of the epochs written in the current batch.
+    writers: BTreeMap<u64, BufWriter<File>>,
+    /// The daily summaries of the pruned epochs.
+    summaries: Summaries,
+}
+
+impl ArchiveWriter {
+    /// Open the archive directory, pruning the expired epochs.
+    fn open(dir: PathBuf, retention_epochs: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let summaries = match fs::read(dir.join(SUMMARIES_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Summaries::default(),
+            Err(err) => return Err(err),
+        };
+
+        let mut writer = Self {
+            dir,
+            retention_epochs: retention_epochs.max(1),
+            latest_epoch: None,
+            writers: BTreeMap::new(),
+            summaries,
+        };
+        writer.latest_epoch = writer.epochs()?.into_iter().max();
+        writer.prune()?;
+        writer.record_usage();
+
+        let latest_epoch = writer.latest_epoch;
+        info!(dir = %writer.dir.display(), ?latest_epoch, "Opened the commitment archive");
+        Ok(writer)
+    }
+
+    /// Process the messages until every handle is dropped. The commitments received together
+    /// are written in a single batch, flushed at the end of it.
+    fn run(mut self, mut rx: mpsc::Receiver<ArchiveMessage>) {
+        while let Some(message) = rx.blocking_recv() {
+            self.handle(message);
+            while let Ok(message) = rx.try_recv() {
+                self.handle(message);
+            }
+
+            if let Err(err) = self.flush() {
+                error!(?err, "Failed to flush the commitment archive");
+            }
+            // Only keep the latest epoch open: the previous ones are seldom written again
+            if let Some(latest) = self.latest_epoch {
+                self.writers.retain(|epoch, _| *epoch == latest);
+            }
+        }
+
+        debug!("Commitment archive writer stopped");
+    }
+
+    fn handle(&mut self, message: ArchiveMessage) {
+        match message {
+            ArchiveMessage::Append(commitment) => {
+                if let Err(err) = self.append(&commitment) {
+                    error!(?err, slot = commitment.slot, "Failed to archive commitment");
+                }
+            }
+            ArchiveMessage::Query(slot, tx) => {
+                let _ = tx.send(self.flush().and_then(|_| self.query(slot)));
+            }
+            ArchiveMessage::Flush(tx) => {
+                let _ = tx.send(self.flush());
+            }
+            ArchiveMessage::Cleanup(slot, tx) => {
+                let _ = tx.send(self.cleanup(slot));
+                self.record_usage();
+            }
+            ArchiveMessage::Summaries(tx) => {
+                let _ = tx.send(self.summaries.days.values().copied().collect());
+            }
+        }
+    }
+
+    /// Append a commitment to the file of its epoch, pruning the expired epochs when a new
+    /// one starts.
+    fn append(&mut self, commitment: &ArchivedCommitment) -> io::Result<()> {
+        let epoch = commitment.slot / SLOTS_PER_EPOCH;
+        if self.latest_epoch.map_or(true, |latest| epoch > latest) {
+            self.latest_epoch = Some(epoch);
+            self.prune()?;
+        }
+
+        if !self.writers.contains_key(&epoch) {
+            let file = OpenOptions::new().create(true).append(true).open(self.epoch_path(epoch))?;
+            self.writers.insert(epoch, BufWriter::new(file));
+        }
+        let writer = self.writers.get_mut(&epoch).expect("writer just inserted");
+
+        let mut line = serde_json::to_vec(commitment)?;
+        line.push(b'\n');
+        writer.write_all(&line)
+    }
+
+    /// Flush the buffered commitments to disk.
+    fn flush(&mut self) -> io::Result<()> {
+        for writer in self.writers.values_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Read the commitments archived for the given slot from the file of its epoch.
+    fn query(&self, slot: Slot) -> io::Result<Vec<ArchivedCommitment>> {
+        let mut commitments = self.read_epoch(slot / SLOTS_PER_EPOCH)?;
+        commitments.retain(|commitment| commitment.slot == slot);
+        Ok(commitments)
+    }
+
+    /// Read all the commitments archived in the file of the given epoch.
+    fn read_epoch(&self, epoch: u64) -> io::Result<Vec<ArchivedCommitment>> {
+        let file = match File::open(self.epoch_path(epoch)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut commitments = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            // A crash can leave a truncated last line behind: skip it instead of failing
+            match serde_json::from_str::<ArchivedCommitment>(&line) {
+                Ok(commitment) => commitments.push(commitment),
+                Err(err) => {
+                    warn!(?err, epoch, "Skipping malformed line of the commitment archive")
+                }
+            }
+        }
+
+        Ok(commitments)
+    }
+
+    /// Prune the epochs older than the retention window before the latest archived epoch.
+    fn prune(&mut self) -> io::Result<()> {
+        let Some(latest) = self.latest_epoch else { return Ok(()) };
+        self.prune_before((latest + 1).saturating_sub(self.retention_epochs))
+    }
+
+    /// Prune the epochs older than the retention window before the epoch of the given slot,
+    /// which may be ahead of the latest archived one if no commitment was signed lately.
+    fn cleanup(&mut self, current_slot: Slot) -> io::Result<()> {
+        let current_epoch = current_slot / SLOTS_PER_EPOCH;
+        self.prune_before((current_epoch + 1).saturating_sub(self.retention_epochs))
+    }
+
+    /// Aggregate the epochs before `oldest_kept` into the daily summaries, then remove their
+    /// files. The summaries are saved before any file is removed, so that a crash in between
+    /// doesn't lose any commitment nor count it twice.
+    fn prune_before(&mut self, oldest_kept: u64) -> io::Result<()> {
+        let mut expired = self.epochs()?;
+        expired.retain(|epoch| *epoch < oldest_kept);
+        if expired.is_empty() {
+            return Ok(());
+        }
+        expired.sort_unstable();
+
+        self.flush()?;
+        self.writers.retain(|epoch, _| *epoch >= oldest_kept);
+
+        let mut aggregated = false;
+        for &epoch in &expired {
+            if self.summaries.last_epoch.is_some_and(|last| epoch <= last) {
+                continue;
+            }
+
+            for commitment in self.read_epoch(epoch)? {
+                let day = commitment.received_at / DAY_MS;
+                let summary = self
+                    .summaries
+                    .days
+                    .entry(day)
+                    .or_insert_with(|| DailySummary { day, ..Default::default() });
+                summary.add(&commitment);
+            }
+            self.summaries.last_epoch = Some(epoch);
+            aggregated = true;
+        }
+        if aggregated {
+            self.save_summaries()?;
+        }
+
+        for epoch in expired {
+            debug!(epoch, "Pruning expired epoch from the commitment archive");
+            fs::remove_file(self.epoch_path(epoch))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the daily summaries to disk, replacing the previous file atomically.
+    fn save_summaries(&self) -> io::Result<()> {
+        let path = self.dir.join(SUMMARIES_FILE);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.summaries)?)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Record the size of the archive and the age of its oldest full record in the metrics.
+    fn record_usage(&self) {
+        let usage = self.size_bytes().and_then(|size| Ok((size, self.oldest_record()?)));
+        match usage {
+            Ok((size, oldest)) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                let age = oldest
+                    .map(|received_at| now.saturating_sub(Duration::from_millis(received_at)));
+                ApiMetrics::set_archive_usage(size, age);
+            }
+            Err(err) => warn!(?err, "Failed to measure the commitment archive"),
+        }
+    }
+
+    /// Returns the total size of the files of the archive directory, in bytes.
+    fn size_bytes(&self) -> io::Result<u64> {
+        let mut size = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            size += entry?.metadata()?.len();
+        }
+        Ok(size)
+    }
+
+    /// Returns the reception time, in milliseconds since the UNIX epoch, of the oldest
+    /// commitment kept in full.
+    fn oldest_record(&self) -> io::Result<Option<u64>> {
+        let Some(oldest) = self.epochs()?.into_iter().min() else { return Ok(None) };
+        Ok(self.read_epoch(oldest)?.iter().map(|c| c.received_at).min())
+    }
+
+    /// Returns the epochs with a file in the archive directory.
+    fn epochs(&self) -> io::Result<Vec<u64>> {
+        let mut epochs = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
+                if let Some(epoch) = path.file_stem().and_then(|s| s.to_str()?.parse().ok()) {
+                    epochs.push(epoch);
+                }
+            }
+        }
+        Ok(epochs)
+    }
+
+    /// Returns the path of the file of the given epoch.
+    fn epoch_path(&self, epoch: u64) -> PathBuf {
+        self.dir.join(format!("{epoch}.jsonl"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::local::PrivateKeySigner;
+
+    use crate::test_util::default_inclusion_request;
+
+    use super::*;
+
+    async fn archived_commitment(signer: &PrivateKeySigner, slot: Slot) -> ArchivedCommitment {
+        let mut request = default_inclusion_request();
+        request.slot = slot;
+        let commitment = request.commit_and_sign(signer).await.unwrap();
+
+        ArchivedCommitment::new(
+            slot,
+            SystemTime::now(),
+            signer.address(),
+            BlsPublicKey::default(),
+            vec![B256::with_last_byte(slot as u8)],
+            commitment,
+            vec![SignedConstraints::default()],
+        )
+        .with_request_id(RequestId::random())
+    }
+
+    #[tokio::test]
+    async fn test_archive_restart_and_query() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let signer = PrivateKeySigner::random();
+
+        // A few hundred commitments over 10 slots, across an epoch boundary
+        let first_slot = 3 * SLOTS_PER_EPOCH - 5;
+        let archive = CommitmentArchive::open(dir.path(), 4)?;
+        let mut expected = BTreeMap::<Slot, Vec<ArchivedCommitment>>::new();
+        for i in 0..300 {
+            let slot = first_slot + i % 10;
+            let commitment = archived_commitment(&signer, slot).await;
+            archive.archive(commitment.clone());
+            expected.entry(slot).or_default().push(commitment);
+        }
+
+        // Queries see the buffered commitments
+        assert_eq!(archive.query(first_slot).await?, expected[&first_slot]);
+        archive.flush().await?;
+        drop(archive);
+
+        // Restart the archive and query every slot
+        let archive = CommitmentArchive::open(dir.path(), 4)?;
+        for (slot, commitments) in &expected {
+            let archived = archive.query(*slot).await?;
+            assert_eq!(archived.len(), 30);
+            assert_eq!(&archived, commitments);
+        }
+        assert!(archive.query(first_slot + 10).await?.is_empty());
+
+        // Commitments keep being appended to the existing files after a restart
+        archive.archive(archived_commitment(&signer, first_slot).await);
+        assert_eq!(archive.query(first_slot).await?.len(), 31);
+
+        // The epochs past the retention window are pruned as soon as a new epoch starts
+        archive.archive(archived_commitment(&signer, 6 * SLOTS_PER_EPOCH).await);
+        archive.flush().await?;
+        assert!(archive.query(first_slot).await?.is_empty());
+        assert_eq!(archive.query(first_slot + 9).await?.len(), 30);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_archive_cleanup_at_cutoff() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let signer = PrivateKeySigner::random();
+        let archive = CommitmentArchive::open(dir.path(), 4)?;
+
+        // Commitments on both sides of the cutoff: keeping 4 epochs at epoch 10, epoch 6 is
+        // the latest pruned one and epoch 7 the oldest kept one
+        let last_pruned_slot = 7 * SLOTS_PER_EPOCH - 1;
+        let first_kept_slot = 7 * SLOTS_PER_EPOCH;
+        let records = [
+            (5 * SLOTS_PER_EPOCH, 1),
+            (last_pruned_slot, 1),
+            (last_pruned_slot, 2),
+            (first_kept_slot, 2),
+        ];
+        for (slot, day) in records {
+            let mut commitment = archived_commitment(&signer, slot).await;
+            commitment.received_at = day * DAY_MS + 1;
+            archive.archive(commitment);
+        }
+        archive.flush().await?;
+
+        // At the end of epoch 9, only epoch 5 is past the horizon
+        archive.cleanup(10 * SLOTS_PER_EPOCH - 1).await?;
+        assert!(archive.query(5 * SLOTS_PER_EPOCH).await?.is_empty());
+        assert_eq!(archive.query(last_pruned_slot).await?.len(), 2);
+
+        // At the start of epoch 10, epoch 6 goes too but epoch 7 stays
+        archive.cleanup(10 * SLOTS_PER_EPOCH).await?;
+        assert!(archive.query(last_pruned_slot).await?.is_empty());
+        assert_eq!(archive.query(first_kept_slot).await?.len(), 1);
+
+        // The pruned commitments are aggregated by day, the kept one isn't
+        let request = default_inclusion_request();
+        let (txs, gas) = (request.txs.len() as u64, request.gas_limit());
+        let expected = vec![
+            DailySummary {
+                day: 1,
+                commitments: 2,
+                transactions: 2 * txs,
+                committed_gas: 2 * gas,
+                first_slot: 5 * SLOTS_PER_EPOCH,
+                last_slot: last_pruned_slot,
+            },
+            DailySummary {
+                day: 2,
+                commitments: 1,
+                transactions: txs,
+                committed_gas: gas,
+                first_slot: last_pruned_slot,
+                last_slot: last_pruned_slot,
+            },
+        ];
+        assert_eq!(archive.summaries().await?, expected);
+
+        // Cleaning up again doesn't count anything twice
+        archive.cleanup(10 * SLOTS_PER_EPOCH).await?;
+        assert_eq!(archive.summaries().await?, expected);
+        drop(archive);
+
+        // An already aggregated epoch left behind by a crash is removed without being counted
+        // again, and the summaries survive the restart
+        let archive_dir = dir.path().join(ARCHIVE_DIR);
+        fs::copy(archive_dir.join("7.jsonl"), archive_dir.join("6.jsonl"))?;
+        let archive = CommitmentArchive::open(dir.path(), 4)?;
+        archive.cleanup(10 * SLOTS_PER_EPOCH).await?;
+        assert!(!archive_dir.join("6.jsonl").exists());
+        assert_eq!(archive.summaries().await?, expected);
+        assert_eq!(archive.query(first_kept_slot).await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, TxHash},
+    rpc::types::{Transaction, TransactionReceipt, TransactionRequest},
+    transports::TransportError,
+};
+use parking_lot::Mutex;
+use tracing::trace;
+
+use crate::{client::SimulatedCall, primitives::AccountState, telemetry::ApiMetrics};
+
+use super::{execution::StateUpdate, fetcher::StateFetcher};
+
+/// Default time-to-live of the cached `has_code` flags. Accounts rarely gain or lose code,
+/// so this can safely span many blocks.
+pub const DEFAULT_CODE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The cached data, guarded by a single lock.
+#[derive(Debug, Default)]
+struct CacheState {
+    /// The latest block number seen through a state update.
+    head: Option<u64>,
+    /// Account states keyed by address and block number.
+    accounts: HashMap<(Address, u64), AccountState>,
+    /// Whether an account has code, with the time at which it has been fetched.
+    code: HashMap<Address, (bool, Instant)>,
+}
+
+impl CacheState {
+    /// Advance the head to the given block number, dropping every account state
+    /// cached for previous blocks.
+    fn advance_head(&mut self, block_number: u64) {
+        if self.head.map_or(true, |head| block_number > head) {
+            self.head = Some(block_number);
+            self.accounts.retain(|(_, block), _| *block >= block_number);
+        }
+    }
+
+    /// Returns the cached `has_code` flag of an account, if it's still fresh.
+    fn fresh_code(&self, address: &Address, ttl: Duration) -> Option<bool> {
+        self.code.get(address).filter(|(_, at)| at.elapsed() < ttl).map(|(has_code, _)| *has_code)
+    }
+}
+
+/// A caching layer over any [StateFetcher], to cut the load on the execution client.
+///
+/// - Account states are cached by `(address, block number)` and dropped as soon as a state update
+///   advances the head block.
+/// - The `has_code` flag of accounts is cached separately with a longer TTL, so that `eth_getCode`
+///   calls can be skipped entirely on cache misses.
+/// - Multiple missing accounts in a state update are fetched with a single batched lookup.
+///
+/// Every other method is forwarded to the inner fetcher as-is.
+///
+/// The cache is kept in memory only: account states are valid for a single block, and the
+/// `has_code` flags are cheap to fetch again in batches after a restart, so persisting them
+/// to disk wouldn't cut any meaningful load on the execution client.
+#[derive(Debug)]
+pub struct CachedStateFetcher<F> {
+    inner: F,
+    code_ttl: Duration,
+    cache: Mutex<CacheState>,
+}
+
+impl<F> CachedStateFetcher<F> {
+    /// Wrap the given fetcher with a cache.
+    pub fn new(inner: F) -> Self {
+        Self { inner, code_ttl: DEFAULT_CODE_TTL, cache: Mutex::default() }
+    }
+
+    /// Set the time-to-live of the cached `has_code` flags.
+    pub fn with_code_ttl(mut self, code_ttl: Duration) -> Self {
+        self.code_ttl = code_ttl;
+        self
+    }
+
+    /// Returns a reference to the inner fetcher.
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+}
+
+impl<F: StateFetcher + Send + Sync> CachedStateFetcher<F> {
+    /// Fetch the given accounts from the inner fetcher in a single lookup, reusing
+    /// the cached `has_code` flags when all of them are fresh, and cache the results.
+    async fn fetch_accounts(
+        &self,
+        addresses: &[Address],
+        block_number: u64,
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        let cached_code = {
+            let cache = self.cache.lock();
+            addresses
+                .iter()
+                .map(|addr| cache.fresh_code(addr, self.code_ttl).map(|code| (*addr, code)))
+                .collect::<Option<HashMap<_, _>>>()
+        };
+
+        let with_code = cached_code.is_none();
+        ApiMetrics::increment_state_cache(if with_code { "miss" } else { "hit" }, "code", 1);
+
+        let mut states =
+            self.inner.get_account_states(addresses, Some(block_number), with_code).await?;
+
+        let mut cache = self.cache.lock();
+        let now = Instant::now();
+        for (address, state) in states.iter_mut() {
+            match cached_code.as_ref().and_then(|code| code.get(address)) {
+                Some(has_code) => state.has_code = *has_code,
+                None => {
+                    cache.code.insert(*address, (state.has_code, now));
+                }
+            }
+
+            cache.accounts.insert((*address, block_number), *state);
+        }
+
+        Ok(states)
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: StateFetcher + Send + Sync> StateFetcher for CachedStateFetcher<F> {
+    async fn get_state_update(
+        &self,
+        addresses: Vec<&Address>,
+        block_number: Option<u64>,
+    ) -> Result<StateUpdate, TransportError> {
+        let block_number = match block_number {
+            Some(block_number) => block_number,
+            None => self.inner.get_head().await?,
+        };
+
+        let mut account_states = HashMap::with_capacity(addresses.len());
+        let mut missing = Vec::new();
+        {
+            let mut cache = self.cache.lock();
+            cache.advance_head(block_number);
+
+            for address in addresses {
+                match cache.accounts.get(&(*address, block_number)) {
+                    Some(state) => {
+                        account_states.insert(*address, *state);
+                    }
+                    None => missing.push(*address),
+                }
+            }
+        }
+
+        trace!(hits = account_states.len(), misses = missing.len(), "State update cache lookup");
+        ApiMetrics::increment_state_cache("hit", "account", account_states.len() as u64);
+        ApiMetrics::increment_state_cache("miss", "account", missing.len() as u64);
+
+        if !missing.is_empty() {
+            account_states.extend(self.fetch_accounts(&missing, block_number).await?);
+        }
+
+        let (min_basefee, min_blob_basefee) =
+            tokio::try_join!(self.inner.get_basefee(None), self.inner.get_blob_basefee(None))?;
+
+        Ok(StateUpdate { account_states, min_basefee, min_blob_basefee, block_number })
+    }
+
+    async fn get_head(&self) -> Result<u64, TransportError> {
+        self.inner.get_head().await
+    }
+
+    async fn get_block_number(&self, tag: BlockNumberOrTag) -> Result<u64, TransportError> {
+        self.inner.get_block_number(tag).await
+    }
+
+    async fn get_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
+        self.inner.get_basefee(block_number).await
+    }
+
+    async fn get_blob_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
+        self.inner.get_blob_basefee(block_number).await
+    }
+
+    async fn get_account_state(
+        &self,
+        address: &Address,
+        block_number: Option<u64>,
+    ) -> Result<AccountState, TransportError> {
+        // Without an explicit block number, the state is fetched at the current head so that
+        // it can be keyed by the block it has actually been read at
+        let block_number = match block_number {
+            Some(block_number) => block_number,
+            None => self.inner.get_head().await?,
+        };
+
+        let cached = self.cache.lock().accounts.get(&(*address, block_number)).copied();
+
+        if let Some(state) = cached {
+            ApiMetrics::increment_state_cache("hit", "account", 1);
+            return Ok(state);
+        }
+
+        ApiMetrics::increment_state_cache("miss", "account", 1);
+        let mut states = self.fetch_accounts(&[*address], block_number).await?;
+
+        states.remove(address).ok_or_else(|| {
+            TransportError::local_usage_str("missing account state in fetcher response")
+        })
+    }
+
+    async fn get_account_states(
+        &self,
+        addresses: &[Address],
+        block_number: Option<u64>,
+        with_code: bool,
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        self.inner.get_account_states(addresses, block_number, with_code).await
+    }
+
+    async fn get_pending_account_states(
+        &self,
+        addresses: &[Address],
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        // The pending state changes with every transaction in the mempool: never cache it.
+        self.inner.get_pending_account_states(addresses).await
+    }
+
+    async fn get_chain_id(&self) -> Result<u64, TransportError> {
+        self.inner.get_chain_id().await
+    }
+
+    async fn simulate_calls(
+        &self,
+        calls: &[TransactionRequest],
+    ) -> Result<Vec<SimulatedCall>, TransportError> {
+        self.inner.simulate_calls(calls).await
+    }
+
+    async fn get_receipts_unordered(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<Vec<Option<TransactionReceipt>>, TransportError> {
+        self.inner.get_receipts_unordered(hashes).await
+    }
+
+    async fn get_pool_transactions(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<HashMap<TxHash, Transaction>, TransportError> {
+        self.inner.get_pool_transactions(hashes).await
+    }
+
+    async fn get_block_transaction_hashes(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<TxHash>, TransportError> {
+        self.inner.get_block_transaction_hashes(block_number).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U64;
+
+    use crate::{
+        state::StateClient,
+        test_util::{launch_anvil, TestFetcher},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_same_sender_same_block_is_fetched_once() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let fetcher = CachedStateFetcher::new(TestFetcher::new(client.clone()));
+        let sender = anvil.addresses()[0];
+
+        let head = client.get_head().await?;
+        fetcher.get_state_update(vec![], Some(head)).await?;
+
+        // Two requests from the same sender in the same block
+        let first = fetcher.get_account_state(&sender, None).await?;
+        let second = fetcher.get_account_state(&sender, None).await?;
+        assert_eq!(first.transaction_count, second.transaction_count);
+        assert_eq!(fetcher.inner().lookups(), 1);
+
+        // A state update in the same block is served from the cache too
+        let update = fetcher.get_state_update(vec![&sender], Some(head)).await?;
+        assert_eq!(update.account_states[&sender].balance, first.balance);
+        assert_eq!(fetcher.inner().lookups(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_latest_state_is_keyed_by_its_block() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let fetcher = CachedStateFetcher::new(TestFetcher::new(client.clone()));
+        let sender = anvil.addresses()[0];
+
+        let head = client.get_head().await?;
+        fetcher.get_state_update(vec![], Some(head)).await?;
+        fetcher.get_account_state(&sender, None).await?;
+        assert_eq!(fetcher.inner().lookups(), 1);
+
+        // The chain advances before the next state update: the latest state is fetched again
+        // instead of being served from the entry of the previous block
+        let _: serde_json::Value = client.inner().request("anvil_mine", (U64::from(1),)).await?;
+        fetcher.get_account_state(&sender, None).await?;
+        assert_eq!(fetcher.inner().lookups(), 2);
+
+        // And it has been cached under the block it has been read at
+        fetcher.get_account_state(&sender, Some(head + 1)).await?;
+        assert_eq!(fetcher.inner().lookups(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_head_invalidates_accounts_but_not_code() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let fetcher = CachedStateFetcher::new(TestFetcher::new(client.clone()));
+        let alice = anvil.addresses()[0];
+        let bob = anvil.addresses()[1];
+
+        let head = client.get_head().await?;
+        fetcher.get_state_update(vec![], Some(head)).await?;
+        fetcher.get_account_state(&alice, None).await?;
+        fetcher.get_account_state(&bob, None).await?;
+        assert_eq!(fetcher.inner().lookups(), 2);
+        assert_eq!(fetcher.inner().code_lookups(), 2);
+
+        // The head advances: both accounts are refreshed in a single batched lookup,
+        // without fetching their code again.
+        let _: serde_json::Value = client.inner().request("anvil_mine", (U64::from(2),)).await?;
+        let update = fetcher.get_state_update(vec![&alice, &bob], Some(head + 1)).await?;
+        assert_eq!(update.block_number, head + 1);
+        assert!(update.account_states.contains_key(&alice));
+        assert!(update.account_states.contains_key(&bob));
+        assert_eq!(fetcher.inner().lookups(), 3);
+        assert_eq!(fetcher.inner().code_lookups(), 2);
+
+        // Once the code TTL expires, the code is fetched again
+        let fetcher = fetcher.with_code_ttl(Duration::ZERO);
+        fetcher.get_state_update(vec![&alice], Some(head + 2)).await?;
+        assert_eq!(fetcher.inner().code_lookups(), 3);
+
+        Ok(())
+    }
+}
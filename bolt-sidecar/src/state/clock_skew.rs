@@ -0,0 +1,208 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use tracing::{debug, info, warn};
+
+use super::SlotTiming;
+use crate::{primitives::Slot, telemetry::ApiMetrics};
+
+/// The number of latest heads over which the clock offset is estimated: one epoch.
+const OFFSET_WINDOW: usize = 32;
+
+/// The maximum shift of the commitment deadlines, whatever the measured clock offset.
+pub const MAX_SKEW_COMPENSATION: Duration = Duration::from_secs(2);
+
+/// Monitors the offset between the local clock and the chain, by comparing the arrival of
+/// the head events with the start of their slot computed from the genesis time with the
+/// local clock.
+///
+/// The arrival delay of a head is the clock offset plus its propagation delay, which varies
+/// from slot to slot but never goes below the time to propose and import a block. The offset
+/// is therefore estimated as the minimum delay over the latest heads, minus the expected
+/// propagation baseline of the fastest blocks. Late blocks don't move it.
+///
+/// A positive offset means that the heads arrive after the start of their slot, e.g. because
+/// the local clock is ahead of the chain.
+#[derive(Debug, Clone)]
+pub struct ClockSkewMonitor {
+    /// The start instants of the slots according to the local clock.
+    timing: SlotTiming,
+    /// The minimum arrival delay of the heads with a synchronized clock.
+    propagation_baseline: Duration,
+    /// The offset above which a warning is logged.
+    warn_threshold: Duration,
+    /// The offset above which the commitment deadlines are compensated. Zero disables the
+    /// compensation.
+    compensation_threshold: Duration,
+    /// The arrival delays of the latest heads in microseconds, at most [OFFSET_WINDOW].
+    delays_us: VecDeque<i64>,
+}
+
+impl ClockSkewMonitor {
+    /// Create a new monitor of the offset of the local clock, with the given thresholds and
+    /// no propagation baseline.
+    pub fn new(
+        timing: SlotTiming,
+        warn_threshold: Duration,
+        compensation_threshold: Duration,
+    ) -> Self {
+        Self {
+            timing,
+            propagation_baseline: Duration::ZERO,
+            warn_threshold,
+            compensation_threshold,
+            delays_us: VecDeque::with_capacity(OFFSET_WINDOW),
+        }
+    }
+
+    /// Set the minimum arrival delay of the heads with a synchronized clock, which is
+    /// subtracted from the measured delays.
+    pub fn with_propagation_baseline(mut self, baseline: Duration) -> Self {
+        self.propagation_baseline = baseline;
+        self
+    }
+
+    /// Record the arrival of the head of the given slot at `received_at`, updating the
+    /// offset of the local clock.
+    pub fn record_head(&mut self, slot: Slot, received_at: Instant) {
+        let start = self.timing.slot_start(slot);
+        let delay = match received_at.checked_duration_since(start) {
+            Some(late) => late.as_micros() as i64,
+            None => -(start.duration_since(received_at).as_micros() as i64),
+        };
+
+        let was_skewed = self.is_skewed();
+        let was_compensating = self.compensation() != 0;
+        if self.delays_us.len() == OFFSET_WINDOW {
+            self.delays_us.pop_front();
+        }
+        self.delays_us.push_back(delay);
+
+        let offset = self.offset_us().unwrap_or_default();
+        ApiMetrics::set_clock_offset(offset as f64 / 1e6);
+
+        // Only the threshold crossings are logged, not every head
+        let (offset_ms, delay_ms) = (offset / 1_000, delay / 1_000);
+        debug!(slot, offset_ms, delay_ms, "Recorded the clock offset of the head");
+        match (was_skewed, self.is_skewed()) {
+            (false, true) => warn!(
+                offset_ms,
+                threshold_ms = self.warn_threshold.as_millis() as u64,
+                "Heads arrive far from the start of their slot, check the local clock"
+            ),
+            (true, false) => {
+                info!(offset_ms, "Heads arrive close to the start of their slot again")
+            }
+            _ => {}
+        }
+
+        let compensation = self.compensation();
+        match (was_compensating, compensation != 0) {
+            (false, true) => warn!(
+                offset_ms,
+                compensation_ms = compensation / 1_000,
+                max_compensation_ms = MAX_SKEW_COMPENSATION.as_millis() as u64,
+                "Shifting the commitment deadlines to compensate the clock offset"
+            ),
+            (true, false) => info!(offset_ms, "Stopped shifting the commitment deadlines"),
+            _ => {}
+        }
+    }
+
+    /// Returns true if the offset is above the warning threshold.
+    fn is_skewed(&self) -> bool {
+        self.offset_us()
+            .is_some_and(|offset| offset.unsigned_abs() > self.warn_threshold.as_micros() as u64)
+    }
+
+    /// Returns the offset of the local clock in microseconds, if any head was received: the
+    /// minimum arrival delay of the latest heads minus the propagation baseline.
+    pub fn offset_us(&self) -> Option<i64> {
+        let baseline = self.propagation_baseline.as_micros() as i64;
+        self.delays_us.iter().min().map(|delay| delay - baseline)
+    }
+
+    /// Returns the shift of the commitment deadlines in microseconds: the offset once
+    /// it exceeds the compensation threshold, clamped to [MAX_SKEW_COMPENSATION]. Zero otherwise.
+    pub fn compensation(&self) -> i64 {
+        let Some(offset) = self.offset_us() else { return 0 };
+        if self.compensation_threshold.is_zero() ||
+            offset.unsigned_abs() <= self.compensation_threshold.as_micros() as u64
+        {
+            return 0;
+        }
+
+        let max = MAX_SKEW_COMPENSATION.as_micros() as i64;
+        offset.clamp(-max, max)
+    }
+
+    /// Shift the given commitment deadline by the compensation of the clock offset.
+    pub fn compensate(&self, deadline: Instant) -> Instant {
+        let compensation = self.compensation();
+        let shift = Duration::from_micros(compensation.unsigned_abs());
+        if compensation >= 0 {
+            deadline + shift
+        } else {
+            deadline.checked_sub(shift).unwrap_or(deadline)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the arrival delay of the head of the given slot with a synchronized clock,
+    /// spread between 1 and 4 seconds with a few late blocks, like on mainnet.
+    fn propagation_delay(slot: Slot) -> Duration {
+        match slot % 16 {
+            7 => Duration::from_millis(9_000),
+            n => Duration::from_millis(1_000 + (n * 1_789) % 3_000),
+        }
+    }
+
+    #[test]
+    fn test_clock_offset() {
+        let slot_time = Duration::from_secs(12);
+        let timing = SlotTiming::from_anchor(96, Instant::now(), slot_time);
+        let new_monitor = || {
+            ClockSkewMonitor::new(timing, Duration::from_millis(500), Duration::from_millis(600))
+                .with_propagation_baseline(Duration::from_secs(1))
+        };
+
+        // The propagation delay of a synchronized clock isn't reported as an offset
+        let mut monitor = new_monitor();
+        assert_eq!((monitor.offset_us(), monitor.compensation()), (None, 0));
+        for slot in 96..200 {
+            monitor.record_head(slot, timing.slot_start(slot) + propagation_delay(slot));
+            assert!(!monitor.is_skewed());
+        }
+        assert_eq!((monitor.offset_us(), monitor.compensation()), (Some(0), 0));
+
+        // A local clock ahead of the chain delays every head, it's measured within an epoch
+        let ahead = Duration::from_millis(1_500);
+        for slot in 200..232 {
+            monitor.record_head(slot, timing.slot_start(slot) + propagation_delay(slot) + ahead);
+        }
+        assert!(monitor.is_skewed());
+        assert_eq!(monitor.offset_us(), Some(1_500_000));
+        assert_eq!(monitor.compensation(), 1_500_000);
+
+        // A local clock behind the chain makes heads arrive early, even before their slot
+        let mut monitor = new_monitor();
+        let behind = Duration::from_millis(1_800);
+        for slot in 100..132 {
+            monitor.record_head(slot, timing.slot_start(slot) + propagation_delay(slot) - behind);
+        }
+        assert_eq!(monitor.offset_us(), Some(-1_800_000));
+        assert_eq!(monitor.compensation(), -1_800_000);
+
+        // Compensation can be disabled
+        let mut disabled =
+            ClockSkewMonitor::new(timing, Duration::from_millis(500), Duration::ZERO);
+        disabled.record_head(100, timing.slot_start(100) + Duration::from_secs(5));
+        assert_eq!(disabled.compensation(), 0);
+    }
+}
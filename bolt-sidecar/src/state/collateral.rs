@@ -0,0 +1,427 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy::primitives::{Address, U256};
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use parking_lot::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+use crate::{
+    chain_io::{manager::OperatorStatus, BoltManager},
+    config::collateral::{CollateralOpts, CollateralPolicy},
+    primitives::commitment::CollateralAttestation,
+    telemetry::{health::OperatorHealth, ApiMetrics, HealthHandle},
+};
+
+use super::ValidationError;
+
+/// The basis points denominator used to apply the collateral multiple with integer math.
+const BPS: u64 = 10_000;
+
+/// The operator status and collateral fetched from the BoltManager contract at a given time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollateralSnapshot {
+    /// The registration status and collateral of the operator.
+    pub status: OperatorStatus,
+    /// The time at which the status has been fetched.
+    pub fetched_at: SystemTime,
+}
+
+/// Keeps track of the operator status and collateral registered in Bolt, and checks
+/// commitment requests against them according to the configured [CollateralPolicy].
+///
+/// Every poll is reported to the [HealthHandle], if any. A failed poll keeps the latest
+/// snapshot until it's older than the maximum staleness, so that RPC hiccups don't flap
+/// the health status.
+///
+/// The tracker is cheap to clone: every clone shares the same snapshot.
+#[derive(Debug, Clone)]
+pub struct CollateralTracker {
+    opts: CollateralOpts,
+    snapshot: Arc<RwLock<Option<CollateralSnapshot>>>,
+    consecutive_failures: Arc<AtomicU32>,
+    health: Option<HealthHandle>,
+}
+
+impl CollateralTracker {
+    /// Create a new tracker with the given options and no collateral snapshot yet.
+    pub fn new(opts: CollateralOpts) -> Self {
+        Self {
+            opts,
+            snapshot: Arc::new(RwLock::new(None)),
+            consecutive_failures: Arc::new(AtomicU32::new(0)),
+            health: None,
+        }
+    }
+
+    /// Sets the handle to report the operator status to.
+    pub fn with_health(mut self, health: HealthHandle) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Returns true if requests should be checked against the operator collateral.
+    pub fn is_enabled(&self) -> bool {
+        self.opts.policy != CollateralPolicy::Disabled || self.opts.require
+    }
+
+    /// Returns the latest collateral snapshot, if any.
+    pub fn snapshot(&self) -> Option<CollateralSnapshot> {
+        *self.snapshot.read()
+    }
+
+    /// Record a freshly fetched operator status.
+    pub fn update(&self, status: OperatorStatus) {
+        *self.snapshot.write() = Some(CollateralSnapshot { status, fetched_at: SystemTime::now() });
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        ApiMetrics::set_operator_status(&status);
+    }
+
+    /// Fetch the status of the operator and its collateral through the given validators from
+    /// the BoltManager contract, record it and report it to the health handle.
+    pub async fn poll(
+        &self,
+        manager: &BoltManager,
+        operator: Address,
+        keys: &[BlsPublicKey],
+    ) -> eyre::Result<OperatorStatus> {
+        let result = manager.get_operator_status(operator, keys).await;
+        match &result {
+            Ok(status) => self.update(*status),
+            Err(_) => {
+                self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+                ApiMetrics::increment_operator_status_poll_failures();
+            }
+        }
+
+        self.report_health(operator);
+        result
+    }
+
+    /// Spawn a background task polling the operator status every poll interval.
+    pub fn spawn_poller(
+        &self,
+        manager: BoltManager,
+        operator: Address,
+        keys: Vec<BlsPublicKey>,
+    ) -> JoinHandle<()> {
+        let tracker = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tracker.opts.poll_interval());
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                match tracker.poll(&manager, operator, &keys).await {
+                    Ok(status) => debug!(?status, "Updated operator status and collateral"),
+                    Err(err) => error!(?err, "Failed to poll operator status and collateral"),
+                }
+            }
+        })
+    }
+
+    /// Report the latest snapshot to the health handle, if any.
+    fn report_health(&self, operator: Address) {
+        let (Some(health), Some(snapshot)) = (&self.health, self.snapshot()) else {
+            return;
+        };
+
+        let operator = OperatorHealth {
+            address: operator,
+            status: snapshot.status,
+            updated_at: unix_secs(snapshot.fetched_at),
+            consecutive_failures: self.consecutive_failures.load(Ordering::Relaxed),
+            stale: self.is_stale(&snapshot),
+        };
+        if !operator.is_healthy() {
+            warn!(?operator, "Operator is not healthy, commitments may be ignored or slashed");
+        }
+
+        health.set_operator(operator);
+        ApiMetrics::set_sidecar_healthy(health.is_healthy());
+    }
+
+    /// Returns true if the snapshot is older than the maximum staleness.
+    fn is_stale(&self, snapshot: &CollateralSnapshot) -> bool {
+        let age = SystemTime::now().duration_since(snapshot.fetched_at).unwrap_or(Duration::ZERO);
+        age > self.opts.max_staleness()
+    }
+
+    /// Returns the latest snapshot if it's recent enough.
+    fn fresh_snapshot(&self) -> Result<CollateralSnapshot, ValidationError> {
+        let Some(snapshot) = self.snapshot() else {
+            warn!("Operator collateral hasn't been fetched yet, rejecting request");
+            return Err(ValidationError::StaleCollateral);
+        };
+
+        if self.is_stale(&snapshot) {
+            warn!(fetched_at = ?snapshot.fetched_at, "Operator collateral is stale, rejecting request");
+            return Err(ValidationError::StaleCollateral);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Check a request transferring a total of `value` wei against the operator collateral.
+    ///
+    /// Returns the [CollateralAttestation] to include in the commitment, or `None` if the
+    /// policy is disabled. If the latest snapshot is missing or older than the maximum
+    /// staleness, the request is rejected regardless of the policy.
+    ///
+    /// If collateral is required, the request is also rejected while the operator is not
+    /// active or its collateral is below the protocol minimum.
+    pub fn check(&self, value: U256) -> Result<Option<CollateralAttestation>, ValidationError> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+
+        let snapshot = self.fresh_snapshot()?;
+        let status = snapshot.status;
+
+        if self.opts.require {
+            if !status.is_active() {
+                return Err(ValidationError::OperatorInactive);
+            }
+            if !status.is_collateralized() {
+                return Err(ValidationError::BelowMinimumCollateral(
+                    status.collateral,
+                    status.minimum_collateral,
+                ));
+            }
+        }
+
+        if self.opts.policy == CollateralPolicy::Disabled {
+            return Ok(None);
+        }
+
+        let multiple_bps = U256::from((self.opts.multiple.max(0.0) * BPS as f64).round() as u64);
+        let max_value = status.collateral.saturating_mul(multiple_bps) / U256::from(BPS);
+
+        if value > max_value {
+            match self.opts.policy {
+                CollateralPolicy::Reject => {
+                    return Err(ValidationError::InsufficientCollateral(value, max_value))
+                }
+                _ => warn!(%value, %max_value, "Request value exceeds the operator collateral"),
+            }
+        }
+
+        Ok(Some(CollateralAttestation {
+            collateral: status.collateral,
+            updated_at: unix_secs(snapshot.fetched_at),
+            policy: self.opts.policy,
+            max_value,
+        }))
+    }
+}
+
+/// Returns the UNIX timestamp in seconds of the given time.
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::FixedBytes, sol_types::SolCall};
+    use serde_json::{json, Value};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    use crate::{
+        chain_io::manager::{
+            BoltManagerContract::{
+                getProposerStatusesCall, isOperatorCall, isOperatorEnabledCall, parametersCall,
+                ProposerStatus,
+            },
+            BoltParametersContract::MINIMUM_OPERATOR_STAKECall,
+        },
+        config::collateral::DEFAULT_COLLATERAL_MAX_STALENESS_SECS,
+    };
+
+    use super::*;
+
+    const ONE_ETH: u64 = 1_000_000_000_000_000_000;
+
+    const OPERATOR: Address = Address::repeat_byte(0x42);
+
+    fn opts(policy: CollateralPolicy) -> CollateralOpts {
+        CollateralOpts { policy, multiple: 2.0, ..Default::default() }
+    }
+
+    /// Start a mock EL node answering the BoltManager calls with a single registered and enabled
+    /// operator, whose collateral amounts can be changed. The protocol minimum is 1 ETH.
+    async fn mock_registry(amounts: Arc<RwLock<Vec<U256>>>) -> MockServer {
+        let server = MockServer::start().await;
+        mount_registry(&server, amounts).await;
+        server
+    }
+
+    async fn mount_registry(server: &MockServer, amounts: Arc<RwLock<Vec<U256>>>) {
+        Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(move |req: &Request| {
+                let body = serde_json::from_slice::<Value>(&req.body).unwrap();
+                let result = match body["method"].as_str() {
+                    Some("eth_call") => {
+                        let params = &body["params"][0];
+                        let input = params["input"].as_str().or(params["data"].as_str()).unwrap();
+                        let input = alloy::hex::decode(input).unwrap();
+
+                        let selector = &input[..4];
+                        let returndata = if selector == getProposerStatusesCall::SELECTOR {
+                            let amounts = amounts.read().clone();
+                            let status = ProposerStatus {
+                                pubkeyHash: FixedBytes::ZERO,
+                                active: true,
+                                operator: OPERATOR,
+                                operatorRPC: "http://localhost:8017".to_string(),
+                                collaterals: vec![Address::ZERO; amounts.len()],
+                                amounts,
+                            };
+                            getProposerStatusesCall::abi_encode_returns(&(vec![status],))
+                        } else if selector == isOperatorCall::SELECTOR {
+                            isOperatorCall::abi_encode_returns(&(true,))
+                        } else if selector == isOperatorEnabledCall::SELECTOR {
+                            isOperatorEnabledCall::abi_encode_returns(&(true,))
+                        } else if selector == parametersCall::SELECTOR {
+                            parametersCall::abi_encode_returns(&(Address::repeat_byte(0x11),))
+                        } else if selector == MINIMUM_OPERATOR_STAKECall::SELECTOR {
+                            MINIMUM_OPERATOR_STAKECall::abi_encode_returns(&(U256::from(ONE_ETH),))
+                        } else {
+                            panic!("unexpected call with selector {selector:?}")
+                        };
+
+                        json!(format!("0x{}", alloy::hex::encode(returndata)))
+                    }
+                    _ => json!("0x1"),
+                };
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+            })
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_under_collateralized_request_is_rejected() -> eyre::Result<()> {
+        let amounts = vec![U256::from(ONE_ETH), U256::from(ONE_ETH)];
+        let server = mock_registry(Arc::new(RwLock::new(amounts))).await;
+        let manager =
+            BoltManager::from_address(server.uri().parse::<reqwest::Url>()?, Address::ZERO);
+
+        let keys = vec![BlsPublicKey::default()];
+
+        let tracker = CollateralTracker::new(opts(CollateralPolicy::Reject));
+        let status = tracker.poll(&manager, OPERATOR, &keys).await?;
+        assert_eq!(status.collateral, U256::from(2 * ONE_ETH));
+
+        // Up to 2x the collateral is fine, and the attestation is returned
+        let attestation = tracker.check(U256::from(4 * ONE_ETH))?.expect("policy enabled");
+        assert_eq!(attestation.collateral, U256::from(2 * ONE_ETH));
+        assert_eq!(attestation.max_value, U256::from(4 * ONE_ETH));
+        assert_eq!(attestation.policy, CollateralPolicy::Reject);
+
+        // Anything above is rejected
+        let err = tracker.check(U256::from(4 * ONE_ETH + 1)).unwrap_err();
+        assert!(matches!(err, ValidationError::InsufficientCollateral(_, _)));
+
+        // With the warn policy the same request is accepted
+        let tracker = CollateralTracker::new(opts(CollateralPolicy::Warn));
+        tracker.poll(&manager, OPERATOR, &keys).await?;
+        assert!(tracker.check(U256::from(4 * ONE_ETH + 1))?.is_some());
+
+        // With the policy disabled, nothing is checked nor attested
+        let tracker = CollateralTracker::new(opts(CollateralPolicy::Disabled));
+        assert!(tracker.check(U256::MAX)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_collateral_is_rejected() {
+        let tracker = CollateralTracker::new(CollateralOpts {
+            max_staleness_secs: 60,
+            ..opts(CollateralPolicy::Warn)
+        });
+
+        // No collateral fetched yet
+        assert!(matches!(tracker.check(U256::ZERO), Err(ValidationError::StaleCollateral)));
+
+        tracker.update(OperatorStatus { collateral: U256::from(ONE_ETH), ..Default::default() });
+        assert!(tracker.check(U256::from(ONE_ETH)).is_ok());
+
+        // Even with the warn policy, stale data falls back to rejecting requests
+        tracker.snapshot.write().as_mut().unwrap().fetched_at =
+            SystemTime::now() - Duration::from_secs(61);
+        assert!(matches!(tracker.check(U256::from(1)), Err(ValidationError::StaleCollateral)));
+    }
+
+    #[tokio::test]
+    async fn test_collateral_below_minimum() -> eyre::Result<()> {
+        let amounts = Arc::new(RwLock::new(vec![U256::from(2 * ONE_ETH)]));
+        let server = mock_registry(amounts.clone()).await;
+        let manager =
+            BoltManager::from_address(server.uri().parse::<reqwest::Url>()?, Address::ZERO);
+
+        let keys = vec![BlsPublicKey::default()];
+        let health = HealthHandle::default();
+        let tracker =
+            CollateralTracker::new(CollateralOpts { require: true, ..Default::default() })
+                .with_health(health.clone());
+
+        let status = tracker.poll(&manager, OPERATOR, &keys).await?;
+        assert!(status.is_active() && status.is_collateralized());
+        assert_eq!(status.minimum_collateral, U256::from(ONE_ETH));
+        assert!(health.is_healthy());
+        assert!(tracker.check(U256::ZERO)?.is_none());
+
+        // The collateral drops below the minimum: the health degrades and requests are rejected
+        *amounts.write() = vec![U256::from(ONE_ETH / 2)];
+        tracker.poll(&manager, OPERATOR, &keys).await?;
+        assert!(!health.is_healthy());
+        let report = health.status();
+        assert!(!report.healthy);
+        assert_eq!(report.operator.unwrap().status.collateral, U256::from(ONE_ETH / 2));
+        let err = tracker.check(U256::ZERO).unwrap_err();
+        assert!(matches!(err, ValidationError::BelowMinimumCollateral(_, _)));
+
+        // Unless collateral isn't required
+        let lenient = CollateralTracker::new(CollateralOpts::default());
+        lenient.poll(&manager, OPERATOR, &keys).await?;
+        assert!(lenient.check(U256::ZERO)?.is_none());
+
+        // Back at the minimum, everything recovers
+        *amounts.write() = vec![U256::from(ONE_ETH)];
+        tracker.poll(&manager, OPERATOR, &keys).await?;
+        assert!(health.is_healthy());
+        assert!(tracker.check(U256::ZERO).is_ok());
+
+        // A failed poll keeps the latest status, without flapping the health
+        server.reset().await;
+        assert!(tracker.poll(&manager, OPERATOR, &keys).await.is_err());
+        assert!(health.is_healthy());
+        assert_eq!(health.status().operator.unwrap().consecutive_failures, 1);
+        assert!(tracker.check(U256::ZERO).is_ok());
+
+        // Until it's stale
+        tracker.snapshot.write().as_mut().unwrap().fetched_at =
+            SystemTime::now() - Duration::from_secs(DEFAULT_COLLATERAL_MAX_STALENESS_SECS + 1);
+        assert!(tracker.poll(&manager, OPERATOR, &keys).await.is_err());
+        let operator = health.status().operator.unwrap();
+        assert!(operator.stale);
+        assert_eq!(operator.consecutive_failures, 2);
+        assert!(!health.is_healthy());
+
+        // And the next successful poll restores it
+        mount_registry(&server, amounts).await;
+        tracker.poll(&manager, OPERATOR, &keys).await?;
+        assert!(health.is_healthy());
+
+        Ok(())
+    }
+}
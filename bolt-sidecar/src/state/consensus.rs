@@ -5,10 +5,14 @@ use std::{
 
 use beacon_api_client::{mainnet::Client, ProposerDuty};
 use ethereum_consensus::{crypto::PublicKey as BlsPublicKey, phase0::mainnet::SLOTS_PER_EPOCH};
+use serde_json::{json, Value};
 use tokio::join;
 use tracing::debug;
 
-use super::CommitmentDeadline;
+use super::{
+    ClockSkewMonitor, CommitmentDeadline, DeadlinePolicy, EffectiveValidators, FixedDeadline,
+    SlotTiming, VerifiedValidators,
+};
 use crate::{
     client::BeaconClient,
     primitives::{InclusionRequest, Slot},
@@ -28,6 +32,30 @@ pub enum ConsensusError {
     DeadlineExceeded,
     #[error("Validator not found in the slot")]
     ValidatorNotFound,
+    #[error("Validator {0} is not registered and active in Bolt")]
+    ValidatorNotVerified(BlsPublicKey),
+    #[error(
+        "Insufficient lead time: {actual:?} before the commitment deadline, {required:?} required"
+    )]
+    InsufficientLeadTime { required: Duration, actual: Duration, earliest_slot: Slot },
+}
+
+impl ConsensusError {
+    /// Returns the context of the error as structured data, for the errors that clients can
+    /// correct, e.g. the earliest slot they can retarget their request to.
+    pub fn data(&self) -> Option<Value> {
+        match self {
+            ConsensusError::InsufficientLeadTime { required, actual, earliest_slot } => {
+                Some(json!({
+                    "reason": "insufficient_lead_time",
+                    "required_ms": required.as_millis() as u64,
+                    "actual_ms": actual.as_millis() as u64,
+                    "earliest_slot": earliest_slot,
+                }))
+            }
+            _ => None,
+        }
+    }
 }
 
 /// Represents an epoch in the beacon chain.
@@ -64,14 +92,30 @@ pub struct ConsensusState {
     /// which won't have time to be included by the PBS pipeline.
     // commitment_deadline: u64,
     commitment_deadline: CommitmentDeadline,
-    /// The duration of the commitment deadline.
-    commitment_deadline_duration: Duration,
+    /// The policy deciding when the commitments for a slot stop being accepted.
+    deadline_policy: Box<dyn DeadlinePolicy>,
+    /// The start of the slots according to the genesis time, to check the lead time of the
+    /// requests. Only set along with the minimum lead time.
+    slot_timing: Option<SlotTiming>,
+    /// The minimum time before the commitment deadline of the target slot at which requests
+    /// must be received.
+    min_lead_time: Duration,
+    /// The monitor of the offset of the local clock, compensating the commitment deadlines
+    /// for it. If not set, the deadlines are never compensated.
+    clock_skew: Option<ClockSkewMonitor>,
     /// If commitment requests should be validated also against the unsafe lookahead
     /// (i.e. the next epoch's proposer duties).
     ///
     /// It is considered unsafe because it is possible for the next epoch's duties to
     /// change if there are beacon chain deposits or withdrawals in the current epoch.
     unsafe_lookahead_enabled: bool,
+    /// The validators verified in the BoltManager contract. Requests for slots of other
+    /// validators are rejected.
+    verified_validators: VerifiedValidators,
+    /// The validators of the sidecar: requests are only accepted for the slots of their
+    /// proposer duties, which arm the commitment deadline. If not set, every proposer duty is
+    /// considered ours.
+    validators: Option<EffectiveValidators>,
 }
 
 impl fmt::Debug for ConsensusState {
@@ -81,8 +125,12 @@ impl fmt::Debug for ConsensusState {
             .field("latest_slot", &self.latest_slot)
             .field("latest_slot_timestamp", &self.latest_slot_timestamp)
             .field("commitment_deadline", &self.commitment_deadline)
-            .field("commitment_deadline_duration", &self.commitment_deadline_duration)
+            .field("deadline_policy", &self.deadline_policy)
+            .field("min_lead_time", &self.min_lead_time)
+            .field("clock_skew", &self.clock_skew)
             .field("unsafe_lookahead_enabled", &self.unsafe_lookahead_enabled)
+            .field("verified_validators", &self.verified_validators.count())
+            .field("validators", &self.validators.as_ref().map(EffectiveValidators::len))
             .finish()
     }
 }
@@ -92,6 +140,7 @@ impl ConsensusState {
     pub fn new(
         beacon_api_client: BeaconClient,
         commitment_deadline_duration: Duration,
+        slot_time: Duration,
         unsafe_lookahead_enabled: bool,
     ) -> Self {
         ConsensusState {
@@ -99,17 +148,60 @@ impl ConsensusState {
             epoch: Epoch::default(),
             latest_slot: Default::default(),
             latest_slot_timestamp: Instant::now(),
-            commitment_deadline: CommitmentDeadline::new(0, commitment_deadline_duration),
-            commitment_deadline_duration,
+            commitment_deadline: CommitmentDeadline::disarmed(0),
+            deadline_policy: Box::new(FixedDeadline::new(commitment_deadline_duration, slot_time)),
+            slot_timing: None,
+            min_lead_time: Duration::ZERO,
+            clock_skew: None,
             unsafe_lookahead_enabled,
+            verified_validators: VerifiedValidators::default(),
+            validators: None,
         }
     }
 
+    /// Set the policy deciding when the commitments for a slot stop being accepted. Defaults
+    /// to the commitment deadline duration after the head preceding the slot.
+    pub fn with_deadline_policy(mut self, deadline_policy: impl DeadlinePolicy + 'static) -> Self {
+        self.deadline_policy = Box::new(deadline_policy);
+        self
+    }
+
+    /// Require the requests to be received at least the given duration before the commitment
+    /// deadline of their slot, computed with the given slot timing. Disabled if zero.
+    pub fn with_min_lead_time(mut self, min_lead_time: Duration, timing: SlotTiming) -> Self {
+        self.min_lead_time = min_lead_time;
+        self.slot_timing = Some(timing);
+        self
+    }
+
+    /// Monitor the offset of the local clock with the arrival of the heads, and compensate the
+    /// commitment deadlines for it once above the threshold of the monitor.
+    pub fn with_clock_skew_monitor(mut self, monitor: ClockSkewMonitor) -> Self {
+        self.clock_skew = Some(monitor);
+        self
+    }
+
+    /// Set the validators verified in the BoltManager contract.
+    pub fn with_verified_validators(mut self, verified_validators: VerifiedValidators) -> Self {
+        self.verified_validators = verified_validators;
+        self
+    }
+
+    /// Set the validators of the sidecar. Requests are only accepted, and the commitment
+    /// deadline only armed, for the slots of their proposer duties. The set is shared, so that
+    /// the keys listed later by a remote signer are accepted too.
+    pub fn with_validators(mut self, validators: EffectiveValidators) -> Self {
+        self.validators = Some(validators);
+        self
+    }
+
     /// Validate an incoming commitment request against beacon chain data.
     /// The request is valid if:
     ///
     /// 1. The target slot is scheduled to be proposed by one of our validators.
-    /// 2. The request hasn't passed the slot deadline.
+    /// 2. The request hasn't passed the commitment deadline of its slot, and leaves at least the
+    ///    minimum lead time before it.
+    /// 3. The validator is still verified in the BoltManager contract.
     ///
     /// If the request is valid, return the validator public key for the target slot.
     pub fn validate_request(&self, req: &InclusionRequest) -> Result<BlsPublicKey, ConsensusError> {
@@ -118,31 +210,93 @@ impl ConsensusState {
             return Err(ConsensusError::InvalidSlot(req.slot));
         }
 
-        // If the request is for the next slot, check if it's within the commitment deadline
-        if req.slot == self.latest_slot + 1 &&
-            self.latest_slot_timestamp + self.commitment_deadline_duration < Instant::now()
-        {
+        // Check if the request is within the commitment deadline of its slot
+        if self.commitment_deadline_at(req.slot) < Instant::now() {
             return Err(ConsensusError::DeadlineExceeded);
         }
+        self.check_lead_time(req.slot, Instant::now())?;
 
         // Find the validator pubkey for the given slot from the proposer duties
-        self.find_validator_pubkey_for_slot(req.slot)
+        let pubkey = self.find_validator_pubkey_for_slot(req.slot)?;
+
+        if !self.verified_validators.contains(&pubkey) {
+            return Err(ConsensusError::ValidatorNotVerified(pubkey));
+        }
+
+        Ok(pubkey)
+    }
+
+    /// Check that a request for the given slot, received at `now`, leaves at least the minimum
+    /// lead time before the commitment deadline of the slot. The deadline is computed from the
+    /// start of the latest head slot according to the genesis time, rather than from the
+    /// instant the head was received.
+    fn check_lead_time(&self, slot: Slot, now: Instant) -> Result<(), ConsensusError> {
+        let Some(timing) = self.slot_timing.filter(|_| !self.min_lead_time.is_zero()) else {
+            return Ok(());
+        };
+
+        let head_start = timing.slot_start(self.latest_slot);
+        let deadline = self.deadline_policy.deadline(slot, self.latest_slot, head_start);
+        let actual = deadline.saturating_duration_since(now);
+        if actual >= self.min_lead_time {
+            return Ok(());
+        }
+
+        // The deadline of every further slot is one slot time later
+        let missing = (now + self.min_lead_time).saturating_duration_since(deadline);
+        let slots = missing.as_nanos().div_ceil(timing.slot_time().as_nanos().max(1));
+        Err(ConsensusError::InsufficientLeadTime {
+            required: self.min_lead_time,
+            actual,
+            earliest_slot: slot + slots as Slot,
+        })
     }
 
-    /// Wait for the commitment deadline to expire.
-    pub async fn wait_commitment_deadline(&mut self) -> Option<u64> {
+    /// Returns the instant at which commitments for the given slot stop being accepted,
+    /// i.e. when its commitment deadline is reached according to the deadline policy,
+    /// compensated for the offset of the local clock if needed.
+    pub fn commitment_deadline_at(&self, slot: Slot) -> Instant {
+        let deadline =
+            self.deadline_policy.deadline(slot, self.latest_slot, self.latest_slot_timestamp);
+        self.clock_skew.as_ref().map_or(deadline, |monitor| monitor.compensate(deadline))
+    }
+
+    /// Record the arrival of the head of the given slot, to monitor the offset of the local
+    /// clock. The deadlines armed from now on are compensated with the updated offset.
+    pub fn record_head(&mut self, slot: Slot, received_at: Instant) {
+        if let Some(monitor) = &mut self.clock_skew {
+            monitor.record_head(slot, received_at);
+        }
+    }
+
+    /// Wait for the commitment deadline to expire. Never resolves while it's disarmed.
+    pub async fn wait_commitment_deadline(&mut self) -> u64 {
         self.commitment_deadline.wait().await
     }
 
+    /// Returns true if the commitment deadline of the next slot is armed, i.e. if one of
+    /// our validators has the proposer duty of the next slot.
+    pub fn is_commitment_deadline_armed(&self) -> bool {
+        self.commitment_deadline.is_armed()
+    }
+
+    /// Disarm the commitment deadline of the next slot, e.g. on a reorg or on shutdown.
+    pub fn cancel_commitment_deadline(&mut self) {
+        self.commitment_deadline.cancel();
+    }
+
+    /// Returns true if one of our verified validators has the proposer duty of the given slot,
+    /// i.e. if commitments can be accepted for it.
+    pub fn has_duty(&self, slot: Slot) -> bool {
+        self.find_validator_pubkey_for_slot(slot)
+            .is_ok_and(|pubkey| self.verified_validators.contains(&pubkey))
+    }
+
     /// Update the latest head and fetch the relevant data from the beacon chain.
     pub async fn update_slot(&mut self, slot: u64) -> Result<(), ConsensusError> {
         debug!("Updating slot to {slot}");
         ApiMetrics::set_latest_head(slot as u32);
 
-        // Reset the commitment deadline to start counting for the next slot.
-        self.commitment_deadline =
-            CommitmentDeadline::new(slot + 1, self.commitment_deadline_duration);
-
         // Update the timestamp with current time
         self.latest_slot_timestamp = Instant::now();
         self.latest_slot = slot;
@@ -151,19 +305,33 @@ impl ConsensusState {
         let epoch = slot / SLOTS_PER_EPOCH;
 
         // If the epoch has changed, update the proposer duties
-        if epoch != self.epoch.value {
+        let duties = if epoch != self.epoch.value {
             debug!("Updating epoch to {epoch}");
             self.epoch.value = epoch;
             self.epoch.start_slot = epoch * SLOTS_PER_EPOCH;
 
-            self.fetch_proposer_duties(epoch).await?;
+            self.fetch_proposer_duties(epoch).await
         } else if self.epoch.proposer_duties.is_empty() {
             debug!(epoch, "No proposer duties found for current epoch, fetching...");
             // If the proposer duties are empty, fetch them
-            self.fetch_proposer_duties(epoch).await?;
-        }
+            self.fetch_proposer_duties(epoch).await
+        } else {
+            Ok(())
+        };
 
-        Ok(())
+        // Reset the commitment deadline to start counting for the next slot, only if we have
+        // a duty in it. It is created after the timestamp so that it never fires before
+        // `commitment_deadline_at`.
+        let next_slot = slot + 1;
+        self.commitment_deadline = if self.has_duty(next_slot) {
+            let deadline = self.commitment_deadline_at(next_slot);
+            CommitmentDeadline::new(next_slot, deadline.saturating_duration_since(Instant::now()))
+        } else {
+            debug!(slot = next_slot, "No proposer duty in the next slot, deadline disarmed");
+            CommitmentDeadline::disarmed(next_slot)
+        };
+
+        duties
     }
 
     /// Fetch proposer duties for the given epoch and the next one if the unsafe lookahead flag is
@@ -191,13 +359,17 @@ impl ConsensusState {
         Ok(())
     }
 
-    /// Finds the validator public key for the given slot from the proposer duties.
+    /// Finds the validator public key for the given slot from the proposer duties, if it's
+    /// one of our validators.
     fn find_validator_pubkey_for_slot(&self, slot: u64) -> Result<BlsPublicKey, ConsensusError> {
         self.epoch
             .proposer_duties
             .iter()
             .find(|&duty| duty.slot == slot)
             .map(|duty| duty.public_key.clone())
+            .filter(|pubkey| {
+                self.validators.as_ref().map_or(true, |validators| validators.contains(pubkey))
+            })
             .ok_or(ConsensusError::ValidatorNotFound)
     }
 
@@ -214,10 +386,14 @@ impl ConsensusState {
 mod tests {
     use beacon_api_client::BlockId;
     use reqwest::Url;
+    use tokio::time::timeout;
     use tracing::warn;
 
     use super::*;
-    use crate::test_util::try_get_beacon_api_url;
+    use crate::{
+        signer::local::LocalSigner, state::clock_skew::MAX_SKEW_COMPENSATION,
+        test_util::try_get_beacon_api_url,
+    };
 
     #[tokio::test]
     async fn test_update_slot() -> eyre::Result<()> {
@@ -239,8 +415,16 @@ mod tests {
             latest_slot: Default::default(),
             latest_slot_timestamp: Instant::now(),
             commitment_deadline: CommitmentDeadline::new(0, commitment_deadline_duration),
-            commitment_deadline_duration,
+            deadline_policy: Box::new(FixedDeadline::new(
+                commitment_deadline_duration,
+                Duration::from_secs(12),
+            )),
+            slot_timing: None,
+            min_lead_time: Duration::ZERO,
+            clock_skew: None,
             unsafe_lookahead_enabled: false,
+            verified_validators: VerifiedValidators::default(),
+            validators: None,
         };
 
         // Update the slot to 32
@@ -284,9 +468,17 @@ mod tests {
             latest_slot: Default::default(),
             latest_slot_timestamp: Instant::now(),
             commitment_deadline: CommitmentDeadline::new(0, commitment_deadline_duration),
-            commitment_deadline_duration,
+            deadline_policy: Box::new(FixedDeadline::new(
+                commitment_deadline_duration,
+                Duration::from_secs(12),
+            )),
+            slot_timing: None,
+            min_lead_time: Duration::ZERO,
+            clock_skew: None,
             // We test for both epochs
             unsafe_lookahead_enabled: true,
+            verified_validators: VerifiedValidators::default(),
+            validators: None,
         };
 
         let epoch =
@@ -298,4 +490,196 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_commitment_deadline_only_armed_for_duties() -> eyre::Result<()> {
+        let ours = LocalSigner::random().pubkey();
+        let other = LocalSigner::random().pubkey();
+        let duty = |slot: Slot, public_key| ProposerDuty {
+            public_key,
+            validator_index: slot as usize,
+            slot,
+        };
+
+        // The proposer duties are already known: the beacon node isn't queried
+        let beacon_client = BeaconClient::new(Url::parse("http://127.0.0.1:1")?);
+        let mut state = ConsensusState::new(
+            beacon_client,
+            Duration::from_millis(10),
+            Duration::from_secs(12),
+            false,
+        )
+        .with_validators(EffectiveValidators::from_iter([ours.clone()]));
+        state.epoch.proposer_duties = vec![duty(5, ours.clone()), duty(7, other), duty(9, ours)];
+
+        let mut resolved = Vec::new();
+        for slot in 1..10 {
+            state.update_slot(slot).await?;
+            assert_eq!(state.is_commitment_deadline_armed(), [4, 8].contains(&slot));
+
+            let wait = timeout(Duration::from_millis(100), state.wait_commitment_deadline());
+            if let Ok(deadline_slot) = wait.await {
+                resolved.push(deadline_slot);
+            }
+        }
+        assert_eq!(resolved, vec![5, 9]);
+
+        // A cancelled deadline doesn't resolve
+        state.update_slot(4).await?;
+        state.cancel_commitment_deadline();
+        let wait = timeout(Duration::from_millis(100), state.wait_commitment_deadline());
+        assert!(wait.await.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duties_of_effective_validators() -> eyre::Result<()> {
+        let delegated = LocalSigner::random().pubkey();
+        let signer = LocalSigner::random().pubkey();
+        let other = LocalSigner::random().pubkey();
+        let duty = |slot: Slot, public_key| ProposerDuty {
+            public_key,
+            validator_index: slot as usize,
+            slot,
+        };
+
+        // The effective validators combine the delegated ones and the ones of the signer
+        let validators = EffectiveValidators::from_iter([delegated.clone(), signer.clone()]);
+        let beacon_client = BeaconClient::new(Url::parse("http://127.0.0.1:1")?);
+        let mut state = ConsensusState::new(
+            beacon_client,
+            Duration::from_millis(10),
+            Duration::from_secs(12),
+            false,
+        )
+        .with_validators(validators.clone());
+        state.epoch.proposer_duties =
+            vec![duty(1, delegated.clone()), duty(2, signer.clone()), duty(3, other.clone())];
+
+        assert_eq!(state.find_validator_pubkey_for_slot(1)?, delegated);
+        assert_eq!(state.find_validator_pubkey_for_slot(2)?, signer);
+        assert!(state.has_duty(1) && state.has_duty(2));
+
+        // The slots of other validators, e.g. excluded ones, are rejected
+        assert!(matches!(
+            state.find_validator_pubkey_for_slot(3),
+            Err(ConsensusError::ValidatorNotFound)
+        ));
+        assert!(!state.has_duty(3));
+
+        // Until their key is listed by the signer, without restarting
+        validators.update([delegated, signer, other.clone()]);
+        assert_eq!(state.find_validator_pubkey_for_slot(3)?, other);
+        assert!(state.has_duty(3));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clock_skew_compensation() -> eyre::Result<()> {
+        let slot_time = Duration::from_secs(12);
+        let timing = SlotTiming::from_anchor(100, Instant::now(), slot_time);
+        let ours = LocalSigner::random().pubkey();
+        let duty = |slot: Slot| ProposerDuty { public_key: ours.clone(), validator_index: 0, slot };
+
+        let beacon_client = BeaconClient::new(Url::parse("http://127.0.0.1:1")?);
+        let monitor =
+            ClockSkewMonitor::new(timing, Duration::from_millis(500), Duration::from_millis(600));
+        let mut state =
+            ConsensusState::new(beacon_client, Duration::from_secs(8), slot_time, false)
+                .with_validators(EffectiveValidators::from_iter([ours.clone()]))
+                .with_clock_skew_monitor(monitor);
+        state.epoch =
+            Epoch { value: 3, start_slot: 96, proposer_duties: (96..128).map(duty).collect() };
+
+        state.update_slot(110).await?;
+        let uncompensated = state.commitment_deadline_at(111);
+
+        // Every head arrives 800ms after the start of its slot
+        for slot in 100..110 {
+            state.record_head(slot, timing.slot_start(slot) + Duration::from_millis(800));
+        }
+
+        // The deadline armed for the next slot is shifted by the offset
+        state.update_slot(110).await?;
+        assert!(state.is_commitment_deadline_armed());
+        let compensated = state.commitment_deadline_at(111);
+        let shift = compensated - uncompensated;
+        assert!(shift >= Duration::from_millis(800) && shift < Duration::from_millis(850));
+
+        // Below the compensation threshold, the deadline isn't shifted
+        let monitor =
+            ClockSkewMonitor::new(timing, Duration::from_millis(500), Duration::from_millis(900));
+        state.clock_skew = Some(monitor);
+        for slot in 100..110 {
+            state.record_head(slot, timing.slot_start(slot) + Duration::from_millis(800));
+        }
+        assert_eq!(
+            state.commitment_deadline_at(111),
+            state.latest_slot_timestamp + Duration::from_secs(8)
+        );
+
+        // The compensation is clamped, whatever the offset
+        for slot in 110..150 {
+            state.record_head(slot, timing.slot_start(slot) + Duration::from_secs(6));
+        }
+        assert_eq!(
+            state.commitment_deadline_at(111),
+            state.latest_slot_timestamp + Duration::from_secs(8) + MAX_SKEW_COMPENSATION
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_lead_time() -> eyre::Result<()> {
+        let slot_time = Duration::from_secs(12);
+        let head_start = Instant::now();
+        let beacon_client = BeaconClient::new(Url::parse("http://127.0.0.1:1")?);
+        let mut state =
+            ConsensusState::new(beacon_client, Duration::from_secs(8), slot_time, false);
+        state.latest_slot = 100;
+
+        // Disabled by default, even past the deadline
+        assert!(state.check_lead_time(101, head_start + Duration::from_secs(10)).is_ok());
+
+        // The deadline of the next slot is 8s into the head slot, whenever the head is received
+        let timing = SlotTiming::from_anchor(100, head_start, slot_time);
+        let mut state = state.with_min_lead_time(Duration::from_secs(2), timing);
+        state.latest_slot_timestamp = head_start + Duration::from_secs(3);
+
+        // Exactly at the boundary
+        let boundary = head_start + Duration::from_secs(6);
+        assert!(state.check_lead_time(101, boundary).is_ok());
+
+        // Right after it, the request can be retargeted to the slot after
+        let late = boundary + Duration::from_millis(1);
+        let err = state.check_lead_time(101, late).unwrap_err();
+        assert!(matches!(
+            err,
+            ConsensusError::InsufficientLeadTime { required, actual, earliest_slot: 102 }
+                if required == Duration::from_secs(2) && actual == Duration::from_millis(1_999)
+        ));
+        assert_eq!(
+            err.data(),
+            Some(json!({
+                "reason": "insufficient_lead_time",
+                "required_ms": 2_000,
+                "actual_ms": 1_999,
+                "earliest_slot": 102,
+            }))
+        );
+
+        // One slot ahead, the lead time is one slot longer
+        assert!(state.check_lead_time(102, late).is_ok());
+
+        // A lead time longer than a slot skips several slots
+        let state = state.with_min_lead_time(Duration::from_secs(30), timing);
+        let err = state.check_lead_time(101, late).unwrap_err();
+        assert!(matches!(err, ConsensusError::InsufficientLeadTime { earliest_slot: 104, .. }));
+        assert!(state.check_lead_time(104, late).is_ok());
+
+        Ok(())
+    }
 }
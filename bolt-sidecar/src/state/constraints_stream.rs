@@ -0,0 +1,142 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::RwLock;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use crate::{primitives::SignedConstraints, telemetry::ApiMetrics};
+
+/// The capacity of the constraints events channel. Subscribers lagging behind skip the
+/// oldest events.
+const CONSTRAINTS_EVENTS_CAPACITY: usize = 256;
+
+/// A cheaply cloneable stream of the signed constraints accepted by the sidecar, for
+/// builders to start building on them right away.
+///
+/// The constraints of the upcoming slots are kept to be replayed to new subscribers, until
+/// a new head reaches their slot.
+#[derive(Debug, Clone)]
+pub struct ConstraintsStream {
+    upcoming: Arc<RwLock<BTreeMap<u64, Vec<SignedConstraints>>>>,
+    events: broadcast::Sender<SignedConstraints>,
+    subscribers: Arc<AtomicUsize>,
+}
+
+impl Default for ConstraintsStream {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(CONSTRAINTS_EVENTS_CAPACITY);
+        Self { upcoming: Arc::default(), events, subscribers: Arc::default() }
+    }
+}
+
+impl ConstraintsStream {
+    /// Publish newly accepted signed constraints to the subscribers.
+    pub fn publish(&self, signed_constraints: SignedConstraints) {
+        // Sending under the lock keeps the replay of new subscribers and the events in sync
+        let mut upcoming = self.upcoming.write();
+        upcoming
+            .entry(signed_constraints.message.slot)
+            .or_default()
+            .push(signed_constraints.clone());
+
+        // An error only means that there are no subscribers
+        let _ = self.events.send(signed_constraints);
+    }
+
+    /// Forget the constraints of the given head slot and the earlier ones.
+    pub fn prune(&self, head_slot: u64) {
+        let mut upcoming = self.upcoming.write();
+        *upcoming = upcoming.split_off(&(head_slot + 1));
+    }
+
+    /// Subscribe to the constraints published from now on, along with the constraints of the
+    /// upcoming slots published before.
+    pub fn subscribe(&self) -> ConstraintsSubscription {
+        let upcoming = self.upcoming.read();
+        let replay = upcoming.values().flatten().cloned().collect();
+        let events = self.events.subscribe();
+
+        let count = self.subscribers.fetch_add(1, Ordering::Relaxed) + 1;
+        ApiMetrics::set_constraints_stream_subscribers(count);
+
+        ConstraintsSubscription { replay, events, subscribers: Arc::clone(&self.subscribers) }
+    }
+
+    /// Returns the number of active subscriptions.
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.load(Ordering::Relaxed)
+    }
+}
+
+/// A subscription to the [ConstraintsStream]. Dropping it unsubscribes.
+#[derive(Debug)]
+pub struct ConstraintsSubscription {
+    /// The constraints of the upcoming slots published before the subscription.
+    pub replay: Vec<SignedConstraints>,
+    events: broadcast::Receiver<SignedConstraints>,
+    subscribers: Arc<AtomicUsize>,
+}
+
+impl ConstraintsSubscription {
+    /// Wait for the next published constraints. Returns `None` if the stream is closed.
+    pub async fn recv(&mut self) -> Option<SignedConstraints> {
+        loop {
+            match self.events.recv().await {
+                Ok(signed_constraints) => return Some(signed_constraints),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "Constraints stream subscriber lagging behind, skipping events");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for ConstraintsSubscription {
+    fn drop(&mut self) {
+        let count = self.subscribers.fetch_sub(1, Ordering::Relaxed) - 1;
+        ApiMetrics::set_constraints_stream_subscribers(count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::bytes;
+
+    use crate::primitives::{ConstraintsMessage, FullTransaction};
+
+    use super::*;
+
+    fn signed_constraints(slot: u64) -> SignedConstraints {
+        let tx_bytes = bytes!("f8678085019dc6838082520894deaddeaddeaddeaddeaddeaddeaddeaddeaddead38808360306ca06664c078fa60bd3ece050903dd295949908dd9686ec8871fa558f868e031cd39a00ed4f0b122b32b73f19230fabe6a726e2d07f84eda5beaa42a1ae1271bdee39f").to_vec();
+        let tx = FullTransaction::decode_enveloped(tx_bytes.as_slice()).unwrap();
+
+        let message = ConstraintsMessage::from_tx(Default::default(), slot, tx);
+        SignedConstraints { message, signature: Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_constraints_stream() {
+        let stream = ConstraintsStream::default();
+        stream.publish(signed_constraints(10));
+        stream.publish(signed_constraints(11));
+
+        // The head reached slot 10: only the constraints of slot 11 are replayed
+        stream.prune(10);
+        let mut subscription = stream.subscribe();
+        assert_eq!(subscription.replay, vec![signed_constraints(11)]);
+        assert_eq!(stream.subscribers(), 1);
+
+        stream.publish(signed_constraints(12));
+        assert_eq!(subscription.recv().await, Some(signed_constraints(12)));
+
+        drop(subscription);
+        assert_eq!(stream.subscribers(), 0);
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    fmt,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::primitives::Slot;
+
+/// A policy deciding when commitment requests for a slot stop being accepted.
+pub trait DeadlinePolicy: fmt::Debug + Send + Sync {
+    /// Returns the instant at which commitments for the target slot stop being accepted,
+    /// given the latest head slot and the instant it was received.
+    fn deadline(&self, target_slot: Slot, head_slot: Slot, head_received_at: Instant) -> Instant;
+}
+
+/// The commitment deadline is a fixed duration after the head preceding the target slot.
+///
+/// NOTE: the deadline of slots after the next one is estimated from the slot time.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedDeadline {
+    /// The duration after the head at which the commitments for the next slot stop.
+    duration: Duration,
+    /// The duration of a slot.
+    slot_time: Duration,
+}
+
+impl FixedDeadline {
+    /// Create a new fixed deadline policy.
+    pub fn new(duration: Duration, slot_time: Duration) -> Self {
+        Self { duration, slot_time }
+    }
+}
+
+impl DeadlinePolicy for FixedDeadline {
+    fn deadline(&self, target_slot: Slot, head_slot: Slot, head_received_at: Instant) -> Instant {
+        let slots_ahead = target_slot.saturating_sub(head_slot + 1) as u32;
+        head_received_at + self.slot_time * slots_ahead + self.duration
+    }
+}
+
+/// The start instants of the slots, computed from the genesis time rather than from the
+/// instants the heads are received.
+#[derive(Debug, Clone, Copy)]
+pub struct SlotTiming {
+    /// A slot whose start instant is known, to compute the start of the other slots.
+    anchor_slot: Slot,
+    /// The start instant of the anchor slot.
+    anchor_start: Instant,
+    /// The duration of a slot.
+    slot_time: Duration,
+}
+
+impl SlotTiming {
+    /// Create the slot timing of a chain with the given genesis time, in seconds since the
+    /// UNIX epoch.
+    pub fn new(genesis_time: u64, slot_time: Duration) -> Self {
+        let since_genesis = SystemTime::now()
+            .duration_since(UNIX_EPOCH + Duration::from_secs(genesis_time))
+            .unwrap_or_default();
+        let anchor_slot = (since_genesis.as_millis() / slot_time.as_millis().max(1)) as Slot;
+        let into_slot = since_genesis.saturating_sub(slot_time * anchor_slot as u32);
+        let anchor_start = Instant::now().checked_sub(into_slot).unwrap_or_else(Instant::now);
+
+        Self::from_anchor(anchor_slot, anchor_start, slot_time)
+    }
+
+    /// Create the slot timing from the start instant of a known slot.
+    pub fn from_anchor(anchor_slot: Slot, anchor_start: Instant, slot_time: Duration) -> Self {
+        Self { anchor_slot, anchor_start, slot_time }
+    }
+
+    /// Returns the duration of a slot.
+    pub fn slot_time(&self) -> Duration {
+        self.slot_time
+    }
+
+    /// Returns the instant at which the given slot starts.
+    pub fn slot_start(&self, slot: Slot) -> Instant {
+        if slot >= self.anchor_slot {
+            self.anchor_start + self.slot_time * (slot - self.anchor_slot) as u32
+        } else {
+            let before = self.slot_time * (self.anchor_slot - slot) as u32;
+            self.anchor_start.checked_sub(before).unwrap_or(self.anchor_start)
+        }
+    }
+
+    /// Returns the slot in progress at the given instant.
+    pub fn slot_at(&self, instant: Instant) -> Slot {
+        let slot_ms = self.slot_time.as_millis().max(1);
+        match instant.checked_duration_since(self.anchor_start) {
+            Some(elapsed) => self.anchor_slot + (elapsed.as_millis() / slot_ms) as Slot,
+            None => {
+                let before = self.anchor_start.duration_since(instant).as_millis();
+                self.anchor_slot.saturating_sub(before.div_ceil(slot_ms) as Slot)
+            }
+        }
+    }
+}
+
+/// The commitment deadline is a safety margin before the start of the target slot, computed
+/// from the genesis time. Requests for slots further away are accepted for longer, and a late
+/// head doesn't push back the deadline of the next slot.
+#[derive(Debug, Clone, Copy)]
+pub struct DutyDistanceDeadline {
+    /// The start instants of the slots.
+    timing: SlotTiming,
+    /// The duration before the start of the target slot at which its commitments stop.
+    safety_margin: Duration,
+}
+
+impl DutyDistanceDeadline {
+    /// Create a new policy for a chain with the given genesis time, in seconds since the
+    /// UNIX epoch.
+    pub fn new(genesis_time: u64, slot_time: Duration, safety_margin: Duration) -> Self {
+        Self { timing: SlotTiming::new(genesis_time, slot_time), safety_margin }
+    }
+
+    /// Create a new policy from the start instant of a known slot.
+    pub fn from_anchor(
+        anchor_slot: Slot,
+        anchor_start: Instant,
+        slot_time: Duration,
+        safety_margin: Duration,
+    ) -> Self {
+        Self {
+            timing: SlotTiming::from_anchor(anchor_slot, anchor_start, slot_time),
+            safety_margin,
+        }
+    }
+}
+
+impl DeadlinePolicy for DutyDistanceDeadline {
+    fn deadline(&self, target_slot: Slot, _head_slot: Slot, _head_received_at: Instant) -> Instant {
+        let start = self.timing.slot_start(target_slot);
+        start.checked_sub(self.safety_margin).unwrap_or(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_policies() {
+        let slot_time = Duration::from_secs(12);
+        let head_slot = 100;
+        let head_start = Instant::now();
+
+        let fixed = FixedDeadline::new(Duration::from_secs(8), slot_time);
+        let duty = DutyDistanceDeadline::from_anchor(
+            head_slot,
+            head_start,
+            slot_time,
+            Duration::from_secs(2),
+        );
+
+        // Whether a request for the slot `ahead` slots after the head, received `offset` into
+        // the head slot, is accepted
+        let accepted = |policy: &dyn DeadlinePolicy, head_delay: u64, ahead: u64, offset: u64| {
+            let head_received_at = head_start + Duration::from_secs(head_delay);
+            let now = head_start + Duration::from_secs(offset);
+            now <= policy.deadline(head_slot + ahead, head_slot, head_received_at)
+        };
+
+        // The fixed deadline of the next slot is 8s after the head, and one slot later for
+        // every further slot
+        assert!(accepted(&fixed, 0, 1, 7));
+        assert!(!accepted(&fixed, 0, 1, 9));
+        assert!(accepted(&fixed, 0, 2, 19));
+        assert!(!accepted(&fixed, 0, 2, 21));
+        assert!(accepted(&fixed, 0, 5, 55));
+        assert!(!accepted(&fixed, 0, 5, 57));
+
+        // The duty distance deadline is 2s before the start of the target slot
+        assert!(accepted(&duty, 0, 1, 9));
+        assert!(!accepted(&duty, 0, 1, 11));
+        assert!(accepted(&duty, 0, 2, 21));
+        assert!(!accepted(&duty, 0, 2, 23));
+        assert!(accepted(&duty, 0, 5, 57));
+        assert!(!accepted(&duty, 0, 5, 59));
+
+        // A late head pushes back the fixed deadline, but not the duty distance one
+        assert!(accepted(&fixed, 4, 1, 11));
+        assert!(!accepted(&duty, 4, 1, 11));
+        assert!(accepted(&fixed, 4, 5, 59));
+        assert!(!accepted(&duty, 4, 5, 59));
+    }
+
+    #[test]
+    fn test_duty_distance_from_genesis() {
+        let slot_time = Duration::from_secs(12);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+        // The chain started 10 slots and 3 seconds ago
+        let genesis_time = now.as_secs() - 123;
+        let policy = DutyDistanceDeadline::new(genesis_time, slot_time, Duration::ZERO);
+        assert_eq!(policy.timing.anchor_slot, 10);
+        assert_eq!(policy.timing.slot_at(Instant::now()), 10);
+        assert_eq!(policy.timing.slot_at(Instant::now() + Duration::from_secs(10)), 11);
+        assert_eq!(policy.timing.slot_at(Instant::now() - Duration::from_secs(4)), 9);
+
+        let until_next_slot = policy.deadline(11, 10, Instant::now()) - Instant::now();
+        assert!(until_next_slot <= Duration::from_secs(9));
+        assert!(until_next_slot > Duration::from_secs(7));
+    }
+}
@@ -0,0 +1,276 @@
+use std::{collections::HashSet, fs, path::PathBuf, sync::Arc, time::Duration};
+
+use alloy::primitives::{Address, TxKind};
+use eyre::Context;
+use parking_lot::RwLock;
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{debug, error};
+
+use crate::{
+    config::denylist::DenylistOpts,
+    primitives::{FullTransaction, TransactionExt},
+};
+
+use super::ValidationError;
+
+/// A set of denylisted addresses, checked against the sender, the recipient and the access
+/// list of every transaction in a request.
+///
+/// The denylist is cheap to clone: every clone shares the same set, which is swapped
+/// atomically when the file is reloaded.
+#[derive(Debug, Clone)]
+pub struct Denylist {
+    path: Option<PathBuf>,
+    reject_contract_creation: bool,
+    addresses: Arc<RwLock<HashSet<Address>>>,
+    /// The number of reloads attempted by the background reloader.
+    reloads: Arc<watch::Sender<u64>>,
+}
+
+impl Default for Denylist {
+    fn default() -> Self {
+        Self {
+            path: None,
+            reject_contract_creation: false,
+            addresses: Arc::default(),
+            reloads: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl Denylist {
+    /// Create a new denylist with the given options, loading the denylist file if provided.
+    pub fn new(opts: &DenylistOpts) -> eyre::Result<Self> {
+        let denylist = Self {
+            path: opts.path.clone(),
+            reject_contract_creation: opts.reject_contract_creation,
+            ..Default::default()
+        };
+
+        denylist.reload()?;
+        Ok(denylist)
+    }
+
+    /// Reload the denylist file, returning the number of denylisted addresses.
+    ///
+    /// The new set replaces the previous one only if the whole file could be parsed.
+    pub fn reload(&self) -> eyre::Result<usize> {
+        let Some(ref path) = self.path else {
+            return Ok(0);
+        };
+
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read denylist file {}", path.display()))?;
+        let addresses = parse_denylist(&contents)?;
+        let count = addresses.len();
+
+        *self.addresses.write() = addresses;
+        Ok(count)
+    }
+
+    /// Spawn a background task reloading the denylist file every `interval`.
+    pub fn spawn_reloader(&self, interval: Duration) -> JoinHandle<()> {
+        let denylist = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            // The first tick completes immediately, and the file has just been loaded
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                match denylist.reload() {
+                    Ok(count) => debug!(count, "Reloaded denylist"),
+                    Err(err) => error!(?err, "Failed to reload denylist, keeping the previous one"),
+                }
+                denylist.reloads.send_modify(|reloads| *reloads += 1);
+            }
+        })
+    }
+
+    /// Subscribe to the reloads of the background reloader, successful or not, as the number
+    /// of reloads attempted so far.
+    pub fn subscribe_reloads(&self) -> watch::Receiver<u64> {
+        self.reloads.subscribe()
+    }
+
+    /// Returns true if the given address is denylisted.
+    pub fn contains(&self, address: &Address) -> bool {
+        self.addresses.read().contains(address)
+    }
+
+    /// Check the given transaction against the denylist. The sender of the transaction
+    /// must have been recovered.
+    pub fn check(&self, tx: &FullTransaction) -> Result<(), ValidationError> {
+        let to = match tx.tx_kind() {
+            TxKind::Call(to) => Some(to),
+            TxKind::Create if self.reject_contract_creation => {
+                return Err(ValidationError::ContractCreationNotAllowed)
+            }
+            TxKind::Create => None,
+        };
+
+        let addresses = self.addresses.read();
+        if addresses.is_empty() {
+            return Ok(());
+        }
+
+        let access_list = tx.access_list().into_iter().flat_map(|list| list.iter());
+        let denylisted = tx
+            .sender()
+            .into_iter()
+            .chain(to.as_ref())
+            .chain(access_list.map(|item| &item.address))
+            .find(|address| addresses.contains(*address));
+
+        match denylisted {
+            Some(address) => Err(ValidationError::Denylisted(*address)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse the contents of a denylist file: one address per line, ignoring empty lines
+/// and lines starting with `#`.
+fn parse_denylist(contents: &str) -> eyre::Result<HashSet<Address>> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            line.parse::<Address>()
+                .wrap_err_with(|| format!("Invalid address at line {}: {line}", index + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        eips::eip2930::AccessListItem,
+        network::TransactionBuilder,
+        rpc::types::TransactionRequest,
+        signers::{k256::SecretKey, local::PrivateKeySigner},
+    };
+
+    use crate::test_util::{create_signed_inclusion_request, default_test_transaction};
+
+    use super::*;
+
+    fn load_denylist(path: &PathBuf, reject_contract_creation: bool) -> Denylist {
+        let opts = DenylistOpts {
+            path: Some(path.clone()),
+            reload_interval_secs: None,
+            reject_contract_creation,
+        };
+
+        Denylist::new(&opts).unwrap()
+    }
+
+    /// Returns the address of the account signing the test transactions.
+    fn sender() -> Address {
+        PrivateKeySigner::from_slice(&[0x42; 32]).unwrap().address()
+    }
+
+    /// Sign the given transaction from the [sender] account and recover its signer.
+    async fn signed_tx(tx: TransactionRequest) -> FullTransaction {
+        let sk = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let request = create_signed_inclusion_request(&[tx], &sk, 10).await.unwrap();
+        request.txs[0].clone()
+    }
+
+    /// Wait for the next reload of the denylist. The reloader runs on the thread of the test,
+    /// so the next reload reads the file as written before this call.
+    async fn next_reload(reloads: &mut watch::Receiver<u64>) -> eyre::Result<()> {
+        reloads.borrow_and_update();
+        tokio::time::timeout(Duration::from_secs(5), reloads.changed()).await??;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_denylist() {
+        let contents = "# OFAC\n\n0x1111111111111111111111111111111111111111\n  0x2222222222222222222222222222222222222222  \n";
+        let addresses = parse_denylist(contents).unwrap();
+        assert_eq!(addresses.len(), 2);
+        assert!(addresses.contains(&Address::repeat_byte(0x22)));
+
+        assert!(parse_denylist("not an address").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_denylist_match_positions() -> eyre::Result<()> {
+        let sender = sender();
+        let recipient = Address::repeat_byte(0x11);
+        let accessed = Address::repeat_byte(0x22);
+
+        let tx = default_test_transaction(sender, None).with_to(recipient).with_access_list(
+            vec![AccessListItem { address: accessed, storage_keys: vec![] }].into(),
+        );
+        let tx = signed_tx(tx).await;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("denylist.txt");
+        for denylisted in [sender, recipient, accessed] {
+            fs::write(&path, format!("{denylisted}\n"))?;
+
+            let denylist = load_denylist(&path, false);
+            match denylist.check(&tx) {
+                Err(ValidationError::Denylisted(address)) => assert_eq!(address, denylisted),
+                res => panic!("expected {denylisted} to be denylisted, got {res:?}"),
+            }
+        }
+
+        // Other addresses are fine
+        fs::write(&path, format!("{}\n", Address::repeat_byte(0x33)))?;
+        assert!(load_denylist(&path, false).check(&tx).is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_contract_creation() -> eyre::Result<()> {
+        let tx = default_test_transaction(sender(), None)
+            .with_kind(TxKind::Create)
+            .with_gas_limit(100_000);
+        let tx = signed_tx(tx).await;
+
+        let allowed = Denylist::default();
+        assert!(allowed.check(&tx).is_ok());
+
+        let opts = DenylistOpts { reject_contract_creation: true, ..Default::default() };
+        let rejected = Denylist::new(&opts)?;
+        assert!(matches!(rejected.check(&tx), Err(ValidationError::ContractCreationNotAllowed)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reload_picks_up_additions() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("denylist.txt");
+        fs::write(&path, "# empty\n")?;
+
+        let denylist = load_denylist(&path, false);
+        let clone = denylist.clone();
+        let mut reloads = denylist.subscribe_reloads();
+        let _reloader = denylist.spawn_reloader(Duration::from_millis(50));
+
+        let address = Address::repeat_byte(0x44);
+        assert!(!clone.contains(&address));
+
+        fs::write(&path, format!("# empty\n{address}\n"))?;
+        next_reload(&mut reloads).await?;
+        assert!(clone.contains(&address));
+
+        // An invalid file keeps the previous denylist
+        fs::write(&path, "garbage\n")?;
+        next_reload(&mut reloads).await?;
+        assert!(clone.contains(&address));
+
+        Ok(())
+    }
+}
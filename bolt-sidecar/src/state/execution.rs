@@ -1,23 +1,58 @@
 use alloy::{
     consensus::BlobTransactionValidationError,
-    eips::eip4844::MAX_BLOBS_PER_BLOCK,
-    primitives::{Address, U256},
+    eips::{eip2718::Encodable2718, eip4844::MAX_BLOBS_PER_BLOCK, BlockNumberOrTag},
+    network::TransactionBuilder,
+    primitives::{Address, TxHash, U256},
+    rpc::types::{Transaction, TransactionRequest},
     transports::TransportError,
 };
-use reth_primitives::{revm_primitives::EnvKzgSettings, PooledTransactionsElement};
-use std::{collections::HashMap, ops::Deref};
+use ethereum_consensus::phase0::mainnet::SLOTS_PER_EPOCH;
+use reth_primitives::{
+    revm_primitives::{EnvKzgSettings, SpecId},
+    PooledTransactionsElement,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Deref,
+    sync::Arc,
+    time::Duration,
+};
 use thiserror::Error;
+use tokio::{
+    sync::{Mutex, MutexGuard},
+    time::Instant,
+};
 use tracing::{debug, trace, warn};
 
 use crate::{
     builder::BlockTemplate,
     common::{calculate_max_basefee, max_transaction_cost, validate_transaction},
-    config::limits::LimitsOpts,
-    primitives::{AccountState, InclusionRequest, SignedConstraints, Slot},
-    telemetry::ApiMetrics,
+    config::{
+        chain::ChainConfig,
+        limits::LimitsOpts,
+        simulation::{SimulationFallback, SimulationOpts},
+    },
+    primitives::{
+        commitment::{SlotCapacity, ValidationVerdict},
+        intrinsic_gas, AccountState, BatchedSignedConstraints, FullTransaction, InclusionRequest,
+        SignedConstraints, Slot, TransactionExt,
+    },
+    telemetry::{ApiMetrics, ExecutionMemoryStats, SlotCommitmentStats},
+};
+
+use super::{
+    constraints_stream::ConstraintsStream,
+    denylist::Denylist,
+    fetcher::StateFetcher,
+    limits::{LimitsUpdate, SharedLimits},
+    recent_inclusions::RecentInclusions,
 };
 
-use super::fetcher::StateFetcher;
+/// The interval at which the head of the execution client is polled while waiting for the block
+/// of a new beacon head.
+const HEAD_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Possible commitment validation errors.
 ///
@@ -25,11 +60,21 @@ use super::fetcher::StateFetcher;
 #[derive(Debug, Error)]
 pub enum ValidationError {
     /// The transaction fee is too low to cover the maximum base fee.
-    #[error("Transaction fee is too low, need {0} gwei to cover the maximum basefee")]
-    BaseFeeTooLow(u128),
+    #[error("Transaction fee too low: need {projected_basefee} wei, got {max_fee}")]
+    BaseFeeTooLow {
+        /// The maximum basefee projected for the target slot, in wei.
+        projected_basefee: u128,
+        /// The lowest max fee per gas of the transactions, in wei.
+        max_fee: u128,
+    },
     /// The transaction blob fee is too low to cover the maximum blob base fee.
-    #[error("Transaction blob fee is too low, need {0} gwei to cover the maximum blob basefee")]
-    BlobBaseFeeTooLow(u128),
+    #[error("Blob fee too low: need {projected_blob_basefee} wei, got {max_fee_per_blob_gas}")]
+    BlobFeeTooLow {
+        /// The maximum blob basefee projected for the target slot, in wei.
+        projected_blob_basefee: u128,
+        /// The max fee per blob gas of the transaction, in wei.
+        max_fee_per_blob_gas: u128,
+    },
     /// The transaction blob is invalid.
     #[error(transparent)]
     BlobValidation(#[from] BlobTransactionValidationError),
@@ -37,11 +82,21 @@ pub enum ValidationError {
     #[error("Invalid max basefee calculation: overflow")]
     MaxBaseFeeCalcOverflow,
     /// The transaction nonce is too low.
-    #[error("Transaction nonce too low. Expected {0}, got {1}")]
-    NonceTooLow(u64, u64),
+    #[error("Transaction nonce too low. Expected {expected}, got {got}")]
+    NonceTooLow {
+        /// The nonce expected for the sender, including its pending commitments.
+        expected: u64,
+        /// The nonce of the transaction.
+        got: u64,
+    },
     /// The transaction nonce is too high.
-    #[error("Transaction nonce too high. Expected {0}, got {1}")]
-    NonceTooHigh(u64, u64),
+    #[error("Transaction nonce too high. Expected {expected}, got {got}")]
+    NonceTooHigh {
+        /// The nonce expected for the sender, including its pending commitments.
+        expected: u64,
+        /// The nonce of the transaction.
+        got: u64,
+    },
     /// The sender account is a smart contract and has code.
     #[error("Account has code")]
     AccountHasCode,
@@ -51,6 +106,28 @@ pub enum ValidationError {
     /// The transaction input size is too high.
     #[error("Transaction input size too high")]
     TransactionSizeTooHigh,
+    /// The encoded transaction is larger than the maximum size.
+    #[error("Transaction size {0} bytes exceeds the maximum of {1} bytes")]
+    TransactionTooLarge(usize, usize),
+    /// The gas limit of a transaction is higher than the maximum per transaction.
+    #[error("Transaction gas limit {gas_limit} exceeds the maximum of {max} per transaction")]
+    TxGasLimitTooHigh {
+        /// The gas limit of the transaction.
+        gas_limit: u64,
+        /// The maximum gas limit per transaction.
+        max: u64,
+    },
+    /// The calldata of a transaction is larger than the maximum per transaction.
+    #[error("Transaction calldata of {size} bytes exceeds the maximum of {max} bytes")]
+    CalldataTooLarge {
+        /// The size of the calldata of the transaction, in bytes.
+        size: usize,
+        /// The maximum size of the calldata per transaction, in bytes.
+        max: usize,
+    },
+    /// The gas limit doesn't cover the intrinsic gas of the transaction.
+    #[error("Gas limit too low: intrinsic gas is {0}, got {1}")]
+    GasLimitTooLow(u64, u64),
     /// Max priority fee per gas is greater than max fee per gas.
     #[error("Max priority fee per gas is greater than max fee per gas")]
     MaxPriorityFeePerGasTooHigh,
@@ -58,20 +135,57 @@ pub enum ValidationError {
     #[error("Max priority fee per gas is less than min priority fee")]
     MaxPriorityFeePerGasTooLow,
     /// The sender does not have enough balance to pay for the transaction.
-    #[error("Not enough balance to pay for value + maximum fee")]
-    InsufficientBalance,
+    #[error("Not enough balance for value + maximum fee: need {required} wei, got {available}")]
+    InsufficientBalance {
+        /// The maximum cost of the transaction, in wei.
+        required: U256,
+        /// The balance of the sender, including its pending commitments, in wei.
+        available: U256,
+    },
     /// There are too many EIP-4844 transactions in the target block.
     #[error("Too many EIP-4844 transactions in target block")]
     Eip4844Limit,
     /// The maximum commitments have been reached for the slot.
     #[error("Already requested a preconfirmation for slot {0}. Slot must be >= {0}")]
     SlotTooLow(u64),
+    /// The target slot is further ahead of the head than the lookahead window.
+    #[error("Slot {slot} is beyond the lookahead window, must be at most {max}")]
+    SlotTooHigh {
+        /// The target slot of the request.
+        slot: u64,
+        /// The furthest slot accepted for commitments.
+        max: u64,
+    },
     /// The maximum commitments have been reached for the slot.
     #[error("Max commitments reached for slot {0}: {1}")]
     MaxCommitmentsReachedForSlot(u64, usize),
     /// The maximum committed gas has been reached for the slot.
     #[error("Max committed gas reached for slot {0}: {1}")]
     MaxCommittedGasReachedForSlot(u64, u64),
+    /// The request has more transactions than the constraints left for the slot.
+    #[error("Slot {slot} is full: room for {remaining} more constrained transactions")]
+    SlotFull {
+        /// The target slot of the request.
+        slot: u64,
+        /// The number of transactions that can still be constrained in the slot.
+        remaining: usize,
+    },
+    /// The top-of-block request doesn't fit in the top-of-block budget left for the slot.
+    #[error(
+        "Top of block of slot {slot} is full: room for {remaining_txs} more transactions and {remaining_gas} gas"
+    )]
+    TopOfBlockFull {
+        /// The target slot of the request.
+        slot: u64,
+        /// The number of transactions that can still be placed at the top of the block.
+        remaining_txs: usize,
+        /// The gas that can still be committed at the top of the block.
+        remaining_gas: u64,
+    },
+    /// A sender of the top-of-block request already has transactions committed below the top
+    /// of the block, which would end up after its new ones.
+    #[error("Sender {0} already has transactions committed below the top of the block")]
+    TopOfBlockSenderConflict(Address),
     /// The signature is invalid.
     #[error("Invalid signature")]
     Signature(#[from] crate::primitives::commitment::SignatureError),
@@ -79,8 +193,62 @@ pub enum ValidationError {
     #[error("Could not recover signer")]
     RecoverSigner,
     /// The transaction chain ID does not match the expected chain ID.
-    #[error("Chain ID mismatch")]
-    ChainIdMismatch,
+    #[error("Chain ID mismatch: expected {expected}, got {got}")]
+    ChainIdMismatch {
+        /// The chain ID of the configured chain.
+        expected: u64,
+        /// The chain ID of the transaction.
+        got: u64,
+    },
+    /// The transaction doesn't have a chain ID, and replay protection is required.
+    #[error("Transaction is not replay protected: missing chain ID")]
+    MissingChainId,
+    /// The total value of the request exceeds the allowed multiple of the operator collateral.
+    #[error("Request value {0} exceeds the maximum of {1} allowed by the operator collateral")]
+    InsufficientCollateral(U256, U256),
+    /// The operator collateral is unknown or hasn't been refreshed recently enough.
+    #[error("Operator collateral is unavailable or stale")]
+    StaleCollateral,
+    /// The operator is not registered in Bolt, or has paused its activity.
+    #[error("Operator is not registered or not enabled in Bolt")]
+    OperatorInactive,
+    /// The operator collateral is below the minimum required by the protocol.
+    #[error("Operator collateral {0} is below the minimum of {1} required by the protocol")]
+    BelowMinimumCollateral(U256, U256),
+    /// The transaction interacts with a denylisted address.
+    #[error("Address {0} is denylisted")]
+    Denylisted(Address),
+    /// The transaction is already included in one of the latest blocks.
+    #[error("Transaction {hash} is already included in block {block_number}")]
+    AlreadyIncluded {
+        /// The hash of the transaction.
+        hash: TxHash,
+        /// The number of the block that includes it.
+        block_number: u64,
+    },
+    /// The transaction is already committed for an upcoming slot.
+    #[error("Transaction {hash} is already committed for slot {slot}")]
+    AlreadyCommitted {
+        /// The hash of the transaction.
+        hash: TxHash,
+        /// The slot it's committed for.
+        slot: u64,
+    },
+    /// The transaction is a contract creation, which is not allowed.
+    #[error("Contract creation transactions are not allowed")]
+    ContractCreationNotAllowed,
+    /// The transaction simulation reverted or ran out of gas.
+    #[error("Transaction simulation failed: {0}")]
+    SimulationFailed(String),
+    /// The transaction simulation timed out or couldn't be served by the execution client.
+    #[error("Transaction simulation unavailable: {0}")]
+    SimulationUnavailable(String),
+    /// Some of the transactions referenced by hash aren't in the mempool.
+    #[error("Transactions not found in the mempool: {0:?}")]
+    TransactionsNotFound(Vec<TxHash>),
+    /// A blob transaction is referenced by hash, but its sidecar can't be fetched.
+    #[error("Blob sidecar of transaction {0} is unavailable, send the full transaction")]
+    BlobSidecarUnavailable(TxHash),
     /// NOTE: this should not be exposed to the user.
     #[error("Internal error: {0}")]
     Internal(String),
@@ -95,32 +263,95 @@ impl ValidationError {
     /// Returns the tag of the enum as a string, mainly for metrics purposes
     pub const fn to_tag_str(&self) -> &'static str {
         match self {
-            ValidationError::BaseFeeTooLow(_) => "base_fee_too_low",
-            ValidationError::BlobBaseFeeTooLow(_) => "blob_base_fee_too_low",
+            ValidationError::BaseFeeTooLow { .. } => "base_fee_too_low",
+            ValidationError::BlobFeeTooLow { .. } => "blob_base_fee_too_low",
             ValidationError::BlobValidation(_) => "blob_validation",
             ValidationError::MaxBaseFeeCalcOverflow => "max_base_fee_calc_overflow",
-            ValidationError::NonceTooLow(_, _) => "nonce_too_low",
-            ValidationError::NonceTooHigh(_, _) => "nonce_too_high",
+            ValidationError::NonceTooLow { .. } => "nonce_too_low",
+            ValidationError::NonceTooHigh { .. } => "nonce_too_high",
             ValidationError::AccountHasCode => "account_has_code",
             ValidationError::GasLimitTooHigh => "gas_limit_too_high",
             ValidationError::TransactionSizeTooHigh => "transaction_size_too_high",
+            ValidationError::TransactionTooLarge(_, _) => "transaction_too_large",
+            ValidationError::TxGasLimitTooHigh { .. } => "tx_gas_limit_too_high",
+            ValidationError::CalldataTooLarge { .. } => "calldata_too_large",
+            ValidationError::GasLimitTooLow(_, _) => "gas_limit_too_low",
             ValidationError::MaxPriorityFeePerGasTooHigh => "max_priority_fee_per_gas_too_high",
             ValidationError::MaxPriorityFeePerGasTooLow => "max_priority_fee_per_gas_too_low",
-            ValidationError::InsufficientBalance => "insufficient_balance",
+            ValidationError::InsufficientBalance { .. } => "insufficient_balance",
             ValidationError::Eip4844Limit => "eip4844_limit",
             ValidationError::SlotTooLow(_) => "slot_too_low",
+            ValidationError::SlotTooHigh { .. } => "slot_too_high",
             ValidationError::MaxCommitmentsReachedForSlot(_, _) => {
                 "max_commitments_reached_for_slot"
             }
             ValidationError::MaxCommittedGasReachedForSlot(_, _) => {
                 "max_committed_gas_reached_for_slot"
             }
+            ValidationError::SlotFull { .. } => "slot_full",
+            ValidationError::TopOfBlockFull { .. } => "top_of_block_full",
+            ValidationError::TopOfBlockSenderConflict(_) => "top_of_block_sender_conflict",
             ValidationError::Signature(_) => "signature",
             ValidationError::RecoverSigner => "recover_signer",
-            ValidationError::ChainIdMismatch => "chain_id_mismatch",
+            ValidationError::ChainIdMismatch { .. } => "chain_id_mismatch",
+            ValidationError::MissingChainId => "missing_chain_id",
+            ValidationError::InsufficientCollateral(_, _) => "insufficient_collateral",
+            ValidationError::StaleCollateral => "stale_collateral",
+            ValidationError::OperatorInactive => "operator_inactive",
+            ValidationError::BelowMinimumCollateral(_, _) => "below_minimum_collateral",
+            ValidationError::Denylisted(_) => "denylisted",
+            ValidationError::AlreadyIncluded { .. } => "already_included",
+            ValidationError::AlreadyCommitted { .. } => "already_committed",
+            ValidationError::ContractCreationNotAllowed => "contract_creation_not_allowed",
+            ValidationError::SimulationFailed(_) => "simulation_failed",
+            ValidationError::SimulationUnavailable(_) => "simulation_unavailable",
+            ValidationError::TransactionsNotFound(_) => "transactions_not_found",
+            ValidationError::BlobSidecarUnavailable(_) => "blob_sidecar_unavailable",
             ValidationError::Internal(_) => "internal",
         }
     }
+
+    /// Returns the structured context of the error about the account state or the fees, if
+    /// any, so that clients can correct their requests. Amounts are decimal strings in wei.
+    pub fn data(&self) -> Option<Value> {
+        let mut data = match self {
+            ValidationError::NonceTooLow { expected, got } |
+            ValidationError::NonceTooHigh { expected, got } => {
+                json!({ "expected": expected, "got": got })
+            }
+            ValidationError::InsufficientBalance { required, available } => {
+                json!({ "required": required.to_string(), "available": available.to_string() })
+            }
+            ValidationError::BaseFeeTooLow { projected_basefee, max_fee } => {
+                json!({
+                    "projected_basefee": projected_basefee.to_string(),
+                    "max_fee": max_fee.to_string(),
+                })
+            }
+            ValidationError::BlobFeeTooLow { projected_blob_basefee, max_fee_per_blob_gas } => {
+                json!({
+                    "projected_blob_basefee": projected_blob_basefee.to_string(),
+                    "max_fee_per_blob_gas": max_fee_per_blob_gas.to_string(),
+                })
+            }
+            ValidationError::TxGasLimitTooHigh { gas_limit, max } => {
+                json!({ "gas_limit": gas_limit, "max": max })
+            }
+            ValidationError::CalldataTooLarge { size, max } => json!({ "size": size, "max": max }),
+            ValidationError::SlotTooHigh { slot, max } => json!({ "slot": slot, "max": max }),
+            ValidationError::TransactionsNotFound(missing) => json!({ "missing": missing }),
+            ValidationError::AlreadyIncluded { hash, block_number } => {
+                json!({ "hash": hash, "block_number": block_number })
+            }
+            ValidationError::AlreadyCommitted { hash, slot } => {
+                json!({ "hash": hash, "slot": slot })
+            }
+            _ => return None,
+        };
+
+        data["reason"] = Value::from(self.to_tag_str());
+        Some(data)
+    }
 }
 
 /// The minimal state of the execution layer at some block number (`head`).
@@ -153,12 +384,36 @@ pub struct ExecutionState<C> {
     block_templates: HashMap<Slot, BlockTemplate>,
     /// The chain ID of the chain (constant).
     chain_id: u64,
-    /// The limits set for the sidecar.
-    limits: LimitsOpts,
+    /// Whether transactions without a chain ID are rejected.
+    require_replay_protection: bool,
+    /// The limits set for the sidecar, which can be updated at runtime.
+    limits: SharedLimits,
+    /// The gas limits registered by the proposers of the upcoming slots, by target slot. The
+    /// gas committed in a slot never exceeds the gas limit of its proposer.
+    registered_gas_limits: HashMap<Slot, u64>,
+    /// The transaction simulation options.
+    simulation: SimulationOpts,
+    /// The denylisted addresses.
+    denylist: Denylist,
+    /// Whether the constraints at risk of being invalidated by their senders are dropped from
+    /// the templates of the slots that aren't sealed yet.
+    drop_at_risk_constraints: bool,
+    /// The stream the accepted constraints are published on, for builders.
+    constraints_stream: ConstraintsStream,
+    /// The senders with constraints at risk of being invalidated, by target slot, as of the
+    /// latest head.
+    at_risk: HashMap<Slot, HashSet<Address>>,
+    /// The latest slot whose commitment deadline passed, with its constraints submitted.
+    sealed_slot: Slot,
+    /// The number of slots after the head accepted for commitments, which bounds the block
+    /// templates held at any time.
+    lookahead_slots: u64,
     /// The KZG settings for validating blobs.
     kzg_settings: EnvKzgSettings,
-    /// The state fetcher client.
-    client: C,
+    /// The transactions included in the latest blocks, to reject requests for them.
+    recent_inclusions: RecentInclusions,
+    /// The state fetcher client, shared with the requests validated concurrently.
+    client: Arc<C>,
     /// Other values used for validation
     validation_params: ValidationParams,
 }
@@ -167,16 +422,17 @@ pub struct ExecutionState<C> {
 #[derive(Debug)]
 pub struct ValidationParams {
     pub block_gas_limit: u64,
-    pub max_tx_input_bytes: usize,
     pub max_init_code_byte_size: usize,
+    /// The fork used to compute the intrinsic gas of transactions.
+    pub spec_id: SpecId,
 }
 
 impl Default for ValidationParams {
     fn default() -> Self {
         Self {
             block_gas_limit: 30_000_000,
-            max_tx_input_bytes: 4 * 32 * 1024,
             max_init_code_byte_size: 2 * 24576,
+            spec_id: SpecId::CANCUN,
         }
     }
 }
@@ -197,11 +453,21 @@ impl<C: StateFetcher> ExecutionState<C> {
             blob_basefee,
             block_number,
             chain_id,
-            limits,
-            client,
+            require_replay_protection: false,
+            limits: SharedLimits::new(limits),
+            registered_gas_limits: HashMap::new(),
+            client: Arc::new(client),
+            simulation: SimulationOpts::default(),
+            denylist: Denylist::default(),
+            drop_at_risk_constraints: false,
+            constraints_stream: ConstraintsStream::default(),
+            at_risk: HashMap::new(),
+            sealed_slot: 0,
+            lookahead_slots: 2 * SLOTS_PER_EPOCH,
             slot: 0,
             account_states: HashMap::new(),
             block_templates: HashMap::new(),
+            recent_inclusions: RecentInclusions::default(),
             // Load the default KZG settings
             kzg_settings: EnvKzgSettings::default(),
             // TODO: add a way to configure these values from CLI
@@ -209,6 +475,78 @@ impl<C: StateFetcher> ExecutionState<C> {
         })
     }
 
+    /// Simulate the transactions of every request before committing to them, according
+    /// to the given options.
+    pub fn with_simulation(mut self, simulation: SimulationOpts) -> Self {
+        self.simulation = simulation;
+        self
+    }
+
+    /// Validate the chain ID of transactions against the given chain configuration, instead
+    /// of the chain ID reported by the execution client.
+    pub fn with_chain(mut self, chain: ChainConfig) -> Self {
+        if chain.chain_id() != self.chain_id {
+            warn!(
+                configured = chain.chain_id(),
+                execution_client = self.chain_id,
+                "Configured chain ID doesn't match the execution client"
+            );
+        }
+
+        self.chain_id = chain.chain_id();
+        self.require_replay_protection = chain.require_replay_protection;
+        self
+    }
+
+    /// Reject requests with transactions interacting with the addresses of the given denylist.
+    pub fn with_denylist(mut self, denylist: Denylist) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Drop the constraints at risk of being invalidated by their senders from the templates,
+    /// as long as their commitment deadline hasn't passed.
+    pub fn with_drop_at_risk_constraints(mut self, drop: bool) -> Self {
+        self.drop_at_risk_constraints = drop;
+        self
+    }
+
+    /// Only accept commitments for the given number of slots after the head, i.e. the
+    /// lookahead window of the proposer duties.
+    pub fn with_lookahead_slots(mut self, lookahead_slots: u64) -> Self {
+        self.lookahead_slots = lookahead_slots;
+        self
+    }
+
+    /// Publish the accepted constraints on the given stream.
+    pub fn with_constraints_stream(mut self, constraints_stream: ConstraintsStream) -> Self {
+        self.constraints_stream = constraints_stream;
+        self
+    }
+
+    /// Returns a shared handle to the operating limits, to update them at runtime.
+    pub fn limits(&self) -> SharedLimits {
+        self.limits.clone()
+    }
+
+    /// Record the gas limit registered by the proposer of the given slot, which caps the gas
+    /// committed in it if lower than the maximum committed gas per slot.
+    pub fn set_registered_gas_limit(&mut self, slot: Slot, gas_limit: u64) {
+        self.registered_gas_limits.insert(slot, gas_limit);
+    }
+
+    /// Returns the gas limit registered by the proposer of the given slot, if known.
+    pub fn registered_gas_limit(&self, slot: Slot) -> Option<u64> {
+        self.registered_gas_limits.get(&slot).copied()
+    }
+
+    /// Returns the maximum gas that can be committed in the given slot: the maximum committed
+    /// gas per slot, tightened to the gas limit registered by its proposer if lower.
+    fn max_committed_gas(&self, slot: Slot, limits: &LimitsOpts) -> u64 {
+        let max_committed_gas = limits.max_committed_gas_per_slot.get();
+        self.registered_gas_limit(slot).map_or(max_committed_gas, |gas| gas.min(max_committed_gas))
+    }
+
     /// Returns the current base fee in gwei
     pub fn basefee(&self) -> u128 {
         self.basefee
@@ -229,40 +567,131 @@ impl<C: StateFetcher> ExecutionState<C> {
         &mut self,
         req: &mut InclusionRequest,
     ) -> Result<(), ValidationError> {
+        self.check_request(req).await?;
+
+        if self.simulation.enabled {
+            let committed = self.committed_transactions_until(req.slot);
+            simulate_transactions(self.client.as_ref(), self.simulation, &committed, &req.txs)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the verdict of a request validated with [ExecutionState::validate_request]: the
+    /// capacity left in its target slot if it were committed, and the fees it's validated
+    /// against. Nothing is reserved for the request.
+    pub fn verdict(&self, req: &InclusionRequest) -> Result<ValidationVerdict, ValidationError> {
+        let blobs = req
+            .txs
+            .iter()
+            .filter_map(|tx| tx.as_eip4844())
+            .map(|tx| tx.blob_versioned_hashes.len())
+            .sum::<usize>();
+        let capacity = self.remaining_capacity(req.slot, req.gas_limit(), blobs, req.txs.len())?;
+
+        Ok(ValidationVerdict {
+            ok: true,
+            slot: req.slot,
+            remaining_gas: capacity.remaining_gas,
+            remaining_blobs: capacity.remaining_blobs,
+            remaining_constraints: capacity.remaining_constraints,
+            projected_basefee: capacity.projected_basefee,
+            min_priority_fee: self.limits.get().min_priority_fee,
+        })
+    }
+
+    /// Returns the capacity left in the given slot with the requests committed so far, and the
+    /// base fee projected for it.
+    pub fn slot_capacity(&self, slot: Slot) -> Result<SlotCapacity, ValidationError> {
+        self.remaining_capacity(slot, 0, 0, 0)
+    }
+
+    /// Returns the capacity left in the given slot once the given gas, blobs and constraints
+    /// are added to the committed ones, and the base fee projected for it.
+    fn remaining_capacity(
+        &self,
+        slot: Slot,
+        gas: u64,
+        blobs: usize,
+        constraints: usize,
+    ) -> Result<SlotCapacity, ValidationError> {
+        let limits = self.limits.get();
+        let template = self.block_templates.get(&slot);
+
+        let committed_gas = template.map(|t| t.committed_gas()).unwrap_or(0) + gas;
+        let constraints = template.map(|t| t.transactions_len()).unwrap_or(0) + constraints;
+        let blobs = template.map(|t| t.blob_count()).unwrap_or(0) + blobs;
+
+        let slot_diff = slot.saturating_sub(self.slot);
+        let projected_basefee = calculate_max_basefee(self.basefee, slot_diff)
+            .ok_or(ValidationError::MaxBaseFeeCalcOverflow)?;
+
+        Ok(SlotCapacity {
+            remaining_gas: self.max_committed_gas(slot, &limits).saturating_sub(committed_gas),
+            remaining_blobs: MAX_BLOBS_PER_BLOCK.saturating_sub(blobs),
+            remaining_constraints: limits
+                .max_constraints_per_slot
+                .get()
+                .saturating_sub(constraints),
+            projected_basefee,
+        })
+    }
+
+    /// Validates the commitment request against the cached state, without simulating its
+    /// transactions.
+    ///
+    /// Only the account states missing from the cache are fetched from the client.
+    async fn check_request(&mut self, req: &mut InclusionRequest) -> Result<(), ValidationError> {
         req.recover_signers()?;
 
+        // Take a snapshot of the limits, which can be updated at runtime
+        let limits = self.limits.get();
+
         let target_slot = req.slot;
 
-        // Validate the chain ID
-        if !req.validate_chain_id(self.chain_id) {
-            return Err(ValidationError::ChainIdMismatch);
+        // Validate the chain ID of every transaction. Pre-EIP155 transactions don't have one.
+        for tx in req.txs.iter() {
+            match tx.chain_id() {
+                Some(got) if got != self.chain_id => {
+                    return Err(ValidationError::ChainIdMismatch { expected: self.chain_id, got })
+                }
+                None if self.require_replay_protection => {
+                    return Err(ValidationError::MissingChainId)
+                }
+                _ => {}
+            }
         }
 
-        // Check if there is room for more commitments
-        if let Some(template) = self.get_block_template(target_slot) {
-            if template.transactions_len() >= self.limits.max_commitments_per_slot.get() {
-                return Err(ValidationError::MaxCommitmentsReachedForSlot(
-                    self.slot,
-                    self.limits.max_commitments_per_slot.get(),
-                ));
+        self.check_duplicates(req)?;
+
+        self.check_slot_capacity(req, &limits)?;
+
+        // Check the size, the gas limit and the intrinsic gas of every transaction
+        for tx in req.txs.iter() {
+            let size = tx.size();
+            if size > limits.max_tx_size.get() {
+                return Err(ValidationError::TransactionTooLarge(size, limits.max_tx_size.get()));
             }
-        }
 
-        // Check if the committed gas exceeds the maximum
-        let template_committed_gas =
-            self.get_block_template(target_slot).map(|t| t.committed_gas()).unwrap_or(0);
+            if let Some(max) = limits.max_gas_per_tx {
+                if tx.gas_limit() > max.get() {
+                    let gas_limit = tx.gas_limit();
+                    return Err(ValidationError::TxGasLimitTooHigh { gas_limit, max: max.get() });
+                }
+            }
 
-        if template_committed_gas + req.gas_limit() >= self.limits.max_committed_gas_per_slot.get()
-        {
-            return Err(ValidationError::MaxCommittedGasReachedForSlot(
-                self.slot,
-                self.limits.max_committed_gas_per_slot.get(),
-            ));
-        }
+            if let Some(max) = limits.max_calldata_bytes {
+                let size = tx.input().len();
+                if size > max.get() {
+                    return Err(ValidationError::CalldataTooLarge { size, max: max.get() });
+                }
+            }
 
-        // Check if the transaction size exceeds the maximum
-        if !req.validate_tx_size_limit(self.validation_params.max_tx_input_bytes) {
-            return Err(ValidationError::TransactionSizeTooHigh);
+            let intrinsic_gas = intrinsic_gas(&tx.tx, self.validation_params.spec_id);
+            if tx.gas_limit() < intrinsic_gas {
+                return Err(ValidationError::GasLimitTooLow(intrinsic_gas, tx.gas_limit()));
+            }
         }
 
         // Check if the transaction is a contract creation and the init code size exceeds the
@@ -292,11 +721,12 @@ impl<C: StateFetcher> ExecutionState<C> {
 
         // Validate the base fee
         if !req.validate_basefee(max_basefee) {
-            return Err(ValidationError::BaseFeeTooLow(max_basefee));
+            let max_fee = req.txs.iter().map(|tx| tx.max_fee_per_gas()).min().unwrap_or_default();
+            return Err(ValidationError::BaseFeeTooLow { projected_basefee: max_basefee, max_fee });
         }
 
         // Ensure max_priority_fee_per_gas is greater than or equal to min_priority_fee
-        if !req.validate_min_priority_fee(max_basefee, self.limits.min_priority_fee) {
+        if !req.validate_min_priority_fee(max_basefee, limits.min_priority_fee) {
             return Err(ValidationError::MaxPriorityFeePerGasTooLow);
         }
 
@@ -305,6 +735,35 @@ impl<C: StateFetcher> ExecutionState<C> {
             return Err(ValidationError::SlotTooLow(self.slot));
         }
 
+        let max_slot = self.slot + self.lookahead_slots - 1;
+        if target_slot > max_slot {
+            debug!(%target_slot, %max_slot, "Target slot beyond the lookahead window");
+            return Err(ValidationError::SlotTooHigh { slot: target_slot, max: max_slot });
+        }
+
+        // Reject transactions interacting with denylisted addresses, and fetch the account
+        // states of the senders that aren't cached yet with a single lookup.
+        let mut missing_senders = Vec::new();
+        for tx in req.txs.iter() {
+            self.denylist.check(tx)?;
+
+            let sender = tx.sender().expect("Recovered sender");
+            if self.account_state(sender).is_none() && !missing_senders.contains(sender) {
+                missing_senders.push(*sender);
+            }
+        }
+
+        if !missing_senders.is_empty() {
+            let accounts = self
+                .client
+                .get_account_states(&missing_senders, None, true)
+                .await
+                .map_err(|err| {
+                    ValidationError::Internal(format!("Error fetching account states: {:?}", err))
+                })?;
+            self.account_states.extend(accounts);
+        }
+
         // Validate each transaction in the request against the account state,
         // keeping track of the nonce and balance diffs, including:
         // - any existing state in the account trie
@@ -350,24 +809,9 @@ impl<C: StateFetcher> ExecutionState<C> {
 
             trace!(nonce_diff, %balance_diff, "Applying diffs to account state");
 
-            let account_state = match self.account_state(sender).copied() {
-                Some(account) => account,
-                None => {
-                    // Fetch the account state from the client if it does not exist
-                    let account = match self.client.get_account_state(sender, None).await {
-                        Ok(account) => account,
-                        Err(err) => {
-                            return Err(ValidationError::Internal(format!(
-                                "Error fetching account state: {:?}",
-                                err
-                            )))
-                        }
-                    };
-
-                    self.account_states.insert(*sender, account);
-                    account
-                }
-            };
+            let account_state = self.account_state(sender).copied().ok_or_else(|| {
+                ValidationError::Internal(format!("Missing account state of {sender}"))
+            })?;
 
             debug!(?account_state, ?nonce_diff, ?balance_diff, "Validating transaction");
 
@@ -411,8 +855,12 @@ impl<C: StateFetcher> ExecutionState<C> {
                     .ok_or(ValidationError::MaxBaseFeeCalcOverflow)?;
 
                 debug!(%max_blob_basefee, blob_basefee = blob_transaction.transaction.tx.max_fee_per_blob_gas, "Validating blob basefee");
-                if blob_transaction.transaction.tx.max_fee_per_blob_gas < max_blob_basefee {
-                    return Err(ValidationError::BlobBaseFeeTooLow(max_blob_basefee));
+                let max_fee_per_blob_gas = blob_transaction.transaction.tx.max_fee_per_blob_gas;
+                if max_fee_per_blob_gas < max_blob_basefee {
+                    return Err(ValidationError::BlobFeeTooLow {
+                        projected_blob_basefee: max_blob_basefee,
+                        max_fee_per_blob_gas,
+                    });
                 }
 
                 // Validate blob against KZG settings
@@ -430,16 +878,186 @@ impl<C: StateFetcher> ExecutionState<C> {
         Ok(())
     }
 
+    /// Returns the transactions committed for the slots up to the given one, in the order
+    /// they'll be included, to simulate new transactions on top of them.
+    fn committed_transactions_until(&self, slot: Slot) -> Vec<FullTransaction> {
+        let mut slots =
+            self.block_templates.keys().filter(|s| **s <= slot).copied().collect::<Vec<_>>();
+        slots.sort_unstable();
+
+        slots
+            .iter()
+            .flat_map(|slot| self.block_templates[slot].transactions_in_block_order())
+            .collect()
+    }
+
+    /// Checks that none of the transactions of the request is already included in one of the
+    /// latest blocks, or already committed for an upcoming slot. Either would make for a
+    /// commitment that can't be honored, or contradicts an earlier one.
+    fn check_duplicates(&self, req: &InclusionRequest) -> Result<(), ValidationError> {
+        let mut committed = HashMap::new();
+        for (slot, template) in &self.block_templates {
+            committed.extend(template.transaction_hashes().into_iter().map(|hash| (hash, *slot)));
+        }
+
+        for tx in &req.txs {
+            let hash = *tx.hash();
+            if let Some(block_number) = self.recent_inclusions.block_of(&hash) {
+                return Err(ValidationError::AlreadyIncluded { hash, block_number });
+            }
+            if let Some(slot) = committed.get(&hash) {
+                return Err(ValidationError::AlreadyCommitted { hash, slot: *slot });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the target slot of the request has room for its transactions and gas.
+    fn check_slot_capacity(
+        &self,
+        req: &InclusionRequest,
+        limits: &LimitsOpts,
+    ) -> Result<(), ValidationError> {
+        let target_slot = req.slot;
+        let template = self.block_templates.get(&target_slot);
+
+        // Check if there is room for more commitments
+        if let Some(template) = template {
+            if template.transactions_len() >= limits.max_commitments_per_slot.get() {
+                return Err(ValidationError::MaxCommitmentsReachedForSlot(
+                    self.slot,
+                    limits.max_commitments_per_slot.get(),
+                ));
+            }
+        }
+
+        // Check if there is room for all the transactions of the request. Every constrained
+        // transaction needs an inclusion proof, and relays cap how many they accept per block
+        let constraints_count = template.map(|t| t.transactions_len()).unwrap_or(0);
+        if constraints_count + req.txs.len() > limits.max_constraints_per_slot.get() {
+            ApiMetrics::increment_slot_full_rejections();
+            return Err(ValidationError::SlotFull {
+                slot: target_slot,
+                remaining: limits.max_constraints_per_slot.get().saturating_sub(constraints_count),
+            });
+        }
+
+        // Check if the committed gas exceeds the maximum, tightened to the registered gas limit
+        let template_committed_gas = template.map(|t| t.committed_gas()).unwrap_or(0);
+        let max_committed_gas = self.max_committed_gas(target_slot, limits);
+
+        if template_committed_gas + req.gas_limit() >= max_committed_gas {
+            return Err(ValidationError::MaxCommittedGasReachedForSlot(
+                self.slot,
+                max_committed_gas,
+            ));
+        }
+
+        if req.top {
+            self.check_top_of_block(req, limits)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the top of the block of the target slot has room for the transactions and
+    /// gas of the top-of-block request, and that they can be placed before the transactions
+    /// already committed below the top.
+    fn check_top_of_block(
+        &self,
+        req: &InclusionRequest,
+        limits: &LimitsOpts,
+    ) -> Result<(), ValidationError> {
+        let template = self.block_templates.get(&req.slot);
+        let top_txs = template.map(|t| t.top_transactions_len()).unwrap_or(0);
+        let top_gas = template.map(|t| t.top_committed_gas()).unwrap_or(0);
+
+        if top_txs + req.txs.len() > limits.max_top_of_block_txs_per_slot ||
+            top_gas + req.gas_limit() > limits.max_top_of_block_gas_per_slot
+        {
+            return Err(ValidationError::TopOfBlockFull {
+                slot: req.slot,
+                remaining_txs: limits.max_top_of_block_txs_per_slot.saturating_sub(top_txs),
+                remaining_gas: limits.max_top_of_block_gas_per_slot.saturating_sub(top_gas),
+            });
+        }
+
+        // The top-of-block transactions are placed first: a sender with transactions already
+        // committed below the top would see its nonces out of order
+        let Some(template) = template else { return Ok(()) };
+        let senders_below_top = template.senders_below_top();
+        for tx in &req.txs {
+            let sender = tx.sender().expect("Recovered sender");
+            if senders_below_top.contains(sender) {
+                return Err(ValidationError::TopOfBlockSenderConflict(*sender));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the senders of the request whose account state isn't cached yet.
+    ///
+    /// NOTE: the signers of the request must be recovered beforehand.
+    fn missing_accounts(&self, req: &InclusionRequest) -> Vec<Address> {
+        let mut missing = req
+            .txs
+            .iter()
+            .filter_map(|tx| tx.sender().copied())
+            .filter(|sender| !self.account_states.contains_key(sender))
+            .collect::<Vec<_>>();
+        missing.sort_unstable();
+        missing.dedup();
+        missing
+    }
+
+    /// Caches the given account states, unless a state update cached them in the meantime.
+    fn cache_account_states(&mut self, account_states: HashMap<Address, AccountState>) {
+        for (address, account_state) in account_states {
+            self.account_states.entry(address).or_insert(account_state);
+        }
+    }
+
+    /// Commits the signed constraints of a validated request to its target slot.
+    ///
+    /// Requests can be validated concurrently, so the capacity of the slot is checked again
+    /// before adding the constraints: another request may have filled it up in the meantime.
+    ///
+    /// Either all the constraints are added or none of them: the checks happen before the
+    /// block template is modified.
+    pub fn commit_request(
+        &mut self,
+        req: &InclusionRequest,
+        signed_constraints: Vec<SignedConstraints>,
+    ) -> Result<(), ValidationError> {
+        self.check_slot_capacity(req, &self.limits.get())?;
+
+        for signed_constraints in signed_constraints {
+            self.add_constraint(req.slot, signed_constraints);
+        }
+
+        Ok(())
+    }
+
     /// Commits the transaction to the target block. Initializes a new block template
     /// if one does not exist for said block number.
     pub fn add_constraint(&mut self, target_slot: u64, signed_constraints: SignedConstraints) {
-        if let Some(template) = self.block_templates.get_mut(&target_slot) {
-            template.add_constraints(signed_constraints);
-        } else {
-            let mut template = BlockTemplate::default();
-            template.add_constraints(signed_constraints);
-            self.block_templates.insert(target_slot, template);
-        }
+        self.constraints_stream.publish(signed_constraints.clone());
+
+        let template = self.block_templates.entry(target_slot).or_default();
+        template.add_constraints(signed_constraints);
+        record_slot_commitments(target_slot, template);
+    }
+
+    /// Returns the block number of the latest state update.
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    /// Returns the slot of the latest head.
+    pub fn slot(&self) -> u64 {
+        self.slot
     }
 
     /// Updates the state corresponding to the provided block number and slot.
@@ -452,13 +1070,15 @@ impl<C: StateFetcher> ExecutionState<C> {
     ) -> Result<(), TransportError> {
         self.slot = slot;
 
-        let accounts = self.account_states.keys().collect::<Vec<_>>();
-        let update = self.client.get_state_update(accounts, block_number).await?;
-        trace!(%slot, ?update, "Applying execution state update");
-
         // Remove any block templates that are no longer valid
         // NOTE: this needs to be called BEFORE applying the state update or we might remove
         // constraints for which we need to get the receipts.
+        //
+        // The templates are only pruned under the state lock, under which the deadline handler
+        // also seals a slot and takes its template: a template is either submitted in full or
+        // pruned, never both.
+        self.constraints_stream.prune(slot);
+        self.registered_gas_limits.retain(|target_slot, _| *target_slot > slot);
         for template in self.remove_block_templates_until(slot) {
             debug!(%slot, "Removed block template for slot");
             let hashes = template.transaction_hashes();
@@ -493,11 +1113,163 @@ impl<C: StateFetcher> ExecutionState<C> {
             }
         }
 
+        // Only keep the accounts of the senders with pending commitments, the others are
+        // fetched again when they send a new request.
+        self.prune_account_states();
+
+        let accounts = self.account_states.keys().collect::<Vec<_>>();
+        let update = self.client.get_state_update(accounts, block_number).await?;
+        trace!(%slot, ?update, "Applying execution state update");
+
         self.apply_state_update(update);
 
+        if let Err(err) = self.refresh_recent_inclusions().await {
+            warn!(?err, "Failed to fetch the transactions of the latest blocks");
+        }
+
+        if let Err(err) = self.revalidate_commitments().await {
+            warn!(?err, "Failed to revalidate the pending commitments");
+        }
+
+        ApiMetrics::set_execution_memory(self.memory_stats());
+
+        Ok(())
+    }
+
+    /// Caches the transaction hashes of the blocks after the latest cached one, up to the head
+    /// block. The head block is fetched again if it's already cached, in case it was reorged.
+    async fn refresh_recent_inclusions(&mut self) -> Result<(), TransportError> {
+        let capacity = self.recent_inclusions.capacity() as u64;
+        if capacity == 0 {
+            return Ok(());
+        }
+
+        let head = self.block_number;
+        let oldest = head.saturating_sub(capacity - 1);
+        let from = self.recent_inclusions.latest_block().map_or(oldest, |latest| latest + 1);
+
+        for block_number in from.clamp(oldest, head)..=head {
+            let hashes = self.client.get_block_transaction_hashes(block_number).await?;
+            self.recent_inclusions.insert(block_number, hashes);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the cached account states of the senders without transactions in any of the
+    /// block templates.
+    fn prune_account_states(&mut self) {
+        let senders = self
+            .block_templates
+            .values()
+            .flat_map(|template| template.senders())
+            .collect::<HashSet<_>>();
+
+        let before = self.account_states.len();
+        self.account_states.retain(|address, _| senders.contains(address));
+        trace!(pruned = before - self.account_states.len(), "Pruned cached account states");
+    }
+
+    /// Returns the number of block templates, constraints and cached account states held in
+    /// memory.
+    pub fn memory_stats(&self) -> ExecutionMemoryStats {
+        ExecutionMemoryStats {
+            templates: self.block_templates.len(),
+            constraints: self
+                .block_templates
+                .values()
+                .map(|template| template.signed_constraints_list.len())
+                .sum(),
+            accounts: self.account_states.len(),
+        }
+    }
+
+    /// Revalidate the committed transactions of the block templates against the pending state
+    /// of their senders, which includes the transactions in the mempool.
+    ///
+    /// A sender who broadcast a conflicting transaction, or spent their balance, since their
+    /// request was accepted can get it included before the target slot, which would invalidate
+    /// the committed transactions. Their constraints are marked as at risk for the slot and the
+    /// following ones, and are dropped from the templates that aren't sealed yet if enabled.
+    ///
+    /// NOTE: this needs to be called AFTER applying the state update, so that conflicts already
+    /// included on chain have been removed from the templates.
+    async fn revalidate_commitments(&mut self) -> Result<(), TransportError> {
+        self.at_risk.clear();
+
+        let mut slots = self.block_templates.keys().copied().collect::<Vec<_>>();
+        slots.sort_unstable();
+
+        let senders = self
+            .block_templates
+            .values()
+            .flat_map(|template| template.senders())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        if senders.is_empty() {
+            return Ok(());
+        }
+
+        // A single batch request for all the senders
+        let pending_states = self.client.get_pending_account_states(&senders).await?;
+
+        for (sender, pending) in pending_states {
+            let mut committed_cost = U256::ZERO;
+            let mut at_risk = false;
+
+            for slot in &slots {
+                let template = &self.block_templates[slot];
+                let Some(committed_nonce) = template.min_nonce(&sender) else { continue };
+                committed_cost += template.get_diff(&sender).map_or(U256::ZERO, |diff| diff.1);
+
+                // Once a slot is at risk, the nonces of the following slots are too
+                at_risk |=
+                    pending.transaction_count > committed_nonce || pending.balance < committed_cost;
+                if at_risk {
+                    warn!(
+                        %sender,
+                        slot,
+                        committed_nonce,
+                        pending_nonce = pending.transaction_count,
+                        "Committed transactions at risk after the sender acted on-chain"
+                    );
+                    self.at_risk.entry(*slot).or_default().insert(sender);
+                }
+            }
+        }
+
+        let mut dropped = 0;
+        for (slot, senders) in &self.at_risk {
+            let template = self.block_templates.get_mut(slot).expect("template of at-risk slot");
+            ApiMetrics::increment_constraints_at_risk(template.signed_constraints_from(senders));
+
+            if self.drop_at_risk_constraints && *slot > self.sealed_slot {
+                dropped += template.remove_signed_constraints_from(senders);
+                record_slot_commitments(*slot, template);
+            }
+        }
+
+        if dropped > 0 {
+            warn!(dropped, "Dropped the constraints at risk from the block templates");
+            ApiMetrics::increment_constraints_invalidated(dropped);
+        }
+
         Ok(())
     }
 
+    /// Returns true if the committed transactions of the sender in the given slot are at risk
+    /// of being invalidated, as of the latest head.
+    pub fn is_at_risk(&self, slot: Slot, sender: &Address) -> bool {
+        self.at_risk.get(&slot).is_some_and(|senders| senders.contains(sender))
+    }
+
+    /// Mark the given slot as sealed: its commitment deadline passed and its constraints are
+    /// submitted, so they are no longer dropped from its template when at risk.
+    pub fn seal_slot(&mut self, slot: Slot) {
+        self.sealed_slot = self.sealed_slot.max(slot);
+    }
+
     fn apply_state_update(&mut self, update: StateUpdate) {
         // Update head and basefee
         self.block_number = update.block_number;
@@ -513,13 +1285,14 @@ impl<C: StateFetcher> ExecutionState<C> {
     /// transactions by checking the nonce and balance of the account after applying the state
     /// diffs.
     fn refresh_templates(&mut self) {
+        let mut invalidated = 0;
         for (address, account_state) in self.account_states.iter_mut() {
             trace!(%address, ?account_state, "Refreshing template...");
             // Iterate over all block templates and apply the state diff
             for (_, template) in self.block_templates.iter_mut() {
                 // Retain only signed constraints where transactions are still valid based on the
                 // canonical account states.
-                template.retain(*address, *account_state);
+                invalidated += template.retain(*address, *account_state);
 
                 // Update the account state with the remaining state diff for the next iteration.
                 if let Some((nonce_diff, balance_diff)) = template.get_diff(address) {
@@ -530,6 +1303,13 @@ impl<C: StateFetcher> ExecutionState<C> {
                 }
             }
         }
+
+        if invalidated > 0 {
+            ApiMetrics::increment_constraints_invalidated(invalidated);
+            for (slot, template) in &self.block_templates {
+                record_slot_commitments(*slot, template);
+            }
+        }
     }
 
     /// Returns the cached account state for the given address
@@ -558,6 +1338,7 @@ impl<C: StateFetcher> ExecutionState<C> {
         let mut templates = Vec::with_capacity(slots_to_remove.len());
         for s in slots_to_remove {
             if let Some(template) = self.block_templates.remove(&s) {
+                ApiMetrics::reset_slot_commitments(s);
                 templates.push(template);
             }
         }
@@ -566,41 +1347,354 @@ impl<C: StateFetcher> ExecutionState<C> {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct StateUpdate {
-    pub account_states: HashMap<Address, AccountState>,
-    pub min_basefee: u128,
-    pub min_blob_basefee: u128,
-    pub block_number: u64,
+/// Records the commitments in the block template of the given slot in the metrics.
+fn record_slot_commitments(slot: u64, template: &BlockTemplate) {
+    ApiMetrics::set_slot_commitments(
+        slot,
+        SlotCommitmentStats {
+            gas: template.committed_gas(),
+            blobs: template.blob_count(),
+            priority_fees: template.max_priority_fees(),
+            transactions: template.transactions_len(),
+        },
+    );
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::{builder::template::StateDiff, signer::local::LocalSigner};
-    use std::{num::NonZero, str::FromStr, time::Duration};
+/// A handle to an [ExecutionState] shared between the driver and the tasks processing
+/// commitment requests concurrently.
+///
+/// The state is only locked for in-memory checks and updates: the RPC round trips needed to
+/// validate a request, i.e. fetching unknown accounts and simulating transactions, happen
+/// without holding the lock, so that head updates are never stuck behind a slow request.
+#[derive(Debug)]
+pub struct SharedExecutionState<C>(Arc<Mutex<ExecutionState<C>>>);
 
-    use alloy::{
-        consensus::constants::{ETH_TO_WEI, GWEI_TO_WEI},
-        eips::eip2718::Encodable2718,
-        network::EthereumWallet,
-        primitives::{uint, Uint},
-        providers::{network::TransactionBuilder, Provider, ProviderBuilder},
-        signers::local::PrivateKeySigner,
-    };
-    use fetcher::{StateClient, StateFetcher};
+impl<C> Clone for SharedExecutionState<C> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
 
-    use crate::{
-        crypto::SignableBLS,
-        primitives::{ConstraintsMessage, SignedConstraints},
-        state::fetcher,
-        test_util::{create_signed_inclusion_request, default_test_transaction, launch_anvil},
-    };
+impl<C: StateFetcher> SharedExecutionState<C> {
+    /// Wraps the given execution state to share it.
+    pub fn new(state: ExecutionState<C>) -> Self {
+        Self(Arc::new(Mutex::new(state)))
+    }
 
-    use super::*;
+    /// Locks the execution state.
+    pub async fn lock(&self) -> MutexGuard<'_, ExecutionState<C>> {
+        self.0.lock().await
+    }
 
-    #[tokio::test]
-    async fn test_valid_inclusion_request() -> eyre::Result<()> {
-        let _ = tracing_subscriber::fmt::try_init();
+    /// Validates the commitment request against the execution state, like
+    /// [ExecutionState::validate_request], without holding the lock while waiting for the
+    /// execution client.
+    ///
+    /// NOTE: requests from the same sender must not be validated concurrently, or the nonce
+    /// and balance diffs of the first one won't be accounted for in the second one.
+    pub async fn validate_request(
+        &self,
+        req: &mut InclusionRequest,
+    ) -> Result<(), ValidationError> {
+        req.recover_signers()?;
+
+        let (client, missing) = {
+            let state = self.lock().await;
+            (Arc::clone(&state.client), state.missing_accounts(req))
+        };
+
+        if !missing.is_empty() {
+            let account_states =
+                client.get_account_states(&missing, None, true).await.map_err(|err| {
+                    ValidationError::Internal(format!("Error fetching account states: {:?}", err))
+                })?;
+            self.lock().await.cache_account_states(account_states);
+        }
+
+        let (committed, simulation) = {
+            let mut state = self.lock().await;
+            state.check_request(req).await?;
+            (state.committed_transactions_until(req.slot), state.simulation)
+        };
+
+        if simulation.enabled {
+            simulate_transactions(client.as_ref(), simulation, &committed, &req.txs).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Validates the commitment request like [SharedExecutionState::validate_request] and
+    /// returns its verdict, without committing it: the pending diffs and the block templates
+    /// are left untouched.
+    pub async fn dry_run(
+        &self,
+        req: &mut InclusionRequest,
+    ) -> Result<ValidationVerdict, ValidationError> {
+        self.validate_request(req).await?;
+        self.lock().await.verdict(req)
+    }
+
+    /// Fetches the transactions referenced by hash in the request from the mempool of the
+    /// execution client, without holding the lock. Requests sending the full transactions
+    /// are left untouched.
+    pub async fn resolve_transactions(
+        &self,
+        req: &mut InclusionRequest,
+    ) -> Result<(), ValidationError> {
+        if !req.is_by_hash() {
+            return Ok(());
+        }
+
+        let client = Arc::clone(&self.lock().await.client);
+        resolve_pool_transactions(client.as_ref(), req).await
+    }
+
+    /// Updates the execution state to the block of a new beacon head at the given slot, like
+    /// [ExecutionState::update_head].
+    ///
+    /// The execution client may not have imported that block yet when the head event is
+    /// received, in which case the "latest" state would still be the one of the previous
+    /// block. Its head is polled without holding the lock until it's past the current block,
+    /// for at most `timeout`, and the state is updated to that block. If the execution client
+    /// doesn't catch up in time, the state is updated to its latest block anyway.
+    pub async fn update_head(&self, slot: Slot, timeout: Duration) -> Result<(), TransportError> {
+        let (client, block_number, current_slot) = {
+            let state = self.lock().await;
+            (Arc::clone(&state.client), state.block_number, state.slot)
+        };
+
+        // Only a new slot is expected to come with a new block
+        let mut head = None;
+        if slot > current_slot {
+            let expected = block_number + 1;
+            head = wait_for_block(client.as_ref(), expected, timeout).await?;
+            if head.is_none() {
+                warn!(slot, expected, ?timeout, "Execution client lagging behind the beacon head");
+                ApiMetrics::increment_execution_head_lagging();
+            }
+        }
+
+        self.lock().await.update_head(head, slot).await
+    }
+
+    /// Fetches the numbers of the finalized and safe blocks from the EL.
+    pub async fn get_checkpoint_blocks(&self) -> Result<(u64, u64), TransportError> {
+        let client = Arc::clone(&self.lock().await.client);
+
+        tokio::try_join!(
+            client.get_block_number(BlockNumberOrTag::Finalized),
+            client.get_block_number(BlockNumberOrTag::Safe)
+        )
+    }
+}
+
+/// Polls the head of the execution client until it reaches the given block number, for at most
+/// the given timeout. Returns the head, or `None` if it didn't reach the block in time.
+async fn wait_for_block<C: StateFetcher>(
+    client: &C,
+    block_number: u64,
+    timeout: Duration,
+) -> Result<Option<u64>, TransportError> {
+    let give_up_at = Instant::now() + timeout;
+    loop {
+        let head = client.get_head().await?;
+        if head >= block_number {
+            return Ok(Some(head));
+        }
+
+        if Instant::now() + HEAD_POLL_INTERVAL > give_up_at {
+            return Ok(None);
+        }
+        tokio::time::sleep(HEAD_POLL_INTERVAL).await;
+    }
+}
+
+/// A read-only handle to the constraints of the block templates, for the servers running
+/// outside of the driver loop.
+#[async_trait::async_trait]
+pub trait TemplateReader: Send + Sync {
+    /// Returns the slot of the latest head. The templates of this slot and the earlier ones
+    /// have been pruned.
+    async fn head_slot(&self) -> Slot;
+
+    /// Returns the signed constraints of the block template for the given slot, or an empty
+    /// list if there is none.
+    async fn signed_constraints(&self, slot: Slot) -> BatchedSignedConstraints;
+}
+
+impl fmt::Debug for dyn TemplateReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TemplateReader")
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: StateFetcher + Send + Sync + 'static> TemplateReader for SharedExecutionState<C> {
+    async fn head_slot(&self) -> Slot {
+        self.lock().await.slot()
+    }
+
+    async fn signed_constraints(&self, slot: Slot) -> BatchedSignedConstraints {
+        let state = self.lock().await;
+        state
+            .block_templates
+            .get(&slot)
+            .map(|template| template.signed_constraints_list.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Fetches the transactions referenced by hash in the request from the mempool of the
+/// execution client, and sets them in the request in the same order.
+///
+/// Blob transactions are rejected: their sidecars can't be fetched from the mempool over
+/// JSON-RPC, so they must be sent in full.
+async fn resolve_pool_transactions<C: StateFetcher>(
+    client: &C,
+    req: &mut InclusionRequest,
+) -> Result<(), ValidationError> {
+    let pool_txs = client.get_pool_transactions(&req.tx_hashes).await.map_err(|err| {
+        ValidationError::Internal(format!("Error fetching pool transactions: {:?}", err))
+    })?;
+
+    let missing = req
+        .tx_hashes
+        .iter()
+        .filter(|hash| !pool_txs.contains_key(*hash))
+        .copied()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(ValidationError::TransactionsNotFound(missing));
+    }
+
+    let mut txs = Vec::with_capacity(req.tx_hashes.len());
+    for hash in &req.tx_hashes {
+        let Transaction { inner, .. } = &pool_txs[hash];
+        if inner.is_eip4844() {
+            return Err(ValidationError::BlobSidecarUnavailable(*hash));
+        }
+
+        let tx = FullTransaction::decode_enveloped(inner.encoded_2718()).map_err(|err| {
+            ValidationError::Internal(format!("Invalid pool transaction {hash}: {err}"))
+        })?;
+        if tx.hash() != hash {
+            return Err(ValidationError::Internal(format!(
+                "Pool transaction hash mismatch: expected {hash}, got {}",
+                tx.hash()
+            )));
+        }
+
+        txs.push(tx);
+    }
+
+    req.set_transactions(txs);
+    Ok(())
+}
+
+/// Simulates the given transactions on top of the transactions already committed ahead of
+/// them, in a single block over the latest state.
+///
+/// Transactions are rejected if their simulation reverts or runs out of their gas limit.
+/// If the simulation doesn't complete within its time budget, or if the execution client
+/// fails to serve it, the transactions are handled according to the simulation fallback.
+async fn simulate_transactions<C: StateFetcher>(
+    client: &C,
+    opts: SimulationOpts,
+    committed: &[FullTransaction],
+    txs: &[FullTransaction],
+) -> Result<(), ValidationError> {
+    let calls = committed.iter().chain(txs).map(simulation_call).collect::<Vec<_>>();
+
+    let results = match tokio::time::timeout(opts.timeout(), client.simulate_calls(&calls)).await {
+        Ok(Ok(results)) => results,
+        Ok(Err(err)) => return simulation_fallback(opts.fallback, err.to_string()),
+        Err(_) => {
+            let reason = format!("timed out after {:?}", opts.timeout());
+            return simulation_fallback(opts.fallback, reason);
+        }
+    };
+
+    // Only the outcome of the transactions of the request matter: the committed ones are
+    // simulated to build the state they'll execute on
+    for (tx, result) in txs.iter().zip(&results[committed.len()..]) {
+        if !result.is_success() {
+            let reason = result.failure_reason();
+            debug!(hash = %tx.hash(), %reason, "Transaction simulation failed");
+            return Err(ValidationError::SimulationFailed(reason));
+        }
+
+        trace!(hash = %tx.hash(), "Transaction simulation succeeded");
+    }
+
+    Ok(())
+}
+
+/// Returns the call simulating the given transaction.
+fn simulation_call(tx: &FullTransaction) -> TransactionRequest {
+    TransactionRequest::default()
+        .with_from(*tx.sender().expect("Recovered sender"))
+        .with_kind(tx.tx_kind())
+        .with_value(tx.value())
+        .with_input(tx.input().clone())
+        .with_gas_limit(tx.gas_limit())
+}
+
+/// Handles a request whose simulation didn't complete for the given reason, according to
+/// the simulation fallback.
+fn simulation_fallback(
+    fallback: SimulationFallback,
+    reason: String,
+) -> Result<(), ValidationError> {
+    match fallback {
+        SimulationFallback::Accept => {
+            warn!(%reason, "Transaction simulation unavailable, accepting the request");
+            Ok(())
+        }
+        SimulationFallback::Reject => {
+            warn!(%reason, "Transaction simulation unavailable, rejecting the request");
+            Err(ValidationError::SimulationUnavailable(reason))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StateUpdate {
+    pub account_states: HashMap<Address, AccountState>,
+    pub min_basefee: u128,
+    pub min_blob_basefee: u128,
+    pub block_number: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{builder::template::StateDiff, signer::local::LocalSigner};
+    use std::{num::NonZero, str::FromStr, time::Duration};
+
+    use alloy::{
+        consensus::constants::{ETH_TO_WEI, GWEI_TO_WEI},
+        eips::eip2718::Encodable2718,
+        network::EthereumWallet,
+        primitives::{bytes, uint, TxHash, Uint, U64},
+        providers::{Provider, ProviderBuilder},
+        signers::local::PrivateKeySigner,
+    };
+    use fetcher::{StateClient, StateFetcher};
+
+    use crate::{
+        crypto::SignableBLS,
+        primitives::{ConstraintsMessage, SignedConstraints},
+        state::{fetcher, recent_inclusions::RECENT_INCLUSION_BLOCKS},
+        test_util::{
+            create_signed_inclusion_request, default_test_transaction, launch_anvil, TestFetcher,
+        },
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_valid_inclusion_request() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
 
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
@@ -692,7 +1786,7 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::NonceTooLow(1, 0))
+            Err(ValidationError::NonceTooLow { expected: 1, got: 0 })
         ));
 
         assert!(state.account_states.get(sender).unwrap().transaction_count == 0);
@@ -704,7 +1798,7 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::NonceTooHigh(1, 2))
+            Err(ValidationError::NonceTooHigh { expected: 1, got: 2 })
         ));
 
         Ok(())
@@ -734,7 +1828,7 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::InsufficientBalance)
+            Err(ValidationError::InsufficientBalance { .. })
         ));
 
         Ok(())
@@ -792,7 +1886,7 @@ mod tests {
         // all of it on the previous preconfirmation
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::InsufficientBalance)
+            Err(ValidationError::InsufficientBalance { .. })
         ));
 
         Ok(())
@@ -809,6 +1903,7 @@ mod tests {
             max_commitments_per_slot: NonZero::new(10).unwrap(),
             max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
             min_priority_fee: 200000000, // 0.2 gwei
+            ..Default::default()
         };
 
         let mut state = ExecutionState::new(client.clone(), limits).await?;
@@ -831,7 +1926,7 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::BaseFeeTooLow(_))
+            Err(ValidationError::BaseFeeTooLow { .. })
         ));
 
         Ok(())
@@ -848,6 +1943,7 @@ mod tests {
             max_commitments_per_slot: NonZero::new(10).unwrap(),
             max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
             min_priority_fee: 2000000000,
+            ..Default::default()
         };
         let mut state = ExecutionState::new(client.clone(), limits).await?;
 
@@ -879,6 +1975,7 @@ mod tests {
             max_commitments_per_slot: NonZero::new(10).unwrap(),
             max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
             min_priority_fee: 2 * GWEI_TO_WEI as u128,
+            ..Default::default()
         };
 
         let mut state = ExecutionState::new(client.clone(), limits).await?;
@@ -921,6 +2018,7 @@ mod tests {
             max_commitments_per_slot: NonZero::new(10).unwrap(),
             max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
             min_priority_fee: 2 * GWEI_TO_WEI as u128,
+            ..Default::default()
         };
 
         let mut state = ExecutionState::new(client.clone(), limits).await?;
@@ -960,17 +2058,13 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_invalid_inclusion_request_duplicate_batch() -> eyre::Result<()> {
+    async fn test_invalid_inclusion_request_chain_id() -> eyre::Result<()> {
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
 
-        let limits = LimitsOpts {
-            max_commitments_per_slot: NonZero::new(10).unwrap(),
-            max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
-            min_priority_fee: 2 * GWEI_TO_WEI as u128,
-        };
-
-        let mut state = ExecutionState::new(client.clone(), limits).await?;
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default())
+            .await?
+            .with_chain(ChainConfig::mainnet());
 
         let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
@@ -979,34 +2073,48 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        let base_fee = state.basefee();
-        let Some(max_base_fee) = calculate_max_basefee(base_fee, 10 - slot) else {
-            return Err(eyre::eyre!("Failed to calculate max base fee"));
-        };
+        // Create a transaction signed for the anvil chain
+        let tx = default_test_transaction(*sender, None);
 
-        // Create a transaction with a gas price that is too low
-        let tx = default_test_transaction(*sender, None)
-            .with_gas_price(max_base_fee + 3 * GWEI_TO_WEI as u128);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
 
-        let mut request = create_signed_inclusion_request(&[tx.clone(), tx], sender_pk, 10).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::ChainIdMismatch { expected: 1, got: 1337 })
+        ));
 
-        let response = state.validate_request(&mut request).await;
-        println!("{response:?}");
+        // Create a pre-EIP155 legacy transaction without a chain ID
+        let max_basefee = calculate_max_basefee(state.basefee(), 10 - slot).unwrap();
+        let mut tx = default_test_transaction(*sender, None)
+            .with_gas_price(max_basefee + 2 * GWEI_TO_WEI as u128);
+        tx.chain_id = None;
+        tx.max_fee_per_gas = None;
+        tx.max_priority_fee_per_gas = None;
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert_eq!(request.txs[0].chain_id(), None);
+
+        // It's valid on any chain unless replay protection is required
+        assert!(state.validate_request(&mut request).await.is_ok());
 
-        assert!(matches!(response, Err(ValidationError::NonceTooLow(_, _))));
+        let chain = ChainConfig { require_replay_protection: true, ..ChainConfig::mainnet() };
+        let mut state = state.with_chain(chain);
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MissingChainId)
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalidate_inclusion_request() -> eyre::Result<()> {
-        let _ = tracing_subscriber::fmt::try_init();
-
+    async fn test_invalid_inclusion_request_intrinsic_gas_and_size() -> eyre::Result<()> {
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
-        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
 
-        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+        let limits = LimitsOpts { max_tx_size: NonZero::new(1024).unwrap(), ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
 
         let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
@@ -1015,51 +2123,42 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        let tx = default_test_transaction(*sender, None);
-
-        // build the signed transaction for submission later
-        let wallet: PrivateKeySigner = anvil.keys()[0].clone().into();
-        let signer: EthereumWallet = wallet.into();
-        let signed = tx.clone().build(&signer).await?;
-
-        let target_slot = 10;
-        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
-        let inclusion_request = request.clone();
-
-        assert!(state.validate_request(&mut request).await.is_ok());
-
-        let bls_signer = LocalSigner::random();
-        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
-        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
-        let signed_constraints = SignedConstraints { message, signature };
-
-        state.add_constraint(target_slot, signed_constraints);
-
-        assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
+        // Create a transaction whose gas limit doesn't cover its calldata
+        let tx = default_test_transaction(*sender, None).with_input(vec![0xff; 10]);
 
-        let notif = provider.send_raw_transaction(&signed.encoded_2718()).await?;
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
 
-        // Wait for confirmation
-        let receipt = notif.get_receipt().await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::GasLimitTooLow(21_160, 21_000))
+        ));
 
-        // Update the head, which should invalidate the transaction due to a nonce conflict
-        state.update_head(receipt.block_number, receipt.block_number.unwrap()).await?;
+        // Create a transaction larger than the configured maximum size
+        let tx = default_test_transaction(*sender, None)
+            .with_input(vec![0xff; 2048])
+            .with_gas_limit(100_000);
 
-        let transactions_len = state.get_block_template(target_slot).unwrap().transactions_len();
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
 
-        assert!(transactions_len == 0);
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::TransactionTooLarge(_, 1024))
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalidate_stale_template() -> eyre::Result<()> {
-        let _ = tracing_subscriber::fmt::try_init();
-
+    async fn test_invalid_inclusion_request_per_tx_caps() -> eyre::Result<()> {
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
 
-        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+        let limits = LimitsOpts {
+            max_gas_per_tx: NonZero::new(100_000),
+            max_calldata_bytes: NonZero::new(64),
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
 
         let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
@@ -1068,93 +2167,655 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        let tx = default_test_transaction(*sender, None);
-
-        let target_slot = 10;
-        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
-        let inclusion_request = request.clone();
-
+        // A transaction right at both caps is accepted
+        let tx = default_test_transaction(*sender, None)
+            .with_input(vec![0xff; 64])
+            .with_gas_limit(100_000);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
         assert!(state.validate_request(&mut request).await.is_ok());
 
-        let bls_signer = LocalSigner::random();
-        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
-        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
-        let signed_constraints = SignedConstraints { message, signature };
-
-        state.add_constraint(target_slot, signed_constraints);
-
-        assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
-
-        // fast-forward the head to the target slot, which should invalidate the entire template
-        // because it's now stale.
-        state.update_head(None, target_slot).await?;
+        // One more unit of gas is rejected, with the cap
+        let tx = default_test_transaction(*sender, None).with_gas_limit(100_001);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        let err = state.validate_request(&mut request).await.unwrap_err();
+        assert_eq!(err.data().unwrap()["max"], 100_000);
+        assert!(matches!(
+            err,
+            ValidationError::TxGasLimitTooHigh { gas_limit: 100_001, max: 100_000 }
+        ));
 
-        assert!(state.get_block_template(target_slot).is_none());
+        // One more byte of calldata is rejected, with the cap
+        let tx = default_test_transaction(*sender, None)
+            .with_input(vec![0xff; 65])
+            .with_gas_limit(100_000);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::CalldataTooLarge { size: 65, max: 64 })
+        ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalidate_inclusion_request_with_excess_gas() -> eyre::Result<()> {
-        let _ = tracing_subscriber::fmt::try_init();
-
+    async fn test_limits_tightened_at_runtime() -> eyre::Result<()> {
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
 
-        let limits: LimitsOpts = LimitsOpts {
-            max_commitments_per_slot: NonZero::new(10).unwrap(),
-            max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
-            min_priority_fee: 1000000000,
-        };
-        let mut state = ExecutionState::new(client.clone(), limits).await?;
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
 
         let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
+        let signer = LocalSigner::random();
 
         // initialize the state by updating the head once
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        let tx = default_test_transaction(*sender, None).with_gas_limit(4_999_999);
-
-        let target_slot = 10;
-        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
-        let inclusion_request = request.clone();
+        let tx = default_test_transaction(*sender, Some(0));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
 
         assert!(state.validate_request(&mut request).await.is_ok());
 
-        let bls_signer = LocalSigner::random();
-        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
-        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
-        let signed_constraints = SignedConstraints { message, signature };
-
-        state.add_constraint(target_slot, signed_constraints);
+        let message = ConstraintsMessage::build(Default::default(), request.clone());
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        state.add_constraint(10, SignedConstraints { message, signature });
 
-        assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
+        // Tighten the gas cap below the gas of two transfers
+        let update =
+            LimitsUpdate { max_committed_gas_per_slot: NonZero::new(30_000), ..Default::default() };
+        state.limits().update(update)?;
 
-        // This tx will exceed the committed gas limit
         let tx = default_test_transaction(*sender, Some(1));
-
         let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::MaxCommittedGasReachedForSlot(_, 5_000_000))
+            Err(ValidationError::MaxCommittedGasReachedForSlot(_, 30_000))
         ));
 
+        // The already accepted constraints are untouched
+        let template = state.get_block_template(10).unwrap();
+        assert_eq!(template.transactions_len(), 1);
+        assert_eq!(template.committed_gas(), 21_000);
+
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_valid_bundle_inclusion_request() -> eyre::Result<()> {
-        let _ = tracing_subscriber::fmt::try_init();
-
+    async fn test_committed_gas_capped_to_registered_gas_limit() -> eyre::Result<()> {
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
 
-        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
-
-        let sender = anvil.addresses().first().unwrap();
+        let limits = LimitsOpts {
+            max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let signer = LocalSigner::random();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // A registration above the configured cap doesn't raise it, one below shrinks it
+        state.set_registered_gas_limit(10, 36_000_000);
+        assert_eq!(state.slot_capacity(10)?.remaining_gas, 5_000_000);
+        state.set_registered_gas_limit(10, 30_000);
+        assert_eq!(state.slot_capacity(10)?.remaining_gas, 30_000);
+        assert_eq!(state.slot_capacity(11)?.remaining_gas, 5_000_000);
+
+        let tx = default_test_transaction(*sender, Some(0));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let message = ConstraintsMessage::build(Default::default(), request.clone());
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        state.add_constraint(10, SignedConstraints { message, signature });
+        assert_eq!(state.slot_capacity(10)?.remaining_gas, 9_000);
+
+        // The gas of a second transfer would exceed the registered gas limit
+        let tx = default_test_transaction(*sender, Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MaxCommittedGasReachedForSlot(_, 30_000))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_slot_full() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits =
+            LimitsOpts { max_constraints_per_slot: NonZero::new(3).unwrap(), ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let recorder = crate::telemetry::prometheus_builder()?.build_recorder();
+        let handle = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let signer = LocalSigner::random();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let txs = [
+            default_test_transaction(*sender, Some(0)),
+            default_test_transaction(*sender, Some(1)),
+        ];
+        let mut request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let message = ConstraintsMessage::build(Default::default(), request.clone());
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        state.add_constraint(10, SignedConstraints { message, signature });
+
+        // Only one more transaction fits in the slot
+        let txs = [
+            default_test_transaction(*sender, Some(2)),
+            default_test_transaction(*sender, Some(3)),
+        ];
+        let mut request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::SlotFull { slot: 10, remaining: 1 })
+        ));
+        assert!(handle.render().contains("bolt_sidecar_slot_full_rejections 1"));
+
+        let tx = default_test_transaction(*sender, Some(2));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_top_of_block() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts {
+            max_top_of_block_txs_per_slot: 2,
+            max_top_of_block_gas_per_slot: 50_000,
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let signer = LocalSigner::random();
+        let (alice, alice_pk) = (anvil.addresses()[0], &anvil.keys()[0]);
+        let (bob, bob_pk) = (anvil.addresses()[1], &anvil.keys()[1]);
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Bob commits a transaction below the top of the block
+        let tx = default_test_transaction(bob, Some(0));
+        let mut request = create_signed_inclusion_request(&[tx], bob_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let message = ConstraintsMessage::build(Default::default(), request.clone());
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        state.add_constraint(10, SignedConstraints { message, signature });
+
+        // Bob can't get the top of the block anymore, the nonces would be out of order
+        let tx = default_test_transaction(bob, Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], bob_pk, 10).await?;
+        request.top = true;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::TopOfBlockSenderConflict(sender)) if sender == bob
+        ));
+
+        // Alice gets the top of the block with a single transaction
+        let tx = default_test_transaction(alice, Some(0));
+        let mut request = create_signed_inclusion_request(&[tx], alice_pk, 10).await?;
+        request.top = true;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let message = ConstraintsMessage::build(Default::default(), request.clone());
+        assert!(message.top);
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        state.add_constraint(10, SignedConstraints { message, signature });
+
+        // Alice's transaction is placed before Bob's in a locally built block
+        let template = state.get_block_template(10).unwrap();
+        let senders = template
+            .as_signed_transactions()
+            .iter()
+            .map(|tx| tx.recover_signer().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(senders, vec![alice, bob]);
+
+        // Only one more transaction fits at the top of the block
+        let txs =
+            [default_test_transaction(alice, Some(1)), default_test_transaction(alice, Some(2))];
+        let mut request = create_signed_inclusion_request(&txs, alice_pk, 10).await?;
+        request.top = true;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::TopOfBlockFull {
+                slot: 10,
+                remaining_txs: 1,
+                remaining_gas: 29_000
+            })
+        ));
+
+        // The same transactions still fit below the top
+        request.top = false;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        // Disabling the top of the block rejects any top request
+        let update = LimitsUpdate { max_top_of_block_txs_per_slot: Some(0), ..Default::default() };
+        state.limits().update(update)?;
+        let tx = default_test_transaction(alice, Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], alice_pk, 11).await?;
+        request.top = true;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::TopOfBlockFull { slot: 11, remaining_txs: 0, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_duplicate_batch() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts {
+            max_commitments_per_slot: NonZero::new(10).unwrap(),
+            max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
+            min_priority_fee: 2 * GWEI_TO_WEI as u128,
+            ..Default::default()
+        };
+
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let base_fee = state.basefee();
+        let Some(max_base_fee) = calculate_max_basefee(base_fee, 10 - slot) else {
+            return Err(eyre::eyre!("Failed to calculate max base fee"));
+        };
+
+        // Create a transaction with a gas price that is too low
+        let tx = default_test_transaction(*sender, None)
+            .with_gas_price(max_base_fee + 3 * GWEI_TO_WEI as u128);
+
+        let mut request = create_signed_inclusion_request(&[tx.clone(), tx], sender_pk, 10).await?;
+
+        let response = state.validate_request(&mut request).await;
+        println!("{response:?}");
+
+        assert!(matches!(response, Err(ValidationError::NonceTooLow { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_already_included_or_committed() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(*sender, None);
+        let wallet: PrivateKeySigner = anvil.keys()[0].clone().into();
+        let signed = tx.clone().build(&EthereumWallet::from(wallet)).await?;
+        let hash = *signed.tx_hash();
+
+        let mut request = create_signed_inclusion_request(&[tx.clone()], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(10, SignedConstraints { message, signature });
+
+        // The same transaction can't be committed again, for the same slot or another one
+        for target_slot in [10, 12] {
+            let mut request =
+                create_signed_inclusion_request(&[tx.clone()], sender_pk, target_slot).await?;
+            assert!(matches!(
+                state.validate_request(&mut request).await,
+                Err(ValidationError::AlreadyCommitted { hash: h, slot: 10 }) if h == hash
+            ));
+        }
+
+        // Once included, its constraint is invalidated and it's rejected as included
+        let receipt =
+            provider.send_raw_transaction(&signed.encoded_2718()).await?.get_receipt().await?;
+        let block_number = receipt.block_number.unwrap();
+        state.update_head(Some(block_number), block_number).await?;
+        assert_eq!(state.get_block_template(10).unwrap().transactions_len(), 0);
+
+        let mut request = create_signed_inclusion_request(&[tx.clone()], sender_pk, 10).await?;
+        let err = state.validate_request(&mut request).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::AlreadyIncluded { hash: h, block_number: b } if (h, b) == (hash, block_number)
+        ));
+        assert_eq!(err.data().unwrap()["reason"], "already_included");
+
+        // Past the cached blocks, only its nonce gives it away
+        let _: serde_json::Value =
+            client.inner().request("anvil_mine", (U64::from(RECENT_INCLUSION_BLOCKS),)).await?;
+        let head = client.get_head().await?;
+        state.update_head(Some(head), head).await?;
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, head + 2).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::NonceTooLow { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_inclusion_request() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(*sender, None);
+
+        // build the signed transaction for submission later
+        let wallet: PrivateKeySigner = anvil.keys()[0].clone().into();
+        let signer: EthereumWallet = wallet.into();
+        let signed = tx.clone().build(&signer).await?;
+
+        let target_slot = 10;
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        let inclusion_request = request.clone();
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        let signed_constraints = SignedConstraints { message, signature };
+
+        state.add_constraint(target_slot, signed_constraints);
+
+        assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
+
+        let notif = provider.send_raw_transaction(&signed.encoded_2718()).await?;
+
+        // Wait for confirmation
+        let receipt = notif.get_receipt().await?;
+
+        // Update the head, which should invalidate the transaction due to a nonce conflict
+        state.update_head(receipt.block_number, receipt.block_number.unwrap()).await?;
+
+        let transactions_len = state.get_block_template(target_slot).unwrap().transactions_len();
+
+        assert!(transactions_len == 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_stale_template() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(*sender, None);
+
+        let target_slot = 10;
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        let inclusion_request = request.clone();
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        let signed_constraints = SignedConstraints { message, signature };
+
+        state.add_constraint(target_slot, signed_constraints);
+
+        assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
+
+        // fast-forward the head to the target slot, which should invalidate the entire template
+        // because it's now stale.
+        state.update_head(None, target_slot).await?;
+
+        assert!(state.get_block_template(target_slot).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_memory_bounded_over_many_slots() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default())
+            .await?
+            .with_lookahead_slots(SLOTS_PER_EPOCH);
+        let bls_signer = LocalSigner::random();
+
+        let start = client.get_head().await?;
+        state.update_head(None, start).await?;
+
+        for slot in start..start + 200 {
+            let target_slot = slot + 2;
+
+            // A commitment from one of the first senders...
+            let i = (slot % 5) as usize;
+            let tx = default_test_transaction(anvil.addresses()[i], None);
+            let mut request =
+                create_signed_inclusion_request(&[tx], &anvil.keys()[i], target_slot).await?;
+            state.validate_request(&mut request).await?;
+
+            let message = ConstraintsMessage::build(Default::default(), request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            state.add_constraint(target_slot, SignedConstraints { message, signature });
+
+            // ...and a request from one of the others, which is never committed
+            let i = i + 5;
+            let tx = default_test_transaction(anvil.addresses()[i], None);
+            let mut request =
+                create_signed_inclusion_request(&[tx], &anvil.keys()[i], target_slot).await?;
+            state.validate_request(&mut request).await?;
+
+            // The deadline of the next slot passes before its head
+            state.seal_slot(slot + 1);
+            let template = state.get_block_template(slot + 1).cloned();
+            assert_eq!(template.is_some(), slot > start);
+
+            state.update_head(None, slot + 1).await?;
+
+            // The template taken at the deadline is left untouched by the pruning
+            if let Some(template) = template {
+                assert_eq!(template.transactions_len(), 1);
+            }
+
+            // Only the template of the target slot is left, with the account of its sender
+            assert!(state.get_block_template(slot + 1).is_none());
+            let stats = state.memory_stats();
+            assert_eq!(stats, ExecutionMemoryStats { templates: 1, constraints: 1, accounts: 1 });
+        }
+
+        // The slots beyond the lookahead window are rejected
+        let max = state.slot() + SLOTS_PER_EPOCH - 1;
+        let sender = anvil.addresses()[0];
+        let tx = default_test_transaction(sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], max + 1).await?;
+        let err = state.validate_request(&mut request).await.unwrap_err();
+        assert!(
+            matches!(err, ValidationError::SlotTooHigh { slot, max: m } if slot == max + 1 && m == max)
+        );
+        assert_eq!(err.data().unwrap()["reason"], "slot_too_high");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_inclusion_request_with_excess_gas() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits: LimitsOpts = LimitsOpts {
+            max_commitments_per_slot: NonZero::new(10).unwrap(),
+            max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
+            min_priority_fee: 1000000000,
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(*sender, None).with_gas_limit(4_999_999);
+
+        let target_slot = 10;
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        let inclusion_request = request.clone();
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        let signed_constraints = SignedConstraints { message, signature };
+
+        state.add_constraint(target_slot, signed_constraints);
+
+        assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
+
+        // This tx will exceed the committed gas limit
+        let tx = default_test_transaction(*sender, Some(1));
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MaxCommittedGasReachedForSlot(_, 5_000_000))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_committed_slot_metrics() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let mut state = ExecutionState::new(client, LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let bls_signer = LocalSigner::random();
+
+        // Two constraints of one transaction each for the same slot, the 11th of its epoch
+        let target_slot = 42;
+        let mut constraints = Vec::new();
+        for nonce in 0..2 {
+            let tx = default_test_transaction(*sender, Some(nonce));
+            let request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+            let message = ConstraintsMessage::build(Default::default(), request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            constraints.push(SignedConstraints { message, signature });
+        }
+
+        let recorder = crate::telemetry::prometheus_builder()?.build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            for signed_constraints in constraints {
+                state.add_constraint(target_slot, signed_constraints);
+            }
+        });
+
+        let scrape = handle.render();
+        let gauge = |name: &str| {
+            let series = format!("{name}{{slot_index=\"10\"}}");
+            let line = scrape
+                .lines()
+                .find(|l| l.starts_with(&series))
+                .unwrap_or_else(|| panic!("missing {series} in:\n{scrape}"));
+            line.split_whitespace().last().unwrap().parse::<f64>().unwrap()
+        };
+
+        // 21k gas at 1 gwei of priority fee for each transaction
+        assert_eq!(gauge("bolt_sidecar_committed_gas"), 42_000.0);
+        assert_eq!(gauge("bolt_sidecar_committed_blobs"), 0.0);
+        assert_eq!(gauge("bolt_sidecar_committed_priority_fees_gwei"), 42_000.0);
+        assert_eq!(gauge("bolt_sidecar_template_transactions"), 2.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_valid_bundle_inclusion_request() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
 
         // initialize the state by updating the head once
@@ -1196,7 +2857,7 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::NonceTooHigh(2, 3))
+            Err(ValidationError::NonceTooHigh { expected: 2, got: 3 })
         ));
 
         Ok(())
@@ -1227,7 +2888,454 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::InsufficientBalance)
+            Err(ValidationError::InsufficientBalance { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_simulate_inclusion_request() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let simulation = SimulationOpts { enabled: true, ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default())
+            .await?
+            .with_simulation(simulation);
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // A contract always reverting with `0xdead`, and another one looping forever
+        let reverting = Address::repeat_byte(0x11);
+        let looping = Address::repeat_byte(0x22);
+        let rpc = client.inner();
+        let _: serde_json::Value =
+            rpc.request("anvil_setCode", (reverting, bytes!("61dead60005260206000fd"))).await?;
+        let _: serde_json::Value =
+            rpc.request("anvil_setCode", (looping, bytes!("5b600056"))).await?;
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // A simple transfer succeeds
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        // A call reverting is rejected with the revert data
+        let tx = default_test_transaction(*sender, None).with_to(reverting).with_gas_limit(100_000);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        match state.validate_request(&mut request).await {
+            Err(ValidationError::SimulationFailed(reason)) => assert!(reason.contains("dead")),
+            res => panic!("expected a simulation failure, got {res:?}"),
+        }
+
+        // A call running out of its gas limit is rejected
+        let tx = default_test_transaction(*sender, None).with_to(looping).with_gas_limit(100_000);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::SimulationFailed(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_simulate_on_top_of_committed_transactions() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let simulation = SimulationOpts { enabled: true, ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default())
+            .await?
+            .with_simulation(simulation);
+        let signer = LocalSigner::random();
+
+        // A contract that can only be called once: it reverts if its first storage slot is set,
+        // and sets it otherwise
+        let once = Address::repeat_byte(0x33);
+        let _: serde_json::Value = client
+            .inner()
+            .request("anvil_setCode", (once, bytes!("600054600c576001600055005b600080fd")))
+            .await?;
+
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // The first call is committed for slot 10
+        let (alice, alice_pk) = (anvil.addresses()[0], &anvil.keys()[0]);
+        let tx = default_test_transaction(alice, None).with_to(once).with_gas_limit(100_000);
+        let mut request = create_signed_inclusion_request(&[tx], alice_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let message = ConstraintsMessage::build(Default::default(), request.clone());
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        state.add_constraint(10, SignedConstraints { message, signature });
+
+        // A second call in the same slot fails on top of the committed one, even though it
+        // would succeed against the latest state
+        let (bob, bob_pk) = (anvil.addresses()[1], &anvil.keys()[1]);
+        let tx = default_test_transaction(bob, None).with_to(once).with_gas_limit(100_000);
+        let mut request = create_signed_inclusion_request(&[tx.clone()], bob_pk, 10).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::SimulationFailed(_))
+        ));
+
+        // But it succeeds for an earlier slot, which is built before the committed call
+        let mut request = create_signed_inclusion_request(&[tx], bob_pk, 9).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_simulation_fallback() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let sender = anvil.addresses()[0];
+        let sender_pk = &anvil.keys()[0];
+
+        let tx = default_test_transaction(sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        request.recover_signers()?;
+
+        // An execution client that can't be reached
+        let client = StateClient::new(reqwest::Url::parse("http://127.0.0.1:1")?);
+
+        // Requests are rejected by default
+        let opts = SimulationOpts { enabled: true, ..Default::default() };
+        assert!(matches!(
+            simulate_transactions(&client, opts, &[], &request.txs).await,
+            Err(ValidationError::SimulationUnavailable(_))
+        ));
+
+        // Unless the fallback accepts them
+        let opts = SimulationOpts { fallback: SimulationFallback::Accept, ..opts };
+        assert!(simulate_transactions(&client, opts, &[], &request.txs).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multi_sender_request_fetches_accounts_once() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let fetcher = TestFetcher::new(client.clone());
+
+        let mut state = ExecutionState::new(fetcher.clone(), LimitsOpts::default()).await?;
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+        fetcher.reset_lookups();
+
+        // A request with transactions from 5 different senders, one of them sending two
+        let mut txs = Vec::new();
+        for (i, (sender, sender_pk)) in
+            anvil.addresses().iter().zip(anvil.keys()).take(5).enumerate()
+        {
+            let tx = default_test_transaction(*sender, None);
+            txs.extend(create_signed_inclusion_request(&[tx], sender_pk, 10).await?.txs);
+            if i == 0 {
+                let tx = default_test_transaction(*sender, Some(1));
+                txs.extend(create_signed_inclusion_request(&[tx], sender_pk, 10).await?.txs);
+            }
+        }
+        let mut request =
+            create_signed_inclusion_request(&[], anvil.keys().first().unwrap(), 10).await?;
+        request.txs = txs;
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        // The accounts of all the senders are fetched with a single lookup
+        assert_eq!(fetcher.lookups(), 1);
+        assert!(anvil
+            .addresses()
+            .iter()
+            .take(5)
+            .all(|sender| state.account_states.contains_key(sender)));
+
+        // They're cached for the next requests
+        assert!(state.validate_request(&mut request).await.is_ok());
+        assert_eq!(fetcher.lookups(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slow_validation_does_not_block_head_updates() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let slow_client = TestFetcher::new(client.clone()).with_delay(Duration::from_secs(2));
+
+        let mut state = ExecutionState::new(slow_client, LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+        let execution = SharedExecutionState::new(state);
+
+        // The account of the sender isn't cached: its lookup is slow
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        let validation = tokio::spawn({
+            let execution = execution.clone();
+            async move { execution.validate_request(&mut request).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Head updates go through while the request is being validated
+        let update = async { execution.lock().await.update_head(None, slot).await };
+        tokio::time::timeout(Duration::from_secs(1), update).await??;
+        assert!(!validation.is_finished());
+
+        assert!(validation.await?.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_revalidate_commitments_after_mempool_conflict() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let fetcher = TestFetcher::new(client.clone());
+
+        let mut state = ExecutionState::new(fetcher.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let target_slot = 10;
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        let inclusion_request = request.clone();
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(target_slot, SignedConstraints { message, signature });
+
+        // Nothing changed in the mempool
+        state.update_head(None, slot).await?;
+        assert!(!state.is_at_risk(target_slot, sender));
+
+        // The sender broadcasts a transaction with the committed nonce: the constraints are
+        // reported as at risk, but kept by default
+        fetcher.set_mempool_txs(1);
+        state.update_head(None, slot).await?;
+        assert!(state.is_at_risk(target_slot, sender));
+        assert_eq!(state.get_block_template(target_slot).unwrap().transactions_len(), 1);
+
+        // Sealed templates are never changed
+        state.drop_at_risk_constraints = true;
+        state.seal_slot(target_slot);
+        state.update_head(None, slot).await?;
+        assert_eq!(state.get_block_template(target_slot).unwrap().transactions_len(), 1);
+
+        // Otherwise the constraints at risk are dropped before the deadline
+        state.sealed_slot = 0;
+        state.update_head(None, slot).await?;
+        assert_eq!(state.get_block_template(target_slot).unwrap().transactions_len(), 0);
+
+        // Once dropped, the sender is no longer at risk
+        state.update_head(None, slot).await?;
+        assert!(!state.is_at_risk(target_slot, sender));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pool_transactions() -> eyre::Result<()> {
+        let signer = PrivateKeySigner::random();
+        let wallet = EthereumWallet::from(signer.clone());
+        let envelope = default_test_transaction(signer.address(), None).build(&wallet).await?;
+        let found = *envelope.tx_hash();
+        let missing = TxHash::repeat_byte(0x42);
+
+        let mut pending_tx = serde_json::to_value(&envelope)?;
+        pending_tx["from"] = json!(signer.address());
+
+        // Mock EL node serving only the first transaction by hash, with an empty mempool
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(move |req: &wiremock::Request| {
+                let respond = |call: &Value| {
+                    let result = match call["method"].as_str() {
+                        Some("eth_getTransactionByHash") if call["params"][0] == json!(found) => {
+                            pending_tx.clone()
+                        }
+                        Some("txpool_content") => json!({ "pending": {}, "queued": {} }),
+                        _ => Value::Null,
+                    };
+                    json!({ "jsonrpc": "2.0", "id": call["id"], "result": result })
+                };
+
+                let body = serde_json::from_slice::<Value>(&req.body).unwrap();
+                let response = match &body {
+                    Value::Array(calls) => Value::Array(calls.iter().map(respond).collect()),
+                    call => respond(call),
+                };
+                wiremock::ResponseTemplate::new(200).set_body_json(response)
+            })
+            .mount(&server)
+            .await;
+        let client = StateClient::new(reqwest::Url::parse(&server.uri())?);
+
+        // The hashes missing from the mempool are reported
+        let mut request: InclusionRequest =
+            serde_json::from_value(json!({ "slot": 10, "tx_hashes": [found, missing] }))?;
+        match resolve_pool_transactions(&client, &mut request).await {
+            Err(ValidationError::TransactionsNotFound(hashes)) => assert_eq!(hashes, vec![missing]),
+            res => panic!("expected missing transactions, got {res:?}"),
+        }
+        assert!(request.is_by_hash() && request.txs.is_empty());
+
+        // The transactions found are set in the request, which keeps the same digest
+        let mut request: InclusionRequest =
+            serde_json::from_value(json!({ "slot": 10, "tx_hashes": [found] }))?;
+        let digest = request.digest();
+        resolve_pool_transactions(&client, &mut request).await?;
+        assert!(!request.is_by_hash());
+        assert_eq!(request.txs.len(), 1);
+        assert_eq!(request.txs[0].hash(), &found);
+        assert_eq!(request.digest(), digest);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_head_with_lagging_execution_client() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+        let fetcher = TestFetcher::new(client.clone());
+
+        let mut state = ExecutionState::new(fetcher.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let wallet: EthereumWallet = PrivateKeySigner::from(anvil.keys()[0].clone()).into();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // The account of the sender is cached with its first nonce
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx.clone()], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+        let execution = SharedExecutionState::new(state);
+
+        // A transaction of the sender is mined, but the execution client lags for 500ms
+        let signed = tx.build(&wallet).await?;
+        let pending = provider.send_raw_transaction(&signed.encoded_2718()).await?;
+        let block_number = pending.get_receipt().await?.block_number.unwrap();
+        fetcher.lag_for(Duration::from_millis(500));
+
+        // The head is updated to the new block once imported, so validation uses its state
+        let start = Instant::now();
+        execution.update_head(slot + 1, Duration::from_secs(2)).await?;
+        assert!(start.elapsed() >= Duration::from_millis(400));
+        {
+            let mut state = execution.lock().await;
+            assert_eq!(state.block_number(), block_number);
+
+            let tx = default_test_transaction(*sender, None);
+            let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+            assert!(matches!(
+                state.validate_request(&mut request).await,
+                Err(ValidationError::NonceTooLow { .. })
+            ));
+        }
+
+        // If the execution client doesn't catch up in time, the head is updated anyway
+        let signed = default_test_transaction(*sender, Some(1)).build(&wallet).await?;
+        let pending = provider.send_raw_transaction(&signed.encoded_2718()).await?;
+        pending.get_receipt().await?;
+        fetcher.lag_for(Duration::from_secs(60));
+
+        execution.update_head(slot + 2, Duration::from_millis(200)).await?;
+        let state = execution.lock().await;
+        assert_eq!(state.slot(), slot + 2);
+        assert_eq!(state.block_number(), block_number);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_state_untouched() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+        let execution = SharedExecutionState::new(state);
+
+        let target_slot = 10;
+        let tx = default_test_transaction(*sender, None);
+        let request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+
+        // The verdict reports the capacity left in the slot once the request is committed
+        let verdict = execution.dry_run(&mut request.clone()).await?;
+        let limits = LimitsOpts::default();
+        let basefee = execution.lock().await.basefee();
+        assert!(verdict.ok);
+        assert_eq!(verdict.slot, target_slot);
+        assert_eq!(verdict.remaining_gas, limits.max_committed_gas_per_slot.get() - 21_000);
+        assert_eq!(verdict.remaining_constraints, limits.max_constraints_per_slot.get() - 1);
+        assert_eq!(verdict.remaining_blobs, MAX_BLOBS_PER_BLOCK);
+        assert_eq!(
+            Some(verdict.projected_basefee),
+            calculate_max_basefee(basefee, target_slot - slot)
+        );
+        assert_eq!(verdict.min_priority_fee, limits.min_priority_fee);
+
+        // Nothing is reserved for the request, which can still be committed
+        assert!(execution.lock().await.get_block_template(target_slot).is_none());
+        assert_eq!(execution.dry_run(&mut request.clone()).await?, verdict);
+
+        let mut committed = request.clone();
+        execution.validate_request(&mut committed).await?;
+        let message = ConstraintsMessage::build(Default::default(), committed.clone());
+        let signature = LocalSigner::random().sign_commit_boost_root(message.digest()).unwrap();
+        let constraints = vec![SignedConstraints { message, signature }];
+        execution.lock().await.commit_request(&committed, constraints)?;
+
+        // Once committed, its nonce is taken
+        assert!(matches!(
+            execution.dry_run(&mut request.clone()).await,
+            Err(ValidationError::NonceTooLow { .. })
         ));
 
         Ok(())
@@ -0,0 +1,491 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, TxHash},
+    rpc::types::{Transaction, TransactionReceipt, TransactionRequest},
+    transports::{TransportError, TransportErrorKind},
+};
+use parking_lot::Mutex;
+use reqwest::Url;
+use tracing::{debug, info, warn};
+
+use crate::{client::SimulatedCall, primitives::AccountState, telemetry::ApiMetrics};
+
+use super::{execution::StateUpdate, fetcher::StateFetcher, StateClient};
+
+/// Default time budget for a single request to an execution endpoint before failing over.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Default time budget for a batch of requests to an execution endpoint before failing over,
+/// e.g. the state update of many accounts.
+pub const DEFAULT_BATCH_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Default interval between two attempts to fail back to the primary endpoint.
+pub const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(12);
+
+/// An execution endpoint, with the name used to identify it in logs and metrics.
+#[derive(Debug)]
+struct Endpoint<F> {
+    name: String,
+    fetcher: F,
+}
+
+/// The failover state shared between clones of a [FailoverStateClient].
+#[derive(Debug)]
+struct FailoverState {
+    /// The index of the endpoint currently used to serve requests.
+    active: AtomicUsize,
+    /// The highest block number seen from any endpoint.
+    last_head: AtomicU64,
+    /// The last time the primary endpoint has been probed while inactive.
+    last_probe: Mutex<Instant>,
+}
+
+/// A [StateFetcher] spreading requests over a primary execution endpoint and a list
+/// of fallbacks, in order of preference.
+///
+/// - Requests are sent to the active endpoint, and fail over to the next ones on transport errors
+///   or when they exceed their time budget: per request, or per batch for the calls sending many
+///   requests at once.
+/// - Endpoints behind the highest block number seen so far are skipped when failing over, to never
+///   validate commitments against stale state.
+/// - While a fallback is active, the primary is probed at most once per probe interval and becomes
+///   active again as soon as it serves a request successfully.
+#[derive(Debug)]
+pub struct FailoverStateClient<F = StateClient> {
+    endpoints: Arc<[Endpoint<F>]>,
+    state: Arc<FailoverState>,
+    request_timeout: Duration,
+    batch_timeout: Duration,
+    probe_interval: Duration,
+}
+
+impl<F> Clone for FailoverStateClient<F> {
+    fn clone(&self) -> Self {
+        Self {
+            endpoints: Arc::clone(&self.endpoints),
+            state: Arc::clone(&self.state),
+            request_timeout: self.request_timeout,
+            batch_timeout: self.batch_timeout,
+            probe_interval: self.probe_interval,
+        }
+    }
+}
+
+impl FailoverStateClient<StateClient> {
+    /// Create a new failover client from the primary execution API URL and its fallbacks.
+    pub fn from_urls(primary: Url, fallbacks: impl IntoIterator<Item = Url>) -> Self {
+        let endpoints = std::iter::once(primary)
+            .chain(fallbacks)
+            .map(|url| (endpoint_name(&url), StateClient::new(url)))
+            .collect();
+
+        Self::new(endpoints)
+    }
+}
+
+impl<F> FailoverStateClient<F> {
+    /// Create a new failover client from a list of named fetchers. The first one is the primary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no endpoint is provided.
+    pub fn new(endpoints: Vec<(String, F)>) -> Self {
+        assert!(!endpoints.is_empty(), "at least one execution endpoint is required");
+
+        for (i, (name, _)) in endpoints.iter().enumerate() {
+            ApiMetrics::set_execution_endpoint_active(name, i == 0);
+        }
+
+        let endpoints =
+            endpoints.into_iter().map(|(name, fetcher)| Endpoint { name, fetcher }).collect();
+
+        Self {
+            endpoints,
+            state: Arc::new(FailoverState {
+                active: AtomicUsize::new(0),
+                last_head: AtomicU64::new(0),
+                last_probe: Mutex::new(Instant::now()),
+            }),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            batch_timeout: DEFAULT_BATCH_TIMEOUT,
+            probe_interval: DEFAULT_PROBE_INTERVAL,
+        }
+    }
+
+    /// Set the time budget for a single request to an execution endpoint.
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Set the time budget for a batch of requests to an execution endpoint.
+    pub fn with_batch_timeout(mut self, batch_timeout: Duration) -> Self {
+        self.batch_timeout = batch_timeout;
+        self
+    }
+
+    /// Set the interval between two attempts to fail back to the primary endpoint.
+    pub fn with_probe_interval(mut self, probe_interval: Duration) -> Self {
+        self.probe_interval = probe_interval;
+        self
+    }
+
+    /// Returns the name of the endpoint currently used to serve requests.
+    pub fn active_endpoint(&self) -> &str {
+        &self.endpoints[self.state.active.load(Ordering::Relaxed)].name
+    }
+
+    /// Returns the indexes of the endpoints in the order in which they should be tried.
+    fn candidates(&self) -> Vec<usize> {
+        let active = self.state.active.load(Ordering::Relaxed);
+
+        let preferred = if active != 0 && self.probe_due() { 0 } else { active };
+        let mut candidates = vec![preferred];
+        if preferred != active {
+            candidates.push(active);
+        }
+        candidates.extend((0..self.endpoints.len()).filter(|i| *i != preferred && *i != active));
+
+        candidates
+    }
+
+    /// Returns true if the primary should be probed, resetting the probe timer if so.
+    fn probe_due(&self) -> bool {
+        let mut last_probe = self.state.last_probe.lock();
+        if last_probe.elapsed() >= self.probe_interval {
+            *last_probe = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Record a block number seen from an endpoint.
+    fn observe_head(&self, block_number: u64) {
+        self.state.last_head.fetch_max(block_number, Ordering::Relaxed);
+    }
+
+    /// Make the endpoint at the given index the active one.
+    fn activate(&self, index: usize) {
+        let previous = self.state.active.swap(index, Ordering::Relaxed);
+        if previous == index {
+            return;
+        }
+
+        let (from, to) = (&self.endpoints[previous].name, &self.endpoints[index].name);
+        if index == 0 {
+            info!(%from, %to, "Failed back to the primary execution endpoint");
+        } else {
+            warn!(%from, %to, "Failed over to a fallback execution endpoint");
+        }
+
+        ApiMetrics::set_execution_endpoint_active(from, false);
+        ApiMetrics::set_execution_endpoint_active(to, true);
+    }
+}
+
+impl<F: StateFetcher + Send + Sync> FailoverStateClient<F> {
+    /// Run the given request against the endpoints in order of preference with the given time
+    /// budget, returning the first successful response or the last error if all of them failed.
+    async fn with_failover<'a, T, Fut>(
+        &'a self,
+        timeout: Duration,
+        request: impl Fn(&'a F) -> Fut + Send,
+    ) -> Result<T, TransportError>
+    where
+        Fut: Future<Output = Result<T, TransportError>> + Send + 'a,
+        T: Send,
+    {
+        let active = self.state.active.load(Ordering::Relaxed);
+        let mut last_error = None;
+
+        for index in self.candidates() {
+            let endpoint = &self.endpoints[index];
+
+            // Before switching endpoint, make sure it isn't lagging behind
+            if index != active {
+                let last_head = self.state.last_head.load(Ordering::Relaxed);
+                match tokio::time::timeout(self.request_timeout, endpoint.fetcher.get_head()).await
+                {
+                    Ok(Ok(head)) if head < last_head => {
+                        debug!(
+                            endpoint = %endpoint.name,
+                            head,
+                            last_head,
+                            "Skipping lagging execution endpoint"
+                        );
+                        continue;
+                    }
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => {
+                        debug!(endpoint = %endpoint.name, ?err, "Execution endpoint unavailable");
+                        last_error = Some(err);
+                        continue;
+                    }
+                    Err(_) => {
+                        debug!(endpoint = %endpoint.name, "Execution endpoint timed out");
+                        last_error = Some(timeout_error());
+                        continue;
+                    }
+                }
+            }
+
+            match tokio::time::timeout(timeout, request(&endpoint.fetcher)).await {
+                Ok(Ok(response)) => {
+                    self.activate(index);
+                    return Ok(response);
+                }
+                Ok(Err(err)) => {
+                    warn!(endpoint = %endpoint.name, ?err, "Request to execution endpoint failed");
+                    last_error = Some(err);
+                }
+                Err(_) => {
+                    warn!(endpoint = %endpoint.name, "Request to execution endpoint timed out");
+                    last_error = Some(timeout_error());
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            TransportErrorKind::Custom("no synced execution endpoint available".into()).into()
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: StateFetcher + Send + Sync> StateFetcher for FailoverStateClient<F> {
+    async fn get_state_update(
+        &self,
+        addresses: Vec<&Address>,
+        block_number: Option<u64>,
+    ) -> Result<StateUpdate, TransportError> {
+        let update = self
+            .with_failover(self.batch_timeout, |fetcher| {
+                fetcher.get_state_update(addresses.clone(), block_number)
+            })
+            .await?;
+
+        self.observe_head(update.block_number);
+        Ok(update)
+    }
+
+    async fn get_head(&self) -> Result<u64, TransportError> {
+        let head = self.with_failover(self.request_timeout, |fetcher| fetcher.get_head()).await?;
+
+        self.observe_head(head);
+        Ok(head)
+    }
+
+    async fn get_block_number(&self, tag: BlockNumberOrTag) -> Result<u64, TransportError> {
+        self.with_failover(self.request_timeout, |fetcher| fetcher.get_block_number(tag)).await
+    }
+
+    async fn get_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
+        self.with_failover(self.request_timeout, |fetcher| fetcher.get_basefee(block_number)).await
+    }
+
+    async fn get_blob_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
+        self.with_failover(self.request_timeout, |fetcher| fetcher.get_blob_basefee(block_number))
+            .await
+    }
+
+    async fn get_account_state(
+        &self,
+        address: &Address,
+        block_number: Option<u64>,
+    ) -> Result<AccountState, TransportError> {
+        self.with_failover(self.request_timeout, |fetcher| {
+            fetcher.get_account_state(address, block_number)
+        })
+        .await
+    }
+
+    async fn get_account_states(
+        &self,
+        addresses: &[Address],
+        block_number: Option<u64>,
+        with_code: bool,
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        self.with_failover(self.batch_timeout, |fetcher| {
+            fetcher.get_account_states(addresses, block_number, with_code)
+        })
+        .await
+    }
+
+    async fn get_pending_account_states(
+        &self,
+        addresses: &[Address],
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        self.with_failover(self.batch_timeout, |fetcher| {
+            fetcher.get_pending_account_states(addresses)
+        })
+        .await
+    }
+
+    async fn get_chain_id(&self) -> Result<u64, TransportError> {
+        self.with_failover(self.request_timeout, |fetcher| fetcher.get_chain_id()).await
+    }
+
+    async fn simulate_calls(
+        &self,
+        calls: &[TransactionRequest],
+    ) -> Result<Vec<SimulatedCall>, TransportError> {
+        self.with_failover(self.batch_timeout, |fetcher| fetcher.simulate_calls(calls)).await
+    }
+
+    async fn get_receipts_unordered(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<Vec<Option<TransactionReceipt>>, TransportError> {
+        self.with_failover(self.batch_timeout, |fetcher| fetcher.get_receipts_unordered(hashes))
+            .await
+    }
+
+    async fn get_pool_transactions(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<HashMap<TxHash, Transaction>, TransportError> {
+        self.with_failover(self.batch_timeout, |fetcher| fetcher.get_pool_transactions(hashes))
+            .await
+    }
+
+    async fn get_block_transaction_hashes(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<TxHash>, TransportError> {
+        self.with_failover(self.request_timeout, |fetcher| {
+            fetcher.get_block_transaction_hashes(block_number)
+        })
+        .await
+    }
+}
+
+/// The name of an endpoint used in logs and metrics. Only the host and port are kept,
+/// as the path of hosted RPC URLs often contains an API key.
+fn endpoint_name(url: &Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        _ => url.scheme().to_string(),
+    }
+}
+
+fn timeout_error() -> TransportError {
+    TransportErrorKind::Custom("execution endpoint request timed out".into()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{node_bindings::Anvil, primitives::U64};
+
+    use crate::{
+        client::RpcClient,
+        config::limits::LimitsOpts,
+        state::ExecutionState,
+        test_util::{create_signed_inclusion_request, default_test_transaction, TestFetcher},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_endpoint_name_strips_path() {
+        let url: Url = "https://mainnet.example.com/v3/secret-key".parse().unwrap();
+        assert_eq!(endpoint_name(&url), "mainnet.example.com");
+
+        let url: Url = "http://localhost:8545".parse().unwrap();
+        assert_eq!(endpoint_name(&url), "localhost:8545");
+    }
+
+    #[tokio::test]
+    async fn test_failover_keeps_validating_when_primary_goes_away() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let primary = Anvil::new().chain_id(1337).spawn();
+        let fallback = Anvil::new().chain_id(1337).spawn();
+        let primary_name = endpoint_name(&primary.endpoint_url());
+        let fallback_name = endpoint_name(&fallback.endpoint_url());
+
+        let client =
+            FailoverStateClient::from_urls(primary.endpoint_url(), [fallback.endpoint_url()])
+                .with_request_timeout(Duration::from_millis(500));
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = primary.addresses().first().unwrap();
+        let sender_pk = primary.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+        assert_eq!(client.active_endpoint(), primary_name);
+
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        // The primary goes away mid-test
+        drop(primary);
+
+        state.update_head(None, slot + 1).await?;
+        assert_eq!(client.active_endpoint(), fallback_name);
+
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_batch_requests_have_their_own_timeout() -> eyre::Result<()> {
+        let anvil = Anvil::new().spawn();
+        let sender = *anvil.addresses().first().unwrap();
+
+        // Every account lookup takes longer than a single request is allowed to
+        let fetcher = TestFetcher::new(StateClient::new(anvil.endpoint_url()))
+            .with_delay(Duration::from_millis(700));
+        let client = FailoverStateClient::new(vec![("primary".to_string(), fetcher)])
+            .with_request_timeout(Duration::from_millis(500))
+            .with_batch_timeout(Duration::from_secs(2));
+
+        let states = client.get_account_states(&[sender], None, false).await?;
+        assert!(states.contains_key(&sender));
+        assert!(client.get_account_state(&sender, None).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failover_skips_lagging_endpoints() -> eyre::Result<()> {
+        let primary = Anvil::new().spawn();
+        let lagging = Anvil::new().spawn();
+        let primary_name = endpoint_name(&primary.endpoint_url());
+
+        let client =
+            FailoverStateClient::from_urls(primary.endpoint_url(), [lagging.endpoint_url()])
+                .with_request_timeout(Duration::from_millis(500));
+
+        // Advance the primary past the fallback and observe its head
+        let rpc = RpcClient::new(primary.endpoint_url());
+        let _: serde_json::Value = rpc.request("anvil_mine", (U64::from(5),)).await?;
+        assert_eq!(client.get_head().await?, 5);
+
+        drop(primary);
+
+        // The only fallback is behind the last seen head, so it must not be used
+        assert!(client.get_head().await.is_err());
+        assert_eq!(client.active_endpoint(), primary_name);
+
+        Ok(())
+    }
+}
@@ -3,14 +3,17 @@ use std::{collections::HashMap, time::Duration};
 use alloy::{
     eips::BlockNumberOrTag,
     primitives::{Address, Bytes, TxHash, U256, U64},
-    rpc::types::TransactionReceipt,
+    rpc::types::{Transaction, TransactionReceipt, TransactionRequest},
     transports::TransportError,
 };
 use futures::{stream::FuturesOrdered, StreamExt};
 use reqwest::Url;
-use tracing::error;
+use tracing::{debug, error, instrument};
 
-use crate::{client::RpcClient, primitives::AccountState};
+use crate::{
+    client::{RpcClient, SimulatedCall},
+    primitives::AccountState,
+};
 
 use super::execution::StateUpdate;
 
@@ -33,6 +36,9 @@ pub trait StateFetcher {
     /// Get the head of the chain.
     async fn get_head(&self) -> Result<u64, TransportError>;
 
+    /// Get the number of the block with the given tag, e.g. the finalized or safe block.
+    async fn get_block_number(&self, tag: BlockNumberOrTag) -> Result<u64, TransportError>;
+
     /// Get the basefee of the latest block or the block at the specified number.
     async fn get_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError>;
 
@@ -46,15 +52,65 @@ pub trait StateFetcher {
         block_number: Option<u64>,
     ) -> Result<AccountState, TransportError>;
 
+    /// Get the account states for the specified addresses at the specified block number.
+    ///
+    /// If `with_code` is false, implementations may skip fetching the code of the accounts,
+    /// in which case `has_code` is left to `false`. The default implementation fetches
+    /// every account separately; implementations should batch the lookups when possible.
+    async fn get_account_states(
+        &self,
+        addresses: &[Address],
+        block_number: Option<u64>,
+        _with_code: bool,
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        let mut states = HashMap::with_capacity(addresses.len());
+        for address in addresses {
+            states.insert(*address, self.get_account_state(address, block_number).await?);
+        }
+
+        Ok(states)
+    }
+
+    /// Get the account states for the specified addresses in the pending block, which includes
+    /// the transactions in the mempool of the execution client. `has_code` is left to `false`.
+    ///
+    /// The default implementation returns the account states at the latest block.
+    async fn get_pending_account_states(
+        &self,
+        addresses: &[Address],
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        self.get_account_states(addresses, None, false).await
+    }
+
     /// Get the chain ID.
     async fn get_chain_id(&self) -> Result<u64, TransportError>;
 
+    /// Simulate the given calls in order, in a single block on top of the latest one.
+    /// Returns the outcome of every call, in the same order.
+    async fn simulate_calls(
+        &self,
+        calls: &[TransactionRequest],
+    ) -> Result<Vec<SimulatedCall>, TransportError>;
+
     /// Get the receipts for the said list of transaction hashes.
     /// IMPORTANT: order is not maintained in the result.
     async fn get_receipts_unordered(
         &self,
         hashes: &[TxHash],
     ) -> Result<Vec<Option<TransactionReceipt>>, TransportError>;
+
+    /// Get the transactions with the given hashes from the mempool of the execution client,
+    /// keyed by hash. The transactions that aren't found are missing from the result.
+    async fn get_pool_transactions(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<HashMap<TxHash, Transaction>, TransportError>;
+
+    /// Get the hashes of the transactions included in the block with the given number.
+    async fn get_block_transaction_hashes(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<TxHash>, TransportError>;
 }
 
 /// A basic state fetcher that uses an RPC client to fetch state updates.
@@ -74,6 +130,7 @@ impl StateClient {
 /// Get state updates for the specified block number or latest block if not provided.
 #[async_trait::async_trait]
 impl StateFetcher for StateClient {
+    #[instrument(skip_all)]
     async fn get_state_update(
         &self,
         addresses: Vec<&Address>,
@@ -175,18 +232,27 @@ impl StateFetcher for StateClient {
         })
     }
 
+    #[instrument(skip_all)]
     async fn get_head(&self) -> Result<u64, TransportError> {
         self.client.get_head().await
     }
 
+    #[instrument(skip_all)]
+    async fn get_block_number(&self, tag: BlockNumberOrTag) -> Result<u64, TransportError> {
+        self.client.get_block_number(tag).await
+    }
+
+    #[instrument(skip_all)]
     async fn get_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
         self.client.get_basefee(block_number).await
     }
 
+    #[instrument(skip_all)]
     async fn get_blob_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
         self.client.get_blob_basefee(block_number).await
     }
 
+    #[instrument(skip_all)]
     async fn get_account_state(
         &self,
         address: &Address,
@@ -210,16 +276,83 @@ impl StateFetcher for StateClient {
         }
     }
 
+    #[instrument(skip_all)]
+    async fn get_account_states(
+        &self,
+        addresses: &[Address],
+        block_number: Option<u64>,
+        with_code: bool,
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        self.client.get_account_states(addresses, block_number, with_code).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_pending_account_states(
+        &self,
+        addresses: &[Address],
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        self.client.get_account_states_at(addresses, BlockNumberOrTag::Pending, false).await
+    }
+
+    #[instrument(skip_all)]
     async fn get_chain_id(&self) -> Result<u64, TransportError> {
         self.client.get_chain_id().await
     }
 
+    #[instrument(skip_all)]
+    async fn simulate_calls(
+        &self,
+        calls: &[TransactionRequest],
+    ) -> Result<Vec<SimulatedCall>, TransportError> {
+        self.client.simulate(calls).await
+    }
+
+    #[instrument(skip_all)]
     async fn get_receipts_unordered(
         &self,
         hashes: &[TxHash],
     ) -> Result<Vec<Option<TransactionReceipt>>, TransportError> {
         self.client.get_receipts(hashes).await
     }
+
+    #[instrument(skip_all)]
+    async fn get_pool_transactions(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<HashMap<TxHash, Transaction>, TransportError> {
+        let txs = self.client.get_transactions_by_hash(hashes).await?;
+        let mut found = hashes
+            .iter()
+            .zip(txs)
+            .filter_map(|(hash, tx)| tx.map(|tx| (*hash, tx)))
+            .collect::<HashMap<_, _>>();
+
+        if found.len() < hashes.len() {
+            // Some execution clients don't serve the pending transactions by hash: look for
+            // the missing ones in the content of the mempool
+            match self.client.get_txpool_content().await {
+                Ok(mut content) => {
+                    for hash in hashes {
+                        if let Some(tx) = content.remove(hash) {
+                            found.entry(*hash).or_insert(tx);
+                        }
+                    }
+                }
+                Err(err) => debug!(?err, "Failed to fetch the content of the mempool"),
+            }
+        }
+
+        Ok(found)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_block_transaction_hashes(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<TxHash>, TransportError> {
+        let block = self.client.get_block(Some(block_number), false).await?;
+        Ok(block.transactions.hashes().collect())
+    }
 }
 
 #[cfg(test)]
@@ -250,4 +383,33 @@ mod tests {
         let basefee = client.get_basefee(None).await.unwrap();
         assert_eq!(basefee, 1_000_000_000);
     }
+
+    #[tokio::test]
+    #[ignore = "Benchmark, only for local development"]
+    async fn bench_account_states_batch() {
+        const ROUNDS: u32 = 50;
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(Url::parse(&anvil.endpoint()).unwrap());
+        let addresses = anvil.addresses().iter().take(5).copied().collect::<Vec<_>>();
+
+        // One lookup per sender, as done before the batched fetch
+        let start = std::time::Instant::now();
+        for _ in 0..ROUNDS {
+            for address in &addresses {
+                client.get_account_state(address, None).await.unwrap();
+            }
+        }
+        let sequential = start.elapsed() / ROUNDS;
+
+        // A single batched lookup for all the senders
+        let start = std::time::Instant::now();
+        for _ in 0..ROUNDS {
+            let states = client.get_account_states(&addresses, None, true).await.unwrap();
+            assert_eq!(states.len(), addresses.len());
+        }
+        let batched = start.elapsed() / ROUNDS;
+
+        println!("5 senders: sequential {sequential:?}, batched {batched:?}");
+    }
 }
@@ -0,0 +1,80 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use parking_lot::RwLock;
+use tracing::{info, warn};
+
+use super::SharedLimits;
+
+/// The gas limits registered by the validators with `registerValidator`, shared between the
+/// builder proxy caching the registrations and the driver building the local payloads.
+///
+/// The local payload of a slot targets the gas limit registered by its proposer, and the gas
+/// committed in it never exceeds that limit.
+#[derive(Debug, Clone, Default)]
+pub struct RegisteredGasLimits {
+    gas_limits: Arc<RwLock<HashMap<BlsPublicKey, u64>>>,
+    /// The operating limits, to flag the registrations below the committed gas cap.
+    limits: SharedLimits,
+}
+
+impl RegisteredGasLimits {
+    /// Create a new shared handle, checking the registrations against the given limits.
+    pub fn new(limits: SharedLimits) -> Self {
+        Self { gas_limits: Arc::default(), limits }
+    }
+
+    /// Returns the gas limit registered by the given validator, if any.
+    pub fn get(&self, pubkey: &BlsPublicKey) -> Option<u64> {
+        self.gas_limits.read().get(pubkey).copied()
+    }
+
+    /// Record the gas limit registered by the given validator. A new gas limit below the
+    /// maximum committed gas per slot is logged, as it caps the gas committed in its slots.
+    pub fn update(&self, pubkey: &BlsPublicKey, gas_limit: u64) {
+        let previous = self.gas_limits.write().insert(pubkey.clone(), gas_limit);
+        if previous == Some(gas_limit) {
+            return;
+        }
+
+        let max_committed_gas = self.limits.get().max_committed_gas_per_slot.get();
+        if gas_limit < max_committed_gas {
+            warn!(
+                %pubkey,
+                gas_limit,
+                max_committed_gas,
+                "Registered gas limit is below the maximum committed gas per slot: \
+                 commitments in the slots of this validator are capped to it"
+            );
+        } else if previous.is_some() {
+            info!(%pubkey, ?previous, gas_limit, "Registered gas limit changed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use crate::config::limits::LimitsOpts;
+
+    use super::*;
+
+    #[test]
+    fn test_registered_gas_limits() {
+        let limits = LimitsOpts {
+            max_committed_gas_per_slot: NonZero::new(10_000_000).unwrap(),
+            ..Default::default()
+        };
+        let gas_limits = RegisteredGasLimits::new(SharedLimits::new(limits));
+        let pubkey = BlsPublicKey::default();
+        assert_eq!(gas_limits.get(&pubkey), None);
+
+        gas_limits.update(&pubkey, 36_000_000);
+        assert_eq!(gas_limits.get(&pubkey), Some(36_000_000));
+
+        // The handle is shared, and the latest registration wins
+        gas_limits.clone().update(&pubkey, 8_000_000);
+        assert_eq!(gas_limits.get(&pubkey), Some(8_000_000));
+    }
+}
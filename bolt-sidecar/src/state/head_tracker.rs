@@ -1,17 +1,18 @@
-use alloy::rpc::types::beacon::events::HeadEvent;
+use alloy::rpc::types::beacon::events::{FinalizedCheckpointEvent, HeadEvent};
 use beacon_api_client::Topic;
+use ethereum_consensus::phase0::mainnet::SLOTS_PER_EPOCH;
 use futures::StreamExt;
 use std::time::Duration;
 use tokio::{sync::broadcast, task::AbortHandle, time::sleep};
 use tracing::warn;
 
-use crate::client::BeaconClient;
+use crate::{client::BeaconClient, primitives::Slot};
 
 /// The delay between retries when attempting to reconnect to the beacon client
 const RETRY_DELAY: Duration = Duration::from_secs(1);
 
-/// Simple actor to keep track of the most recent head of the beacon chain
-/// and broadcast updates to its subscribers.
+/// Simple actor to keep track of the most recent head and finalized checkpoint of the
+/// beacon chain and broadcast updates to its subscribers.
 ///
 /// Durability: the tracker will always attempt to reconnect to the provided
 /// beacon client URL in case of disconnection or other errors.
@@ -19,9 +20,11 @@ const RETRY_DELAY: Duration = Duration::from_secs(1);
 pub struct HeadTracker {
     /// Channel to receive updates of the "Head" beacon topic
     new_heads_rx: broadcast::Receiver<HeadEvent>,
-    /// Handle to the background task that listens for new head events.
+    /// Channel to receive updates of the "FinalizedCheckpoint" beacon topic
+    finalized_rx: broadcast::Receiver<FinalizedCheckpointEvent>,
+    /// Handles to the background tasks that listen for new events.
     /// Kept to allow for graceful shutdown.
-    quit: AbortHandle,
+    quit: Vec<AbortHandle>,
 }
 
 /// A topic for subscribing to new head events
@@ -34,49 +37,34 @@ impl Topic for NewHeadsTopic {
     type Data = HeadEvent;
 }
 
+/// A topic for subscribing to new finalized checkpoint events
+#[derive(Debug)]
+pub struct FinalizedCheckpointTopic;
+
+impl Topic for FinalizedCheckpointTopic {
+    const NAME: &'static str = "finalized_checkpoint";
+
+    type Data = FinalizedCheckpointEvent;
+}
+
 impl HeadTracker {
     /// Create a new `HeadTracker` with the given beacon client HTTP URL and
-    /// start listening for new head events in the background
+    /// start listening for new head and finalized checkpoint events in the background
     pub fn start(beacon_client: BeaconClient) -> Self {
         let (new_heads_tx, new_heads_rx) = broadcast::channel(32);
+        let (finalized_tx, finalized_rx) = broadcast::channel(8);
 
-        let task = tokio::spawn(async move {
-            loop {
-                let mut event_stream = match beacon_client.get_events::<NewHeadsTopic>().await {
-                    Ok(events) => events,
-                    Err(err) => {
-                        warn!(?err, "failed to subscribe to new heads topic, retrying...");
-                        sleep(RETRY_DELAY).await;
-                        continue;
-                    }
-                };
-
-                let event = match event_stream.next().await {
-                    Some(Ok(event)) => event,
-                    Some(Err(err)) => {
-                        warn!(?err, "error reading new head event stream, retrying...");
-                        sleep(RETRY_DELAY).await;
-                        continue;
-                    }
-                    None => {
-                        warn!("new head event stream ended, retrying...");
-                        sleep(RETRY_DELAY).await;
-                        continue;
-                    }
-                };
-
-                if let Err(err) = new_heads_tx.send(event) {
-                    warn!(?err, "failed to broadcast new head event to subscribers");
-                }
-            }
-        });
+        let quit = vec![
+            spawn_listener::<NewHeadsTopic>(beacon_client.clone(), new_heads_tx),
+            spawn_listener::<FinalizedCheckpointTopic>(beacon_client, finalized_tx),
+        ];
 
-        Self { new_heads_rx, quit: task.abort_handle() }
+        Self { new_heads_rx, finalized_rx, quit }
     }
 
     /// Stop the tracker and cleanup resources
     pub fn stop(self) {
-        self.quit.abort();
+        self.quit.iter().for_each(AbortHandle::abort);
     }
 
     /// Get the next head event from the tracker
@@ -84,6 +72,13 @@ impl HeadTracker {
         self.new_heads_rx.recv().await
     }
 
+    /// Get the next finalized checkpoint event from the tracker
+    pub async fn next_finalized(
+        &mut self,
+    ) -> Result<FinalizedCheckpointEvent, broadcast::error::RecvError> {
+        self.finalized_rx.recv().await
+    }
+
     /// Subscribe to new head events from the tracker
     ///
     /// The returned channel will NOT contain any previously emitted events cached in
@@ -91,6 +86,58 @@ impl HeadTracker {
     pub fn subscribe_new_heads(&self) -> broadcast::Receiver<HeadEvent> {
         self.new_heads_rx.resubscribe()
     }
+
+    /// Subscribe to new finalized checkpoint events from the tracker, with the same
+    /// semantics as [HeadTracker::subscribe_new_heads]
+    pub fn subscribe_finalized(&self) -> broadcast::Receiver<FinalizedCheckpointEvent> {
+        self.finalized_rx.resubscribe()
+    }
+}
+
+/// Returns the finalized slot of a finalized checkpoint event: the first slot of its epoch.
+pub fn finalized_slot(event: &FinalizedCheckpointEvent) -> Slot {
+    event.epoch * SLOTS_PER_EPOCH
+}
+
+/// Spawn a task listening to the events of the given beacon topic and broadcasting them.
+fn spawn_listener<T>(beacon_client: BeaconClient, tx: broadcast::Sender<T::Data>) -> AbortHandle
+where
+    T: Topic + 'static,
+    T::Data: Clone + Send + 'static,
+{
+    let task = tokio::spawn(async move {
+        let topic = T::NAME;
+        loop {
+            let mut event_stream = match beacon_client.get_events::<T>().await {
+                Ok(events) => events,
+                Err(err) => {
+                    warn!(?err, topic, "failed to subscribe to beacon topic, retrying...");
+                    sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            let event = match event_stream.next().await {
+                Some(Ok(event)) => event,
+                Some(Err(err)) => {
+                    warn!(?err, topic, "error reading beacon event stream, retrying...");
+                    sleep(RETRY_DELAY).await;
+                    continue;
+                }
+                None => {
+                    warn!(topic, "beacon event stream ended, retrying...");
+                    sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            if tx.send(event).is_err() {
+                warn!(topic, "failed to broadcast beacon event to subscribers");
+            }
+        }
+    });
+
+    task.abort_handle()
 }
 
 #[cfg(test)]
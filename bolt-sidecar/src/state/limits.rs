@@ -0,0 +1,261 @@
+use std::{fmt::Display, num::NonZero, sync::Arc};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+use crate::{
+    config::limits::{LimitsOpts, MAX_TX_SIZE},
+    telemetry::ApiMetrics,
+};
+
+/// The operating limits of the sidecar, shared between the execution state validating
+/// requests and the admin API updating them at runtime.
+///
+/// Updated limits only apply to the requests validated afterwards: the constraints
+/// already accepted for upcoming slots are never re-validated nor evicted.
+#[derive(Debug, Clone)]
+pub struct SharedLimits(Arc<RwLock<LimitsOpts>>);
+
+impl Default for SharedLimits {
+    fn default() -> Self {
+        Self::new(LimitsOpts::default())
+    }
+}
+
+impl SharedLimits {
+    /// Create a new shared handle to the given limits.
+    pub fn new(limits: LimitsOpts) -> Self {
+        ApiMetrics::set_limits(&limits);
+        Self(Arc::new(RwLock::new(limits)))
+    }
+
+    /// Returns a snapshot of the current limits.
+    pub fn get(&self) -> LimitsOpts {
+        *self.0.read()
+    }
+
+    /// Apply the given update, returning the new limits. Every change is logged.
+    pub fn update(&self, update: LimitsUpdate) -> Result<LimitsOpts, LimitsUpdateError> {
+        let mut limits = self.0.write();
+        let old = *limits;
+        let new = update.apply(old)?;
+
+        log_change(
+            "max_commitments_per_slot",
+            old.max_commitments_per_slot,
+            new.max_commitments_per_slot,
+        );
+        log_change(
+            "max_committed_gas_per_slot",
+            old.max_committed_gas_per_slot,
+            new.max_committed_gas_per_slot,
+        );
+        log_change(
+            "max_constraints_per_slot",
+            old.max_constraints_per_slot,
+            new.max_constraints_per_slot,
+        );
+        log_change(
+            "max_top_of_block_txs_per_slot",
+            old.max_top_of_block_txs_per_slot,
+            new.max_top_of_block_txs_per_slot,
+        );
+        log_change(
+            "max_top_of_block_gas_per_slot",
+            old.max_top_of_block_gas_per_slot,
+            new.max_top_of_block_gas_per_slot,
+        );
+        log_change("min_priority_fee", old.min_priority_fee, new.min_priority_fee);
+        log_change("max_tx_size", old.max_tx_size, new.max_tx_size);
+        log_cap_change("max_gas_per_tx", old.max_gas_per_tx, new.max_gas_per_tx);
+        log_cap_change("max_calldata_bytes", old.max_calldata_bytes, new.max_calldata_bytes);
+
+        *limits = new;
+        ApiMetrics::set_limits(&new);
+
+        Ok(new)
+    }
+}
+
+/// An update of the operating limits. Only the limits present are updated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsUpdate {
+    /// Max number of commitments to accept per block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_commitments_per_slot: Option<NonZero<usize>>,
+    /// Max committed gas per slot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_committed_gas_per_slot: Option<NonZero<u64>>,
+    /// Max number of constrained transactions to accept per block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_constraints_per_slot: Option<NonZero<usize>>,
+    /// Max number of transactions to accept at the top of the block per slot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_top_of_block_txs_per_slot: Option<usize>,
+    /// Max gas to accept at the top of the block per slot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_top_of_block_gas_per_slot: Option<u64>,
+    /// Min priority fee to accept for a commitment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_priority_fee: Option<u128>,
+    /// Max size in bytes of a single transaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tx_size: Option<NonZero<usize>>,
+    /// Max gas limit of a single transaction, removed with `null`
+    #[serde(
+        default,
+        deserialize_with = "deserialize_cap",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_gas_per_tx: Option<Option<NonZero<u64>>>,
+    /// Max size in bytes of the calldata of a single transaction, removed with `null`
+    #[serde(
+        default,
+        deserialize_with = "deserialize_cap",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_calldata_bytes: Option<Option<NonZero<usize>>>,
+}
+
+impl LimitsUpdate {
+    /// Returns the given limits with this update applied.
+    pub fn apply(self, limits: LimitsOpts) -> Result<LimitsOpts, LimitsUpdateError> {
+        if self == Self::default() {
+            return Err(LimitsUpdateError::Empty);
+        }
+
+        if let Some(size) = self.max_tx_size {
+            if size.get() > MAX_TX_SIZE {
+                return Err(LimitsUpdateError::MaxTxSizeTooHigh(size.get()));
+            }
+        }
+
+        Ok(LimitsOpts {
+            max_commitments_per_slot: self
+                .max_commitments_per_slot
+                .unwrap_or(limits.max_commitments_per_slot),
+            max_committed_gas_per_slot: self
+                .max_committed_gas_per_slot
+                .unwrap_or(limits.max_committed_gas_per_slot),
+            max_constraints_per_slot: self
+                .max_constraints_per_slot
+                .unwrap_or(limits.max_constraints_per_slot),
+            max_top_of_block_txs_per_slot: self
+                .max_top_of_block_txs_per_slot
+                .unwrap_or(limits.max_top_of_block_txs_per_slot),
+            max_top_of_block_gas_per_slot: self
+                .max_top_of_block_gas_per_slot
+                .unwrap_or(limits.max_top_of_block_gas_per_slot),
+            min_priority_fee: self.min_priority_fee.unwrap_or(limits.min_priority_fee),
+            max_tx_size: self.max_tx_size.unwrap_or(limits.max_tx_size),
+            max_gas_per_tx: self.max_gas_per_tx.unwrap_or(limits.max_gas_per_tx),
+            max_calldata_bytes: self.max_calldata_bytes.unwrap_or(limits.max_calldata_bytes),
+        })
+    }
+}
+
+/// Error updating the operating limits.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LimitsUpdateError {
+    /// The update doesn't contain any limit.
+    #[error("Empty limits update")]
+    Empty,
+    /// The max transaction size is higher than the allowed maximum.
+    #[error("Max tx size {0} can't be higher than {MAX_TX_SIZE} bytes")]
+    MaxTxSizeTooHigh(usize),
+}
+
+/// Deserialize an optional cap of an update, so that a `null` cap removes it while a missing
+/// cap leaves it untouched.
+fn deserialize_cap<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+fn log_change<T: PartialEq + Display>(limit: &str, old: T, new: T) {
+    if old != new {
+        info!(limit, %old, %new, "Updated limit");
+    }
+}
+
+fn log_cap_change<T: PartialEq + Display>(limit: &str, old: Option<T>, new: Option<T>) {
+    let display = |cap: Option<T>| cap.map_or_else(|| "none".to_string(), |cap| cap.to_string());
+    if old != new {
+        log_change(limit, display(old), display(new));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_update() {
+        let limits = SharedLimits::default();
+        let clone = limits.clone();
+
+        let update: LimitsUpdate =
+            serde_json::from_str(r#"{ "max_committed_gas_per_slot": 1000000 }"#).unwrap();
+        let new = limits.update(update).unwrap();
+
+        assert_eq!(new.max_committed_gas_per_slot.get(), 1_000_000);
+        assert_eq!(clone.get(), new);
+
+        // Other limits are untouched
+        let default = LimitsOpts::default();
+        assert_eq!(new.max_commitments_per_slot, default.max_commitments_per_slot);
+        assert_eq!(new.min_priority_fee, default.min_priority_fee);
+        assert_eq!(new.max_tx_size, default.max_tx_size);
+    }
+
+    #[test]
+    fn test_invalid_limits_update() {
+        let limits = SharedLimits::default();
+
+        assert_eq!(limits.update(LimitsUpdate::default()), Err(LimitsUpdateError::Empty));
+
+        let update = LimitsUpdate {
+            max_tx_size: NonZero::new(MAX_TX_SIZE + 1),
+            max_commitments_per_slot: NonZero::new(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.update(update),
+            Err(LimitsUpdateError::MaxTxSizeTooHigh(MAX_TX_SIZE + 1))
+        );
+
+        // Invalid updates don't apply partially
+        assert_eq!(limits.get(), LimitsOpts::default());
+
+        // Zero values and unknown limits are rejected when parsing
+        assert!(serde_json::from_str::<LimitsUpdate>(r#"{ "max_tx_size": 0 }"#).is_err());
+        assert!(serde_json::from_str::<LimitsUpdate>(r#"{ "max_gas": 1 }"#).is_err());
+        assert!(serde_json::from_str::<LimitsUpdate>(r#"{ "max_gas_per_tx": 0 }"#).is_err());
+    }
+
+    #[test]
+    fn test_per_tx_caps_update() {
+        let limits = SharedLimits::default();
+        assert_eq!(limits.get().max_gas_per_tx, None);
+
+        let update: LimitsUpdate =
+            serde_json::from_str(r#"{ "max_gas_per_tx": 1000000, "max_calldata_bytes": 4096 }"#)
+                .unwrap();
+        let new = limits.update(update).unwrap();
+        assert_eq!(new.max_gas_per_tx, NonZero::new(1_000_000));
+        assert_eq!(new.max_calldata_bytes, NonZero::new(4096));
+
+        // A missing cap is left untouched, while a null cap is removed
+        let update: LimitsUpdate =
+            serde_json::from_str(r#"{ "max_calldata_bytes": null }"#).unwrap();
+        let new = limits.update(update).unwrap();
+        assert_eq!(new.max_gas_per_tx, NonZero::new(1_000_000));
+        assert_eq!(new.max_calldata_bytes, None);
+    }
+}
@@ -9,21 +9,82 @@ use tokio::time::Sleep;
 
 /// Module to perform state validation.
 mod execution;
-pub use execution::{ExecutionState, ValidationError};
+pub use execution::{
+    ExecutionState, SharedExecutionState, StateUpdate, TemplateReader, ValidationError,
+};
 
 /// Module to fetch state from the Execution layer.
 pub mod fetcher;
 pub use fetcher::StateClient;
 
+/// Module to cache the state fetched from the Execution layer.
+pub mod cache;
+pub use cache::CachedStateFetcher;
+
+/// Module to fail over between multiple execution endpoints.
+pub mod failover;
+pub use failover::FailoverStateClient;
+
 /// Module to track the consensus state.
 pub mod consensus;
 pub use consensus::ConsensusState;
 
+/// Module to decide when the commitments for a slot stop being accepted.
+pub mod deadline;
+pub use deadline::{DeadlinePolicy, DutyDistanceDeadline, FixedDeadline, SlotTiming};
+
+/// Module to monitor the offset of the local clock relative to the chain.
+pub mod clock_skew;
+pub use clock_skew::ClockSkewMonitor;
+
+/// Module to track the operator collateral and gate commitments on it.
+pub mod collateral;
+pub use collateral::CollateralTracker;
+
+/// Module to keep track of the validators verified in the BoltManager contract.
+pub mod validators;
+pub use validators::{EffectiveValidators, VerifiedValidators};
+
+/// Module to refuse commitments on transactions interacting with denylisted addresses.
+pub mod denylist;
+pub use denylist::Denylist;
+
+/// Module to share the operating limits and update them at runtime.
+pub mod limits;
+pub use limits::{LimitsUpdate, SharedLimits};
+
+/// Module to share the gas limits registered by the validators.
+pub mod gas_limits;
+pub use gas_limits::RegisteredGasLimits;
+
+/// Module to record the slots whose constraints are sealed at the commitment deadline.
+pub mod sealing;
+pub use sealing::{SealedSlot, SealedSlots};
+
+/// Module to stream the accepted constraints to builders.
+pub mod constraints_stream;
+pub use constraints_stream::{ConstraintsStream, ConstraintsSubscription};
+
+/// Module to persist the signed commitments for later dispute resolution.
+pub mod archive;
+pub use archive::{ArchivedCommitment, CommitmentArchive, DailySummary};
+
+/// Module to hold the commitment requests waiting for an earlier nonce of their sender.
+pub mod nonce_gaps;
+pub use nonce_gaps::NonceGapQueue;
+
+/// Module to remember the transactions included in the latest blocks.
+pub mod recent_inclusions;
+pub use recent_inclusions::RecentInclusions;
+
 /// Module to track the head of the chain.
 pub mod head_tracker;
 pub use head_tracker::HeadTracker;
 
 /// The deadline for a which a commitment is considered valid.
+///
+/// A deadline is only armed for the slots the sidecar can commit to. Once reached or
+/// cancelled, or if created disarmed, it never resolves.
 #[derive(Debug)]
 pub struct CommitmentDeadline {
     slot: u64,
@@ -37,8 +98,29 @@ impl CommitmentDeadline {
         Self { slot, sleep }
     }
 
-    /// Poll the deadline until it is reached.
-    pub async fn wait(&mut self) -> Option<u64> {
+    /// Create a disarmed deadline for a given slot, e.g. without any proposer duty in it.
+    pub fn disarmed(slot: u64) -> Self {
+        Self { slot, sleep: None }
+    }
+
+    /// Returns the slot of the deadline.
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    /// Returns true if the deadline is armed, i.e. if it will resolve once reached.
+    pub fn is_armed(&self) -> bool {
+        self.sleep.is_some()
+    }
+
+    /// Disarm the deadline, e.g. on a reorg or on shutdown.
+    pub fn cancel(&mut self) {
+        self.sleep = None;
+    }
+
+    /// Wait for the deadline to be reached, returning its slot. The deadline is disarmed
+    /// afterwards, so that it only resolves once.
+    pub async fn wait(&mut self) -> u64 {
         let slot = poll_fn(|cx| self.poll_unpin(cx)).await;
         self.sleep = None;
         slot
@@ -47,18 +129,18 @@ impl CommitmentDeadline {
 
 /// Poll the deadline until it is reached.
 ///
-/// - If already reached, the future will return `None` immediately.
-/// - If not reached, the future will return `Some(slot)` when the deadline is reached.
+/// - If disarmed, the future never resolves.
+/// - If armed, the future will return the slot when the deadline is reached.
 impl Future for CommitmentDeadline {
-    type Output = Option<u64>;
+    type Output = u64;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let Some(ref mut sleep) = self.sleep else {
-            return Poll::Ready(None);
+            return Poll::Pending;
         };
 
         match sleep.poll_unpin(cx) {
-            Poll::Ready(_) => Poll::Ready(Some(self.slot)),
+            Poll::Ready(_) => Poll::Ready(self.slot),
             Poll::Pending => Poll::Pending,
         }
     }
@@ -66,6 +148,7 @@ impl Future for CommitmentDeadline {
 
 #[cfg(test)]
 mod tests {
+    use tokio::time::timeout;
 
     use super::*;
 
@@ -76,11 +159,20 @@ mod tests {
 
         let slot = deadline.wait().await;
         println!("Deadline reached. Passed {:?}", time.elapsed());
-        assert_eq!(slot, Some(0));
+        assert_eq!(slot, 0);
 
-        let time = std::time::Instant::now();
-        let slot = deadline.wait().await;
-        println!("Deadline reached. Passed {:?}", time.elapsed());
-        assert_eq!(slot, None);
+        // A reached deadline doesn't resolve again
+        assert!(!deadline.is_armed());
+        assert!(timeout(Duration::from_millis(100), deadline.wait()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commitment_deadline_disarmed() {
+        let mut deadline = CommitmentDeadline::disarmed(5);
+        assert!(timeout(Duration::from_millis(100), deadline.wait()).await.is_err());
+
+        let mut deadline = CommitmentDeadline::new(5, Duration::from_millis(50));
+        deadline.cancel();
+        assert!(timeout(Duration::from_millis(100), deadline.wait()).await.is_err());
     }
 }
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use alloy::primitives::Address;
+
+use crate::primitives::Slot;
+
+/// The maximum number of requests of a single sender waiting in the [NonceGapQueue].
+pub const MAX_QUEUED_REQUESTS_PER_SENDER: usize = 8;
+
+/// The maximum number of requests waiting in the [NonceGapQueue].
+pub const MAX_QUEUED_REQUESTS: usize = 256;
+
+/// A short-lived queue of commitment requests rejected only because their nonce is too high,
+/// waiting for the requests with the earlier nonces of the same sender to be committed.
+///
+/// Requests are keyed by sender, target slot and nonce, and can be taken back in nonce order
+/// once an earlier nonce of the same sender is committed for the slot. The queue is bounded
+/// per sender and globally.
+#[derive(Debug)]
+pub struct NonceGapQueue<T> {
+    requests: BTreeMap<(Address, Slot, u64), T>,
+    max_per_sender: usize,
+    max_total: usize,
+}
+
+impl<T> Default for NonceGapQueue<T> {
+    fn default() -> Self {
+        Self::new(MAX_QUEUED_REQUESTS_PER_SENDER, MAX_QUEUED_REQUESTS)
+    }
+}
+
+impl<T> NonceGapQueue<T> {
+    /// Create a new queue with the given bounds.
+    pub fn new(max_per_sender: usize, max_total: usize) -> Self {
+        Self { requests: BTreeMap::new(), max_per_sender, max_total }
+    }
+
+    /// Queue a request of the given sender, target slot and nonce.
+    ///
+    /// The request is handed back if the queue is full, for the sender or globally, or if a
+    /// request with the same key is already queued.
+    pub fn push(&mut self, sender: Address, slot: Slot, nonce: u64, request: T) -> Result<(), T> {
+        let queued_by_sender =
+            self.requests.range((sender, 0, 0)..=(sender, Slot::MAX, u64::MAX)).count();
+
+        if self.requests.len() >= self.max_total ||
+            queued_by_sender >= self.max_per_sender ||
+            self.requests.contains_key(&(sender, slot, nonce))
+        {
+            return Err(request);
+        }
+
+        self.requests.insert((sender, slot, nonce), request);
+        Ok(())
+    }
+
+    /// Take the requests of the given sender for the given slot, in nonce order.
+    pub fn take(&mut self, sender: Address, slot: Slot) -> Vec<T> {
+        let keys = self
+            .requests
+            .range((sender, slot, 0)..=(sender, slot, u64::MAX))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+
+        keys.into_iter().filter_map(|key| self.requests.remove(&key)).collect()
+    }
+
+    /// Take all the queued requests, in nonce order for every sender.
+    pub fn take_all(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.requests).into_values().collect()
+    }
+
+    /// Take the requests targeting the given slot or an earlier one, which expire when the
+    /// commitment deadline of the slot is reached.
+    pub fn take_expired(&mut self, slot: Slot) -> Vec<T> {
+        let (expired, kept) = std::mem::take(&mut self.requests)
+            .into_iter()
+            .partition::<BTreeMap<_, _>, _>(|((_, target_slot, _), _)| *target_slot <= slot);
+
+        self.requests = kept;
+        expired.into_values().collect()
+    }
+
+    /// Returns the number of queued requests.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns true if no request is queued.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_gap_queue() {
+        let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let mut queue = NonceGapQueue::new(2, 3);
+
+        assert!(queue.push(alice, 10, 8, "alice-8").is_ok());
+        assert!(queue.push(alice, 10, 7, "alice-7").is_ok());
+        assert!(queue.push(bob, 11, 3, "bob-3").is_ok());
+
+        // Bounded per sender and globally
+        assert_eq!(queue.push(alice, 11, 9, "alice-9"), Err("alice-9"));
+        assert_eq!(queue.push(bob, 11, 4, "bob-4"), Err("bob-4"));
+        assert_eq!(queue.len(), 3);
+
+        // Requests are taken back in nonce order, only for the given sender and slot
+        assert!(queue.take(alice, 11).is_empty());
+        assert_eq!(queue.take(alice, 10), vec!["alice-7", "alice-8"]);
+        assert!(queue.take(alice, 10).is_empty());
+
+        // Duplicates are refused
+        assert!(queue.push(alice, 12, 1, "alice-1").is_ok());
+        assert_eq!(queue.push(alice, 12, 1, "alice-1-again"), Err("alice-1-again"));
+
+        // Requests expire at the deadline of their slot
+        assert_eq!(queue.take_expired(11), vec!["bob-3"]);
+        assert_eq!(queue.take_all(), vec!["alice-1"]);
+        assert!(queue.is_empty());
+    }
+}
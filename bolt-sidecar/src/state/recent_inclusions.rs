@@ -0,0 +1,110 @@
+use std::collections::{HashMap, VecDeque};
+
+use alloy::primitives::TxHash;
+
+/// The number of recent blocks whose transactions are kept by default.
+pub const RECENT_INCLUSION_BLOCKS: usize = 8;
+
+/// A bounded ring of the transaction hashes included in the latest blocks, keyed by block
+/// number, to reject requests for transactions that are already on chain.
+///
+/// Once full, the oldest block is evicted for every new one. A block at or below the latest
+/// one replaces it and every later block, as after a reorg.
+#[derive(Debug)]
+pub struct RecentInclusions {
+    /// The transaction hashes of the cached blocks, from the oldest to the latest.
+    blocks: VecDeque<(u64, Vec<TxHash>)>,
+    /// The block number of every cached transaction hash.
+    included: HashMap<TxHash, u64>,
+    capacity: usize,
+}
+
+impl Default for RecentInclusions {
+    fn default() -> Self {
+        Self::new(RECENT_INCLUSION_BLOCKS)
+    }
+}
+
+impl RecentInclusions {
+    /// Create a new ring holding the transactions of at most `capacity` blocks.
+    pub fn new(capacity: usize) -> Self {
+        Self { blocks: VecDeque::with_capacity(capacity), included: HashMap::new(), capacity }
+    }
+
+    /// Returns the number of the latest cached block, if any.
+    pub fn latest_block(&self) -> Option<u64> {
+        self.blocks.back().map(|(number, _)| *number)
+    }
+
+    /// Returns the maximum number of cached blocks.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Cache the transaction hashes of the given block, evicting the oldest block if full.
+    pub fn insert(&mut self, block_number: u64, hashes: Vec<TxHash>) {
+        // A reorged block replaces the cached one and its descendants
+        while self.latest_block().is_some_and(|latest| latest >= block_number) {
+            self.pop(false);
+        }
+
+        for hash in &hashes {
+            self.included.insert(*hash, block_number);
+        }
+        self.blocks.push_back((block_number, hashes));
+
+        while self.blocks.len() > self.capacity {
+            self.pop(true);
+        }
+    }
+
+    /// Returns the number of the cached block that includes the given transaction, if any.
+    pub fn block_of(&self, hash: &TxHash) -> Option<u64> {
+        self.included.get(hash).copied()
+    }
+
+    /// Remove the oldest or latest cached block.
+    fn pop(&mut self, oldest: bool) {
+        let popped = if oldest { self.blocks.pop_front() } else { self.blocks.pop_back() };
+        let Some((number, hashes)) = popped else { return };
+
+        // The same transaction can't be included twice, but a reorg can move it
+        for hash in hashes {
+            if self.included.get(&hash) == Some(&number) {
+                self.included.remove(&hash);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_inclusions_roll_over() {
+        let mut inclusions = RecentInclusions::new(2);
+        let hashes = (1..=4).map(TxHash::repeat_byte).collect::<Vec<_>>();
+        assert_eq!(inclusions.latest_block(), None);
+
+        inclusions.insert(10, vec![hashes[0]]);
+        inclusions.insert(11, vec![hashes[1]]);
+        assert_eq!(inclusions.block_of(&hashes[0]), Some(10));
+        assert_eq!(inclusions.block_of(&hashes[1]), Some(11));
+
+        // The oldest block is evicted once the ring is full
+        inclusions.insert(12, vec![hashes[2]]);
+        assert_eq!(inclusions.block_of(&hashes[0]), None);
+        assert_eq!(inclusions.block_of(&hashes[2]), Some(12));
+        assert_eq!(inclusions.latest_block(), Some(12));
+
+        // A reorged block replaces the cached one, and a transaction can move to it
+        inclusions.insert(12, vec![hashes[3], hashes[1]]);
+        assert_eq!(inclusions.block_of(&hashes[2]), None);
+        assert_eq!(inclusions.block_of(&hashes[1]), Some(12));
+        inclusions.insert(13, Vec::new());
+        assert_eq!(inclusions.block_of(&hashes[1]), Some(12));
+        assert_eq!(inclusions.block_of(&hashes[3]), Some(12));
+        assert_eq!(inclusions.latest_block(), Some(13));
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy::{
+    primitives::B256,
+    signers::k256::sha2::{Digest, Sha256},
+};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{debug, error, warn};
+
+use crate::{crypto::SignableBLS, primitives::SignedConstraints};
+
+/// The maximum number of sealed slots kept in memory. Older slots are pruned first.
+const MAX_SEALED_SLOTS: usize = 64;
+
+/// The capacity of the sealing events channel.
+const SEALED_EVENTS_CAPACITY: usize = 64;
+
+/// The name of the file of the sealed slots within the data directory.
+const SEALED_SLOTS_FILE: &str = "sealed_slots.json";
+
+/// A slot whose constraint set has been sealed at the commitment deadline. No constraint
+/// can be added to it afterwards, so builders can start building on top of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedSlot {
+    /// The sealed slot.
+    pub slot: u64,
+    /// The hash of the canonical constraint list of the slot: the SHA256 hash of the
+    /// concatenated digests of the signed constraints, in the order they were accepted.
+    pub constraints_hash: B256,
+    /// The number of signed constraints in the slot.
+    pub constraints_count: usize,
+}
+
+impl SealedSlot {
+    /// Create a new sealed slot record from the final list of signed constraints.
+    pub fn new(slot: u64, constraints: &[SignedConstraints]) -> Self {
+        let mut hasher = Sha256::new();
+        for signed_constraints in constraints {
+            hasher.update(signed_constraints.message.digest());
+        }
+
+        Self {
+            slot,
+            constraints_hash: B256::from_slice(&hasher.finalize()),
+            constraints_count: constraints.len(),
+        }
+    }
+}
+
+/// A cheaply cloneable registry of the sealed slots, notifying subscribers
+/// every time a new slot is sealed.
+///
+/// When opened in a data directory, the registry is persisted there so that slots are
+/// sealed exactly once across restarts.
+#[derive(Debug, Clone)]
+pub struct SealedSlots {
+    slots: Arc<RwLock<BTreeMap<u64, SealedSlot>>>,
+    events: broadcast::Sender<SealedSlot>,
+    path: Option<Arc<PathBuf>>,
+}
+
+impl Default for SealedSlots {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(SEALED_EVENTS_CAPACITY);
+        Self { slots: Arc::default(), events, path: None }
+    }
+}
+
+impl SealedSlots {
+    /// Open the registry persisted in the given data directory, restoring the slots sealed
+    /// before the last restart.
+    pub fn open<P: AsRef<Path>>(data_dir: P) -> io::Result<Self> {
+        fs::create_dir_all(data_dir.as_ref())?;
+        let path = data_dir.as_ref().join(SEALED_SLOTS_FILE);
+
+        let sealed: Vec<SealedSlot> = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+
+        if let Some(last) = sealed.last() {
+            debug!(count = sealed.len(), last = last.slot, "Restored the sealed slots");
+        }
+
+        let slots = sealed.into_iter().map(|sealed| (sealed.slot, sealed)).collect();
+        Ok(Self {
+            slots: Arc::new(RwLock::new(slots)),
+            path: Some(Arc::new(path)),
+            ..Self::default()
+        })
+    }
+
+    /// Seal the given slot with its final list of signed constraints.
+    ///
+    /// A slot is sealed exactly once: if it was already sealed, the existing record is kept
+    /// and `None` is returned.
+    pub fn seal(&self, slot: u64, constraints: &[SignedConstraints]) -> Option<SealedSlot> {
+        let sealed = {
+            let mut slots = self.slots.write();
+            if let Some(existing) = slots.get(&slot) {
+                warn!(slot, hash = %existing.constraints_hash, "Slot already sealed, ignoring");
+                return None;
+            }
+
+            let sealed = SealedSlot::new(slot, constraints);
+            slots.insert(slot, sealed.clone());
+
+            while slots.len() > MAX_SEALED_SLOTS {
+                slots.pop_first();
+            }
+
+            self.persist(&slots);
+            sealed
+        };
+
+        debug!(slot, hash = %sealed.constraints_hash, count = sealed.constraints_count, "Sealed slot");
+
+        // An error only means that there are no subscribers
+        let _ = self.events.send(sealed.clone());
+
+        Some(sealed)
+    }
+
+    /// Prune the records of the slots before the given finalized slot. Returns the number of
+    /// pruned records.
+    pub fn prune_finalized(&self, finalized_slot: u64) -> usize {
+        let mut slots = self.slots.write();
+        let kept = slots.split_off(&finalized_slot);
+        let pruned = std::mem::replace(&mut *slots, kept).len();
+
+        if pruned > 0 {
+            debug!(finalized_slot, pruned, "Pruned finalized sealed slots");
+            self.persist(&slots);
+        }
+
+        pruned
+    }
+
+    /// Returns the latest sealed slot, if any.
+    pub fn last_sealed(&self) -> Option<u64> {
+        self.slots.read().last_key_value().map(|(slot, _)| *slot)
+    }
+
+    /// Write the given sealed slots to disk if the registry is persisted, replacing the
+    /// previous file atomically.
+    fn persist(&self, slots: &BTreeMap<u64, SealedSlot>) {
+        let Some(path) = self.path.as_deref() else {
+            return;
+        };
+
+        let write = || -> io::Result<()> {
+            let tmp = path.with_extension("json.tmp");
+            fs::write(&tmp, serde_json::to_vec(&slots.values().collect::<Vec<_>>())?)?;
+            fs::rename(tmp, path)
+        };
+
+        if let Err(err) = write() {
+            error!(?err, path = %path.display(), "Failed to persist the sealed slots");
+        }
+    }
+
+    /// Returns the sealed record of the given slot, if it has been sealed.
+    pub fn get(&self, slot: u64) -> Option<SealedSlot> {
+        self.slots.read().get(&slot).cloned()
+    }
+
+    /// Subscribe to the slots sealed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<SealedSlot> {
+        self.events.subscribe()
+    }
+
+    /// Wait until the given slot is sealed, up to the given timeout. Returns immediately
+    /// if the slot is already sealed, and `None` if it isn't sealed before the timeout.
+    pub async fn wait_sealed(&self, slot: u64, timeout: Duration) -> Option<SealedSlot> {
+        // Subscribe before checking the registry so that a concurrent seal can't be missed
+        let mut events = self.subscribe();
+
+        if let Some(sealed) = self.get(slot) {
+            return Some(sealed);
+        }
+
+        let wait = async {
+            loop {
+                match events.recv().await {
+                    Ok(sealed) if sealed.slot == slot => return Some(sealed),
+                    Ok(_) => continue,
+                    // Some events were missed, the slot may have been sealed in between
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        if let Some(sealed) = self.get(slot) {
+                            return Some(sealed);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, wait).await.ok().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::bytes, rpc::types::beacon::events::FinalizedCheckpointEvent};
+    use serde_json::json;
+
+    use crate::{
+        primitives::{ChainHead, ConstraintsMessage, FullTransaction},
+        state::head_tracker::finalized_slot,
+    };
+
+    use super::*;
+
+    fn signed_constraints(slot: u64) -> SignedConstraints {
+        let tx_bytes = bytes!("f8678085019dc6838082520894deaddeaddeaddeaddeaddeaddeaddeaddeaddead38808360306ca06664c078fa60bd3ece050903dd295949908dd9686ec8871fa558f868e031cd39a00ed4f0b122b32b73f19230fabe6a726e2d07f84eda5beaa42a1ae1271bdee39f").to_vec();
+        let tx = FullTransaction::decode_enveloped(tx_bytes.as_slice()).unwrap();
+
+        let message = ConstraintsMessage::from_tx(Default::default(), slot, tx);
+        SignedConstraints { message, signature: Default::default() }
+    }
+
+    #[test]
+    fn test_slot_is_sealed_once() {
+        let sealed_slots = SealedSlots::default();
+        let constraints = vec![signed_constraints(10)];
+
+        let sealed = sealed_slots.seal(10, &constraints).expect("first seal");
+        assert_eq!(sealed.constraints_count, 1);
+        assert_eq!(sealed, SealedSlot::new(10, &constraints));
+
+        // A second seal with a different set doesn't override the first one
+        assert!(sealed_slots.seal(10, &[]).is_none());
+        assert_eq!(sealed_slots.get(10), Some(sealed));
+    }
+
+    #[test]
+    fn test_sealed_slots_are_pruned() {
+        let sealed_slots = SealedSlots::default();
+        for slot in 0..(MAX_SEALED_SLOTS as u64 + 1) {
+            sealed_slots.seal(slot, &[]);
+        }
+
+        assert!(sealed_slots.get(0).is_none());
+        assert!(sealed_slots.get(MAX_SEALED_SLOTS as u64).is_some());
+    }
+
+    #[test]
+    fn test_sealed_slots_are_pruned_at_finalization() {
+        let sealed_slots = SealedSlots::default();
+        let chain_head = ChainHead::default();
+        for slot in 30..70 {
+            sealed_slots.seal(slot, &[]);
+        }
+
+        let finalize = |epoch: u64| {
+            let event: FinalizedCheckpointEvent = serde_json::from_value(json!({
+                "block": B256::repeat_byte(epoch as u8),
+                "state": B256::ZERO,
+                "epoch": epoch.to_string(),
+                "execution_optimistic": false,
+            }))
+            .unwrap();
+
+            let slot = finalized_slot(&event);
+            if chain_head.update_finalized(slot, epoch * 32) {
+                sealed_slots.prune_finalized(slot)
+            } else {
+                0
+            }
+        };
+
+        // Nothing is pruned before the first finalized slot
+        assert_eq!(finalize(0), 0);
+        assert!(sealed_slots.get(30).is_some());
+
+        // The boundary is the first slot of the finalized epoch
+        assert_eq!(finalize(1), 2);
+        assert!(sealed_slots.get(31).is_none());
+        assert_eq!(sealed_slots.get(32).map(|sealed| sealed.slot), Some(32));
+
+        // Stale and repeated checkpoints don't prune anything
+        assert_eq!(finalize(1), 0);
+        assert_eq!(finalize(2), 32);
+        assert!(sealed_slots.get(63).is_none());
+        assert!(sealed_slots.get(64).is_some());
+        assert_eq!(chain_head.finalized_slot(), 64);
+    }
+
+    #[test]
+    fn test_sealed_slots_survive_restart() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let sealed_slots = SealedSlots::open(dir.path())?;
+        let sealed = sealed_slots.seal(10, &[signed_constraints(10)]).expect("first seal");
+        sealed_slots.seal(11, &[]);
+        drop(sealed_slots);
+
+        // After a restart, the slots are still sealed with their original constraints
+        let sealed_slots = SealedSlots::open(dir.path())?;
+        assert_eq!(sealed_slots.get(10), Some(sealed.clone()));
+        assert_eq!(sealed_slots.last_sealed(), Some(11));
+        assert!(sealed_slots.seal(10, &[]).is_none());
+
+        // Pruned slots stay pruned
+        sealed_slots.prune_finalized(11);
+        let sealed_slots = SealedSlots::open(dir.path())?;
+        assert!(sealed_slots.get(10).is_none());
+        assert!(sealed_slots.get(11).is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wait_sealed() {
+        let sealed_slots = SealedSlots::default();
+
+        let waiter = {
+            let sealed_slots = sealed_slots.clone();
+            tokio::spawn(async move { sealed_slots.wait_sealed(10, Duration::from_secs(5)).await })
+        };
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sealed_slots.seal(9, &[]);
+        let sealed = sealed_slots.seal(10, &[signed_constraints(10)]);
+
+        assert_eq!(waiter.await.unwrap(), sealed);
+
+        // Unsealed slots time out
+        assert!(sealed_slots.wait_sealed(11, Duration::from_millis(10)).await.is_none());
+    }
+}
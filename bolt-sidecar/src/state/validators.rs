@@ -0,0 +1,389 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use alloy::primitives::Address;
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use parking_lot::RwLock;
+use tokio::{task::JoinHandle, time::Instant};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    chain_io::{manager::VerificationReport, BoltManager},
+    telemetry::ApiMetrics,
+};
+
+/// The set of validators verified in the BoltManager contract: registered in Bolt, active,
+/// and authorized to the operator of the sidecar.
+///
+/// Without any verification, e.g. if on-chain checks are disabled, every validator is
+/// considered verified. The set is cheap to clone: every clone shares the same keys.
+#[derive(Debug, Clone, Default)]
+pub struct VerifiedValidators(Arc<RwLock<Option<HashSet<BlsPublicKey>>>>);
+
+impl VerifiedValidators {
+    /// Returns true if the given validator is verified.
+    pub fn contains(&self, pubkey: &BlsPublicKey) -> bool {
+        self.0.read().as_ref().map_or(true, |keys| keys.contains(pubkey))
+    }
+
+    /// Returns the number of verified validators, if they have been verified.
+    pub fn count(&self) -> Option<usize> {
+        self.0.read().as_ref().map(HashSet::len)
+    }
+
+    /// Replace the verified validators with the ones of the given report, logging the ones
+    /// that dropped out.
+    pub fn update(&self, report: &VerificationReport) {
+        let keys = report.verified.iter().cloned().collect::<HashSet<_>>();
+
+        let mut current = self.0.write();
+        if let Some(current) = current.as_ref() {
+            for pubkey in current.difference(&keys) {
+                warn!(%pubkey, "Validator is no longer verified in Bolt, rejecting its commitments");
+            }
+        }
+
+        ApiMetrics::set_verified_validators(keys.len());
+        ApiMetrics::set_excluded_validators(report.unverified_count());
+        *current = Some(keys);
+    }
+
+    /// Verify the given validators in the BoltManager contract and record the verified ones.
+    pub async fn verify(
+        &self,
+        manager: &BoltManager,
+        keys: Vec<BlsPublicKey>,
+        operator: Address,
+    ) -> eyre::Result<VerificationReport> {
+        let start = Instant::now();
+        let report = manager.verify_validators(keys, operator).await?;
+        ApiMetrics::observe_validators_verification(start.elapsed());
+
+        self.update(&report);
+        Ok(report)
+    }
+
+    /// Verify the given validators in the BoltManager contract when starting the sidecar.
+    ///
+    /// In strict mode, this fails if any of them can't be verified. Otherwise, the unverified
+    /// validators are logged and excluded from commitments.
+    pub async fn verify_at_startup(
+        &self,
+        manager: &BoltManager,
+        keys: Vec<BlsPublicKey>,
+        operator: Address,
+        allow_unverified: bool,
+    ) -> eyre::Result<VerificationReport> {
+        let report = self.verify(manager, keys, operator).await?;
+
+        if !allow_unverified {
+            report.ensure_all_verified(operator)?;
+        } else if report.unverified_count() > 0 {
+            report.log_unverified();
+            warn!(
+                verified = report.verified.len(),
+                excluded = report.unverified_count(),
+                "Some validators couldn't be verified in Bolt, excluding them from commitments"
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Spawn a background task verifying the given validators every `interval`, starting
+    /// after the first interval. The current keys of the set are verified every time, so that
+    /// the validators added to it are verified too.
+    pub fn spawn_verifier(
+        &self,
+        manager: BoltManager,
+        keys: EffectiveValidators,
+        operator: Address,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let validators = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(Instant::now() + interval, interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                let keys = keys.keys();
+                match validators.verify(&manager, keys.clone(), operator).await {
+                    Ok(report) => {
+                        let verified = report.verified.len();
+                        debug!(verified, total = keys.len(), "Re-verified validators")
+                    }
+                    Err(err) => {
+                        error!(?err, "Failed to re-verify validators, keeping the last set")
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// The validators the sidecar acts for: the ones of the delegations file or of the constraint
+/// signer, minus the excluded ones. Requests are only accepted for the slots of their proposer
+/// duties.
+///
+/// The keys are updated when a remote signer lists new ones, with the exclusions applied
+/// again. The set is cheap to clone: every clone shares the same keys.
+#[derive(Debug, Clone, Default)]
+pub struct EffectiveValidators(Arc<RwLock<EffectiveKeys>>);
+
+#[derive(Debug, Default)]
+struct EffectiveKeys {
+    keys: HashSet<BlsPublicKey>,
+    excluded: HashSet<BlsPublicKey>,
+}
+
+impl FromIterator<BlsPublicKey> for EffectiveValidators {
+    fn from_iter<I: IntoIterator<Item = BlsPublicKey>>(keys: I) -> Self {
+        let keys = EffectiveKeys { keys: keys.into_iter().collect(), excluded: HashSet::new() };
+        Self(Arc::new(RwLock::new(keys)))
+    }
+}
+
+impl EffectiveValidators {
+    /// Returns true if the sidecar acts for the given validator.
+    pub fn contains(&self, pubkey: &BlsPublicKey) -> bool {
+        self.0.read().keys.contains(pubkey)
+    }
+
+    /// Returns the number of validators.
+    pub fn len(&self) -> usize {
+        self.0.read().keys.len()
+    }
+
+    /// Returns true if the sidecar acts for no validator.
+    pub fn is_empty(&self) -> bool {
+        self.0.read().keys.is_empty()
+    }
+
+    /// Returns the public keys of the validators.
+    pub fn keys(&self) -> Vec<BlsPublicKey> {
+        self.0.read().keys.iter().cloned().collect()
+    }
+
+    /// Exclude the given validators, now and on every update.
+    pub fn exclude(&self, excluded: HashSet<BlsPublicKey>) {
+        let mut current = self.0.write();
+        current.keys.retain(|pubkey| !excluded.contains(pubkey));
+        current.excluded = excluded;
+        ApiMetrics::set_effective_validators(current.keys.len());
+    }
+
+    /// Replace the validators with the given ones, minus the excluded ones. Returns true if
+    /// the set changed, logging the validators added and removed.
+    pub fn update(&self, keys: impl IntoIterator<Item = BlsPublicKey>) -> bool {
+        let mut current = self.0.write();
+        let keys = keys
+            .into_iter()
+            .filter(|pubkey| !current.excluded.contains(pubkey))
+            .collect::<HashSet<_>>();
+        if keys == current.keys {
+            return false;
+        }
+
+        for pubkey in keys.difference(&current.keys) {
+            info!(%pubkey, "New validator key listed by the signer, accepting its commitments");
+        }
+        for pubkey in current.keys.difference(&keys) {
+            warn!(%pubkey, "Validator key no longer listed by the signer, rejecting its commitments");
+        }
+
+        ApiMetrics::set_effective_validators(keys.len());
+        current.keys = keys;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::FixedBytes,
+        sol_types::{SolCall, SolError},
+    };
+    use serde_json::{json, Value};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    use crate::chain_io::{
+        manager::BoltManagerContract::{
+            getProposerStatusesCall, ProposerStatus, ValidatorDoesNotExist,
+        },
+        utils::pubkey_hash,
+    };
+
+    use super::*;
+
+    const OPERATOR: Address = Address::repeat_byte(0x42);
+
+    fn test_keys(count: usize) -> Vec<BlsPublicKey> {
+        (0..count)
+            .map(|i| {
+                let mut bytes = [0u8; 48];
+                bytes[..8].copy_from_slice(&(i as u64 + 1).to_be_bytes());
+                BlsPublicKey::try_from(bytes.as_ref()).unwrap()
+            })
+            .collect()
+    }
+
+    /// Start a mock EL node answering `getProposerStatuses` calls like the BoltManager contract,
+    /// and recording the number of keys of every call.
+    ///
+    /// All the validators are active with the same operator, except the inactive and mismatched
+    /// ones, and calls including a missing validator revert.
+    async fn mock_registry(
+        inactive: HashSet<FixedBytes<20>>,
+        missing: HashSet<FixedBytes<20>>,
+        mismatched: HashSet<FixedBytes<20>>,
+    ) -> (MockServer, Arc<RwLock<Vec<usize>>>) {
+        let calls = Arc::new(RwLock::new(Vec::new()));
+        let recorded = calls.clone();
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(move |req: &Request| {
+                let body = serde_json::from_slice::<Value>(&req.body).unwrap();
+                let mut response = json!({ "jsonrpc": "2.0", "id": body["id"], "result": "0x1" });
+
+                if body["method"] == "eth_call" {
+                    let params = &body["params"][0];
+                    let input = params["input"].as_str().or(params["data"].as_str()).unwrap();
+                    let input = alloy::hex::decode(input).unwrap();
+                    let hashes =
+                        getProposerStatusesCall::abi_decode(&input, true).unwrap().pubkeyHashes;
+                    recorded.write().push(hashes.len());
+
+                    if let Some(hash) = hashes.iter().find(|hash| missing.contains(*hash)) {
+                        let data = ValidatorDoesNotExist { pubkeyHash: *hash }.abi_encode();
+                        response["error"] = json!({
+                            "code": 3,
+                            "message": "execution reverted",
+                            "data": format!("0x{}", alloy::hex::encode(data)),
+                        });
+                        response.as_object_mut().unwrap().remove("result");
+                    } else {
+                        let statuses = hashes
+                            .into_iter()
+                            .map(|pubkeyHash| ProposerStatus {
+                                pubkeyHash,
+                                active: !inactive.contains(&pubkeyHash),
+                                operator: if mismatched.contains(&pubkeyHash) {
+                                    Address::ZERO
+                                } else {
+                                    OPERATOR
+                                },
+                                ..Default::default()
+                            })
+                            .collect();
+                        let returndata = getProposerStatusesCall::abi_encode_returns(&(statuses,));
+                        response["result"] = json!(format!("0x{}", alloy::hex::encode(returndata)));
+                    }
+                }
+
+                ResponseTemplate::new(200).set_body_json(response)
+            })
+            .mount(&server)
+            .await;
+
+        (server, calls)
+    }
+
+    #[tokio::test]
+    async fn test_verify_validators_in_chunks() -> eyre::Result<()> {
+        let keys = test_keys(1_000);
+        let inactive = HashSet::from([pubkey_hash(&keys[0]), pubkey_hash(&keys[999])]);
+        let missing = HashSet::from([pubkey_hash(&keys[500])]);
+        let (server, calls) = mock_registry(inactive, missing, HashSet::new()).await;
+
+        let manager =
+            BoltManager::from_address(server.uri().parse::<reqwest::Url>()?, Address::ZERO);
+        let validators = VerifiedValidators::default();
+
+        // Before any verification, every validator is allowed
+        assert!(validators.contains(&keys[0]));
+
+        let report = validators.verify(&manager, keys.clone(), OPERATOR).await?;
+        assert_eq!(report.verified.len(), 997);
+        assert_eq!(report.unregistered, vec![keys[500].clone()]);
+        assert_eq!(report.inactive.len(), 2);
+        assert_eq!(validators.count(), Some(997));
+        assert!(validators.contains(&keys[1]));
+        assert!(!validators.contains(&keys[0]));
+        assert!(!validators.contains(&keys[500]));
+
+        // The keys are queried in chunks of 100, and the chunk with the missing validator
+        // is queried again without it
+        let sizes = calls.read().clone();
+        assert_eq!(sizes.len(), 11);
+        assert_eq!(sizes.iter().filter(|&&size| size == 100).count(), 10);
+        assert!(sizes.contains(&99));
+
+        // The startup verification fails on the missing and inactive validators
+        let res = manager.verify_validator_pubkeys(keys.clone(), OPERATOR).await;
+        assert!(res.unwrap_err().to_string().contains("ValidatorDoesNotExist"));
+        let res = manager.verify_validator_pubkeys(keys[..2].to_vec(), OPERATOR).await;
+        assert!(res.unwrap_err().to_string().contains("is not active in Bolt"));
+
+        // Validators authorized to another operator drop out on the next verification
+        let report = validators.verify(&manager, keys.clone(), Address::ZERO).await?;
+        assert!(report.verified.is_empty());
+        assert_eq!(report.operator_mismatch.len(), 997);
+        assert!(!validators.contains(&keys[1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_effective_validators_updated() {
+        let keys = test_keys(4);
+        let validators = keys[..2].iter().cloned().collect::<EffectiveValidators>();
+        validators.exclude(HashSet::from([keys[1].clone()]));
+        assert!(validators.contains(&keys[0]) && !validators.contains(&keys[1]));
+
+        // New keys are added, with the exclusions applied again
+        assert!(validators.clone().update(keys.clone()));
+        assert_eq!(validators.len(), 3);
+        assert!(!validators.contains(&keys[1]));
+        assert!(validators.contains(&keys[2]) && validators.contains(&keys[3]));
+        assert!(!validators.update(keys.clone()));
+
+        // Removed keys are dropped
+        assert!(validators.update(keys[2..].to_vec()));
+        assert!(!validators.contains(&keys[0]));
+        assert_eq!(validators.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unverified_validators_at_startup() -> eyre::Result<()> {
+        let keys = test_keys(5);
+        let (server, _) = mock_registry(
+            HashSet::from([pubkey_hash(&keys[1])]),
+            HashSet::from([pubkey_hash(&keys[2])]),
+            HashSet::from([pubkey_hash(&keys[3])]),
+        )
+        .await;
+        let manager =
+            BoltManager::from_address(server.uri().parse::<reqwest::Url>()?, Address::ZERO);
+
+        // Strict mode fails on the first unverified validator
+        let validators = VerifiedValidators::default();
+        let res = validators.verify_at_startup(&manager, keys.clone(), OPERATOR, false).await;
+        assert!(res.unwrap_err().to_string().contains("ValidatorDoesNotExist"));
+
+        // Otherwise the unverified validators are reported and excluded
+        let validators = VerifiedValidators::default();
+        let report = validators.verify_at_startup(&manager, keys.clone(), OPERATOR, true).await?;
+        assert_eq!(report.unverified_count(), 3);
+        assert_eq!(report.inactive, vec![keys[1].clone()]);
+        assert_eq!(report.unregistered, vec![keys[2].clone()]);
+        assert_eq!(report.operator_mismatch, vec![(keys[3].clone(), Address::ZERO)]);
+
+        assert_eq!(validators.count(), Some(2));
+        assert!(validators.contains(&keys[0]) && validators.contains(&keys[4]));
+        assert!(keys[1..4].iter().all(|key| !validators.contains(key)));
+
+        Ok(())
+    }
+}
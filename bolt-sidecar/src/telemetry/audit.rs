@@ -0,0 +1,252 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::primitives::B256;
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use serde::{Deserialize, Serialize};
+
+use crate::{crypto::bls::BLSSig, primitives::Slot};
+
+/// Default maximum size of a single audit log file before it gets rotated (100 MiB).
+pub const DEFAULT_AUDIT_LOG_MAX_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Default number of rotated audit log files to keep around.
+pub const DEFAULT_AUDIT_LOG_MAX_FILES: usize = 10;
+
+/// A single entry of the audit log, recording a BLS signature produced by the sidecar.
+///
+/// Entries are serialized as newline-delimited JSON (NDJSON) so that they can be
+/// processed line by line by external tools, such as `bolt audit verify`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix timestamp in milliseconds of when the signature was produced.
+    pub timestamp: u64,
+    /// The slot the signed constraints are valid for.
+    pub slot: Slot,
+    /// The public key that produced the signature.
+    pub pubkey: BlsPublicKey,
+    /// The digest (object root) that has been signed.
+    pub digest: B256,
+    /// The resulting BLS signature.
+    pub signature: BLSSig,
+    /// The correlation ID of the request that triggered the signature.
+    pub request_id: B256,
+    /// The signer backend that produced the signature (e.g. "local", "keystore").
+    pub signer: String,
+}
+
+impl AuditEntry {
+    /// Create a new audit entry timestamped with the current system time.
+    pub fn new(
+        slot: Slot,
+        pubkey: BlsPublicKey,
+        digest: B256,
+        signature: BLSSig,
+        request_id: B256,
+        signer: &str,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+
+        Self { timestamp, slot, pubkey, digest, signature, request_id, signer: signer.to_string() }
+    }
+}
+
+/// An append-only audit log of every BLS signature produced by the sidecar.
+///
+/// The log is kept separate from the general application logs and it's written
+/// as NDJSON. Writes are buffered, and the file is fsync'd every time it gets rotated
+/// so that rotated files are always durable on disk.
+///
+/// Rotation follows the usual scheme: `audit.log` -> `audit.log.1` -> `audit.log.2` ...
+/// up to `max_files`, after which the oldest file is discarded.
+#[derive(Debug)]
+pub struct AuditLog {
+    /// The path of the active log file.
+    path: PathBuf,
+    /// The buffered writer over the active log file.
+    writer: BufWriter<File>,
+    /// The number of bytes written to the active log file.
+    written: u64,
+    /// The maximum size of the active log file before rotating it.
+    max_size: u64,
+    /// The maximum number of rotated files to keep.
+    max_files: usize,
+}
+
+impl AuditLog {
+    /// Open (or create) the audit log at the given path, appending to any existing content.
+    pub fn open<P: AsRef<Path>>(path: P, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(Self { path, writer: BufWriter::new(file), written, max_size, max_files })
+    }
+
+    /// Returns the path of the active log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a new entry to the log, rotating the file first if it has grown too large.
+    pub fn append(&mut self, entry: &AuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        if self.written > 0 && self.written + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(&line)?;
+        // Hand the entry over to the OS right away: it's cheap compared to an fsync and
+        // it guarantees that a crash of the sidecar doesn't lose any recorded signature.
+        self.writer.flush()?;
+        self.written += line.len() as u64;
+
+        Ok(())
+    }
+
+    /// Rotate the active log file, shifting the existing rotated files by one.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+
+        if self.max_files == 0 {
+            // No history to keep: just truncate the active file.
+            let file = OpenOptions::new().write(true).truncate(true).open(&self.path)?;
+            self.writer = BufWriter::new(file);
+            self.written = 0;
+            return Ok(());
+        }
+
+        // Drop the oldest file and shift every other one by one position
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for i in (1..self.max_files).rev() {
+            let from = self.rotated_path(i);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(i + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.written = 0;
+
+        Ok(())
+    }
+
+    /// Returns the path of the rotated file with the given index.
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+}
+
+impl Drop for AuditLog {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+        let _ = self.writer.get_ref().sync_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+
+    use alloy::primitives::B256;
+
+    use crate::{
+        crypto::SignableBLS, primitives::ConstraintsMessage, signer::local::LocalSigner,
+        test_util::default_inclusion_request,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_audit_log_entries_roundtrip() {
+        let signer = LocalSigner::random();
+        let request = default_inclusion_request();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+
+        let mut log = AuditLog::open(&path, DEFAULT_AUDIT_LOG_MAX_SIZE, 1).unwrap();
+
+        for tx in &request.txs {
+            let message = ConstraintsMessage::from_tx(signer.pubkey(), request.slot, tx.clone());
+            let digest = message.digest();
+            let signature = signer.sign_commit_boost_root(digest).unwrap();
+
+            let entry = AuditEntry::new(
+                request.slot,
+                signer.pubkey(),
+                B256::from(digest),
+                signature,
+                request.digest(),
+                "local",
+            );
+            log.append(&entry).unwrap();
+        }
+        drop(log);
+
+        let file = File::open(&path).unwrap();
+        let entries = BufReader::new(file)
+            .lines()
+            .map(|line| serde_json::from_str::<AuditEntry>(&line.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(entries.len(), request.txs.len());
+        for entry in entries {
+            assert_eq!(entry.slot, request.slot);
+            assert_eq!(entry.pubkey, signer.pubkey());
+            assert_eq!(entry.request_id, request.digest());
+            assert_eq!(entry.signer, "local");
+
+            let sig = blst::min_pk::Signature::from_bytes(entry.signature.as_ref()).unwrap();
+            assert!(signer.verify_commit_boost_root(entry.digest.0, &sig).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_audit_log_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let entry = AuditEntry::new(
+            1,
+            BlsPublicKey::default(),
+            B256::ZERO,
+            BLSSig::ZERO,
+            B256::ZERO,
+            "local",
+        );
+        let entry_size = serde_json::to_vec(&entry).unwrap().len() as u64 + 1;
+
+        // Room for exactly two entries per file, keeping two rotated files around
+        let mut log = AuditLog::open(&path, entry_size * 2, 2).unwrap();
+        for _ in 0..7 {
+            log.append(&entry).unwrap();
+        }
+        drop(log);
+
+        let count_lines = |p: &Path| fs::read_to_string(p).unwrap().lines().count();
+        assert_eq!(count_lines(&path), 1);
+        assert_eq!(count_lines(&PathBuf::from(format!("{}.1", path.display()))), 2);
+        assert_eq!(count_lines(&PathBuf::from(format!("{}.2", path.display()))), 2);
+        assert!(!PathBuf::from(format!("{}.3", path.display())).exists());
+    }
+}
@@ -0,0 +1,460 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::Address;
+use futures::future::join_all;
+use parking_lot::RwLock;
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::{
+    api::spec::STATUS_PATH, chain_io::manager::OperatorStatus, config::Opts, telemetry::ApiMetrics,
+};
+
+/// Default interval between two rounds of health checks, in seconds (one slot).
+pub const DEFAULT_HEALTH_CHECK_INTERVAL_SECS: u64 = 12;
+
+/// The timeout for a single health check request.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The beacon node API path used to check its health.
+const BEACON_SYNCING_PATH: &str = "/eth/v1/node/syncing";
+
+/// The kind of an upstream endpoint, which determines how it's probed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetKind {
+    /// A beacon node API, probed with `GET /eth/v1/node/syncing`.
+    Beacon,
+    /// An execution client JSON-RPC API, probed with `eth_syncing`.
+    Execution,
+    /// A Constraints API relay, probed with its builder status endpoint.
+    Constraints,
+}
+
+/// An upstream endpoint to probe periodically.
+#[derive(Debug, Clone)]
+pub struct HealthTarget {
+    /// The name of the target, used as the metrics label.
+    pub name: String,
+    /// The kind of the target.
+    pub kind: TargetKind,
+    /// The base URL of the target.
+    pub url: Url,
+}
+
+impl HealthTarget {
+    /// Create a new health target.
+    pub fn new(name: impl Into<String>, kind: TargetKind, url: Url) -> Self {
+        Self { name: name.into(), kind, url }
+    }
+}
+
+/// The result of the latest health check of a target.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetStatus {
+    /// The name of the target.
+    pub name: String,
+    /// The kind of the target.
+    pub kind: TargetKind,
+    /// Whether the target responded successfully to the latest check.
+    pub reachable: bool,
+    /// The latency of the latest check in milliseconds.
+    pub latency_ms: u64,
+    /// The error returned by the latest check, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The status of the operator in the BoltManager contract, as of the latest successful poll.
+#[derive(Debug, Clone, Serialize)]
+pub struct OperatorHealth {
+    /// The address of the operator, i.e. the commitment signer.
+    pub address: Address,
+    /// The registration status and collateral of the operator.
+    #[serde(flatten)]
+    pub status: OperatorStatus,
+    /// The UNIX timestamp in seconds of the latest successful poll.
+    pub updated_at: u64,
+    /// The number of failed polls since the latest successful one.
+    pub consecutive_failures: u32,
+    /// Whether the latest successful poll is too old to be trusted.
+    pub stale: bool,
+}
+
+impl OperatorHealth {
+    /// Returns true if the operator is active and collateralized according to fresh data.
+    pub fn is_healthy(&self) -> bool {
+        !self.stale && self.status.is_active() && self.status.is_collateralized()
+    }
+}
+
+/// The health of the sidecar and of all of its upstream endpoints.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthStatus {
+    /// Whether all the targets are reachable, the operator is healthy and the sidecar isn't
+    /// waiting for the beacon node.
+    pub healthy: bool,
+    /// Whether the sidecar is running in degraded mode, waiting for the beacon node to become
+    /// reachable at startup.
+    pub waiting_for_beacon: bool,
+    /// The status of every target.
+    pub targets: Vec<TargetStatus>,
+    /// The status of the operator in Bolt, if it's tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<OperatorHealth>,
+}
+
+impl Default for HealthStatus {
+    fn default() -> Self {
+        // Until the first round of checks completes, assume everything is fine
+        Self { healthy: true, waiting_for_beacon: false, targets: Vec::new(), operator: None }
+    }
+}
+
+/// A cheaply cloneable handle to read the latest health status reported by a [HealthProber],
+/// along with the operator status reported by the collateral tracker.
+#[derive(Debug, Clone, Default)]
+pub struct HealthHandle {
+    status: Arc<RwLock<HealthStatus>>,
+    operator: Arc<RwLock<Option<OperatorHealth>>>,
+    waiting_for_beacon: Arc<AtomicBool>,
+}
+
+impl HealthHandle {
+    /// Returns true if all the upstream endpoints were reachable in the latest checks, the
+    /// operator is healthy if it's tracked, and the sidecar isn't waiting for the beacon node.
+    pub fn is_healthy(&self) -> bool {
+        self.status.read().healthy && self.is_operator_healthy() && !self.is_waiting_for_beacon()
+    }
+
+    /// Returns a snapshot of the latest health status.
+    pub fn status(&self) -> HealthStatus {
+        let mut status = self.status.read().clone();
+        status.operator = self.operator.read().clone();
+        status.waiting_for_beacon = self.is_waiting_for_beacon();
+        status.healthy &= status.operator.as_ref().map_or(true, OperatorHealth::is_healthy) &&
+            !status.waiting_for_beacon;
+        status
+    }
+
+    /// Returns true if the sidecar is waiting for the beacon node to become reachable at
+    /// startup, in which case commitment requests can't be processed.
+    pub fn is_waiting_for_beacon(&self) -> bool {
+        self.waiting_for_beacon.load(Ordering::Relaxed)
+    }
+
+    /// Record whether the sidecar is waiting for the beacon node.
+    pub fn set_waiting_for_beacon(&self, waiting: bool) {
+        self.waiting_for_beacon.store(waiting, Ordering::Relaxed);
+        ApiMetrics::set_sidecar_healthy(self.is_healthy());
+    }
+
+    /// Record the latest status of the operator.
+    pub fn set_operator(&self, operator: OperatorHealth) {
+        *self.operator.write() = Some(operator);
+    }
+
+    fn is_operator_healthy(&self) -> bool {
+        self.operator.read().as_ref().map_or(true, OperatorHealth::is_healthy)
+    }
+}
+
+/// A lightweight prober that periodically checks the reachability of the upstream
+/// endpoints of the sidecar, exposing the results as Prometheus gauges and through
+/// a [HealthHandle].
+#[derive(Debug)]
+pub struct HealthProber {
+    client: reqwest::Client,
+    targets: Vec<HealthTarget>,
+    interval: Duration,
+    handle: HealthHandle,
+}
+
+impl HealthProber {
+    /// Create a new prober for the given targets.
+    pub fn new(targets: Vec<HealthTarget>, interval: Duration) -> Self {
+        let client = reqwest::ClientBuilder::new()
+            .user_agent("bolt-sidecar")
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .build()
+            .expect("valid client");
+
+        Self { client, targets, interval, handle: HealthHandle::default() }
+    }
+
+    /// Create a new prober for the beacon, execution and constraints endpoints in [Opts],
+    /// including the relays the validator registrations are forwarded to.
+    pub fn from_opts(opts: &Opts) -> Self {
+        let mut targets = vec![
+            HealthTarget::new("beacon", TargetKind::Beacon, opts.beacon_api_url.clone()),
+            HealthTarget::new("execution", TargetKind::Execution, opts.execution_api_url.clone()),
+            HealthTarget::new(
+                "constraints",
+                TargetKind::Constraints,
+                opts.constraints_api_url.clone(),
+            ),
+        ];
+        targets.extend(opts.registration_relay_urls.iter().map(|url| {
+            // Only the host is kept in the name, as relay URLs may contain credentials
+            let name = match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("relay:{host}:{port}"),
+                (host, _) => format!("relay:{}", host.unwrap_or_default()),
+            };
+            HealthTarget::new(name, TargetKind::Constraints, url.clone())
+        }));
+
+        Self::new(targets, Duration::from_secs(opts.telemetry.health_check_interval_secs))
+    }
+
+    /// Returns a handle to the health status reported by this prober.
+    pub fn handle(&self) -> HealthHandle {
+        self.handle.clone()
+    }
+
+    /// Spawn the prober in the background, checking all targets every interval.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                self.probe_all().await;
+            }
+        })
+    }
+
+    /// Check all targets concurrently, updating the gauges and the health status.
+    pub async fn probe_all(&self) -> HealthStatus {
+        let targets = join_all(self.targets.iter().map(|target| self.probe(target))).await;
+        let healthy = targets.iter().all(|t| t.reachable);
+
+        for target in &targets {
+            ApiMetrics::set_endpoint_reachable(&target.name, target.reachable);
+            ApiMetrics::set_endpoint_latency(
+                &target.name,
+                Duration::from_millis(target.latency_ms),
+            );
+        }
+
+        *self.handle.status.write() =
+            HealthStatus { healthy, waiting_for_beacon: false, targets, operator: None };
+        ApiMetrics::set_sidecar_healthy(self.handle.is_healthy());
+
+        self.handle.status()
+    }
+
+    /// Check a single target, returning its status.
+    async fn probe(&self, target: &HealthTarget) -> TargetStatus {
+        let start = Instant::now();
+        let result = match target.kind {
+            TargetKind::Beacon => self.check_get(&target.url, BEACON_SYNCING_PATH).await,
+            TargetKind::Constraints => self.check_get(&target.url, STATUS_PATH).await,
+            TargetKind::Execution => self.check_execution(&target.url).await,
+        };
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        match &result {
+            Ok(()) => debug!(endpoint = %target.name, latency_ms, "Health check succeeded"),
+            Err(err) => warn!(endpoint = %target.name, %err, "Health check failed"),
+        }
+
+        TargetStatus {
+            name: target.name.clone(),
+            kind: target.kind,
+            reachable: result.is_ok(),
+            latency_ms,
+            error: result.err(),
+        }
+    }
+
+    /// Check that a `GET` request to the given path returns a successful status code.
+    async fn check_get(&self, url: &Url, path: &str) -> Result<(), String> {
+        let url = append_path(url, path)?;
+        let response = self.client.get(url).send().await.map_err(|e| e.to_string())?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("unexpected status code {}", response.status()))
+        }
+    }
+
+    /// Check that the execution client responds to an `eth_syncing` JSON-RPC call.
+    async fn check_execution(&self, url: &Url) -> Result<(), String> {
+        let payload = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_syncing", "params": [] });
+        let response =
+            self.client.post(url.clone()).json(&payload).send().await.map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("unexpected status code {}", response.status()));
+        }
+
+        let body = response.json::<Value>().await.map_err(|e| e.to_string())?;
+        match (body.get("result"), body.get("error")) {
+            (Some(_), None) => Ok(()),
+            (_, Some(err)) => Err(format!("JSON-RPC error: {err}")),
+            _ => Err("missing JSON-RPC result".to_string()),
+        }
+    }
+}
+
+/// Append the given path to the one of the base URL, keeping its prefix if any, e.g. for
+/// upstreams served behind a reverse proxy like `https://host/beacon/`.
+fn append_path(base: &Url, path: &str) -> Result<Url, String> {
+    let mut url = base.clone();
+    url.path_segments_mut()
+        .map_err(|_| format!("invalid base URL {base}"))?
+        .pop_if_empty()
+        .extend(path.trim_start_matches('/').split('/'));
+
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use clap::Parser;
+
+    use crate::{
+        common::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig},
+        telemetry::prometheus_builder,
+    };
+
+    use super::*;
+
+    fn gauge_value(scrape: &str, series: &str) -> Option<f64> {
+        scrape
+            .lines()
+            .find(|line| line.starts_with(series))
+            .and_then(|line| line.split_whitespace().last())
+            .and_then(|value| value.parse().ok())
+    }
+
+    #[tokio::test]
+    async fn test_health_gauges_follow_flapping_target() {
+        let recorder = prometheus_builder().unwrap().build_recorder();
+        let metrics = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+
+        let server = MockServer::start().await;
+        let url = Url::parse(&server.uri()).unwrap();
+        let prober = HealthProber::new(
+            vec![HealthTarget::new("beacon", TargetKind::Beacon, url)],
+            Duration::from_secs(DEFAULT_HEALTH_CHECK_INTERVAL_SECS),
+        );
+        let handle = prober.handle();
+
+        for up in [true, false, true] {
+            server.reset().await;
+            let status = if up { 200 } else { 503 };
+            Mock::given(method("GET"))
+                .and(path(BEACON_SYNCING_PATH))
+                .respond_with(ResponseTemplate::new(status))
+                .mount(&server)
+                .await;
+
+            let report = prober.probe_all().await;
+            assert_eq!(report.healthy, up);
+            assert_eq!(handle.is_healthy(), up);
+
+            let scrape = metrics.render();
+            let expected = if up { 1.0 } else { 0.0 };
+            assert_eq!(
+                gauge_value(&scrape, "bolt_sidecar_endpoint_reachable{target=\"beacon\"}"),
+                Some(expected)
+            );
+            assert_eq!(gauge_value(&scrape, "bolt_sidecar_healthy"), Some(expected));
+            assert!(gauge_value(&scrape, "bolt_sidecar_endpoint_latency_seconds").is_some());
+        }
+    }
+
+    #[test]
+    fn test_append_path_keeps_prefix() {
+        let url = |base: &str| append_path(&Url::parse(base).unwrap(), BEACON_SYNCING_PATH);
+
+        assert_eq!(url("http://localhost:5052").unwrap().path(), "/eth/v1/node/syncing");
+        assert_eq!(url("http://localhost:5052/").unwrap().path(), "/eth/v1/node/syncing");
+        assert_eq!(url("https://host/beacon/").unwrap().path(), "/beacon/eth/v1/node/syncing");
+        assert_eq!(url("https://host/beacon").unwrap().path(), "/beacon/eth/v1/node/syncing");
+    }
+
+    #[tokio::test]
+    async fn test_probe_every_relay() {
+        let constraints = MockServer::start().await;
+        let relay = MockServer::start().await;
+        for (server, status) in [(&constraints, 200), (&relay, 503)] {
+            Mock::given(method("GET"))
+                .and(path(format!("/relay{STATUS_PATH}")))
+                .respond_with(ResponseTemplate::new(status))
+                .mount(server)
+                .await;
+        }
+
+        let opts = Opts::try_parse_from([
+            "bolt-sidecar".to_string(),
+            format!("--constraints-api-url={}/relay/", constraints.uri()),
+            format!("--registration-relay-urls={}/relay", relay.uri()),
+            format!("--engine-jwt-hex={}", JwtSecretConfig::default()),
+            "--fee-recipient=0x0000000000000000000000000000000000000000".to_string(),
+            format!("--builder-private-key={}", BlsSecretKeyWrapper::random()),
+            format!("--commitment-private-key={}", EcdsaSecretKeyWrapper::random()),
+            format!("--constraint-private-key={}", BlsSecretKeyWrapper::random()),
+        ])
+        .unwrap();
+        let prober = HealthProber::from_opts(&opts);
+
+        let report = prober.probe_all().await;
+        let reachable =
+            |name: &str| report.targets.iter().find(|t| t.name == name).unwrap().reachable;
+
+        assert!(reachable("constraints"));
+        let relay_name = format!("relay:127.0.0.1:{}", relay.address().port());
+        assert!(!reachable(&relay_name));
+        assert!(!report.healthy);
+    }
+
+    #[tokio::test]
+    async fn test_execution_health_check() {
+        let server = MockServer::start().await;
+        let url = Url::parse(&server.uri()).unwrap();
+        let prober = HealthProber::new(
+            vec![HealthTarget::new("execution", TargetKind::Execution, url)],
+            Duration::from_secs(DEFAULT_HEALTH_CHECK_INTERVAL_SECS),
+        );
+
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": false })),
+            )
+            .mount(&server)
+            .await;
+        assert!(prober.probe_all().await.healthy);
+
+        server.reset().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32000, "message": "down" } }),
+            ))
+            .mount(&server)
+            .await;
+
+        let report = prober.probe_all().await;
+        assert!(!report.healthy);
+        assert!(report.targets[0].error.as_ref().unwrap().contains("down"));
+    }
+}
@@ -1,9 +1,14 @@
 use std::time::Duration;
 
+use alloy::primitives::utils::format_ether;
+use ethereum_consensus::phase0::mainnet::SLOTS_PER_EPOCH;
 use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
 use reth_primitives::TxType;
 
-use crate::primitives::transaction::tx_type_str;
+use crate::{
+    chain_io::manager::OperatorStatus, client::circuit_breaker::CircuitState,
+    config::limits::LimitsOpts, primitives::transaction::tx_type_str,
+};
 
 //  Counters ----------------------------------------------------------------
 /// Counter for the total number of HTTP requests received.
@@ -24,14 +29,141 @@ const VALIDATION_ERRORS: &str = "bolt_sidecar_validation_errors";
 /// We call it "gross" because in the case of PBS, it doesn't mean the proposer will
 /// get all of this as revenue.
 const GROSS_TIP_REVENUE: &str = "bolt_sidecar_gross_tip_revenue";
+/// Counter for the lookups in the execution state cache, by result (hit/miss) and kind
+const STATE_CACHE_LOOKUPS: &str = "bolt_sidecar_state_cache_lookups";
+/// Counter for the state changes of the relay circuit breakers, by new state
+const RELAY_CIRCUIT_TRANSITIONS: &str = "bolt_sidecar_relay_circuit_transitions";
+/// Counter for the requests rejected because their target slot is full of constraints
+const SLOT_FULL_REJECTIONS: &str = "bolt_sidecar_slot_full_rejections";
+/// Counter for the deadlines missed by the builder proxy, by route
+const BUILDER_PROXY_TIMEOUTS: &str = "bolt_sidecar_builder_proxy_timeouts";
+/// Counter for the failed polls of the operator status from the BoltManager contract
+const OPERATOR_STATUS_POLL_FAILURES: &str = "bolt_sidecar_operator_status_poll_failures";
+/// Counter for the commitment requests rejected because the request queue is full
+const API_QUEUE_OVERFLOWS: &str = "bolt_sidecar_api_queue_overflows";
+/// Counter for the commitment requests aborted because they missed the commitment deadline,
+/// by processing stage
+const DEADLINE_ABORTS: &str = "bolt_sidecar_deadline_aborts";
+/// Counter for the commitment requests rejected because they expired before being committed,
+/// by processing stage
+const EXPIRED_REQUESTS: &str = "bolt_sidecar_expired_requests";
+/// Counter for the signed constraints removed from the block templates because their
+/// transactions were invalidated by a new head
+const CONSTRAINTS_INVALIDATED: &str = "bolt_sidecar_constraints_invalidated";
+/// Counter for the signed constraints at risk of being invalidated by the mempool transactions
+/// of their senders, counted at every head
+const CONSTRAINTS_AT_RISK: &str = "bolt_sidecar_constraints_at_risk";
+/// Counter for the slots whose constraints couldn't be submitted to the relay, or whose local
+/// payload couldn't be built, by reason
+const CONSTRAINTS_SUBMISSION_FAILED: &str = "bolt_sidecar_constraints_submission_failed_total";
+/// Counter for the local payloads that failed to be built at the commitment deadline
+const LOCAL_PAYLOAD_BUILD_FAILED: &str = "bolt_sidecar_local_payload_build_failed_total";
+/// Counter for the head events whose block wasn't imported by the execution client in time
+const EXECUTION_HEAD_LAGGING: &str = "bolt_sidecar_execution_head_lagging_total";
+/// Counter for the commitment requests whose constraints were signed with a fallback pubkey,
+/// after the selected one failed, by signer backend
+const SIGNING_KEY_FALLBACKS: &str = "bolt_sidecar_signing_key_fallbacks_total";
+/// Counter for the failed attempts to submit constraints to the relay, by failure reason
+const CONSTRAINTS_SUBMISSION_ATTEMPT_FAILED: &str =
+    "bolt_sidecar_constraints_submission_attempts_failed_total";
 
 //  Gauges ------------------------------------------------------------------
 /// Gauge for the latest slot number
 const LATEST_HEAD: &str = "bolt_sidecar_latest_head";
+/// Gauge for the latest finalized slot number
+const FINALIZED_SLOT: &str = "bolt_sidecar_finalized_slot";
+/// Gauge for the latest finalized execution block number
+const FINALIZED_BLOCK: &str = "bolt_sidecar_finalized_block";
+/// Gauge for the latest safe execution block number
+const SAFE_BLOCK: &str = "bolt_sidecar_safe_block";
+/// Gauge for the number of commitment requests waiting to be processed
+const API_QUEUE_DEPTH: &str = "bolt_sidecar_api_queue_depth";
+/// Gauge for the reachability of an upstream endpoint (1 = reachable, 0 = unreachable)
+const ENDPOINT_REACHABLE: &str = "bolt_sidecar_endpoint_reachable";
+/// Gauge for the latency of the latest health check of an upstream endpoint
+const ENDPOINT_LATENCY_SECONDS: &str = "bolt_sidecar_endpoint_latency_seconds";
+/// Gauge summarizing whether all upstream endpoints are reachable and the operator is
+/// healthy (1 = healthy)
+const SIDECAR_HEALTHY: &str = "bolt_sidecar_healthy";
+/// Gauge for the execution endpoint currently used to fetch state (1 = active, 0 = standby)
+const EXECUTION_ENDPOINT_ACTIVE: &str = "bolt_sidecar_execution_endpoint_active";
+/// Gauge for the current value of each operating limit
+const LIMIT: &str = "bolt_sidecar_limit";
+/// Gauge for the state of the circuit breaker of a relay (0 = closed, 1 = half-open, 2 = open)
+const RELAY_CIRCUIT_STATE: &str = "bolt_sidecar_relay_circuit_state";
+/// Gauge for the number of validators verified in the BoltManager contract
+const VERIFIED_VALIDATORS: &str = "bolt_sidecar_verified_validators";
+/// Gauge for the number of validators excluded from commitments because they aren't verified
+const EXCLUDED_VALIDATORS: &str = "bolt_sidecar_excluded_validators";
+/// Gauge for the validators the sidecar acts for, after the configured exclusions
+const EFFECTIVE_VALIDATORS: &str = "bolt_sidecar_effective_validators";
+/// Gauge for the registration of the operator in Bolt (1 = registered, 0 = not registered)
+const OPERATOR_REGISTERED: &str = "bolt_sidecar_operator_registered";
+/// Gauge for the activity of the operator in Bolt (1 = enabled, 0 = paused or not registered)
+const OPERATOR_ENABLED: &str = "bolt_sidecar_operator_enabled";
+/// Gauge for the total collateral of the operator, in ether
+const OPERATOR_COLLATERAL: &str = "bolt_sidecar_operator_collateral";
+/// Gauge for the minimum operator collateral required by the protocol, in ether
+const OPERATOR_MINIMUM_COLLATERAL: &str = "bolt_sidecar_operator_minimum_collateral";
+/// Gauge for the gas committed in the block template of an upcoming slot, by slot index
+/// in the epoch
+const COMMITTED_GAS: &str = "bolt_sidecar_committed_gas";
+/// Gauge for the blobs committed in the block template of an upcoming slot, by slot index
+/// in the epoch
+const COMMITTED_BLOBS: &str = "bolt_sidecar_committed_blobs";
+/// Gauge for the maximum priority fees of the transactions committed in the block template of
+/// an upcoming slot in gwei, i.e. the potential priority fee revenue, by slot index in the epoch
+const COMMITTED_PRIORITY_FEES_GWEI: &str = "bolt_sidecar_committed_priority_fees_gwei";
+/// Gauge for the transactions in the block template of an upcoming slot, by slot index
+/// in the epoch
+const TEMPLATE_TRANSACTIONS: &str = "bolt_sidecar_template_transactions";
+/// Gauge for the number of builders subscribed to the constraints stream of the builder proxy
+const CONSTRAINTS_STREAM_SUBSCRIBERS: &str = "bolt_sidecar_constraints_stream_subscribers";
+/// Gauge for the latest slot whose constraints were submitted to the relay
+const LAST_SUCCESSFUL_CONSTRAINT_SUBMISSION_SLOT: &str =
+    "bolt_sidecar_last_successful_constraint_submission_slot";
+/// Gauge for the number of block templates held in memory
+const EXECUTION_TEMPLATES: &str = "bolt_sidecar_execution_templates";
+/// Gauge for the number of signed constraints held in the block templates
+const EXECUTION_CONSTRAINTS: &str = "bolt_sidecar_execution_constraints";
+/// Gauge for the number of account states cached for the senders with pending commitments
+const EXECUTION_CACHED_ACCOUNTS: &str = "bolt_sidecar_execution_cached_accounts";
+/// Gauge for the size on disk of the commitment archive, including its daily summaries
+const ARCHIVE_SIZE_BYTES: &str = "bolt_sidecar_archive_size_bytes";
+/// Gauge for the age of the oldest commitment kept in full in the archive, in seconds
+const ARCHIVE_OLDEST_RECORD_AGE_SECONDS: &str = "bolt_sidecar_archive_oldest_record_age_seconds";
+/// Gauge for the estimated offset of the local clock from the arrival of the heads, net of
+/// their propagation delay, in seconds
+const CLOCK_OFFSET_SECONDS: &str = "bolt_sidecar_clock_offset_seconds";
 
 //  Histograms --------------------------------------------------------------
 /// Histogram for the total duration of HTTP requests in seconds.
 const HTTP_REQUESTS_DURATION_SECONDS: &str = "bolt_sidecar_http_requests_duration_seconds";
+/// Histogram for the time from receiving a commitment request to sending the response.
+const COMMITMENT_LATENCY_SECONDS: &str = "bolt_sidecar_commitment_latency_seconds";
+/// Histogram for the time spent validating a commitment request, by validation kind.
+const VALIDATION_DURATION_SECONDS: &str = "bolt_sidecar_validation_duration_seconds";
+/// Histogram for the time spent signing a single constraint, by signer backend.
+const CONSTRAINT_SIGNING_DURATION_SECONDS: &str =
+    "bolt_sidecar_constraint_signing_duration_seconds";
+/// Histogram for the duration of each constraints submission attempt to the relay.
+const CONSTRAINTS_SUBMISSION_DURATION_SECONDS: &str =
+    "bolt_sidecar_constraints_submission_duration_seconds";
+/// Histogram for the duration of the verification of the validators in the BoltManager contract.
+const VALIDATORS_VERIFICATION_DURATION_SECONDS: &str =
+    "bolt_sidecar_validators_verification_duration_seconds";
+
+/// Histogram buckets (in seconds) for latencies that must fit in the commitment deadline budget.
+pub const LATENCY_BUCKETS: &[f64] =
+    &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+
+/// The histograms that should use the [LATENCY_BUCKETS].
+pub const LATENCY_HISTOGRAMS: &[&str] = &[
+    COMMITMENT_LATENCY_SECONDS,
+    VALIDATION_DURATION_SECONDS,
+    CONSTRAINT_SIGNING_DURATION_SECONDS,
+    CONSTRAINTS_SUBMISSION_DURATION_SECONDS,
+];
 
 /// Metrics for the commitments API.
 #[derive(Debug, Clone, Copy)]
@@ -49,15 +181,133 @@ impl ApiMetrics {
         describe_counter!(TRANSACTIONS_PRECONFIRMED, "Transactions preconfirmed");
         describe_counter!(VALIDATION_ERRORS, "Validation errors");
         describe_counter!(GROSS_TIP_REVENUE, "Gross tip revenue");
+        describe_counter!(STATE_CACHE_LOOKUPS, "Execution state cache lookups");
+        describe_counter!(RELAY_CIRCUIT_TRANSITIONS, "State changes of relay circuit breakers");
+        describe_counter!(SLOT_FULL_REJECTIONS, "Requests rejected because their slot is full");
+        describe_counter!(BUILDER_PROXY_TIMEOUTS, "Deadlines missed by the builder proxy");
+        describe_counter!(OPERATOR_STATUS_POLL_FAILURES, "Failed polls of the operator status");
+        describe_counter!(
+            API_QUEUE_OVERFLOWS,
+            "Requests rejected because the request queue is full"
+        );
+        describe_counter!(DEADLINE_ABORTS, "Requests aborted after the commitment deadline");
+        describe_counter!(EXPIRED_REQUESTS, "Requests rejected after their expiry");
+        describe_counter!(
+            CONSTRAINTS_INVALIDATED,
+            "Signed constraints removed from the block templates after being invalidated"
+        );
+        describe_counter!(
+            CONSTRAINTS_AT_RISK,
+            "Signed constraints at risk of being invalidated by their senders"
+        );
+        describe_counter!(
+            CONSTRAINTS_SUBMISSION_FAILED,
+            "Slots whose constraints couldn't be submitted or whose local payload failed"
+        );
+        describe_counter!(LOCAL_PAYLOAD_BUILD_FAILED, "Local payloads that failed to be built");
+        describe_counter!(
+            EXECUTION_HEAD_LAGGING,
+            "Head events whose block wasn't imported by the execution client in time"
+        );
+        describe_counter!(
+            SIGNING_KEY_FALLBACKS,
+            "Requests signed with a fallback pubkey after the selected one failed"
+        );
+        describe_counter!(
+            CONSTRAINTS_SUBMISSION_ATTEMPT_FAILED,
+            "Failed attempts to submit constraints to the relay"
+        );
 
         // Gauges
         describe_gauge!(LATEST_HEAD, "Latest slot number");
+        describe_gauge!(FINALIZED_SLOT, "Latest finalized slot number");
+        describe_gauge!(FINALIZED_BLOCK, "Latest finalized execution block number");
+        describe_gauge!(SAFE_BLOCK, "Latest safe execution block number");
+        describe_gauge!(API_QUEUE_DEPTH, "Commitment requests waiting to be processed");
+        describe_gauge!(ENDPOINT_REACHABLE, "Whether an upstream endpoint is reachable");
+        describe_gauge!(
+            ENDPOINT_LATENCY_SECONDS,
+            "Latency of the latest health check of an upstream endpoint in seconds"
+        );
+        describe_gauge!(
+            SIDECAR_HEALTHY,
+            "Whether the sidecar and its upstream endpoints are healthy"
+        );
+        describe_gauge!(
+            EXECUTION_ENDPOINT_ACTIVE,
+            "Whether an execution endpoint is the one currently used to fetch state"
+        );
+        describe_gauge!(LIMIT, "Current value of an operating limit");
+        describe_gauge!(
+            RELAY_CIRCUIT_STATE,
+            "State of the circuit breaker of a relay (0 = closed, 1 = half-open, 2 = open)"
+        );
+        describe_gauge!(VERIFIED_VALIDATORS, "Validators verified in the BoltManager contract");
+        describe_gauge!(EXCLUDED_VALIDATORS, "Validators excluded from commitments as unverified");
+        describe_gauge!(EFFECTIVE_VALIDATORS, "Validators the sidecar accepts commitments for");
+        describe_gauge!(OPERATOR_REGISTERED, "Whether the operator is registered in Bolt");
+        describe_gauge!(OPERATOR_ENABLED, "Whether the operator is enabled in Bolt");
+        describe_gauge!(OPERATOR_COLLATERAL, "Total collateral of the operator in ether");
+        describe_gauge!(
+            OPERATOR_MINIMUM_COLLATERAL,
+            "Minimum operator collateral required by the protocol in ether"
+        );
+        describe_gauge!(COMMITTED_GAS, "Gas committed in the block template of a slot");
+        describe_gauge!(COMMITTED_BLOBS, "Blobs committed in the block template of a slot");
+        describe_gauge!(
+            COMMITTED_PRIORITY_FEES_GWEI,
+            "Maximum priority fees committed in the block template of a slot in gwei"
+        );
+        describe_gauge!(TEMPLATE_TRANSACTIONS, "Transactions in the block template of a slot");
+        describe_gauge!(
+            CONSTRAINTS_STREAM_SUBSCRIBERS,
+            "Builders subscribed to the constraints stream"
+        );
+        describe_gauge!(
+            LAST_SUCCESSFUL_CONSTRAINT_SUBMISSION_SLOT,
+            "Latest slot whose constraints were submitted to the relay"
+        );
+        describe_gauge!(EXECUTION_TEMPLATES, "Block templates held in memory");
+        describe_gauge!(EXECUTION_CONSTRAINTS, "Signed constraints held in the block templates");
+        describe_gauge!(
+            EXECUTION_CACHED_ACCOUNTS,
+            "Account states cached for the senders with pending commitments"
+        );
+        describe_gauge!(ARCHIVE_SIZE_BYTES, "Size on disk of the commitment archive");
+        describe_gauge!(
+            ARCHIVE_OLDEST_RECORD_AGE_SECONDS,
+            "Age of the oldest commitment kept in full in the archive"
+        );
+        describe_gauge!(
+            CLOCK_OFFSET_SECONDS,
+            "Estimated offset of the local clock from the arrival of the heads"
+        );
 
         // Histograms
         describe_histogram!(
             HTTP_REQUESTS_DURATION_SECONDS,
             "Total duration of HTTP requests in seconds"
         );
+        describe_histogram!(
+            COMMITMENT_LATENCY_SECONDS,
+            "Time from receiving a commitment request to sending the response in seconds"
+        );
+        describe_histogram!(
+            VALIDATION_DURATION_SECONDS,
+            "Time spent validating commitment requests in seconds"
+        );
+        describe_histogram!(
+            CONSTRAINT_SIGNING_DURATION_SECONDS,
+            "Time spent signing a single constraint in seconds"
+        );
+        describe_histogram!(
+            CONSTRAINTS_SUBMISSION_DURATION_SECONDS,
+            "Duration of constraints submission attempts to the relay in seconds"
+        );
+        describe_histogram!(
+            VALIDATORS_VERIFICATION_DURATION_SECONDS,
+            "Duration of the verification of the validators in the BoltManager contract in seconds"
+        );
     }
 
     /// Counters ----------------------------------------------------------------
@@ -78,6 +328,58 @@ impl ApiMetrics {
         counter!(REMOTE_BLOCKS_PROPOSED).increment(1);
     }
 
+    pub fn increment_slot_full_rejections() {
+        counter!(SLOT_FULL_REJECTIONS).increment(1);
+    }
+
+    pub fn increment_builder_proxy_timeouts(route: &'static str) {
+        counter!(BUILDER_PROXY_TIMEOUTS, &[("route", route)]).increment(1);
+    }
+
+    pub fn increment_operator_status_poll_failures() {
+        counter!(OPERATOR_STATUS_POLL_FAILURES).increment(1);
+    }
+
+    pub fn increment_api_queue_overflows() {
+        counter!(API_QUEUE_OVERFLOWS).increment(1);
+    }
+
+    pub fn increment_deadline_aborts(stage: &'static str) {
+        counter!(DEADLINE_ABORTS, &[("stage", stage)]).increment(1);
+    }
+
+    pub fn increment_expired_requests(stage: &'static str) {
+        counter!(EXPIRED_REQUESTS, &[("stage", stage)]).increment(1);
+    }
+
+    pub fn increment_constraints_invalidated(count: usize) {
+        counter!(CONSTRAINTS_INVALIDATED).increment(count as u64);
+    }
+
+    pub fn increment_constraints_at_risk(count: usize) {
+        counter!(CONSTRAINTS_AT_RISK).increment(count as u64);
+    }
+
+    pub fn increment_constraints_submission_failed(reason: &'static str) {
+        counter!(CONSTRAINTS_SUBMISSION_FAILED, &[("reason", reason)]).increment(1);
+    }
+
+    pub fn increment_local_payload_build_failed() {
+        counter!(LOCAL_PAYLOAD_BUILD_FAILED).increment(1);
+    }
+
+    pub fn increment_execution_head_lagging() {
+        counter!(EXECUTION_HEAD_LAGGING).increment(1);
+    }
+
+    pub fn increment_signing_key_fallbacks(backend: &'static str) {
+        counter!(SIGNING_KEY_FALLBACKS, &[("backend", backend)]).increment(1);
+    }
+
+    pub fn increment_constraints_submission_attempt_failed(reason: &'static str) {
+        counter!(CONSTRAINTS_SUBMISSION_ATTEMPT_FAILED, &[("reason", reason)]).increment(1);
+    }
+
     pub fn increment_inclusion_commitments_received() {
         counter!(INCLUSION_COMMITMENTS_RECEIVED).increment(1);
     }
@@ -113,12 +415,176 @@ impl ApiMetrics {
         counter!(VALIDATION_ERRORS, &[("type", err_type)]).increment(1);
     }
 
+    pub fn increment_state_cache(result: &'static str, kind: &'static str, count: u64) {
+        counter!(STATE_CACHE_LOOKUPS, &[("result", result), ("kind", kind)]).increment(count);
+    }
+
     /// Gauges ----------------------------------------------------------------
 
     pub fn set_latest_head(slot: u32) {
         gauge!(LATEST_HEAD).set(slot);
     }
 
+    pub fn set_finalized_checkpoint(slot: u64, block: u64) {
+        gauge!(FINALIZED_SLOT).set(slot as f64);
+        gauge!(FINALIZED_BLOCK).set(block as f64);
+    }
+
+    pub fn set_safe_block(block: u64) {
+        gauge!(SAFE_BLOCK).set(block as f64);
+    }
+
+    pub fn set_constraints_stream_subscribers(count: usize) {
+        gauge!(CONSTRAINTS_STREAM_SUBSCRIBERS).set(count as f64);
+    }
+
+    pub fn set_last_successful_constraint_submission_slot(slot: u64) {
+        gauge!(LAST_SUCCESSFUL_CONSTRAINT_SUBMISSION_SLOT).set(slot as f64);
+    }
+
+    pub fn set_api_queue_depth(depth: usize) {
+        gauge!(API_QUEUE_DEPTH).set(depth as f64);
+    }
+
+    pub fn set_endpoint_reachable(target: &str, reachable: bool) {
+        gauge!(ENDPOINT_REACHABLE, &[("target", target.to_string())]).set(if reachable {
+            1.0
+        } else {
+            0.0
+        });
+    }
+
+    pub fn set_execution_endpoint_active(endpoint: &str, active: bool) {
+        gauge!(EXECUTION_ENDPOINT_ACTIVE, &[("endpoint", endpoint.to_string())]).set(if active {
+            1.0
+        } else {
+            0.0
+        });
+    }
+
+    pub fn set_endpoint_latency(target: &str, latency: Duration) {
+        gauge!(ENDPOINT_LATENCY_SECONDS, &[("target", target.to_string())])
+            .set(latency.as_secs_f64());
+    }
+
+    pub fn set_sidecar_healthy(healthy: bool) {
+        gauge!(SIDECAR_HEALTHY).set(if healthy { 1.0 } else { 0.0 });
+    }
+
+    pub fn set_limits(limits: &LimitsOpts) {
+        let values = [
+            ("max_commitments_per_slot", limits.max_commitments_per_slot.get() as f64),
+            ("max_committed_gas_per_slot", limits.max_committed_gas_per_slot.get() as f64),
+            ("max_constraints_per_slot", limits.max_constraints_per_slot.get() as f64),
+            ("max_top_of_block_txs_per_slot", limits.max_top_of_block_txs_per_slot as f64),
+            ("max_top_of_block_gas_per_slot", limits.max_top_of_block_gas_per_slot as f64),
+            ("min_priority_fee", limits.min_priority_fee as f64),
+            ("max_tx_size", limits.max_tx_size.get() as f64),
+            // Zero means that the cap is disabled, as a cap is never zero
+            ("max_gas_per_tx", limits.max_gas_per_tx.map_or(0.0, |cap| cap.get() as f64)),
+            ("max_calldata_bytes", limits.max_calldata_bytes.map_or(0.0, |cap| cap.get() as f64)),
+        ];
+
+        for (limit, value) in values {
+            gauge!(LIMIT, &[("limit", limit)]).set(value);
+        }
+    }
+
+    /// Records a state change of the circuit breaker of a relay.
+    pub fn set_relay_circuit_state(relay: &str, state: CircuitState) {
+        let relay = relay.to_string();
+        gauge!(RELAY_CIRCUIT_STATE, &[("relay", relay.clone())]).set(state as u8 as f64);
+        counter!(RELAY_CIRCUIT_TRANSITIONS, &[("relay", relay), ("state", state.to_string())])
+            .increment(1);
+    }
+
+    pub fn set_verified_validators(count: usize) {
+        gauge!(VERIFIED_VALIDATORS).set(count as f64);
+    }
+
+    pub fn set_excluded_validators(count: usize) {
+        gauge!(EXCLUDED_VALIDATORS).set(count as f64);
+    }
+
+    pub fn set_effective_validators(count: usize) {
+        gauge!(EFFECTIVE_VALIDATORS).set(count as f64);
+    }
+
+    pub fn set_operator_status(status: &OperatorStatus) {
+        let ether = |wei| format_ether(wei).parse::<f64>().unwrap_or_default();
+
+        gauge!(OPERATOR_REGISTERED).set(if status.registered { 1.0 } else { 0.0 });
+        gauge!(OPERATOR_ENABLED).set(if status.enabled { 1.0 } else { 0.0 });
+        gauge!(OPERATOR_COLLATERAL).set(ether(status.collateral));
+        gauge!(OPERATOR_MINIMUM_COLLATERAL).set(ether(status.minimum_collateral));
+    }
+
+    /// Records the commitments in the block template of the given upcoming slot.
+    ///
+    /// The gauges are labeled by the index of the slot in its epoch, to keep a bounded number
+    /// of series: they are reset with [ApiMetrics::reset_slot_commitments] once the slot is
+    /// over, before the index is reused.
+    pub fn set_slot_commitments(slot: u64, stats: SlotCommitmentStats) {
+        let labels = [("slot_index", slot_index(slot))];
+        gauge!(COMMITTED_GAS, &labels).set(stats.gas as f64);
+        gauge!(COMMITTED_BLOBS, &labels).set(stats.blobs as f64);
+        gauge!(COMMITTED_PRIORITY_FEES_GWEI, &labels).set(stats.priority_fees as f64 / 1e9);
+        gauge!(TEMPLATE_TRANSACTIONS, &labels).set(stats.transactions as f64);
+    }
+
+    /// Resets the commitment gauges of the given slot, once its block template is gone.
+    pub fn reset_slot_commitments(slot: u64) {
+        Self::set_slot_commitments(slot, SlotCommitmentStats::default());
+    }
+
+    /// Records the block templates, constraints and account states held by the execution state.
+    pub fn set_execution_memory(stats: ExecutionMemoryStats) {
+        gauge!(EXECUTION_TEMPLATES).set(stats.templates as f64);
+        gauge!(EXECUTION_CONSTRAINTS).set(stats.constraints as f64);
+        gauge!(EXECUTION_CACHED_ACCOUNTS).set(stats.accounts as f64);
+    }
+
+    /// Records the size of the commitment archive and the age of its oldest full record.
+    pub fn set_archive_usage(size_bytes: u64, oldest_record_age: Option<Duration>) {
+        gauge!(ARCHIVE_SIZE_BYTES).set(size_bytes as f64);
+        gauge!(ARCHIVE_OLDEST_RECORD_AGE_SECONDS)
+            .set(oldest_record_age.map_or(0.0, |age| age.as_secs_f64()));
+    }
+
+    /// Records the estimated offset of the local clock from the arrival of the heads, net of
+    /// their propagation delay, in seconds. Negative if the heads arrive early.
+    pub fn set_clock_offset(offset_seconds: f64) {
+        gauge!(CLOCK_OFFSET_SECONDS).set(offset_seconds);
+    }
+
+    /// Histograms ----------------------------------------------------------------
+
+    /// Observes the time taken to answer an inclusion request, whether it was committed or not.
+    pub fn observe_commitment_latency(duration: Duration) {
+        histogram!(COMMITMENT_LATENCY_SECONDS).record(duration.as_secs_f64());
+    }
+
+    /// Observes the duration of a validation step, where `kind` is either
+    /// "consensus" or "execution".
+    pub fn observe_validation_duration(kind: &'static str, duration: Duration) {
+        histogram!(VALIDATION_DURATION_SECONDS, &[("kind", kind)]).record(duration.as_secs_f64());
+    }
+
+    pub fn observe_constraint_signing(signer: &'static str, duration: Duration) {
+        histogram!(CONSTRAINT_SIGNING_DURATION_SECONDS, &[("signer", signer)])
+            .record(duration.as_secs_f64());
+    }
+
+    pub fn observe_constraints_submission(success: bool, duration: Duration) {
+        let status = if success { "success" } else { "failure" };
+        histogram!(CONSTRAINTS_SUBMISSION_DURATION_SECONDS, &[("status", status)])
+            .record(duration.as_secs_f64());
+    }
+
+    pub fn observe_validators_verification(duration: Duration) {
+        histogram!(VALIDATORS_VERIFICATION_DURATION_SECONDS).record(duration.as_secs_f64());
+    }
+
     /// Mixed ----------------------------------------------------------------
 
     /// Observes the duration of an HTTP request by storing it in a histogram,
@@ -129,3 +595,77 @@ impl ApiMetrics {
         histogram!(HTTP_REQUESTS_DURATION_SECONDS, &labels,).record(duration.as_secs_f64());
     }
 }
+
+/// The commitments in the block template of a slot, as recorded by
+/// [ApiMetrics::set_slot_commitments].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlotCommitmentStats {
+    /// The committed gas.
+    pub gas: u64,
+    /// The committed blobs.
+    pub blobs: usize,
+    /// The sum of the maximum priority fees of the transactions, in wei.
+    pub priority_fees: u128,
+    /// The number of transactions.
+    pub transactions: usize,
+}
+
+/// The block templates, constraints and account states held in memory by the execution state,
+/// as recorded by [ApiMetrics::set_execution_memory].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionMemoryStats {
+    /// The number of block templates.
+    pub templates: usize,
+    /// The number of signed constraints across the block templates.
+    pub constraints: usize,
+    /// The number of cached account states, i.e. the senders with pending commitments.
+    pub accounts: usize,
+}
+
+/// Returns the index of the given slot in its epoch, as a metric label.
+fn slot_index(slot: u64) -> String {
+    (slot % SLOTS_PER_EPOCH).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::telemetry::prometheus_builder;
+
+    use super::*;
+
+    #[test]
+    fn test_latency_histograms_are_recorded() {
+        let recorder = prometheus_builder().expect("valid buckets").build_recorder();
+        let handle = recorder.handle();
+
+        metrics::with_local_recorder(&recorder, || {
+            ApiMetrics::observe_validation_duration("consensus", Duration::from_micros(150));
+            ApiMetrics::observe_validation_duration("execution", Duration::from_millis(12));
+            ApiMetrics::observe_constraint_signing("local", Duration::from_micros(800));
+            ApiMetrics::observe_constraints_submission(true, Duration::from_millis(300));
+        });
+
+        let scrape = handle.render();
+
+        for (series, labels) in [
+            (VALIDATION_DURATION_SECONDS, "kind=\"consensus\""),
+            (VALIDATION_DURATION_SECONDS, "kind=\"execution\""),
+            (CONSTRAINT_SIGNING_DURATION_SECONDS, "signer=\"local\""),
+            (CONSTRAINTS_SUBMISSION_DURATION_SECONDS, "status=\"success\""),
+        ] {
+            let count_line = scrape
+                .lines()
+                .find(|l| l.starts_with(&format!("{series}_count")) && l.contains(labels))
+                .unwrap_or_else(|| panic!("missing {series}_count{{{labels}}} in:\n{scrape}"));
+            let count = count_line.split_whitespace().last().unwrap().parse::<u64>().unwrap();
+            assert_eq!(count, 1, "unexpected count for {series}");
+
+            // Make sure the histogram uses the custom latency buckets
+            assert!(scrape.contains(&format!("{series}_bucket")), "{series} is not a histogram");
+        }
+
+        assert!(scrape.contains("le=\"0.005\""), "latency buckets not applied");
+    }
+}
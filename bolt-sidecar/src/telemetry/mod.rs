@@ -1,7 +1,9 @@
 use std::net::SocketAddr;
 
 use eyre::{bail, Result};
-use metrics_exporter_prometheus::PrometheusBuilder;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+use opentelemetry::trace::TracerProvider as _;
+use reqwest::Url;
 use tracing::info;
 use tracing_subscriber::{
     fmt::Layer as FmtLayer, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
@@ -9,12 +11,25 @@ use tracing_subscriber::{
 };
 
 mod metrics;
-pub use metrics::ApiMetrics;
+pub use metrics::{ApiMetrics, ExecutionMemoryStats, SlotCommitmentStats};
+use metrics::{LATENCY_BUCKETS, LATENCY_HISTOGRAMS};
 
-/// Initialize the tracing stack and Prometheus metrics recorder.
+/// Periodic health checks of the upstream endpoints.
+pub mod health;
+pub use health::{HealthHandle, HealthProber};
+
+/// Append-only audit log of the BLS signatures produced by the sidecar.
+pub mod audit;
+pub use audit::{AuditEntry, AuditLog};
+
+/// OpenTelemetry export of the traces of the commitment requests.
+pub mod otel;
+
+/// Initialize the tracing stack and Prometheus metrics recorder, exporting the traces to the
+/// given OTLP endpoint if any.
 ///
 /// **This function should be called at the beginning of the program.**
-pub fn init_telemetry_stack(metrics_port: Option<u16>) -> Result<()> {
+pub fn init_telemetry_stack(metrics_port: Option<u16>, otlp_endpoint: Option<&Url>) -> Result<()> {
     let std_layer = FmtLayer::default().with_writer(std::io::stdout).with_filter(
         EnvFilter::builder()
             .with_default_directive("bolt_sidecar=info".parse()?)
@@ -23,10 +38,28 @@ pub fn init_telemetry_stack(metrics_port: Option<u16>) -> Result<()> {
             .add_directive("alloy_transport_http=error".parse()?),
     );
 
-    Registry::default().with(std_layer).try_init()?;
+    let otel_layer = match otlp_endpoint {
+        Some(endpoint) => {
+            let provider = otel::otlp_tracer_provider(endpoint)?;
+            let tracer = provider.tracer(otel::SERVICE_NAME);
+            opentelemetry::global::set_tracer_provider(provider);
+
+            let layer = tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(EnvFilter::new("bolt_sidecar=info"));
+            Some(layer)
+        }
+        None => None,
+    };
+
+    Registry::default().with(std_layer).with(otel_layer).try_init()?;
+    if let Some(endpoint) = otlp_endpoint {
+        info!(%endpoint, "Exporting traces to the OTLP endpoint");
+    }
+
     if let Some(metrics_port) = metrics_port {
         let prometheus_addr = SocketAddr::from(([0, 0, 0, 0], metrics_port));
-        let builder = PrometheusBuilder::new().with_http_listener(prometheus_addr);
+        let builder = prometheus_builder()?.with_http_listener(prometheus_addr);
 
         if let Err(e) = builder.install() {
             bail!("failed to init telemetry stack. Error installing Prometheus recorder: {:?}", e);
@@ -42,3 +75,14 @@ pub fn init_telemetry_stack(metrics_port: Option<u16>) -> Result<()> {
 
     Ok(())
 }
+
+/// Returns a [PrometheusBuilder] configured with the custom histogram buckets of the sidecar.
+pub(crate) fn prometheus_builder() -> Result<PrometheusBuilder> {
+    let mut builder = PrometheusBuilder::new();
+    for name in LATENCY_HISTOGRAMS {
+        builder =
+            builder.set_buckets_for_metric(Matcher::Full(name.to_string()), LATENCY_BUCKETS)?;
+    }
+
+    Ok(builder)
+}
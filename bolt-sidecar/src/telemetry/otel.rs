@@ -0,0 +1,35 @@
+use eyre::Result;
+use opentelemetry::{
+    trace::{TraceContextExt, TraceId},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use reqwest::Url;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// The service name of the sidecar in the exported traces.
+pub const SERVICE_NAME: &str = "bolt-sidecar";
+
+/// Returns a tracer provider exporting spans in batches to the given OTLP gRPC endpoint.
+///
+/// NOTE: must be called from within a Tokio runtime, which runs the batch exporter.
+pub fn otlp_tracer_provider(endpoint: &Url) -> Result<TracerProvider> {
+    let exporter = opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint.as_str());
+    let resource = Resource::new([KeyValue::new("service.name", SERVICE_NAME)]);
+
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(opentelemetry_sdk::trace::Config::default().with_resource(resource))
+        .install_batch(runtime::Tokio)?;
+
+    Ok(provider)
+}
+
+/// Returns the ID of the trace of the current span, if traces are exported.
+pub fn current_trace_id() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let trace_id = context.span().span_context().trace_id();
+    (trace_id != TraceId::INVALID).then(|| trace_id.to_string())
+}
@@ -1,33 +1,49 @@
-use std::env;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use alloy::{
-    eips::eip2718::Encodable2718,
+    eips::{eip2718::Encodable2718, BlockNumberOrTag},
     network::{EthereumWallet, TransactionBuilder},
-    primitives::{Address, Signature, U256},
-    rpc::types::TransactionRequest,
+    primitives::{Address, Signature, TxHash, U256},
+    rpc::types::{Transaction, TransactionReceipt, TransactionRequest},
     signers::{
         k256::{ecdsa::SigningKey as K256SigningKey, SecretKey as K256SecretKey},
         local::PrivateKeySigner,
         Signer,
     },
+    transports::TransportError,
 };
 use alloy_node_bindings::{Anvil, AnvilInstance};
 use blst::min_pk::SecretKey;
 use clap::Parser;
 use ethereum_consensus::crypto::bls::{PublicKey as BlsPublicKey, Signature as BlsSignature};
+use parking_lot::Mutex;
 use rand::Rng;
+use reqwest::Url;
 use secp256k1::Message;
+use tokio::{net::TcpListener, task::JoinHandle, time::Instant};
 use tracing::warn;
 
 use crate::{
+    api::mock_relay::{MockRelay, MockRelayBehavior},
+    client::SimulatedCall,
     common::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig},
     config::{ChainConfig, Opts},
     crypto::{ecdsa::SignableECDSA, SignableBLS},
     primitives::{
-        ConstraintsMessage, DelegationMessage, FullTransaction, InclusionRequest,
+        AccountState, ConstraintsMessage, DelegationMessage, FullTransaction, InclusionRequest,
         RevocationMessage, SignedConstraints, SignedDelegation, SignedRevocation,
     },
     signer::local::LocalSigner,
+    state::{fetcher::StateFetcher, StateClient, StateUpdate},
 };
 
 /// The URL of the test execution client HTTP API.
@@ -119,6 +135,243 @@ pub(crate) fn launch_anvil() -> AnvilInstance {
     Anvil::new().block_time(1).chain_id(1337).spawn()
 }
 
+/// Launch a local instance of the mock relay on a random port, returning its URL.
+pub(crate) async fn launch_mock_relay(
+    behavior: MockRelayBehavior,
+) -> eyre::Result<(Url, JoinHandle<()>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?).parse()?;
+
+    let relay = MockRelay::new(ChainConfig::default(), BlsSecretKeyWrapper::random(), behavior);
+
+    Ok((url, relay.spawn(listener)))
+}
+
+/// A [StateFetcher] forwarding to a [StateClient], with hooks to simulate the behaviours of
+/// a real execution client in tests. Clones share the same hooks, so that a test can keep
+/// one to drive the fetcher it has handed over.
+#[derive(Debug, Clone)]
+pub(crate) struct TestFetcher {
+    inner: StateClient,
+    /// The delay of every account lookup, to simulate a slow execution client.
+    delay: Option<Duration>,
+    /// The number of account lookups sent to the execution client.
+    lookups: Arc<AtomicUsize>,
+    /// The number of account lookups also fetching the code of the accounts.
+    code_lookups: Arc<AtomicUsize>,
+    /// The number of transactions added to the pending nonce of every account, to simulate
+    /// senders broadcasting transactions to the public mempool.
+    mempool_txs: Arc<AtomicU64>,
+    /// The instant until which the latest block is hidden, to simulate an execution client
+    /// lagging behind the beacon head.
+    lag_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl TestFetcher {
+    /// Wrap the given client, without any hook set.
+    pub(crate) fn new(inner: StateClient) -> Self {
+        Self {
+            inner,
+            delay: None,
+            lookups: Arc::default(),
+            code_lookups: Arc::default(),
+            mempool_txs: Arc::default(),
+            lag_until: Arc::default(),
+        }
+    }
+
+    /// Delay every account lookup by the given duration.
+    pub(crate) fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Returns the number of account lookups sent to the execution client.
+    pub(crate) fn lookups(&self) -> usize {
+        self.lookups.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of account lookups that also fetched the code of the accounts.
+    pub(crate) fn code_lookups(&self) -> usize {
+        self.code_lookups.load(Ordering::SeqCst)
+    }
+
+    /// Reset the account lookup counters.
+    pub(crate) fn reset_lookups(&self) {
+        self.lookups.store(0, Ordering::SeqCst);
+        self.code_lookups.store(0, Ordering::SeqCst);
+    }
+
+    /// Bump the pending nonce of every account by the given number of mempool transactions.
+    pub(crate) fn set_mempool_txs(&self, count: u64) {
+        self.mempool_txs.store(count, Ordering::SeqCst);
+    }
+
+    /// Hide the latest block of the execution client for the given duration.
+    pub(crate) fn lag_for(&self, duration: Duration) {
+        *self.lag_until.lock() = Some(Instant::now() + duration);
+    }
+
+    /// Returns the head visible while lagging, or `None` if the client caught up.
+    async fn lagging_head(&self) -> Result<Option<u64>, TransportError> {
+        let lagging = self.lag_until.lock().is_some_and(|until| Instant::now() < until);
+        if !lagging {
+            return Ok(None);
+        }
+
+        Ok(Some(self.inner.get_head().await? - 1))
+    }
+
+    /// Apply the hooks of an account lookup at the given block, returning the block to query.
+    async fn account_lookup(
+        &self,
+        block_number: Option<u64>,
+        with_code: bool,
+    ) -> Result<Option<u64>, TransportError> {
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        if with_code {
+            self.code_lookups.fetch_add(1, Ordering::SeqCst);
+        }
+
+        match block_number {
+            Some(block_number) => Ok(Some(block_number)),
+            None => self.lagging_head().await,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl StateFetcher for TestFetcher {
+    async fn get_state_update(
+        &self,
+        addresses: Vec<&Address>,
+        head: Option<u64>,
+    ) -> Result<StateUpdate, TransportError> {
+        let head = match head {
+            Some(head) => Some(head),
+            None => self.lagging_head().await?,
+        };
+        self.inner.get_state_update(addresses, head).await
+    }
+
+    async fn get_head(&self) -> Result<u64, TransportError> {
+        match self.lagging_head().await? {
+            Some(head) => Ok(head),
+            None => self.inner.get_head().await,
+        }
+    }
+
+    async fn get_block_number(&self, tag: BlockNumberOrTag) -> Result<u64, TransportError> {
+        self.inner.get_block_number(tag).await
+    }
+
+    async fn get_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
+        self.inner.get_basefee(block_number).await
+    }
+
+    async fn get_blob_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
+        self.inner.get_blob_basefee(block_number).await
+    }
+
+    async fn get_account_state(
+        &self,
+        address: &Address,
+        block_number: Option<u64>,
+    ) -> Result<AccountState, TransportError> {
+        let block_number = self.account_lookup(block_number, true).await?;
+        self.inner.get_account_state(address, block_number).await
+    }
+
+    async fn get_account_states(
+        &self,
+        addresses: &[Address],
+        block_number: Option<u64>,
+        with_code: bool,
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        let block_number = self.account_lookup(block_number, with_code).await?;
+        self.inner.get_account_states(addresses, block_number, with_code).await
+    }
+
+    async fn get_pending_account_states(
+        &self,
+        addresses: &[Address],
+    ) -> Result<HashMap<Address, AccountState>, TransportError> {
+        let mempool_txs = self.mempool_txs.load(Ordering::SeqCst);
+        let mut states = self.inner.get_pending_account_states(addresses).await?;
+        states.values_mut().for_each(|state| state.transaction_count += mempool_txs);
+        Ok(states)
+    }
+
+    async fn get_chain_id(&self) -> Result<u64, TransportError> {
+        self.inner.get_chain_id().await
+    }
+
+    async fn simulate_calls(
+        &self,
+        calls: &[TransactionRequest],
+    ) -> Result<Vec<SimulatedCall>, TransportError> {
+        self.inner.simulate_calls(calls).await
+    }
+
+    async fn get_receipts_unordered(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<Vec<Option<TransactionReceipt>>, TransportError> {
+        self.inner.get_receipts_unordered(hashes).await
+    }
+
+    async fn get_pool_transactions(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<HashMap<TxHash, Transaction>, TransportError> {
+        self.inner.get_pool_transactions(hashes).await
+    }
+
+    async fn get_block_transaction_hashes(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<TxHash>, TransportError> {
+        self.inner.get_block_transaction_hashes(block_number).await
+    }
+}
+
+/// The PEM files of a test TLS certificate for `localhost`, issued by a self-signed CA.
+pub(crate) struct TestTls {
+    /// The PEM-encoded certificate of the CA.
+    pub ca_pem: String,
+    /// The path of the PEM-encoded certificate.
+    pub cert_path: PathBuf,
+    /// The path of the PEM-encoded private key of the certificate.
+    pub key_path: PathBuf,
+}
+
+/// Generate a test CA and a certificate for `localhost` issued by it, writing the
+/// certificate and its private key in the given directory.
+pub(crate) fn generate_test_tls(dir: &Path) -> eyre::Result<TestTls> {
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+
+    let ca_key = KeyPair::generate()?;
+    let mut ca_params = CertificateParams::new(Vec::<String>::new())?;
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = ca_params.self_signed(&ca_key)?;
+
+    let key = KeyPair::generate()?;
+    let cert =
+        CertificateParams::new(vec!["localhost".to_string()])?.signed_by(&key, &ca, &ca_key)?;
+
+    fs::create_dir_all(dir)?;
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+    fs::write(&cert_path, cert.pem())?;
+    fs::write(&key_path, key.serialize_pem())?;
+
+    Ok(TestTls { ca_pem: ca.pem(), cert_path, key_path })
+}
+
 /// Create a default transaction template to use for tests
 pub(crate) fn default_test_transaction(sender: Address, nonce: Option<u64>) -> TransactionRequest {
     TransactionRequest::default()
@@ -177,7 +430,15 @@ pub(crate) async fn create_signed_inclusion_request(
         let full_tx = FullTransaction::decode_enveloped(raw_encoded.as_slice())?;
         full_txs.push(full_tx);
     }
-    let mut request = InclusionRequest { txs: full_txs, slot, signature: None, signer: None };
+    let mut request = InclusionRequest {
+        txs: full_txs,
+        tx_hashes: Vec::new(),
+        slot,
+        top: false,
+        expires_at: None,
+        signature: None,
+        signer: None,
+    };
 
     request.recover_signers()?;
 
@@ -188,8 +449,9 @@ pub(crate) async fn create_signed_inclusion_request(
     Ok(request)
 }
 
-fn random_constraints(count: usize) -> Vec<FullTransaction> {
-    // Random inclusion request
+/// Returns an inclusion request for slot 10 with a few valid mainnet transactions,
+/// without any signature attached.
+pub(crate) fn default_inclusion_request() -> InclusionRequest {
     let json_req = r#"{
         "slot": 10,
         "txs": [
@@ -199,9 +461,11 @@ fn random_constraints(count: usize) -> Vec<FullTransaction> {
         ]
     }"#;
 
-    let req: InclusionRequest = serde_json::from_str(json_req).unwrap();
+    serde_json::from_str(json_req).unwrap()
+}
 
-    req.txs.iter().take(count).cloned().collect()
+fn random_constraints(count: usize) -> Vec<FullTransaction> {
+    default_inclusion_request().txs.into_iter().take(count).collect()
 }
 
 #[tokio::test]